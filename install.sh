@@ -46,7 +46,7 @@ fi
 ASSETS_SOURCE_DIR="${REPO_ROOT}/assets/icons/material"
 ASSETS_DEST_DIR="${CONFIG_DIR}/assets"
 mkdir -p "${ASSETS_DEST_DIR}"
-for icon in monitor.svg headphones.svg; do
+for icon in monitor.svg headphones.svg mic.svg mic_off.svg; do
   if [ ! -f "${ASSETS_DEST_DIR}/${icon}" ]; then
     install -Dm644 "${ASSETS_SOURCE_DIR}/${icon}" "${ASSETS_DEST_DIR}/${icon}"
   fi
@@ -1,49 +1,148 @@
-use std::time::Duration;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use crossbeam_channel::Receiver;
-use tracing::{info, warn};
+use crossbeam_channel::{Receiver, Sender};
+use tracing::{debug, info, warn};
 
-use crate::config;
+use crate::config::{self, ConfigWatcher, LauncherButtonConfig, StreamDeckSettings};
+#[cfg(feature = "watch")]
+use crate::config_watch::ConfigFileWatcher;
 use crate::controls::{
-    AudioToggleController, AudioToggleSettings, BrightnessController, EncoderController,
-    LauncherController, NowPlayingController, Tickable, TimerController, VolumeController,
+    AppVolumeController, AudioToggleController, AudioToggleSettings, BluetoothController,
+    BrightnessBackendKind, BrightnessController, BrightnessRamp, CaptureController, ClockConfig,
+    ClockController, CommandButtonConfig, CommandController, CommandLine, DesktopTimerAlert,
+    DndController, DoublePressDetector, EncoderController, HoldDetector, LauncherController,
+    MicMuteController, MonitorTarget, NightSchedule, NowPlayingController, PageSwitcher,
+    PomodoroConfig, PressGesture, SnippetController, StripGesture, SystemStatsController, Tickable,
+    TimerController, TimerMode, VolumeController, VpnController, WalkthroughController,
+    WeatherController, classify_swipe, coalesce_turns,
 };
 use crate::hardware::{
-    EncoderId, HardwareConfig, HardwareEvent, HardwareHandle, start as start_hardware,
+    DeviceEvent, DeviceId, DisplayPipeline, EncoderDisplay, EncoderId, HardwareConfig,
+    HardwareEvent, HardwareHandle, ProgressStyle, start as start_hardware,
 };
-use crate::system::audio::PulseAudioBackend;
+use crate::ipc::{self, IpcCommand, IpcServer};
+use crate::metrics::Metrics;
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsServer;
+use crate::state_file::{self, AppState, StateFile, TimerState};
+use crate::system::audio::{AudioBackend, PulseAudioBackend};
 use crate::system::audio_switch::PulseAudioSwitch;
-use crate::system::brightness::DdcutilBackend;
+use crate::system::bluetooth::BluetoothctlBackend;
+use crate::system::brightness::{
+    BrightnessBackend, DdcutilBackend, DisplayBrightnessBackend, SYSFS_BACKLIGHT_ROOT,
+    SysfsBacklightBackend,
+};
+use crate::system::dnd::DesktopDndBackend;
 use crate::system::now_playing::PlayerctlBackend;
+use crate::system::pulse_events::{PulseEvent, PulseEventWatcher};
+use crate::system::session::{LoginctlSession, SessionChange, SessionWatch};
+use crate::system::snippet::DesktopSnippetBackend;
+use crate::system::system_stats::ProcStatsBackend;
+use crate::system::timer_state::FileTimerStore;
+use crate::system::worker;
+
+// Encoder controllers still without a first reading after this show
+// "init…" on their segment, and the splash comes down.
+const STARTUP_PATIENCE: Duration = Duration::from_secs(3);
+const STARTUP_SPLASH: &str = "streamdeck_ctrl starting…";
+const STARTUP_SPLASH_MAX: Duration = Duration::from_secs(30);
+const DEVICE_CONNECTED_BANNER: &str = "Stream Deck connected";
+const DEVICE_CONNECTED_BANNER_FOR: Duration = Duration::from_secs(2);
 
-pub struct App {
-    volume: VolumeController<PulseAudioBackend, HardwareHandle>,
-    brightness: BrightnessController<DdcutilBackend, HardwareHandle>,
+// Generic over the volume and brightness backends only so that tests can run
+// it against mocks; `App::new` always picks the system ones.
+pub struct App<A = PulseAudioBackend, B = DisplayBrightnessBackend>
+where
+    A: AudioBackend,
+    B: BrightnessBackend,
+{
+    volume: VolumeController<A, HardwareHandle>,
+    brightness: BrightnessController<B, HardwareHandle>,
     timer: TimerController<HardwareHandle>,
     audio_toggle: Option<AudioToggleController<PulseAudioSwitch, HardwareHandle>>,
+    mic_mute: Option<MicMuteController<PulseAudioBackend, HardwareHandle>>,
+    dnd: Option<DndController<DesktopDndBackend, HardwareHandle>>,
+    vpn: Option<VpnController<HardwareHandle>>,
+    capture: Option<CaptureController<HardwareHandle>>,
     now_playing: Option<NowPlayingController<PlayerctlBackend, HardwareHandle>>,
+    /// The `now_playing_player` from `AppConfig`, used when the file has none.
+    default_player: Option<String>,
+    app_volume: Option<AppVolumeController<PulseAudioBackend, HardwareHandle>>,
+    system_stats: Option<SystemStatsController<ProcStatsBackend, HardwareHandle>>,
+    weather: Option<WeatherController<HardwareHandle>>,
+    clock: Option<ClockController<HardwareHandle>>,
     launchers: Option<LauncherController>,
+    commands: Option<CommandController<HardwareHandle>>,
+    snippets: Option<SnippetController<DesktopSnippetBackend>>,
+    bluetooth: Option<BluetoothController<BluetoothctlBackend, HardwareHandle>>,
+    page_switch: Option<PageSwitcher<HardwareHandle>>,
+    other_devices: Vec<DeviceButtons>,
+    settings: Option<StreamDeckSettings>,
+    brightness_cycle_button: Option<u8>,
+    config_watcher: ConfigWatcher,
+    walkthrough: Option<WalkthroughController<HardwareHandle>>,
+    encoder_pressed_at: [Option<Instant>; 4],
+    button_pressed_at: HashMap<u8, Instant>,
+    long_press: HoldDetector,
+    swipe_min_px: u16,
+    volume_presses: Option<DoublePressDetector>,
     hardware: HardwareHandle,
     shutdown: Option<Receiver<()>>,
-    events: Receiver<HardwareEvent>,
+    reload_requests: Receiver<()>,
+    reload_sender: Sender<()>,
+    #[cfg(feature = "watch")]
+    config_file_watcher: Option<ConfigFileWatcher>,
+    events: Receiver<DeviceEvent>,
+    pulse_events: PulseEventWatcher,
+    session: SessionWatch<LoginctlSession>,
+    locked_brightness: Option<u8>,
+    state_file: Option<StateFile>,
+    metrics: Arc<Metrics>,
+    #[cfg(feature = "metrics")]
+    _metrics_server: Option<MetricsServer>,
+    _ipc_server: Option<IpcServer>,
+    ipc_commands: Receiver<IpcCommand>,
 }
 
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub volume_step_percent: i32,
+    pub volume_default: Option<u8>,
+    pub volume_hold_command: Option<CommandLine>,
     pub brightness_step_percent: u8,
     pub brightness_min: u8,
     pub brightness_max: u8,
     pub brightness_night: u8,
+    pub brightness_reconcile_ticks: u32,
+    pub brightness_default: Option<u8>,
     pub timer_step_secs: u64,
     pub timer_min_secs: u64,
     pub timer_max_secs: u64,
     pub timer_default_secs: u64,
+    pub timer_mode: TimerMode,
+    pub pomodoro: PomodoroConfig,
+    pub long_press_ms: u64,
+    pub double_press_ms: u64,
+    /// Touch strip swipes shorter than this are taken as taps.
+    pub swipe_min_px: u16,
+    pub command_timeout_ms: u64,
     pub pulse_sink: Option<String>,
     pub monitor_display: Option<String>,
     pub monitor_bus: Option<u8>,
     pub now_playing_player: Option<String>,
+    pub ipc_socket: Option<PathBuf>,
+    pub locked_brightness: Option<u8>,
+    pub state_file: Option<PathBuf>,
+    /// Where to serve `/metrics` and `/healthz`, e.g. `127.0.0.1:9835`.
+    pub metrics_address: Option<String>,
+    /// Colours the volume and brightness bars instead of the theme.
+    pub progress_gradient: Option<ProgressStyle>,
     pub hardware: HardwareConfig,
 }
 
@@ -51,53 +150,77 @@ impl Default for AppConfig {
     fn default() -> Self {
         Self {
             volume_step_percent: 3,
+            volume_default: None,
+            volume_hold_command: None,
             brightness_step_percent: 5,
             brightness_min: 10,
             brightness_max: 100,
             brightness_night: 15,
+            brightness_reconcile_ticks: 10,
+            brightness_default: None,
             timer_step_secs: 30,
             timer_min_secs: 30,
             timer_max_secs: 60 * 60,
             timer_default_secs: 25 * 60,
+            timer_mode: TimerMode::Simple,
+            pomodoro: PomodoroConfig::default(),
+            long_press_ms: 600,
+            double_press_ms: 400,
+            swipe_min_px: 40,
+            command_timeout_ms: worker::DEFAULT_COMMAND_TIMEOUT.as_millis() as u64,
             pulse_sink: None,
             monitor_display: None,
             monitor_bus: None,
             now_playing_player: Some("spotify,%any".to_string()),
+            ipc_socket: ipc::default_socket_path(),
+            locked_brightness: None,
+            state_file: state_file::default_state_path(),
+            metrics_address: None,
+            progress_gradient: None,
             hardware: HardwareConfig::default(),
         }
     }
 }
 
 impl App {
-    pub fn new(config: AppConfig) -> Result<Self> {
-        info!("starting hardware backend");
-        let (hardware_handle, events) = start_hardware(config.hardware.clone())?;
-
-        let config_settings = match config::load_settings() {
-            Ok(settings) => settings,
+    pub fn new(mut config: AppConfig, metrics: Arc<Metrics>) -> Result<Self> {
+        let startup = Instant::now();
+        let config_watcher = ConfigWatcher::new();
+        let (config_settings, config_missing) = match config::load_settings() {
+            Ok(settings) => {
+                let missing = settings.is_none();
+                (settings, missing)
+            }
             Err(err) => {
                 warn!(
                     error = %err,
                     "failed to load streamdeck_ctrl configuration; optional features disabled"
                 );
-                None
+                (None, false)
             }
         };
+        if let Some(settings) = config_settings.as_ref() {
+            settings.app.apply(&mut config);
+            if let Some(theme) = settings.theme.as_ref() {
+                config.hardware.strip_theme = theme.segments;
+            }
+        }
+        worker::set_command_timeout(Duration::from_millis(config.command_timeout_ms));
 
-        let audio_toggle_settings = config_settings.as_ref().and_then(|settings| {
-            settings
-                .audio_toggle
-                .clone()
-                .map(|config| AudioToggleSettings {
-                    config,
-                    config_path: Some(settings.path.clone()),
-                })
-        });
+        info!("starting hardware backend");
+        config.hardware.metrics = Arc::clone(&metrics);
+        let (hardware_handle, events) = start_hardware(config.hardware.clone())?;
+        let has_encoders = hardware_handle
+            .capabilities()
+            .map(|capabilities| capabilities.has_encoders())
+            .unwrap_or(true);
+        if !has_encoders {
+            warn!(
+                "connected Stream Deck has no encoders or LCD strip; volume, brightness, timer and now-playing controls are unavailable"
+            );
+        }
 
-        let launcher_configs = config_settings
-            .as_ref()
-            .map(|settings| settings.launchers.clone())
-            .unwrap_or_default();
+        show_startup_splash(&hardware_handle, has_encoders);
 
         let pulse_audio = config
             .pulse_sink
@@ -107,77 +230,155 @@ impl App {
         if !pulse_audio.is_available() {
             warn!("PulseAudio CLI (`pactl`) not found; volume control disabled");
         }
+        let app_volume_audio = pulse_audio.clone();
+        let long_press = HoldDetector::new(Duration::from_millis(config.long_press_ms));
 
-        let ddc_backend = DdcutilBackend::new(config.monitor_display.clone(), config.monitor_bus);
-        if !ddc_backend.is_available() {
-            warn!("ddcutil not found or failed; brightness control disabled");
-        }
-
-        let volume = VolumeController::new(
-            pulse_audio,
-            hardware_handle.clone(),
-            EncoderId::One,
-            config.volume_step_percent,
-        )?;
-
-        let brightness = BrightnessController::new(
-            ddc_backend,
-            hardware_handle.clone(),
-            EncoderId::Two,
-            config.brightness_step_percent,
-            config.brightness_min,
-            config.brightness_max,
-            config.brightness_night,
-        )?;
-
-        let timer = TimerController::new(
-            hardware_handle.clone(),
-            EncoderId::Three,
-            config.timer_step_secs,
-            config.timer_min_secs,
-            config.timer_max_secs,
-            config.timer_default_secs,
-        )?;
-
-        let audio_toggle = if let Some(settings) = audio_toggle_settings {
-            match AudioToggleController::with_default_backend(settings, hardware_handle.clone()) {
-                Ok(controller) => Some(controller),
-                Err(err) => {
-                    warn!(error = %err, "failed to initialise audio output toggle");
-                    None
+        // Controllers take their first reading while being built, and ddcutil
+        // alone can take seconds, so the encoders start side by side while
+        // the timer and keys are set up here.
+        let (ready_tx, ready_rx) = crossbeam_channel::unbounded();
+        let spawned = Instant::now();
+        let (volume, brightness, timer, fourth, keys) = thread::scope(|scope| -> Result<_> {
+            let volume = spawn_init(scope, EncoderId::One, &ready_tx, || {
+                VolumeController::new(
+                    pulse_audio,
+                    hardware_handle.clone(),
+                    EncoderId::One,
+                    config.volume_step_percent,
+                )?
+                .with_default_volume(config.volume_default)
+                .with_hold_command(config.volume_hold_command.as_ref())
+                .with_progress_gradient(config.progress_gradient.clone())
+                .in_background()
+            });
+            let brightness = spawn_init(scope, EncoderId::Two, &ready_tx, || {
+                build_brightness(config_settings.as_ref(), &config, &hardware_handle)
+            });
+            let fourth = spawn_init(scope, EncoderId::Four, &ready_tx, || {
+                if has_encoders {
+                    build_fourth_encoder(
+                        config_settings.as_ref(),
+                        &config,
+                        app_volume_audio,
+                        &hardware_handle,
+                    )
+                } else {
+                    FourthEncoder::default()
                 }
+            });
+
+            let timer = build_timer(config_settings.as_ref(), &config, &hardware_handle)?;
+            let keys = (
+                build_audio_toggle(config_settings.as_ref(), 0, &hardware_handle, &metrics),
+                build_mic_mute(config_settings.as_ref(), &hardware_handle),
+                build_dnd(config_settings.as_ref(), &hardware_handle),
+                build_vpn(config_settings.as_ref(), &hardware_handle),
+                build_capture(config_settings.as_ref(), &hardware_handle),
+                build_launchers(config_settings.as_ref(), 0, &hardware_handle, long_press),
+                build_commands(config_settings.as_ref(), &hardware_handle),
+                build_snippets(config_settings.as_ref(), &hardware_handle),
+                build_bluetooth(config_settings.as_ref(), &hardware_handle),
+                build_page_switch(config_settings.as_ref(), 0, &hardware_handle),
+                build_other_devices(config_settings.as_ref(), 0, &hardware_handle, long_press),
+            );
+
+            let waiting = if has_encoders {
+                vec![EncoderId::One, EncoderId::Two, EncoderId::Four]
+            } else {
+                Vec::new()
+            };
+            await_encoders(
+                &hardware_handle,
+                &ready_rx,
+                waiting,
+                spawned + STARTUP_PATIENCE,
+            );
+            let volume = join_init(volume)?;
+            let brightness = join_init(brightness)?;
+            Ok((volume, brightness, timer, join_init(fourth), keys))
+        })?;
+        let FourthEncoder {
+            app_volume,
+            system_stats,
+            weather,
+            clock,
+            now_playing,
+        } = fourth;
+        let (
+            audio_toggle,
+            mic_mute,
+            dnd,
+            vpn,
+            capture,
+            launchers,
+            commands,
+            snippets,
+            bluetooth,
+            page_switch,
+            other_devices,
+        ) = keys;
+        info!(
+            elapsed_ms = startup.elapsed().as_millis() as u64,
+            "controls initialised"
+        );
+
+        let (ipc_server, ipc_commands) = match config.ipc_socket.as_ref().map(IpcServer::start) {
+            Some(Ok((server, commands))) => (Some(server), commands),
+            Some(Err(err)) => {
+                warn!(error = %err, "failed to start IPC socket; external commands disabled");
+                (None, crossbeam_channel::never())
+            }
+            None => {
+                warn!("XDG_RUNTIME_DIR is not set; IPC socket disabled");
+                (None, crossbeam_channel::never())
             }
-        } else {
-            None
         };
 
-        let now_playing = {
-            let player = config_settings
-                .as_ref()
-                .and_then(|settings| settings.now_playing_player.clone())
-                .or_else(|| config.now_playing_player.clone())
-                .unwrap_or_else(|| "spotify,%any".to_string());
-            let backend = PlayerctlBackend::new(player);
-            match NowPlayingController::new(backend, hardware_handle.clone(), EncoderId::Four) {
-                Ok(controller) => Some(controller),
+        let (reload_sender, reload_requests) = crossbeam_channel::bounded(1);
+        #[cfg(feature = "watch")]
+        let config_file_watcher = config_settings.as_ref().and_then(|settings| {
+            match ConfigFileWatcher::start(&settings.path, reload_sender.clone()) {
+                Ok(watcher) => Some(watcher),
                 Err(err) => {
-                    warn!(error = %err, "failed to initialise now-playing display");
+                    warn!(error = %err, "failed to watch the configuration file; polling it instead");
                     None
                 }
             }
-        };
+        });
 
-        let launchers = if launcher_configs.is_empty() {
-            None
-        } else {
-            match LauncherController::new(&launcher_configs, &hardware_handle) {
-                Ok(Some(controller)) => Some(controller),
-                Ok(None) => None,
+        #[cfg(feature = "metrics")]
+        let metrics_server = config.metrics_address.as_deref().and_then(|address| {
+            match MetricsServer::start(address, Arc::clone(&metrics)) {
+                Ok(server) => Some(server),
                 Err(err) => {
-                    warn!(error = %err, "failed to initialise application launchers");
+                    warn!(error = %err, "failed to start metrics endpoint; metrics disabled");
                     None
                 }
             }
+        });
+        #[cfg(not(feature = "metrics"))]
+        if config.metrics_address.is_some() {
+            warn!("`metrics_address` is set but this build has no `metrics` feature; ignoring it");
+        }
+
+        let walkthrough = if config_missing && has_encoders {
+            // Headless runs behave as on a Stream Deck Plus.
+            let key_count = hardware_handle
+                .capabilities()
+                .map_or(8, |capabilities| capabilities.key_count);
+            // The per-user file rather than the first candidate, which is the
+            // checkout's own `config/` directory.
+            config::user_config_path().and_then(|path| {
+                match WalkthroughController::new(hardware_handle.clone(), path, key_count) {
+                    Ok(controller) => Some(controller),
+                    Err(err) => {
+                        warn!(error = %err, "failed to start first-run walkthrough");
+                        None
+                    }
+                }
+            })
+        } else {
+            None
         };
 
         Ok(Self {
@@ -185,47 +386,90 @@ impl App {
             brightness,
             timer,
             audio_toggle,
+            mic_mute,
+            dnd,
+            vpn,
+            capture,
             now_playing,
+            app_volume,
+            system_stats,
+            weather,
+            clock,
             launchers,
+            commands,
+            snippets,
+            bluetooth,
+            page_switch,
+            other_devices,
+            brightness_cycle_button: brightness_cycle_button(config_settings.as_ref()),
+            settings: config_settings,
+            config_watcher,
+            walkthrough,
+            encoder_pressed_at: [None; 4],
+            button_pressed_at: HashMap::new(),
+            long_press,
+            swipe_min_px: config.swipe_min_px,
+            default_player: config.now_playing_player.clone(),
+            volume_presses: (config.double_press_ms > 0)
+                .then(|| DoublePressDetector::new(Duration::from_millis(config.double_press_ms))),
             hardware: hardware_handle,
             shutdown: None,
+            reload_requests,
+            reload_sender,
+            #[cfg(feature = "watch")]
+            config_file_watcher,
             events,
+            pulse_events: PulseEventWatcher::spawn(),
+            session: SessionWatch::new(LoginctlSession::new()),
+            locked_brightness: config.locked_brightness,
+            state_file: config.state_file.map(StateFile::new),
+            metrics,
+            #[cfg(feature = "metrics")]
+            _metrics_server: metrics_server,
+            _ipc_server: ipc_server,
+            ipc_commands,
         })
     }
+}
 
+impl<A, B> App<A, B>
+where
+    A: AudioBackend,
+    B: BrightnessBackend + Clone + Send + 'static,
+{
     pub fn run(&mut self) -> Result<()> {
         let ticker = crossbeam_channel::tick(Duration::from_secs(1));
         let shutdown_rx = self.shutdown.clone();
+        let reload_rx = self.reload_requests.clone();
+        let pulse_events = self.pulse_events.events().clone();
         let result = (|| -> Result<()> {
             loop {
+                let press_timeout = self
+                    .next_press_deadline()
+                    .map(crossbeam_channel::at)
+                    .unwrap_or_else(crossbeam_channel::never);
                 if let Some(ref shutdown) = shutdown_rx {
                     crossbeam_channel::select! {
                         recv(self.events) -> event => match event {
-                            Ok(event) => self.handle_event(event)?,
+                            Ok(event) => self.handle_queued_events(event)?,
                             Err(_) => {
                                 warn!("hardware event channel closed");
                                 break Ok(());
                             }
                         },
-                        recv(ticker) -> _ => {
-                            if let Err(err) = self.timer.on_tick() {
-                                warn!(error = %err, "timer tick failed");
-                            }
-                            if let Err(err) = self.brightness.on_tick() {
-                                warn!(error = %err, "brightness tick failed");
+                        recv(self.ipc_commands) -> command => {
+                            if let Ok(command) = command {
+                                self.handle_ipc_command(command)?;
                             }
-                            if let Some(toggle) = self.audio_toggle.as_mut() {
-                                if let Err(err) = toggle.on_tick() {
-                                    warn!(error = %err, "audio sink update failed");
-                                }
-                            }
-
-                            if let Some(now_playing) = self.now_playing.as_mut() {
-                                if let Err(err) = now_playing.on_tick() {
-                                    warn!(error = %err, "now-playing update failed");
-                                }
+                        }
+                        recv(pulse_events) -> event => {
+                            if let Ok(event) = event {
+                                self.on_pulse_events(event);
                             }
-                        },
+                        }
+                        recv(ticker) -> _ => self.on_tick(),
+                        recv(reload_rx) -> _ => self.reload(),
+                        recv(press_timeout) -> _ => self.flush_pending_presses(),
                         recv(shutdown) -> _ => {
                             break Ok(());
                         }
@@ -233,51 +477,595 @@ impl App {
                 } else {
                     crossbeam_channel::select! {
                         recv(self.events) -> event => match event {
-                            Ok(event) => self.handle_event(event)?,
+                            Ok(event) => self.handle_queued_events(event)?,
                             Err(_) => {
                                 warn!("hardware event channel closed");
                                 break Ok(());
                             }
                         },
-                        recv(ticker) -> _ => {
-                            if let Err(err) = self.timer.on_tick() {
-                                warn!(error = %err, "timer tick failed");
-                            }
-                            if let Err(err) = self.brightness.on_tick() {
-                                warn!(error = %err, "brightness tick failed");
+                        recv(self.ipc_commands) -> command => {
+                            if let Ok(command) = command {
+                                self.handle_ipc_command(command)?;
                             }
-                            if let Some(toggle) = self.audio_toggle.as_mut() {
-                                if let Err(err) = toggle.on_tick() {
-                                    warn!(error = %err, "audio sink update failed");
-                                }
-                            }
-
-                            if let Some(now_playing) = self.now_playing.as_mut() {
-                                if let Err(err) = now_playing.on_tick() {
-                                    warn!(error = %err, "now-playing update failed");
-                                }
+                        }
+                        recv(pulse_events) -> event => {
+                            if let Ok(event) = event {
+                                self.on_pulse_events(event);
                             }
                         }
+                        recv(ticker) -> _ => self.on_tick(),
+                        recv(reload_rx) -> _ => self.reload(),
+                        recv(press_timeout) -> _ => self.flush_pending_presses()
                     }
                 }
+                self.write_state();
             }
         })();
 
-        if let Err(err) = self.hardware.clear_all_displays() {
-            warn!(error = %err, "failed to clear stream deck displays");
+        for device in self.hardware.devices() {
+            if let Err(err) = device.clear_all_displays() {
+                warn!(error = %err, "failed to clear stream deck displays");
+            }
         }
 
         result
     }
 
+    fn on_tick(&mut self) {
+        if let Some(walkthrough) = self.walkthrough.as_mut() {
+            if let Err(err) = walkthrough.on_tick() {
+                warn!(error = %err, "walkthrough tick failed");
+            }
+            return;
+        }
+
+        self.reload_config_if_changed();
+        match self.session.poll(Instant::now()) {
+            Some(SessionChange::Locked) => self.on_session_locked(),
+            Some(SessionChange::Unlocked) => self.on_session_unlocked(),
+            None => {}
+        }
+        // Nobody sees the deck while the session is locked, so the status
+        // polls that shell out every tick are skipped until it unlocks.
+        let locked = self.session.is_locked();
+
+        if let Err(err) = self.volume.on_tick() {
+            warn!(error = %err, "volume tick failed");
+        }
+        if let Err(err) = self.timer.on_tick() {
+            warn!(error = %err, "timer tick failed");
+        }
+        if let Err(err) = self.brightness.on_tick() {
+            warn!(error = %err, "brightness tick failed");
+        }
+        // With a live subscription, sinks and sources refresh on change
+        // instead of every tick.
+        if !self.pulse_events.is_live() && !locked {
+            self.refresh_audio_devices(true, true);
+        }
+        if let Some(Err(err)) = self.dnd.as_mut().map(|dnd| dnd.on_tick()) {
+            warn!(error = %err, "do-not-disturb update failed");
+        }
+        if let Some(Err(err)) = self.vpn.as_mut().map(|vpn| vpn.on_tick()) {
+            warn!(error = %err, "vpn update failed");
+        }
+        if let Some(Err(err)) = self.bluetooth.as_mut().map(|bluetooth| bluetooth.on_tick()) {
+            warn!(error = %err, "bluetooth update failed");
+        }
+        if let Some(Err(err)) = self.capture.as_mut().map(|capture| capture.on_tick()) {
+            warn!(error = %err, "screen capture update failed");
+        }
+        if let Some(Err(err)) = self.commands.as_mut().map(|commands| commands.on_tick()) {
+            warn!(error = %err, "command button update failed");
+        }
+        for device in &mut self.other_devices {
+            if let Some(Err(err)) = device.commands.as_mut().map(|commands| commands.on_tick()) {
+                warn!(error = %err, "command button update failed");
+            }
+        }
+
+        if let Some(now_playing) = self.now_playing.as_mut().filter(|_| !locked) {
+            if let Err(err) = now_playing.on_tick() {
+                warn!(error = %err, "now-playing update failed");
+            }
+        }
+        if let Some(Err(err)) = self
+            .app_volume
+            .as_mut()
+            .filter(|_| !locked)
+            .map(|app_volume| app_volume.on_tick())
+        {
+            warn!(error = %err, "application volume update failed");
+        }
+        if let Some(Err(err)) = self
+            .system_stats
+            .as_mut()
+            .filter(|_| !locked)
+            .map(|stats| stats.on_tick())
+        {
+            warn!(error = %err, "system stats update failed");
+        }
+        if let Some(Err(err)) = self.weather.as_mut().map(|weather| weather.on_tick()) {
+            warn!(error = %err, "weather update failed");
+        }
+        if let Some(Err(err)) = self.clock.as_mut().map(|clock| clock.on_tick()) {
+            warn!(error = %err, "clock update failed");
+        }
+        self.update_metrics();
+    }
+
+    fn update_metrics(&self) {
+        let metrics = &self.metrics;
+        metrics
+            .command_queue_depth
+            .store(self.hardware.queue_depth() as u64, Ordering::Relaxed);
+        metrics
+            .pactl_available
+            .store(self.volume.is_available(), Ordering::Relaxed);
+        metrics
+            .ddcutil_available
+            .store(self.brightness.is_available(), Ordering::Relaxed);
+        metrics.playerctl_available.store(
+            self.now_playing
+                .as_ref()
+                .is_some_and(|now_playing| now_playing.is_available()),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn on_session_locked(&mut self) {
+        info!("session locked; pausing status polling");
+        if let Some(level) = self.locked_brightness {
+            self.set_device_brightness(level);
+        }
+    }
+
+    // Everything skipped while locked is refreshed at once, so no stale
+    // reading is left on the deck.
+    fn on_session_unlocked(&mut self) {
+        info!("session unlocked; refreshing controls");
+        if self.locked_brightness.is_some() && self.brightness.syncs_deck() {
+            self.brightness.resync_deck();
+        } else if self.locked_brightness.is_some() {
+            let level = self
+                .settings
+                .as_ref()
+                .and_then(|settings| settings.app.hardware.device_brightness)
+                .filter(|level| *level <= 100)
+                .unwrap_or(HardwareConfig::default().device_brightness);
+            self.set_device_brightness(level);
+        }
+        if let Err(err) = self.volume.sync() {
+            warn!(error = %err, "failed to refresh volume after unlock");
+        }
+        self.refresh_audio_devices(true, true);
+        if let Some(Err(err)) = self
+            .now_playing
+            .as_mut()
+            .map(|now_playing| now_playing.sync())
+        {
+            warn!(error = %err, "failed to refresh now-playing after unlock");
+        }
+        if let Some(Err(err)) = self.system_stats.as_mut().map(|stats| stats.on_tick()) {
+            warn!(error = %err, "failed to refresh system stats after unlock");
+        }
+    }
+
+    // Throttled by `StateFile`; a change it holds back is written on a
+    // later pass, at the latest on the next tick.
+    fn write_state(&mut self) {
+        let Some(file) = self.state_file.as_mut() else {
+            return;
+        };
+        let state = AppState::capture(
+            &self.hardware.shown(),
+            self.volume.sink_label(),
+            TimerState {
+                state: self.timer.state(),
+                remaining_secs: self.timer.remaining_secs(),
+            },
+        );
+        if let Err(err) = file.update(state, Instant::now()) {
+            warn!(
+                error = %err,
+                path = %file.path().display(),
+                "failed to write deck state file"
+            );
+        }
+    }
+
+    fn set_device_brightness(&self, level: u8) {
+        for device in self.hardware.devices() {
+            if let Err(err) = device.set_device_brightness(level) {
+                warn!(error = %err, "failed to change device brightness");
+            }
+        }
+    }
+
+    fn on_pulse_events(&mut self, first: PulseEvent) {
+        let queued: Vec<PulseEvent> = std::iter::once(first)
+            .chain(self.pulse_events.events().try_iter())
+            .collect();
+        if self.walkthrough.is_some() {
+            return;
+        }
+        let sinks = queued
+            .iter()
+            .any(|event| matches!(event, PulseEvent::Sink | PulseEvent::Server));
+        let sources = queued
+            .iter()
+            .any(|event| matches!(event, PulseEvent::Source | PulseEvent::Server));
+        if sinks {
+            if let Err(err) = self.volume.sync() {
+                warn!(error = %err, "failed to refresh volume after audio change");
+            }
+        }
+        self.refresh_audio_devices(sinks, sources);
+    }
+
+    fn refresh_audio_devices(&mut self, sinks: bool, sources: bool) {
+        if let Some(Err(err)) = self
+            .audio_toggle
+            .as_mut()
+            .filter(|_| sinks)
+            .map(|toggle| toggle.on_tick())
+        {
+            warn!(error = %err, "audio sink update failed");
+        }
+        if let Some(Err(err)) = self
+            .mic_mute
+            .as_mut()
+            .filter(|_| sources)
+            .map(|mic_mute| mic_mute.on_tick())
+        {
+            warn!(error = %err, "microphone mute update failed");
+        }
+    }
+
+    fn reload_config_if_changed(&mut self) {
+        #[cfg(feature = "watch")]
+        if self.config_file_watcher.is_some() {
+            return;
+        }
+        if self.config_watcher.poll_changed() {
+            self.reload();
+        }
+    }
+
+    /// Reads the configuration file again and rebuilds the keys from it; the
+    /// current layout stays when the file cannot be loaded.
+    pub fn reload(&mut self) {
+        // Keeps the next tick from loading the same file a second time.
+        self.config_watcher.poll_changed();
+        let settings = match config::load_settings() {
+            Ok(Some(settings)) => settings,
+            Ok(None) => {
+                warn!("configuration file no longer found; keeping current button layout");
+                return;
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to reload configuration; keeping previous settings");
+                return;
+            }
+        };
+        info!(path = %settings.path.display(), "configuration changed; reloading buttons");
+
+        let previous_buttons = self.configured_buttons();
+        let previous_other: Vec<_> = self
+            .other_devices
+            .drain(..)
+            .map(|device| (device.buttons(), device.hardware))
+            .collect();
+        let page = self.current_page().min(settings.page_count() - 1);
+        self.audio_toggle = None;
+        self.mic_mute = None;
+        self.dnd = None;
+        self.vpn = None;
+        let recorder = self
+            .capture
+            .take()
+            .and_then(|mut capture| capture.take_recorder());
+        self.launchers = None;
+        self.commands = None;
+        self.snippets = None;
+        self.bluetooth = None;
+        self.page_switch = None;
+        self.audio_toggle =
+            build_audio_toggle(Some(&settings), page, &self.hardware, &self.metrics);
+        self.mic_mute = build_mic_mute(Some(&settings), &self.hardware);
+        self.dnd = build_dnd(Some(&settings), &self.hardware);
+        self.vpn = build_vpn(Some(&settings), &self.hardware);
+        self.capture = build_capture(Some(&settings), &self.hardware);
+        // A recording in progress carries over; without a record key it is
+        // stopped.
+        if let Some(recorder) = recorder {
+            match self.capture.as_mut() {
+                Some(capture) => {
+                    if let Err(err) = capture.adopt_recorder(recorder) {
+                        warn!(error = %err, "failed to show recording state after reload");
+                    }
+                }
+                None => {
+                    if let Err(err) = recorder.stop() {
+                        warn!(error = %err, "failed to stop screen recording");
+                    }
+                }
+            }
+        }
+        self.launchers = build_launchers(Some(&settings), page, &self.hardware, self.long_press);
+        self.commands = build_commands(Some(&settings), &self.hardware);
+        self.snippets = build_snippets(Some(&settings), &self.hardware);
+        self.bluetooth = build_bluetooth(Some(&settings), &self.hardware);
+        self.page_switch = build_page_switch(Some(&settings), page, &self.hardware);
+        self.other_devices =
+            build_other_devices(Some(&settings), page, &self.hardware, self.long_press);
+        self.brightness_cycle_button = brightness_cycle_button(Some(&settings));
+        self.brightness
+            .set_night_schedule(brightness_night_schedule(Some(&settings)));
+        let sync_deck = brightness_syncs_deck(Some(&settings));
+        if sync_deck != self.brightness.syncs_deck() {
+            self.brightness.set_deck_sync(sync_deck);
+        }
+        self.apply_device_brightness(&settings);
+        self.reload_now_playing(&settings);
+        self.settings = Some(settings);
+
+        let current_buttons = self.configured_buttons();
+        clear_removed_buttons(&self.hardware, &previous_buttons, &current_buttons);
+        for ((previous, hardware), current) in previous_other.iter().zip(&self.other_devices) {
+            clear_removed_buttons(hardware, previous, &current.buttons());
+        }
+    }
+
+    // The rest of the `app` section needs a restart; the deck's own backlight
+    // can follow the file.
+    fn apply_device_brightness(&self, settings: &StreamDeckSettings) {
+        let level = settings
+            .app
+            .hardware
+            .device_brightness
+            .filter(|level| *level <= 100);
+        let previous = self
+            .settings
+            .as_ref()
+            .and_then(|previous| previous.app.hardware.device_brightness);
+        let Some(level) = level.filter(|level| Some(*level) != previous) else {
+            return;
+        };
+        info!(level, "changing device brightness");
+        self.set_device_brightness(level);
+    }
+
+    // A changed `now_playing_player` restarts now-playing; otherwise it keeps
+    // running, and a player that cannot be watched leaves the old one.
+    fn reload_now_playing(&mut self, settings: &StreamDeckSettings) {
+        if self.now_playing.is_none() {
+            return;
+        }
+        let fallback = self.default_player.as_deref();
+        let player = now_playing_player(Some(settings), fallback);
+        if player == now_playing_player(self.settings.as_ref(), fallback) {
+            return;
+        }
+        info!(player, "now-playing player changed");
+        match build_now_playing(player, &self.hardware) {
+            Ok(controller) => self.now_playing = Some(controller),
+            Err(err) => {
+                warn!(error = %err, "failed to restart now-playing; keeping the previous player");
+            }
+        }
+    }
+
+    fn current_page(&self) -> usize {
+        self.page_switch
+            .as_ref()
+            .map_or(0, |switcher| switcher.current())
+    }
+
+    // Every key of the old page is cleared before the new page draws, so none
+    // keeps a stale icon, including keys the new page leaves without one.
+    fn switch_page(&mut self) -> Result<()> {
+        let Some(switcher) = self.page_switch.as_mut() else {
+            return Ok(());
+        };
+        let page = switcher.advance()?;
+        info!(page = page + 1, "switching button page");
+
+        let previous_buttons = self.page_buttons();
+        self.audio_toggle = None;
+        self.launchers = None;
+        for index in previous_buttons {
+            if let Err(err) = self.hardware.update_button_icon(index, None) {
+                warn!(error = %err, index, "failed to clear button from previous page");
+            }
+        }
+        self.audio_toggle =
+            build_audio_toggle(self.settings.as_ref(), page, &self.hardware, &self.metrics);
+        self.launchers = build_launchers(
+            self.settings.as_ref(),
+            page,
+            &self.hardware,
+            self.long_press,
+        );
+        for device in &mut self.other_devices {
+            let previous: Vec<u8> = device
+                .launchers
+                .take()
+                .map(|launchers| launchers.button_indices().collect())
+                .unwrap_or_default();
+            for index in previous {
+                if let Err(err) = device.hardware.update_button_icon(index, None) {
+                    warn!(error = %err, index, "failed to clear button from previous page");
+                }
+            }
+            device.launchers = build_launchers(
+                self.settings.as_ref(),
+                page,
+                &device.hardware,
+                self.long_press,
+            );
+        }
+        Ok(())
+    }
+
+    fn page_buttons(&self) -> BTreeSet<u8> {
+        let mut buttons = BTreeSet::new();
+        if let Some(toggle) = self.audio_toggle.as_ref() {
+            buttons.extend(toggle.button_indices());
+        }
+        if let Some(launchers) = self.launchers.as_ref() {
+            buttons.extend(launchers.button_indices());
+        }
+        buttons
+    }
+
+    fn configured_buttons(&self) -> BTreeSet<u8> {
+        let mut buttons = self.page_buttons();
+        if let Some(switcher) = self.page_switch.as_ref() {
+            buttons.insert(switcher.button_index());
+        }
+        if let Some(mic_mute) = self.mic_mute.as_ref() {
+            buttons.extend(mic_mute.button_indices());
+        }
+        if let Some(dnd) = self.dnd.as_ref() {
+            buttons.extend(dnd.button_indices());
+        }
+        if let Some(vpn) = self.vpn.as_ref() {
+            buttons.extend(vpn.button_indices());
+        }
+        if let Some(capture) = self.capture.as_ref() {
+            buttons.extend(capture.button_indices());
+        }
+        if let Some(commands) = self.commands.as_ref() {
+            buttons.extend(commands.button_indices());
+        }
+        if let Some(snippets) = self.snippets.as_ref() {
+            buttons.extend(snippets.button_indices());
+        }
+        if let Some(bluetooth) = self.bluetooth.as_ref() {
+            buttons.extend(bluetooth.button_indices());
+        }
+        buttons
+    }
+
+    // Everything already queued behind `first` is handled in the same pass,
+    // with turn bursts summed, so slow controller calls cannot build a backlog
+    // of single detents.
+    fn handle_queued_events(&mut self, first: DeviceEvent) -> Result<()> {
+        let queued: Vec<DeviceEvent> = std::iter::once(first)
+            .chain(self.events.try_iter())
+            .collect();
+        let mut queued = queued.into_iter().peekable();
+        while let Some(DeviceEvent { device, event }) = queued.next() {
+            let mut run = vec![event];
+            while let Some(next) = queued.next_if(|next| next.device == device) {
+                run.push(next.event);
+            }
+            for event in coalesce_turns(run) {
+                self.handle_device_event(device, event)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Encoders and the touch strip are only read from the default device;
+    // keys on the other decks reach the launchers and commands placed there.
+    fn handle_device_event(&mut self, device: DeviceId, event: HardwareEvent) -> Result<()> {
+        if device == DeviceId::default() {
+            return self.handle_event(event);
+        }
+        let Some(buttons) = self
+            .other_devices
+            .iter_mut()
+            .find(|buttons| buttons.hardware.device() == device)
+            .filter(|_| self.walkthrough.is_none())
+        else {
+            return Ok(());
+        };
+        match event {
+            HardwareEvent::ButtonPressed(index) => buttons.on_button_pressed(index),
+            HardwareEvent::ButtonReleased(index) => buttons.on_button_released(index),
+            other => {
+                debug!(device = device.0, event = ?other, "ignoring input from secondary device");
+                Ok(())
+            }
+        }
+    }
+
     fn handle_event(&mut self, event: HardwareEvent) -> Result<()> {
+        if let Some(walkthrough) = self.walkthrough.as_mut() {
+            walkthrough.handle_event(&event)?;
+            if walkthrough.is_finished() {
+                self.finish_walkthrough();
+            }
+            return Ok(());
+        }
+
         match event {
             HardwareEvent::EncoderTurned { encoder, delta } => self.handle_turn(encoder, delta),
             HardwareEvent::EncoderPressed { encoder } => self.handle_press(encoder),
             HardwareEvent::EncoderReleased { encoder } => self.handle_release(encoder),
             HardwareEvent::ButtonPressed(index) => self.handle_button_press(index),
-            HardwareEvent::ButtonReleased(_) => Ok(()),
-            HardwareEvent::Touch => Ok(()),
+            HardwareEvent::ButtonReleased(index) => self.handle_button_release(index),
+            HardwareEvent::TouchTap { x, y, segment } => {
+                debug!(x, y, ?segment, "touch strip tap");
+                self.handle_touch_tap(segment)
+            }
+            HardwareEvent::TouchSwipe { from_x, to_x } => {
+                debug!(from_x, to_x, "touch strip swipe");
+                match classify_swipe(from_x, to_x, self.swipe_min_px) {
+                    StripGesture::Tap(segment) => self.handle_touch_tap(segment),
+                    StripGesture::Swipe { segment, forward } => {
+                        match self.encoder_controller(segment) {
+                            Some(controller) => controller.on_touch_swipe(forward),
+                            None => Ok(()),
+                        }
+                    }
+                    StripGesture::Ignored => Ok(()),
+                }
+            }
+            HardwareEvent::DeviceDisconnected => {
+                info!("Stream Deck disconnected");
+                Ok(())
+            }
+            HardwareEvent::DeviceConnected => {
+                info!("Stream Deck connected");
+                self.hardware.show_banner(
+                    DEVICE_CONNECTED_BANNER,
+                    DEVICE_CONNECTED_BANNER_FOR,
+                    [255; 3],
+                )
+            }
+        }
+    }
+
+    fn handle_touch_tap(&mut self, segment: EncoderId) -> Result<()> {
+        match segment {
+            EncoderId::One => self.volume.on_touch_tap(),
+            EncoderId::Two => self.brightness.on_touch_tap(),
+            EncoderId::Three => self.timer.on_touch_tap(),
+            EncoderId::Four => match self.fourth_encoder() {
+                Some(controller) => controller.on_touch_tap(),
+                None => Ok(()),
+            },
+        }
+    }
+
+    // Injected events go through the same path as hardware input, so scripts
+    // see exactly what a physical turn or press would do.
+    fn handle_ipc_command(&mut self, command: IpcCommand) -> Result<()> {
+        match command {
+            IpcCommand::Event(event) => {
+                debug!(?event, "IPC event");
+                self.handle_event(event)
+            }
+            IpcCommand::SetEncoderDisplay { encoder, display } => {
+                self.hardware.update_encoder(encoder, display)
+            }
+            IpcCommand::QueryStats(reply) => {
+                reply.send(self.hardware.stats()?).ok();
+                Ok(())
+            }
         }
     }
 
@@ -286,50 +1074,229 @@ impl App {
             EncoderId::One => self.volume.on_turn(delta),
             EncoderId::Two => self.brightness.on_turn(delta),
             EncoderId::Three => self.timer.on_turn(delta),
-            EncoderId::Four => match self.now_playing.as_mut() {
-                Some(now_playing) => now_playing.on_turn(delta),
-                None => Ok(()),
-            },
+            EncoderId::Four => {
+                if let Some(app_volume) = self.app_volume.as_mut() {
+                    return app_volume.on_turn(delta);
+                }
+                if let Some(stats) = self.system_stats.as_mut() {
+                    return stats.on_turn(delta);
+                }
+                if let Some(weather) = self.weather.as_mut() {
+                    return weather.on_turn(delta);
+                }
+                if let Some(clock) = self.clock.as_mut() {
+                    return clock.on_turn(delta);
+                }
+                let held = self.encoder_pressed_at[EncoderId::Four.index()].is_some();
+                match self.now_playing.as_mut() {
+                    Some(now_playing) if held => now_playing.scrub(delta),
+                    Some(now_playing) => now_playing.on_turn(delta),
+                    None => Ok(()),
+                }
+            }
         }
     }
 
     fn handle_press(&mut self, encoder: EncoderId) -> Result<()> {
-        match encoder {
-            EncoderId::One => self.volume.on_press(),
-            EncoderId::Two => self.brightness.on_press(),
-            EncoderId::Three => self.timer.on_press(),
-            EncoderId::Four => Ok(()),
+        let now = Instant::now();
+        self.encoder_pressed_at[encoder.index()] = Some(now);
+        // Tap or long press is only known on release.
+        if self
+            .encoder_controller(encoder)
+            .is_some_and(|controller| controller.has_long_press())
+        {
+            return Ok(());
+        }
+        self.handle_tap(encoder, now)
+    }
+
+    fn handle_tap(&mut self, encoder: EncoderId, now: Instant) -> Result<()> {
+        let cycles_output = encoder == EncoderId::One && self.audio_toggle.is_some();
+        if let Some(presses) = self.volume_presses.as_mut().filter(|_| cycles_output) {
+            // Mute is held back until the double-press window closes so a
+            // double press never toggles it; see `flush_pending_presses`.
+            return match presses.on_press(now) {
+                PressGesture::Pending => Ok(()),
+                PressGesture::Double => self.handle_volume_double_press(),
+            };
+        }
+        match self.encoder_controller(encoder) {
+            Some(controller) => controller.on_press(),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_volume_double_press(&mut self) -> Result<()> {
+        debug!("volume encoder double press; cycling audio output");
+        if let Some(toggle) = self.audio_toggle.as_mut() {
+            toggle.cycle_outputs();
+        }
+        if let Err(err) = self.volume.sync() {
+            warn!(error = %err, "failed to refresh volume after audio sink switch");
+        }
+        Ok(())
+    }
+
+    fn next_press_deadline(&self) -> Option<Instant> {
+        let launchers = self.launchers.iter().chain(
+            self.other_devices
+                .iter()
+                .filter_map(|device| device.launchers.as_ref()),
+        );
+        self.volume_presses
+            .as_ref()
+            .and_then(DoublePressDetector::deadline)
+            .into_iter()
+            .chain(launchers.filter_map(LauncherController::deadline))
+            .min()
+    }
+
+    fn flush_pending_presses(&mut self) {
+        let expired = self
+            .volume_presses
+            .as_mut()
+            .is_some_and(|presses| presses.take_expired(Instant::now()));
+        if let Some(Err(err)) = expired.then(|| self.volume.on_press()) {
+            warn!(error = %err, "volume press failed");
+        }
+        let launchers = self.launchers.iter_mut().chain(
+            self.other_devices
+                .iter_mut()
+                .filter_map(|device| device.launchers.as_mut()),
+        );
+        for launchers in launchers {
+            if let Err(err) = launchers.on_tick() {
+                warn!(error = %err, "launcher press failed");
+            }
         }
     }
 
     fn handle_release(&mut self, encoder: EncoderId) -> Result<()> {
+        let pressed_at = self.encoder_pressed_at[encoder.index()].take();
+        let held = held_duration(pressed_at);
+        let deferred = pressed_at.is_some()
+            && self
+                .encoder_controller(encoder)
+                .is_some_and(|controller| controller.has_long_press());
+        if deferred && self.long_press.is_long(held) {
+            debug!(
+                ?encoder,
+                held_ms = held.as_millis() as u64,
+                "encoder long press"
+            );
+            if let Some(controller) = self.encoder_controller(encoder) {
+                controller.on_long_press()?;
+            }
+        } else if deferred {
+            self.handle_tap(encoder, Instant::now())?;
+        }
+        match self.encoder_controller(encoder) {
+            Some(controller) => controller.on_release(held),
+            None => Ok(()),
+        }
+    }
+
+    fn encoder_controller(&mut self, encoder: EncoderId) -> Option<&mut dyn EncoderController> {
         match encoder {
-            EncoderId::One => self.volume.on_release(),
-            EncoderId::Two => self.brightness.on_release(),
-            EncoderId::Three => self.timer.on_release(),
-            EncoderId::Four => Ok(()),
+            EncoderId::One => Some(&mut self.volume),
+            EncoderId::Two => Some(&mut self.brightness),
+            EncoderId::Three => Some(&mut self.timer),
+            EncoderId::Four => self.fourth_encoder(),
         }
     }
 
+    fn fourth_encoder(&mut self) -> Option<&mut dyn EncoderController> {
+        if let Some(app_volume) = self.app_volume.as_mut() {
+            return Some(app_volume);
+        }
+        if let Some(stats) = self.system_stats.as_mut() {
+            return Some(stats);
+        }
+        if let Some(weather) = self.weather.as_mut() {
+            return Some(weather);
+        }
+        if let Some(clock) = self.clock.as_mut() {
+            return Some(clock);
+        }
+        self.now_playing
+            .as_mut()
+            .map(|now_playing| now_playing as &mut dyn EncoderController)
+    }
+
     fn handle_button_press(&mut self, index: u8) -> Result<()> {
+        // A stale entry here means the previous release was lost; the new press
+        // replaces it.
+        self.button_pressed_at.insert(index, Instant::now());
+        if self.is_page_switch_button(index) {
+            return self.switch_page();
+        }
         let mut handled = false;
         if let Some(toggle) = self.audio_toggle.as_mut() {
-            if toggle.on_button_pressed(index)? {
-                if let Err(err) = self.volume.sync() {
-                    warn!(error = %err, "failed to refresh volume after audio sink switch");
-                }
-                handled = true;
-            }
+            handled = toggle.on_button_pressed(index)?;
+        }
+
+        if !handled {
+            handled = match self.mic_mute.as_mut() {
+                Some(mic_mute) => mic_mute.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
+        if !handled {
+            handled = match self.dnd.as_mut() {
+                Some(dnd) => dnd.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
+        if !handled {
+            handled = match self.vpn.as_mut() {
+                Some(vpn) => vpn.on_button_pressed(index)?,
+                None => false,
+            };
         }
 
         if !handled {
-            if let Some(launchers) = self.launchers.as_ref() {
+            handled = match self.capture.as_mut() {
+                Some(capture) => capture.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
+        if !handled && self.brightness_cycle_button == Some(index) {
+            self.brightness.cycle_monitor()?;
+            handled = true;
+        }
+
+        if !handled {
+            if let Some(launchers) = self.launchers.as_mut() {
                 if launchers.on_button_pressed(index)? {
                     handled = true;
                 }
             }
         }
 
+        if !handled {
+            handled = match self.commands.as_mut() {
+                Some(commands) => commands.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
+        if !handled {
+            handled = match self.snippets.as_mut() {
+                Some(snippets) => snippets.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
+        if !handled {
+            handled = match self.bluetooth.as_mut() {
+                Some(bluetooth) => bluetooth.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+
         if !handled {
             info!(index, "button pressed (unused)");
         }
@@ -337,19 +1304,918 @@ impl App {
         Ok(())
     }
 
+    fn handle_button_release(&mut self, index: u8) -> Result<()> {
+        let Some(pressed_at) = self.button_pressed_at.remove(&index) else {
+            debug!(button = index, "ignoring release without a recorded press");
+            return Ok(());
+        };
+        let held = pressed_at.elapsed();
+        debug!(
+            button = index,
+            held_ms = held.as_millis() as u64,
+            "button released"
+        );
+
+        if self.toggles_claim_release(index, held)? {
+            return Ok(());
+        }
+        if let Some(launchers) = self.launchers.as_mut() {
+            launchers.on_button_released(index, held)?;
+        }
+        Ok(())
+    }
+
+    fn toggles_claim_release(&mut self, index: u8, held: Duration) -> Result<bool> {
+        // The audio toggle switches on release, so the volume it restored is
+        // picked up here.
+        let toggle_claimed = match self.audio_toggle.as_mut() {
+            Some(toggle) => toggle.on_button_released(index, held)?,
+            None => false,
+        };
+        if toggle_claimed {
+            if let Err(err) = self.volume.sync() {
+                warn!(error = %err, "failed to refresh volume after audio sink switch");
+            }
+        }
+        let mic_claimed = match self.mic_mute.as_mut() {
+            Some(mic_mute) => mic_mute.on_button_released(index)?,
+            None => false,
+        };
+        let dnd_claimed = match self.dnd.as_mut() {
+            Some(dnd) => dnd.on_button_released(index)?,
+            None => false,
+        };
+        let vpn_claimed = match self.vpn.as_mut() {
+            Some(vpn) => vpn.on_button_released(index)?,
+            None => false,
+        };
+        let capture_claimed = match self.capture.as_mut() {
+            Some(capture) => capture.on_button_released(index)?,
+            None => false,
+        };
+        let snippet_claimed = match self.snippets.as_mut() {
+            Some(snippets) => snippets.on_button_released(index)?,
+            None => false,
+        };
+        let bluetooth_claimed = match self.bluetooth.as_mut() {
+            Some(bluetooth) => bluetooth.on_button_released(index)?,
+            None => false,
+        };
+        Ok(toggle_claimed
+            || mic_claimed
+            || dnd_claimed
+            || vpn_claimed
+            || capture_claimed
+            || snippet_claimed
+            || bluetooth_claimed
+            || self.is_page_switch_button(index))
+    }
+
+    fn is_page_switch_button(&self, index: u8) -> bool {
+        self.page_switch
+            .as_ref()
+            .is_some_and(|switcher| switcher.button_index() == index)
+    }
+
+    fn finish_walkthrough(&mut self) {
+        self.walkthrough = None;
+        if let Err(err) = self.volume.sync() {
+            warn!(error = %err, "failed to restore volume display after walkthrough");
+        }
+        if let Err(err) = self.brightness.sync() {
+            warn!(error = %err, "failed to restore brightness display after walkthrough");
+        }
+        if let Err(err) = self.timer.sync() {
+            warn!(error = %err, "failed to restore timer display after walkthrough");
+        }
+        if let Some(now_playing) = self.now_playing.as_mut() {
+            if let Err(err) = now_playing.sync() {
+                warn!(error = %err, "failed to restore now-playing display after walkthrough");
+            }
+        }
+    }
+
     pub fn set_shutdown_channel(&mut self, shutdown: Receiver<()>) {
         self.shutdown = Some(shutdown);
     }
 
+    /// Each message makes the run loop call [`App::reload`]; one reload
+    /// queued at a time is enough, so senders should use `try_send`.
+    pub fn reload_sender(&self) -> Sender<()> {
+        self.reload_sender.clone()
+    }
+
     pub fn hardware_handle(&self) -> HardwareHandle {
         self.hardware.clone()
     }
 }
 
-impl Drop for App {
+impl<A, B> Drop for App<A, B>
+where
+    A: AudioBackend,
+    B: BrightnessBackend,
+{
     fn drop(&mut self) {
-        if let Err(err) = self.hardware.clear_all_displays() {
-            warn!(error = %err, "failed to clear stream deck displays on drop");
+        for device in self.hardware.devices() {
+            if let Err(err) = device.clear_all_displays() {
+                warn!(error = %err, "failed to clear stream deck displays on drop");
+            }
+        }
+    }
+}
+
+/// Launchers and command keys placed on a deck other than the default one.
+struct DeviceButtons {
+    hardware: HardwareHandle,
+    launchers: Option<LauncherController>,
+    commands: Option<CommandController<HardwareHandle>>,
+    pressed_at: HashMap<u8, Instant>,
+}
+
+impl DeviceButtons {
+    fn buttons(&self) -> BTreeSet<u8> {
+        let mut buttons = BTreeSet::new();
+        if let Some(launchers) = self.launchers.as_ref() {
+            buttons.extend(launchers.button_indices());
+        }
+        if let Some(commands) = self.commands.as_ref() {
+            buttons.extend(commands.button_indices());
+        }
+        buttons
+    }
+
+    fn on_button_pressed(&mut self, index: u8) -> Result<()> {
+        self.pressed_at.insert(index, Instant::now());
+        let mut handled = match self.launchers.as_mut() {
+            Some(launchers) => launchers.on_button_pressed(index)?,
+            None => false,
+        };
+        if !handled {
+            handled = match self.commands.as_mut() {
+                Some(commands) => commands.on_button_pressed(index)?,
+                None => false,
+            };
+        }
+        if !handled {
+            info!(
+                index,
+                device = self.hardware.device().0,
+                "button pressed (unused)"
+            );
+        }
+        Ok(())
+    }
+
+    fn on_button_released(&mut self, index: u8) -> Result<()> {
+        let Some(pressed_at) = self.pressed_at.remove(&index) else {
+            return Ok(());
+        };
+        if let Some(launchers) = self.launchers.as_mut() {
+            launchers.on_button_released(index, pressed_at.elapsed())?;
+        }
+        Ok(())
+    }
+}
+
+fn clear_removed_buttons(
+    hardware: &HardwareHandle,
+    previous: &BTreeSet<u8>,
+    current: &BTreeSet<u8>,
+) {
+    for index in previous.difference(current) {
+        if let Err(err) = hardware.update_button_icon(*index, None) {
+            warn!(error = %err, index, "failed to clear icon for removed button");
+        }
+    }
+}
+
+// Entries naming an unknown device are left off every deck.
+fn on_device(hardware: &HardwareHandle, role: Option<&str>) -> bool {
+    match hardware.device_id(role) {
+        Some(device) => device == hardware.device(),
+        None => {
+            if hardware.device() == DeviceId::default() {
+                warn!(role, "button names an unknown device; it is not shown");
+            }
+            false
+        }
+    }
+}
+
+fn held_duration(pressed_at: Option<Instant>) -> Duration {
+    pressed_at
+        .map(|instant| instant.elapsed())
+        .unwrap_or(Duration::ZERO)
+}
+
+fn show_startup_splash(hardware: &HardwareHandle, has_encoders: bool) {
+    if !has_encoders {
+        return;
+    }
+    if let Err(err) = hardware.show_banner(STARTUP_SPLASH, STARTUP_SPLASH_MAX, [255; 3]) {
+        warn!(error = %err, "failed to show startup splash");
+    }
+}
+
+/// Builds a controller on its own thread, reporting `encoder` on `ready` once
+/// it has pushed its first display.
+fn spawn_init<'scope, T: Send + 'scope>(
+    scope: &'scope thread::Scope<'scope, '_>,
+    encoder: EncoderId,
+    ready: &Sender<EncoderId>,
+    build: impl FnOnce() -> T + Send + 'scope,
+) -> thread::ScopedJoinHandle<'scope, T> {
+    let ready = ready.clone();
+    scope.spawn(move || {
+        let built = build();
+        ready.send(encoder).ok();
+        built
+    })
+}
+
+fn join_init<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+// Returns once every encoder in `waiting` is ready or `deadline` passes; the
+// ones left get a placeholder until their controller catches up.
+fn await_encoders(
+    hardware: &HardwareHandle,
+    ready: &Receiver<EncoderId>,
+    mut waiting: Vec<EncoderId>,
+    deadline: Instant,
+) {
+    while !waiting.is_empty() {
+        match ready.recv_deadline(deadline) {
+            Ok(encoder) => waiting.retain(|pending| *pending != encoder),
+            Err(_) => break,
+        }
+    }
+    if let Err(err) = hardware.dismiss_banner() {
+        warn!(error = %err, "failed to take down startup splash");
+    }
+    for encoder in waiting {
+        info!(?encoder, "encoder control is slow to start");
+        let title = match encoder {
+            EncoderId::One => "volume",
+            EncoderId::Two => "bright",
+            _ => "",
+        };
+        let placeholder = EncoderDisplay::new(title, "…").with_status("init…");
+        if let Err(err) = hardware.show_placeholder(encoder, placeholder) {
+            warn!(error = %err, "failed to show startup placeholder");
         }
     }
 }
+
+fn build_brightness(
+    settings: Option<&StreamDeckSettings>,
+    config: &AppConfig,
+    hardware: &HardwareHandle,
+) -> Result<BrightnessController<DisplayBrightnessBackend, HardwareHandle>> {
+    let monitors = brightness_monitors(settings, config);
+    if !monitors[0].backend.is_available() {
+        warn!("brightness backend unavailable; brightness control disabled");
+    }
+    Ok(BrightnessController::for_monitors(
+        monitors,
+        hardware.clone(),
+        EncoderId::Two,
+        config.brightness_step_percent,
+        config.brightness_min,
+        config.brightness_max,
+        config.brightness_night,
+    )?
+    .with_reconcile_interval(config.brightness_reconcile_ticks)
+    .with_default_level(config.brightness_default)
+    .with_progress_gradient(config.progress_gradient.clone())
+    .with_ramp(brightness_ramp(settings))
+    .with_night_schedule(brightness_night_schedule(settings))
+    .with_deck_sync(brightness_syncs_deck(settings)))
+}
+
+fn build_timer(
+    settings: Option<&StreamDeckSettings>,
+    config: &AppConfig,
+    hardware: &HardwareHandle,
+) -> Result<TimerController<HardwareHandle>> {
+    let timer = TimerController::new(
+        hardware.clone(),
+        EncoderId::Three,
+        config.timer_step_secs,
+        config.timer_min_secs,
+        config.timer_max_secs,
+        config.timer_default_secs,
+    )?
+    .with_mode(
+        settings
+            .and_then(|settings| settings.timer_mode)
+            .unwrap_or(config.timer_mode),
+        config.pomodoro,
+    )?
+    .with_alert(DesktopTimerAlert::new(timer_sound(settings)));
+    match FileTimerStore::default_location() {
+        Some(store) => timer.with_store(store),
+        None => Ok(timer),
+    }
+}
+
+#[derive(Default)]
+struct FourthEncoder {
+    app_volume: Option<AppVolumeController<PulseAudioBackend, HardwareHandle>>,
+    system_stats: Option<SystemStatsController<ProcStatsBackend, HardwareHandle>>,
+    weather: Option<WeatherController<HardwareHandle>>,
+    clock: Option<ClockController<HardwareHandle>>,
+    now_playing: Option<NowPlayingController<PlayerctlBackend, HardwareHandle>>,
+}
+
+// There is no encoder mapping yet, so an application's volume, system stats,
+// the weather or else the clock take over the fourth encoder from now-playing
+// when configured, in that order. The clock also stands in when now-playing
+// fails to start, rather than leaving the segment empty.
+fn build_fourth_encoder(
+    settings: Option<&StreamDeckSettings>,
+    config: &AppConfig,
+    audio: PulseAudioBackend,
+    hardware: &HardwareHandle,
+) -> FourthEncoder {
+    let app_volume = settings
+        .and_then(|settings| settings.app_volume.as_ref())
+        .and_then(|app_volume_config| {
+            let controller = AppVolumeController::new(
+                app_volume_config,
+                audio,
+                hardware.clone(),
+                EncoderId::Four,
+                config.volume_step_percent,
+            )
+            .and_then(AppVolumeController::in_background);
+            match controller {
+                Ok(controller) => Some(controller),
+                Err(err) => {
+                    warn!(error = %err, "failed to initialise application volume control");
+                    None
+                }
+            }
+        });
+
+    let system_stats = settings
+        .and_then(|settings| settings.system_stats.clone())
+        .filter(|_| app_volume.is_none())
+        .and_then(|stats_config| {
+            match SystemStatsController::new(
+                stats_config,
+                ProcStatsBackend::default(),
+                hardware.clone(),
+                EncoderId::Four,
+            ) {
+                Ok(controller) => Some(controller),
+                Err(err) => {
+                    warn!(error = %err, "failed to initialise system stats display");
+                    None
+                }
+            }
+        });
+
+    let weather = settings
+        .and_then(|settings| settings.weather.as_ref())
+        .filter(|_| app_volume.is_none() && system_stats.is_none())
+        .and_then(|weather_config| {
+            match WeatherController::with_default_backend(
+                weather_config,
+                hardware.clone(),
+                EncoderId::Four,
+            ) {
+                Ok(controller) => Some(controller),
+                Err(err) => {
+                    warn!(error = %err, "failed to initialise weather display");
+                    None
+                }
+            }
+        });
+
+    let clock = settings
+        .and_then(|settings| settings.clock.as_ref())
+        .filter(|_| app_volume.is_none() && system_stats.is_none() && weather.is_none())
+        .and_then(|clock_config| build_clock(clock_config, hardware));
+
+    let now_playing =
+        if app_volume.is_none() && system_stats.is_none() && weather.is_none() && clock.is_none() {
+            let player = now_playing_player(settings, config.now_playing_player.as_deref());
+            match build_now_playing(player, hardware) {
+                Ok(controller) => Some(controller),
+                Err(err) => {
+                    warn!(error = %err, "failed to initialise now-playing display");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+    let unused = app_volume.is_none()
+        && system_stats.is_none()
+        && weather.is_none()
+        && clock.is_none()
+        && now_playing.is_none();
+    let clock = if unused {
+        build_clock(&ClockConfig::default(), hardware)
+    } else {
+        clock
+    };
+
+    FourthEncoder {
+        app_volume,
+        system_stats,
+        weather,
+        clock,
+        now_playing,
+    }
+}
+
+fn now_playing_player(settings: Option<&StreamDeckSettings>, fallback: Option<&str>) -> String {
+    settings
+        .and_then(|settings| settings.now_playing_player.as_deref())
+        .or(fallback)
+        .unwrap_or("spotify,%any")
+        .to_string()
+}
+
+fn build_now_playing(
+    player: String,
+    hardware: &HardwareHandle,
+) -> Result<NowPlayingController<PlayerctlBackend, HardwareHandle>> {
+    NowPlayingController::new(
+        PlayerctlBackend::new(player),
+        hardware.clone(),
+        EncoderId::Four,
+    )
+    .and_then(NowPlayingController::in_background)
+}
+
+fn build_clock(
+    config: &ClockConfig,
+    hardware: &HardwareHandle,
+) -> Option<ClockController<HardwareHandle>> {
+    match ClockController::with_system_time(config, hardware.clone(), EncoderId::Four) {
+        Ok(controller) => Some(controller),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise clock display");
+            None
+        }
+    }
+}
+
+fn timer_sound(settings: Option<&StreamDeckSettings>) -> Option<PathBuf> {
+    let settings = settings?;
+    let sound = settings.timer_sound.as_ref()?;
+    match settings.path.parent() {
+        Some(dir) if sound.is_relative() => Some(dir.join(sound)),
+        _ => Some(sound.clone()),
+    }
+}
+
+fn build_audio_toggle(
+    settings: Option<&StreamDeckSettings>,
+    page: usize,
+    hardware: &HardwareHandle,
+    metrics: &Arc<Metrics>,
+) -> Option<AudioToggleController<PulseAudioSwitch, HardwareHandle>> {
+    let settings = settings?;
+    let toggle_settings = AudioToggleSettings {
+        config: settings.page(page).0?.clone(),
+        config_path: Some(settings.path.clone()),
+    };
+    match AudioToggleController::with_default_backend(toggle_settings, hardware.clone()) {
+        Ok(controller) => Some(controller.with_metrics(Arc::clone(metrics))),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise audio output toggle");
+            None
+        }
+    }
+}
+
+fn build_mic_mute(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<MicMuteController<PulseAudioBackend, HardwareHandle>> {
+    let settings = settings?;
+    let config = settings.mic_mute.clone()?;
+    match MicMuteController::with_default_backend(config, Some(&settings.path), hardware.clone()) {
+        Ok(controller) => Some(controller),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise microphone mute toggle");
+            None
+        }
+    }
+}
+
+fn build_dnd(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<DndController<DesktopDndBackend, HardwareHandle>> {
+    let settings = settings?;
+    let config = settings.dnd.clone()?;
+    match DndController::with_default_backend(config, Some(&settings.path), hardware.clone()) {
+        Ok(controller) => Some(controller),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise do-not-disturb toggle");
+            None
+        }
+    }
+}
+
+fn build_capture(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<CaptureController<HardwareHandle>> {
+    let settings = settings?;
+    let config = settings.capture.as_ref()?;
+    match CaptureController::new(config, Some(&settings.path), hardware.clone()) {
+        Ok(controller) => Some(controller),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise screen capture keys");
+            None
+        }
+    }
+}
+
+fn build_vpn(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<VpnController<HardwareHandle>> {
+    let settings = settings?;
+    let config = settings.vpn.clone()?;
+    match VpnController::with_default_backend(config, Some(&settings.path), hardware.clone()) {
+        Ok(controller) => Some(controller),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise vpn toggle");
+            None
+        }
+    }
+}
+
+// The backend and multi-monitor mode are only read at startup; ddcutil detect
+// takes seconds.
+fn brightness_monitors(
+    settings: Option<&StreamDeckSettings>,
+    config: &AppConfig,
+) -> Vec<MonitorTarget<DisplayBrightnessBackend>> {
+    let brightness = settings.and_then(|settings| settings.brightness.as_ref());
+    let kind = brightness
+        .map(|brightness| brightness.backend)
+        .unwrap_or_default();
+    if kind != BrightnessBackendKind::Ddcutil {
+        let device = brightness.and_then(|brightness| brightness.backlight_device.as_deref());
+        match SysfsBacklightBackend::detect(Path::new(SYSFS_BACKLIGHT_ROOT), device) {
+            Ok(Some(backend)) => {
+                info!(device = %backend.name(), "driving brightness through the backlight device");
+                return vec![MonitorTarget::new(
+                    DisplayBrightnessBackend::Backlight(backend),
+                    "",
+                )];
+            }
+            Ok(None) if kind == BrightnessBackendKind::Auto => {}
+            Ok(None) => warn!("no backlight device found; falling back to ddcutil for brightness"),
+            Err(err) => {
+                warn!(error = %err, "failed to open backlight device; falling back to ddcutil for brightness")
+            }
+        }
+    }
+
+    let multi_monitor = brightness.is_some_and(|brightness| brightness.multi_monitor);
+    let single = || {
+        vec![MonitorTarget::new(
+            DisplayBrightnessBackend::Ddcutil(DdcutilBackend::new(
+                config.monitor_display.clone(),
+                config.monitor_bus,
+            )),
+            "",
+        )]
+    };
+    if !multi_monitor {
+        return single();
+    }
+    match DdcutilBackend::enumerate() {
+        Ok(detected) if !detected.is_empty() => {
+            info!(
+                count = detected.len(),
+                "driving brightness on detected monitors"
+            );
+            detected
+                .into_iter()
+                .map(|monitor| {
+                    MonitorTarget::new(
+                        DisplayBrightnessBackend::Ddcutil(DdcutilBackend::new(
+                            Some(monitor.display.to_string()),
+                            None,
+                        )),
+                        monitor.model,
+                    )
+                })
+                .collect()
+        }
+        Ok(_) => {
+            warn!("ddcutil detected no monitors; using the default display for brightness");
+            single()
+        }
+        Err(err) => {
+            warn!(error = %err, "failed to enumerate monitors; using the default display for brightness");
+            single()
+        }
+    }
+}
+
+fn brightness_cycle_button(settings: Option<&StreamDeckSettings>) -> Option<u8> {
+    settings?.brightness.as_ref()?.cycle_button
+}
+
+fn brightness_night_schedule(settings: Option<&StreamDeckSettings>) -> Option<NightSchedule> {
+    settings?.brightness.as_ref()?.night_schedule()
+}
+
+fn brightness_ramp(settings: Option<&StreamDeckSettings>) -> Option<BrightnessRamp> {
+    settings?.brightness.as_ref()?.ramp()
+}
+
+fn brightness_syncs_deck(settings: Option<&StreamDeckSettings>) -> bool {
+    settings
+        .and_then(|settings| settings.brightness.as_ref())
+        .is_some_and(|brightness| brightness.sync_deck_brightness)
+}
+
+fn build_launchers(
+    settings: Option<&StreamDeckSettings>,
+    page: usize,
+    hardware: &HardwareHandle,
+    long_press: HoldDetector,
+) -> Option<LauncherController> {
+    let configs: Vec<LauncherButtonConfig> = settings
+        .map(|settings| settings.page(page).1)?
+        .iter()
+        .filter(|config| on_device(hardware, config.device.as_deref()))
+        .cloned()
+        .collect();
+    if configs.is_empty() {
+        return None;
+    }
+    let config_path = settings.map(|settings| settings.path.as_path());
+    let double_press = settings
+        .and_then(|settings| settings.app.double_press_ms)
+        .map(Duration::from_millis);
+    match LauncherController::new(&configs, config_path, hardware) {
+        Ok(controller) => controller.map(|controller| {
+            let controller = controller.with_long_press(long_press);
+            match double_press {
+                Some(window) => controller.with_double_press_window(window),
+                None => controller,
+            }
+        }),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise application launchers");
+            None
+        }
+    }
+}
+
+fn build_commands(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<CommandController<HardwareHandle>> {
+    let settings = settings?;
+    let configs: Vec<CommandButtonConfig> = settings
+        .commands
+        .iter()
+        .filter(|config| on_device(hardware, config.device.as_deref()))
+        .cloned()
+        .collect();
+    if configs.is_empty() {
+        return None;
+    }
+    match CommandController::new(&configs, Some(&settings.path), hardware.clone()) {
+        Ok(controller) => controller,
+        Err(err) => {
+            warn!(error = %err, "failed to initialise command buttons");
+            None
+        }
+    }
+}
+
+fn build_snippets(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<SnippetController<DesktopSnippetBackend>> {
+    let settings = settings?;
+    if settings.snippets.is_empty() {
+        return None;
+    }
+    match SnippetController::with_default_backend(
+        &settings.snippets,
+        Some(&settings.path),
+        hardware,
+    ) {
+        Ok(controller) => controller,
+        Err(err) => {
+            warn!(error = %err, "failed to initialise snippet keys");
+            None
+        }
+    }
+}
+
+fn build_bluetooth(
+    settings: Option<&StreamDeckSettings>,
+    hardware: &HardwareHandle,
+) -> Option<BluetoothController<BluetoothctlBackend, HardwareHandle>> {
+    let settings = settings?;
+    if settings.bluetooth.is_empty() {
+        return None;
+    }
+    match BluetoothController::with_default_backend(
+        &settings.bluetooth,
+        Some(&settings.path),
+        hardware.clone(),
+    ) {
+        Ok(controller) => controller,
+        Err(err) => {
+            warn!(error = %err, "failed to initialise bluetooth keys");
+            None
+        }
+    }
+}
+
+fn build_page_switch(
+    settings: Option<&StreamDeckSettings>,
+    page: usize,
+    hardware: &HardwareHandle,
+) -> Option<PageSwitcher<HardwareHandle>> {
+    let settings = settings?;
+    let button_index = settings.page_switch_button?;
+    if settings.page_count() < 2 {
+        return None;
+    }
+    match PageSwitcher::new(button_index, settings.page_count(), page, hardware.clone()) {
+        Ok(switcher) => Some(switcher),
+        Err(err) => {
+            warn!(error = %err, "failed to initialise page switch button");
+            None
+        }
+    }
+}
+
+fn build_other_devices(
+    settings: Option<&StreamDeckSettings>,
+    page: usize,
+    hardware: &HardwareHandle,
+    long_press: HoldDetector,
+) -> Vec<DeviceButtons> {
+    hardware
+        .devices()
+        .into_iter()
+        .skip(1)
+        .map(|device| DeviceButtons {
+            launchers: build_launchers(settings, page, &device, long_press),
+            commands: build_commands(settings, &device),
+            hardware: device,
+            pressed_at: HashMap::new(),
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "hardware"))]
+mod tests {
+    use super::*;
+    use crate::system::audio::tests::{MockAudioBackend, MockAudioState};
+    use crate::system::brightness::tests::{MockBrightnessBackend, MockBrightnessState};
+    use serde_json::{Value, json};
+    use std::sync::Mutex;
+
+    type TestApp = App<MockAudioBackend, MockBrightnessBackend>;
+
+    fn test_app(state_path: PathBuf) -> TestApp {
+        let (hardware, events) = HardwareHandle::headless().expect("hardware");
+        let audio = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 40.0,
+                sink_label: Some("Digital Output - A50".into()),
+                ..Default::default()
+            })),
+        };
+        let brightness = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                ..Default::default()
+            })),
+        };
+        let (reload_sender, reload_requests) = crossbeam_channel::bounded(1);
+        let long_press = HoldDetector::new(Duration::from_millis(600));
+        App {
+            volume: VolumeController::new(audio, hardware.clone(), EncoderId::One, 5)
+                .expect("volume"),
+            brightness: BrightnessController::new(
+                brightness,
+                hardware.clone(),
+                EncoderId::Two,
+                5,
+                10,
+                100,
+                15,
+            )
+            .expect("brightness"),
+            timer: TimerController::new(hardware.clone(), EncoderId::Three, 30, 30, 3600, 300)
+                .expect("timer"),
+            audio_toggle: None,
+            mic_mute: None,
+            dnd: None,
+            vpn: None,
+            capture: None,
+            now_playing: None,
+            default_player: None,
+            app_volume: None,
+            system_stats: None,
+            weather: None,
+            clock: None,
+            launchers: None,
+            commands: None,
+            snippets: None,
+            bluetooth: None,
+            page_switch: Some(PageSwitcher::new(7, 2, 0, hardware.clone()).expect("pages")),
+            other_devices: Vec::new(),
+            settings: None,
+            brightness_cycle_button: None,
+            config_watcher: ConfigWatcher::new(),
+            walkthrough: None,
+            encoder_pressed_at: [None; 4],
+            button_pressed_at: HashMap::new(),
+            long_press,
+            swipe_min_px: 40,
+            volume_presses: None,
+            hardware,
+            shutdown: None,
+            reload_requests,
+            reload_sender,
+            #[cfg(feature = "watch")]
+            config_file_watcher: None,
+            events,
+            pulse_events: PulseEventWatcher::idle(),
+            session: SessionWatch::new(LoginctlSession::new()),
+            locked_brightness: None,
+            state_file: Some(StateFile::new(state_path)),
+            metrics: Arc::default(),
+            #[cfg(feature = "metrics")]
+            _metrics_server: None,
+            _ipc_server: None,
+            ipc_commands: crossbeam_channel::never(),
+        }
+    }
+
+    fn read(path: &Path) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn state_file_follows_input_handled_by_the_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let mut app = test_app(path.clone());
+
+        app.handle_event(HardwareEvent::EncoderTurned {
+            encoder: EncoderId::One,
+            delta: 2,
+        })
+        .unwrap();
+        app.handle_event(HardwareEvent::EncoderTurned {
+            encoder: EncoderId::Three,
+            delta: 2,
+        })
+        .unwrap();
+        app.handle_event(HardwareEvent::ButtonPressed(7)).unwrap();
+        app.handle_event(HardwareEvent::ButtonReleased(7)).unwrap();
+        assert!(!path.exists());
+        app.write_state();
+
+        let state = read(&path);
+        let volume = &state["encoders"][0];
+        assert_eq!(volume["value"].as_str().map(str::trim), Some("50%"));
+        assert_eq!(volume["status"], "A50");
+        assert_eq!(
+            state["encoders"][1]["value"].as_str().map(str::trim),
+            Some("60%")
+        );
+        assert!(state["encoders"][2].is_object());
+        assert_eq!(state["encoders"][3], Value::Null);
+        assert_eq!(state["active_sink"], "A50");
+        assert_eq!(
+            state["timer"],
+            json!({"state": "setting", "remaining_secs": 360})
+        );
+        assert_eq!(
+            state["buttons"],
+            json!([{"index": 7, "icon": "page-switch-7-1of2"}])
+        );
+
+        drop(app);
+        assert!(!path.exists());
+    }
+}
@@ -1,18 +1,389 @@
+use std::collections::HashMap;
 use std::env;
-use std::fs;
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
-use anyhow::{Context, Result, anyhow};
-use serde::Deserialize;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::warn;
 
-use crate::controls::AudioToggleConfig;
+use crate::app::AppConfig;
+use crate::hardware::{DeviceConfig, IdleDim, ProgressStyle, SegmentTheme};
+use crate::system::audio_switch::SinkInfo;
+use crate::system::desktop::DesktopEntry;
+
+use crate::controls::{
+    AppVolumeConfig, AudioToggleConfig, BluetoothConfig, BrightnessConfig, CaptureConfig,
+    ClockConfig, CommandButtonConfig, CommandLine, DndConfig, IconConfig, MicMuteConfig,
+    PomodoroConfig, SnippetConfig, SystemStatsConfig, TimerMode, VpnConfig, WeatherConfig,
+};
 
 #[derive(Debug, Clone)]
 pub struct StreamDeckSettings {
     pub path: PathBuf,
     pub audio_toggle: Option<AudioToggleConfig>,
+    pub mic_mute: Option<MicMuteConfig>,
+    pub dnd: Option<DndConfig>,
+    pub vpn: Option<VpnConfig>,
+    pub capture: Option<CaptureConfig>,
+    pub brightness: Option<BrightnessConfig>,
+    pub app_volume: Option<AppVolumeConfig>,
+    pub system_stats: Option<SystemStatsConfig>,
+    pub weather: Option<WeatherConfig>,
+    pub clock: Option<ClockConfig>,
+    pub theme: Option<ThemeConfig>,
     pub now_playing_player: Option<String>,
+    pub timer_sound: Option<PathBuf>,
+    pub timer_mode: Option<TimerMode>,
+    pub launchers: Vec<LauncherButtonConfig>,
+    pub commands: Vec<CommandButtonConfig>,
+    pub snippets: Vec<SnippetConfig>,
+    pub bluetooth: Vec<BluetoothConfig>,
+    pub pages: Vec<PageConfig>,
+    pub page_switch_button: Option<u8>,
+    pub app: AppOverrides,
+}
+
+impl StreamDeckSettings {
+    /// The top-level layout is the first page; each `pages` entry adds one.
+    pub fn page_count(&self) -> usize {
+        1 + self.pages.len()
+    }
+
+    /// The audio toggle and launchers shown on `page`.
+    pub fn page(&self, page: usize) -> (Option<&AudioToggleConfig>, &[LauncherButtonConfig]) {
+        match page.checked_sub(1).and_then(|index| self.pages.get(index)) {
+            Some(extra) => (extra.audio_toggle.as_ref(), &extra.launchers),
+            None => (self.audio_toggle.as_ref(), &self.launchers),
+        }
+    }
+}
+
+const DEFAULT_IDLE_DIM_BRIGHTNESS: u8 = 5;
+
+/// Settings from the `app` section, applied over the `AppConfig` defaults
+/// at startup. Absent fields keep their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppOverrides {
+    pub volume_step_percent: Option<i32>,
+    /// Volume a long press on the volume encoder snaps to.
+    pub volume_default: Option<u8>,
+    /// Started by holding the volume encoder, e.g. `pavucontrol`.
+    pub volume_hold_command: Option<CommandLine>,
+    pub brightness_step_percent: Option<u8>,
+    pub brightness_min: Option<u8>,
+    pub brightness_max: Option<u8>,
+    pub brightness_night: Option<u8>,
+    pub brightness_reconcile_ticks: Option<u32>,
+    /// Brightness a long press on the brightness encoder snaps to.
+    pub brightness_default: Option<u8>,
+    pub timer_step_secs: Option<u64>,
+    pub timer_min_secs: Option<u64>,
+    pub timer_max_secs: Option<u64>,
+    pub timer_default_secs: Option<u64>,
+    pub pomodoro: Option<PomodoroConfig>,
+    pub long_press_ms: Option<u64>,
+    pub double_press_ms: Option<u64>,
+    pub swipe_min_px: Option<u16>,
+    /// How long a pactl or playerctl call may run before it is killed.
+    pub command_timeout_ms: Option<u64>,
+    pub pulse_sink: Option<String>,
+    pub monitor_display: Option<String>,
+    pub monitor_bus: Option<u8>,
+    /// Deck brightness while the session is locked; unchanged when unset.
+    pub locked_brightness: Option<u8>,
+    pub state_file: Option<StateFileSetting>,
+    /// Address for the `/metrics` and `/healthz` endpoint; off when unset.
+    pub metrics_address: Option<String>,
+    /// Two colours, e.g. `["#1e5aa0", "#50c8ff"]`, the volume and brightness
+    /// bars blend between from left to right.
+    pub progress_gradient: Option<[String; 2]>,
+    pub hardware: HardwareOverrides,
+}
+
+/// `false` turns the deck state file off; a path moves it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum StateFileSetting {
+    Enabled(bool),
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HardwareOverrides {
+    pub serial: Option<String>,
+    pub device_brightness: Option<u8>,
+    /// Seconds without input before the deck dims; 0 turns dimming off.
+    pub idle_dim_secs: Option<u64>,
+    pub idle_dim_brightness: Option<u8>,
+    /// Eases the strip's progress bars to big changes; on by default.
+    pub animate_values: Option<bool>,
+    /// Smaller changes, in percent of the bar, are drawn at once.
+    pub animate_min_delta_percent: Option<u8>,
+    /// Decks to drive at once, the first being the default device.
+    pub devices: Vec<DeviceSettings>,
+}
+
+/// One entry of `hardware.devices`; launchers and commands pick a deck by
+/// `role`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceSettings {
+    #[serde(default)]
+    pub serial: Option<String>,
+    #[serde(alias = "name")]
+    pub role: String,
+}
+
+impl AppOverrides {
+    /// Out-of-range values are logged and clamped to the nearest valid one.
+    pub fn apply(&self, config: &mut AppConfig) {
+        set_clamped(
+            &mut config.volume_step_percent,
+            "volume_step_percent",
+            self.volume_step_percent,
+            1..=100,
+        );
+        set_clamped(
+            &mut config.brightness_step_percent,
+            "brightness_step_percent",
+            self.brightness_step_percent,
+            1..=100,
+        );
+        set_clamped(
+            &mut config.brightness_min,
+            "brightness_min",
+            self.brightness_min,
+            0..=99,
+        );
+        set_clamped(
+            &mut config.brightness_max,
+            "brightness_max",
+            self.brightness_max,
+            1..=100,
+        );
+        if config.brightness_min >= config.brightness_max {
+            warn!(
+                brightness_min = config.brightness_min,
+                brightness_max = config.brightness_max,
+                "`app.brightness_min` must be below `app.brightness_max`; lowering it"
+            );
+            config.brightness_min = config.brightness_max - 1;
+        }
+        set_clamped(
+            &mut config.brightness_night,
+            "brightness_night",
+            self.brightness_night,
+            config.brightness_min..=config.brightness_max,
+        );
+        set_clamped(
+            &mut config.brightness_reconcile_ticks,
+            "brightness_reconcile_ticks",
+            self.brightness_reconcile_ticks,
+            1..=u32::MAX,
+        );
+
+        set_clamped(
+            &mut config.timer_step_secs,
+            "timer_step_secs",
+            self.timer_step_secs,
+            1..=u64::MAX,
+        );
+        if let Some(min) = self.timer_min_secs {
+            config.timer_min_secs = min;
+        }
+        set_clamped(
+            &mut config.timer_max_secs,
+            "timer_max_secs",
+            self.timer_max_secs,
+            1..=u64::MAX,
+        );
+        if config.timer_min_secs > config.timer_max_secs {
+            warn!(
+                timer_min_secs = config.timer_min_secs,
+                timer_max_secs = config.timer_max_secs,
+                "`app.timer_min_secs` exceeds `app.timer_max_secs`; lowering it"
+            );
+            config.timer_min_secs = config.timer_max_secs;
+        }
+        set_clamped(
+            &mut config.timer_default_secs,
+            "timer_default_secs",
+            self.timer_default_secs,
+            config.timer_min_secs..=config.timer_max_secs,
+        );
+        if let Some(pomodoro) = self.pomodoro {
+            if pomodoro.work_secs == 0 || pomodoro.cycles == 0 {
+                warn!(
+                    ?pomodoro,
+                    "`app.pomodoro` needs a work length and at least one cycle; raising them"
+                );
+            }
+            config.pomodoro = PomodoroConfig {
+                work_secs: pomodoro.work_secs.max(1),
+                cycles: pomodoro.cycles.max(1),
+                ..pomodoro
+            };
+        }
+
+        set_clamped(
+            &mut config.long_press_ms,
+            "long_press_ms",
+            self.long_press_ms,
+            1..=u64::MAX,
+        );
+        if let Some(ms) = self.double_press_ms {
+            config.double_press_ms = ms;
+        }
+        set_clamped(
+            &mut config.swipe_min_px,
+            "swipe_min_px",
+            self.swipe_min_px,
+            1..=u16::MAX,
+        );
+        set_clamped(
+            &mut config.command_timeout_ms,
+            "command_timeout_ms",
+            self.command_timeout_ms,
+            1..=u64::MAX,
+        );
+        if let Some(sink) = &self.pulse_sink {
+            config.pulse_sink = Some(sink.clone());
+        }
+        if let Some(display) = &self.monitor_display {
+            config.monitor_display = Some(display.clone());
+        }
+        if let Some(bus) = self.monitor_bus {
+            config.monitor_bus = Some(bus);
+        }
+        if let Some(command) = &self.volume_hold_command {
+            config.volume_hold_command = Some(command.clone());
+        }
+        for (field, value, target, max) in [
+            (
+                "locked_brightness",
+                self.locked_brightness,
+                &mut config.locked_brightness,
+                100,
+            ),
+            (
+                "volume_default",
+                self.volume_default,
+                &mut config.volume_default,
+                150,
+            ),
+            (
+                "brightness_default",
+                self.brightness_default,
+                &mut config.brightness_default,
+                100,
+            ),
+        ] {
+            if let Some(mut level) = value {
+                set_clamped(&mut level, field, value, 0..=max);
+                *target = Some(level);
+            }
+        }
+        match &self.state_file {
+            Some(StateFileSetting::Enabled(false)) => config.state_file = None,
+            Some(StateFileSetting::Path(path)) => config.state_file = Some(path.clone()),
+            Some(StateFileSetting::Enabled(true)) | None => {}
+        }
+        if let Some(address) = &self.metrics_address {
+            config.metrics_address = Some(address.clone());
+        }
+        if let Some([from, to]) = &self.progress_gradient {
+            match parse_hex_color(from).and_then(|from| Ok((from, parse_hex_color(to)?))) {
+                Ok((from, to)) => {
+                    config.progress_gradient = Some(ProgressStyle::Gradient { from, to })
+                }
+                Err(err) => warn!(error = %err, "ignoring invalid `app.progress_gradient`"),
+            }
+        }
+
+        if let Some(serial) = &self.hardware.serial {
+            config.hardware.serial = Some(serial.clone());
+        }
+        set_clamped(
+            &mut config.hardware.device_brightness,
+            "hardware.device_brightness",
+            self.hardware.device_brightness,
+            0..=100,
+        );
+        if let Some(secs) = self.hardware.idle_dim_secs {
+            let mut brightness = config
+                .hardware
+                .idle_dim
+                .map_or(DEFAULT_IDLE_DIM_BRIGHTNESS, |dim| dim.brightness);
+            set_clamped(
+                &mut brightness,
+                "hardware.idle_dim_brightness",
+                self.hardware.idle_dim_brightness,
+                0..=100,
+            );
+            config.hardware.idle_dim = (secs > 0).then(|| IdleDim {
+                after: Duration::from_secs(secs),
+                brightness,
+            });
+        }
+        if let Some(enabled) = self.hardware.animate_values {
+            config.hardware.value_animation =
+                enabled.then(|| config.hardware.value_animation.unwrap_or_default());
+        }
+        if let Some(animation) = config.hardware.value_animation.as_mut() {
+            let mut min_delta = (animation.min_delta * 100.0).round() as u8;
+            set_clamped(
+                &mut min_delta,
+                "hardware.animate_min_delta_percent",
+                self.hardware.animate_min_delta_percent,
+                0..=100,
+            );
+            animation.min_delta = f32::from(min_delta) / 100.0;
+        }
+        let mut devices: Vec<DeviceConfig> = Vec::new();
+        for device in &self.hardware.devices {
+            if devices.iter().any(|known| known.role == device.role) {
+                warn!(role = %device.role, "ignoring `hardware.devices` entry with a duplicate role");
+                continue;
+            }
+            devices.push(DeviceConfig {
+                serial: device.serial.clone(),
+                role: device.role.clone(),
+            });
+        }
+        if !devices.is_empty() {
+            config.hardware.devices = devices;
+        }
+    }
+}
+
+fn set_clamped<T>(target: &mut T, field: &str, value: Option<T>, range: RangeInclusive<T>)
+where
+    T: Copy + Ord + fmt::Display,
+{
+    let Some(value) = value else {
+        return;
+    };
+    let clamped = value.clamp(*range.start(), *range.end());
+    if clamped != value {
+        warn!(field, %value, %clamped, "out-of-range `app` setting; clamping it");
+    }
+    *target = clamped;
+}
+
+/// An additional button page; other buttons and the encoders stay the same
+/// on every page.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PageConfig {
+    pub audio_toggle: Option<AudioToggleConfig>,
     pub launchers: Vec<LauncherButtonConfig>,
 }
 
@@ -22,41 +393,257 @@ pub struct LauncherButtonConfig {
     pub button_index: u8,
     #[serde(alias = "desktop", alias = "path")]
     pub desktop_file: PathBuf,
+    /// Runs this `[Desktop Action ...]` instead of the entry's main command.
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default, alias = "hold_action")]
+    pub long_press_action: Option<String>,
+    /// Another application started by holding the key; takes the place of
+    /// `long_press_action`.
+    #[serde(default)]
+    pub long_press_desktop_file: Option<PathBuf>,
+    /// Another application started by pressing the key twice. The single
+    /// press then waits for the double-press window to close.
+    #[serde(default)]
+    pub double_press_desktop_file: Option<PathBuf>,
+    #[serde(default)]
+    pub show_label: bool,
+    /// Replaces the icon named by the desktop entry.
+    #[serde(default)]
+    pub icon: Option<IconConfig>,
+    /// `#RRGGBB` fill behind transparent icon pixels.
+    #[serde(default)]
+    pub background: Option<String>,
+    /// Focuses a running window of the application instead of starting
+    /// another instance.
+    #[serde(default)]
+    pub single_instance: bool,
+    /// Role of the `hardware.devices` entry this key is on; the default
+    /// device when unset.
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+/// A wall-clock time written as `HH:MM` in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ClockTime {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl FromStr for ClockTime {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (hour, minute) = value
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected HH:MM, got `{value}`"))?;
+        let hour: u8 = hour
+            .parse()
+            .with_context(|| format!("invalid hour in `{value}`"))?;
+        let minute: u8 = minute
+            .parse()
+            .with_context(|| format!("invalid minute in `{value}`"))?;
+        if hour > 23 || minute > 59 {
+            bail!("`{value}` is not a valid time of day");
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+impl TryFrom<String> for ClockTime {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// A colour written as `#rrggbb` in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl TryFrom<String> for HexColor {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        parse_hex_color(&value).map(Self)
+    }
+}
+
+pub(crate) fn parse_hex_color(value: &str) -> Result<[u8; 3]> {
+    let digits = value.trim().strip_prefix('#').unwrap_or(value.trim());
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("expected a colour like \"#1e1e2e\", got {value:?}");
+    }
+    let channel = |start: usize| u8::from_str_radix(&digits[start..start + 2], 16);
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// LCD strip colours from the `theme` section. Fields set at the top level
+/// apply to every segment; `encoders` entries, keyed by encoder index,
+/// override them for one segment.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawTheme")]
+pub struct ThemeConfig {
+    pub segments: [SegmentTheme; 4],
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct RawTheme {
+    #[serde(flatten)]
+    global: SegmentThemeOverrides,
+    #[serde(default)]
+    encoders: HashMap<String, SegmentThemeOverrides>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct SegmentThemeOverrides {
+    background: Option<String>,
+    title: Option<String>,
+    value: Option<String>,
+    status: Option<String>,
+    progress_background: Option<String>,
+    progress: Option<String>,
+    border: Option<bool>,
+    use_ttf: Option<bool>,
+}
+
+impl SegmentThemeOverrides {
+    // Colours are parsed here rather than by serde so errors can name the key.
+    fn apply(&self, theme: &mut SegmentTheme, scope: &str) -> Result<()> {
+        let colors = [
+            ("background", &self.background, &mut theme.background),
+            ("title", &self.title, &mut theme.title),
+            ("value", &self.value, &mut theme.value),
+            ("status", &self.status, &mut theme.status),
+            (
+                "progress_background",
+                &self.progress_background,
+                &mut theme.progress_background,
+            ),
+            ("progress", &self.progress, &mut theme.progress),
+        ];
+        for (key, color, slot) in colors {
+            if let Some(color) = color {
+                *slot = parse_hex_color(color)
+                    .map_err(|err| anyhow!("invalid `{scope}.{key}`: {err}"))?;
+            }
+        }
+        if let Some(border) = self.border {
+            theme.border = border;
+        }
+        if let Some(use_ttf) = self.use_ttf {
+            theme.use_ttf = use_ttf;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<RawTheme> for ThemeConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawTheme) -> Result<Self> {
+        let mut base = SegmentTheme::default();
+        raw.global.apply(&mut base, "theme")?;
+        let mut segments = [base; 4];
+        for (key, overrides) in &raw.encoders {
+            let index = key
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < segments.len())
+                .ok_or_else(|| {
+                    anyhow!("`theme.encoders` keys are encoder indices 0-3, got `{key}`")
+                })?;
+            overrides.apply(&mut segments[index], &format!("theme.encoders.{key}"))?;
+        }
+        Ok(Self { segments })
+    }
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 struct StructuredConfig {
     pub audio_toggle: Option<AudioToggleConfig>,
+    pub mic_mute: Option<MicMuteConfig>,
+    pub dnd: Option<DndConfig>,
+    pub vpn: Option<VpnConfig>,
+    pub capture: Option<CaptureConfig>,
+    pub brightness: Option<BrightnessConfig>,
+    pub app_volume: Option<AppVolumeConfig>,
+    pub system_stats: Option<SystemStatsConfig>,
+    pub weather: Option<WeatherConfig>,
+    pub clock: Option<ClockConfig>,
+    pub theme: Option<ThemeConfig>,
     pub now_playing_player: Option<String>,
+    pub timer_sound: Option<PathBuf>,
+    pub timer_mode: Option<TimerMode>,
     pub launchers: Vec<LauncherButtonConfig>,
+    pub commands: Vec<CommandButtonConfig>,
+    pub snippets: Vec<SnippetConfig>,
+    pub bluetooth: Vec<BluetoothConfig>,
+    pub pages: Vec<PageConfig>,
+    pub page_switch_button: Option<u8>,
+    pub app: AppOverrides,
 }
 
 pub fn load_settings() -> Result<Option<StreamDeckSettings>> {
-    for candidate in default_config_paths() {
-        if !candidate.exists() {
-            continue;
-        }
-        let contents = fs::read_to_string(&candidate).with_context(|| {
-            format!(
-                "failed to read streamdeck_ctrl configuration at {}",
-                candidate.display()
-            )
-        })?;
-        let structured = parse_config(&contents).with_context(|| {
-            format!(
-                "failed to parse streamdeck_ctrl configuration at {}",
-                candidate.display()
-            )
-        })?;
-        return Ok(Some(StreamDeckSettings {
-            path: candidate,
-            audio_toggle: structured.audio_toggle,
-            now_playing_player: structured.now_playing_player,
-            launchers: structured.launchers,
-        }));
+    match default_config_paths()
+        .into_iter()
+        .find(|candidate| candidate.exists())
+    {
+        Some(path) => load_settings_from(&path).map(Some),
+        None => Ok(None),
     }
-    Ok(None)
+}
+
+pub fn load_settings_from(path: &Path) -> Result<StreamDeckSettings> {
+    let contents = fs::read_to_string(path).with_context(|| {
+        format!(
+            "failed to read streamdeck_ctrl configuration at {}",
+            path.display()
+        )
+    })?;
+    let structured = parse_config(&contents).with_context(|| {
+        format!(
+            "failed to parse streamdeck_ctrl configuration at {}",
+            path.display()
+        )
+    })?;
+    Ok(StreamDeckSettings {
+        path: path.to_path_buf(),
+        audio_toggle: structured.audio_toggle,
+        mic_mute: structured.mic_mute,
+        dnd: structured.dnd,
+        vpn: structured.vpn,
+        capture: structured.capture,
+        brightness: structured.brightness,
+        app_volume: structured.app_volume,
+        system_stats: structured.system_stats,
+        weather: structured.weather,
+        clock: structured.clock,
+        theme: structured.theme,
+        now_playing_player: structured.now_playing_player,
+        timer_sound: structured.timer_sound,
+        timer_mode: structured.timer_mode,
+        app: structured.app,
+        launchers: structured.launchers,
+        commands: structured.commands,
+        snippets: structured.snippets,
+        bluetooth: structured.bluetooth,
+        pages: structured.pages,
+        page_switch_button: structured.page_switch_button,
+    })
 }
 
 fn parse_config(contents: &str) -> Result<StructuredConfig> {
@@ -75,6 +662,52 @@ fn parse_config(contents: &str) -> Result<StructuredConfig> {
             .transpose()?
             .unwrap_or_default();
 
+        let commands = map
+            .remove("commands")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `commands` entries from configuration")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let snippets = map
+            .remove("snippets")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `snippets` entries from configuration")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let bluetooth = map
+            .remove("bluetooth")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `bluetooth` entries from configuration")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let pages: Vec<PageConfig> = map
+            .remove("pages")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `pages` from configuration")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let page_switch_button = map
+            .remove("page_switch_button")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `page_switch_button` from configuration")
+            })
+            .transpose()?;
+        if !pages.is_empty() && page_switch_button.is_none() {
+            bail!("`pages` needs a `page_switch_button` to switch between them");
+        }
+
         let now_playing_player = map
             .remove("now_playing_player")
             .map(|raw| {
@@ -83,6 +716,30 @@ fn parse_config(contents: &str) -> Result<StructuredConfig> {
             })
             .transpose()?;
 
+        let timer_sound = map
+            .remove("timer_sound")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `timer_sound` from configuration")
+            })
+            .transpose()?;
+
+        let app = map
+            .remove("app")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `app` configuration section")
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let timer_mode = map
+            .remove("timer_mode")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `timer_mode` from configuration")
+            })
+            .transpose()?;
+
         let audio_toggle = map
             .remove("audio_toggle")
             .map(|raw| {
@@ -91,9 +748,93 @@ fn parse_config(contents: &str) -> Result<StructuredConfig> {
             })
             .transpose()?;
 
+        let mic_mute = map
+            .remove("mic_mute")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `mic_mute` configuration section")
+            })
+            .transpose()?;
+
+        let dnd = map
+            .remove("dnd")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `dnd` configuration section")
+            })
+            .transpose()?;
+
+        let vpn = map
+            .remove("vpn")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `vpn` configuration section")
+            })
+            .transpose()?;
+
+        let capture = map
+            .remove("capture")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `capture` configuration section")
+            })
+            .transpose()?;
+
+        let brightness: Option<BrightnessConfig> = map
+            .remove("brightness")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `brightness` configuration section")
+            })
+            .transpose()?;
+        if brightness.as_ref().is_some_and(|brightness| {
+            brightness.night_start.is_some() != brightness.night_end.is_some()
+        }) {
+            bail!("`brightness` needs both `night_start` and `night_end` for a night schedule");
+        }
+
+        let app_volume = map
+            .remove("app_volume")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `app_volume` configuration section")
+            })
+            .transpose()?;
+
+        let system_stats = map
+            .remove("system_stats")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `system_stats` configuration section")
+            })
+            .transpose()?;
+
+        let weather = map
+            .remove("weather")
+            .map(|raw| {
+                serde_json::from_value(raw)
+                    .context("failed to parse `weather` configuration section")
+            })
+            .transpose()?;
+
+        let clock = map
+            .remove("clock")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `clock` configuration section")
+            })
+            .transpose()?;
+
+        let theme = map
+            .remove("theme")
+            .map(|raw| {
+                serde_json::from_value(raw).context("failed to parse `theme` configuration section")
+            })
+            .transpose()?;
+
         let inline_toggle = if audio_toggle.is_none() && map.contains_key("outputs") {
             let mut inline_map = map.clone();
             inline_map.remove("launchers");
+            inline_map.remove("commands");
+            inline_map.remove("snippets");
+            inline_map.remove("bluetooth");
             inline_map.remove("now_playing_player");
             serde_json::from_value(Value::Object(inline_map)).ok()
         } else {
@@ -102,21 +843,222 @@ fn parse_config(contents: &str) -> Result<StructuredConfig> {
 
         return Ok(StructuredConfig {
             audio_toggle: audio_toggle.or(inline_toggle),
+            mic_mute,
+            dnd,
+            vpn,
+            capture,
+            brightness,
+            app_volume,
+            system_stats,
+            weather,
+            clock,
+            theme,
             now_playing_player,
+            timer_sound,
+            timer_mode,
+            app,
             launchers,
+            commands,
+            snippets,
+            bluetooth,
+            pages,
+            page_switch_button,
         });
     }
 
     match serde_json::from_value::<AudioToggleConfig>(value.clone()) {
         Ok(audio_toggle) => Ok(StructuredConfig {
             audio_toggle: Some(audio_toggle),
+            mic_mute: None,
+            dnd: None,
+            vpn: None,
+            capture: None,
+            brightness: None,
+            app_volume: None,
+            system_stats: None,
+            weather: None,
+            clock: None,
+            theme: None,
             now_playing_player: None,
+            timer_sound: None,
+            timer_mode: None,
+            app: AppOverrides::default(),
             launchers: Vec::new(),
+            commands: Vec::new(),
+            snippets: Vec::new(),
+            bluetooth: Vec::new(),
+            pages: Vec::new(),
+            page_switch_button: None,
         }),
         Err(err) => Err(anyhow!(err)),
     }
 }
 
+// The top row of a Stream Deck Plus holds the outputs, the bottom row the
+// launchers.
+const STARTER_OUTPUTS: usize = 4;
+const STARTER_FIRST_LAUNCHER_KEY: u8 = 4;
+const STARTER_LAUNCHERS: usize = 2;
+/// Desktop entries a starter configuration has room for; those past the
+/// launcher keys are kept as spares.
+pub const STARTER_ENTRIES: usize = 4;
+
+// JSON has no comments, and keys the daemon does not know are ignored, so
+// notes and spare entries go under `_` keys.
+#[derive(Debug, Serialize)]
+struct StarterConfig {
+    #[serde(rename = "_comment")]
+    comment: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio_toggle: Option<StarterToggle>,
+    launchers: Vec<StarterLauncher>,
+    #[serde(rename = "_more_launchers", skip_serializing_if = "Vec::is_empty")]
+    more_launchers: Vec<StarterLauncher>,
+}
+
+#[derive(Debug, Serialize)]
+struct StarterToggle {
+    outputs: Vec<StarterOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct StarterOutput {
+    button_index: u8,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StarterLauncher {
+    button_index: u8,
+    desktop_file: PathBuf,
+}
+
+/// A first configuration with an audio toggle over `sinks` and launchers
+/// for `entries`; the toggle is left out when there are no sinks.
+pub fn starter_config(sinks: &[SinkInfo], entries: &[DesktopEntry]) -> Result<String> {
+    let outputs: Vec<StarterOutput> = sinks
+        .iter()
+        .take(STARTER_OUTPUTS)
+        .zip(0..)
+        .map(|(sink, button_index)| StarterOutput {
+            button_index,
+            name: sink.name.clone(),
+            description: sink.description.clone(),
+        })
+        .collect();
+    let mut launchers: Vec<StarterLauncher> = entries
+        .iter()
+        .take(STARTER_ENTRIES)
+        .zip(STARTER_FIRST_LAUNCHER_KEY..)
+        .map(|(entry, button_index)| StarterLauncher {
+            button_index,
+            desktop_file: entry.source_path.clone(),
+        })
+        .collect();
+    let more_launchers = launchers.split_off(launchers.len().min(STARTER_LAUNCHERS));
+
+    let config = StarterConfig {
+        comment: "Generated by streamdeck_ctrl. Move entries from `_more_launchers` \
+                  into `launchers` to use them, then run `streamdeck_ctrl --check-config`.",
+        audio_toggle: (!outputs.is_empty()).then_some(StarterToggle { outputs }),
+        launchers,
+        more_launchers,
+    };
+    let mut json = serde_json::to_string_pretty(&config)?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Writes `starter_config` to `path`, which must not exist yet unless
+/// `force` is set.
+pub fn write_starter_config(
+    path: &Path,
+    sinks: &[SinkInfo],
+    entries: &[DesktopEntry],
+    force: bool,
+) -> Result<()> {
+    let contents = starter_config(sinks, entries)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create config directory {}", dir.display()))?;
+    }
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .create_new(!force)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            bail!(
+                "refusing to overwrite existing configuration at {}",
+                path.display()
+            );
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to create {}", path.display()));
+        }
+    };
+    file.write_all(contents.as_bytes()).with_context(|| {
+        format!(
+            "failed to write starter configuration to {}",
+            path.display()
+        )
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ConfigFingerprint {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+// Tracks which configuration file `load_settings` would pick and its mtime, so
+// creating, editing, or deleting a candidate file is noticed on the next poll.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    candidates: Vec<PathBuf>,
+    fingerprint: Option<ConfigFingerprint>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        Self::with_candidates(default_config_paths())
+    }
+
+    fn with_candidates(candidates: Vec<PathBuf>) -> Self {
+        let fingerprint = fingerprint(&candidates);
+        Self {
+            candidates,
+            fingerprint,
+        }
+    }
+
+    pub fn poll_changed(&mut self) -> bool {
+        let current = fingerprint(&self.candidates);
+        if current == self.fingerprint {
+            return false;
+        }
+        self.fingerprint = current;
+        true
+    }
+}
+
+fn fingerprint(candidates: &[PathBuf]) -> Option<ConfigFingerprint> {
+    candidates.iter().find_map(|path| {
+        let metadata = fs::metadata(path).ok()?;
+        Some(ConfigFingerprint {
+            path: path.clone(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    })
+}
+
 pub fn default_config_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
@@ -131,15 +1073,7 @@ pub fn default_config_paths() -> Vec<PathBuf> {
         paths.push(manifest_dir.join("config").join(name));
     }
 
-    if let Some(xdg) = env::var_os("XDG_CONFIG_HOME") {
-        let base = PathBuf::from(xdg).join("streamdeck_ctrl");
-        for name in &candidate_names {
-            paths.push(base.join(name));
-        }
-    }
-
-    if let Some(home) = env::var_os("HOME") {
-        let base = PathBuf::from(home).join(".config/streamdeck_ctrl");
+    for base in user_config_dirs() {
         for name in &candidate_names {
             paths.push(base.join(name));
         }
@@ -161,6 +1095,27 @@ pub fn default_config_paths() -> Vec<PathBuf> {
     paths
 }
 
+/// Where a new configuration goes: the first per-user file
+/// `default_config_paths` looks for.
+pub fn user_config_path() -> Option<PathBuf> {
+    user_config_dirs()
+        .into_iter()
+        .next()
+        .map(|dir| dir.join("stream-deck.json"))
+}
+
+// `XDG_CONFIG_HOME` first, then `~/.config`.
+fn user_config_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(xdg) = env::var_os("XDG_CONFIG_HOME") {
+        dirs.push(PathBuf::from(xdg).join("streamdeck_ctrl"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".config/streamdeck_ctrl"));
+    }
+    dirs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,9 +1136,24 @@ mod tests {
                         {"description": "Headset"}
                     ]
                 },
+                "mic_mute": {"button_index": 2},
+                "dnd": {"button_index": 3},
+                "vpn": {"button_index": 7, "connection": "wg0"},
+                "capture": {"screenshot_button": 8, "record_button": 9},
+                "brightness": {
+                    "multi_monitor": true,
+                    "cycle_button": 5,
+                    "night_start": "22:30",
+                    "night_end": "7:00"
+                },
                 "now_playing_player": "spotify,%any",
+                "timer_sound": "/usr/share/sounds/bell.oga",
+                "timer_mode": "pomodoro",
                 "launchers": [
                     {"button_index": 4, "desktop_file": "/tmp/app.desktop"}
+                ],
+                "commands": [
+                    {"button_index": 6, "command": ["systemctl", "suspend"], "confirm": true}
                 ]
             }"#,
         )
@@ -194,13 +1164,336 @@ mod tests {
                 .unwrap();
 
         assert!(settings.audio_toggle.is_some());
+        assert_eq!(
+            settings.mic_mute.as_ref().map(|mic| mic.button_index),
+            Some(2)
+        );
+        assert_eq!(settings.dnd.as_ref().map(|dnd| dnd.button_index), Some(3));
+        let vpn = settings.vpn.as_ref().unwrap();
+        assert_eq!(vpn.button_index, 7);
+        assert_eq!(vpn.connection.as_deref(), Some("wg0"));
+        let capture = settings.capture.as_ref().unwrap();
+        assert_eq!(capture.screenshot_button, Some(8));
+        assert_eq!(capture.record_button, Some(9));
+        assert!(capture.record_command.is_none());
+        let brightness = settings.brightness.as_ref().unwrap();
+        assert!(brightness.multi_monitor);
+        assert_eq!(brightness.cycle_button, Some(5));
+        assert_eq!(
+            brightness.night_start,
+            Some(ClockTime {
+                hour: 22,
+                minute: 30
+            })
+        );
+        assert_eq!(
+            brightness.night_end.map(|end| end.to_string()).as_deref(),
+            Some("07:00")
+        );
         assert_eq!(settings.now_playing_player.as_deref(), Some("spotify,%any"));
+        assert_eq!(
+            settings.timer_sound.as_deref(),
+            Some(Path::new("/usr/share/sounds/bell.oga"))
+        );
+        assert_eq!(settings.timer_mode, Some(TimerMode::Pomodoro));
         assert_eq!(settings.launchers.len(), 1);
         assert_eq!(settings.launchers[0].button_index, 4);
         assert_eq!(
             settings.launchers[0].desktop_file,
             PathBuf::from("/tmp/app.desktop")
         );
+        assert_eq!(settings.commands.len(), 1);
+        assert_eq!(settings.commands[0].button_index, 6);
+        assert!(settings.commands[0].confirm);
+    }
+
+    fn applied(contents: &str) -> AppConfig {
+        let mut config = AppConfig::default();
+        parse_config(contents).unwrap().app.apply(&mut config);
+        config
+    }
+
+    #[test]
+    fn partial_app_section_overrides_only_listed_fields() {
+        let defaults = AppConfig::default();
+        assert_eq!(
+            format!("{:?}", applied(r#"{"launchers": []}"#)),
+            format!("{defaults:?}")
+        );
+
+        let config = applied(
+            r#"{
+                "app": {
+                    "brightness_step_percent": 10,
+                    "monitor_bus": 7,
+                    "locked_brightness": 0,
+                    "volume_default": 40,
+                    "volume_hold_command": "pavucontrol --tab=3",
+                    "brightness_default": 70,
+                    "state_file": "/tmp/deck-state.json",
+                    "metrics_address": "127.0.0.1:9835",
+                    "progress_gradient": ["1e5aa0", "50c8ff"],
+                    "pomodoro": {"work_secs": 3000},
+                    "hardware": {
+                        "device_brightness": 70,
+                        "idle_dim_secs": 300,
+                        "animate_min_delta_percent": 25
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(config.brightness_step_percent, 10);
+        assert_eq!(config.monitor_bus, Some(7));
+        assert_eq!(config.locked_brightness, Some(0));
+        assert_eq!(config.volume_default, Some(40));
+        assert_eq!(
+            config.volume_hold_command,
+            Some(CommandLine::Text("pavucontrol --tab=3".into()))
+        );
+        assert_eq!(config.brightness_default, Some(70));
+        assert_eq!(
+            config.state_file.as_deref(),
+            Some(Path::new("/tmp/deck-state.json"))
+        );
+        assert_eq!(
+            applied(r#"{"app": {"state_file": false}}"#).state_file,
+            None
+        );
+        assert_eq!(config.metrics_address.as_deref(), Some("127.0.0.1:9835"));
+        assert_eq!(
+            config.progress_gradient,
+            Some(ProgressStyle::Gradient {
+                from: [0x1e, 0x5a, 0xa0],
+                to: [0x50, 0xc8, 0xff],
+            })
+        );
+        assert_eq!(config.hardware.device_brightness, 70);
+        assert_eq!(
+            config
+                .hardware
+                .value_animation
+                .map(|animation| animation.min_delta),
+            Some(0.25)
+        );
+        assert_eq!(
+            applied(r#"{"app": {"hardware": {"animate_values": false}}}"#)
+                .hardware
+                .value_animation,
+            None
+        );
+        assert_eq!(
+            config.hardware.idle_dim,
+            Some(IdleDim {
+                after: Duration::from_secs(300),
+                brightness: DEFAULT_IDLE_DIM_BRIGHTNESS,
+            })
+        );
+        assert_eq!(
+            config.pomodoro,
+            PomodoroConfig {
+                work_secs: 3000,
+                ..PomodoroConfig::default()
+            }
+        );
+
+        assert_eq!(config.volume_step_percent, defaults.volume_step_percent);
+        assert_eq!(config.brightness_min, defaults.brightness_min);
+        assert_eq!(config.brightness_max, defaults.brightness_max);
+        assert_eq!(config.timer_default_secs, defaults.timer_default_secs);
+        assert_eq!(config.pulse_sink, defaults.pulse_sink);
+        assert_eq!(config.hardware.serial, defaults.hardware.serial);
+        assert_eq!(config.long_press_ms, defaults.long_press_ms);
+    }
+
+    #[test]
+    fn out_of_range_app_settings_are_clamped() {
+        let config = applied(
+            r#"{
+                "app": {
+                    "volume_step_percent": 0,
+                    "command_timeout_ms": 0,
+                    "brightness_min": 80,
+                    "brightness_max": 60,
+                    "brightness_night": 120,
+                    "volume_default": 151,
+                    "brightness_default": 101,
+                    "timer_min_secs": 600,
+                    "timer_max_secs": 1200,
+                    "timer_default_secs": 60,
+                    "pulse_sink": "alsa_output.usb",
+                    "progress_gradient": ["1e5aa0", "blue"],
+                    "hardware": {
+                        "serial": "A1B2C3",
+                        "device_brightness": 150,
+                        "idle_dim_secs": 0,
+                        "idle_dim_brightness": 101,
+                        "animate_min_delta_percent": 150
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(config.volume_step_percent, 1);
+        assert_eq!(config.command_timeout_ms, 1);
+        assert_eq!((config.brightness_min, config.brightness_max), (59, 60));
+        assert_eq!(config.brightness_night, 60);
+        assert_eq!(config.volume_default, Some(150));
+        assert_eq!(config.brightness_default, Some(100));
+        assert_eq!((config.timer_min_secs, config.timer_max_secs), (600, 1200));
+        assert_eq!(config.timer_default_secs, 600);
+        assert_eq!(config.pulse_sink.as_deref(), Some("alsa_output.usb"));
+        assert_eq!(config.progress_gradient, None);
+        assert_eq!(config.hardware.serial.as_deref(), Some("A1B2C3"));
+        assert_eq!(config.hardware.device_brightness, 100);
+        assert_eq!(config.hardware.idle_dim, None);
+        assert_eq!(
+            config
+                .hardware
+                .value_animation
+                .map(|animation| animation.min_delta),
+            Some(1.0)
+        );
+
+        let config = applied(
+            r#"{
+                "app": {
+                    "timer_min_secs": 900,
+                    "timer_max_secs": 600,
+                    "pomodoro": {"work_secs": 0, "cycles": 0},
+                    "hardware": {"idle_dim_secs": 300, "idle_dim_brightness": 101}
+                }
+            }"#,
+        );
+        assert_eq!((config.timer_min_secs, config.timer_max_secs), (600, 600));
+        assert_eq!((config.pomodoro.work_secs, config.pomodoro.cycles), (1, 1));
+        assert_eq!(
+            config.hardware.idle_dim.map(|dim| dim.brightness),
+            Some(100)
+        );
+
+        assert!(parse_config(r#"{"app": {"brightness_min": "low"}}"#).is_err());
+    }
+
+    #[test]
+    fn device_list_drops_duplicate_roles() {
+        let config = applied(
+            r#"{
+                "app": {
+                    "hardware": {
+                        "devices": [
+                            {"serial": "PLUS1", "role": "main"},
+                            {"role": "keys"},
+                            {"serial": "MK2", "role": "main"}
+                        ]
+                    }
+                },
+                "launchers": [{"button_index": 2, "desktop_file": "/tmp/a.desktop", "device": "keys"}]
+            }"#,
+        );
+        assert_eq!(
+            config.hardware.devices,
+            [
+                DeviceConfig {
+                    serial: Some("PLUS1".to_string()),
+                    role: "main".to_string(),
+                },
+                DeviceConfig {
+                    serial: None,
+                    role: "keys".to_string(),
+                },
+            ]
+        );
+        assert!(applied("{}").hardware.devices.is_empty());
+    }
+
+    #[test]
+    fn theme_merges_encoder_overrides_over_the_global_theme() {
+        let structured = parse_config(
+            r##"{"theme": {
+                "background": "#101018",
+                "border": false,
+                "use_ttf": true,
+                "encoders": {"2": {"progress": "#e04040", "border": true}}
+            }}"##,
+        )
+        .unwrap();
+        let segments = structured.theme.unwrap().segments;
+        assert_eq!(segments[0].background, [0x10, 0x10, 0x18]);
+        assert!(!segments[0].border);
+        assert!(segments[0].use_ttf && segments[2].use_ttf);
+        assert_eq!(segments[0].progress, SegmentTheme::default().progress);
+        assert_eq!(segments[2].background, [0x10, 0x10, 0x18]);
+        assert_eq!(segments[2].progress, [0xe0, 0x40, 0x40]);
+        assert!(segments[2].border);
+
+        let err = parse_config(r##"{"theme": {"title": "#12345"}}"##).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("expected a colour like"), "{message}");
+        assert!(message.contains("`theme.title`"), "{message}");
+        let err =
+            parse_config(r##"{"theme": {"encoders": {"1": {"progress": "red"}}}}"##).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("`theme.encoders.1.progress`"),
+            "{err:#}"
+        );
+        assert!(parse_config(r##"{"theme": {"encoders": {"4": {"value": "#ffffff"}}}}"##).is_err());
+        assert!(parse_config(r#"{"theme": {"encoders": {"one": {}}}}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_or_half_night_schedules() {
+        assert!("24:00".parse::<ClockTime>().is_err());
+        assert!("7h30".parse::<ClockTime>().is_err());
+        assert!(
+            parse_config(r#"{"brightness": {"night_start": "22:61", "night_end": "07:00"}}"#)
+                .is_err()
+        );
+        assert!(parse_config(r#"{"brightness": {"night_start": "22:00"}}"#).is_err());
+    }
+
+    #[test]
+    fn pages_extend_the_top_level_layout() {
+        let structured = parse_config(
+            r#"{
+                "launchers": [{"button_index": 1, "desktop_file": "/tmp/one.desktop"}],
+                "page_switch_button": 7,
+                "pages": [
+                    {"launchers": [{"button_index": 1, "desktop_file": "/tmp/two.desktop"}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let settings = StreamDeckSettings {
+            path: PathBuf::from("/tmp/stream-deck.json"),
+            audio_toggle: structured.audio_toggle,
+            mic_mute: None,
+            dnd: None,
+            vpn: None,
+            capture: None,
+            brightness: None,
+            app_volume: None,
+            system_stats: None,
+            weather: None,
+            clock: None,
+            theme: None,
+            now_playing_player: None,
+            timer_sound: None,
+            timer_mode: None,
+            app: AppOverrides::default(),
+            launchers: structured.launchers,
+            commands: structured.commands,
+            snippets: structured.snippets,
+            bluetooth: structured.bluetooth,
+            pages: structured.pages,
+            page_switch_button: structured.page_switch_button,
+        };
+
+        assert_eq!(settings.page_count(), 2);
+        assert_eq!(settings.page_switch_button, Some(7));
+        let desktop_file = |page| settings.page(page).1[0].desktop_file.clone();
+        assert_eq!(desktop_file(0), PathBuf::from("/tmp/one.desktop"));
+        assert_eq!(desktop_file(1), PathBuf::from("/tmp/two.desktop"));
+
+        assert!(parse_config(r#"{"pages": [{"launchers": []}]}"#).is_err());
     }
 
     #[test]
@@ -227,4 +1520,69 @@ mod tests {
         assert!(settings.now_playing_player.is_none());
         assert!(settings.launchers.is_empty());
     }
+
+    fn sink(name: &str, description: &str) -> SinkInfo {
+        SinkInfo {
+            id: None,
+            name: name.into(),
+            description: Some(description.into()),
+            icon_name: None,
+            form_factor: None,
+        }
+    }
+
+    #[test]
+    fn starter_config_parses_and_is_not_overwritten() {
+        let dir = tempdir().unwrap();
+        let app = dir.path().join("app.desktop");
+        fs::write(
+            &app,
+            "[Desktop Entry]\nName=App\nExec=/usr/bin/true\nType=Application\n",
+        )
+        .unwrap();
+        let entries: Vec<DesktopEntry> = (0..3)
+            .map(|_| DesktopEntry::from_path(&app).unwrap())
+            .collect();
+        let sinks = [
+            sink("alsa_output.pci.analog-stereo", "Built-in Audio"),
+            sink("alsa_output.usb.iec958-stereo", "USB Headset"),
+        ];
+        let path = dir.path().join("streamdeck_ctrl").join("stream-deck.json");
+
+        write_starter_config(&path, &sinks, &entries, false).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("\"_more_launchers\""));
+        let settings = load_settings_from(&path).unwrap();
+        let (toggle, launchers) = settings.page(0);
+        assert_eq!(toggle.map(|toggle| toggle.outputs.len()), Some(2));
+        let keys: Vec<u8> = launchers.iter().map(|l| l.button_index).collect();
+        assert_eq!(keys, [4, 5]);
+
+        let err = write_starter_config(&path, &[], &[], false).unwrap_err();
+        assert!(err.to_string().contains("refusing to overwrite"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        write_starter_config(&path, &[], &[], true).unwrap();
+        let settings = load_settings_from(&path).unwrap();
+        assert!(settings.audio_toggle.is_none());
+        assert!(settings.launchers.is_empty());
+    }
+
+    #[test]
+    fn watcher_detects_creation_edits_and_removal() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stream-deck.json");
+        let mut watcher = ConfigWatcher::with_candidates(vec![path.clone()]);
+        assert!(!watcher.poll_changed());
+
+        fs::write(&path, "{}").unwrap();
+        assert!(watcher.poll_changed());
+        assert!(!watcher.poll_changed());
+
+        fs::write(&path, r#"{"launchers": []}"#).unwrap();
+        assert!(watcher.poll_changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.poll_changed());
+        assert!(!watcher.poll_changed());
+    }
 }
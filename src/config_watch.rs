@@ -0,0 +1,127 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, warn};
+
+// Editors often write a file twice, or write a copy and rename it over.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Asks for a reload as soon as the configuration file is written, instead of
+/// waiting for the next poll of its mtime.
+///
+/// The directory holding the file is watched rather than the file itself, so
+/// the watch survives the file being deleted, recreated or renamed over.
+pub struct ConfigFileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigFileWatcher {
+    /// Sends one message on `reload` for each burst of changes to `path`.
+    pub fn start(path: &Path, reload: Sender<()>) -> Result<Self> {
+        let file_name = path
+            .file_name()
+            .context("configuration path has no file name")?
+            .to_owned();
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+
+        let (changes_tx, changes_rx) = crossbeam_channel::unbounded();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+                Ok(event) if touches(&event, &file_name) => {
+                    let _ = changes_tx.send(());
+                }
+                Ok(_) => {}
+                Err(err) => warn!(error = %err, "configuration file watch failed"),
+            })
+            .context("failed to create configuration file watcher")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", dir.display()))?;
+
+        thread::Builder::new()
+            .name("config-watch".into())
+            .spawn(move || debounce(&changes_rx, &reload, DEBOUNCE))
+            .context("failed to start configuration watch thread")?;
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+// Reads do not count, or loading the file after a change would trigger
+// another reload.
+fn touches(event: &Event, file_name: &OsStr) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|path| path.file_name() == Some(file_name))
+}
+
+// Returns once the watcher, and with it the sending side, is dropped.
+fn debounce(changes: &Receiver<()>, reload: &Sender<()>, quiet: Duration) {
+    while changes.recv().is_ok() {
+        while changes.recv_timeout(quiet).is_ok() {}
+        debug!("configuration file changed");
+        // A full channel already holds a reload that will read this change.
+        if let Err(TrySendError::Disconnected(_)) = reload.try_send(()) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, ModifyKind};
+    use std::path::PathBuf;
+
+    #[test]
+    fn bursts_of_changes_ask_for_one_reload() {
+        let (changes_tx, changes_rx) = crossbeam_channel::unbounded();
+        let (reload_tx, reload_rx) = crossbeam_channel::bounded(4);
+        for _ in 0..3 {
+            changes_tx.send(()).unwrap();
+        }
+        drop(changes_tx);
+
+        debounce(&changes_rx, &reload_tx, Duration::from_millis(10));
+        assert_eq!(reload_rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn only_writes_to_the_configuration_file_count() {
+        let name = OsStr::new("stream-deck.json");
+        let event = |kind, path: &str| Event::new(kind).add_path(PathBuf::from(path));
+
+        assert!(touches(
+            &event(
+                EventKind::Modify(ModifyKind::Any),
+                "/home/me/.config/stream-deck.json"
+            ),
+            name
+        ));
+        assert!(!touches(
+            &event(
+                EventKind::Modify(ModifyKind::Any),
+                "/home/me/.config/other.json"
+            ),
+            name
+        ));
+        assert!(!touches(
+            &event(
+                EventKind::Access(AccessKind::Any),
+                "/home/me/.config/stream-deck.json"
+            ),
+            name
+        ));
+    }
+}
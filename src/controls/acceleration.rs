@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const ACCELERATION_WINDOW: Duration = Duration::from_millis(200);
+const ACCELERATION_DETENTS: u32 = 5;
+const ACCELERATION_FACTOR: i32 = 3;
+
+pub trait Clock: Send {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Scales encoder turns once more than `ACCELERATION_DETENTS` detents land
+/// within `ACCELERATION_WINDOW`.
+///
+/// Only turns inside the window count, so acceleration stops as soon as the
+/// rate drops; reversing direction starts over at the normal step.
+#[derive(Debug, Clone)]
+pub struct TurnAcceleration<C: Clock = SystemClock> {
+    clock: C,
+    recent: VecDeque<(Instant, u32)>,
+    direction: i32,
+}
+
+impl<C: Clock> TurnAcceleration<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            recent: VecDeque::new(),
+            direction: 0,
+        }
+    }
+
+    /// Records a turn and returns the factor to apply to its step.
+    pub fn factor(&mut self, delta: i32) -> i32 {
+        if delta == 0 {
+            return 1;
+        }
+        let now = self.clock.now();
+        if delta.signum() != self.direction {
+            self.recent.clear();
+            self.direction = delta.signum();
+        }
+        while self
+            .recent
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > ACCELERATION_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((now, delta.unsigned_abs()));
+
+        let detents: u32 = self.recent.iter().map(|(_, detents)| detents).sum();
+        if detents > ACCELERATION_DETENTS {
+            ACCELERATION_FACTOR
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone)]
+    pub struct FakeClock {
+        now: Arc<Mutex<Instant>>,
+    }
+
+    impl Default for FakeClock {
+        fn default() -> Self {
+            Self {
+                now: Arc::new(Mutex::new(Instant::now())),
+            }
+        }
+    }
+
+    impl FakeClock {
+        pub fn advance(&self, by: Duration) {
+            *self.now.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn fast_turns_accelerate_and_slow_turns_do_not() {
+        let clock = FakeClock::default();
+        let mut acceleration = TurnAcceleration::new(clock.clone());
+
+        let factors: Vec<i32> = (0..7)
+            .map(|_| {
+                clock.advance(Duration::from_millis(30));
+                acceleration.factor(1)
+            })
+            .collect();
+        assert_eq!(factors, vec![1, 1, 1, 1, 1, 3, 3]);
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(acceleration.factor(1), 1);
+
+        for _ in 0..6 {
+            clock.advance(Duration::from_millis(100));
+            assert_eq!(acceleration.factor(1), 1);
+        }
+    }
+
+    #[test]
+    fn batched_deltas_count_every_detent_and_reversal_resets() {
+        let clock = FakeClock::default();
+        let mut acceleration = TurnAcceleration::new(clock.clone());
+        assert_eq!(acceleration.factor(4), 1);
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(acceleration.factor(2), 3);
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(acceleration.factor(-1), 1);
+    }
+}
@@ -0,0 +1,381 @@
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+use crate::system::audio::{SinkInput, SinkInputBackend};
+use crate::system::worker::BackendWorker;
+
+use super::{EncoderController, Tickable};
+
+const MAX_APP_VOLUME_PERCENT: f32 = 100.0;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppVolumeConfig {
+    /// Matched against each stream's `application.name` and executable,
+    /// ignoring case.
+    pub application: String,
+    /// Segment title; defaults to `application`.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Volume and mute of one application's playback streams. The streams are
+/// looked up again on every tick, since their indices change whenever the
+/// application restarts playback; all of them are adjusted together and the
+/// newest one's level is shown.
+pub struct AppVolumeController<B, D>
+where
+    B: SinkInputBackend,
+    D: DisplayPipeline,
+{
+    backend: BackendWorker<B, StreamReport>,
+    display: D,
+    encoder: EncoderId,
+    application: String,
+    title: String,
+    step: i32,
+    reading: StreamReading,
+    shown: Option<StreamReading>,
+}
+
+enum StreamReport {
+    Read(Result<StreamReading>),
+    Adjusted(Result<f32>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct StreamReading {
+    available: bool,
+    /// Indices of the application's streams, oldest first.
+    streams: Vec<u32>,
+    volume: f32,
+    muted: bool,
+}
+
+fn read_streams<B: SinkInputBackend>(backend: &B, application: &str) -> Result<StreamReading> {
+    if !backend.is_available() {
+        return Ok(StreamReading::default());
+    }
+    let mut streams: Vec<SinkInput> = backend
+        .list_sink_inputs()?
+        .into_iter()
+        .filter(|input| input.belongs_to(application))
+        .collect();
+    streams.sort_by_key(|input| input.index);
+    let (volume, muted) = streams
+        .last()
+        .map_or((0.0, false), |latest| (latest.volume, latest.muted));
+    Ok(StreamReading {
+        available: true,
+        streams: streams.iter().map(|input| input.index).collect(),
+        volume,
+        muted,
+    })
+}
+
+fn set_volume<B: SinkInputBackend>(
+    backend: &B,
+    streams: &[u32],
+    percent: f32,
+    unmute: bool,
+) -> Result<f32> {
+    for index in streams {
+        if unmute {
+            backend.set_sink_input_mute(*index, false)?;
+        }
+        backend.set_sink_input_volume(*index, percent)?;
+    }
+    match streams.last() {
+        Some(latest) => backend.get_sink_input_volume(*latest),
+        None => Ok(percent),
+    }
+}
+
+impl<B, D> AppVolumeController<B, D>
+where
+    B: SinkInputBackend,
+    D: DisplayPipeline,
+{
+    pub fn new(
+        config: &AppVolumeConfig,
+        backend: B,
+        display: D,
+        encoder: EncoderId,
+        step: i32,
+    ) -> Result<Self> {
+        let mut controller = Self {
+            backend: BackendWorker::inline(backend),
+            display,
+            encoder,
+            application: config.application.clone(),
+            title: config
+                .label
+                .clone()
+                .unwrap_or_else(|| config.application.clone()),
+            step: step.max(1),
+            reading: StreamReading::default(),
+            shown: None,
+        };
+        controller.request_reading()?;
+        controller.poll_reports()?;
+        controller.push_display()?;
+        Ok(controller)
+    }
+
+    /// Runs pactl on a worker thread from now on.
+    pub fn in_background(mut self) -> Result<Self> {
+        self.backend = self.backend.into_threaded("app-volume-pactl")?;
+        Ok(self)
+    }
+
+    fn request_reading(&mut self) -> Result<()> {
+        let application = self.application.clone();
+        self.backend
+            .submit(move |backend: &mut B| StreamReport::Read(read_streams(backend, &application)))
+    }
+
+    fn poll_reports(&mut self) -> Result<()> {
+        for report in self.backend.finished() {
+            match report {
+                StreamReport::Read(Ok(reading)) => self.reading = reading,
+                StreamReport::Read(Err(err)) => {
+                    warn!(
+                        error = %err,
+                        application = %self.application,
+                        "failed to read application streams"
+                    );
+                }
+                StreamReport::Adjusted(Ok(volume)) => self.reading.volume = volume,
+                // Most likely the stream ended in the meantime.
+                StreamReport::Adjusted(Err(err)) => {
+                    warn!(
+                        error = %err,
+                        application = %self.application,
+                        "failed to set application volume"
+                    );
+                    self.request_reading()?;
+                }
+            }
+        }
+        self.push_display()
+    }
+
+    fn push_display(&mut self) -> Result<()> {
+        if self.shown.as_ref() == Some(&self.reading) {
+            return Ok(());
+        }
+        let display = stream_display(&self.title, &self.reading);
+        self.display.update_encoder(self.encoder, display)?;
+        self.shown = Some(self.reading.clone());
+        Ok(())
+    }
+}
+
+fn stream_display(title: &str, reading: &StreamReading) -> EncoderDisplay {
+    if !reading.available {
+        return EncoderDisplay::new(title, "N/A")
+            .with_status("audio disabled")
+            .with_progress(0.0);
+    }
+    if reading.streams.is_empty() {
+        return EncoderDisplay::new(title, "--")
+            .with_status("no stream")
+            .with_progress(0.0);
+    }
+    let display = EncoderDisplay::new(title, format!("{:>3.0}%", reading.volume))
+        .with_progress(reading.volume / 100.0);
+    if reading.muted {
+        display.with_status("muted")
+    } else {
+        display
+    }
+}
+
+impl<B, D> EncoderController for AppVolumeController<B, D>
+where
+    B: SinkInputBackend,
+    D: DisplayPipeline,
+{
+    fn on_turn(&mut self, delta: i32) -> Result<()> {
+        self.poll_reports()?;
+        if delta == 0 || self.reading.streams.is_empty() {
+            return Ok(());
+        }
+        let target = (self.reading.volume + (delta * self.step) as f32)
+            .clamp(0.0, MAX_APP_VOLUME_PERCENT.max(self.reading.volume));
+        let streams = self.reading.streams.clone();
+        let unmute = self.reading.muted;
+        self.backend.submit(move |backend: &mut B| {
+            StreamReport::Adjusted(set_volume(backend, &streams, target, unmute))
+        })?;
+        self.reading.volume = target;
+        self.reading.muted = false;
+        self.push_display()?;
+        self.poll_reports()
+    }
+
+    fn on_press(&mut self) -> Result<()> {
+        self.poll_reports()?;
+        if self.reading.streams.is_empty() {
+            return Ok(());
+        }
+        let streams = self.reading.streams.clone();
+        let muted = !self.reading.muted;
+        let application = self.application.clone();
+        self.backend.submit(move |backend: &mut B| {
+            let toggled = streams
+                .iter()
+                .try_for_each(|index| backend.set_sink_input_mute(*index, muted));
+            StreamReport::Read(toggled.and_then(|()| read_streams(backend, &application)))
+        })?;
+        self.reading.muted = muted;
+        self.push_display()?;
+        self.poll_reports()
+    }
+}
+
+impl<B, D> Tickable for AppVolumeController<B, D>
+where
+    B: SinkInputBackend,
+    D: DisplayPipeline,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        self.poll_reports()?;
+        if !self.backend.is_busy() {
+            self.request_reading()?;
+        }
+        self.poll_reports()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::audio::tests::MockSinkInputBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct TestDisplay {
+        inner: Arc<Mutex<Vec<EncoderDisplay>>>,
+    }
+
+    impl DisplayPipeline for TestDisplay {
+        fn update_encoder(&self, _encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.inner.lock().unwrap().push(display);
+            Ok(())
+        }
+    }
+
+    impl TestDisplay {
+        fn last(&self) -> EncoderDisplay {
+            self.inner.lock().unwrap().last().unwrap().clone()
+        }
+    }
+
+    fn spotify() -> AppVolumeConfig {
+        AppVolumeConfig {
+            application: "Spotify".into(),
+            label: None,
+        }
+    }
+
+    #[test]
+    fn follows_the_application_across_restarts() {
+        let backend = MockSinkInputBackend::default();
+        let display = TestDisplay::default();
+        let mut controller = AppVolumeController::new(
+            &spotify(),
+            backend.clone(),
+            display.clone(),
+            EncoderId::Four,
+            5,
+        )
+        .unwrap();
+        assert_eq!(display.last().value, "--");
+        assert_eq!(display.last().status.as_deref(), Some("no stream"));
+        controller.on_turn(1).unwrap();
+        controller.on_press().unwrap();
+
+        backend.inputs.lock().unwrap().extend([
+            MockSinkInputBackend::stream(12, "spotify", 40.0),
+            MockSinkInputBackend::stream(13, "Firefox", 90.0),
+        ]);
+        controller.on_tick().unwrap();
+        assert_eq!(display.last().value, " 40%");
+        assert_eq!(display.last().title, "Spotify");
+        let updates = display.inner.lock().unwrap().len();
+        controller.on_tick().unwrap();
+        assert_eq!(display.inner.lock().unwrap().len(), updates);
+
+        // Restarting playback hands out a new index.
+        *backend.inputs.lock().unwrap() = vec![MockSinkInputBackend::stream(31, "spotify", 55.0)];
+        controller.on_tick().unwrap();
+        controller.on_turn(-2).unwrap();
+        assert_eq!(display.last().value, " 45%");
+        assert_eq!(backend.inputs.lock().unwrap()[0].volume, 45.0);
+
+        backend.inputs.lock().unwrap().clear();
+        controller.on_tick().unwrap();
+        assert_eq!(display.last().status.as_deref(), Some("no stream"));
+    }
+
+    #[test]
+    fn adjusts_and_mutes_every_stream_of_the_application() {
+        let backend = MockSinkInputBackend::default();
+        backend.inputs.lock().unwrap().extend([
+            MockSinkInputBackend::stream(40, "spotify", 70.0),
+            MockSinkInputBackend::stream(9, "spotify", 30.0),
+            MockSinkInputBackend::stream(41, "Firefox", 90.0),
+        ]);
+        let display = TestDisplay::default();
+        let mut controller = AppVolumeController::new(
+            &spotify(),
+            backend.clone(),
+            display.clone(),
+            EncoderId::Four,
+            5,
+        )
+        .unwrap();
+        assert_eq!(display.last().value, " 70%");
+
+        controller.on_press().unwrap();
+        assert_eq!(display.last().status.as_deref(), Some("muted"));
+        let muted: Vec<bool> = backend
+            .inputs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|input| input.muted)
+            .collect();
+        assert_eq!(muted, [true, true, false]);
+
+        // Turning unmutes, like the main volume encoder.
+        controller.on_turn(10).unwrap();
+        assert_eq!(display.last().value, "100%");
+        assert_eq!(display.last().status, None);
+        let levels: Vec<(f32, bool)> = backend
+            .inputs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|input| (input.volume, input.muted))
+            .collect();
+        assert_eq!(levels, [(100.0, false), (100.0, false), (90.0, false)]);
+    }
+
+    #[test]
+    fn shows_audio_disabled_without_pactl() {
+        let backend = MockSinkInputBackend {
+            unavailable: true,
+            ..Default::default()
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            AppVolumeController::new(&spotify(), backend, display.clone(), EncoderId::Four, 5)
+                .unwrap();
+        controller.on_turn(1).unwrap();
+        assert_eq!(display.last().value, "N/A");
+        assert_eq!(display.last().status.as_deref(), Some("audio disabled"));
+    }
+}
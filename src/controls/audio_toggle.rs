@@ -1,18 +1,28 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow, bail};
 use serde::Deserialize;
 use tracing::{info, warn};
 
+use crate::config::HexColor;
 use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::metrics::Metrics;
 use crate::system::audio_switch::{AudioSwitchBackend, PulseAudioSwitch, SinkInfo, SinkSelector};
+use crate::system::worker::BackendWorker;
 use crate::util::icons;
 
+use super::commands::CommandLine;
+use super::launcher::{ExecSpec, launch_exec};
+use super::volume::short_sink_label;
+
 const MATERIAL_ICON_TINT: [u8; 3] = [220, 235, 255];
 
 #[derive(Debug, Clone, Deserialize)]
@@ -21,6 +31,15 @@ pub struct AudioToggleConfig {
     pub button_index: Option<u8>,
     #[serde(default)]
     pub outputs: Vec<AudioOutputConfig>,
+    /// Run instead of switching when a toggle button is held for
+    /// `mixer_hold_ms`.
+    #[serde(default = "default_mixer_command")]
+    pub mixer_command: CommandLine,
+    #[serde(default = "default_mixer_hold_ms")]
+    pub mixer_hold_ms: u64,
+    /// Icon colours for every output; an output's own `tints` win.
+    #[serde(default)]
+    pub tints: StateTints,
 }
 
 #[derive(Debug, Clone)]
@@ -41,33 +60,199 @@ pub struct AudioOutputConfig {
     pub description: Option<String>,
     #[serde(default)]
     pub icon: Option<IconConfig>,
+    #[serde(default)]
+    pub volume: Option<u8>,
+    #[serde(default)]
+    pub show_label: bool,
+    #[serde(default)]
+    pub kind: AudioDeviceKind,
+    #[serde(default)]
+    pub tints: StateTints,
+    /// Run after the deck switches to this output.
+    #[serde(default)]
+    pub on_activate: Option<CommandLine>,
+    /// Run before the deck switches away from this output.
+    #[serde(default)]
+    pub on_deactivate: Option<CommandLine>,
+}
+
+/// Icon colours per output state; states left unset keep the defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct StateTints {
+    #[serde(default)]
+    pub active: Option<HexColor>,
+    #[serde(default)]
+    pub available: Option<HexColor>,
+    #[serde(default)]
+    pub unavailable: Option<HexColor>,
+    /// The active output while it is unavailable.
+    #[serde(default)]
+    pub degraded: Option<HexColor>,
+}
+
+impl StateTints {
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            active: self.active.or(fallback.active),
+            available: self.available.or(fallback.available),
+            unavailable: self.unavailable.or(fallback.unavailable),
+            degraded: self.degraded.or(fallback.degraded),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioDeviceKind {
+    #[default]
+    Sink,
+    Source,
+}
+
+impl AudioDeviceKind {
+    pub(super) fn noun(self) -> &'static str {
+        match self {
+            AudioDeviceKind::Sink => "sink",
+            AudioDeviceKind::Source => "source",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
-#[serde(untagged)]
+#[serde(try_from = "RawIconConfig")]
 pub enum IconConfig {
-    Material { material: MaterialIcon },
-    Path { path: String },
+    Material {
+        material: MaterialIcon,
+    },
+    Path {
+        path: String,
+    },
+    /// Base64 image data, bare or as a `data:` URI.
+    Inline {
+        data: String,
+    },
     Simple(MaterialIcon),
     File(String),
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
-#[serde(rename_all = "lowercase")]
+// Material names are checked here rather than by an untagged enum, whose
+// only error is that nothing matched.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawIconConfig {
+    Material { material: String },
+    Path { path: String },
+    Inline { data: String },
+    Text(String),
+}
+
+impl TryFrom<RawIconConfig> for IconConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawIconConfig) -> Result<Self> {
+        Ok(match raw {
+            RawIconConfig::Material { material } => IconConfig::Material {
+                material: material.parse()?,
+            },
+            RawIconConfig::Path { path } => IconConfig::Path { path },
+            RawIconConfig::Inline { data } => IconConfig::Inline { data },
+            // A bare string that is not an icon name is a file path.
+            RawIconConfig::Text(text) => match text.parse() {
+                Ok(material) => IconConfig::Simple(material),
+                Err(_) => IconConfig::File(text),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MaterialIcon {
     Monitor,
     Headphones,
+    Speaker,
+    Earbuds,
+    Tv,
+    Usb,
+    Mic,
+    MicOff,
+    Notifications,
+    NotificationsOff,
+    Terminal,
+    VpnKey,
+    PhotoCamera,
+    Videocam,
+    TextSnippet,
+    Bluetooth,
 }
 
-const ACTIVE_TINT: [u8; 3] = [0, 200, 150];
-const AVAILABLE_TINT: [u8; 3] = [120, 185, 255];
-const UNAVAILABLE_TINT: [u8; 3] = [110, 110, 125];
-const DEGRADED_TINT: [u8; 3] = [230, 170, 90];
+impl MaterialIcon {
+    const NAMES: [&str; 16] = [
+        "monitor",
+        "headphones",
+        "speaker",
+        "earbuds",
+        "tv",
+        "usb",
+        "mic",
+        "mic-off",
+        "notifications",
+        "notifications-off",
+        "terminal",
+        "vpn-key",
+        "photo-camera",
+        "videocam",
+        "text-snippet",
+        "bluetooth",
+    ];
+}
+
+impl FromStr for MaterialIcon {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        let icon = match name.replace('_', "-").as_str() {
+            "monitor" => MaterialIcon::Monitor,
+            "headphones" => MaterialIcon::Headphones,
+            "speaker" => MaterialIcon::Speaker,
+            "earbuds" => MaterialIcon::Earbuds,
+            "tv" => MaterialIcon::Tv,
+            "usb" => MaterialIcon::Usb,
+            "mic" => MaterialIcon::Mic,
+            "mic-off" | "micoff" => MaterialIcon::MicOff,
+            "notifications" => MaterialIcon::Notifications,
+            "notifications-off" | "notificationsoff" => MaterialIcon::NotificationsOff,
+            "terminal" => MaterialIcon::Terminal,
+            "vpn-key" | "vpnkey" | "vpn" => MaterialIcon::VpnKey,
+            "photo-camera" | "photocamera" | "screenshot" => MaterialIcon::PhotoCamera,
+            "videocam" => MaterialIcon::Videocam,
+            "text-snippet" | "textsnippet" | "text" => MaterialIcon::TextSnippet,
+            "bluetooth" => MaterialIcon::Bluetooth,
+            _ => bail!(
+                "unknown material icon {name:?}; expected one of {}",
+                Self::NAMES.join(", ")
+            ),
+        };
+        Ok(icon)
+    }
+}
+
+pub(super) const ACTIVE_TINT: [u8; 3] = [0, 200, 150];
+pub(super) const AVAILABLE_TINT: [u8; 3] = [120, 185, 255];
+pub(super) const UNAVAILABLE_TINT: [u8; 3] = [110, 110, 125];
+pub(super) const DEGRADED_TINT: [u8; 3] = [230, 170, 90];
 
 fn default_button_index() -> Option<u8> {
     Some(0)
 }
 
+fn default_mixer_command() -> CommandLine {
+    CommandLine::Text("pavucontrol".to_string())
+}
+
+fn default_mixer_hold_ms() -> u64 {
+    800
+}
+
 impl AudioToggleConfig {
     pub fn load_default() -> Result<Option<AudioToggleSettings>> {
         if let Some(settings) = crate::config::load_settings()? {
@@ -97,12 +282,36 @@ where
     B: AudioSwitchBackend,
     H: DisplayPipeline,
 {
-    backend: B,
+    backend: Arc<B>,
+    refresher: BackendWorker<Arc<B>, DeviceListing>,
     hardware: H,
     outputs: Vec<OutputEntry>,
     button_map: HashMap<u8, Vec<usize>>,
+    remembered_volumes: HashMap<String, u8>,
+    pressed: HashSet<u8>,
+    mixer: ExecSpec,
+    mixer_hold: Duration,
+    metrics: Arc<Metrics>,
+    icon_paths: IconPaths,
+    hooks: Box<dyn HookSpawner>,
+}
+
+/// Starts an output's `on_activate` / `on_deactivate` command.
+trait HookSpawner: Send {
+    fn spawn(&self, exec: &ExecSpec) -> Result<()>;
 }
 
+struct DetachedSpawner;
+
+impl HookSpawner for DetachedSpawner {
+    fn spawn(&self, exec: &ExecSpec) -> Result<()> {
+        launch_exec(exec, None, false)
+    }
+}
+
+/// Devices and the current default of each kind, read off the event loop.
+type DeviceListing = Vec<(AudioDeviceKind, Result<(Vec<SinkInfo>, Option<SinkInfo>)>)>;
+
 #[derive(Debug, Clone)]
 struct OutputEntry {
     profile: OutputProfile,
@@ -111,10 +320,20 @@ struct OutputEntry {
 
 #[derive(Debug, Clone)]
 struct OutputProfile {
+    kind: AudioDeviceKind,
     selector: SinkSelector,
     icons: OutputIcons,
+    /// Set when no icon is configured; follows the device's form factor once
+    /// it has been seen.
+    automatic_icon: Option<MaterialIcon>,
+    key_label: Option<String>,
+    tints: StateTints,
+    index: usize,
     label: String,
     button_index: u8,
+    volume: Option<u8>,
+    on_activate: Option<ExecSpec>,
+    on_deactivate: Option<ExecSpec>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -156,10 +375,16 @@ where
         }
 
         let fallback_button = config.button_index;
+        let tints = config.tints;
+        let mixer = config
+            .mixer_command
+            .to_exec()
+            .context("invalid audio toggle `mixer_command`")?;
 
         let mut outputs = Vec::with_capacity(config.outputs.len());
         for (index, entry) in config.outputs.iter().enumerate() {
-            let profile = OutputProfile::from_config(entry, fallback_button, index, icon_paths)?;
+            let profile =
+                OutputProfile::from_config(entry, fallback_button, index, tints, icon_paths)?;
             outputs.push(OutputEntry {
                 profile,
                 state: OutputState::default(),
@@ -174,41 +399,145 @@ where
                 .push(idx);
         }
 
+        let backend = Arc::new(backend);
         let mut controller = Self {
+            refresher: BackendWorker::inline(Arc::clone(&backend)),
             backend,
             hardware,
             outputs,
             button_map,
+            remembered_volumes: HashMap::new(),
+            pressed: HashSet::new(),
+            mixer,
+            mixer_hold: Duration::from_millis(config.mixer_hold_ms),
+            metrics: Arc::default(),
+            icon_paths: icon_paths.clone(),
+            hooks: Box::new(DetachedSpawner),
         };
         controller.initialise_icons()?;
         controller.refresh_state()?;
         Ok(controller)
     }
 
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Reads device state on a worker thread from now on. Switching still
+    /// runs on the caller, which needs the new default before it returns.
+    pub fn in_background(mut self) -> Result<Self> {
+        self.refresher = self.refresher.into_threaded("audio-toggle-pactl")?;
+        Ok(self)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.button_map.keys().copied()
+    }
+
+    fn owns_button(&self, button_index: u8) -> bool {
+        self.button_map
+            .get(&button_index)
+            .is_some_and(|indices| !indices.is_empty())
+    }
+
+    // Nothing happens until the release, which decides between switching and
+    // opening the mixer.
     pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
-        let Some(indices) = self.button_map.get(&button_index) else {
+        if !self.owns_button(button_index) {
             return Ok(false);
-        };
+        }
+        self.pressed.insert(button_index);
+        Ok(true)
+    }
 
-        if indices.is_empty() {
+    /// Switches on a short press and opens the mixer on one `held` for at
+    /// least `mixer_hold_ms`. Returns whether the button belongs to the
+    /// toggle.
+    pub fn on_button_released(&mut self, button_index: u8, held: Duration) -> Result<bool> {
+        if !self.owns_button(button_index) {
             return Ok(false);
         }
+        // A release without a press was begun before a page switch or reload.
+        if !self.pressed.remove(&button_index) {
+            return Ok(true);
+        }
+        if held >= self.mixer_hold {
+            self.open_mixer();
+        } else {
+            self.switch_button(button_index);
+        }
+        Ok(true)
+    }
+
+    fn open_mixer(&self) {
+        match launch_exec(&self.mixer, None, false) {
+            Ok(()) => info!(command = %self.mixer.program, "opened audio mixer"),
+            Err(err) => warn!(error = %err, "failed to open audio mixer"),
+        }
+    }
 
+    fn switch_button(&mut self, button_index: u8) {
+        let Some(indices) = self.button_map.get(&button_index) else {
+            return;
+        };
         let target_index = if indices.len() == 1 {
-            indices[0]
+            Some(indices[0])
         } else {
             self.select_next_in_group(indices)
         };
+        if let Some(target_index) = target_index {
+            self.switch_to(target_index);
+        }
+    }
 
+    /// Switches to the sink after the active one, across every configured button.
+    pub fn cycle_outputs(&mut self) {
+        let sinks: Vec<usize> = (0..self.outputs.len())
+            .filter(|idx| self.outputs[*idx].profile.kind == AudioDeviceKind::Sink)
+            .collect();
+        if sinks.is_empty() {
+            return;
+        }
+        if let Some(target_index) = self.select_next_in_group(&sinks) {
+            self.switch_to(target_index);
+        }
+    }
+
+    // The target's volume is applied before returning so the caller's
+    // follow-up volume refresh shows the new level. Hooks only run here, so
+    // a switch made outside the deck and picked up by a refresh runs none.
+    fn switch_to(&mut self, target_index: usize) {
+        let kind = self.outputs[target_index].profile.kind;
+        if kind == AudioDeviceKind::Sink {
+            self.remember_current_volume();
+        }
+        let previous = (0..self.outputs.len()).find(|idx| {
+            let entry = &self.outputs[*idx];
+            *idx != target_index && entry.profile.kind == kind && entry.state.active
+        });
+        if let Some(previous) = previous {
+            self.run_hook(previous, |profile| profile.on_deactivate.as_ref());
+        }
         let target = &self.outputs[target_index];
-        info!(target = %target.profile.label, "switching audio output");
-
-        match self
-            .backend
-            .set_default_sink(&target.profile.selector)
-            .with_context(|| format!("failed to set default sink to {}", target.profile.label))
-        {
-            Ok(_) => {
+        info!(target = %target.profile.label, kind = kind.noun(), "switching audio device");
+
+        let switched = match kind {
+            AudioDeviceKind::Sink => self.backend.set_default_sink(&target.profile.selector),
+            AudioDeviceKind::Source => self.backend.set_default_source(&target.profile.selector),
+        };
+        match switched.with_context(|| {
+            format!(
+                "failed to set default {} to {}",
+                kind.noun(),
+                target.profile.label
+            )
+        }) {
+            Ok(device) => {
+                if kind == AudioDeviceKind::Sink {
+                    self.restore_volume(target_index, &device.name);
+                }
+                self.run_hook(target_index, |profile| profile.on_activate.as_ref());
                 if let Err(err) = self.refresh_state() {
                     warn!(
                         error = %err,
@@ -222,6 +551,11 @@ where
                     target = %target.profile.label,
                     "failed to switch audio output"
                 );
+                if kind == AudioDeviceKind::Sink {
+                    self.metrics
+                        .sink_switch_failures
+                        .fetch_add(1, Ordering::Relaxed);
+                }
                 notify_switch_failure(&target.profile.label, &err);
                 if let Err(refresh_err) = self.refresh_state() {
                     warn!(
@@ -231,34 +565,89 @@ where
                 }
             }
         }
-
-        Ok(true)
     }
 
-    pub fn on_tick(&mut self) -> Result<()> {
-        self.refresh_state()
+    // A failing hook leaves the switch in place.
+    fn run_hook(&self, index: usize, hook: impl Fn(&OutputProfile) -> Option<&ExecSpec>) {
+        let profile = &self.outputs[index].profile;
+        let Some(exec) = hook(profile) else {
+            return;
+        };
+        if let Err(err) = self.hooks.spawn(exec) {
+            warn!(
+                error = %err,
+                output = %profile.label,
+                command = %exec.program,
+                "audio output hook failed"
+            );
+            notify(&format!("Hook for {} failed:\n{}", profile.label, err));
+        }
     }
 
-    fn select_next_in_group(&self, indices: &[usize]) -> usize {
-        if indices.len() <= 1 {
-            return indices[0];
+    fn restore_volume(&self, index: usize, sink_name: &str) {
+        let volume = self.outputs[index]
+            .profile
+            .volume
+            .or_else(|| self.remembered_volumes.get(sink_name).copied());
+        let restored = volume.map(|volume| self.backend.set_sink_volume(sink_name, volume));
+        if let Some(Err(err)) = restored {
+            warn!(error = %err, sink = %sink_name, "failed to restore output volume");
         }
+    }
 
-        let active_position = indices.iter().enumerate().find_map(|(pos, idx)| {
-            let active = self.outputs[*idx].state.active;
-            active.then_some(pos)
-        });
+    fn remember_current_volume(&mut self) {
+        let current = match self.backend.current_default_sink() {
+            Ok(Some(sink)) => sink,
+            Ok(None) => return,
+            Err(err) => {
+                warn!(error = %err, "failed to read current sink before switching");
+                return;
+            }
+        };
+        match self.backend.sink_volume(&current.name) {
+            Ok(Some(volume)) => {
+                self.remembered_volumes.insert(current.name, volume);
+            }
+            Ok(None) => {}
+            Err(err) => warn!(error = %err, sink = %current.name, "failed to read sink volume"),
+        }
+    }
 
-        if let Some(pos) = active_position {
-            let next = (pos + 1) % indices.len();
-            return indices[next];
+    // A listing still being read is not queued behind; finished ones are
+    // collected first so the worker is free for the next.
+    pub fn on_tick(&mut self) -> Result<()> {
+        let mut listings = self.refresher.finished();
+        if !self.refresher.is_busy() {
+            let kinds = self.device_kinds();
+            self.refresher
+                .submit(move |backend: &mut Arc<B>| read_devices(backend.as_ref(), &kinds))?;
+            listings.extend(self.refresher.finished());
         }
+        let mut outcome = Ok(());
+        for listing in listings {
+            if let Err(err) = self.apply_listing(listing) {
+                outcome = Err(err);
+            }
+        }
+        outcome
+    }
 
-        indices
+    // The first available entry after the active one, wrapping around;
+    // `None` when no other entry is available.
+    fn select_next_in_group(&self, indices: &[usize]) -> Option<usize> {
+        let active_position = indices
             .iter()
-            .copied()
-            .find(|idx| self.outputs[*idx].state.available)
-            .unwrap_or(indices[0])
+            .position(|idx| self.outputs[*idx].state.active);
+        let start = active_position.map_or(0, |pos| pos + 1);
+        let next = (0..indices.len())
+            .map(|offset| (start + offset) % indices.len())
+            .filter(|pos| Some(*pos) != active_position)
+            .map(|pos| indices[pos])
+            .find(|idx| self.outputs[*idx].state.available);
+        if next.is_none() {
+            warn!("no other audio output in the group is available; staying on the current one");
+        }
+        next
     }
 
     fn initialise_icons(&mut self) -> Result<()> {
@@ -268,44 +657,111 @@ where
         Ok(())
     }
 
+    // Reads on the caller; a listing from the worker that started earlier
+    // would be older than this one, so it is dropped.
     fn refresh_state(&mut self) -> Result<()> {
-        let sinks = self.backend.list_sinks()?;
-        let current = self.backend.current_default_sink()?;
+        self.refresher.cancel_pending();
+        let listing = read_devices(self.backend.as_ref(), &self.device_kinds());
+        self.apply_listing(listing)
+    }
+
+    fn device_kinds(&self) -> Vec<AudioDeviceKind> {
+        [AudioDeviceKind::Sink, AudioDeviceKind::Source]
+            .into_iter()
+            .filter(|kind| self.outputs.iter().any(|entry| entry.profile.kind == *kind))
+            .collect()
+    }
+
+    fn apply_listing(&mut self, listing: DeviceListing) -> Result<()> {
+        for (kind, devices) in listing {
+            let (devices, current) = devices?;
+            self.apply_devices(kind, &devices, current)?;
+        }
+        Ok(())
+    }
+
+    // Sinks and sources have separate defaults, so each entry is only compared
+    // against devices of its own kind.
+    fn apply_devices(
+        &mut self,
+        kind: AudioDeviceKind,
+        devices: &[SinkInfo],
+        current: Option<SinkInfo>,
+    ) -> Result<()> {
         let mut matched_default = false;
 
         for index in 0..self.outputs.len() {
             let profile = &self.outputs[index].profile;
-            let available = sinks.iter().any(|sink| profile.selector.matches(sink));
+            if profile.kind != kind {
+                continue;
+            }
+            let available = devices
+                .iter()
+                .any(|device| profile.selector.matches(device));
             let active = current
                 .as_ref()
-                .map(|sink| profile.selector.matches(sink))
+                .map(|device| profile.selector.matches(device))
                 .unwrap_or(false);
             if active {
                 matched_default = true;
             }
+            let icon_changed = self.resolve_automatic_icon(index, devices);
             let new_state = OutputState { available, active };
-            self.apply_state(index, new_state)?;
+            self.apply_state(index, new_state, icon_changed)?;
         }
 
-        if let Some(current_sink) = &current {
-            if !matched_default {
-                warn!(
-                    sink = %current_sink.name,
-                    "default sink not present in audio toggle configuration"
-                );
-            }
+        if let Some(current_device) = current.as_ref().filter(|_| !matched_default) {
+            warn!(
+                device = %current_device.name,
+                kind = kind.noun(),
+                "default device not present in audio toggle configuration"
+            );
         }
 
         Ok(())
     }
 
-    fn apply_state(&mut self, index: usize, new_state: OutputState) -> Result<()> {
+    // Returns whether the icon changed. A device that is away keeps the icon
+    // it was last seen with.
+    fn resolve_automatic_icon(&mut self, index: usize, devices: &[SinkInfo]) -> bool {
+        let profile = &self.outputs[index].profile;
+        let Some(current) = profile.automatic_icon else {
+            return false;
+        };
+        let Some(wanted) = devices
+            .iter()
+            .find(|device| profile.selector.matches(device))
+            .and_then(device_icon)
+            .filter(|wanted| *wanted != current)
+        else {
+            return false;
+        };
+        match load_material_icon(wanted, &self.icon_paths) {
+            Ok(base) => {
+                let profile = &mut self.outputs[index].profile;
+                profile.set_base_icon(base);
+                profile.automatic_icon = Some(wanted);
+                true
+            }
+            Err(err) => {
+                warn!(error = %err, output = %profile.label, "failed to load output icon");
+                false
+            }
+        }
+    }
+
+    fn apply_state(
+        &mut self,
+        index: usize,
+        new_state: OutputState,
+        icon_changed: bool,
+    ) -> Result<()> {
         let entry = self
             .outputs
             .get_mut(index)
             .ok_or_else(|| anyhow!("output index {} out of bounds", index))?;
 
-        if entry.state == new_state {
+        if entry.state == new_state && !icon_changed {
             return Ok(());
         }
 
@@ -314,13 +770,36 @@ where
     }
 
     fn push_icon(&self, index: usize) -> Result<()> {
-        let entry = self
+        let button_index = self
             .outputs
             .get(index)
-            .ok_or_else(|| anyhow!("output index {} out of bounds", index))?;
-        let icon = entry.profile.icons.icon(entry.state);
+            .ok_or_else(|| anyhow!("output index {} out of bounds", index))?
+            .profile
+            .button_index;
         self.hardware
-            .update_button_icon(entry.profile.button_index, Some(icon))
+            .update_button_icon(button_index, Some(self.button_icon(index)))
+    }
+
+    // A key shared by several outputs shows the active one, or else the
+    // first, badged with its position in the group.
+    fn button_icon(&self, index: usize) -> ButtonImage {
+        let entry = &self.outputs[index];
+        let group = self
+            .button_map
+            .get(&entry.profile.button_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if group.len() <= 1 {
+            return entry.profile.icons.icon(entry.state);
+        }
+        let position = group
+            .iter()
+            .position(|idx| self.outputs[*idx].state.active)
+            .unwrap_or(0);
+        let shown = &self.outputs[group[position]];
+        let mut icon = shown.profile.icons.icon(shown.state);
+        icon.badge = Some(format!("{}/{}", position + 1, group.len()));
+        icon
     }
 
     #[cfg(test)]
@@ -343,7 +822,8 @@ where
             PulseAudioSwitch::new(),
             hardware,
             &icon_paths,
-        )
+        )?
+        .in_background()
     }
 }
 
@@ -352,6 +832,7 @@ impl OutputProfile {
         config: &AudioOutputConfig,
         fallback_button: Option<u8>,
         index: usize,
+        tints: StateTints,
         icon_paths: &IconPaths,
     ) -> Result<Self> {
         let selector = config.selector()?;
@@ -361,25 +842,88 @@ impl OutputProfile {
                 index
             )
         })?;
+        // Only shown until the device turns up and names its form factor.
         let fallback_icon = match index {
             0 => MaterialIcon::Monitor,
             _ => MaterialIcon::Headphones,
         };
         let mut base_icon = load_icon_from_config(config.icon.as_ref(), fallback_icon, icon_paths)?;
-        base_icon.tint = None;
         let label = config.label();
-        let icons = OutputIcons::from_base(&base_icon, button_index, index);
+        if config.kind == AudioDeviceKind::Source && config.volume.is_some() {
+            warn!(output = %label, "ignoring `volume` on an audio source entry");
+        }
+        let tints = config.tints.or(tints);
+        let key_label = config.show_label.then(|| config.display_label());
+        let on_activate = config
+            .on_activate
+            .as_ref()
+            .map(CommandLine::to_exec)
+            .transpose()
+            .with_context(|| format!("invalid `on_activate` for audio output {label}"))?;
+        let on_deactivate = config
+            .on_deactivate
+            .as_ref()
+            .map(CommandLine::to_exec)
+            .transpose()
+            .with_context(|| format!("invalid `on_deactivate` for audio output {label}"))?;
+        base_icon.tint = None;
+        base_icon.label = key_label.clone().or(base_icon.label);
         Ok(Self {
+            kind: config.kind,
             selector,
-            icons,
+            icons: OutputIcons::from_base(&base_icon, button_index, index, tints),
+            automatic_icon: config.icon.is_none().then_some(fallback_icon),
+            key_label,
+            tints,
+            index,
             label,
             button_index,
+            volume: config.volume,
+            on_activate,
+            on_deactivate,
         })
     }
+
+    fn set_base_icon(&mut self, mut base: ButtonImage) {
+        base.tint = None;
+        base.label = self.key_label.clone().or(base.label);
+        self.icons = OutputIcons::from_base(&base, self.button_index, self.index, self.tints);
+    }
+}
+
+// PulseAudio leaves `device.form_factor` unset for most cards, so the icon
+// name is the fallback.
+fn device_icon(device: &SinkInfo) -> Option<MaterialIcon> {
+    let by_form_factor = device
+        .form_factor
+        .as_deref()
+        .and_then(|form_factor| match form_factor {
+            "headset" | "headphone" | "hands-free" | "handset" => Some(MaterialIcon::Headphones),
+            "speaker" | "hifi" | "portable" | "car" | "internal" | "computer" => {
+                Some(MaterialIcon::Speaker)
+            }
+            "tv" | "hdmi" => Some(MaterialIcon::Tv),
+            "microphone" | "webcam" => Some(MaterialIcon::Mic),
+            _ => None,
+        });
+    by_form_factor.or_else(|| {
+        let icon_name = device.icon_name.as_deref()?;
+        [
+            ("headset", MaterialIcon::Headphones),
+            ("headphone", MaterialIcon::Headphones),
+            ("video-display", MaterialIcon::Monitor),
+            ("hdmi", MaterialIcon::Tv),
+            ("speaker", MaterialIcon::Speaker),
+            ("usb", MaterialIcon::Usb),
+        ]
+        .into_iter()
+        .find(|(hint, _)| icon_name.contains(hint))
+        .map(|(_, icon)| icon)
+    })
 }
 
 impl AudioOutputConfig {
-    fn selector(&self) -> Result<SinkSelector> {
+    pub(super) fn selector(&self) -> Result<SinkSelector> {
         if let Some(id) = self.id {
             return Ok(SinkSelector::by_id(id));
         }
@@ -395,19 +939,28 @@ impl AudioOutputConfig {
         bail!("audio toggle output entry must provide `id`, `name`, or `description`");
     }
 
-    fn label(&self) -> String {
+    // Descriptions read better on a key than PulseAudio sink names.
+    fn display_label(&self) -> String {
+        match self.description.as_deref() {
+            Some(description) => short_sink_label(description),
+            None => self.label(),
+        }
+    }
+
+    pub(super) fn label(&self) -> String {
         self.name
             .as_ref()
             .or(self.description.as_ref())
             .cloned()
-            .or_else(|| self.id.map(|id| format!("sink #{id}")))
-            .unwrap_or_else(|| "unnamed sink".to_string())
+            .or_else(|| self.id.map(|id| format!("{} #{id}", self.kind.noun())))
+            .unwrap_or_else(|| format!("unnamed {}", self.kind.noun()))
     }
 }
 
 impl OutputIcons {
-    fn from_base(base: &ButtonImage, button_index: u8, index: usize) -> Self {
+    fn from_base(base: &ButtonImage, button_index: u8, index: usize, tints: StateTints) -> Self {
         let base_id = normalize_id(&base.id);
+        let tint = |color: Option<HexColor>, default| color.map_or(default, |HexColor(rgb)| rgb);
         Self {
             available_selected: tinted_variant(
                 base,
@@ -415,7 +968,7 @@ impl OutputIcons {
                 index,
                 &base_id,
                 "active",
-                ACTIVE_TINT,
+                tint(tints.active, ACTIVE_TINT),
             ),
             available_inactive: tinted_variant(
                 base,
@@ -423,7 +976,7 @@ impl OutputIcons {
                 index,
                 &base_id,
                 "available",
-                AVAILABLE_TINT,
+                tint(tints.available, AVAILABLE_TINT),
             ),
             unavailable_selected: tinted_variant(
                 base,
@@ -431,7 +984,7 @@ impl OutputIcons {
                 index,
                 &base_id,
                 "unavailable-active",
-                DEGRADED_TINT,
+                tint(tints.degraded, DEGRADED_TINT),
             ),
             unavailable_inactive: tinted_variant(
                 base,
@@ -439,7 +992,7 @@ impl OutputIcons {
                 index,
                 &base_id,
                 "unavailable",
-                UNAVAILABLE_TINT,
+                tint(tints.unavailable, UNAVAILABLE_TINT),
             ),
         }
     }
@@ -454,6 +1007,27 @@ impl OutputIcons {
     }
 }
 
+// Stops at the first kind that fails, like a refresh on the caller would.
+fn read_devices<B: AudioSwitchBackend>(backend: &B, kinds: &[AudioDeviceKind]) -> DeviceListing {
+    let mut listing = Vec::with_capacity(kinds.len());
+    for &kind in kinds {
+        let devices = match kind {
+            AudioDeviceKind::Sink => backend
+                .list_sinks()
+                .and_then(|sinks| Ok((sinks, backend.current_default_sink()?))),
+            AudioDeviceKind::Source => backend
+                .list_sources()
+                .and_then(|sources| Ok((sources, backend.current_default_source()?))),
+        };
+        let failed = devices.is_err();
+        listing.push((kind, devices));
+        if failed {
+            break;
+        }
+    }
+    listing
+}
+
 fn normalize_id(id: &str) -> String {
     let mut slug = String::with_capacity(id.len());
     for ch in id.chars() {
@@ -486,6 +1060,10 @@ fn tinted_variant(
         id: format!("audio-{}-{}-{}-{}", button_index, index, base_id, suffix),
         image: Arc::clone(&base.image),
         tint: Some(tint),
+        label: base.label.clone(),
+        animation: base.animation.clone(),
+        background: base.background,
+        badge: None,
     }
 }
 
@@ -494,19 +1072,60 @@ fn load_icon_from_config(
     fallback: MaterialIcon,
     paths: &IconPaths,
 ) -> Result<ButtonImage> {
-    match icon {
-        Some(IconConfig::Material { material }) => load_material_icon(*material, paths),
-        Some(IconConfig::Path { path }) => load_icon_from_path(Path::new(path), path, None, paths),
-        Some(IconConfig::Simple(material)) => load_material_icon(*material, paths),
-        Some(IconConfig::File(path)) => load_icon_from_path(Path::new(path), path, None, paths),
+    match icon.map(|icon| load_configured_icon(icon, paths)) {
+        Some(Ok(icon)) => Ok(icon),
+        Some(Err(err)) => {
+            warn!(error = %err, "failed to load configured icon; using the default icon");
+            load_material_icon(fallback, paths)
+        }
         None => load_material_icon(fallback, paths),
     }
 }
 
-fn load_material_icon(icon: MaterialIcon, paths: &IconPaths) -> Result<ButtonImage> {
+pub(super) fn load_configured_icon(icon: &IconConfig, paths: &IconPaths) -> Result<ButtonImage> {
+    match icon {
+        IconConfig::Material { material } | IconConfig::Simple(material) => {
+            load_material_icon(*material, paths)
+        }
+        IconConfig::Inline { data } => load_inline_icon(data),
+        IconConfig::File(path) if icons::is_data_uri(path) => load_inline_icon(path),
+        IconConfig::Path { path } | IconConfig::File(path) => {
+            load_icon_from_path(Path::new(path), path, None, paths)
+        }
+    }
+}
+
+fn load_inline_icon(data: &str) -> Result<ButtonImage> {
+    let (key, image) = icons::load_inline_icon(data)?;
+    Ok(ButtonImage {
+        id: format!("inline-{key:016x}"),
+        image,
+        tint: None,
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
+    })
+}
+
+pub(super) fn load_material_icon(icon: MaterialIcon, paths: &IconPaths) -> Result<ButtonImage> {
     let (filename, id) = match icon {
         MaterialIcon::Monitor => ("monitor.svg", "monitor"),
         MaterialIcon::Headphones => ("headphones.svg", "headphones"),
+        MaterialIcon::Speaker => ("speaker.svg", "speaker"),
+        MaterialIcon::Earbuds => ("earbuds.svg", "earbuds"),
+        MaterialIcon::Tv => ("tv.svg", "tv"),
+        MaterialIcon::Usb => ("usb.svg", "usb"),
+        MaterialIcon::Mic => ("mic.svg", "mic"),
+        MaterialIcon::MicOff => ("mic_off.svg", "mic-off"),
+        MaterialIcon::Notifications => ("notifications.svg", "notifications"),
+        MaterialIcon::NotificationsOff => ("notifications_off.svg", "notifications-off"),
+        MaterialIcon::Terminal => ("terminal.svg", "terminal"),
+        MaterialIcon::VpnKey => ("vpn_key.svg", "vpn-key"),
+        MaterialIcon::PhotoCamera => ("photo_camera.svg", "photo-camera"),
+        MaterialIcon::Videocam => ("videocam.svg", "videocam"),
+        MaterialIcon::TextSnippet => ("text_snippet.svg", "text-snippet"),
+        MaterialIcon::Bluetooth => ("bluetooth.svg", "bluetooth"),
     };
 
     let mut candidates: Vec<PathBuf> = Vec::new();
@@ -574,12 +1193,24 @@ fn resolve_icon_path(path: &Path, paths: &IconPaths) -> Option<PathBuf> {
 
 fn load_icon_from_resolved(path: &Path, id: String, tint: Option<[u8; 3]>) -> Result<ButtonImage> {
     let image = icons::load_icon(path)?;
-    Ok(ButtonImage { id, image, tint })
+    let animation = icons::load_animated_icon(path)?;
+    Ok(ButtonImage {
+        id,
+        image,
+        tint,
+        label: None,
+        animation,
+        background: None,
+        badge: None,
+    })
 }
 
 fn notify_switch_failure(label: &str, error: &anyhow::Error) {
+    notify(&format!("Failed to switch to {}:\n{}", label, error));
+}
+
+fn notify(body: &str) {
     let title = "Stream Deck Audio Toggle";
-    let body = format!("Failed to switch to {}:\n{}", label, error);
     match Command::new("notify-send").arg(title).arg(body).status() {
         Ok(status) => {
             if !status.success() {
@@ -587,7 +1218,7 @@ fn notify_switch_failure(label: &str, error: &anyhow::Error) {
             }
         }
         Err(err) => {
-            warn!(error = %err, "failed to send audio switch failure notification");
+            warn!(error = %err, "failed to send audio toggle notification");
         }
     }
 }
@@ -646,11 +1277,16 @@ mod tests {
         sinks: Vec<SinkInfo>,
         set_calls: std::sync::Mutex<Vec<SinkSelector>>,
         current: std::sync::Mutex<Option<SinkInfo>>,
+        volumes: Arc<Mutex<HashMap<String, u8>>>,
+        sources: Vec<SinkInfo>,
+        current_source: Mutex<Option<SinkInfo>>,
+        events: Arc<Mutex<Vec<String>>>,
     }
 
     impl AudioSwitchBackend for FakeBackend {
         fn set_default_sink(&self, selector: &SinkSelector) -> Result<SinkInfo> {
             self.set_calls.lock().unwrap().push(selector.clone());
+            self.events.lock().unwrap().push("switch".to_string());
             let sink = self
                 .sinks
                 .iter()
@@ -668,6 +1304,37 @@ mod tests {
         fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
             Ok(self.sinks.clone())
         }
+
+        fn sink_volume(&self, sink_name: &str) -> Result<Option<u8>> {
+            Ok(self.volumes.lock().unwrap().get(sink_name).copied())
+        }
+
+        fn set_sink_volume(&self, sink_name: &str, percent: u8) -> Result<()> {
+            self.volumes
+                .lock()
+                .unwrap()
+                .insert(sink_name.to_string(), percent);
+            Ok(())
+        }
+
+        fn set_default_source(&self, selector: &SinkSelector) -> Result<SinkInfo> {
+            let source = self
+                .sources
+                .iter()
+                .find(|source| selector.matches(source))
+                .cloned()
+                .ok_or_else(|| anyhow!("no source matches selector {:?}", selector))?;
+            *self.current_source.lock().unwrap() = Some(source.clone());
+            Ok(source)
+        }
+
+        fn current_default_source(&self) -> Result<Option<SinkInfo>> {
+            Ok(self.current_source.lock().unwrap().clone())
+        }
+
+        fn list_sources(&self) -> Result<Vec<SinkInfo>> {
+            Ok(self.sources.clone())
+        }
     }
 
     fn sample_config() -> AudioToggleConfig {
@@ -682,6 +1349,12 @@ mod tests {
                     icon: Some(IconConfig::Material {
                         material: MaterialIcon::Monitor,
                     }),
+                    volume: None,
+                    show_label: false,
+                    kind: AudioDeviceKind::Sink,
+                    tints: StateTints::default(),
+                    on_activate: None,
+                    on_deactivate: None,
                 },
                 AudioOutputConfig {
                     button_index: None,
@@ -691,8 +1364,17 @@ mod tests {
                     icon: Some(IconConfig::Material {
                         material: MaterialIcon::Headphones,
                     }),
+                    volume: None,
+                    show_label: false,
+                    kind: AudioDeviceKind::Sink,
+                    tints: StateTints::default(),
+                    on_activate: None,
+                    on_deactivate: None,
                 },
             ],
+            mixer_command: default_mixer_command(),
+            mixer_hold_ms: default_mixer_hold_ms(),
+            tints: StateTints::default(),
         }
     }
 
@@ -708,6 +1390,12 @@ mod tests {
                     icon: Some(IconConfig::Material {
                         material: MaterialIcon::Monitor,
                     }),
+                    volume: None,
+                    show_label: false,
+                    kind: AudioDeviceKind::Sink,
+                    tints: StateTints::default(),
+                    on_activate: None,
+                    on_deactivate: None,
                 },
                 AudioOutputConfig {
                     button_index: Some(1),
@@ -717,6 +1405,12 @@ mod tests {
                     icon: Some(IconConfig::Material {
                         material: MaterialIcon::Headphones,
                     }),
+                    volume: None,
+                    show_label: false,
+                    kind: AudioDeviceKind::Sink,
+                    tints: StateTints::default(),
+                    on_activate: None,
+                    on_deactivate: None,
                 },
                 AudioOutputConfig {
                     button_index: Some(2),
@@ -726,11 +1420,31 @@ mod tests {
                     icon: Some(IconConfig::Material {
                         material: MaterialIcon::Headphones,
                     }),
+                    volume: None,
+                    show_label: false,
+                    kind: AudioDeviceKind::Sink,
+                    tints: StateTints::default(),
+                    on_activate: None,
+                    on_deactivate: None,
                 },
             ],
+            mixer_command: default_mixer_command(),
+            mixer_hold_ms: default_mixer_hold_ms(),
+            tints: StateTints::default(),
         }
     }
 
+    fn tap<B, H>(controller: &mut AudioToggleController<B, H>, button_index: u8) -> bool
+    where
+        B: AudioSwitchBackend,
+        H: DisplayPipeline,
+    {
+        controller.on_button_pressed(button_index).unwrap()
+            && controller
+                .on_button_released(button_index, Duration::ZERO)
+                .unwrap()
+    }
+
     #[test]
     fn config_loads_from_disk() {
         let dir = tempfile::tempdir().unwrap();
@@ -752,6 +1466,39 @@ mod tests {
         assert_eq!(config.outputs[0].description.as_deref(), Some("Output A"));
     }
 
+    #[test]
+    fn inline_icons_load_and_bad_data_falls_back() {
+        let config: AudioToggleConfig = serde_json::from_str(
+            r#"{
+                "button_index": 0,
+                "outputs": [
+                    { "description": "Monitor", "icon": { "data": "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR4nGP4z8DwHwAFAAH/iZk9HQAAAABJRU5ErkJggg==" } },
+                    { "description": "Headset", "icon": "data:image/png;base64,%%%" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let paths = IconPaths::new(None);
+
+        let inline = load_icon_from_config(
+            config.outputs[0].icon.as_ref(),
+            MaterialIcon::Monitor,
+            &paths,
+        )
+        .unwrap();
+        assert!(inline.id.starts_with("inline-"), "{}", inline.id);
+        assert_eq!(inline.image.dimensions(), (1, 1));
+
+        assert!(matches!(config.outputs[1].icon, Some(IconConfig::File(_))));
+        let fallback = load_icon_from_config(
+            config.outputs[1].icon.as_ref(),
+            MaterialIcon::Headphones,
+            &paths,
+        )
+        .unwrap();
+        assert_eq!(fallback.id, "headphones");
+    }
+
     #[test]
     fn parses_string_icon_path() {
         let config: AudioToggleConfig = serde_json::from_str(
@@ -822,17 +1569,20 @@ mod tests {
                     id: Some(1),
                     name: "sink_a".into(),
                     description: Some("HDMI/DisplayPort - HDA NVidia".into()),
+                    ..Default::default()
                 },
                 SinkInfo {
                     id: Some(2),
                     name: "sink_b".into(),
                     description: Some("Digital Output - A50".into()),
+                    ..Default::default()
                 },
             ],
             current: std::sync::Mutex::new(Some(SinkInfo {
                 id: Some(2),
                 name: "sink_b".into(),
                 description: Some("Digital Output - A50".into()),
+                ..Default::default()
             })),
             ..Default::default()
         };
@@ -845,6 +1595,52 @@ mod tests {
         assert!(controller.state_for_index(1).active);
     }
 
+    #[test]
+    fn outputs_without_icons_follow_the_device_form_factor() {
+        let mut config = sample_config();
+        for (output, button) in config.outputs.iter_mut().zip([2, 3]) {
+            output.icon = None;
+            output.button_index = Some(button);
+        }
+        let device = |id: u32, name: &str, description: &str, form_factor: &str| SinkInfo {
+            id: Some(id),
+            name: name.into(),
+            description: Some(description.into()),
+            form_factor: Some(form_factor.into()),
+            ..Default::default()
+        };
+        let headset = device(2, "sink_headset", "Digital Output - A50", "headset");
+        let backend = FakeBackend {
+            sinks: vec![headset.clone()],
+            current: std::sync::Mutex::new(Some(headset)),
+            ..Default::default()
+        };
+        let hardware = Arc::new(RecordingHardware::new());
+        let icon_paths = IconPaths::new(None);
+        let mut controller =
+            AudioToggleController::new(config, backend, Arc::clone(&hardware), &icon_paths)
+                .unwrap();
+        let last_icon = |button: u8| {
+            hardware
+                .updates()
+                .into_iter()
+                .rev()
+                .find(|(index, _)| *index == button)
+                .and_then(|(_, id)| id)
+                .unwrap()
+        };
+        assert_eq!(last_icon(3), "audio-3-1-headphones-active");
+        // Not seen yet, so it keeps the stand-in.
+        assert_eq!(last_icon(2), "audio-2-0-monitor-unavailable");
+
+        // The HDMI sink only appears once the display wakes up.
+        let hdmi = device(1, "sink_hdmi", "HDMI/DisplayPort - HDA NVidia", "hdmi");
+        controller
+            .apply_devices(AudioDeviceKind::Sink, &[hdmi], None)
+            .unwrap();
+        assert_eq!(last_icon(2), "audio-2-0-tv-available");
+    }
+
     #[test]
     fn toggles_between_outputs() {
         let config = sample_config();
@@ -854,17 +1650,20 @@ mod tests {
                     id: Some(1),
                     name: "sink_monitor".into(),
                     description: Some("HDMI/DisplayPort - HDA NVidia".into()),
+                    ..Default::default()
                 },
                 SinkInfo {
                     id: Some(2),
                     name: "sink_headset".into(),
                     description: Some("Digital Output - A50".into()),
+                    ..Default::default()
                 },
             ],
             current: std::sync::Mutex::new(Some(SinkInfo {
                 id: Some(1),
                 name: "sink_monitor".into(),
                 description: Some("HDMI/DisplayPort - HDA NVidia".into()),
+                ..Default::default()
             })),
             ..Default::default()
         };
@@ -875,13 +1674,29 @@ mod tests {
             AudioToggleController::new(config, backend, Arc::clone(&hardware), &icon_paths)
                 .unwrap();
         assert!(controller.state_for_index(0).active);
-        assert!(controller.on_button_pressed(2).unwrap());
+        assert!(tap(&mut controller, 2));
         assert!(controller.state_for_index(1).active);
         let updates = hardware.updates();
         assert!(!updates.is_empty());
         assert_eq!(updates.last().unwrap().0, 2);
     }
 
+    #[test]
+    fn failed_sink_switches_are_counted() {
+        let metrics = Arc::new(Metrics::default());
+        let mut controller = AudioToggleController::new(
+            sample_config(),
+            FakeBackend::default(),
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap()
+        .with_metrics(Arc::clone(&metrics));
+
+        controller.switch_to(1);
+        assert_eq!(metrics.sink_switch_failures.load(Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn selects_individual_buttons() {
         let config = multi_button_config();
@@ -891,22 +1706,26 @@ mod tests {
                     id: Some(1),
                     name: "sink_monitor".into(),
                     description: Some("Monitor".into()),
+                    ..Default::default()
                 },
                 SinkInfo {
                     id: Some(2),
                     name: "sink_headset".into(),
                     description: Some("Headset".into()),
+                    ..Default::default()
                 },
                 SinkInfo {
                     id: Some(3),
                     name: "sink_earbuds".into(),
                     description: Some("Earbuds".into()),
+                    ..Default::default()
                 },
             ],
             current: std::sync::Mutex::new(Some(SinkInfo {
                 id: Some(1),
                 name: "sink_monitor".into(),
                 description: Some("Monitor".into()),
+                ..Default::default()
             })),
             ..Default::default()
         };
@@ -921,11 +1740,386 @@ mod tests {
         assert!(controller.state_for_index(1).available);
         assert!(controller.state_for_index(2).available);
 
-        assert!(controller.on_button_pressed(2).unwrap());
+        assert!(tap(&mut controller, 2));
         assert!(controller.state_for_index(2).active);
 
+        assert!(tap(&mut controller, 1));
+        assert!(controller.state_for_index(1).active);
+    }
+
+    #[test]
+    fn cycling_walks_outputs_across_buttons() {
+        let backend = FakeBackend {
+            sinks: vec![
+                SinkInfo {
+                    id: Some(1),
+                    name: "sink_monitor".into(),
+                    description: Some("Monitor".into()),
+                    ..Default::default()
+                },
+                SinkInfo {
+                    id: Some(2),
+                    name: "sink_headset".into(),
+                    description: Some("Headset".into()),
+                    ..Default::default()
+                },
+                SinkInfo {
+                    id: Some(3),
+                    name: "sink_earbuds".into(),
+                    description: Some("Earbuds".into()),
+                    ..Default::default()
+                },
+            ],
+            current: std::sync::Mutex::new(Some(SinkInfo {
+                id: Some(3),
+                name: "sink_earbuds".into(),
+                description: Some("Earbuds".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        let hardware = Arc::new(RecordingHardware::new());
+        let icon_paths = IconPaths::new(None);
+        let mut controller = AudioToggleController::new(
+            multi_button_config(),
+            backend,
+            Arc::clone(&hardware),
+            &icon_paths,
+        )
+        .unwrap();
+
+        controller.cycle_outputs();
+        assert!(controller.state_for_index(0).active);
+        controller.cycle_outputs();
+        assert!(controller.state_for_index(1).active);
+    }
+
+    // All three outputs on key 4, with (available, active) set per entry.
+    fn grouped(
+        states: [(bool, bool); 3],
+    ) -> AudioToggleController<FakeBackend, Arc<RecordingHardware>> {
+        let mut config = multi_button_config();
+        config.button_index = Some(4);
+        for output in &mut config.outputs {
+            output.button_index = None;
+        }
+        let mut controller = AudioToggleController::new(
+            config,
+            FakeBackend::default(),
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+        for (entry, (available, active)) in controller.outputs.iter_mut().zip(states) {
+            entry.state = OutputState { available, active };
+        }
+        controller
+    }
+
+    #[test]
+    fn group_cycling_skips_unavailable_outputs() {
+        let group = [0, 1, 2];
+        let unplugged = (false, false);
+        let plugged = (true, false);
+
+        let all_unavailable = grouped([(false, true), unplugged, unplugged]);
+        assert_eq!(all_unavailable.select_next_in_group(&group), None);
+
+        let one_available = grouped([(true, true), unplugged, plugged]);
+        assert_eq!(one_available.select_next_in_group(&group), Some(2));
+        let only_active = grouped([unplugged, (true, true), unplugged]);
+        assert_eq!(only_active.select_next_in_group(&group), None);
+
+        let active_unplugged = grouped([plugged, (false, true), plugged]);
+        assert_eq!(active_unplugged.select_next_in_group(&group), Some(2));
+        let wraps = grouped([plugged, plugged, (true, true)]);
+        assert_eq!(wraps.select_next_in_group(&group), Some(0));
+        let none_active = grouped([unplugged, plugged, plugged]);
+        assert_eq!(none_active.select_next_in_group(&group), Some(1));
+    }
+
+    #[test]
+    fn grouped_key_shows_the_active_output_with_a_badge() {
+        let controller = grouped([(true, false), (true, true), (false, false)]);
+        for index in 0..3 {
+            let icon = controller.button_icon(index);
+            assert_eq!(icon.badge.as_deref(), Some("2/3"));
+            assert!(icon.id.starts_with("audio-4-1-"), "{}", icon.id);
+        }
+
+        let idle = grouped([(true, false), (true, false), (false, false)]);
+        assert_eq!(idle.button_icon(2).badge.as_deref(), Some("1/3"));
+
+        let mut config = sample_config();
+        config.outputs.truncate(1);
+        let alone = AudioToggleController::new(
+            config,
+            FakeBackend::default(),
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+        assert_eq!(alone.button_icon(0).badge, None);
+    }
+
+    #[test]
+    fn switching_restores_each_outputs_volume() {
+        let mut config = multi_button_config();
+        config.outputs[2].volume = Some(20);
+        let volumes = Arc::new(Mutex::new(HashMap::from([
+            ("sink_monitor".to_string(), 70),
+            ("sink_headset".to_string(), 35),
+        ])));
+        let backend = FakeBackend {
+            sinks: ["sink_monitor", "sink_headset", "sink_earbuds"]
+                .into_iter()
+                .zip(1..)
+                .map(|(name, id)| SinkInfo {
+                    id: Some(id),
+                    name: name.into(),
+                    description: None,
+                    ..Default::default()
+                })
+                .collect(),
+            current: std::sync::Mutex::new(None),
+            volumes: Arc::clone(&volumes),
+            ..Default::default()
+        };
+        let mut controller = AudioToggleController::new(
+            config,
+            backend,
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+
+        tap(&mut controller, 0);
+        tap(&mut controller, 1);
+        // The headset was turned down while active; the next switch remembers that.
+        volumes
+            .lock()
+            .unwrap()
+            .insert("sink_headset".to_string(), 15);
+        tap(&mut controller, 0);
+        volumes
+            .lock()
+            .unwrap()
+            .insert("sink_monitor".to_string(), 5);
+        tap(&mut controller, 1);
+        assert_eq!(volumes.lock().unwrap()["sink_headset"], 15);
+
+        tap(&mut controller, 0);
+        assert_eq!(volumes.lock().unwrap()["sink_monitor"], 5);
+
+        tap(&mut controller, 2);
+        assert_eq!(volumes.lock().unwrap()["sink_earbuds"], 20);
+    }
+
+    #[test]
+    fn sources_switch_independently_of_sinks() {
+        let config: AudioToggleConfig = serde_json::from_str(
+            r#"{
+                "outputs": [
+                    { "button_index": 0, "name": "sink_headset" },
+                    { "button_index": 1, "name": "usb_mic", "kind": "source" },
+                    { "button_index": 1, "name": "webcam_mic", "kind": "source" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let device = |name: &str| SinkInfo {
+            id: None,
+            name: name.into(),
+            description: None,
+            ..Default::default()
+        };
+        let backend = FakeBackend {
+            sinks: vec![device("sink_headset")],
+            current: Mutex::new(Some(device("sink_headset"))),
+            sources: vec![device("usb_mic"), device("webcam_mic")],
+            current_source: Mutex::new(Some(device("usb_mic"))),
+            ..Default::default()
+        };
+        let mut controller = AudioToggleController::new(
+            config,
+            backend,
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+        assert!(controller.state_for_index(0).active);
+        assert!(controller.state_for_index(1).active);
+
+        assert!(tap(&mut controller, 1));
+        assert!(controller.state_for_index(0).active);
+        assert!(!controller.state_for_index(1).active);
+        assert!(controller.state_for_index(2).active);
+        assert!(controller.backend.set_calls.lock().unwrap().is_empty());
+
+        controller.cycle_outputs();
+        assert!(controller.state_for_index(2).active);
+    }
+
+    struct RecordingSpawner {
+        events: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl HookSpawner for RecordingSpawner {
+        fn spawn(&self, exec: &ExecSpec) -> Result<()> {
+            self.events.lock().unwrap().push(exec.args.join(" "));
+            if self.fail {
+                bail!("hook exited with status 1");
+            }
+            Ok(())
+        }
+    }
+
+    fn hooked_controller(fail: bool) -> AudioToggleController<FakeBackend, Arc<RecordingHardware>> {
+        let config: AudioToggleConfig = serde_json::from_str(
+            r#"{
+                "outputs": [
+                    {
+                        "button_index": 0,
+                        "name": "sink_hdmi",
+                        "on_activate": "echo activate-hdmi",
+                        "on_deactivate": ["echo", "deactivate-hdmi"]
+                    },
+                    {
+                        "button_index": 1,
+                        "name": "sink_headset",
+                        "on_activate": "echo activate-headset",
+                        "on_deactivate": "echo deactivate-headset"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let device = |name: &str| SinkInfo {
+            name: name.into(),
+            ..Default::default()
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let backend = FakeBackend {
+            sinks: vec![device("sink_hdmi"), device("sink_headset")],
+            current: Mutex::new(Some(device("sink_headset"))),
+            events: Arc::clone(&events),
+            ..Default::default()
+        };
+        let mut controller = AudioToggleController::new(
+            config,
+            backend,
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+        controller.hooks = Box::new(RecordingSpawner { events, fail });
+        controller
+    }
+
+    #[test]
+    fn hooks_run_around_a_deck_initiated_switch() {
+        let mut controller = hooked_controller(false);
+        assert!(tap(&mut controller, 0));
+        assert_eq!(
+            *controller.backend.events.lock().unwrap(),
+            vec!["deactivate-headset", "switch", "activate-hdmi"]
+        );
+    }
+
+    #[test]
+    fn failing_hooks_keep_the_switch() {
+        let mut controller = hooked_controller(true);
+        assert!(tap(&mut controller, 0));
+        assert!(controller.state_for_index(0).active);
+        assert!(!controller.state_for_index(1).active);
+        assert_eq!(controller.backend.events.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn external_switches_run_no_hooks() {
+        let mut controller = hooked_controller(false);
+        *controller.backend.current.lock().unwrap() = Some(SinkInfo {
+            name: "sink_hdmi".into(),
+            ..Default::default()
+        });
+        controller.refresh_state().unwrap();
+        assert!(controller.state_for_index(0).active);
+        assert!(controller.backend.events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_hook_commands() {
+        let mut config = sample_config();
+        config.outputs[0].on_activate = Some(CommandLine::Text(String::new()));
+        let result = AudioToggleController::new(
+            config,
+            FakeBackend::default(),
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn long_press_opens_the_mixer_instead_of_switching() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("mixer-opened");
+        let mut config = multi_button_config();
+        config.mixer_command =
+            CommandLine::Argv(vec!["touch".to_string(), marker.display().to_string()]);
+        let backend = FakeBackend {
+            sinks: vec![SinkInfo {
+                id: Some(2),
+                name: "sink_headset".into(),
+                description: Some("Headset".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut controller = AudioToggleController::new(
+            config,
+            backend,
+            Arc::new(RecordingHardware::new()),
+            &IconPaths::new(None),
+        )
+        .unwrap();
+
+        assert!(!controller.on_button_pressed(7).unwrap());
         assert!(controller.on_button_pressed(1).unwrap());
+        assert!(controller.backend.set_calls.lock().unwrap().is_empty());
+        assert!(
+            controller
+                .on_button_released(1, Duration::from_millis(799))
+                .unwrap()
+        );
+        assert_eq!(controller.backend.set_calls.lock().unwrap().len(), 1);
+        assert!(controller.state_for_index(1).active);
+
+        assert!(controller.on_button_pressed(0).unwrap());
+        assert!(
+            controller
+                .on_button_released(0, Duration::from_millis(800))
+                .unwrap()
+        );
+        assert_eq!(controller.backend.set_calls.lock().unwrap().len(), 1);
         assert!(controller.state_for_index(1).active);
+        for _ in 0..200 {
+            if marker.exists() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(marker.exists());
+
+        // A release whose press was lost is claimed but does nothing.
+        assert!(
+            controller
+                .on_button_released(0, Duration::from_millis(800))
+                .unwrap()
+        );
+        assert_eq!(controller.backend.set_calls.lock().unwrap().len(), 1);
     }
 
     #[test]
@@ -934,15 +2128,95 @@ mod tests {
         let icon = load_material_icon(MaterialIcon::Monitor, &icon_paths).unwrap();
         assert_eq!(icon.tint, Some(MATERIAL_ICON_TINT));
     }
+
+    #[test]
+    fn every_material_icon_resolves() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("stream-deck.json");
+        fs::create_dir(dir.path().join("assets")).unwrap();
+        fs::copy(
+            "assets/icons/material/usb.svg",
+            dir.path().join("assets").join("usb.svg"),
+        )
+        .unwrap();
+        let beside_config = IconPaths {
+            base_dir: Some(dir.path().to_path_buf()),
+            assets_root: Some(dir.path().join("assets")),
+        };
+        assert_eq!(
+            load_material_icon(MaterialIcon::Usb, &beside_config)
+                .unwrap()
+                .id,
+            "usb"
+        );
+
+        let icon_paths = IconPaths::new(Some(&config_path));
+        for name in MaterialIcon::NAMES {
+            let icon = load_material_icon(name.parse().unwrap(), &icon_paths).unwrap();
+            assert_eq!(icon.id, name);
+        }
+    }
+
+    #[test]
+    fn unknown_material_names_list_the_choices() {
+        let parsed: AudioToggleConfig = serde_json::from_str(
+            r#"{"outputs": [{"id": 1, "icon": "tv"}, {"id": 2, "icon": "usb.png"}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            parsed.outputs[0].icon,
+            Some(IconConfig::Simple(MaterialIcon::Tv))
+        ));
+        assert!(matches!(parsed.outputs[1].icon, Some(IconConfig::File(_))));
+
+        let err = serde_json::from_str::<AudioToggleConfig>(
+            r#"{"outputs": [{"id": 1, "icon": {"material": "speakers"}}]}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("unknown material icon \"speakers\""), "{err}");
+        assert!(err.contains("speaker, earbuds, tv, usb"), "{err}");
+    }
+
+    #[test]
+    fn tints_from_config_override_the_defaults() {
+        let config: AudioToggleConfig = serde_json::from_str(
+            r##"{
+                "tints": {"active": "#00c896", "unavailable": "#202020"},
+                "outputs": [
+                    {"id": 1, "icon": "speaker"},
+                    {"id": 2, "icon": "earbuds", "tints": {"active": "#ff8800"}}
+                ]
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(config.tints.active, Some(HexColor([0, 200, 150])));
+        assert!(serde_json::from_str::<StateTints>(r#"{"active": "green"}"#).is_err());
+
+        let paths = IconPaths::new(None);
+        let profile = |index: usize| {
+            OutputProfile::from_config(&config.outputs[index], Some(0), index, config.tints, &paths)
+                .unwrap()
+                .icons
+        };
+        let speaker = profile(0);
+        assert_eq!(speaker.available_selected.tint, Some([0, 200, 150]));
+        assert_eq!(speaker.unavailable_inactive.tint, Some([32, 32, 32]));
+        assert_eq!(speaker.available_inactive.tint, Some(AVAILABLE_TINT));
+        let earbuds = profile(1);
+        assert_eq!(earbuds.available_selected.tint, Some([255, 136, 0]));
+        assert_eq!(earbuds.unavailable_inactive.tint, Some([32, 32, 32]));
+        assert_eq!(earbuds.unavailable_selected.tint, Some(DEGRADED_TINT));
+    }
 }
 #[derive(Clone, Debug)]
-struct IconPaths {
+pub(super) struct IconPaths {
     base_dir: Option<PathBuf>,
     assets_root: Option<PathBuf>,
 }
 
 impl IconPaths {
-    fn new(config_path: Option<&Path>) -> Self {
+    pub(super) fn new(config_path: Option<&Path>) -> Self {
         let env_assets = env::var_os("STREAMDECK_CTRL_ASSETS").map(PathBuf::from);
         let base_dir = config_path
             .and_then(|path| path.parent())
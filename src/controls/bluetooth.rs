@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::bluetooth::{BluetoothBackend, BluetoothctlBackend, is_valid_mac};
+use crate::system::worker::BackendWorker;
+
+use super::audio_toggle::{
+    ACTIVE_TINT, AVAILABLE_TINT, DEGRADED_TINT, IconConfig, IconPaths, MaterialIcon,
+    UNAVAILABLE_TINT, load_configured_icon, load_material_icon,
+};
+
+const CONNECTING_TINT: [u8; 3] = [150, 120, 230];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BluetoothConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+    /// Device address as shown by `bluetoothctl devices`.
+    pub mac: String,
+    /// Printed on the key and used in notifications; defaults to the address.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<IconConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BluetoothState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Failed,
+    Unavailable,
+}
+
+enum BluetoothReport {
+    Toggled {
+        index: u8,
+        target: bool,
+        result: Result<()>,
+    },
+    Observed(Vec<(u8, Result<bool>)>),
+}
+
+struct BluetoothDevice {
+    mac: String,
+    name: String,
+    icons: DeviceIcons,
+    connected: Option<bool>,
+    toggling: bool,
+    failed: bool,
+    state: Option<BluetoothState>,
+}
+
+struct DeviceIcons {
+    connected: ButtonImage,
+    disconnected: ButtonImage,
+    connecting: ButtonImage,
+    failed: ButtonImage,
+    unavailable: ButtonImage,
+}
+
+/// Keys that connect or disconnect a paired Bluetooth device. Connecting
+/// can take seconds, so both toggles and status reads go through a worker;
+/// the key shows "connecting" in the meantime.
+pub struct BluetoothController<B, H>
+where
+    B: BluetoothBackend,
+    H: DisplayPipeline,
+{
+    worker: BackendWorker<B, BluetoothReport>,
+    hardware: H,
+    available: bool,
+    devices: BTreeMap<u8, BluetoothDevice>,
+}
+
+impl<B, H> BluetoothController<B, H>
+where
+    B: BluetoothBackend,
+    H: DisplayPipeline,
+{
+    fn new(
+        configs: &[BluetoothConfig],
+        backend: B,
+        hardware: H,
+        icon_paths: &IconPaths,
+    ) -> Result<Option<Self>> {
+        let mut devices = BTreeMap::new();
+        for config in configs {
+            if !is_valid_mac(&config.mac) {
+                warn!(
+                    button_index = config.button_index,
+                    mac = %config.mac,
+                    "skipping bluetooth key with an invalid address"
+                );
+                continue;
+            }
+            let icons = match device_icons(config, icon_paths) {
+                Ok(icons) => icons,
+                Err(err) => {
+                    warn!(
+                        error = %err,
+                        button_index = config.button_index,
+                        "skipping bluetooth key due to configuration error"
+                    );
+                    continue;
+                }
+            };
+            let device = BluetoothDevice {
+                mac: config.mac.to_ascii_uppercase(),
+                name: config.name.clone().unwrap_or_else(|| config.mac.clone()),
+                icons,
+                connected: None,
+                toggling: false,
+                failed: false,
+                state: None,
+            };
+            if devices.insert(config.button_index, device).is_some() {
+                warn!(
+                    button_index = config.button_index,
+                    "overriding previously configured bluetooth key"
+                );
+            }
+        }
+        if devices.is_empty() {
+            return Ok(None);
+        }
+
+        let available = backend.is_available();
+        if !available {
+            warn!("bluetoothctl not found on PATH; bluetooth keys disabled");
+        }
+        let mut controller = Self {
+            worker: BackendWorker::inline(backend),
+            hardware,
+            available,
+            devices,
+        };
+        controller.on_tick()?;
+        Ok(Some(controller))
+    }
+
+    pub fn in_background(mut self) -> Result<Self> {
+        self.worker = self.worker.into_threaded("bluetoothctl")?;
+        Ok(self)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.devices.keys().copied()
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        if !self.devices.contains_key(&button_index) {
+            return Ok(false);
+        }
+        self.poll_reports();
+        let Some(device) = self.devices.get_mut(&button_index) else {
+            return Ok(true);
+        };
+        if !self.available || device.toggling {
+            return Ok(true);
+        }
+
+        let target = !device.connected.unwrap_or(false);
+        let mac = device.mac.clone();
+        self.worker
+            .submit(move |backend: &mut B| BluetoothReport::Toggled {
+                index: button_index,
+                target,
+                result: backend.set_connected(&mac, target),
+            })?;
+        device.toggling = true;
+        device.failed = false;
+        self.update_display()?;
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(self.devices.contains_key(&button_index))
+    }
+
+    /// Reads every device's state unless a toggle or an earlier read is
+    /// still running, so connections made elsewhere show up too.
+    pub fn on_tick(&mut self) -> Result<()> {
+        self.poll_reports();
+        if self.available && !self.worker.is_busy() {
+            let macs: Vec<(u8, String)> = self
+                .devices
+                .iter()
+                .map(|(index, device)| (*index, device.mac.clone()))
+                .collect();
+            self.worker.submit(move |backend: &mut B| {
+                BluetoothReport::Observed(
+                    macs.into_iter()
+                        .map(|(index, mac)| (index, backend.is_connected(&mac)))
+                        .collect(),
+                )
+            })?;
+        }
+        self.update_display()
+    }
+
+    // A failed toggle stays amber until the device is seen to change or the
+    // key is pressed again; a read alone would hide it within a tick.
+    fn poll_reports(&mut self) {
+        for report in self.worker.finished() {
+            match report {
+                BluetoothReport::Toggled {
+                    index,
+                    target,
+                    result,
+                } => {
+                    let Some(device) = self.devices.get_mut(&index) else {
+                        continue;
+                    };
+                    device.toggling = false;
+                    match result {
+                        Ok(()) => {
+                            info!(device = %device.name, connected = target, "toggled bluetooth device");
+                            device.connected = Some(target);
+                        }
+                        Err(err) => {
+                            warn!(error = %err, device = %device.name, "failed to toggle bluetooth device");
+                            device.failed = true;
+                            device.connected = Some(!target);
+                            notify_toggle_failure(&device.name, target, &err);
+                        }
+                    }
+                }
+                BluetoothReport::Observed(readings) => {
+                    for (index, reading) in readings {
+                        let Some(device) = self.devices.get_mut(&index) else {
+                            continue;
+                        };
+                        // A read queued before a press says nothing about it.
+                        if device.toggling {
+                            continue;
+                        }
+                        match reading {
+                            Ok(connected) => {
+                                if !device.failed || device.connected != Some(connected) {
+                                    device.failed = false;
+                                    device.connected = Some(connected);
+                                }
+                            }
+                            Err(err) => {
+                                if device.connected.is_some() || !device.failed {
+                                    warn!(error = %err, device = %device.name, "failed to query bluetooth device");
+                                }
+                                device.failed = true;
+                                device.connected = None;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn update_display(&mut self) -> Result<()> {
+        for (index, device) in &mut self.devices {
+            let state = if !self.available {
+                BluetoothState::Unavailable
+            } else if device.toggling {
+                BluetoothState::Connecting
+            } else if device.failed {
+                BluetoothState::Failed
+            } else if device.connected == Some(true) {
+                BluetoothState::Connected
+            } else {
+                BluetoothState::Disconnected
+            };
+            if device.state == Some(state) {
+                continue;
+            }
+            device.state = Some(state);
+
+            let icon = match state {
+                BluetoothState::Connected => &device.icons.connected,
+                BluetoothState::Disconnected => &device.icons.disconnected,
+                BluetoothState::Connecting => &device.icons.connecting,
+                BluetoothState::Failed => &device.icons.failed,
+                BluetoothState::Unavailable => &device.icons.unavailable,
+            };
+            self.hardware
+                .update_button_icon(*index, Some(icon.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<H> BluetoothController<BluetoothctlBackend, H>
+where
+    H: DisplayPipeline,
+{
+    pub fn with_default_backend(
+        configs: &[BluetoothConfig],
+        config_path: Option<&Path>,
+        hardware: H,
+    ) -> Result<Option<Self>> {
+        let icon_paths = IconPaths::new(config_path);
+        BluetoothController::new(configs, BluetoothctlBackend, hardware, &icon_paths)?
+            .map(BluetoothController::in_background)
+            .transpose()
+    }
+}
+
+fn device_icons(config: &BluetoothConfig, icon_paths: &IconPaths) -> Result<DeviceIcons> {
+    let base = match config.icon.as_ref() {
+        Some(icon) => load_configured_icon(icon, icon_paths)?,
+        None => load_material_icon(MaterialIcon::Bluetooth, icon_paths)?,
+    };
+    let index = config.button_index;
+    let tinted = |suffix: &str, tint: [u8; 3], label: Option<&str>| ButtonImage {
+        id: format!("bluetooth-{index}-{suffix}"),
+        image: base.image.clone(),
+        tint: Some(tint),
+        label: label.map(str::to_string).or_else(|| config.name.clone()),
+        animation: None,
+        background: base.background,
+        badge: None,
+    };
+    Ok(DeviceIcons {
+        connected: tinted("connected", ACTIVE_TINT, None),
+        disconnected: tinted("disconnected", AVAILABLE_TINT, None),
+        connecting: tinted("connecting", CONNECTING_TINT, Some("connecting")),
+        failed: tinted("error", DEGRADED_TINT, None),
+        unavailable: tinted("unavailable", UNAVAILABLE_TINT, None),
+    })
+}
+
+fn notify_toggle_failure(name: &str, target: bool, error: &anyhow::Error) {
+    let title = "Stream Deck Bluetooth";
+    let verb = if target { "connect" } else { "disconnect" };
+    let body = format!("Failed to {verb} {name}:\n{error}");
+    match Command::new("notify-send").arg(title).arg(body).status() {
+        Ok(status) => {
+            if !status.success() {
+                warn!(code = ?status.code(), "notify-send exited with failure status");
+            }
+        }
+        Err(err) => {
+            warn!(error = %err, "failed to send bluetooth failure notification");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use crate::system::bluetooth::tests::MockBluetoothBackend;
+    use std::sync::{Arc, Mutex};
+
+    const HEADPHONES: &str = "AA:BB:CC:DD:EE:FF";
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, _index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons.lock().unwrap().push(icon.map(|icon| icon.id));
+            Ok(())
+        }
+    }
+
+    fn controller(
+        backend: MockBluetoothBackend,
+        hardware: RecordingHardware,
+    ) -> BluetoothController<MockBluetoothBackend, RecordingHardware> {
+        let configs = [
+            BluetoothConfig {
+                button_index: 5,
+                mac: HEADPHONES.to_string(),
+                name: Some("Headphones".to_string()),
+                icon: None,
+            },
+            BluetoothConfig {
+                button_index: 6,
+                mac: "not-a-mac".to_string(),
+                name: None,
+                icon: None,
+            },
+        ];
+        BluetoothController::new(&configs, backend, hardware, &IconPaths::new(None))
+            .unwrap()
+            .expect("controller")
+    }
+
+    fn last_icon(hardware: &RecordingHardware) -> Option<String> {
+        hardware.icons.lock().unwrap().last().unwrap().clone()
+    }
+
+    #[test]
+    fn press_connects_via_connecting_and_ticks_follow_other_devices() {
+        let backend = MockBluetoothBackend::default();
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+        assert_eq!(controller.button_indices().collect::<Vec<_>>(), [5]);
+        controller.on_tick().unwrap();
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-disconnected")
+        );
+
+        assert!(!controller.on_button_pressed(6).unwrap());
+        assert!(controller.on_button_pressed(5).unwrap());
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-connecting")
+        );
+        controller.on_tick().unwrap();
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-connected")
+        );
+
+        // Disconnected from the phone.
+        backend
+            .connected
+            .lock()
+            .unwrap()
+            .insert(HEADPHONES.to_string(), false);
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-disconnected")
+        );
+    }
+
+    #[test]
+    fn failed_connect_stays_amber_until_the_device_changes() {
+        let backend = MockBluetoothBackend::default();
+        backend
+            .script
+            .lock()
+            .unwrap()
+            .push_back(Err("br-connection-page-timeout".to_string()));
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+
+        assert!(controller.on_button_pressed(5).unwrap());
+        controller.on_tick().unwrap();
+        assert_eq!(last_icon(&hardware).as_deref(), Some("bluetooth-5-error"));
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        assert_eq!(last_icon(&hardware).as_deref(), Some("bluetooth-5-error"));
+
+        assert!(controller.on_button_pressed(5).unwrap());
+        controller.on_tick().unwrap();
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-connected")
+        );
+    }
+
+    #[test]
+    fn missing_bluetoothctl_shows_disabled_icon() {
+        let backend = MockBluetoothBackend {
+            unavailable: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+        assert!(controller.on_button_pressed(5).unwrap());
+        assert_eq!(
+            last_icon(&hardware).as_deref(),
+            Some("bluetooth-5-unavailable")
+        );
+        assert!(backend.connected.lock().unwrap().is_empty());
+    }
+}
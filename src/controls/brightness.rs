@@ -1,33 +1,167 @@
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::time::Duration;
 
 use anyhow::{Result, anyhow};
-use crossbeam_channel::{Receiver, TryRecvError, bounded};
-use tracing::warn;
+use chrono::Timelike;
+use crossbeam_channel::{Receiver, Sender, TryRecvError, unbounded};
+use serde::Deserialize;
+use tracing::{info, warn};
 
-use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+use crate::config::ClockTime;
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId, ProgressStyle};
 use crate::system::brightness::BrightnessBackend;
 
+use super::transient::TransientStatus;
 use super::{EncoderController, Tickable};
 
+const RECONCILE_TOLERANCE: u8 = 1;
+const SAVED_STATUS_TICKS: u32 = 2;
+const FAILED_STATUS_TICKS: u32 = 4;
+const RESET_STATUS_TICKS: u32 = 2;
+const HOLD_CYCLE_MIN: Duration = Duration::from_millis(600);
+const MAX_MONITOR_LABEL_CHARS: usize = 10;
+// Deck backlight at the lowest monitor level, so its keys stay readable.
+const DECK_SYNC_MIN: u8 = 10;
+const RAMP_STEP: u8 = 5;
+const RAMP_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BrightnessConfig {
+    #[serde(alias = "brightness_backend")]
+    pub backend: BrightnessBackendKind,
+    /// Device under `/sys/class/backlight`; the first one when unset.
+    pub backlight_device: Option<String>,
+    /// Drive every monitor reported by `ddcutil detect` instead of one display.
+    #[serde(alias = "all_monitors")]
+    pub multi_monitor: bool,
+    #[serde(alias = "cycle_button_index")]
+    pub cycle_button: Option<u8>,
+    pub night_start: Option<ClockTime>,
+    pub night_end: Option<ClockTime>,
+    /// Applies that move further than this many points fade in steps.
+    #[serde(alias = "fade_threshold")]
+    pub ramp_threshold: Option<u8>,
+    /// Dims and brightens the deck's own backlight along with the monitor.
+    pub sync_deck_brightness: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrightnessBackendKind {
+    #[default]
+    Ddcutil,
+    Backlight,
+    /// The backlight device when one exists, otherwise ddcutil.
+    Auto,
+}
+
+impl BrightnessConfig {
+    pub fn night_schedule(&self) -> Option<NightSchedule> {
+        Some(NightSchedule {
+            start: self.night_start?,
+            end: self.night_end?,
+        })
+    }
+
+    pub fn ramp(&self) -> Option<BrightnessRamp> {
+        self.ramp_threshold.map(BrightnessRamp::new)
+    }
+}
+
+/// Steps the backend through intermediate levels when an apply moves
+/// further than `threshold`, instead of jumping straight to the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrightnessRamp {
+    pub threshold: u8,
+    pub step: u8,
+    pub interval: Duration,
+}
+
+impl BrightnessRamp {
+    pub fn new(threshold: u8) -> Self {
+        Self {
+            threshold,
+            step: RAMP_STEP,
+            interval: RAMP_INTERVAL,
+        }
+    }
+}
+
+/// Daily window during which the night level is applied automatically. A
+/// window whose end is earlier than its start runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightSchedule {
+    pub start: ClockTime,
+    pub end: ClockTime,
+}
+
+impl NightSchedule {
+    pub fn contains(&self, now: ClockTime) -> bool {
+        if self.start <= self.end {
+            self.start <= now && now < self.end
+        } else {
+            now >= self.start || now < self.end
+        }
+    }
+}
+
+pub struct MonitorTarget<B> {
+    pub backend: B,
+    pub label: String,
+    last_level: Option<u8>,
+    night_restore: Option<u8>,
+}
+
+impl<B> MonitorTarget<B> {
+    pub fn new(backend: B, label: impl Into<String>) -> Self {
+        Self {
+            backend,
+            label: label.into(),
+            last_level: None,
+            night_restore: None,
+        }
+    }
+}
+
 pub struct BrightnessController<B, D>
 where
     B: BrightnessBackend,
     D: DisplayPipeline,
 {
-    backend: B,
-    display: D,
-    encoder: EncoderId,
+    monitors: Vec<MonitorTarget<B>>,
+    selected: usize,
+    status: TransientStatus<D>,
     step: u8,
     min_level: u8,
     max_level: u8,
     level: u8,
     pending_level: u8,
     pending_dirty: bool,
-    apply_inflight: Option<u8>,
-    apply_rx: Option<Receiver<Result<u8>>>,
+    apply_inflight: Option<(usize, u8)>,
+    // Only applies committed from the encoder report back on the LCD.
+    confirm_apply: bool,
+    settled: Option<ApplyOutcome>,
+    worker: Option<BrightnessWorker>,
+    ramp: Option<BrightnessRamp>,
+    reconcile_interval: u32,
+    ticks_since_reconcile: u32,
+    reconcile_inflight: bool,
     night_level: u8,
     previous_level: u8,
+    night_schedule: Option<NightSchedule>,
+    in_night_window: bool,
+    auto_night: bool,
+    manual_override: bool,
     available: bool,
+    default_level: Option<u8>,
+    deck_sync: bool,
+    gradient: Option<ProgressStyle>,
+    /// The deck backlight last sent, so only changes are.
+    deck_level: Cell<Option<u8>>,
 }
 
 impl<B, D> BrightnessController<B, D>
@@ -44,11 +178,43 @@ where
         max_level: u8,
         night_level: u8,
     ) -> Result<Self> {
-        let initial_available = backend.is_available();
-        let mut controller = Self {
-            backend,
+        Self::for_monitors(
+            vec![MonitorTarget::new(backend, "")],
             display,
             encoder,
+            step,
+            min_level,
+            max_level,
+            night_level,
+        )
+    }
+
+    /// With more than one monitor, turning adjusts the selected monitor and a
+    /// long press (or [`Self::cycle_monitor`]) moves the selection on.
+    pub fn for_monitors(
+        mut monitors: Vec<MonitorTarget<B>>,
+        display: D,
+        encoder: EncoderId,
+        step: u8,
+        min_level: u8,
+        max_level: u8,
+        night_level: u8,
+    ) -> Result<Self> {
+        if monitors.is_empty() {
+            return Err(anyhow!("brightness controller needs at least one monitor"));
+        }
+        for monitor in monitors.iter_mut().skip(1) {
+            monitor.last_level = monitor
+                .backend
+                .is_available()
+                .then(|| monitor.backend.get_brightness().ok())
+                .flatten();
+        }
+        let initial_available = monitors[0].backend.is_available();
+        let mut controller = Self {
+            monitors,
+            selected: 0,
+            status: TransientStatus::new(display, encoder),
             step: step.max(1),
             min_level,
             max_level: max_level.max(min_level + 1),
@@ -56,22 +222,140 @@ where
             pending_level: min_level,
             pending_dirty: false,
             apply_inflight: None,
-            apply_rx: None,
+            confirm_apply: false,
+            settled: None,
+            worker: None,
+            ramp: None,
+            reconcile_interval: 0,
+            ticks_since_reconcile: 0,
+            reconcile_inflight: false,
             night_level: night_level.clamp(min_level, max_level),
             previous_level: max_level,
+            night_schedule: None,
+            in_night_window: false,
+            auto_night: false,
+            manual_override: false,
             available: initial_available,
+            default_level: None,
+            deck_sync: false,
+            gradient: None,
+            deck_level: Cell::new(None),
         };
         controller.refresh_state()?;
         Ok(controller)
     }
 
+    pub fn with_reconcile_interval(mut self, ticks: u32) -> Self {
+        self.reconcile_interval = ticks;
+        self
+    }
+
+    /// Only takes effect before the first apply, when the worker starts.
+    pub fn with_ramp(mut self, ramp: Option<BrightnessRamp>) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    /// The level a long press snaps to. Only used with a single monitor,
+    /// where holding does not already cycle monitors.
+    pub fn with_default_level(mut self, level: Option<u8>) -> Self {
+        self.default_level = level;
+        self
+    }
+
+    /// Colours the level bar with a `ProgressStyle::Gradient`.
+    pub fn with_progress_gradient(mut self, gradient: Option<ProgressStyle>) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    pub fn with_deck_sync(mut self, sync: bool) -> Self {
+        self.set_deck_sync(sync);
+        self
+    }
+
+    /// Makes the deck's backlight follow the monitor level, mapped from
+    /// `min_level..=max_level` onto `DECK_SYNC_MIN..=100`.
+    pub fn set_deck_sync(&mut self, sync: bool) {
+        self.deck_sync = sync;
+        self.resync_deck();
+    }
+
+    pub fn syncs_deck(&self) -> bool {
+        self.deck_sync
+    }
+
+    /// Sends the synced deck level again, e.g. after something else changed
+    /// the backlight.
+    pub fn resync_deck(&self) {
+        self.deck_level.set(None);
+        self.sync_deck();
+    }
+
+    pub fn with_night_schedule(mut self, schedule: Option<NightSchedule>) -> Self {
+        self.set_night_schedule(schedule);
+        self
+    }
+
+    /// Takes effect on the next tick; removing the schedule while inside the
+    /// window counts as leaving it.
+    pub fn set_night_schedule(&mut self, schedule: Option<NightSchedule>) {
+        self.night_schedule = schedule;
+    }
+
+    fn backend(&self) -> &B {
+        &self.monitors[self.selected].backend
+    }
+
+    fn is_multi_monitor(&self) -> bool {
+        self.monitors.len() > 1
+    }
+
+    /// Selects the next monitor. Its level is shown from the last known value
+    /// and corrected by a background read, so the event loop never blocks on
+    /// ddcutil here.
+    pub fn cycle_monitor(&mut self) -> Result<()> {
+        self.poll_apply()?;
+        if !self.is_multi_monitor() {
+            return Ok(());
+        }
+
+        self.monitors[self.selected].last_level = Some(self.level);
+        self.selected = (self.selected + 1) % self.monitors.len();
+        info!(monitor = %self.monitors[self.selected].label, "selected monitor for brightness");
+
+        // An apply still in flight belongs to the previous monitor; its report
+        // no longer matches and is dropped in `settle_apply`.
+        self.apply_inflight = None;
+        self.status.cancel();
+        self.pending_dirty = false;
+        if let Some(level) = self.monitors[self.selected].last_level {
+            self.level = level.clamp(self.min_level, self.max_level);
+        }
+        self.pending_level = self.level;
+        self.previous_level = self.level.max(self.night_level + 1);
+        self.ticks_since_reconcile = 0;
+        if self
+            .send_to_worker(WorkerRequest::Read(self.selected))
+            .is_ok()
+        {
+            self.reconcile_inflight = true;
+        }
+
+        self.available = self.backend().is_available();
+        if !self.available {
+            return self.push_unavailable_display();
+        }
+        self.push_display()
+    }
+
     fn refresh_state(&mut self) -> Result<()> {
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         if !self.available {
             return self.push_unavailable_display();
         }
 
-        let current = match self.backend.get_brightness() {
+        let current = match self.backend().get_brightness() {
             Ok(value) => value,
             Err(err) => {
                 warn!(
@@ -79,7 +363,7 @@ where
                     "failed to query brightness; defaulting to {}%",
                     self.max_level
                 );
-                self.available = self.backend.is_available();
+                self.available = self.backend().is_available();
                 self.max_level
             }
         };
@@ -87,9 +371,8 @@ where
         self.pending_level = self.level;
         self.pending_dirty = false;
         self.apply_inflight = None;
-        self.apply_rx = None;
         self.previous_level = self.level;
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         if !self.available {
             return self.push_unavailable_display();
         }
@@ -97,6 +380,24 @@ where
     }
 
     fn push_display(&self) -> Result<()> {
+        self.sync_deck();
+        self.status.push_base(self.level_display())
+    }
+
+    fn sync_deck(&self) {
+        if !self.deck_sync {
+            return;
+        }
+        let level = deck_level_for(self.level, self.min_level, self.max_level);
+        if self.deck_level.replace(Some(level)) == Some(level) {
+            return;
+        }
+        if let Err(err) = self.status.display().set_device_brightness(level) {
+            warn!(error = %err, "failed to change device brightness");
+        }
+    }
+
+    fn level_display(&self) -> EncoderDisplay {
         let display_level = if self.pending_dirty {
             self.pending_level
         } else {
@@ -110,110 +411,306 @@ where
             0.0
         };
         display.progress = Some(progress);
+        display.progress_style = self.gradient.clone();
 
+        let monitor = self
+            .is_multi_monitor()
+            .then(|| short_monitor_label(&self.monitors[self.selected].label));
         if self.pending_dirty {
             display.status = Some("pending".into());
         } else if self.apply_inflight.is_some() {
             display.status = Some("apply".into());
         } else if display_level <= self.night_level {
-            display.status = Some("night".into());
+            let night = if self.auto_night {
+                "night (auto)"
+            } else {
+                "night"
+            };
+            display.status = Some(match monitor {
+                Some(monitor) => format!("{night} · {monitor}"),
+                None => night.into(),
+            });
+        } else {
+            display.status = monitor;
         }
-
-        self.display.update_encoder(self.encoder, display)
+        display
     }
 
-    fn push_unavailable_display(&self) -> Result<()> {
+    fn unavailable_display() -> EncoderDisplay {
         let mut display = EncoderDisplay::new("bright", "N/A");
         display.status = Some("ddc disabled".into());
         display.progress = Some(0.0);
-        self.display.update_encoder(self.encoder, display)
+        display
+    }
+
+    fn base_display(&self) -> EncoderDisplay {
+        if self.available {
+            self.level_display()
+        } else {
+            Self::unavailable_display()
+        }
+    }
+
+    fn push_unavailable_display(&self) -> Result<()> {
+        self.status.push_base(Self::unavailable_display())
+    }
+
+    // Shows whether the last committed level reached the monitor, then falls
+    // back to the normal status on a later tick.
+    fn show_outcome(&mut self, outcome: ApplyOutcome) -> Result<()> {
+        let (label, ticks) = match outcome {
+            ApplyOutcome::Saved => ("saved", SAVED_STATUS_TICKS),
+            ApplyOutcome::Failed => ("failed", FAILED_STATUS_TICKS),
+        };
+        let mut display = self.level_display();
+        display.status = Some(if self.is_multi_monitor() {
+            format!(
+                "{label} · {}",
+                short_monitor_label(&self.monitors[self.selected].label)
+            )
+        } else {
+            label.into()
+        });
+        self.status.show(display, ticks)
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
+
+    pub fn sync(&mut self) -> Result<()> {
+        self.poll_apply()?;
+        if self.apply_inflight.is_some() {
+            return self.push_display();
+        }
+        self.refresh_state()
     }
 
     fn poll_apply(&mut self) -> Result<()> {
-        let mut finished = false;
-        let mut outcome = None;
-
-        if let Some(rx) = self.apply_rx.as_ref() {
-            match rx.try_recv() {
-                Ok(result) => {
-                    finished = true;
-                    outcome = Some(result);
-                }
-                Err(TryRecvError::Empty) => {}
+        let Some(worker) = self.worker.as_ref() else {
+            return Ok(());
+        };
+
+        let mut reports = Vec::new();
+        let mut disconnected = false;
+        loop {
+            match worker.reports.try_recv() {
+                Ok(report) => reports.push(report),
+                Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => {
-                    finished = true;
-                    outcome = Some(Err(anyhow!("brightness worker disconnected")));
+                    disconnected = true;
+                    break;
                 }
             }
         }
+        if disconnected {
+            self.worker = None;
+            self.reconcile_inflight = false;
+            if let Some((monitor, target)) = self.apply_inflight {
+                reports.push(WorkerReport::Applied(
+                    monitor,
+                    target,
+                    Err(anyhow!("brightness worker disconnected")),
+                ));
+            }
+        }
 
-        if !finished {
+        // Reports arrive in the order the worker handled them, so a read taken
+        // before a newer apply is seen while that apply is still in flight.
+        let mut changed = false;
+        for report in reports {
+            changed |= match report {
+                WorkerReport::Applied(monitor, target, outcome) => {
+                    self.settle_apply(monitor, target, outcome)
+                }
+                WorkerReport::Observed(monitor, outcome) => self.reconcile(monitor, outcome),
+                // A ramp only stops for a newer level, which settles on its own;
+                // should it still be the one in flight, stop waiting for it.
+                WorkerReport::Stopped(monitor, target) => {
+                    let current = self.apply_inflight == Some((monitor, target));
+                    if current {
+                        self.apply_inflight = None;
+                        self.confirm_apply = false;
+                    }
+                    current
+                }
+            };
+        }
+        if !changed {
             return Ok(());
         }
 
-        self.apply_rx = None;
+        let settled = self.settled.take();
+        self.available = self.backend().is_available();
+        if !self.available {
+            self.push_unavailable_display()
+        } else if let Some(outcome) = settled {
+            self.show_outcome(outcome)
+        } else {
+            self.push_display()
+        }
+    }
+
+    // Completions for superseded targets, or for a monitor that is no longer
+    // selected, are dropped; only the latest committed level settles the
+    // controller state.
+    fn settle_apply(&mut self, monitor: usize, target: u8, outcome: Result<u8>) -> bool {
+        if self.apply_inflight != Some((monitor, target)) {
+            return false;
+        }
         self.apply_inflight = None;
+        let confirm = std::mem::take(&mut self.confirm_apply);
 
-        match outcome.unwrap_or_else(|| Ok(self.level)) {
+        match outcome {
             Ok(applied) => {
                 self.level = applied;
                 self.pending_level = applied;
                 if applied > self.night_level {
                     self.previous_level = applied;
                 }
+                self.settled = confirm.then_some(ApplyOutcome::Saved);
             }
             Err(err) => {
                 warn!(error = %err, "failed to apply brightness");
+                self.settled = confirm.then_some(ApplyOutcome::Failed);
             }
         }
+        true
+    }
 
-        self.available = self.backend.is_available();
-        if !self.available {
-            self.push_unavailable_display()
-        } else {
-            self.push_display()
+    fn reconcile(&mut self, monitor: usize, outcome: Result<u8>) -> bool {
+        if monitor != self.selected {
+            return false;
+        }
+        self.reconcile_inflight = false;
+        if self.pending_dirty || self.apply_inflight.is_some() || !self.backend().is_available() {
+            return false;
+        }
+
+        let observed = match outcome {
+            Ok(value) => value,
+            Err(err) => {
+                warn!(error = %err, "failed to read back monitor brightness");
+                return false;
+            }
+        };
+        if observed.abs_diff(self.level) <= RECONCILE_TOLERANCE {
+            return false;
+        }
+
+        info!(
+            displayed = self.level,
+            observed, "monitor brightness changed externally"
+        );
+        self.level = observed;
+        self.pending_level = observed;
+        if observed > self.night_level {
+            self.previous_level = observed;
         }
+        true
     }
 
-    fn enqueue_apply(&mut self, target: u8) -> Result<()> {
-        self.apply_rx = None;
-        let (tx, rx) = bounded(1);
-        let backend = self.backend.clone();
-        thread::spawn(move || {
-            let result = backend.set_brightness(target);
-            let _ = tx.send(result);
+    fn request_reconcile(&mut self) {
+        if self.reconcile_interval == 0 {
+            return;
+        }
+        self.ticks_since_reconcile = self.ticks_since_reconcile.saturating_add(1);
+        if self.ticks_since_reconcile < self.reconcile_interval
+            || self.pending_dirty
+            || self.apply_inflight.is_some()
+            || self.reconcile_inflight
+            || !self.backend().is_available()
+        {
+            return;
+        }
+
+        self.ticks_since_reconcile = 0;
+        if self
+            .send_to_worker(WorkerRequest::Read(self.selected))
+            .is_ok()
+        {
+            self.reconcile_inflight = true;
+        }
+    }
+
+    fn send_to_worker(&mut self, request: WorkerRequest) -> Result<()> {
+        let (monitors, selected, level, ramp) =
+            (&self.monitors, self.selected, self.level, self.ramp);
+        let worker = self.worker.get_or_insert_with(|| {
+            let known = monitors
+                .iter()
+                .enumerate()
+                .map(|(index, monitor)| {
+                    if index == selected {
+                        Some(level)
+                    } else {
+                        monitor.last_level
+                    }
+                })
+                .collect();
+            BrightnessWorker::spawn(
+                monitors
+                    .iter()
+                    .map(|monitor| monitor.backend.clone())
+                    .collect(),
+                known,
+                ramp,
+            )
         });
-        self.apply_rx = Some(rx);
-        self.apply_inflight = Some(target);
+        if worker.requests.send(request).is_err() {
+            self.worker = None;
+            return Err(anyhow!("brightness worker disconnected"));
+        }
+        Ok(())
+    }
+
+    // A different target replaces any ramp still running; re-committing the
+    // same target lets it finish.
+    fn enqueue_apply(&mut self, target: u8) -> Result<()> {
+        if self.apply_inflight != Some((self.selected, target)) {
+            self.cancel_ramp();
+        }
+        self.send_to_worker(WorkerRequest::Apply(self.selected, target))?;
+        self.status.cancel();
+        self.confirm_apply = false;
+        self.apply_inflight = Some((self.selected, target));
         self.pending_dirty = false;
         self.pending_level = target;
         self.level = target;
         if target > self.night_level {
             self.previous_level = target;
         }
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         self.push_display()
     }
 
     fn preview_level(&mut self, level: i32) -> Result<()> {
         self.poll_apply()?;
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         if !self.available {
             return self.push_unavailable_display();
         }
 
         let clamped = level.clamp(self.min_level as i32, self.max_level as i32) as u8;
+        self.status.cancel();
         self.pending_level = clamped;
         self.pending_dirty = self.pending_level != self.level;
         if self.pending_dirty {
+            self.cancel_ramp();
             self.apply_inflight = None;
         }
         self.push_display()
     }
 
+    fn cancel_ramp(&self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
     fn set_level(&mut self, level: i32) -> Result<()> {
         self.poll_apply()?;
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         if !self.available {
             return self.push_unavailable_display();
         }
@@ -221,6 +718,281 @@ where
         let clamped = level.clamp(self.min_level as i32, self.max_level as i32) as u8;
         self.enqueue_apply(clamped)
     }
+
+    fn commit_or_toggle_night(&mut self) -> Result<()> {
+        self.poll_apply()?;
+        self.available = self.backend().is_available();
+        if !self.available {
+            return self.push_unavailable_display();
+        }
+        self.note_manual_change();
+
+        let target = if self.pending_dirty {
+            self.pending_level
+        } else if self.level <= self.night_level {
+            self.previous_level.max(self.night_level + 1)
+        } else {
+            self.previous_level = self.level;
+            self.night_level
+        };
+        self.set_level(target as i32)?;
+        self.confirm_apply = self.apply_inflight.is_some();
+        Ok(())
+    }
+
+    // Within the window, a manual change wins until the window ends.
+    fn note_manual_change(&mut self) {
+        if self.in_night_window {
+            self.manual_override = true;
+        }
+        self.auto_night = false;
+    }
+
+    fn apply_night_schedule(&mut self, now: ClockTime) -> Result<()> {
+        let inside = self
+            .night_schedule
+            .is_some_and(|schedule| schedule.contains(now));
+        if inside == self.in_night_window {
+            return Ok(());
+        }
+        self.in_night_window = inside;
+        if inside {
+            self.enter_scheduled_night()
+        } else {
+            self.leave_scheduled_night()
+        }
+    }
+
+    fn enter_scheduled_night(&mut self) -> Result<()> {
+        info!(level = self.night_level, "night schedule started");
+        let night_level = self.night_level;
+        let mut applies = Vec::new();
+        for (index, monitor) in self.monitors.iter_mut().enumerate() {
+            let current = if index == self.selected {
+                Some(self.level)
+            } else {
+                monitor.last_level
+            };
+            monitor.night_restore = current.filter(|level| *level > night_level);
+            if index != self.selected && monitor.night_restore.is_some() {
+                monitor.last_level = Some(night_level);
+                applies.push(WorkerRequest::Apply(index, night_level));
+            }
+        }
+        for request in applies {
+            self.send_to_worker(request)?;
+        }
+
+        self.auto_night = true;
+        if self.monitors[self.selected].night_restore.is_some() {
+            self.set_level(night_level as i32)
+        } else {
+            self.push_display()
+        }
+    }
+
+    fn leave_scheduled_night(&mut self) -> Result<()> {
+        let restore = !self.manual_override;
+        self.manual_override = false;
+        self.auto_night = false;
+        info!(restore, "night schedule ended");
+
+        let mut applies = Vec::new();
+        let mut selected_restore = None;
+        for (index, monitor) in self.monitors.iter_mut().enumerate() {
+            let Some(level) = monitor.night_restore.take().filter(|_| restore) else {
+                continue;
+            };
+            if index == self.selected {
+                selected_restore = Some(level);
+            } else {
+                monitor.last_level = Some(level);
+                applies.push(WorkerRequest::Apply(index, level));
+            }
+        }
+        for request in applies {
+            self.send_to_worker(request)?;
+        }
+
+        match selected_restore {
+            Some(level) => self.set_level(level as i32),
+            None => self.push_display(),
+        }
+    }
+}
+
+fn local_clock_time() -> ClockTime {
+    let now = chrono::Local::now();
+    ClockTime {
+        hour: now.hour() as u8,
+        minute: now.minute() as u8,
+    }
+}
+
+fn short_monitor_label(label: &str) -> String {
+    label.trim().chars().take(MAX_MONITOR_LABEL_CHARS).collect()
+}
+
+fn deck_level_for(level: u8, min_level: u8, max_level: u8) -> u8 {
+    let span = u32::from(max_level.saturating_sub(min_level).max(1));
+    let offset = u32::from(level.clamp(min_level, max_level) - min_level);
+    let deck_span = u32::from(100 - DECK_SYNC_MIN);
+    DECK_SYNC_MIN + (offset * deck_span / span) as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApplyOutcome {
+    Saved,
+    Failed,
+}
+
+enum WorkerRequest {
+    Apply(usize, u8),
+    Read(usize),
+}
+
+enum WorkerReport {
+    Applied(usize, u8, Result<u8>),
+    Observed(usize, Result<u8>),
+    Stopped(usize, u8),
+}
+
+enum RampEnd {
+    Reached(u8),
+    Stopped(u8),
+}
+
+struct BrightnessWorker {
+    requests: Sender<WorkerRequest>,
+    reports: Receiver<WorkerReport>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl BrightnessWorker {
+    // A single thread owns all ddcutil traffic so applies never race on the bus
+    // and reconcile reads stay off the event loop. Queued applies are coalesced
+    // to the newest per monitor before each write; every request carries the
+    // monitor it was issued for, so switching monitors never redirects a write.
+    // `known` tracks each monitor's last written or read level so a ramp knows
+    // where to start.
+    fn spawn<B>(backends: Vec<B>, mut known: Vec<Option<u8>>, ramp: Option<BrightnessRamp>) -> Self
+    where
+        B: BrightnessBackend + Send + 'static,
+    {
+        let (requests, request_rx) = unbounded::<WorkerRequest>();
+        let (report_tx, reports) = unbounded();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::clone(&cancel);
+        thread::spawn(move || {
+            while let Ok(first) = request_rx.recv() {
+                let mut targets: Vec<Option<u8>> = vec![None; backends.len()];
+                let mut reads = Vec::new();
+                for request in std::iter::once(first).chain(request_rx.try_iter()) {
+                    match request {
+                        WorkerRequest::Apply(monitor, value) => targets[monitor] = Some(value),
+                        WorkerRequest::Read(monitor) if !reads.contains(&monitor) => {
+                            reads.push(monitor)
+                        }
+                        WorkerRequest::Read(_) => {}
+                    }
+                }
+                cancelled.store(false, Ordering::SeqCst);
+
+                for (monitor, target) in targets.into_iter().enumerate() {
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    let backend = &backends[monitor];
+                    let ramped = match (ramp, known[monitor]) {
+                        (Some(ramp), Some(from)) => {
+                            ramp_brightness(backend, from, target, ramp, &cancelled)
+                        }
+                        _ => backend.set_brightness(target).map(RampEnd::Reached),
+                    };
+                    let mut result = match ramped {
+                        Ok(RampEnd::Stopped(level)) => {
+                            known[monitor] = Some(level);
+                            if report_tx
+                                .send(WorkerReport::Stopped(monitor, target))
+                                .is_err()
+                            {
+                                return;
+                            }
+                            continue;
+                        }
+                        Ok(RampEnd::Reached(applied)) => Ok(applied),
+                        Err(err) => Err(err),
+                    };
+                    if let Ok(applied) = result {
+                        known[monitor] = Some(applied);
+                    }
+                    if matches!(result, Ok(applied) if applied != target) {
+                        warn!(
+                            target,
+                            "monitor brightness diverged after apply; retrying once"
+                        );
+                        result = backend.set_brightness(target);
+                    }
+                    if report_tx
+                        .send(WorkerReport::Applied(monitor, target, result))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+
+                for monitor in reads {
+                    let observed = backends[monitor].get_brightness();
+                    if let Ok(level) = observed {
+                        known[monitor] = Some(level);
+                    }
+                    if report_tx
+                        .send(WorkerReport::Observed(monitor, observed))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+        Self {
+            requests,
+            reports,
+            cancel,
+        }
+    }
+}
+
+// Only `cancelled`, set when a different level is committed, stops a ramp
+// early; other queued requests wait for it to finish.
+fn ramp_brightness<B: BrightnessBackend>(
+    backend: &B,
+    from: u8,
+    target: u8,
+    ramp: BrightnessRamp,
+    cancelled: &AtomicBool,
+) -> Result<RampEnd> {
+    if from.abs_diff(target) <= ramp.threshold {
+        return backend.set_brightness(target).map(RampEnd::Reached);
+    }
+    let step = ramp.step.max(1);
+    let mut level = from;
+    while level.abs_diff(target) > step {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(RampEnd::Stopped(level));
+        }
+        level = if target > level {
+            level + step
+        } else {
+            level - step
+        };
+        backend.set_brightness(level)?;
+        thread::sleep(ramp.interval);
+    }
+    if cancelled.load(Ordering::SeqCst) {
+        return Ok(RampEnd::Stopped(level));
+    }
+    backend.set_brightness(target).map(RampEnd::Reached)
 }
 
 impl<B, D> EncoderController for BrightnessController<B, D>
@@ -230,7 +1002,7 @@ where
 {
     fn on_turn(&mut self, delta: i32) -> Result<()> {
         self.poll_apply()?;
-        self.available = self.backend.is_available();
+        self.available = self.backend().is_available();
         if !self.available {
             return self.push_unavailable_display();
         }
@@ -238,6 +1010,7 @@ where
         if delta == 0 {
             return Ok(());
         }
+        self.note_manual_change();
         let magnitude = (delta.abs() as u32)
             .saturating_mul(self.step as u32)
             .min(u8::MAX as u32) as i32;
@@ -246,28 +1019,40 @@ where
     }
 
     fn on_press(&mut self) -> Result<()> {
-        self.poll_apply()?;
-        self.available = self.backend.is_available();
-        if !self.available {
-            return self.push_unavailable_display();
+        if self.is_multi_monitor() {
+            // Tap vs hold is decided on release.
+            return self.poll_apply();
         }
+        self.commit_or_toggle_night()
+    }
 
-        if self.pending_dirty {
-            return self.set_level(self.pending_level as i32);
+    fn on_release(&mut self, held: Duration) -> Result<()> {
+        if !self.is_multi_monitor() {
+            return self.poll_apply();
         }
-
-        if self.level <= self.night_level {
-            let restore = self.previous_level.max(self.night_level + 1);
-            self.set_level(restore as i32)
+        if held >= HOLD_CYCLE_MIN {
+            self.cycle_monitor()
         } else {
-            self.previous_level = self.level;
-            self.set_level(self.night_level as i32)
+            self.commit_or_toggle_night()
         }
     }
 
-    fn on_release(&mut self) -> Result<()> {
-        self.poll_apply()?;
-        Ok(())
+    fn has_long_press(&self) -> bool {
+        self.default_level.is_some() && !self.is_multi_monitor()
+    }
+
+    fn on_long_press(&mut self) -> Result<()> {
+        let Some(level) = self.default_level.filter(|_| !self.is_multi_monitor()) else {
+            return self.on_press();
+        };
+        self.note_manual_change();
+        self.set_level(level as i32)?;
+        if !self.available {
+            return Ok(());
+        }
+        let mut display = self.level_display();
+        display.status = Some("reset".into());
+        self.status.show(display, RESET_STATUS_TICKS)
     }
 }
 
@@ -277,7 +1062,14 @@ where
     D: DisplayPipeline,
 {
     fn on_tick(&mut self) -> Result<()> {
-        self.poll_apply()
+        let base = self.base_display();
+        self.status.on_tick(|| base)?;
+        self.poll_apply()?;
+        if self.night_schedule.is_some() || self.in_night_window {
+            self.apply_night_schedule(local_clock_time())?;
+        }
+        self.request_reconcile();
+        Ok(())
     }
 }
 
@@ -295,6 +1087,7 @@ mod tests {
     #[derive(Clone, Default)]
     struct TestDisplay {
         updates: Arc<Mutex<Vec<EncoderDisplay>>>,
+        deck_levels: Arc<Mutex<Vec<u8>>>,
     }
 
     impl DisplayPipeline for TestDisplay {
@@ -302,6 +1095,11 @@ mod tests {
             self.updates.lock().unwrap().push(display);
             Ok(())
         }
+
+        fn set_device_brightness(&self, level: u8) -> Result<()> {
+            self.deck_levels.lock().unwrap().push(level);
+            Ok(())
+        }
     }
 
     fn wait_for_apply(controller: &mut BrightnessController<MockBrightnessBackend, TestDisplay>) {
@@ -379,6 +1177,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deck_backlight_follows_committed_levels_when_synced() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            BrightnessController::new(backend, display.clone(), EncoderId::Two, 5, 10, 100, 15)
+                .expect("init");
+        controller.on_turn(-1).expect("turn");
+        assert!(display.deck_levels.lock().unwrap().is_empty());
+
+        let mut controller = controller.with_deck_sync(true);
+        controller.on_turn(-1).expect("preview turn");
+        assert_eq!(*display.deck_levels.lock().unwrap(), [60]);
+        controller.on_press().expect("commit press");
+        wait_for_apply(&mut controller);
+        assert_eq!(*display.deck_levels.lock().unwrap(), [60, 50]);
+
+        controller.resync_deck();
+        assert_eq!(*display.deck_levels.lock().unwrap(), [60, 50, 50]);
+    }
+
+    #[test]
+    fn deck_levels_span_the_monitor_range() {
+        assert_eq!(deck_level_for(20, 20, 100), DECK_SYNC_MIN);
+        assert_eq!(deck_level_for(60, 20, 100), 55);
+        assert_eq!(deck_level_for(100, 20, 100), 100);
+        assert_eq!(deck_level_for(5, 20, 100), DECK_SYNC_MIN);
+    }
+
     #[test]
     fn press_toggles_night_mode() {
         let backend = MockBrightnessBackend::default();
@@ -400,4 +1232,524 @@ mod tests {
         let status = updates.last().unwrap().status.clone();
         assert!(matches!(status.as_deref(), Some("night")));
     }
+
+    #[test]
+    fn long_press_snaps_to_the_default_level() {
+        let backend = MockBrightnessBackend::default();
+        let display = TestDisplay::default();
+        let mut controller = BrightnessController::new(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init")
+        .with_default_level(Some(70));
+        assert!(controller.has_long_press());
+
+        controller.on_long_press().expect("long press");
+        assert_eq!(
+            display
+                .updates
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .status
+                .as_deref(),
+            Some("reset")
+        );
+        wait_for_apply(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().level, 70);
+    }
+
+    #[test]
+    fn long_press_is_left_to_monitor_cycling_with_several_monitors() {
+        let controller = BrightnessController::for_monitors(
+            vec![
+                MonitorTarget::new(MockBrightnessBackend::default(), "DELL U2720Q"),
+                MonitorTarget::new(MockBrightnessBackend::default(), "LG HDR 4K"),
+            ],
+            TestDisplay::default(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init")
+        .with_default_level(Some(70));
+        assert!(!controller.has_long_press());
+    }
+
+    fn statuses(display: &TestDisplay) -> Vec<Option<String>> {
+        display
+            .updates
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|update| update.status.clone())
+            .collect()
+    }
+
+    fn wait_for_settle(controller: &mut BrightnessController<MockBrightnessBackend, TestDisplay>) {
+        for _ in 0..100 {
+            controller.poll_apply().unwrap();
+            if controller.apply_inflight.is_none() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("apply never settled");
+    }
+
+    #[test]
+    fn committed_level_shows_saved_then_failed_briefly() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller = BrightnessController::new(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init");
+
+        controller.on_turn(-1).unwrap();
+        controller.on_press().unwrap();
+        wait_for_settle(&mut controller);
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        assert_eq!(
+            statuses(&display),
+            vec![
+                None,
+                Some("pending".into()),
+                Some("apply".into()),
+                Some("saved".into()),
+                None,
+            ]
+        );
+        assert_eq!(display.updates.lock().unwrap()[3].value, " 55%");
+
+        display.updates.lock().unwrap().clear();
+        backend.inner.lock().unwrap().fail_sets = true;
+        controller.on_turn(1).unwrap();
+        controller.on_press().unwrap();
+        wait_for_settle(&mut controller);
+        for _ in 0..FAILED_STATUS_TICKS {
+            assert_eq!(
+                statuses(&display).last().unwrap().as_deref(),
+                Some("failed")
+            );
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(
+            statuses(&display),
+            vec![
+                Some("pending".into()),
+                Some("apply".into()),
+                Some("failed".into()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn turning_replaces_the_saved_status_at_once() {
+        let backend = MockBrightnessBackend::default();
+        let display = TestDisplay::default();
+        let mut controller = BrightnessController::new(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init");
+
+        controller.on_turn(1).unwrap();
+        controller.on_press().unwrap();
+        wait_for_settle(&mut controller);
+        controller.on_turn(1).unwrap();
+        let last = display.updates.lock().unwrap().last().unwrap().clone();
+        assert_eq!(last.status.as_deref(), Some("pending"));
+        assert_eq!(last.value, " 60%");
+    }
+
+    fn at(hour: u8, minute: u8) -> ClockTime {
+        ClockTime { hour, minute }
+    }
+
+    fn scheduled_controller(
+        backend: MockBrightnessBackend,
+        display: TestDisplay,
+    ) -> BrightnessController<MockBrightnessBackend, TestDisplay> {
+        BrightnessController::new(backend, display, EncoderId::Two, 5, 10, 100, 15)
+            .expect("init")
+            .with_night_schedule(Some(NightSchedule {
+                start: at(22, 0),
+                end: at(7, 0),
+            }))
+    }
+
+    // Polls without ticking so the real clock never drives the schedule.
+    fn settle(controller: &mut BrightnessController<MockBrightnessBackend, TestDisplay>) {
+        for _ in 0..20 {
+            controller.poll_apply().unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn last_status(display: &TestDisplay) -> Option<String> {
+        display
+            .updates
+            .lock()
+            .unwrap()
+            .last()
+            .unwrap()
+            .status
+            .clone()
+    }
+
+    #[test]
+    fn schedule_dims_at_start_and_restores_at_end() {
+        let backend = MockBrightnessBackend::default();
+        let display = TestDisplay::default();
+        let mut controller = scheduled_controller(backend.clone(), display.clone());
+
+        controller.apply_night_schedule(at(21, 59)).unwrap();
+        assert!(backend.inner.lock().unwrap().history.is_empty());
+
+        controller.apply_night_schedule(at(22, 0)).unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().level, 15);
+        assert_eq!(last_status(&display).as_deref(), Some("night (auto)"));
+
+        controller.apply_night_schedule(at(3, 0)).unwrap();
+        controller.apply_night_schedule(at(7, 0)).unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().history, vec![15, 50]);
+        assert_eq!(last_status(&display), None);
+    }
+
+    #[test]
+    fn manual_change_inside_window_is_kept_when_it_ends() {
+        let backend = MockBrightnessBackend::default();
+        let display = TestDisplay::default();
+        let mut controller = scheduled_controller(backend.clone(), display.clone());
+
+        controller.apply_night_schedule(at(23, 0)).unwrap();
+        settle(&mut controller);
+        controller.on_press().unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().level, 50);
+        controller.on_press().unwrap();
+        settle(&mut controller);
+        assert_eq!(last_status(&display).as_deref(), Some("saved"));
+        assert_eq!(controller.level_display().status.as_deref(), Some("night"));
+
+        controller.apply_night_schedule(at(7, 30)).unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().history, vec![15, 50, 15]);
+
+        // The override only lasts for the window it was made in.
+        controller.on_press().unwrap();
+        settle(&mut controller);
+        controller.apply_night_schedule(at(22, 15)).unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().level, 15);
+    }
+
+    #[test]
+    fn rapid_commits_apply_serially_and_settle_on_last_level() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                set_delay: Duration::from_millis(30),
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller = BrightnessController::new(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init");
+
+        for delta in [-1, -1, 3, -2] {
+            controller.on_turn(delta).expect("turn");
+            controller.on_press().expect("commit");
+        }
+        let committed = controller.level;
+        assert_eq!(committed, 55);
+
+        for _ in 0..100 {
+            controller.on_tick().unwrap();
+            if controller.apply_inflight.is_none() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert!(controller.apply_inflight.is_none());
+        assert_eq!(controller.level, committed);
+        let state = backend.inner.lock().unwrap();
+        assert_eq!(state.level, committed);
+        assert_eq!(state.history.last(), Some(&committed));
+        assert_eq!(state.max_active_writers, 1);
+    }
+
+    fn reconciling_controller(
+        backend: &MockBrightnessBackend,
+        display: &TestDisplay,
+    ) -> BrightnessController<MockBrightnessBackend, TestDisplay> {
+        BrightnessController::new(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init")
+        .with_reconcile_interval(3)
+    }
+
+    fn drain_worker(controller: &mut BrightnessController<MockBrightnessBackend, TestDisplay>) {
+        for _ in 0..20 {
+            controller.poll_apply().unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn reconcile_adopts_external_brightness_change() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller = reconciling_controller(&backend, &display);
+
+        backend.inner.lock().unwrap().level = 80;
+        for _ in 0..3 {
+            controller.on_tick().unwrap();
+        }
+        drain_worker(&mut controller);
+
+        assert_eq!(controller.level, 80);
+        let updates = display.updates.lock().unwrap();
+        assert_eq!(updates.last().unwrap().value, " 80%");
+        assert!(backend.inner.lock().unwrap().history.is_empty());
+    }
+
+    #[test]
+    fn reconcile_ignores_external_change_while_preview_pending() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller = reconciling_controller(&backend, &display);
+
+        controller.on_turn(1).expect("preview turn");
+        backend.inner.lock().unwrap().level = 30;
+        for _ in 0..9 {
+            controller.on_tick().unwrap();
+            drain_worker(&mut controller);
+        }
+
+        assert_eq!(controller.level, 60);
+        assert_eq!(controller.pending_level, 65);
+        let updates = display.updates.lock().unwrap();
+        let last = updates.last().unwrap();
+        assert_eq!(last.value, " 65%");
+        assert_eq!(last.status.as_deref(), Some("pending"));
+    }
+
+    #[test]
+    fn cycle_and_turn_writes_each_apply_to_its_own_monitor() {
+        let slow_state = || {
+            Arc::new(Mutex::new(MockBrightnessState {
+                level: 60,
+                set_delay: Duration::from_millis(30),
+                ..Default::default()
+            }))
+        };
+        let left = MockBrightnessBackend {
+            inner: slow_state(),
+        };
+        let right = MockBrightnessBackend {
+            inner: slow_state(),
+        };
+        let display = TestDisplay::default();
+        let mut controller = BrightnessController::for_monitors(
+            vec![
+                MonitorTarget::new(left.clone(), "DELL U2720Q"),
+                MonitorTarget::new(right.clone(), "LG HDR 4K"),
+            ],
+            display.clone(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init");
+        assert_eq!(
+            display
+                .updates
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .status
+                .as_deref(),
+            Some("DELL U2720")
+        );
+
+        controller.on_turn(-2).expect("turn");
+        controller.on_press().expect("press");
+        controller
+            .on_release(Duration::from_millis(50))
+            .expect("commit");
+        controller.on_press().expect("press");
+        controller
+            .on_release(Duration::from_millis(800))
+            .expect("cycle");
+        controller.on_turn(4).expect("turn");
+        controller.on_press().expect("press");
+        controller
+            .on_release(Duration::from_millis(50))
+            .expect("commit");
+
+        for _ in 0..100 {
+            controller.on_tick().unwrap();
+            if controller.apply_inflight.is_none() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(left.inner.lock().unwrap().history, vec![50]);
+        assert_eq!(right.inner.lock().unwrap().history, vec![80]);
+        assert_eq!(controller.level, 80);
+        let updates = display.updates.lock().unwrap();
+        assert_eq!(
+            updates.last().unwrap().status.as_deref(),
+            Some("saved · LG HDR 4K")
+        );
+    }
+
+    fn ramping_controller(
+        backend: &MockBrightnessBackend,
+        interval: Duration,
+    ) -> BrightnessController<MockBrightnessBackend, TestDisplay> {
+        BrightnessController::new(
+            backend.clone(),
+            TestDisplay::default(),
+            EncoderId::Two,
+            5,
+            10,
+            100,
+            15,
+        )
+        .expect("init")
+        .with_ramp(Some(BrightnessRamp {
+            interval,
+            ..BrightnessRamp::new(20)
+        }))
+    }
+
+    #[test]
+    fn large_commits_ramp_through_intermediate_levels() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 100,
+                ..Default::default()
+            })),
+        };
+        let mut controller = ramping_controller(&backend, Duration::from_millis(1));
+
+        controller.on_press().expect("night");
+        settle(&mut controller);
+        assert_eq!(controller.level, 15);
+        let expected: Vec<u8> = (3..=19).rev().map(|step| step * 5).collect();
+        assert_eq!(backend.inner.lock().unwrap().history, expected);
+
+        backend.inner.lock().unwrap().history.clear();
+        controller.on_turn(2).expect("turn");
+        controller.on_press().expect("commit");
+        settle(&mut controller);
+        assert_eq!(backend.inner.lock().unwrap().history, vec![25]);
+    }
+
+    #[test]
+    fn reads_queued_mid_ramp_wait_for_it_to_finish() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 100,
+                ..Default::default()
+            })),
+        };
+        let mut controller = ramping_controller(&backend, Duration::from_millis(2));
+
+        controller.on_press().expect("night");
+        thread::sleep(Duration::from_millis(6));
+        controller
+            .send_to_worker(WorkerRequest::Read(0))
+            .expect("read");
+        settle(&mut controller);
+
+        assert_eq!(controller.apply_inflight, None);
+        assert_eq!(controller.level, 15);
+        let expected: Vec<u8> = (3..=19).rev().map(|step| step * 5).collect();
+        assert_eq!(backend.inner.lock().unwrap().history, expected);
+    }
+
+    #[test]
+    fn turning_mid_ramp_cancels_it() {
+        let backend = MockBrightnessBackend {
+            inner: Arc::new(Mutex::new(MockBrightnessState {
+                level: 100,
+                ..Default::default()
+            })),
+        };
+        let mut controller = ramping_controller(&backend, Duration::from_millis(20));
+
+        controller.on_press().expect("night");
+        thread::sleep(Duration::from_millis(50));
+        controller.on_turn(1).expect("turn");
+        thread::sleep(Duration::from_millis(60));
+
+        let history = backend.inner.lock().unwrap().history.clone();
+        assert!(!history.is_empty() && history.len() < 5, "{history:?}");
+        assert!(history.iter().all(|level| *level > 15));
+        assert!(history.windows(2).all(|pair| pair[0] > pair[1]));
+    }
 }
@@ -0,0 +1,358 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::capture::Recorder;
+
+use super::audio_toggle::{AVAILABLE_TINT, IconPaths, MaterialIcon, load_material_icon};
+use super::commands::CommandLine;
+use super::launcher::{ExecSpec, launch_exec};
+
+const RECORDING_TINT: [u8; 3] = [230, 60, 60];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptureConfig {
+    #[serde(default)]
+    pub screenshot_button: Option<u8>,
+    #[serde(default)]
+    pub record_button: Option<u8>,
+    /// Defaults to `grim -g "$(slurp)"` under Wayland and `flameshot gui`
+    /// otherwise.
+    #[serde(default)]
+    pub screenshot_command: Option<CommandLine>,
+    /// Stopped with SIGINT. Defaults to `wf-recorder` writing a timestamped
+    /// file to `~/Videos`.
+    #[serde(default)]
+    pub record_command: Option<CommandLine>,
+}
+
+/// A screenshot key and a key that starts and stops a screen recording,
+/// tinted red while the recorder runs.
+pub struct CaptureController<H>
+where
+    H: DisplayPipeline,
+{
+    hardware: H,
+    screenshot: Option<(u8, ExecSpec)>,
+    record: Option<RecordButton>,
+    recorder: Option<Recorder>,
+}
+
+struct RecordButton {
+    index: u8,
+    command: Option<ExecSpec>,
+    idle: ButtonImage,
+    recording: ButtonImage,
+}
+
+impl<H> CaptureController<H>
+where
+    H: DisplayPipeline,
+{
+    pub fn new(config: &CaptureConfig, config_path: Option<&Path>, hardware: H) -> Result<Self> {
+        if config.screenshot_button.is_none() && config.record_button.is_none() {
+            bail!("capture needs a `screenshot_button` or a `record_button`");
+        }
+        let icon_paths = IconPaths::new(config_path);
+
+        let screenshot = match config.screenshot_button {
+            Some(index) => {
+                let command = config
+                    .screenshot_command
+                    .clone()
+                    .unwrap_or_else(default_screenshot_command)
+                    .to_exec()
+                    .context("invalid `screenshot_command`")?;
+                let mut icon = load_material_icon(MaterialIcon::PhotoCamera, &icon_paths)?;
+                icon.id = format!("capture-{index}-{}", icon.id);
+                hardware
+                    .update_button_icon(index, Some(icon))
+                    .with_context(|| format!("failed to set icon for screenshot button {index}"))?;
+                Some((index, command))
+            }
+            None => None,
+        };
+
+        let record = match config.record_button {
+            Some(index) => {
+                let command = config
+                    .record_command
+                    .as_ref()
+                    .map(|command| command.to_exec())
+                    .transpose()
+                    .context("invalid `record_command`")?;
+                let base = load_material_icon(MaterialIcon::Videocam, &icon_paths)?;
+                let tinted = |state: &str, tint: [u8; 3]| ButtonImage {
+                    id: format!("capture-{index}-{state}"),
+                    image: base.image.clone(),
+                    tint: Some(tint),
+                    label: None,
+                    animation: None,
+                    background: None,
+                    badge: None,
+                };
+                Some(RecordButton {
+                    index,
+                    command,
+                    idle: tinted("idle", AVAILABLE_TINT),
+                    recording: tinted("recording", RECORDING_TINT),
+                })
+            }
+            None => None,
+        };
+
+        let controller = Self {
+            hardware,
+            screenshot,
+            record,
+            recorder: None,
+        };
+        controller.push_record_icon()?;
+        Ok(controller)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.screenshot
+            .iter()
+            .map(|(index, _)| *index)
+            .chain(self.record.iter().map(|record| record.index))
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        if let Some((_, command)) = self
+            .screenshot
+            .as_ref()
+            .filter(|(index, _)| *index == button_index)
+        {
+            match launch_exec(command, None, false) {
+                Ok(()) => info!(command = %command.program, "taking screenshot"),
+                Err(err) => warn!(error = %err, "failed to take screenshot"),
+            }
+            return Ok(true);
+        }
+        if self
+            .record
+            .as_ref()
+            .is_none_or(|record| record.index != button_index)
+        {
+            return Ok(false);
+        }
+
+        match self.recorder.take() {
+            Some(recorder) => stop_recorder(recorder),
+            None => self.start_recording(),
+        }
+        self.push_record_icon()?;
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(self.button_indices().any(|index| index == button_index))
+    }
+
+    /// Notices a recorder that exited by itself, e.g. because the output
+    /// it was capturing went away.
+    pub fn on_tick(&mut self) -> Result<()> {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return Ok(());
+        };
+        match recorder.try_exited() {
+            Ok(None) => return Ok(()),
+            Ok(Some(status)) if status.success() => info!("screen recording ended"),
+            Ok(Some(status)) => warn!(code = ?status.code(), "screen recorder exited"),
+            Err(err) => warn!(error = %err, "lost track of the screen recorder"),
+        }
+        self.recorder = None;
+        self.push_record_icon()
+    }
+
+    /// Hands a running recorder over to a controller rebuilt after a
+    /// configuration reload.
+    pub fn take_recorder(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+
+    pub fn adopt_recorder(&mut self, recorder: Recorder) -> Result<()> {
+        if self.record.is_none() {
+            stop_recorder(recorder);
+            return Ok(());
+        }
+        self.recorder = Some(recorder);
+        self.push_record_icon()
+    }
+
+    fn start_recording(&mut self) {
+        let Some(record) = self.record.as_ref() else {
+            return;
+        };
+        let command = match record.command.clone() {
+            Some(command) => Ok(command),
+            None => default_record_command(),
+        };
+        match command.and_then(|command| Recorder::spawn(&command.program, &command.args)) {
+            Ok(recorder) => {
+                info!("screen recording started");
+                self.recorder = Some(recorder);
+            }
+            Err(err) => warn!(error = %err, "failed to start screen recording"),
+        }
+    }
+
+    fn push_record_icon(&self) -> Result<()> {
+        let Some(record) = self.record.as_ref() else {
+            return Ok(());
+        };
+        let icon = if self.recorder.is_some() {
+            &record.recording
+        } else {
+            &record.idle
+        };
+        self.hardware
+            .update_button_icon(record.index, Some(icon.clone()))
+    }
+}
+
+// Dropping the controller, including on shutdown, finishes the recording
+// rather than leaving the recorder running.
+impl<H> Drop for CaptureController<H>
+where
+    H: DisplayPipeline,
+{
+    fn drop(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            stop_recorder(recorder);
+        }
+    }
+}
+
+fn stop_recorder(recorder: Recorder) {
+    match recorder.stop() {
+        Ok(status) => info!(code = ?status.code(), "screen recording stopped"),
+        Err(err) => warn!(error = %err, "failed to stop screen recording"),
+    }
+}
+
+fn default_screenshot_command() -> CommandLine {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        CommandLine::Argv(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"grim -g "$(slurp)""#.to_string(),
+        ])
+    } else {
+        CommandLine::Text("flameshot gui".to_string())
+    }
+}
+
+fn default_record_command() -> Result<ExecSpec> {
+    let dir = env::var_os("XDG_VIDEOS_DIR")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join("Videos")))
+        .context("neither XDG_VIDEOS_DIR nor HOME is set")?;
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let file = dir.join(format!(
+        "recording-{}.mp4",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    Ok(ExecSpec {
+        program: "wf-recorder".to_string(),
+        args: vec!["-f".to_string(), file.display().to_string()],
+        env: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, _index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons.lock().unwrap().push(icon.map(|icon| icon.id));
+            Ok(())
+        }
+    }
+
+    impl RecordingHardware {
+        fn last_icon(&self) -> Option<String> {
+            self.icons.lock().unwrap().last().unwrap().clone()
+        }
+    }
+
+    fn argv(args: &[&str]) -> Option<CommandLine> {
+        Some(CommandLine::Argv(
+            args.iter().map(|arg| arg.to_string()).collect(),
+        ))
+    }
+
+    #[test]
+    fn second_press_stops_the_recorder() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("shot.png");
+        let config = CaptureConfig {
+            screenshot_button: Some(8),
+            record_button: Some(9),
+            screenshot_command: argv(&["touch", &marker.display().to_string()]),
+            record_command: argv(&["sleep", "30"]),
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = CaptureController::new(&config, None, hardware.clone()).unwrap();
+        assert_eq!(controller.button_indices().collect::<Vec<_>>(), [8, 9]);
+        assert_eq!(hardware.last_icon().as_deref(), Some("capture-9-idle"));
+
+        assert!(controller.on_button_pressed(8).unwrap());
+        assert!(!controller.on_button_pressed(3).unwrap());
+        assert!(controller.on_button_pressed(9).unwrap());
+        assert_eq!(hardware.last_icon().as_deref(), Some("capture-9-recording"));
+        controller.on_tick().unwrap();
+        assert!(controller.recorder.is_some());
+
+        assert!(controller.on_button_pressed(9).unwrap());
+        assert!(controller.recorder.is_none());
+        assert_eq!(hardware.last_icon().as_deref(), Some("capture-9-idle"));
+        assert!((0..200).any(|_| {
+            thread::sleep(Duration::from_millis(10));
+            marker.exists()
+        }));
+    }
+
+    #[test]
+    fn recorder_exiting_by_itself_resets_the_icon() {
+        let config = CaptureConfig {
+            screenshot_button: None,
+            record_button: Some(9),
+            screenshot_command: None,
+            record_command: argv(&["true"]),
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = CaptureController::new(&config, None, hardware.clone()).unwrap();
+        assert!(controller.on_button_pressed(9).unwrap());
+        assert_eq!(hardware.last_icon().as_deref(), Some("capture-9-recording"));
+
+        for _ in 0..200 {
+            controller.on_tick().unwrap();
+            if controller.recorder.is_none() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(hardware.last_icon().as_deref(), Some("capture-9-idle"));
+    }
+}
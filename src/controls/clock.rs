@@ -0,0 +1,353 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+
+use super::{EncoderController, Tickable};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    pub show_seconds: bool,
+    pub twelve_hour: bool,
+    /// An IANA name such as `Asia/Tokyo`, reached by turning the encoder.
+    pub second_timezone: Option<String>,
+}
+
+/// The current time, so tests can step it.
+pub trait TimeSource: Send {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zone {
+    Local,
+    Named(Tz),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClockView {
+    zone: Zone,
+    twelve_hour: bool,
+}
+
+/// Shows the time as the value and the date as the status. Turning steps
+/// through 24/12-hour formats and the second timezone, a press goes back to
+/// the first. Ticks only reach the strip when the shown text changes.
+pub struct ClockController<D, T = SystemTimeSource>
+where
+    D: DisplayPipeline,
+    T: TimeSource,
+{
+    display: D,
+    encoder: EncoderId,
+    time: T,
+    views: Vec<ClockView>,
+    view: usize,
+    show_seconds: bool,
+    last_pushed: Option<(String, String, Option<String>)>,
+}
+
+impl<D> ClockController<D>
+where
+    D: DisplayPipeline,
+{
+    pub fn with_system_time(config: &ClockConfig, display: D, encoder: EncoderId) -> Result<Self> {
+        Self::new(config, display, encoder, SystemTimeSource)
+    }
+}
+
+impl<D, T> ClockController<D, T>
+where
+    D: DisplayPipeline,
+    T: TimeSource,
+{
+    pub fn new(config: &ClockConfig, display: D, encoder: EncoderId, time: T) -> Result<Self> {
+        let mut zones = vec![Zone::Local];
+        if let Some(name) = &config.second_timezone {
+            let tz = name
+                .parse::<Tz>()
+                .map_err(|err| anyhow!("unknown clock `second_timezone` {name:?}: {err}"))?;
+            zones.push(Zone::Named(tz));
+        }
+        let views = zones
+            .into_iter()
+            .flat_map(|zone| {
+                [config.twelve_hour, !config.twelve_hour]
+                    .map(|twelve_hour| ClockView { zone, twelve_hour })
+            })
+            .collect();
+        let mut controller = Self {
+            display,
+            encoder,
+            time,
+            views,
+            view: 0,
+            show_seconds: config.show_seconds,
+            last_pushed: None,
+        };
+        controller.push_if_changed()?;
+        Ok(controller)
+    }
+
+    fn push_if_changed(&mut self) -> Result<()> {
+        let view = self.views[self.view];
+        let now = self.time.now();
+        let (title, value, status) = match view.zone {
+            Zone::Local => {
+                let (value, status) = format_time(
+                    &now.with_timezone(&Local),
+                    view.twelve_hour,
+                    self.show_seconds,
+                );
+                ("clock".to_string(), value, status)
+            }
+            Zone::Named(tz) => {
+                let local = now.with_timezone(&tz);
+                let (value, status) = format_time(&local, view.twelve_hour, self.show_seconds);
+                let status = format!("{status} {}", local.format("%Z"));
+                (zone_title(tz), value, status)
+            }
+        };
+        let shown = (title, value, Some(status));
+        if self.last_pushed.as_ref() == Some(&shown) {
+            return Ok(());
+        }
+        let mut display = EncoderDisplay::new(shown.0.clone(), shown.1.clone());
+        display.status = shown.2.clone();
+        self.display.update_encoder(self.encoder, display)?;
+        self.last_pushed = Some(shown);
+        Ok(())
+    }
+}
+
+// The meridiem goes on the status line; "12:59:59 PM" is too wide for the
+// value at its scale.
+fn format_time<Z>(time: &DateTime<Z>, twelve_hour: bool, show_seconds: bool) -> (String, String)
+where
+    Z: TimeZone,
+    Z::Offset: std::fmt::Display,
+{
+    let pattern = match (twelve_hour, show_seconds) {
+        (false, false) => "%H:%M",
+        (false, true) => "%H:%M:%S",
+        (true, false) => "%-I:%M",
+        (true, true) => "%-I:%M:%S",
+    };
+    let mut status = time.format("%a %d %b").to_string().to_uppercase();
+    if twelve_hour {
+        status.push(' ');
+        status.push_str(&time.format("%p").to_string());
+    }
+    (time.format(pattern).to_string(), status)
+}
+
+fn zone_title(tz: Tz) -> String {
+    let name = tz.name();
+    name.rsplit('/').next().unwrap_or(name).replace('_', " ")
+}
+
+impl<D, T> EncoderController for ClockController<D, T>
+where
+    D: DisplayPipeline,
+    T: TimeSource,
+{
+    fn on_turn(&mut self, delta: i32) -> Result<()> {
+        if delta == 0 {
+            return Ok(());
+        }
+        let count = self.views.len() as i32;
+        self.view = (self.view as i32 + delta.signum()).rem_euclid(count) as usize;
+        self.push_if_changed()
+    }
+
+    fn on_press(&mut self) -> Result<()> {
+        self.view = 0;
+        self.push_if_changed()
+    }
+}
+
+impl<D, T> Tickable for ClockController<D, T>
+where
+    D: DisplayPipeline,
+    T: TimeSource,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        self.push_if_changed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct FakeTime(Arc<Mutex<DateTime<Utc>>>);
+
+    impl FakeTime {
+        fn at(rfc3339: &str) -> Self {
+            let time = DateTime::parse_from_rfc3339(rfc3339).unwrap().to_utc();
+            Self(Arc::new(Mutex::new(time)))
+        }
+
+        fn advance(&self, secs: i64) {
+            *self.0.lock().unwrap() += chrono::Duration::seconds(secs);
+        }
+    }
+
+    impl TimeSource for FakeTime {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingDisplay {
+        updates: Arc<Mutex<Vec<EncoderDisplay>>>,
+    }
+
+    impl DisplayPipeline for RecordingDisplay {
+        fn update_encoder(&self, _encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.updates.lock().unwrap().push(display);
+            Ok(())
+        }
+    }
+
+    impl RecordingDisplay {
+        fn count(&self) -> usize {
+            self.updates.lock().unwrap().len()
+        }
+
+        fn last(&self) -> (String, String, Option<String>) {
+            let updates = self.updates.lock().unwrap();
+            let last = updates.last().unwrap();
+            (last.title.clone(), last.value.clone(), last.status.clone())
+        }
+    }
+
+    fn clock(
+        config: ClockConfig,
+        time: &FakeTime,
+    ) -> (
+        ClockController<RecordingDisplay, FakeTime>,
+        RecordingDisplay,
+    ) {
+        let display = RecordingDisplay::default();
+        let controller =
+            ClockController::new(&config, display.clone(), EncoderId::Four, time.clone())
+                .expect("clock");
+        (controller, display)
+    }
+
+    #[test]
+    fn ticks_within_the_same_minute_push_nothing() {
+        let time = FakeTime::at("2024-06-04T10:00:05Z");
+        let (mut controller, display) = clock(ClockConfig::default(), &time);
+        assert_eq!(display.count(), 1);
+
+        for _ in 0..54 {
+            time.advance(1);
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(display.count(), 1);
+
+        time.advance(1);
+        controller.on_tick().unwrap();
+        assert_eq!(display.count(), 2);
+    }
+
+    #[test]
+    fn seconds_push_once_per_second() {
+        let time = FakeTime::at("2024-06-04T10:00:05Z");
+        let config = ClockConfig {
+            show_seconds: true,
+            ..ClockConfig::default()
+        };
+        let (mut controller, display) = clock(config, &time);
+
+        controller.on_tick().unwrap();
+        assert_eq!(display.count(), 1);
+        for _ in 0..3 {
+            time.advance(1);
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(display.count(), 4);
+    }
+
+    #[test]
+    fn turning_reaches_the_second_timezone_in_both_formats() {
+        let time = FakeTime::at("2024-06-04T10:00:00Z");
+        let config = ClockConfig {
+            second_timezone: Some("Asia/Tokyo".into()),
+            ..ClockConfig::default()
+        };
+        let (mut controller, display) = clock(config, &time);
+
+        controller.on_turn(1).unwrap();
+        controller.on_turn(1).unwrap();
+        assert_eq!(
+            display.last(),
+            (
+                "Tokyo".to_string(),
+                "19:00".to_string(),
+                Some("TUE 04 JUN JST".to_string())
+            )
+        );
+        controller.on_turn(1).unwrap();
+        assert_eq!(
+            display.last(),
+            (
+                "Tokyo".to_string(),
+                "7:00".to_string(),
+                Some("TUE 04 JUN PM JST".to_string())
+            )
+        );
+
+        let pushed = display.count();
+        controller.on_tick().unwrap();
+        assert_eq!(display.count(), pushed);
+
+        controller.on_press().unwrap();
+        assert_eq!(display.last().0, "clock");
+    }
+
+    #[test]
+    fn format_uses_two_digit_hours_only_in_24_hour_mode() {
+        let time = DateTime::parse_from_rfc3339("2024-12-25T07:08:09Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            format_time(&time, false, true),
+            ("07:08:09".to_string(), "WED 25 DEC".to_string())
+        );
+        assert_eq!(
+            format_time(&time, true, false),
+            ("7:08".to_string(), "WED 25 DEC AM".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_timezones_are_rejected() {
+        let config = ClockConfig {
+            second_timezone: Some("Mars/Olympus_Mons".into()),
+            ..ClockConfig::default()
+        };
+        let time = FakeTime::at("2024-06-04T10:00:00Z");
+        assert!(
+            ClockController::new(&config, RecordingDisplay::default(), EncoderId::Four, time)
+                .is_err()
+        );
+    }
+}
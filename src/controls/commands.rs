@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+
+use super::acceleration::{Clock, SystemClock};
+use super::audio_toggle::{
+    DEGRADED_TINT, IconConfig, IconPaths, MaterialIcon, load_configured_icon, load_material_icon,
+};
+use super::launcher::{ExecSpec, launch_exec, split_exec};
+
+/// How long a confirm button stays armed after the first press.
+const CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandButtonConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+    pub command: CommandLine,
+    #[serde(default)]
+    pub icon: Option<IconConfig>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Requires a second press within `CONFIRM_WINDOW` before running.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Relative paths are resolved against the configuration directory.
+    #[serde(default, alias = "cwd")]
+    pub working_dir: Option<PathBuf>,
+    /// Role of the `hardware.devices` entry this key is on.
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+/// A shell-style command string, split on whitespace with quoting, or an
+/// explicit argument vector.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum CommandLine {
+    Text(String),
+    Argv(Vec<String>),
+}
+
+impl CommandLine {
+    pub(super) fn to_exec(&self) -> Result<ExecSpec> {
+        let mut args = match self {
+            CommandLine::Text(command) => split_exec(command),
+            CommandLine::Argv(argv) => argv.clone(),
+        };
+        if args.first().is_none_or(|program| program.is_empty()) {
+            bail!("command is empty");
+        }
+        let program = args.remove(0);
+        Ok(ExecSpec {
+            program,
+            args,
+            env: Vec::new(),
+        })
+    }
+}
+
+struct CommandButton {
+    exec: ExecSpec,
+    working_dir: Option<PathBuf>,
+    icon: ButtonImage,
+    confirm_icon: Option<ButtonImage>,
+    armed_at: Option<Instant>,
+}
+
+impl CommandButton {
+    fn from_config(
+        config: &CommandButtonConfig,
+        base_dir: Option<&Path>,
+        icon_paths: &IconPaths,
+    ) -> Result<Self> {
+        let exec = config.command.to_exec()?;
+        let working_dir = config.working_dir.as_ref().map(|dir| match base_dir {
+            Some(base) if dir.is_relative() => base.join(dir),
+            _ => dir.clone(),
+        });
+
+        let mut icon = match config.icon.as_ref() {
+            Some(icon) => load_configured_icon(icon, icon_paths)?,
+            None => load_material_icon(MaterialIcon::Terminal, icon_paths)?,
+        };
+        icon.id = format!("command-{}-{}", config.button_index, icon.id);
+        icon.label = config.label.clone();
+        let confirm_icon = config.confirm.then(|| ButtonImage {
+            id: format!("{}-confirm", icon.id),
+            image: Arc::clone(&icon.image),
+            tint: Some(DEGRADED_TINT),
+            label: icon.label.clone(),
+            animation: None,
+            background: icon.background,
+            badge: None,
+        });
+
+        Ok(Self {
+            exec,
+            working_dir,
+            icon,
+            confirm_icon,
+            armed_at: None,
+        })
+    }
+
+    fn run(&self, index: u8) {
+        match launch_exec(&self.exec, self.working_dir.as_deref(), false) {
+            Ok(()) => info!(button_index = index, command = %self.exec.program, "ran command"),
+            Err(err) => warn!(
+                error = %err,
+                button_index = index,
+                "failed to run command button"
+            ),
+        }
+    }
+}
+
+/// Buttons that spawn a command, optionally only after a second press.
+pub struct CommandController<H, C = SystemClock>
+where
+    H: DisplayPipeline,
+    C: Clock,
+{
+    buttons: HashMap<u8, CommandButton>,
+    hardware: H,
+    clock: C,
+}
+
+impl<H> CommandController<H>
+where
+    H: DisplayPipeline,
+{
+    pub fn new(
+        configs: &[CommandButtonConfig],
+        config_path: Option<&Path>,
+        hardware: H,
+    ) -> Result<Option<Self>> {
+        Self::with_clock(configs, config_path, hardware, SystemClock)
+    }
+}
+
+impl<H, C> CommandController<H, C>
+where
+    H: DisplayPipeline,
+    C: Clock,
+{
+    pub fn with_clock(
+        configs: &[CommandButtonConfig],
+        config_path: Option<&Path>,
+        hardware: H,
+        clock: C,
+    ) -> Result<Option<Self>> {
+        let icon_paths = IconPaths::new(config_path);
+        let base_dir = config_path.and_then(Path::parent);
+        let mut buttons = HashMap::new();
+
+        for config in configs {
+            match CommandButton::from_config(config, base_dir, &icon_paths) {
+                Ok(button) => {
+                    if buttons.insert(config.button_index, button).is_some() {
+                        warn!(
+                            button_index = config.button_index,
+                            "overriding previously configured command button"
+                        );
+                    }
+                }
+                Err(err) => warn!(
+                    error = %err,
+                    button_index = config.button_index,
+                    "skipping command button due to configuration error"
+                ),
+            }
+        }
+
+        if buttons.is_empty() {
+            return Ok(None);
+        }
+
+        for (index, button) in &buttons {
+            hardware
+                .update_button_icon(*index, Some(button.icon.clone()))
+                .with_context(|| format!("failed to set icon for command button {index}"))?;
+        }
+
+        Ok(Some(Self {
+            buttons,
+            hardware,
+            clock,
+        }))
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.buttons.keys().copied()
+    }
+
+    // A confirm button arms on the first press and shows the warning tint;
+    // a second press inside the window runs it.
+    pub fn on_button_pressed(&mut self, index: u8) -> Result<bool> {
+        let now = self.clock.now();
+        let Some(button) = self.buttons.get_mut(&index) else {
+            return Ok(false);
+        };
+
+        let Some(confirm_icon) = button.confirm_icon.as_ref() else {
+            button.run(index);
+            return Ok(true);
+        };
+
+        match button.armed_at.take() {
+            Some(armed_at) if now.duration_since(armed_at) <= CONFIRM_WINDOW => {
+                button.run(index);
+                self.hardware
+                    .update_button_icon(index, Some(button.icon.clone()))?;
+            }
+            _ => {
+                button.armed_at = Some(now);
+                self.hardware
+                    .update_button_icon(index, Some(confirm_icon.clone()))?;
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn on_tick(&mut self) -> Result<()> {
+        let now = self.clock.now();
+        for (index, button) in &mut self.buttons {
+            if button
+                .armed_at
+                .is_some_and(|armed_at| now.duration_since(armed_at) > CONFIRM_WINDOW)
+            {
+                button.armed_at = None;
+                self.hardware
+                    .update_button_icon(*index, Some(button.icon.clone()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::acceleration::tests::FakeClock;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use std::sync::Mutex;
+    use std::thread;
+
+    // Button index and icon id of each icon update, in order.
+    type IconUpdates = Arc<Mutex<Vec<(u8, Option<String>)>>>;
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: IconUpdates,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons
+                .lock()
+                .unwrap()
+                .push((index, icon.map(|icon| icon.id)));
+            Ok(())
+        }
+    }
+
+    impl RecordingHardware {
+        fn last_icon(&self) -> Option<String> {
+            self.icons.lock().unwrap().last().unwrap().1.clone()
+        }
+    }
+
+    fn config(command: CommandLine, confirm: bool) -> CommandButtonConfig {
+        CommandButtonConfig {
+            button_index: 2,
+            command,
+            icon: None,
+            label: None,
+            confirm,
+            working_dir: None,
+            device: None,
+        }
+    }
+
+    fn wait_for(path: &Path) -> bool {
+        (0..100).any(|_| {
+            thread::sleep(Duration::from_millis(20));
+            path.exists()
+        })
+    }
+
+    #[test]
+    fn parses_command_strings_and_argv() {
+        let parsed: CommandButtonConfig = serde_json::from_str(
+            r#"{"button": 1, "command": "notify-send 'Build done' --urgency=low", "cwd": "scripts"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.command.to_exec().unwrap(),
+            ExecSpec {
+                program: "notify-send".to_string(),
+                args: vec!["Build done".to_string(), "--urgency=low".to_string()],
+                env: Vec::new(),
+            }
+        );
+        assert_eq!(parsed.working_dir, Some(PathBuf::from("scripts")));
+
+        let parsed: CommandButtonConfig = serde_json::from_str(
+            r#"{"button_index": 1, "command": ["xdotool", "key", "ctrl+alt+t"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.command.to_exec().unwrap().args,
+            ["key", "ctrl+alt+t"]
+        );
+
+        assert!(CommandLine::Text("  ".to_string()).to_exec().is_err());
+        assert!(CommandLine::Argv(Vec::new()).to_exec().is_err());
+    }
+
+    #[test]
+    fn confirm_runs_only_on_second_press_within_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ran");
+        let command = CommandLine::Argv(vec![
+            "touch".to_string(),
+            marker.to_string_lossy().into_owned(),
+        ]);
+        let clock = FakeClock::default();
+        let hardware = RecordingHardware::default();
+        let mut controller = CommandController::with_clock(
+            &[config(command, true)],
+            None,
+            hardware.clone(),
+            clock.clone(),
+        )
+        .unwrap()
+        .expect("controller");
+        assert_eq!(hardware.last_icon().as_deref(), Some("command-2-terminal"));
+
+        assert!(!controller.on_button_pressed(5).unwrap());
+        assert!(controller.on_button_pressed(2).unwrap());
+        assert_eq!(
+            hardware.last_icon().as_deref(),
+            Some("command-2-terminal-confirm")
+        );
+
+        clock.advance(Duration::from_secs(3));
+        controller.on_tick().unwrap();
+        assert_eq!(hardware.last_icon().as_deref(), Some("command-2-terminal"));
+        assert!(controller.on_button_pressed(2).unwrap());
+        assert!(!marker.exists());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(controller.on_button_pressed(2).unwrap());
+        assert_eq!(hardware.last_icon().as_deref(), Some("command-2-terminal"));
+        assert!(wait_for(&marker));
+    }
+
+    #[test]
+    fn failed_spawn_is_handled() {
+        let hardware = RecordingHardware::default();
+        let command = CommandLine::Text("/nonexistent/streamdeck-command --flag".to_string());
+        let mut controller = CommandController::new(&[config(command, false)], None, hardware)
+            .unwrap()
+            .expect("controller");
+        assert!(controller.on_button_pressed(2).unwrap());
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::config::{self, StreamDeckSettings};
+use crate::system::audio_switch::{AudioSwitchBackend, SinkInfo};
+
+use super::audio_toggle::{AudioDeviceKind, AudioOutputConfig, IconPaths, load_configured_icon};
+use super::launcher::check_launcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub subject: String,
+    pub message: String,
+}
+
+/// The outcome of `check_config`, one finding per configured entry plus one
+/// per problem found with it.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ConfigReport {
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    fn push(&mut self, severity: Severity, subject: &str, message: impl Into<String>) {
+        self.findings.push(Finding {
+            severity,
+            subject: subject.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            let tag = match finding.severity {
+                Severity::Ok => "OK",
+                Severity::Warning => "WARN",
+                Severity::Error => "ERROR",
+            };
+            writeln!(f, "{tag:<5} {}: {}", finding.subject, finding.message)?;
+        }
+        write!(
+            f,
+            "{} errors, {} warnings",
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+/// Loads the configuration at `path` and resolves each launcher and audio
+/// output the way the running daemon would, without touching a deck.
+pub fn check_config(path: &Path, audio: &dyn AudioSwitchBackend) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let settings = match config::load_settings_from(path) {
+        Ok(settings) => settings,
+        Err(err) => {
+            report.push(Severity::Error, "config", format!("{err:#}"));
+            return report;
+        }
+    };
+    report.push(Severity::Ok, "config", format!("parsed {}", path.display()));
+
+    let icon_paths = IconPaths::new(Some(path));
+    let mut devices = DeviceLists::new(audio);
+    for page in 0..settings.page_count() {
+        check_page(&settings, page, &icon_paths, &mut devices, &mut report);
+    }
+    report
+}
+
+fn check_page(
+    settings: &StreamDeckSettings,
+    page: usize,
+    icon_paths: &IconPaths,
+    devices: &mut DeviceLists<'_>,
+    report: &mut ConfigReport,
+) {
+    let (audio_toggle, launchers) = settings.page(page);
+    let prefix = if page == 0 {
+        String::new()
+    } else {
+        format!("page {page} ")
+    };
+    let default_role = settings
+        .app
+        .hardware
+        .devices
+        .first()
+        .map_or("default", |device| device.role.as_str());
+    let mut keys = KeyClaims::default();
+
+    for (index, output) in audio_toggle
+        .map(|config| config.outputs.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let subject = format!("{prefix}audio output {index} ({})", output.label());
+        if let Some(button_index) = output.button_index {
+            keys.claim(default_role, button_index, &subject, report);
+        }
+        check_output(output, icon_paths, devices, &subject, report);
+    }
+
+    for launcher in launchers {
+        let subject = format!(
+            "{prefix}launcher {} ({})",
+            launcher.button_index,
+            launcher.desktop_file.display()
+        );
+        if let Some(role) = launcher
+            .device
+            .as_deref()
+            .filter(|role| !known_device(settings, role))
+        {
+            report.push(
+                Severity::Error,
+                &subject,
+                format!("`device` names `{role}`, which is not in `hardware.devices`"),
+            );
+            continue;
+        }
+        let role = launcher.device.as_deref().unwrap_or(default_role);
+        keys.claim(role, launcher.button_index, &subject, report);
+        match check_launcher(launcher, icon_paths) {
+            Ok(warnings) if warnings.is_empty() => report.push(Severity::Ok, &subject, "resolved"),
+            Ok(warnings) => {
+                for warning in warnings {
+                    report.push(Severity::Warning, &subject, warning);
+                }
+            }
+            Err(err) => report.push(Severity::Error, &subject, format!("{err:#}")),
+        }
+    }
+}
+
+/// Which entry took each key of a page, by device role and key index.
+#[derive(Default)]
+struct KeyClaims<'a> {
+    taken: HashMap<(&'a str, u8), String>,
+}
+
+impl<'a> KeyClaims<'a> {
+    fn claim(&mut self, role: &'a str, index: u8, subject: &str, report: &mut ConfigReport) {
+        match self.taken.get(&(role, index)) {
+            Some(first) => report.push(
+                Severity::Error,
+                subject,
+                format!("key {index} is already used by {first}"),
+            ),
+            None => {
+                self.taken.insert((role, index), subject.to_string());
+            }
+        }
+    }
+}
+
+fn check_output(
+    output: &AudioOutputConfig,
+    icon_paths: &IconPaths,
+    devices: &mut DeviceLists<'_>,
+    subject: &str,
+    report: &mut ConfigReport,
+) {
+    let mut clean = true;
+    if output.button_index.is_none() {
+        report.push(Severity::Error, subject, "missing `button_index`");
+        clean = false;
+    }
+    if let Some(Err(err)) = output
+        .icon
+        .as_ref()
+        .map(|icon| load_configured_icon(icon, icon_paths))
+    {
+        report.push(
+            Severity::Warning,
+            subject,
+            format!("configured icon failed to load; the default icon will be used: {err:#}"),
+        );
+        clean = false;
+    }
+
+    let selector = match output.selector() {
+        Ok(selector) => selector,
+        Err(err) => {
+            report.push(Severity::Error, subject, format!("{err:#}"));
+            return;
+        }
+    };
+    match devices.list(output.kind) {
+        Ok(available) if available.iter().any(|device| selector.matches(device)) => {}
+        Ok(available) => {
+            let names: Vec<&str> = available
+                .iter()
+                .map(|device| device.name.as_str())
+                .collect();
+            report.push(
+                Severity::Error,
+                subject,
+                format!(
+                    "`{}` matches no {}; available: {}",
+                    selector.describe(),
+                    output.kind.noun(),
+                    names.join(", ")
+                ),
+            );
+            clean = false;
+        }
+        Err(err) => {
+            report.push(
+                Severity::Warning,
+                subject,
+                format!("could not list {}s to probe: {err:#}", output.kind.noun()),
+            );
+            clean = false;
+        }
+    }
+    if clean {
+        report.push(Severity::Ok, subject, "matched");
+    }
+}
+
+fn known_device(settings: &StreamDeckSettings, role: &str) -> bool {
+    let devices = &settings.app.hardware.devices;
+    if devices.is_empty() {
+        return role == "default";
+    }
+    devices.iter().any(|device| device.role == role)
+}
+
+// Each device list is fetched once, on first use.
+struct DeviceLists<'a> {
+    audio: &'a dyn AudioSwitchBackend,
+    sinks: Option<Result<Vec<SinkInfo>, String>>,
+    sources: Option<Result<Vec<SinkInfo>, String>>,
+}
+
+impl<'a> DeviceLists<'a> {
+    fn new(audio: &'a dyn AudioSwitchBackend) -> Self {
+        Self {
+            audio,
+            sinks: None,
+            sources: None,
+        }
+    }
+
+    fn list(&mut self, kind: AudioDeviceKind) -> Result<&[SinkInfo], &str> {
+        let audio = self.audio;
+        let cached = match kind {
+            AudioDeviceKind::Sink => self
+                .sinks
+                .get_or_insert_with(|| audio.list_sinks().map_err(|err| format!("{err:#}"))),
+            AudioDeviceKind::Source => self
+                .sources
+                .get_or_insert_with(|| audio.list_sources().map_err(|err| format!("{err:#}"))),
+        };
+        cached.as_deref().map_err(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::audio_switch::{SinkSelector, SourceInfo};
+    use anyhow::bail;
+    use std::fs;
+    use std::path::PathBuf;
+
+    struct FixedSinks(Vec<SinkInfo>);
+
+    impl AudioSwitchBackend for FixedSinks {
+        fn set_default_sink(&self, _: &SinkSelector) -> Result<SinkInfo> {
+            bail!("read-only")
+        }
+
+        fn current_default_sink(&self) -> Result<Option<SinkInfo>> {
+            Ok(self.0.first().cloned())
+        }
+
+        fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
+            Ok(self.0.clone())
+        }
+
+        fn sink_volume(&self, _: &str) -> Result<Option<u8>> {
+            Ok(None)
+        }
+
+        fn set_sink_volume(&self, _: &str, _: u8) -> Result<()> {
+            bail!("read-only")
+        }
+
+        fn set_default_source(&self, _: &SinkSelector) -> Result<SourceInfo> {
+            bail!("read-only")
+        }
+
+        fn current_default_source(&self) -> Result<Option<SourceInfo>> {
+            Ok(None)
+        }
+
+        fn list_sources(&self) -> Result<Vec<SourceInfo>> {
+            bail!("pactl is not running")
+        }
+    }
+
+    fn speakers() -> FixedSinks {
+        FixedSinks(vec![SinkInfo {
+            id: Some(3),
+            name: "alsa_output.pci-0000_00_1f.3.analog-stereo".to_string(),
+            description: Some("Built-in Audio Analog Stereo".to_string()),
+            ..Default::default()
+        }])
+    }
+
+    fn write_fixture(dir: &Path, launcher: &Path) -> PathBuf {
+        let path = dir.join("config.json");
+        let config = serde_json::json!({
+            "audio_toggle": {
+                "outputs": [
+                    { "button_index": 0, "description": "Built-in Audio" },
+                    { "button_index": 1, "description": "USB Headset Anlaog Stereo" }
+                ]
+            },
+            "launchers": [
+                { "button_index": 4, "desktop_file": launcher },
+                { "button_index": 5, "desktop_file": launcher, "device": "side" }
+            ]
+        });
+        fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    fn severity_of(report: &ConfigReport, subject: &str) -> Vec<Severity> {
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.subject.starts_with(subject))
+            .map(|finding| finding.severity)
+            .collect()
+    }
+
+    #[test]
+    fn reports_missing_desktop_file_and_unknown_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), &dir.path().join("missing.desktop"));
+
+        let report = check_config(&path, &speakers());
+        assert!(report.has_errors());
+        assert_eq!(severity_of(&report, "audio output 0"), [Severity::Ok]);
+        assert_eq!(severity_of(&report, "audio output 1"), [Severity::Error]);
+        assert_eq!(severity_of(&report, "launcher 4"), [Severity::Error]);
+        assert_eq!(severity_of(&report, "launcher 5"), [Severity::Error]);
+
+        let printed = report.to_string();
+        assert!(printed.contains("alsa_output.pci-0000_00_1f.3.analog-stereo"));
+        assert!(printed.contains("missing.desktop"));
+        assert!(printed.contains("`side`"));
+        assert!(printed.ends_with("3 errors, 0 warnings"));
+    }
+
+    #[test]
+    fn resolvable_config_has_no_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let icon = dir.path().join("icon.svg");
+        fs::write(
+            &icon,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"></svg>"#,
+        )
+        .unwrap();
+        let desktop = dir.path().join("app.desktop");
+        fs::write(
+            &desktop,
+            format!(
+                "[Desktop Entry]\nName=App\nExec=/usr/bin/true\nTryExec=/nonexistent/app\nIcon={}\nType=Application\n",
+                icon.display()
+            ),
+        )
+        .unwrap();
+        let path = write_fixture(dir.path(), &desktop);
+        let config = fs::read_to_string(&path)
+            .unwrap()
+            .replace("USB Headset Anlaog Stereo", "Analog Stereo")
+            .replace("\"side\"", "\"default\"");
+        fs::write(&path, config).unwrap();
+
+        let report = check_config(&path, &speakers());
+        assert!(!report.has_errors(), "{report}");
+        assert_eq!(severity_of(&report, "launcher 4"), [Severity::Warning]);
+
+        let broken = dir.path().join("broken.json");
+        fs::write(&broken, "{ \"launchers\": [").unwrap();
+        assert!(check_config(&broken, &speakers()).has_errors());
+    }
+
+    #[test]
+    fn reports_keys_used_twice_on_the_same_deck() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), &dir.path().join("missing.desktop"));
+        let config = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"button_index\":4", "\"button_index\":1")
+            .replace("\"button_index\":5", "\"button_index\":0");
+        fs::write(&path, config).unwrap();
+
+        let report = check_config(&path, &speakers());
+        let reused: Vec<&str> = report
+            .findings
+            .iter()
+            .filter(|finding| finding.message.contains("already used"))
+            .map(|finding| finding.subject.as_str())
+            .collect();
+        // The second launcher names another deck, so key 0 there is free.
+        assert_eq!(reused.len(), 1);
+        assert!(reused[0].starts_with("launcher 1"));
+    }
+}
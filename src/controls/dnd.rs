@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::dnd::{DesktopDndBackend, DndBackend};
+
+use super::audio_toggle::{
+    ACTIVE_TINT, DEGRADED_TINT, IconPaths, MaterialIcon, UNAVAILABLE_TINT, load_material_icon,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DndConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DndState {
+    Notifying,
+    Silenced,
+    Unavailable,
+}
+
+pub struct DndController<B, H>
+where
+    B: DndBackend,
+    H: DisplayPipeline,
+{
+    backend: B,
+    hardware: H,
+    button_index: u8,
+    icons: DndIcons,
+    state: Option<DndState>,
+}
+
+struct DndIcons {
+    notifying: ButtonImage,
+    silenced: ButtonImage,
+    unavailable: ButtonImage,
+}
+
+impl<B, H> DndController<B, H>
+where
+    B: DndBackend,
+    H: DisplayPipeline,
+{
+    fn new(config: DndConfig, backend: B, hardware: H, icon_paths: &IconPaths) -> Result<Self> {
+        let button_index = config.button_index;
+        let bell = load_material_icon(MaterialIcon::Notifications, icon_paths)?;
+        let bell_off = load_material_icon(MaterialIcon::NotificationsOff, icon_paths)?;
+        let icons = DndIcons {
+            notifying: tinted(&bell, button_index, "notifying", ACTIVE_TINT),
+            silenced: tinted(&bell_off, button_index, "silenced", DEGRADED_TINT),
+            unavailable: tinted(&bell_off, button_index, "unavailable", UNAVAILABLE_TINT),
+        };
+
+        let mut controller = Self {
+            backend,
+            hardware,
+            button_index,
+            icons,
+            state: None,
+        };
+        controller.refresh_state()?;
+        Ok(controller)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        std::iter::once(self.button_index)
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        if button_index != self.button_index {
+            return Ok(false);
+        }
+
+        if !self.backend.is_available() {
+            self.apply_state(DndState::Unavailable)?;
+            return Ok(true);
+        }
+
+        match self.backend.toggle() {
+            Ok(enabled) => {
+                info!(enabled, "toggled do-not-disturb");
+                self.apply_state(if enabled {
+                    DndState::Silenced
+                } else {
+                    DndState::Notifying
+                })?;
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to toggle do-not-disturb");
+                self.refresh_state()?;
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(button_index == self.button_index)
+    }
+
+    pub fn on_tick(&mut self) -> Result<()> {
+        self.refresh_state()
+    }
+
+    fn refresh_state(&mut self) -> Result<()> {
+        let state = if !self.backend.is_available() {
+            DndState::Unavailable
+        } else {
+            match self.backend.is_enabled() {
+                Ok(true) => DndState::Silenced,
+                Ok(false) => DndState::Notifying,
+                Err(err) => {
+                    warn!(error = %err, "failed to query do-not-disturb state");
+                    DndState::Unavailable
+                }
+            }
+        };
+        self.apply_state(state)
+    }
+
+    fn apply_state(&mut self, state: DndState) -> Result<()> {
+        if self.state == Some(state) {
+            return Ok(());
+        }
+        self.state = Some(state);
+
+        let icon = match state {
+            DndState::Notifying => &self.icons.notifying,
+            DndState::Silenced => &self.icons.silenced,
+            DndState::Unavailable => &self.icons.unavailable,
+        };
+        self.hardware
+            .update_button_icon(self.button_index, Some(icon.clone()))
+    }
+}
+
+impl<H> DndController<DesktopDndBackend, H>
+where
+    H: DisplayPipeline,
+{
+    pub fn with_default_backend(
+        config: DndConfig,
+        config_path: Option<&Path>,
+        hardware: H,
+    ) -> Result<Self> {
+        let icon_paths = IconPaths::new(config_path);
+        DndController::new(config, DesktopDndBackend::detect(), hardware, &icon_paths)
+    }
+}
+
+fn tinted(base: &ButtonImage, button_index: u8, suffix: &str, tint: [u8; 3]) -> ButtonImage {
+    ButtonImage {
+        id: format!("dnd-{button_index}-{suffix}"),
+        image: base.image.clone(),
+        tint: Some(tint),
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use crate::system::dnd::tests::MockDndBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, _index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons.lock().unwrap().push(icon.map(|icon| icon.id));
+            Ok(())
+        }
+    }
+
+    fn controller(
+        backend: MockDndBackend,
+        hardware: RecordingHardware,
+    ) -> DndController<MockDndBackend, RecordingHardware> {
+        DndController::new(
+            DndConfig { button_index: 6 },
+            backend,
+            hardware,
+            &IconPaths::new(None),
+        )
+        .expect("controller")
+    }
+
+    fn last_icon(hardware: &RecordingHardware) -> Option<String> {
+        hardware.icons.lock().unwrap().last().unwrap().clone()
+    }
+
+    #[test]
+    fn press_toggles_and_tick_follows_external_changes() {
+        let backend = MockDndBackend {
+            available: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("dnd-6-notifying"));
+
+        assert!(!controller.on_button_pressed(2).unwrap());
+        assert!(controller.on_button_pressed(6).unwrap());
+        assert!(*backend.enabled.lock().unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("dnd-6-silenced"));
+
+        *backend.enabled.lock().unwrap() = false;
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        assert_eq!(hardware.icons.lock().unwrap().len(), 3);
+        assert_eq!(last_icon(&hardware).as_deref(), Some("dnd-6-notifying"));
+    }
+
+    #[test]
+    fn missing_daemon_shows_disabled_icon() {
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(MockDndBackend::default(), hardware.clone());
+        assert!(controller.on_button_pressed(6).unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("dnd-6-unavailable"));
+    }
+}
@@ -0,0 +1,286 @@
+use std::time::{Duration, Instant};
+
+use crate::hardware::{EncoderId, HardwareEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressGesture {
+    /// The press is held back until the window closes or a second press lands.
+    Pending,
+    Double,
+}
+
+/// Tells single presses from double presses by deferring the first press.
+///
+/// A press that is not followed by another within `window` is reported once by
+/// [`DoublePressDetector::take_expired`], so the single-press action never runs
+/// as part of a double press.
+#[derive(Debug, Clone)]
+pub struct DoublePressDetector {
+    window: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl DoublePressDetector {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending_since: None,
+        }
+    }
+
+    pub fn on_press(&mut self, now: Instant) -> PressGesture {
+        match self.pending_since.take() {
+            Some(first) if now.duration_since(first) < self.window => PressGesture::Double,
+            _ => {
+                self.pending_since = Some(now);
+                PressGesture::Pending
+            }
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending_since.map(|first| first + self.window)
+    }
+
+    /// Returns true once when a pending press has outlived the window.
+    pub fn take_expired(&mut self, now: Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Tells taps from long presses once a key or encoder is released, so the
+/// tap action never runs for a press that was held.
+#[derive(Debug, Clone, Copy)]
+pub struct HoldDetector {
+    threshold: Duration,
+}
+
+impl HoldDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+
+    pub fn is_long(&self, held: Duration) -> bool {
+        held >= self.threshold
+    }
+}
+
+/// What a touch strip swipe amounts to once its length is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripGesture {
+    /// Too short to be a swipe, so a tap where it started.
+    Tap(EncoderId),
+    /// A swipe starting on `segment`; `forward` when moving left, as when
+    /// turning a page.
+    Swipe { segment: EncoderId, forward: bool },
+    /// Too short to be a swipe and on a segment border.
+    Ignored,
+}
+
+/// Swipes covering fewer than `min_distance` pixels count as taps.
+pub fn classify_swipe(from_x: u16, to_x: u16, min_distance: u16) -> StripGesture {
+    if from_x.abs_diff(to_x) < min_distance {
+        return match EncoderId::from_strip_tap(from_x) {
+            Some(segment) => StripGesture::Tap(segment),
+            None => StripGesture::Ignored,
+        };
+    }
+    StripGesture::Swipe {
+        segment: EncoderId::from_strip_x(from_x),
+        forward: to_x < from_x,
+    }
+}
+
+/// Sums each encoder's turns within a run of queued events so a fast spin
+/// costs one controller call instead of one per detent.
+///
+/// Turns are never merged across any other event, so presses, releases and
+/// key events keep their order relative to the turns around them.
+pub fn coalesce_turns(events: impl IntoIterator<Item = HardwareEvent>) -> Vec<HardwareEvent> {
+    let mut coalesced = Vec::new();
+    let mut run: Vec<(EncoderId, i32)> = Vec::new();
+    let flush = |run: &mut Vec<(EncoderId, i32)>, coalesced: &mut Vec<HardwareEvent>| {
+        coalesced.extend(
+            run.drain(..)
+                .filter(|(_, delta)| *delta != 0)
+                .map(|(encoder, delta)| HardwareEvent::EncoderTurned { encoder, delta }),
+        );
+    };
+
+    for event in events {
+        match event {
+            HardwareEvent::EncoderTurned { encoder, delta } => {
+                match run.iter_mut().find(|(queued, _)| *queued == encoder) {
+                    Some((_, total)) => *total += delta,
+                    None => run.push((encoder, delta)),
+                }
+            }
+            other => {
+                flush(&mut run, &mut coalesced);
+                coalesced.push(other);
+            }
+        }
+    }
+    flush(&mut run, &mut coalesced);
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::{EncoderController, VolumeController};
+    use crate::hardware::{DisplayPipeline, EncoderDisplay};
+    use crate::system::audio::tests::MockAudioBackend;
+
+    const WINDOW: Duration = Duration::from_millis(400);
+
+    #[test]
+    fn lone_press_is_released_after_window() {
+        let start = Instant::now();
+        let mut detector = DoublePressDetector::new(WINDOW);
+
+        assert_eq!(detector.on_press(start), PressGesture::Pending);
+        assert!(!detector.take_expired(start + Duration::from_millis(100)));
+        assert!(detector.take_expired(start + WINDOW));
+        assert!(!detector.take_expired(start + WINDOW * 2));
+    }
+
+    #[test]
+    fn second_press_within_window_is_double_and_suppresses_single() {
+        let start = Instant::now();
+        let mut detector = DoublePressDetector::new(WINDOW);
+
+        assert_eq!(detector.on_press(start), PressGesture::Pending);
+        assert_eq!(
+            detector.on_press(start + Duration::from_millis(250)),
+            PressGesture::Double
+        );
+        assert_eq!(detector.deadline(), None);
+        assert!(!detector.take_expired(start + WINDOW * 2));
+    }
+
+    fn turn(encoder: EncoderId, delta: i32) -> HardwareEvent {
+        HardwareEvent::EncoderTurned { encoder, delta }
+    }
+
+    #[test]
+    fn turn_bursts_are_summed_without_crossing_other_events() {
+        let mut burst: Vec<HardwareEvent> = (0..30)
+            .map(|index| turn(EncoderId::One, if index % 10 == 9 { -1 } else { 1 }))
+            .collect();
+        burst.insert(10, turn(EncoderId::Two, -1));
+        burst.insert(
+            20,
+            HardwareEvent::EncoderPressed {
+                encoder: EncoderId::One,
+            },
+        );
+        burst.extend((0..17).map(|_| turn(EncoderId::One, -1)));
+        burst.push(turn(EncoderId::Two, 1));
+        assert_eq!(burst.len(), 50);
+
+        let coalesced = coalesce_turns(burst);
+        assert_eq!(
+            coalesced,
+            vec![
+                turn(EncoderId::One, 17),
+                turn(EncoderId::Two, -1),
+                HardwareEvent::EncoderPressed {
+                    encoder: EncoderId::One
+                },
+                turn(EncoderId::One, 7 - 17),
+                turn(EncoderId::Two, 1),
+            ]
+        );
+        assert_eq!(
+            coalesce_turns([turn(EncoderId::Three, 1), turn(EncoderId::Three, -1)]),
+            Vec::new()
+        );
+    }
+
+    struct NullDisplay;
+
+    impl DisplayPipeline for NullDisplay {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fifty_detent_burst_reaches_the_backend_once() {
+        let audio = MockAudioBackend::default();
+        let mut volume = VolumeController::new(audio.clone(), NullDisplay, EncoderId::One, 1)
+            .expect("volume controller");
+        let burst = (0..50).map(|_| turn(EncoderId::One, -1));
+
+        for event in coalesce_turns(burst) {
+            if let HardwareEvent::EncoderTurned { delta, .. } = event {
+                assert_eq!(delta, -50);
+                volume.on_turn(delta).unwrap();
+            }
+        }
+        let history = audio.inner.lock().unwrap().history.clone();
+        assert_eq!(history, vec!["adjust:-50"]);
+    }
+
+    #[test]
+    fn short_swipes_are_taps_and_long_ones_keep_their_direction() {
+        let cases = [
+            ((650, 630), StripGesture::Tap(EncoderId::Four)),
+            ((650, 611), StripGesture::Tap(EncoderId::Four)),
+            ((198, 210), StripGesture::Ignored),
+            (
+                (650, 610),
+                StripGesture::Swipe {
+                    segment: EncoderId::Four,
+                    forward: true,
+                },
+            ),
+            (
+                (620, 780),
+                StripGesture::Swipe {
+                    segment: EncoderId::Four,
+                    forward: false,
+                },
+            ),
+            (
+                (180, 20),
+                StripGesture::Swipe {
+                    segment: EncoderId::One,
+                    forward: true,
+                },
+            ),
+        ];
+        for ((from_x, to_x), expected) in cases {
+            assert_eq!(
+                classify_swipe(from_x, to_x, 40),
+                expected,
+                "{from_x}->{to_x}"
+            );
+        }
+    }
+
+    #[test]
+    fn holds_at_the_threshold_are_long() {
+        let hold = HoldDetector::new(Duration::from_millis(600));
+        assert!(!hold.is_long(Duration::from_millis(599)));
+        assert!(hold.is_long(Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn late_second_press_starts_a_new_window() {
+        let start = Instant::now();
+        let mut detector = DoublePressDetector::new(WINDOW);
+
+        detector.on_press(start);
+        let late = start + Duration::from_millis(500);
+        assert_eq!(detector.on_press(late), PressGesture::Pending);
+        assert_eq!(detector.deadline(), Some(late + WINDOW));
+    }
+}
@@ -4,28 +4,62 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use once_cell::sync::Lazy;
 use tracing::{debug, info, warn};
 
-use crate::config::LauncherButtonConfig;
+use crate::config::{LauncherButtonConfig, parse_hex_color};
 use crate::hardware::{ButtonImage, DisplayPipeline};
 use crate::system::desktop::DesktopEntry;
+use crate::system::window_focus::{DesktopWindowActivator, WindowActivator};
 use crate::util::icons;
 
+use super::Tickable;
+use super::audio_toggle::{IconPaths, UNAVAILABLE_TINT, load_configured_icon};
+use super::gesture::{DoublePressDetector, HoldDetector, PressGesture};
+
+// Theme icon names resolved so far, including names that were not found.
+static ICON_NAME_CACHE: Lazy<Mutex<HashMap<String, Option<PathBuf>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+// Sizes tried first when looking a name up in the hicolor theme.
+const HICOLOR_SIZES: &[&str] = &["48x48", "64x64", "scalable", "128x128", "256x256", "32x32"];
+const DEFAULT_DOUBLE_PRESS_WINDOW: Duration = Duration::from_millis(400);
+const DEFAULT_LONG_PRESS: Duration = Duration::from_millis(600);
+
 pub struct LauncherController {
     buttons: HashMap<u8, LauncherButton>,
+    windows: Box<dyn WindowActivator>,
+    /// One per key with a double-press entry; its single press waits here.
+    presses: HashMap<u8, DoublePressDetector>,
+    long_press: HoldDetector,
 }
 
 impl LauncherController {
-    pub fn new<H>(configs: &[LauncherButtonConfig], hardware: &H) -> Result<Option<Self>>
+    pub fn new<H>(
+        configs: &[LauncherButtonConfig],
+        config_path: Option<&Path>,
+        hardware: &H,
+    ) -> Result<Option<Self>>
     where
         H: DisplayPipeline,
     {
+        let icon_paths = IconPaths::new(config_path);
         let mut buttons = HashMap::new();
 
-        for entry in configs {
-            match LauncherButton::from_config(entry) {
+        let started = Instant::now();
+        let resolved = resolve_buttons(configs, &icon_paths);
+        info!(
+            buttons = configs.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "resolved launcher icons"
+        );
+
+        for (entry, resolved) in configs.iter().zip(resolved) {
+            match resolved {
                 Ok(button) => {
                     if let Some(previous) = buttons.insert(entry.button_index, button) {
                         warn!(
@@ -58,17 +92,202 @@ impl LauncherController {
             }
         }
 
-        Ok(Some(Self { buttons }))
+        let presses = buttons
+            .iter()
+            .filter(|(_, button)| button.double_press.is_some())
+            .map(|(index, _)| {
+                (
+                    *index,
+                    DoublePressDetector::new(DEFAULT_DOUBLE_PRESS_WINDOW),
+                )
+            })
+            .collect();
+        Ok(Some(Self {
+            buttons,
+            windows: Box::new(DesktopWindowActivator),
+            presses,
+            long_press: HoldDetector::new(DEFAULT_LONG_PRESS),
+        }))
+    }
+
+    pub fn with_double_press_window(mut self, window: Duration) -> Self {
+        for presses in self.presses.values_mut() {
+            *presses = DoublePressDetector::new(window);
+        }
+        self
+    }
+
+    pub fn with_long_press(mut self, long_press: HoldDetector) -> Self {
+        self.long_press = long_press;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_window_activator(mut self, windows: impl WindowActivator + 'static) -> Self {
+        self.windows = Box::new(windows);
+        self
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.buttons.keys().copied()
+    }
+
+    // Buttons with a long- or double-press action launch on release instead,
+    // once the press duration is known.
+    pub fn on_button_pressed(&mut self, index: u8) -> Result<bool> {
+        match self.buttons.get(&index) {
+            Some(button) if button.launches_on_release() => Ok(true),
+            Some(button) => {
+                button.activate(self.windows.as_ref())?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// `held` is how long the key was down; past the long-press threshold
+    /// the key's long-press entry runs instead.
+    pub fn on_button_released(&mut self, index: u8, held: Duration) -> Result<bool> {
+        if self.long_press.is_long(held) {
+            return self.long_pressed(index);
+        }
+        self.released_at(index, Instant::now())
     }
 
-    pub fn on_button_pressed(&self, index: u8) -> Result<bool> {
-        if let Some(button) = self.buttons.get(&index) {
-            button.activate()?;
-            Ok(true)
-        } else {
-            Ok(false)
+    fn released_at(&mut self, index: u8, now: Instant) -> Result<bool> {
+        let Some(button) = self.buttons.get(&index) else {
+            return Ok(false);
+        };
+        if let Some(presses) = self.presses.get_mut(&index) {
+            // The single press is held back until `on_tick` finds the window
+            // closed, so a double press never starts both.
+            if presses.on_press(now) == PressGesture::Double {
+                button.activate_double_press(self.windows.as_ref())?;
+            }
+        } else if button.long_press.is_some() {
+            button.activate(self.windows.as_ref())?;
         }
+        Ok(true)
+    }
+
+    /// When the oldest held-back single press is due.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.presses
+            .values()
+            .filter_map(DoublePressDetector::deadline)
+            .min()
+    }
+
+    fn flush_expired(&mut self, now: Instant) -> Result<()> {
+        for (index, presses) in &mut self.presses {
+            if !presses.take_expired(now) {
+                continue;
+            }
+            if let Some(button) = self.buttons.get(index) {
+                button.activate(self.windows.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Plain launchers already started when pressed, so holding them does
+    // nothing more.
+    fn long_pressed(&mut self, index: u8) -> Result<bool> {
+        match self.buttons.get(&index) {
+            Some(button) if !button.launches_on_release() => Ok(true),
+            Some(button) => {
+                button.activate_long_press(self.windows.as_ref())?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl Tickable for LauncherController {
+    fn on_tick(&mut self) -> Result<()> {
+        self.flush_expired(Instant::now())
+    }
+}
+
+// Desktop entries are parsed and their icons found and decoded on a few
+// scoped workers; results come back in configuration order so a later entry
+// for the same key still wins.
+fn resolve_buttons(
+    configs: &[LauncherButtonConfig],
+    icon_paths: &IconPaths,
+) -> Vec<Result<LauncherButton>> {
+    if configs.is_empty() {
+        return Vec::new();
+    }
+    let workers = thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .min(configs.len());
+    let chunk_size = configs.len().div_ceil(workers);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = configs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|config| LauncherButton::from_config(config, icon_paths))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .zip(configs.chunks(chunk_size))
+            .flat_map(|(handle, chunk)| {
+                handle.join().unwrap_or_else(|_| {
+                    chunk
+                        .iter()
+                        .map(|_| Err(anyhow!("launcher icon resolution panicked")))
+                        .collect()
+                })
+            })
+            .collect()
+    })
+}
+
+/// Resolves a launcher the way the controller would, returning the problems
+/// that leave the button usable; an `Err` means it would be skipped.
+pub(super) fn check_launcher(
+    config: &LauncherButtonConfig,
+    icon_paths: &IconPaths,
+) -> Result<Vec<String>> {
+    let button = LauncherButton::from_config(config, icon_paths)?;
+    let mut warnings = Vec::new();
+    if let Some(Err(err)) = config
+        .icon
+        .as_ref()
+        .map(|icon| load_configured_icon(icon, icon_paths))
+    {
+        warnings.push(format!("configured icon failed to load: {err:#}"));
+    }
+    if button.icon.is_none() {
+        warnings.push("no icon found for the desktop entry".to_string());
+    }
+    if let Some(try_exec) = &button.missing_try_exec {
+        warnings.push(format!("TryExec program `{try_exec}` is not installed"));
+    }
+    if config.long_press_desktop_file.is_some() {
+        if !matches!(button.long_press, Some(AltLaunch::Entry(_))) {
+            warnings.push("long-press desktop file could not be loaded".to_string());
+        }
+    } else if config.long_press_action.is_some() && button.long_press.is_none() {
+        warnings.push("long-press action is not defined by the desktop entry".to_string());
+    }
+    if config.double_press_desktop_file.is_some() && button.double_press.is_none() {
+        warnings.push("double-press desktop file could not be loaded".to_string());
+    }
+    if button.exec.is_none() && button.action.is_none() {
+        warnings.push("desktop entry has no Exec line".to_string());
     }
+    Ok(warnings)
 }
 
 #[derive(Clone)]
@@ -77,49 +296,199 @@ struct LauncherButton {
     name: Option<String>,
     icon: Option<ButtonImage>,
     exec: Option<ExecSpec>,
+    /// The configured desktop action, run in place of `exec`.
+    action: Option<EntryAction>,
+    long_press: Option<AltLaunch>,
+    double_press: Option<Box<LauncherButton>>,
+    /// The entry's `TryExec` program when it is not installed.
+    missing_try_exec: Option<String>,
     working_dir: Option<PathBuf>,
     terminal: bool,
     source_path: PathBuf,
+    /// Set for single-instance launchers: the app id or WM class to focus.
+    window_class: Option<String>,
 }
 
 impl LauncherButton {
-    fn from_config(config: &LauncherButtonConfig) -> Result<Self> {
+    fn from_config(config: &LauncherButtonConfig, icon_paths: &IconPaths) -> Result<Self> {
         let entry = DesktopEntry::from_path(&config.desktop_file)?;
+        ensure_application(&entry)?;
+
+        let action = config
+            .action
+            .as_deref()
+            .map(|action| resolve_action(&entry, action))
+            .transpose()?;
+        let missing_try_exec = entry
+            .try_exec
+            .clone()
+            .filter(|try_exec| !try_exec_available(try_exec));
+        if let Some(try_exec) = missing_try_exec.as_deref() {
+            warn!(
+                desktop_id = %entry.desktop_id,
+                try_exec,
+                "launcher program is not installed; button disabled"
+            );
+        }
 
-        if let Some(entry_type) = entry.entry_type.as_deref() {
-            if !entry_type.eq_ignore_ascii_case("application") {
-                bail!("desktop entry type {entry_type:?} is not supported for launchers");
+        let label = action
+            .as_ref()
+            .and_then(|action| action.name.clone())
+            .or_else(|| entry.name.clone())
+            .filter(|_| config.show_label);
+        let background =
+            config
+                .background
+                .as_deref()
+                .and_then(|value| match parse_hex_color(value) {
+                    Ok(color) => Some(color),
+                    Err(err) => {
+                        warn!(
+                            error = %err,
+                            button_index = config.button_index,
+                            "ignoring launcher background colour"
+                        );
+                        None
+                    }
+                });
+        let icon = match config
+            .icon
+            .as_ref()
+            .map(|icon| load_configured_icon(icon, icon_paths))
+        {
+            Some(Ok(icon)) => Some(ButtonImage {
+                id: format!("launcher:{}:{}", entry.desktop_id, icon.id),
+                ..icon
+            }),
+            Some(Err(err)) => {
+                warn!(
+                    error = %err,
+                    button_index = config.button_index,
+                    "failed to load configured launcher icon; using the desktop entry icon"
+                );
+                resolve_icon(&entry).transpose()?
             }
+            None => resolve_icon(&entry).transpose()?,
         }
-
-        let icon = resolve_icon(&entry)
-            .transpose()?
-            .map(|(id, image)| ButtonImage {
-                id,
-                image,
-                tint: None,
-            });
+        .map(|icon| ButtonImage {
+            label,
+            background,
+            ..icon
+        })
+        .map(|icon| match missing_try_exec {
+            Some(_) => ButtonImage {
+                id: format!("{}:unavailable", icon.id),
+                tint: Some(UNAVAILABLE_TINT),
+                ..icon
+            },
+            None => icon,
+        });
 
         let exec = parse_exec(&entry);
+        let long_press = match &config.long_press_desktop_file {
+            Some(path) => other_entry(path, &entry, "long-press").map(AltLaunch::Entry),
+            None => config.long_press_action.as_deref().and_then(|action| {
+                resolve_action(&entry, action)
+                    .inspect_err(|err| {
+                        warn!(
+                            error = %err,
+                            desktop_id = %entry.desktop_id,
+                            "ignoring launcher long-press action"
+                        )
+                    })
+                    .ok()
+                    .map(AltLaunch::Action)
+            }),
+        };
+        let double_press = config
+            .double_press_desktop_file
+            .as_deref()
+            .and_then(|path| other_entry(path, &entry, "double-press"));
+
+        // Without StartupWMClass, Wayland apps usually use the desktop file
+        // name as their app id.
+        let window_class = config.single_instance.then(|| {
+            entry
+                .startup_wm_class
+                .clone()
+                .unwrap_or_else(|| entry.desktop_id.trim_end_matches(".desktop").to_string())
+        });
 
         Ok(Self {
             desktop_id: entry.desktop_id,
             name: entry.name,
             icon,
             exec,
+            action,
+            long_press,
+            double_press,
+            missing_try_exec,
             working_dir: entry.working_dir,
             terminal: entry.terminal,
             source_path: entry.source_path,
+            window_class,
         })
     }
 
-    fn activate(&self) -> Result<()> {
+    // Entries reached by a long or double press have no key of their own, so
+    // no icon, label or window to focus.
+    fn from_desktop_file(path: &Path) -> Result<Self> {
+        let entry = DesktopEntry::from_path(path)?;
+        ensure_application(&entry)?;
+        let missing_try_exec = entry
+            .try_exec
+            .clone()
+            .filter(|try_exec| !try_exec_available(try_exec));
+        Ok(Self {
+            exec: parse_exec(&entry),
+            desktop_id: entry.desktop_id,
+            name: entry.name,
+            icon: None,
+            action: None,
+            long_press: None,
+            double_press: None,
+            missing_try_exec,
+            working_dir: entry.working_dir,
+            terminal: entry.terminal,
+            source_path: entry.source_path,
+            window_class: None,
+        })
+    }
+
+    fn launches_on_release(&self) -> bool {
+        self.long_press.is_some() || self.double_press.is_some()
+    }
+
+    fn activate(&self, windows: &dyn WindowActivator) -> Result<()> {
+        if self.refuse_unavailable() {
+            return Ok(());
+        }
+        if let Some(action) = &self.action {
+            return self.run_action(action);
+        }
         info!(
             desktop_id = %self.desktop_id,
             app = self.name.as_deref().unwrap_or("Unnamed Application"),
             "activating launcher"
         );
 
+        if let Some(class) = self.window_class.as_deref() {
+            match windows.activate(class) {
+                Ok(true) => {
+                    debug!(desktop_id = %self.desktop_id, class, "focused running window");
+                    return Ok(());
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    warn!(
+                        error = %err,
+                        desktop_id = %self.desktop_id,
+                        "failed to look for a running window; launching instead"
+                    );
+                }
+            }
+        }
+
         match try_gtk_launch(&self.desktop_id) {
             Ok(()) => return Ok(()),
             Err(err) if err.kind() == io::ErrorKind::NotFound => {
@@ -153,12 +522,126 @@ impl LauncherButton {
             )
         })
     }
+
+    fn activate_long_press(&self, windows: &dyn WindowActivator) -> Result<()> {
+        match &self.long_press {
+            Some(AltLaunch::Action(_)) if self.refuse_unavailable() => Ok(()),
+            Some(AltLaunch::Action(action)) => self.run_action(action),
+            Some(AltLaunch::Entry(other)) => other.activate(windows),
+            None => self.activate(windows),
+        }
+    }
+
+    fn activate_double_press(&self, windows: &dyn WindowActivator) -> Result<()> {
+        match &self.double_press {
+            Some(other) => other.activate(windows),
+            None => self.activate(windows),
+        }
+    }
+
+    fn refuse_unavailable(&self) -> bool {
+        let Some(try_exec) = self.missing_try_exec.as_deref() else {
+            return false;
+        };
+        warn!(
+            desktop_id = %self.desktop_id,
+            try_exec,
+            "not launching: TryExec program is not installed or not executable"
+        );
+        true
+    }
+
+    fn run_action(&self, action: &EntryAction) -> Result<()> {
+        info!(
+            desktop_id = %self.desktop_id,
+            action = %action.id,
+            "activating launcher desktop action"
+        );
+        launch_exec(&action.exec, self.working_dir.as_deref(), self.terminal).with_context(|| {
+            format!(
+                "failed to execute action {} for desktop entry {}",
+                action.id, self.desktop_id
+            )
+        })
+    }
 }
 
+/// What holding a key starts instead of its main launch.
 #[derive(Clone)]
-struct ExecSpec {
-    program: String,
-    args: Vec<String>,
+enum AltLaunch {
+    /// A `[Desktop Action ...]` of the key's own entry.
+    Action(EntryAction),
+    Entry(Box<LauncherButton>),
+}
+
+fn ensure_application(entry: &DesktopEntry) -> Result<()> {
+    if let Some(entry_type) = entry.entry_type.as_deref() {
+        if !entry_type.eq_ignore_ascii_case("application") {
+            bail!("desktop entry type {entry_type:?} is not supported for launchers");
+        }
+    }
+    Ok(())
+}
+
+fn other_entry(path: &Path, entry: &DesktopEntry, gesture: &str) -> Option<Box<LauncherButton>> {
+    LauncherButton::from_desktop_file(path)
+        .inspect_err(|err| {
+            warn!(
+                error = %err,
+                desktop_id = %entry.desktop_id,
+                path = %path.display(),
+                "ignoring launcher {gesture} desktop file"
+            )
+        })
+        .ok()
+        .map(Box::new)
+}
+
+#[derive(Clone)]
+struct EntryAction {
+    id: String,
+    name: Option<String>,
+    exec: ExecSpec,
+}
+
+fn resolve_action(entry: &DesktopEntry, action: &str) -> Result<EntryAction> {
+    let desktop_action = entry
+        .actions
+        .get(action)
+        .ok_or_else(|| anyhow!("desktop entry has no action {action:?}"))?;
+    let exec = desktop_action
+        .exec
+        .as_deref()
+        .and_then(|exec| parse_exec_command(exec, entry))
+        .ok_or_else(|| anyhow!("desktop action {action:?} lacks an executable command"))?;
+    Ok(EntryAction {
+        id: action.to_string(),
+        name: desktop_action.name.clone(),
+        exec,
+    })
+}
+
+// TryExec is an absolute path or a program name looked up on PATH.
+fn try_exec_available(try_exec: &str) -> bool {
+    let path = Path::new(try_exec);
+    if path.is_absolute() {
+        return is_executable(path);
+    }
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(try_exec))))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ExecSpec {
+    pub(super) program: String,
+    pub(super) args: Vec<String>,
+    pub(super) env: Vec<(String, String)>,
 }
 
 fn try_gtk_launch(desktop_id: &str) -> io::Result<()> {
@@ -171,9 +654,14 @@ fn try_gtk_launch(desktop_id: &str) -> io::Result<()> {
         .map(|_| ())
 }
 
-fn launch_exec(spec: &ExecSpec, working_dir: Option<&Path>, terminal: bool) -> Result<()> {
+pub(super) fn launch_exec(
+    spec: &ExecSpec,
+    working_dir: Option<&Path>,
+    terminal: bool,
+) -> Result<()> {
     let mut command = Command::new(&spec.program);
     command.args(&spec.args);
+    command.envs(spec.env.iter().map(|(key, value)| (key, value)));
     command.stdin(Stdio::null());
     command.stdout(Stdio::null());
     command.stderr(Stdio::null());
@@ -195,18 +683,28 @@ fn launch_exec(spec: &ExecSpec, working_dir: Option<&Path>, terminal: bool) -> R
 }
 
 fn parse_exec(entry: &DesktopEntry) -> Option<ExecSpec> {
-    let command = entry.exec.as_ref()?;
-    let tokens = split_exec(command);
-    let mut processed = Vec::new();
+    parse_exec_command(entry.exec.as_ref()?, entry)
+}
 
-    for token in tokens {
-        if let Some(clean) = strip_field_codes(&token) {
-            if !clean.is_empty() {
-                processed.push(clean);
+fn parse_exec_command(command: &str, entry: &DesktopEntry) -> Option<ExecSpec> {
+    let mut processed = Vec::new();
+    for token in split_desktop_exec(&unescape_string(command)) {
+        // %i is the only code that expands to more than one argument, and
+        // only when it stands alone.
+        if token == "%i" {
+            if let Some(icon) = &entry.icon {
+                processed.push("--icon".to_string());
+                processed.push(icon.clone());
             }
+            continue;
+        }
+        let clean = expand_field_codes(&token, entry);
+        if !clean.is_empty() || token.is_empty() {
+            processed.push(clean);
         }
     }
 
+    let env = strip_env_prefix(&mut processed);
     if processed.is_empty() {
         return None;
     }
@@ -215,10 +713,116 @@ fn parse_exec(entry: &DesktopEntry) -> Option<ExecSpec> {
     Some(ExecSpec {
         program,
         args: processed,
+        env,
     })
 }
 
-fn split_exec(command: &str) -> Vec<String> {
+// `env NAME=value... program` is unwrapped so the assignments are applied to
+// the spawned process directly; anything using env's own flags is left alone.
+fn strip_env_prefix(tokens: &mut Vec<String>) -> Vec<(String, String)> {
+    if tokens.first().map(String::as_str) != Some("env") {
+        return Vec::new();
+    }
+    let assignments: Vec<(String, String)> = tokens[1..]
+        .iter()
+        .map_while(|token| {
+            let (key, value) = token.split_once('=')?;
+            is_env_name(key).then(|| (key.to_string(), value.to_string()))
+        })
+        .collect();
+    let rest = &tokens[1 + assignments.len()..];
+    if rest.first().is_none_or(|program| program.starts_with('-')) {
+        return Vec::new();
+    }
+    tokens.drain(..=assignments.len());
+    assignments
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_ascii_alphabetic())
+        && chars.all(|ch| ch == '_' || ch.is_ascii_alphanumeric())
+}
+
+// Desktop entry string values escape `\s`, `\n`, `\t`, `\r` and `\\`;
+// these are undone before the Exec quoting rules apply.
+fn unescape_string(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            output.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => output.push(' '),
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('\\') => output.push('\\'),
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            }
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+// Exec quoting only knows double quotes, inside which a backslash escapes
+// `"`, `` ` ``, `$` and `\\`. Single quotes are accepted as well since plenty
+// of entries in the wild use them for `sh -c` arguments.
+fn split_desktop_exec(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = command.chars().peekable();
+    let mut in_single = false;
+    let mut in_double = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' if !in_double => {
+                in_single = !in_single;
+                quoted = true;
+            }
+            '"' if !in_single => {
+                in_double = !in_double;
+                quoted = true;
+            }
+            '\\' if in_double => match chars.peek() {
+                Some(&next @ ('"' | '`' | '$' | '\\')) => {
+                    chars.next();
+                    current.push(next);
+                }
+                _ => current.push(ch),
+            },
+            '\\' if !in_single => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !in_single && !in_double => {
+                if !current.is_empty() || quoted {
+                    args.push(std::mem::take(&mut current));
+                }
+                quoted = false;
+            }
+            _ => current.push(ch),
+        }
+    }
+
+    if !current.is_empty() || quoted {
+        args.push(current);
+    }
+
+    args
+}
+
+pub(super) fn split_exec(command: &str) -> Vec<String> {
     let mut args = Vec::new();
     let mut current = String::new();
     let mut chars = command.chars().peekable();
@@ -255,35 +859,30 @@ fn split_exec(command: &str) -> Vec<String> {
     args
 }
 
-fn strip_field_codes(token: &str) -> Option<String> {
+// Field codes are removed in place so `--new-window=%u` keeps its flag;
+// `%c` and `%k` expand from the entry since a button press has no files.
+fn expand_field_codes(token: &str, entry: &DesktopEntry) -> String {
     let mut output = String::new();
-    let mut chars = token.chars().peekable();
+    let mut chars = token.chars();
     while let Some(ch) = chars.next() {
-        if ch == '%' {
-            if let Some(next) = chars.next() {
-                match next {
-                    '%' => output.push('%'),
-                    'f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' | 'k' | 'c' | 'i'
-                    | 's' => return None,
-                    other => {
-                        debug!(placeholder = %other, "dropping unsupported Exec placeholder");
-                        return None;
-                    }
-                }
-            } else {
-                output.push('%');
-            }
-        } else {
+        if ch != '%' {
             output.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('%') | None => output.push('%'),
+            Some('c') => output.push_str(entry.name.as_deref().unwrap_or_default()),
+            Some('k') => output.push_str(&entry.source_path.to_string_lossy()),
+            Some('f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' | 'i') => {}
+            Some(other) => {
+                debug!(placeholder = %other, "dropping unsupported Exec placeholder");
+            }
         }
     }
-
-    Some(output)
+    output
 }
 
-fn resolve_icon(
-    entry: &DesktopEntry,
-) -> Option<Result<(String, std::sync::Arc<image::RgbaImage>)>> {
+fn resolve_icon(entry: &DesktopEntry) -> Option<Result<ButtonImage>> {
     let icon = entry.icon.as_deref()?;
     let entry_dir = entry.source_path.parent();
 
@@ -319,19 +918,43 @@ fn resolve_icon(
         }
     }
 
-    for dir in icon_search_directories() {
-        if let Some(found) = search_icon_in_dir(&dir, icon, 2) {
-            return Some(load_icon_image(&found, &entry.desktop_id));
-        }
+    let found = lookup_icon_name(icon)?;
+    Some(load_icon_image(&found, &entry.desktop_id))
+}
+
+fn lookup_icon_name(name: &str) -> Option<PathBuf> {
+    if let Some(cached) = ICON_NAME_CACHE
+        .lock()
+        .expect("icon name cache mutex poisoned")
+        .get(name)
+    {
+        return cached.clone();
     }
 
-    None
+    let dirs = icon_search_directories();
+    let found = themed_icon_path(&dirs, name)
+        .or_else(|| dirs.iter().find_map(|dir| search_icon_in_dir(dir, name, 2)));
+    ICON_NAME_CACHE
+        .lock()
+        .expect("icon name cache mutex poisoned")
+        .insert(name.to_string(), found.clone());
+    found
+}
+
+// Checks the usual hicolor application sizes and the top of each directory
+// (as for pixmaps) directly, without listing any directory.
+fn themed_icon_path(dirs: &[PathBuf], name: &str) -> Option<PathBuf> {
+    dirs.iter().find_map(|dir| {
+        HICOLOR_SIZES
+            .iter()
+            .map(|size| dir.join("hicolor").join(size).join("apps"))
+            .chain(std::iter::once(dir.clone()))
+            .flat_map(|base| icon_name_candidates(&base, name))
+            .find(|candidate| candidate.is_file())
+    })
 }
 
-fn load_icon_image(
-    path: &Path,
-    desktop_id: &str,
-) -> Result<(String, std::sync::Arc<image::RgbaImage>)> {
+fn load_icon_image(path: &Path, desktop_id: &str) -> Result<ButtonImage> {
     let id = format!(
         "launcher:{}:{}",
         desktop_id,
@@ -339,7 +962,15 @@ fn load_icon_image(
             .and_then(|name| name.to_str())
             .unwrap_or("icon")
     );
-    icons::load_icon(path).map(|image| (id, image))
+    Ok(ButtonImage {
+        id,
+        image: icons::load_icon(path)?,
+        tint: None,
+        label: None,
+        animation: icons::load_animated_icon(path)?,
+        background: None,
+        badge: None,
+    })
 }
 
 fn resolve_with_extensions(base: &Path) -> Option<PathBuf> {
@@ -410,13 +1041,17 @@ fn search_icon_in_dir(base: &Path, name: &str, depth: usize) -> Option<PathBuf>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::controls::IconConfig;
 
     use std::sync::Arc;
     use tempfile::tempdir;
 
+    // Button index and icon id of each icon update, in order.
+    type IconUpdates = Arc<std::sync::Mutex<Vec<(u8, Option<String>)>>>;
+
     #[derive(Clone)]
     struct RecordingHardware {
-        updates: Arc<std::sync::Mutex<Vec<(u8, Option<String>)>>>,
+        updates: IconUpdates,
     }
 
     impl RecordingHardware {
@@ -449,42 +1084,140 @@ mod tests {
         }
     }
 
-    #[test]
-    fn parses_exec_without_placeholders() {
-        let entry = DesktopEntry {
+    fn entry_with_exec(exec: &str) -> DesktopEntry {
+        DesktopEntry {
             source_path: PathBuf::from("/tmp/app.desktop"),
             desktop_id: "app.desktop".into(),
             name: Some("App".into()),
-            icon: None,
-            exec: Some("env VAR=1 /usr/bin/app --flag".into()),
+            icon: Some("app-icon".into()),
+            exec: Some(exec.into()),
             try_exec: None,
             working_dir: None,
             terminal: false,
             startup_wm_class: None,
             entry_type: Some("Application".into()),
-        };
-        let spec = parse_exec(&entry).expect("exec should parse");
-        assert_eq!(spec.program, "env");
-        assert_eq!(spec.args, vec!["VAR=1", "/usr/bin/app", "--flag"]);
+            actions: HashMap::new(),
+        }
+    }
+
+    fn parse(exec: &str) -> ExecSpec {
+        parse_exec(&entry_with_exec(exec)).expect("exec should parse")
+    }
+
+    #[test]
+    fn parses_exec_without_placeholders() {
+        let spec = parse("/usr/bin/app --flag");
+        assert_eq!(spec.program, "/usr/bin/app");
+        assert_eq!(spec.args, vec!["--flag"]);
+        assert!(spec.env.is_empty());
     }
 
     #[test]
     fn removes_field_codes_from_exec() {
-        let entry = DesktopEntry {
-            source_path: PathBuf::from("/tmp/app.desktop"),
-            desktop_id: "app.desktop".into(),
-            name: Some("App".into()),
-            icon: None,
-            exec: Some("\"/usr/bin/app\" %f --option=%u".into()),
-            try_exec: None,
-            working_dir: None,
-            terminal: false,
-            startup_wm_class: None,
-            entry_type: Some("Application".into()),
-        };
-        let spec = parse_exec(&entry).expect("exec should parse");
+        let spec = parse("\"/usr/bin/app\" %f --option=%u %U");
         assert_eq!(spec.program, "/usr/bin/app");
-        assert_eq!(spec.args, Vec::<String>::new());
+        assert_eq!(spec.args, vec!["--option="]);
+    }
+
+    #[test]
+    fn unwraps_env_prefix_into_environment() {
+        let spec = parse("env VAR=1 OTHER=\"a b\" /usr/bin/app --flag");
+        assert_eq!(spec.program, "/usr/bin/app");
+        assert_eq!(spec.args, vec!["--flag"]);
+        assert_eq!(
+            spec.env,
+            vec![
+                ("VAR".to_string(), "1".to_string()),
+                ("OTHER".to_string(), "a b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_env_with_flags_alone() {
+        let spec = parse("env -u VAR /usr/bin/app");
+        assert_eq!(spec.program, "env");
+        assert_eq!(spec.args, vec!["-u", "VAR", "/usr/bin/app"]);
+        assert!(spec.env.is_empty());
+    }
+
+    #[test]
+    fn keeps_quoted_program_path_with_spaces() {
+        let spec = parse("\"/opt/My App/bin/app\" --open");
+        assert_eq!(spec.program, "/opt/My App/bin/app");
+        assert_eq!(spec.args, vec!["--open"]);
+    }
+
+    #[test]
+    fn handles_escapes_inside_quotes() {
+        // `\\\\` in the file is one backslash after both unescaping passes.
+        let spec = parse(r#"app "say \"hi\"" "cost \$5" "back\\\\slash" "keep\x""#);
+        assert_eq!(
+            spec.args,
+            vec!["say \"hi\"", "cost $5", "back\\slash", "keep\\x"]
+        );
+    }
+
+    #[test]
+    fn keeps_empty_quoted_arguments() {
+        let spec = parse("app \"\" --flag");
+        assert_eq!(spec.args, vec!["", "--flag"]);
+    }
+
+    #[test]
+    fn expands_entry_field_codes() {
+        let spec = parse("app --title=%c-window %i --desktop-file %k");
+        assert_eq!(
+            spec.args,
+            vec![
+                "--title=App-window",
+                "--icon",
+                "app-icon",
+                "--desktop-file",
+                "/tmp/app.desktop",
+            ]
+        );
+    }
+
+    #[test]
+    fn preserves_literal_and_trailing_percent() {
+        let spec = parse("app --progress=100%% --scale 50%");
+        assert_eq!(spec.args, vec!["--progress=100%", "--scale", "50%"]);
+    }
+
+    #[test]
+    fn strips_field_codes_inside_shell_commands() {
+        let spec = parse("sh -c \"foo %f; bar\"");
+        assert_eq!(spec.program, "sh");
+        assert_eq!(spec.args, vec!["-c", "foo ; bar"]);
+    }
+
+    #[test]
+    fn rejects_exec_made_only_of_field_codes() {
+        assert!(parse_exec(&entry_with_exec("%f %U")).is_none());
+    }
+
+    #[test]
+    fn finds_hicolor_icons_without_scanning() {
+        let dir = tempdir().unwrap();
+        let icons = dir.path().join("icons");
+        let apps = icons.join("hicolor/64x64/apps");
+        fs::create_dir_all(&apps).unwrap();
+        fs::write(apps.join("sample.png"), b"").unwrap();
+        let pixmaps = dir.path().join("pixmaps");
+        fs::create_dir_all(&pixmaps).unwrap();
+        fs::write(pixmaps.join("legacy.xpm"), b"").unwrap();
+        let dirs = [icons, pixmaps.clone()];
+
+        assert_eq!(
+            themed_icon_path(&dirs, "sample"),
+            Some(apps.join("sample.png"))
+        );
+        assert_eq!(
+            themed_icon_path(&dirs, "legacy"),
+            Some(pixmaps.join("legacy.xpm"))
+        );
+        assert_eq!(themed_icon_path(&dirs, "missing"), None);
     }
 
     #[test]
@@ -515,10 +1248,19 @@ Type=Application
         let config = LauncherButtonConfig {
             button_index: 5,
             desktop_file: desktop_path.clone(),
+            action: None,
+            long_press_action: None,
+            long_press_desktop_file: None,
+            double_press_desktop_file: None,
+            show_label: false,
+            icon: None,
+            background: None,
+            single_instance: false,
+            device: None,
         };
 
         let hardware = RecordingHardware::new();
-        let controller = LauncherController::new(&[config], &hardware)
+        let controller = LauncherController::new(&[config], None, &hardware)
             .expect("launcher creation should succeed")
             .expect("launcher controller should be created");
 
@@ -528,4 +1270,383 @@ Type=Application
         assert_eq!(updates[0].0, 5);
         assert!(updates[0].1.as_deref().unwrap().contains("launcher"));
     }
+
+    #[test]
+    fn show_label_uses_desktop_entry_name() {
+        let dir = tempdir().unwrap();
+        let icon_path = dir.path().join("icon.svg");
+        fs::write(
+            &icon_path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"></svg>"#,
+        )
+        .unwrap();
+        let desktop_path = dir.path().join("dev-shell.desktop");
+        fs::write(
+            &desktop_path,
+            format!(
+                "[Desktop Entry]
+Name=Dev Shell
+Exec=/usr/bin/true
+Icon={}
+Type=Application
+",
+                icon_path.display()
+            ),
+        )
+        .unwrap();
+
+        for (show_label, expected) in [(true, Some("Dev Shell")), (false, None)] {
+            let button = LauncherButton::from_config(
+                &LauncherButtonConfig {
+                    button_index: 2,
+                    desktop_file: desktop_path.clone(),
+                    action: None,
+                    long_press_action: None,
+                    long_press_desktop_file: None,
+                    double_press_desktop_file: None,
+                    show_label,
+                    icon: None,
+                    background: None,
+                    single_instance: false,
+                    device: None,
+                },
+                &IconPaths::new(None),
+            )
+            .expect("button");
+            let icon = button.icon.expect("icon");
+            assert_eq!(icon.label.as_deref(), expected);
+        }
+    }
+
+    #[test]
+    fn resolves_long_press_action_from_desktop_entry() {
+        let dir = tempdir().unwrap();
+        let desktop_path = dir.path().join("browser.desktop");
+        fs::write(
+            &desktop_path,
+            "[Desktop Entry]
+Name=Browser
+Exec=/usr/bin/true
+Type=Application
+Actions=private;
+
+[Desktop Action private]
+Name=Private Window
+Exec=/usr/bin/true --private %u
+",
+        )
+        .unwrap();
+
+        let button = LauncherButton::from_config(
+            &LauncherButtonConfig {
+                button_index: 1,
+                desktop_file: desktop_path.clone(),
+                action: None,
+                long_press_action: Some("private".into()),
+                long_press_desktop_file: None,
+                double_press_desktop_file: None,
+                show_label: false,
+                icon: None,
+                background: None,
+                single_instance: false,
+                device: None,
+            },
+            &IconPaths::new(None),
+        )
+        .expect("button");
+        let Some(AltLaunch::Action(action)) = button.long_press else {
+            panic!("long-press action");
+        };
+        assert_eq!(action.id, "private");
+        assert_eq!(action.exec.program, "/usr/bin/true");
+        assert_eq!(action.exec.args, vec!["--private"]);
+
+        let button = LauncherButton::from_config(
+            &LauncherButtonConfig {
+                button_index: 1,
+                desktop_file: desktop_path,
+                action: None,
+                long_press_action: Some("missing".into()),
+                long_press_desktop_file: None,
+                double_press_desktop_file: None,
+                show_label: false,
+                icon: None,
+                background: None,
+                single_instance: false,
+                device: None,
+            },
+            &IconPaths::new(None),
+        )
+        .expect("button");
+        assert!(button.long_press.is_none());
+    }
+
+    #[test]
+    fn missing_try_exec_disables_button_and_actions_replace_exec() {
+        let dir = tempdir().unwrap();
+        let icon_path = dir.path().join("icon.svg");
+        fs::write(
+            &icon_path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"></svg>"#,
+        )
+        .unwrap();
+        let marker = dir.path().join("private");
+        let write_entry = |name: &str, try_exec: &str| {
+            let path = dir.path().join(name);
+            fs::write(
+                &path,
+                format!(
+                    "[Desktop Entry]
+Name=Browser
+TryExec={try_exec}
+Exec=/usr/bin/true %u
+Icon={}
+Type=Application
+Actions=new-private-window;
+
+[Desktop Action new-private-window]
+Name=New Private Window
+Exec=touch {} %u
+",
+                    icon_path.display(),
+                    marker.display()
+                ),
+            )
+            .unwrap();
+            path
+        };
+        let config = |desktop_file: PathBuf, action: &str| LauncherButtonConfig {
+            button_index: 3,
+            desktop_file,
+            action: Some(action.into()),
+            long_press_action: None,
+            long_press_desktop_file: None,
+            double_press_desktop_file: None,
+            show_label: true,
+            icon: None,
+            background: None,
+            single_instance: false,
+            device: None,
+        };
+
+        let missing = write_entry("missing.desktop", "/nonexistent/browser");
+        let button = LauncherButton::from_config(
+            &config(missing.clone(), "new-private-window"),
+            &IconPaths::new(None),
+        )
+        .expect("button");
+        assert_eq!(
+            button.missing_try_exec.as_deref(),
+            Some("/nonexistent/browser")
+        );
+        let icon = button.icon.clone().expect("icon");
+        assert!(icon.id.ends_with(":unavailable"));
+        assert_eq!(icon.tint, Some(UNAVAILABLE_TINT));
+        assert_eq!(icon.label.as_deref(), Some("New Private Window"));
+        let action = button.action.as_ref().expect("action");
+        assert_eq!(action.exec.program, "touch");
+        assert_eq!(
+            action.exec.args,
+            vec![marker.to_string_lossy().into_owned()]
+        );
+        button.activate(&DesktopWindowActivator).unwrap();
+
+        assert!(
+            LauncherButton::from_config(&config(missing, "missing"), &IconPaths::new(None))
+                .is_err()
+        );
+
+        let installed = write_entry("installed.desktop", "sh");
+        let mut controller = LauncherController::new(
+            &[config(installed, "new-private-window")],
+            None,
+            &RecordingHardware::new(),
+        )
+        .unwrap()
+        .expect("launcher controller");
+        assert!(controller.buttons[&3].missing_try_exec.is_none());
+        assert!(controller.on_button_pressed(3).unwrap());
+        let ran = (0..100).any(|_| {
+            thread::sleep(std::time::Duration::from_millis(20));
+            marker.exists()
+        });
+        assert!(ran);
+    }
+
+    struct FakeWindows {
+        open: bool,
+        queried: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl WindowActivator for FakeWindows {
+        fn activate(&self, class: &str) -> Result<bool> {
+            self.queried.lock().unwrap().push(class.to_string());
+            Ok(self.open)
+        }
+    }
+
+    #[test]
+    fn single_instance_focuses_running_window() {
+        let dir = tempdir().unwrap();
+        let desktop_path = dir.path().join("org.example.Notes.desktop");
+        fs::write(
+            &desktop_path,
+            "[Desktop Entry]
+Name=Notes
+Exec=/usr/bin/true
+Type=Application
+",
+        )
+        .unwrap();
+        let config = LauncherButtonConfig {
+            button_index: 0,
+            desktop_file: desktop_path,
+            action: None,
+            long_press_action: None,
+            long_press_desktop_file: None,
+            double_press_desktop_file: None,
+            show_label: false,
+            icon: None,
+            background: None,
+            single_instance: true,
+            device: None,
+        };
+
+        let queried = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut controller = LauncherController::new(&[config], None, &RecordingHardware::new())
+            .unwrap()
+            .expect("launcher controller")
+            .with_window_activator(FakeWindows {
+                open: true,
+                queried: queried.clone(),
+            });
+
+        assert!(controller.on_button_pressed(0).unwrap());
+        assert_eq!(*queried.lock().unwrap(), vec!["org.example.Notes"]);
+
+        // Held past the long-press threshold, it still launches only once.
+        assert!(
+            controller
+                .on_button_released(0, DEFAULT_LONG_PRESS)
+                .unwrap()
+        );
+        assert_eq!(queried.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn double_press_entry_holds_back_the_single_press() {
+        let dir = tempdir().unwrap();
+        let main_path = dir.path().join("org.example.Notes.desktop");
+        fs::write(
+            &main_path,
+            "[Desktop Entry]
+Name=Notes
+Exec=/usr/bin/true
+Type=Application
+",
+        )
+        .unwrap();
+        let marker = dir.path().join("double");
+        let double_path = dir.path().join("other.desktop");
+        fs::write(
+            &double_path,
+            format!(
+                "[Desktop Entry]
+Name=Other
+Exec=touch {}
+Type=Application
+",
+                marker.display()
+            ),
+        )
+        .unwrap();
+        let config = LauncherButtonConfig {
+            button_index: 2,
+            desktop_file: main_path,
+            action: None,
+            long_press_action: None,
+            long_press_desktop_file: None,
+            double_press_desktop_file: Some(double_path),
+            show_label: false,
+            icon: None,
+            background: None,
+            single_instance: true,
+            device: None,
+        };
+
+        let queried = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut controller = LauncherController::new(&[config], None, &RecordingHardware::new())
+            .unwrap()
+            .expect("launcher controller")
+            .with_window_activator(FakeWindows {
+                open: true,
+                queried: queried.clone(),
+            });
+        let window = DEFAULT_DOUBLE_PRESS_WINDOW;
+        let start = Instant::now();
+
+        assert!(controller.on_button_pressed(2).unwrap());
+        assert!(controller.released_at(2, start).unwrap());
+        assert!(queried.lock().unwrap().is_empty());
+        assert_eq!(controller.deadline(), Some(start + window));
+        controller.flush_expired(start + window).unwrap();
+        assert_eq!(queried.lock().unwrap().len(), 1);
+        assert_eq!(controller.deadline(), None);
+
+        let later = start + window * 4;
+        controller.released_at(2, later).unwrap();
+        controller.released_at(2, later + window / 2).unwrap();
+        assert_eq!(controller.deadline(), None);
+        let ran = (0..100).any(|_| {
+            thread::sleep(std::time::Duration::from_millis(20));
+            marker.exists()
+        });
+        assert!(ran);
+        assert_eq!(queried.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn configured_icon_and_background_override_desktop_entry() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("custom.svg"),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"></svg>"#,
+        )
+        .unwrap();
+        let desktop_path = dir.path().join("plain.desktop");
+        fs::write(
+            &desktop_path,
+            "[Desktop Entry]
+Name=Plain
+Exec=/usr/bin/true
+Type=Application
+",
+        )
+        .unwrap();
+        let icon_paths = IconPaths::new(Some(&dir.path().join("config.json")));
+        let config = |background: &str| LauncherButtonConfig {
+            button_index: 4,
+            desktop_file: desktop_path.clone(),
+            action: None,
+            long_press_action: None,
+            long_press_desktop_file: None,
+            double_press_desktop_file: None,
+            show_label: false,
+            icon: Some(IconConfig::File("custom.svg".into())),
+            background: Some(background.into()),
+            single_instance: false,
+            device: None,
+        };
+
+        let button = LauncherButton::from_config(&config("#1E1e2e"), &icon_paths).expect("button");
+        let icon = button.icon.expect("configured icon");
+        assert_eq!(icon.id, "launcher:plain.desktop:custom.svg");
+        assert_eq!(icon.background, Some([0x1e, 0x1e, 0x2e]));
+
+        let button = LauncherButton::from_config(&config("#12345"), &icon_paths).expect("button");
+        assert_eq!(button.icon.expect("configured icon").background, None);
+
+        assert_eq!(parse_hex_color("00ff80").unwrap(), [0, 255, 128]);
+        assert!(parse_hex_color("#gg0000").is_err());
+    }
 }
@@ -0,0 +1,71 @@
+/// Scrolls text through a fixed-width window of characters, two characters per
+/// advance.
+#[derive(Debug, Clone)]
+pub(super) struct Marquee {
+    chars: Vec<char>,
+    window: usize,
+    offset: usize,
+}
+
+impl Marquee {
+    const GAP: usize = 6;
+    const STEP: usize = 2;
+
+    pub(super) fn new(text: String, window: usize) -> Option<Self> {
+        if window == 0 {
+            return None;
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            return None;
+        }
+
+        let mut buffer: Vec<char> = chars.clone();
+        if buffer.len() <= window {
+            buffer.extend(std::iter::repeat(' ').take(Self::GAP));
+            buffer.extend(chars.iter().copied());
+        } else {
+            buffer.extend(std::iter::repeat(' ').take(Self::GAP));
+        }
+
+        Some(Self {
+            chars: buffer,
+            window,
+            offset: 0,
+        })
+    }
+
+    pub(super) fn render(&mut self, advance: bool) -> String {
+        if self.chars.is_empty() || self.window == 0 {
+            return String::new();
+        }
+
+        if advance {
+            self.offset = (self.offset + Self::STEP) % self.chars.len();
+        }
+
+        let mut value = String::with_capacity(self.window);
+        let len = self.chars.len();
+        for step in 0..self.window {
+            let idx = (self.offset + step) % len;
+            value.push(self.chars[idx]);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_text_wraps_around_after_a_gap() {
+        let mut marquee = Marquee::new("abcdefgh".into(), 4).expect("marquee");
+        assert_eq!(marquee.render(false), "abcd");
+        assert_eq!(marquee.render(true), "cdef");
+        assert_eq!(marquee.render(true), "efgh");
+        assert_eq!(marquee.render(true), "gh  ");
+        assert!(Marquee::new(String::new(), 4).is_none());
+    }
+}
@@ -0,0 +1,249 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::audio::{AudioSourceBackend, PulseAudioBackend};
+
+use super::audio_toggle::{
+    ACTIVE_TINT, DEGRADED_TINT, IconPaths, MaterialIcon, UNAVAILABLE_TINT, load_material_icon,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MicMuteConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MicState {
+    Live,
+    Muted,
+    Unavailable,
+}
+
+pub struct MicMuteController<B, H>
+where
+    B: AudioSourceBackend,
+    H: DisplayPipeline,
+{
+    backend: B,
+    hardware: H,
+    button_index: u8,
+    icons: MicIcons,
+    state: Option<MicState>,
+}
+
+struct MicIcons {
+    live: ButtonImage,
+    muted: ButtonImage,
+    unavailable: ButtonImage,
+}
+
+impl<B, H> MicMuteController<B, H>
+where
+    B: AudioSourceBackend,
+    H: DisplayPipeline,
+{
+    fn new(config: MicMuteConfig, backend: B, hardware: H, icon_paths: &IconPaths) -> Result<Self> {
+        let button_index = config.button_index;
+        let mic = load_material_icon(MaterialIcon::Mic, icon_paths)?;
+        let mic_off = load_material_icon(MaterialIcon::MicOff, icon_paths)?;
+        let icons = MicIcons {
+            live: tinted(&mic, button_index, "live", ACTIVE_TINT),
+            muted: tinted(&mic_off, button_index, "muted", DEGRADED_TINT),
+            unavailable: tinted(&mic_off, button_index, "unavailable", UNAVAILABLE_TINT),
+        };
+
+        let mut controller = Self {
+            backend,
+            hardware,
+            button_index,
+            icons,
+            state: None,
+        };
+        controller.refresh_state()?;
+        Ok(controller)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        std::iter::once(self.button_index)
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        if button_index != self.button_index {
+            return Ok(false);
+        }
+
+        if !self.backend.is_available() {
+            self.apply_state(MicState::Unavailable)?;
+            return Ok(true);
+        }
+
+        match self.backend.toggle_source_mute() {
+            Ok(muted) => {
+                info!(muted, "toggled microphone mute");
+                self.apply_state(if muted {
+                    MicState::Muted
+                } else {
+                    MicState::Live
+                })?;
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to toggle microphone mute");
+                self.refresh_state()?;
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(button_index == self.button_index)
+    }
+
+    pub fn on_tick(&mut self) -> Result<()> {
+        self.refresh_state()
+    }
+
+    fn refresh_state(&mut self) -> Result<()> {
+        let state = if !self.backend.is_available() {
+            MicState::Unavailable
+        } else {
+            match self.backend.is_source_muted() {
+                Ok(true) => MicState::Muted,
+                Ok(false) => MicState::Live,
+                Err(err) => {
+                    warn!(error = %err, "failed to query microphone mute state");
+                    MicState::Unavailable
+                }
+            }
+        };
+        self.apply_state(state)
+    }
+
+    fn apply_state(&mut self, state: MicState) -> Result<()> {
+        if self.state == Some(state) {
+            return Ok(());
+        }
+        self.state = Some(state);
+
+        let icon = match state {
+            MicState::Live => &self.icons.live,
+            MicState::Muted => &self.icons.muted,
+            MicState::Unavailable => &self.icons.unavailable,
+        };
+        self.hardware
+            .update_button_icon(self.button_index, Some(icon.clone()))
+    }
+}
+
+impl<H> MicMuteController<PulseAudioBackend, H>
+where
+    H: DisplayPipeline,
+{
+    pub fn with_default_backend(
+        config: MicMuteConfig,
+        config_path: Option<&Path>,
+        hardware: H,
+    ) -> Result<Self> {
+        let icon_paths = IconPaths::new(config_path);
+        MicMuteController::new(config, PulseAudioBackend::default(), hardware, &icon_paths)
+    }
+}
+
+fn tinted(base: &ButtonImage, button_index: u8, suffix: &str, tint: [u8; 3]) -> ButtonImage {
+    ButtonImage {
+        id: format!("mic-{button_index}-{suffix}"),
+        image: base.image.clone(),
+        tint: Some(tint),
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use crate::system::audio::tests::MockAudioSourceBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, _index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons.lock().unwrap().push(icon.map(|icon| icon.id));
+            Ok(())
+        }
+    }
+
+    fn controller(
+        backend: MockAudioSourceBackend,
+        hardware: RecordingHardware,
+    ) -> MicMuteController<MockAudioSourceBackend, RecordingHardware> {
+        MicMuteController::new(
+            MicMuteConfig { button_index: 3 },
+            backend,
+            hardware,
+            &IconPaths::new(None),
+        )
+        .expect("controller")
+    }
+
+    fn last_icon(hardware: &RecordingHardware) -> Option<String> {
+        hardware.icons.lock().unwrap().last().unwrap().clone()
+    }
+
+    #[test]
+    fn press_toggles_mute_and_swaps_icon() {
+        let backend = MockAudioSourceBackend {
+            available: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("mic-3-live"));
+
+        assert!(!controller.on_button_pressed(1).unwrap());
+        assert!(controller.on_button_pressed(3).unwrap());
+        assert!(*backend.muted.lock().unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("mic-3-muted"));
+    }
+
+    #[test]
+    fn tick_reflects_external_mute_once() {
+        let backend = MockAudioSourceBackend {
+            available: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+
+        *backend.muted.lock().unwrap() = true;
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+
+        let icons = hardware.icons.lock().unwrap();
+        assert_eq!(icons.len(), 2);
+        assert_eq!(icons[1].as_deref(), Some("mic-3-muted"));
+    }
+
+    #[test]
+    fn unavailable_backend_shows_disabled_icon() {
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(MockAudioSourceBackend::default(), hardware.clone());
+        assert!(controller.on_button_pressed(3).unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("mic-3-unavailable"));
+    }
+}
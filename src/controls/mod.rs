@@ -1,23 +1,81 @@
+mod acceleration;
+mod app_volume;
 mod audio_toggle;
+mod bluetooth;
 mod brightness;
+mod capture;
+mod clock;
+mod commands;
+mod config_check;
+mod dnd;
+mod gesture;
 mod launcher;
+mod marquee;
+mod mic_mute;
 mod now_playing;
+mod pages;
+mod snippet;
+mod system_stats;
 mod timer;
+mod transient;
 mod volume;
+mod vpn;
+mod walkthrough;
+mod weather;
 
-pub use audio_toggle::{AudioToggleConfig, AudioToggleController, AudioToggleSettings};
-pub use brightness::BrightnessController;
+pub use app_volume::{AppVolumeConfig, AppVolumeController};
+pub use audio_toggle::{AudioToggleConfig, AudioToggleController, AudioToggleSettings, IconConfig};
+pub use bluetooth::{BluetoothConfig, BluetoothController};
+pub use brightness::{
+    BrightnessBackendKind, BrightnessConfig, BrightnessController, BrightnessRamp, MonitorTarget,
+    NightSchedule,
+};
+pub use capture::{CaptureConfig, CaptureController};
+pub use clock::{ClockConfig, ClockController};
+pub use commands::{CommandButtonConfig, CommandController, CommandLine};
+pub use config_check::check_config;
+pub use dnd::{DndConfig, DndController};
+pub use gesture::{
+    DoublePressDetector, HoldDetector, PressGesture, StripGesture, classify_swipe, coalesce_turns,
+};
 pub use launcher::LauncherController;
+pub use mic_mute::{MicMuteConfig, MicMuteController};
 pub use now_playing::NowPlayingController;
-pub use timer::TimerController;
+pub use pages::PageSwitcher;
+pub use snippet::{SnippetConfig, SnippetController};
+pub use system_stats::{SystemStatsConfig, SystemStatsController};
+pub use timer::{DesktopTimerAlert, PomodoroConfig, TimerController, TimerDisplayState, TimerMode};
 pub use volume::VolumeController;
+pub use vpn::{VpnConfig, VpnController};
+pub use walkthrough::WalkthroughController;
+pub use weather::{WeatherConfig, WeatherController};
+
+use std::time::Duration;
 
 use anyhow::Result;
 
 pub trait EncoderController: Send {
     fn on_turn(&mut self, delta: i32) -> Result<()>;
     fn on_press(&mut self) -> Result<()>;
-    fn on_release(&mut self) -> Result<()>;
+    /// Whether a hold past the long-press threshold does something of its
+    /// own; the press is then only acted on once released.
+    fn has_long_press(&self) -> bool {
+        false
+    }
+    fn on_long_press(&mut self) -> Result<()> {
+        self.on_press()
+    }
+    fn on_release(&mut self, _held: Duration) -> Result<()> {
+        Ok(())
+    }
+    /// A tap on this encoder's segment of the touch strip.
+    fn on_touch_tap(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// A swipe starting on this encoder's segment; `forward` when leftwards.
+    fn on_touch_swipe(&mut self, _forward: bool) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait Tickable: Send {
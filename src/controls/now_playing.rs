@@ -1,36 +1,75 @@
+use std::time::{Duration, Instant};
+
 use anyhow::{Context, Result};
 
 use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+use crate::system::album_art::AlbumArtCache;
 use crate::system::now_playing::{NowPlayingBackend, PlaybackState, PlaybackStatus};
-
-use super::Tickable;
-
-pub struct NowPlayingController<B, D>
+use crate::system::worker::BackendWorker;
+
+use super::acceleration::{Clock, SystemClock};
+use super::marquee::Marquee;
+use super::{EncoderController, Tickable};
+
+const SEEK_STEP_SECS: i64 = 5;
+// Roughly what fits in the status row without and beside cover art; status
+// lines mix in narrow spaces and separators, and the renderer ellipsizes
+// anything that still overflows.
+const MAX_STATUS_CHARS: usize = 15;
+const MAX_STATUS_CHARS_WITH_ART: usize = 8;
+// Reported positions further than this from the extrapolated one mean a seek
+// or a stall, so the bar jumps to the reported position.
+const POSITION_RESYNC: Duration = Duration::from_secs(2);
+
+pub struct NowPlayingController<B, D, C = SystemClock>
 where
     B: NowPlayingBackend,
     D: DisplayPipeline,
+    C: Clock,
 {
-    backend: B,
+    player: BackendWorker<B, Result<PlaybackState>>,
     display: D,
     encoder: EncoderId,
     last_state: Option<PlaybackState>,
     marquee: Option<Marquee>,
+    status_marquee: Option<Marquee>,
+    art: AlbumArtCache,
+    scrubbed_while_held: bool,
+    clock: C,
+    /// Playback position at an instant, extrapolated while playing.
+    position_anchor: Option<(Duration, Instant)>,
 }
 
 impl<B, D> NowPlayingController<B, D>
 where
     B: NowPlayingBackend,
     D: DisplayPipeline,
+{
+    pub fn new(backend: B, display: D, encoder: EncoderId) -> Result<Self> {
+        Self::with_clock(backend, display, encoder, SystemClock)
+    }
+}
+
+impl<B, D, C> NowPlayingController<B, D, C>
+where
+    B: NowPlayingBackend,
+    D: DisplayPipeline,
+    C: Clock,
 {
     const MAX_TITLE_CHARS: usize = 22;
 
-    pub fn new(backend: B, display: D, encoder: EncoderId) -> Result<Self> {
+    pub fn with_clock(backend: B, display: D, encoder: EncoderId, clock: C) -> Result<Self> {
         let mut controller = Self {
-            backend,
+            player: BackendWorker::inline(backend),
             display,
             encoder,
             last_state: None,
             marquee: None,
+            status_marquee: None,
+            art: AlbumArtCache::default(),
+            scrubbed_while_held: false,
+            clock,
+            position_anchor: None,
         };
         controller
             .refresh_display(false)
@@ -38,30 +77,89 @@ where
         Ok(controller)
     }
 
+    /// Runs playerctl on a worker thread from now on; states then show on a
+    /// later tick.
+    pub fn in_background(mut self) -> Result<Self> {
+        self.player = self.player.into_threaded("now-playing-playerctl")?;
+        Ok(self)
+    }
+
     fn refresh_display(&mut self, advance_scroll: bool) -> Result<()> {
-        let state = self.backend.now_playing()?;
-        let state_changed = self.last_state.as_ref() != Some(&state);
+        self.player
+            .submit(|backend: &mut B| backend.now_playing())?;
+        let states = self.player.finished();
+        self.show_states(states, advance_scroll)
+    }
+
+    // The state is read again after the action, so the display follows it.
+    fn run_action(&mut self, action: impl FnOnce(&B) -> Result<()> + Send + 'static) -> Result<()> {
+        self.player.submit(move |backend: &mut B| {
+            action(backend)?;
+            backend.now_playing()
+        })?;
+        let states = self.player.finished();
+        self.show_states(states, false)
+    }
+
+    // Every state is shown; the last failure, if any, is returned.
+    fn show_states(
+        &mut self,
+        states: Vec<Result<PlaybackState>>,
+        advance_scroll: bool,
+    ) -> Result<()> {
+        let mut outcome = Ok(());
+        for state in states {
+            match state {
+                Ok(state) => self.show_state(state, advance_scroll)?,
+                Err(err) => outcome = Err(err),
+            }
+        }
+        outcome
+    }
+
+    fn show_state(&mut self, state: PlaybackState, advance_scroll: bool) -> Result<()> {
+        let state_changed = !self
+            .last_state
+            .as_ref()
+            .is_some_and(|last| last.same_track_as(&state));
         if state_changed {
-            self.marquee = Marquee::from_state(&state, Self::MAX_TITLE_CHARS);
+            self.marquee = title_marquee(&state, Self::MAX_TITLE_CHARS);
+            self.status_marquee = status_marquee(&state);
             self.last_state = Some(state.clone());
         }
 
-        if !state_changed && self.marquee.is_none() {
+        if !state_changed
+            && self.marquee.is_none()
+            && self.status_marquee.is_none()
+            && playback_fraction(&state, Duration::ZERO).is_none()
+        {
             return Ok(());
         }
 
         self.push_display(&state, advance_scroll && !state_changed)
     }
 
-    pub fn on_turn(&mut self, delta: i32) -> Result<()> {
-        if delta > 0 {
-            self.backend.next()?;
-        } else if delta < 0 {
-            self.backend.previous()?;
-        }
+    /// False once playerctl turned out to be missing.
+    pub fn is_available(&self) -> bool {
+        self.last_state
+            .as_ref()
+            .is_none_or(|state| state.status != PlaybackStatus::Unavailable)
+    }
+
+    pub fn sync(&mut self) -> Result<()> {
+        self.last_state = None;
+        self.player.cancel_pending();
         self.refresh_display(false)
     }
 
+    /// Seeks within the current track; used for turns while the encoder is
+    /// held, which also cancels the play/pause on release.
+    pub fn scrub(&mut self, delta: i32) -> Result<()> {
+        self.scrubbed_while_held = true;
+        let offset = delta as i64 * SEEK_STEP_SECS;
+        self.run_action(move |backend| backend.seek(offset))
+    }
+
     fn push_display(&mut self, state: &PlaybackState, advance_marquee: bool) -> Result<()> {
         let base_value = match state.status {
             PlaybackStatus::Playing | PlaybackStatus::Paused => state
@@ -81,109 +179,160 @@ where
         };
 
         let mut display = EncoderDisplay::new("spotify", value);
+        display.status = match self.status_marquee.as_mut() {
+            Some(marquee) => Some(marquee.render(advance_marquee)),
+            None => status_line(state),
+        };
+        if matches!(
+            state.status,
+            PlaybackStatus::Playing | PlaybackStatus::Paused
+        ) {
+            display.icon = state.art_url.as_deref().and_then(|url| self.art.get(url));
+        }
+        let position = self.extrapolated_position(state);
+        display.progress = position.and_then(|position| playback_fraction(state, position));
+        self.display.update_encoder(self.encoder, display)
+    }
 
-        let mut status_line = match state.status {
-            PlaybackStatus::Playing => None,
-            PlaybackStatus::Paused => Some("paused".to_string()),
-            PlaybackStatus::Stopped => Some("stopped".to_string()),
-            PlaybackStatus::Unavailable => None,
+    // Polls only land once a second and report whole seconds, so the bar
+    // follows a local clock anchored at the last reported position instead.
+    fn extrapolated_position(&mut self, state: &PlaybackState) -> Option<Duration> {
+        let reported = state
+            .position_secs
+            .filter(|_| state.status == PlaybackStatus::Playing)
+            .map(Duration::from_secs);
+        let Some(reported) = reported else {
+            self.position_anchor = None;
+            return None;
         };
 
-        if let Some(artist) = state.artist.as_deref().filter(|artist| !artist.is_empty()) {
-            status_line = match status_line.take() {
-                Some(prefix) => Some(format!("{prefix} · {artist}")),
-                None => Some(artist.to_string()),
-            };
+        let now = self.clock.now();
+        let extrapolated = self
+            .position_anchor
+            .map(|(position, at)| position + now.saturating_duration_since(at))
+            .filter(|position| position.abs_diff(reported) <= POSITION_RESYNC);
+        match extrapolated {
+            Some(position) => Some(position),
+            None => {
+                self.position_anchor = Some((reported, now));
+                Some(reported)
+            }
         }
+    }
+}
 
-        display.status = status_line;
-        self.display.update_encoder(self.encoder, display)
+/// How far through the track `position` is, for tracks with a known length
+/// that are playing.
+fn playback_fraction(state: &PlaybackState, position: Duration) -> Option<f32> {
+    if state.status != PlaybackStatus::Playing || state.position_secs.is_none() {
+        return None;
     }
+    let length = state.length_secs.filter(|length| *length > 0)?;
+    Some((position.as_secs_f32() / length as f32).clamp(0.0, 1.0))
 }
 
-impl<B, D> Tickable for NowPlayingController<B, D>
+impl<B, D, C> EncoderController for NowPlayingController<B, D, C>
 where
     B: NowPlayingBackend,
     D: DisplayPipeline,
+    C: Clock,
 {
-    fn on_tick(&mut self) -> Result<()> {
-        self.refresh_display(true)
+    fn on_turn(&mut self, delta: i32) -> Result<()> {
+        match delta.signum() {
+            1 => self.run_action(|backend| backend.next()),
+            -1 => self.run_action(|backend| backend.previous()),
+            _ => self.refresh_display(false),
+        }
     }
-}
 
-#[derive(Debug, Clone)]
-struct Marquee {
-    chars: Vec<char>,
-    window: usize,
-    offset: usize,
-}
+    fn on_press(&mut self) -> Result<()> {
+        self.scrubbed_while_held = false;
+        Ok(()) // play/pause waits for release so press-and-turn can scrub
+    }
 
-impl Marquee {
-    const GAP: usize = 6;
-    const STEP: usize = 2;
-
-    fn from_state(state: &PlaybackState, window: usize) -> Option<Self> {
-        match state.status {
-            PlaybackStatus::Playing | PlaybackStatus::Paused => {
-                let title = state
-                    .title
-                    .as_deref()
-                    .filter(|title| !title.is_empty())
-                    .unwrap_or("No title")
-                    .to_string();
-                Self::new(title, window)
-            }
-            _ => None,
+    fn on_release(&mut self, _held: Duration) -> Result<()> {
+        if std::mem::take(&mut self.scrubbed_while_held) {
+            return Ok(());
         }
+        self.run_action(|backend| backend.play_pause())
     }
 
-    fn new(text: String, window: usize) -> Option<Self> {
-        if window == 0 {
-            return None;
-        }
+    fn on_touch_tap(&mut self) -> Result<()> {
+        self.run_action(|backend| backend.play_pause())
+    }
 
-        let chars: Vec<char> = text.chars().collect();
-        if chars.is_empty() {
-            return None;
-        }
+    fn on_touch_swipe(&mut self, forward: bool) -> Result<()> {
+        self.on_turn(if forward { 1 } else { -1 })
+    }
+}
 
-        let mut buffer: Vec<char> = chars.clone();
-        if buffer.len() <= window {
-            buffer.extend(std::iter::repeat(' ').take(Self::GAP));
-            buffer.extend(chars.iter().copied());
-        } else {
-            buffer.extend(std::iter::repeat(' ').take(Self::GAP));
+impl<B, D, C> Tickable for NowPlayingController<B, D, C>
+where
+    B: NowPlayingBackend,
+    D: DisplayPipeline,
+    C: Clock,
+{
+    // Finished polls are collected first so the worker is free for the next
+    // one; a poll still running is not queued behind.
+    fn on_tick(&mut self) -> Result<()> {
+        let mut states = self.player.finished();
+        if !self.player.is_busy() {
+            self.player
+                .submit(|backend: &mut B| backend.now_playing())?;
+            states.extend(self.player.finished());
         }
-
-        Some(Self {
-            chars: buffer,
-            window,
-            offset: 0,
-        })
+        self.show_states(states, true)
     }
+}
 
-    fn render(&mut self, advance: bool) -> String {
-        if self.chars.is_empty() || self.window == 0 {
-            return String::new();
+fn title_marquee(state: &PlaybackState, window: usize) -> Option<Marquee> {
+    match state.status {
+        PlaybackStatus::Playing | PlaybackStatus::Paused => {
+            let title = state
+                .title
+                .as_deref()
+                .filter(|title| !title.is_empty())
+                .unwrap_or("No title")
+                .to_string();
+            Marquee::new(title, window)
         }
+        _ => None,
+    }
+}
 
-        if advance {
-            self.offset = (self.offset + Self::STEP) % self.chars.len();
-        }
+// Unlike the title, the status line only scrolls when it would not fit.
+fn status_marquee(state: &PlaybackState) -> Option<Marquee> {
+    let line = status_line(state)?;
+    let window = if state.art_url.is_some() {
+        MAX_STATUS_CHARS_WITH_ART
+    } else {
+        MAX_STATUS_CHARS
+    };
+    if line.chars().count() <= window {
+        return None;
+    }
+    Marquee::new(line, window)
+}
 
-        let mut value = String::with_capacity(self.window);
-        let len = self.chars.len();
-        for step in 0..self.window {
-            let idx = (self.offset + step) % len;
-            value.push(self.chars[idx]);
-        }
-        value
+fn status_line(state: &PlaybackState) -> Option<String> {
+    let prefix = match state.status {
+        PlaybackStatus::Playing => None,
+        PlaybackStatus::Paused => Some("paused"),
+        PlaybackStatus::Stopped => Some("stopped"),
+        PlaybackStatus::Unavailable => None,
+    };
+    let artist = state.artist.as_deref().filter(|artist| !artist.is_empty());
+    match (prefix, artist) {
+        (Some(prefix), Some(artist)) => Some(format!("{prefix} · {artist}")),
+        (Some(prefix), None) => Some(prefix.to_string()),
+        (None, artist) => artist.map(str::to_string),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::controls::acceleration::tests::FakeClock;
     use crate::hardware::DisplayPipeline;
     use std::sync::{Arc, Mutex};
 
@@ -207,14 +356,20 @@ mod tests {
         }
     }
 
+    #[derive(Clone)]
     struct MockBackend {
-        states: Vec<PlaybackState>,
+        states: Arc<Mutex<Vec<PlaybackState>>>,
         index: usize,
+        seeks: Arc<Mutex<Vec<i64>>>,
     }
 
     impl MockBackend {
         fn new(states: Vec<PlaybackState>) -> Self {
-            Self { states, index: 0 }
+            Self {
+                states: Arc::new(Mutex::new(states)),
+                index: 0,
+                seeks: Arc::default(),
+            }
         }
     }
 
@@ -222,6 +377,8 @@ mod tests {
         fn now_playing(&self) -> Result<PlaybackState> {
             Ok(self
                 .states
+                .lock()
+                .unwrap()
                 .get(self.index)
                 .cloned()
                 .unwrap_or_else(PlaybackState::stopped))
@@ -234,6 +391,22 @@ mod tests {
         fn previous(&self) -> Result<()> {
             Ok(())
         }
+
+        fn play_pause(&self) -> Result<()> {
+            let mut states = self.states.lock().unwrap();
+            if let Some(state) = states.get_mut(self.index) {
+                state.status = match state.status {
+                    PlaybackStatus::Playing => PlaybackStatus::Paused,
+                    _ => PlaybackStatus::Playing,
+                };
+            }
+            Ok(())
+        }
+
+        fn seek(&self, offset_secs: i64) -> Result<()> {
+            self.seeks.lock().unwrap().push(offset_secs);
+            Ok(())
+        }
     }
 
     #[test]
@@ -242,6 +415,9 @@ mod tests {
             status: PlaybackStatus::Playing,
             title: Some("Track A".into()),
             artist: Some("Artist A".into()),
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
         }]);
 
         let display = RecordingDisplay::new();
@@ -275,6 +451,9 @@ mod tests {
             status: PlaybackStatus::Playing,
             title: Some("An Incredibly Long Song Title That Keeps Going".into()),
             artist: None,
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
         }]);
 
         let display = RecordingDisplay::new();
@@ -308,6 +487,9 @@ mod tests {
             status: PlaybackStatus::Playing,
             title: Some("Short Title".into()),
             artist: None,
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
         }]);
 
         let display = RecordingDisplay::new();
@@ -325,6 +507,169 @@ mod tests {
         assert_ne!(first, second);
         assert_ne!(second, third);
     }
+
+    #[test]
+    fn long_artist_lines_scroll_with_the_title() {
+        let backend = MockBackend::new(vec![PlaybackState {
+            status: PlaybackStatus::Playing,
+            title: Some("Track".into()),
+            artist: Some("Artist One, Artist Two".into()),
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
+        }]);
+        let display = RecordingDisplay::new();
+        let mut controller =
+            NowPlayingController::new(backend, display.clone(), EncoderId::Four).expect("init");
+        controller.on_tick().unwrap();
+
+        let events = display.inner.lock().unwrap();
+        let statuses: Vec<&str> = events
+            .iter()
+            .map(|(_, event)| event.status.as_deref().unwrap())
+            .collect();
+        assert_eq!(statuses, ["Artist One, Art", "tist One, Artis"]);
+    }
+
+    #[test]
+    fn press_toggles_playback_and_refreshes_status() {
+        let backend = MockBackend::new(vec![PlaybackState {
+            status: PlaybackStatus::Playing,
+            title: Some("Track".into()),
+            artist: Some("Artist".into()),
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
+        }]);
+        let display = RecordingDisplay::new();
+        let mut controller =
+            NowPlayingController::new(backend.clone(), display.clone(), EncoderId::Four)
+                .expect("init");
+
+        controller.on_press().unwrap();
+        controller.on_release(Duration::from_millis(80)).unwrap();
+
+        let events = display.inner.lock().unwrap();
+        let (_, event) = events.last().unwrap();
+        assert_eq!(event.status.as_deref(), Some("paused · Artist"));
+    }
+
+    #[test]
+    fn turning_while_held_scrubs_instead_of_pausing() {
+        let backend = MockBackend::new(vec![PlaybackState {
+            status: PlaybackStatus::Playing,
+            title: Some("Track".into()),
+            artist: None,
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
+        }]);
+        let display = RecordingDisplay::new();
+        let mut controller =
+            NowPlayingController::new(backend.clone(), display, EncoderId::Four).expect("init");
+
+        controller.on_press().unwrap();
+        controller.scrub(2).unwrap();
+        controller.scrub(-1).unwrap();
+        controller.on_release(Duration::from_millis(900)).unwrap();
+
+        assert_eq!(*backend.seeks.lock().unwrap(), vec![10, -5]);
+        assert_eq!(
+            backend.now_playing().unwrap().status,
+            PlaybackStatus::Playing
+        );
+    }
+
+    #[test]
+    fn progress_bar_extrapolates_between_polls_and_only_shows_while_playing() {
+        let backend = MockBackend::new(vec![PlaybackState {
+            status: PlaybackStatus::Playing,
+            title: Some("Track".into()),
+            artist: None,
+            art_url: None,
+            position_secs: Some(60),
+            length_secs: Some(240),
+        }]);
+        let display = RecordingDisplay::new();
+        let clock = FakeClock::default();
+        let mut controller = NowPlayingController::with_clock(
+            backend.clone(),
+            display.clone(),
+            EncoderId::Four,
+            clock.clone(),
+        )
+        .expect("init");
+        let last_progress = || display.inner.lock().unwrap().last().unwrap().1.progress;
+        let set_state = |update: &dyn Fn(&mut PlaybackState)| {
+            update(&mut backend.states.lock().unwrap()[0]);
+        };
+        let assert_progress = |expected: f32| {
+            let progress = last_progress().expect("progress shown");
+            assert!(
+                (progress - expected).abs() < 1e-4,
+                "{progress} != {expected}"
+            );
+        };
+        assert_progress(0.25);
+
+        clock.advance(Duration::from_millis(500));
+        controller.on_tick().unwrap();
+        assert_progress(60.5 / 240.0);
+
+        set_state(&|state| state.position_secs = Some(61));
+        clock.advance(Duration::from_millis(600));
+        controller.on_tick().unwrap();
+        assert_progress(61.1 / 240.0);
+
+        set_state(&|state| state.position_secs = Some(120));
+        controller.on_tick().unwrap();
+        assert_progress(0.5);
+
+        set_state(&|state| state.status = PlaybackStatus::Paused);
+        controller.on_tick().unwrap();
+        assert_eq!(last_progress(), None);
+
+        set_state(&|state| {
+            state.status = PlaybackStatus::Playing;
+            state.length_secs = None;
+        });
+        controller.on_tick().unwrap();
+        assert_eq!(last_progress(), None);
+    }
+
+    #[test]
+    fn album_art_is_attached_and_broken_urls_fall_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let cover = dir.path().join("cover.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 0, 255]))
+            .save(&cover)
+            .unwrap();
+
+        for (url, expect_icon) in [
+            (format!("file://{}", cover.display()), true),
+            (
+                format!("file://{}/missing.png", dir.path().display()),
+                false,
+            ),
+        ] {
+            let backend = MockBackend::new(vec![PlaybackState {
+                status: PlaybackStatus::Playing,
+                title: Some("Track".into()),
+                artist: None,
+                art_url: Some(url),
+                position_secs: None,
+                length_secs: None,
+            }]);
+            let display = RecordingDisplay::new();
+            let _controller =
+                NowPlayingController::new(backend, display.clone(), EncoderId::Four).expect("init");
+
+            let events = display.inner.lock().unwrap();
+            let (_, event) = events.last().unwrap();
+            assert_eq!(event.icon.is_some(), expect_icon);
+            assert!(event.value.starts_with("Track"));
+        }
+    }
 }
 
 fn ellipsize(input: &str, max_chars: usize) -> String {
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+
+const INDICATOR_SIZE: u32 = 72;
+const DOT_RADIUS: i64 = 5;
+const DOT_SPACING: u32 = 16;
+const ACTIVE_DOT: [u8; 4] = [235, 240, 255, 255];
+const INACTIVE_DOT: [u8; 4] = [90, 95, 110, 255];
+
+/// The key that cycles button pages; its icon is a row of dots with the
+/// current page lit.
+pub struct PageSwitcher<H>
+where
+    H: DisplayPipeline,
+{
+    hardware: H,
+    button_index: u8,
+    page_count: usize,
+    current: usize,
+}
+
+impl<H> PageSwitcher<H>
+where
+    H: DisplayPipeline,
+{
+    pub fn new(button_index: u8, page_count: usize, current: usize, hardware: H) -> Result<Self> {
+        let switcher = Self {
+            hardware,
+            button_index,
+            page_count: page_count.max(1),
+            current: current.min(page_count.saturating_sub(1)),
+        };
+        switcher.push_icon()?;
+        Ok(switcher)
+    }
+
+    pub fn button_index(&self) -> u8 {
+        self.button_index
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Moves to the next page, wrapping after the last, and returns it.
+    pub fn advance(&mut self) -> Result<usize> {
+        self.current = (self.current + 1) % self.page_count;
+        self.push_icon()?;
+        Ok(self.current)
+    }
+
+    fn push_icon(&self) -> Result<()> {
+        let icon = ButtonImage {
+            id: format!(
+                "page-switch-{}-{}of{}",
+                self.button_index, self.current, self.page_count
+            ),
+            image: Arc::new(indicator_image(self.page_count, self.current)),
+            tint: None,
+            label: None,
+            animation: None,
+            background: None,
+            badge: None,
+        };
+        self.hardware
+            .update_button_icon(self.button_index, Some(icon))
+    }
+}
+
+// Dots shrink their spacing so that any page count fits across the key.
+fn indicator_image(page_count: usize, current: usize) -> RgbaImage {
+    let mut image = RgbaImage::new(INDICATOR_SIZE, INDICATOR_SIZE);
+    let count = page_count.max(1) as u32;
+    let spacing = DOT_SPACING.min(INDICATOR_SIZE / count).max(1);
+    let first_x = (INDICATOR_SIZE - spacing * (count - 1)) / 2;
+    let center_y = (INDICATOR_SIZE / 2) as i64;
+
+    for page in 0..count {
+        let center_x = (first_x + spacing * page) as i64;
+        let color = if page as usize == current {
+            ACTIVE_DOT
+        } else {
+            INACTIVE_DOT
+        };
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let (dx, dy) = (x as i64 - center_x, y as i64 - center_y);
+            if dx * dx + dy * dy <= DOT_RADIUS * DOT_RADIUS {
+                *pixel = Rgba(color);
+            }
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingHardware {
+        icons: Mutex<Vec<(u8, Option<String>)>>,
+    }
+
+    impl DisplayPipeline for &RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons
+                .lock()
+                .unwrap()
+                .push((index, icon.map(|icon| icon.id)));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn advancing_wraps_and_redraws_the_indicator() {
+        let hardware = RecordingHardware::default();
+        let mut switcher = PageSwitcher::new(7, 3, 0, &hardware).unwrap();
+        assert_eq!(switcher.advance().unwrap(), 1);
+        assert_eq!(switcher.advance().unwrap(), 2);
+        assert_eq!(switcher.advance().unwrap(), 0);
+
+        let icons = hardware.icons.lock().unwrap();
+        assert_eq!(icons.len(), 4);
+        assert_eq!(icons[1], (7, Some("page-switch-7-1of3".into())));
+    }
+
+    #[test]
+    fn indicator_lights_the_current_page() {
+        let image = indicator_image(3, 1);
+        assert_eq!(image.get_pixel(20, 36), &Rgba(INACTIVE_DOT));
+        assert_eq!(image.get_pixel(36, 36), &Rgba(ACTIVE_DOT));
+        assert_eq!(image.get_pixel(52, 36), &Rgba(INACTIVE_DOT));
+        assert_eq!(image.get_pixel(28, 36)[3], 0);
+    }
+}
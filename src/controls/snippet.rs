@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::snippet::{DesktopSnippetBackend, PasteMethod, SnippetBackend};
+
+use super::audio_toggle::{
+    IconConfig, IconPaths, MaterialIcon, UNAVAILABLE_TINT, load_configured_icon, load_material_icon,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnippetConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+    /// Typed or copied as is, newlines included.
+    pub text: String,
+    /// Defaults to `wtype` under Wayland and `xdotool` otherwise; the
+    /// clipboard is used when the typing tool is missing.
+    #[serde(default)]
+    pub method: Option<PasteMethod>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub icon: Option<IconConfig>,
+}
+
+struct SnippetButton {
+    text: String,
+    method: Option<PasteMethod>,
+    icon: ButtonImage,
+}
+
+/// Keys that type a configured snippet into the focused window or copy it
+/// to the clipboard. Without any tool to do either, the key is dimmed.
+pub struct SnippetController<B>
+where
+    B: SnippetBackend,
+{
+    backend: B,
+    buttons: HashMap<u8, SnippetButton>,
+}
+
+impl<B> SnippetController<B>
+where
+    B: SnippetBackend,
+{
+    fn new(
+        configs: &[SnippetConfig],
+        backend: B,
+        hardware: &impl DisplayPipeline,
+        icon_paths: &IconPaths,
+    ) -> Result<Option<Self>> {
+        let mut buttons = HashMap::new();
+        for config in configs {
+            match snippet_icon(config, icon_paths) {
+                Ok(mut icon) => {
+                    let method = backend.resolve(config.method);
+                    if method.is_none() {
+                        icon.id = format!("{}-unavailable", icon.id);
+                        icon.tint = Some(UNAVAILABLE_TINT);
+                    }
+                    let button = SnippetButton {
+                        text: config.text.clone(),
+                        method,
+                        icon,
+                    };
+                    if buttons.insert(config.button_index, button).is_some() {
+                        warn!(
+                            button_index = config.button_index,
+                            "overriding previously configured snippet key"
+                        );
+                    }
+                }
+                Err(err) => warn!(
+                    error = %err,
+                    button_index = config.button_index,
+                    "skipping snippet key due to configuration error"
+                ),
+            }
+        }
+
+        if buttons.is_empty() {
+            return Ok(None);
+        }
+        for (index, button) in &buttons {
+            hardware
+                .update_button_icon(*index, Some(button.icon.clone()))
+                .with_context(|| format!("failed to set icon for snippet key {index}"))?;
+        }
+        Ok(Some(Self { backend, buttons }))
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        self.buttons.keys().copied()
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        let Some(button) = self.buttons.get(&button_index) else {
+            return Ok(false);
+        };
+        let Some(method) = button.method else {
+            warn!(button_index, "no tool to type or copy the snippet with");
+            return Ok(true);
+        };
+        match self.backend.paste(method, &button.text) {
+            Ok(()) => info!(button_index, ?method, "pasted snippet"),
+            Err(err) => warn!(error = %err, button_index, "failed to paste snippet"),
+        }
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(self.buttons.contains_key(&button_index))
+    }
+}
+
+impl SnippetController<DesktopSnippetBackend> {
+    pub fn with_default_backend(
+        configs: &[SnippetConfig],
+        config_path: Option<&Path>,
+        hardware: &impl DisplayPipeline,
+    ) -> Result<Option<Self>> {
+        let icon_paths = IconPaths::new(config_path);
+        SnippetController::new(configs, DesktopSnippetBackend, hardware, &icon_paths)
+    }
+}
+
+fn snippet_icon(config: &SnippetConfig, icon_paths: &IconPaths) -> Result<ButtonImage> {
+    let mut icon = match config.icon.as_ref() {
+        Some(icon) => load_configured_icon(icon, icon_paths)?,
+        None => load_material_icon(MaterialIcon::TextSnippet, icon_paths)?,
+    };
+    icon.id = format!("snippet-{}-{}", config.button_index, icon.id);
+    icon.label = config.label.clone();
+    Ok(icon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use crate::system::snippet::tests::MockSnippetBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<HashMap<u8, ButtonImage>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            if let Some(icon) = icon {
+                self.icons.lock().unwrap().insert(index, icon);
+            }
+            Ok(())
+        }
+    }
+
+    fn snippet(button_index: u8, text: &str, method: Option<PasteMethod>) -> SnippetConfig {
+        SnippetConfig {
+            button_index,
+            text: text.to_string(),
+            method,
+            label: Some("sig".to_string()),
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn press_pastes_with_the_resolved_method() {
+        let backend = MockSnippetBackend {
+            methods: vec![PasteMethod::Wtype, PasteMethod::Clipboard],
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let configs = [
+            snippet(4, "Regards,\nÅsa", None),
+            snippet(5, "make test", Some(PasteMethod::Xdotool)),
+        ];
+        let mut controller =
+            SnippetController::new(&configs, backend.clone(), &hardware, &IconPaths::new(None))
+                .unwrap()
+                .unwrap();
+
+        assert!(controller.on_button_pressed(4).unwrap());
+        assert!(controller.on_button_pressed(5).unwrap());
+        assert!(!controller.on_button_pressed(6).unwrap());
+        assert!(controller.on_button_released(5).unwrap());
+        assert_eq!(
+            *backend.pasted.lock().unwrap(),
+            [
+                (PasteMethod::Wtype, "Regards,\nÅsa".to_string()),
+                (PasteMethod::Clipboard, "make test".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_tools_dim_the_key() {
+        let backend = MockSnippetBackend::default();
+        let hardware = RecordingHardware::default();
+        let configs = [snippet(4, "hello", None)];
+        let mut controller =
+            SnippetController::new(&configs, backend.clone(), &hardware, &IconPaths::new(None))
+                .unwrap()
+                .unwrap();
+
+        let icon = hardware.icons.lock().unwrap()[&4].clone();
+        assert_eq!(icon.id, "snippet-4-text-snippet-unavailable");
+        assert_eq!(icon.tint, Some(UNAVAILABLE_TINT));
+        assert_eq!(icon.label.as_deref(), Some("sig"));
+        assert!(controller.on_button_pressed(4).unwrap());
+        assert!(backend.pasted.lock().unwrap().is_empty());
+    }
+}
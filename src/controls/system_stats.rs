@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId, ProgressStyle};
+use crate::system::system_stats::{CpuTimes, MemoryUsage, SystemStatsBackend};
+
+use super::{EncoderController, Tickable};
+
+const CPU_WARNING_COLOR: [u8; 3] = [230, 80, 60];
+const KIB_PER_GIB: f32 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SystemStatsConfig {
+    /// CPU usage above which the progress bar turns red.
+    pub cpu_warning_percent: f32,
+}
+
+impl Default for SystemStatsConfig {
+    fn default() -> Self {
+        Self {
+            cpu_warning_percent: 90.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatsView {
+    Cpu,
+    Memory,
+    Load,
+}
+
+impl StatsView {
+    const ALL: [StatsView; 3] = [StatsView::Cpu, StatsView::Memory, StatsView::Load];
+
+    fn step(self, delta: i32) -> Self {
+        let count = Self::ALL.len() as i32;
+        let current = Self::ALL.iter().position(|view| *view == self).unwrap_or(0) as i32;
+        Self::ALL[(current + delta).rem_euclid(count) as usize]
+    }
+}
+
+/// Shows CPU, memory or load average as the primary value; the progress bar
+/// always tracks CPU usage.
+pub struct SystemStatsController<B, D>
+where
+    B: SystemStatsBackend,
+    D: DisplayPipeline,
+{
+    backend: B,
+    display: D,
+    encoder: EncoderId,
+    warning_percent: f32,
+    view: StatsView,
+    last_cpu: CpuTimes,
+    cpu_percent: Option<f32>,
+    memory: MemoryUsage,
+    load: f32,
+}
+
+impl<B, D> SystemStatsController<B, D>
+where
+    B: SystemStatsBackend,
+    D: DisplayPipeline,
+{
+    pub fn new(
+        config: SystemStatsConfig,
+        backend: B,
+        display: D,
+        encoder: EncoderId,
+    ) -> Result<Self> {
+        let last_cpu = backend.cpu_times().context("failed to read CPU times")?;
+        let memory = backend.memory().context("failed to read memory usage")?;
+        let load = backend
+            .load_average()
+            .context("failed to read load average")?;
+        let controller = Self {
+            backend,
+            display,
+            encoder,
+            warning_percent: config.cpu_warning_percent,
+            view: StatsView::Cpu,
+            last_cpu,
+            cpu_percent: None,
+            memory,
+            load,
+        };
+        controller.push_display()?;
+        Ok(controller)
+    }
+
+    // CPU usage needs two samples, so it reads "--" until the first tick.
+    fn sample(&mut self) -> Result<()> {
+        let cpu = self.backend.cpu_times()?;
+        if let Some(percent) = cpu.usage_since(&self.last_cpu) {
+            self.cpu_percent = Some(percent);
+        }
+        self.last_cpu = cpu;
+        self.memory = self.backend.memory()?;
+        self.load = self.backend.load_average()?;
+        Ok(())
+    }
+
+    fn push_display(&self) -> Result<()> {
+        let cpu_value = match self.cpu_percent {
+            Some(percent) => format!("{:>3.0}%", percent),
+            None => " --%".to_string(),
+        };
+        let memory_status = format!(
+            "{:.1}/{:.0}G",
+            self.memory.used_kib as f32 / KIB_PER_GIB,
+            self.memory.total_kib as f32 / KIB_PER_GIB
+        );
+
+        let mut display = match self.view {
+            StatsView::Cpu => EncoderDisplay::new("cpu", cpu_value).with_status(memory_status),
+            StatsView::Memory => {
+                EncoderDisplay::new("mem", format!("{:>3.0}%", self.memory.percent()))
+                    .with_status(memory_status)
+            }
+            StatsView::Load => EncoderDisplay::new("load", format!("{:.2}", self.load))
+                .with_status(format!("cpu {}", cpu_value.trim())),
+        };
+
+        let cpu = self.cpu_percent.unwrap_or(0.0);
+        display.progress = Some((cpu / 100.0).clamp(0.0, 1.0));
+        if cpu > self.warning_percent {
+            display.progress_style = Some(ProgressStyle::Solid(CPU_WARNING_COLOR));
+        }
+        self.display.update_encoder(self.encoder, display)
+    }
+}
+
+impl<B, D> EncoderController for SystemStatsController<B, D>
+where
+    B: SystemStatsBackend,
+    D: DisplayPipeline,
+{
+    fn on_turn(&mut self, delta: i32) -> Result<()> {
+        if delta == 0 {
+            return Ok(());
+        }
+        self.view = self.view.step(delta.signum());
+        self.push_display()
+    }
+
+    fn on_press(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<B, D> Tickable for SystemStatsController<B, D>
+where
+    B: SystemStatsBackend,
+    D: DisplayPipeline,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        self.sample()?;
+        self.push_display()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system_stats::tests::MockSystemStatsBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingDisplay {
+        updates: Arc<Mutex<Vec<EncoderDisplay>>>,
+    }
+
+    impl DisplayPipeline for RecordingDisplay {
+        fn update_encoder(&self, _encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.updates.lock().unwrap().push(display);
+            Ok(())
+        }
+    }
+
+    impl RecordingDisplay {
+        fn last(&self) -> EncoderDisplay {
+            self.updates.lock().unwrap().last().unwrap().clone()
+        }
+    }
+
+    fn controller(
+        backend: MockSystemStatsBackend,
+        display: RecordingDisplay,
+    ) -> SystemStatsController<MockSystemStatsBackend, RecordingDisplay> {
+        SystemStatsController::new(
+            SystemStatsConfig::default(),
+            backend,
+            display,
+            EncoderId::Four,
+        )
+        .expect("controller")
+    }
+
+    const MEMORY: MemoryUsage = MemoryUsage {
+        used_kib: 12_897_485,
+        total_kib: 33_554_432,
+    };
+
+    #[test]
+    fn shows_cpu_from_successive_samples_with_memory_status() {
+        let backend = MockSystemStatsBackend::new(MEMORY, 0.5);
+        let display = RecordingDisplay::default();
+        let mut controller = controller(backend.clone(), display.clone());
+        assert_eq!(display.last().value, " --%");
+
+        backend.run_cpu(42);
+        controller.on_tick().unwrap();
+        let shown = display.last();
+        assert_eq!(shown.title, "cpu");
+        assert_eq!(shown.value, " 42%");
+        assert_eq!(shown.status.as_deref(), Some("12.3/32G"));
+        assert!((shown.progress.unwrap() - 0.42).abs() < 1e-4);
+        assert_eq!(shown.progress_style, None);
+
+        backend.run_cpu(95);
+        controller.on_tick().unwrap();
+        assert_eq!(
+            display.last().progress_style,
+            Some(ProgressStyle::Solid(CPU_WARNING_COLOR))
+        );
+    }
+
+    #[test]
+    fn turning_cycles_primary_value_but_progress_stays_on_cpu() {
+        let backend = MockSystemStatsBackend::new(MEMORY, 1.25);
+        let display = RecordingDisplay::default();
+        let mut controller = controller(backend.clone(), display.clone());
+        backend.run_cpu(30);
+        controller.on_tick().unwrap();
+
+        controller.on_turn(1).unwrap();
+        let memory = display.last();
+        assert_eq!(
+            (memory.title.as_str(), memory.value.as_str()),
+            ("mem", " 38%")
+        );
+        assert!((memory.progress.unwrap() - 0.3).abs() < 1e-4);
+
+        controller.on_turn(1).unwrap();
+        let load = display.last();
+        assert_eq!((load.title.as_str(), load.value.as_str()), ("load", "1.25"));
+        assert_eq!(load.status.as_deref(), Some("cpu 30%"));
+
+        controller.on_turn(1).unwrap();
+        assert_eq!(display.last().title, "cpu");
+        controller.on_turn(-1).unwrap();
+        assert_eq!(display.last().title, "load");
+    }
+}
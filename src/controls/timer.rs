@@ -1,16 +1,171 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
 use anyhow::Result;
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
-use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
-use crate::util::format_duration;
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId, ProgressStyle};
+use crate::system::timer_state::{TimerSnapshot, TimerStore, unix_now};
+use crate::util::{format_clock_time, format_duration};
 
 use super::{EncoderController, Tickable};
 
 const PROGRESS_ALERT_COLOR: [u8; 3] = [64, 130, 255];
+const FINAL_MINUTE_COLOR: [u8; 3] = [240, 170, 0];
+const FINAL_MINUTE_SECS: u64 = 60;
+const TAP_MAX: Duration = Duration::from_millis(400);
+const HOLD_RESET_MIN: Duration = Duration::from_millis(1500);
+const WORK_COLOR: [u8; 3] = [230, 90, 70];
+const BREAK_COLOR: [u8; 3] = [0, 180, 120];
+const LONG_BREAK_COLOR: [u8; 3] = [120, 210, 255];
+const FINISHED_BANNER_DURATION: Duration = Duration::from_secs(3);
+
+pub trait TimerAlert: Send {
+    fn timer_finished(&self, configured_secs: u64);
+
+    /// Called as a pomodoro phase ends, just before the next one starts.
+    fn phase_finished(&self, _phase: PomodoroPhase, secs: u64) {
+        self.timer_finished(secs);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerMode {
+    #[default]
+    Simple,
+    Pomodoro,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub work_secs: u64,
+    pub break_secs: u64,
+    pub long_break_secs: u64,
+    /// Work phases before a long break.
+    pub cycles: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            work_secs: 25 * 60,
+            break_secs: 5 * 60,
+            long_break_secs: 15 * 60,
+            cycles: 4,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PomodoroPhase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+impl PomodoroPhase {
+    fn label(self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "work",
+            PomodoroPhase::Break => "break",
+            PomodoroPhase::LongBreak => "long break",
+        }
+    }
+}
+
+/// Announces completion with `notify-send` and, when configured, a `paplay` sound.
+#[derive(Debug, Clone, Default)]
+pub struct DesktopTimerAlert {
+    sound: Option<PathBuf>,
+}
+
+impl DesktopTimerAlert {
+    pub fn new(sound: Option<PathBuf>) -> Self {
+        Self { sound }
+    }
+}
+
+impl TimerAlert for DesktopTimerAlert {
+    fn timer_finished(&self, configured_secs: u64) {
+        self.notify(format!(
+            "{} countdown finished",
+            format_duration(configured_secs)
+        ));
+    }
+
+    fn phase_finished(&self, phase: PomodoroPhase, secs: u64) {
+        self.notify(format!(
+            "{} {} finished",
+            format_duration(secs),
+            phase.label()
+        ));
+    }
+}
+
+impl DesktopTimerAlert {
+    fn notify(&self, body: String) {
+        let sound = self.sound.clone();
+        // Both commands can block (paplay for the length of the sound), so keep
+        // them off the event loop.
+        thread::spawn(move || {
+            match Command::new("notify-send")
+                .arg("Stream Deck Timer")
+                .arg(body)
+                .status()
+            {
+                Ok(status) => {
+                    if !status.success() {
+                        warn!(code = ?status.code(), "notify-send exited with failure status");
+                    }
+                }
+                Err(err) => {
+                    warn!(error = %err, "failed to send timer notification");
+                }
+            }
+
+            let Some(sound) = sound else {
+                return;
+            };
+            match Command::new("paplay").arg(&sound).status() {
+                Ok(status) => {
+                    if !status.success() {
+                        warn!(code = ?status.code(), sound = %sound.display(), "paplay exited with failure status");
+                    }
+                }
+                Err(err) => {
+                    warn!(error = %err, "failed to play timer sound");
+                }
+            }
+        });
+    }
+}
+
+/// Local time of day, used to show when a running countdown ends.
+pub trait WallClock: Send {
+    fn secs_since_midnight(&self) -> u64;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalWallClock;
+
+impl WallClock for LocalWallClock {
+    fn secs_since_midnight(&self) -> u64 {
+        chrono::Local::now().num_seconds_from_midnight() as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TimerDisplayState {
     Setting,
     Running,
+    Paused,
     Finished,
 }
 
@@ -27,6 +182,16 @@ where
     max: u64,
     state: TimerDisplayState,
     finished_blink: bool,
+    store: Option<Box<dyn TimerStore>>,
+    alert: Option<Box<dyn TimerAlert>>,
+    clock: Box<dyn WallClock>,
+    /// End of the running countdown as local seconds since midnight.
+    deadline: Option<u64>,
+    mode: TimerMode,
+    pomodoro: PomodoroConfig,
+    phase: PomodoroPhase,
+    /// The current work cycle, starting at 1.
+    cycle: u32,
 }
 
 impl<D> TimerController<D>
@@ -64,25 +229,183 @@ where
             max: max_bound,
             state: TimerDisplayState::Setting,
             finished_blink: false,
+            store: None,
+            alert: None,
+            clock: Box::new(LocalWallClock),
+            deadline: None,
+            mode: TimerMode::Simple,
+            pomodoro: PomodoroConfig::default(),
+            phase: PomodoroPhase::Work,
+            cycle: 1,
         };
         controller.push_display()?;
         Ok(controller)
     }
 
+    /// In pomodoro mode the configured duration is the work phase.
+    pub fn with_mode(mut self, mode: TimerMode, pomodoro: PomodoroConfig) -> Result<Self> {
+        self.pomodoro = PomodoroConfig {
+            cycles: pomodoro.cycles.max(1),
+            ..pomodoro
+        };
+        self.set_mode(mode);
+        self.push_display()?;
+        Ok(self)
+    }
+
+    fn set_mode(&mut self, mode: TimerMode) {
+        self.mode = mode;
+        if mode == TimerMode::Pomodoro {
+            self.configured = self.clamp_configured(self.pomodoro.work_secs as i64);
+        }
+        self.remaining = self.configured;
+        self.state = TimerDisplayState::Setting;
+        self.finished_blink = false;
+        self.deadline = None;
+        self.phase = PomodoroPhase::Work;
+        self.cycle = 1;
+    }
+
+    fn toggle_mode(&mut self) -> Result<()> {
+        let mode = match self.mode {
+            TimerMode::Simple => TimerMode::Pomodoro,
+            TimerMode::Pomodoro => TimerMode::Simple,
+        };
+        info!(?mode, "switched timer mode");
+        self.set_mode(mode);
+        self.push_display()
+    }
+
+    fn phase_secs(&self) -> u64 {
+        match (self.mode, self.phase) {
+            (TimerMode::Pomodoro, PomodoroPhase::Break) => self.pomodoro.break_secs,
+            (TimerMode::Pomodoro, PomodoroPhase::LongBreak) => self.pomodoro.long_break_secs,
+            _ => self.configured,
+        }
+    }
+
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: WallClock + 'static,
+    {
+        self.clock = Box::new(clock);
+        self.update_deadline();
+        self
+    }
+
+    fn update_deadline(&mut self) {
+        self.deadline = (self.state == TimerDisplayState::Running)
+            .then(|| self.clock.secs_since_midnight() + self.remaining);
+    }
+
+    pub fn with_alert<A>(mut self, alert: A) -> Self
+    where
+        A: TimerAlert + 'static,
+    {
+        self.alert = Some(Box::new(alert));
+        self
+    }
+
+    /// Persists the countdown to `store` and resumes any countdown it holds.
+    /// Pomodoro progress is not persisted.
+    pub fn with_store<S>(mut self, store: S) -> Result<Self>
+    where
+        S: TimerStore + 'static,
+    {
+        let saved = store.load();
+        self.store = Some(Box::new(store));
+        match saved {
+            Ok(Some(_)) if self.mode == TimerMode::Pomodoro => {}
+            Ok(Some(snapshot)) => {
+                self.restore(snapshot);
+                self.persist();
+            }
+            Ok(None) => {}
+            Err(err) => warn!(error = %err, "failed to load saved timer state"),
+        }
+        self.push_display()?;
+        Ok(self)
+    }
+
+    fn restore(&mut self, snapshot: TimerSnapshot) {
+        self.configured = self.clamp_configured(snapshot.configured as i64);
+        match snapshot.deadline {
+            Some(deadline) => {
+                let now = unix_now();
+                if deadline <= now {
+                    self.remaining = 0;
+                    self.state = TimerDisplayState::Finished;
+                    self.finished_blink = true;
+                } else {
+                    self.remaining = (deadline - now).min(self.configured);
+                    self.state = TimerDisplayState::Running;
+                    self.update_deadline();
+                }
+            }
+            None if snapshot.remaining > 0 => {
+                self.remaining = snapshot.remaining.min(self.configured);
+                self.state = TimerDisplayState::Paused;
+            }
+            None => {
+                self.remaining = self.configured;
+                self.state = TimerDisplayState::Setting;
+            }
+        }
+        info!(
+            state = ?self.state,
+            remaining = self.remaining,
+            "restored saved timer state"
+        );
+    }
+
+    fn persist(&self) {
+        let Some(store) = self
+            .store
+            .as_ref()
+            .filter(|_| self.mode == TimerMode::Simple)
+        else {
+            return;
+        };
+        let result = match self.state {
+            TimerDisplayState::Running => store.save(&TimerSnapshot {
+                configured: self.configured,
+                remaining: self.remaining,
+                deadline: Some(unix_now() + self.remaining),
+            }),
+            TimerDisplayState::Paused => store.save(&TimerSnapshot {
+                configured: self.configured,
+                remaining: self.remaining,
+                deadline: None,
+            }),
+            TimerDisplayState::Setting | TimerDisplayState::Finished => store.clear(),
+        };
+        if let Err(err) = result {
+            warn!(error = %err, "failed to persist timer state");
+        }
+    }
+
     fn clamp_configured(&self, value: i64) -> u64 {
         value.clamp(self.min as i64, self.max as i64) as u64
     }
 
     fn push_display(&self) -> Result<()> {
         let value = match self.state {
-            TimerDisplayState::Running => format_duration(self.remaining),
+            TimerDisplayState::Running | TimerDisplayState::Paused => {
+                format_duration(self.remaining)
+            }
             TimerDisplayState::Finished => "00:00".to_string(),
             TimerDisplayState::Setting => format_duration(self.configured),
         };
 
-        let mut display = EncoderDisplay::new("timer", value);
-        let ratio = if self.configured > 0 {
-            (self.remaining as f32 / self.configured as f32).clamp(0.0, 1.0)
+        let title = match self.mode {
+            TimerMode::Simple => "timer",
+            TimerMode::Pomodoro if self.phase == PomodoroPhase::Work => "work",
+            TimerMode::Pomodoro => "break",
+        };
+        let mut display = EncoderDisplay::new(title, value);
+        let phase_secs = self.phase_secs();
+        let ratio = if phase_secs > 0 {
+            (self.remaining as f32 / phase_secs as f32).clamp(0.0, 1.0)
         } else {
             0.0
         };
@@ -91,11 +414,21 @@ where
             TimerDisplayState::Setting => {
                 display.progress = Some(if self.configured > 0 { 1.0 } else { 0.0 });
             }
-            TimerDisplayState::Running => {
+            TimerDisplayState::Running | TimerDisplayState::Paused => {
                 display.progress = Some(ratio);
-                if ratio <= 0.1 {
-                    display.progress_color = Some(PROGRESS_ALERT_COLOR);
+                if self.remaining < FINAL_MINUTE_SECS {
+                    display.value_color = Some(FINAL_MINUTE_COLOR);
                 }
+                display.progress_style = Some(match self.mode {
+                    TimerMode::Pomodoro => ProgressStyle::Solid(match self.phase {
+                        PomodoroPhase::Work => WORK_COLOR,
+                        PomodoroPhase::Break => BREAK_COLOR,
+                        PomodoroPhase::LongBreak => LONG_BREAK_COLOR,
+                    }),
+                    TimerMode::Simple => {
+                        ProgressStyle::Thresholds(vec![(0.1, PROGRESS_ALERT_COLOR)])
+                    }
+                });
             }
             TimerDisplayState::Finished => {
                 display.progress = Some(0.0);
@@ -105,16 +438,36 @@ where
             }
         }
 
-        let status = match self.state {
-            TimerDisplayState::Setting => Some("set"),
-            TimerDisplayState::Running => Some("run"),
-            TimerDisplayState::Finished => Some("done"),
+        let cycles = format!("{}/{}", self.cycle, self.pomodoro.cycles);
+        let status = match (self.mode, self.state, self.deadline) {
+            (TimerMode::Pomodoro, TimerDisplayState::Setting, _) => "pomodoro".to_string(),
+            (TimerMode::Pomodoro, TimerDisplayState::Paused, _) => format!("paused {cycles}"),
+            (TimerMode::Pomodoro, _, _) => format!("{} {cycles}", self.phase.label()),
+            (TimerMode::Simple, TimerDisplayState::Setting, _) => "set".to_string(),
+            (TimerMode::Simple, TimerDisplayState::Running, Some(deadline)) => {
+                format!("run · {}", format_clock_time(deadline))
+            }
+            (TimerMode::Simple, TimerDisplayState::Running, None) => "run".to_string(),
+            (TimerMode::Simple, TimerDisplayState::Paused, _) => "paused".to_string(),
+            (TimerMode::Simple, TimerDisplayState::Finished, _) => "done".to_string(),
         };
-        display.status = status.map(|s| s.to_string());
+        display.status = Some(status);
 
         self.display.update_encoder(self.encoder, display)
     }
 
+    pub fn sync(&self) -> Result<()> {
+        self.push_display()
+    }
+
+    pub fn state(&self) -> TimerDisplayState {
+        self.state
+    }
+
+    pub fn remaining_secs(&self) -> u64 {
+        self.remaining
+    }
+
     fn start(&mut self) -> Result<()> {
         if self.configured == 0 {
             return Ok(());
@@ -122,6 +475,24 @@ where
         self.remaining = self.configured;
         self.state = TimerDisplayState::Running;
         self.finished_blink = false;
+        self.phase = PomodoroPhase::Work;
+        self.cycle = 1;
+        self.update_deadline();
+        self.persist();
+        self.push_display()
+    }
+
+    fn pause(&mut self) -> Result<()> {
+        self.state = TimerDisplayState::Paused;
+        self.deadline = None;
+        self.persist();
+        self.push_display()
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        self.state = TimerDisplayState::Running;
+        self.update_deadline();
+        self.persist();
         self.push_display()
     }
 
@@ -129,6 +500,10 @@ where
         self.remaining = self.configured;
         self.state = TimerDisplayState::Setting;
         self.finished_blink = false;
+        self.deadline = None;
+        self.phase = PomodoroPhase::Work;
+        self.cycle = 1;
+        self.persist();
         self.push_display()
     }
 
@@ -136,6 +511,45 @@ where
         self.remaining = 0;
         self.state = TimerDisplayState::Finished;
         self.finished_blink = true;
+        self.deadline = None;
+        self.persist();
+        if let Some(alert) = self.alert.as_ref() {
+            alert.timer_finished(self.configured);
+        }
+        let banner = format!("{} countdown finished", format_duration(self.configured));
+        if let Err(err) =
+            self.display
+                .show_banner(&banner, FINISHED_BANNER_DURATION, PROGRESS_ALERT_COLOR)
+        {
+            warn!(error = %err, "failed to show timer banner");
+        }
+        self.push_display()
+    }
+
+    fn complete_phase(&mut self) -> Result<()> {
+        match self.mode {
+            TimerMode::Simple => self.finish(),
+            TimerMode::Pomodoro => self.advance_phase(),
+        }
+    }
+
+    // A pomodoro never stops on its own: every phase rolls into the next,
+    // with a long break after the last work cycle.
+    fn advance_phase(&mut self) -> Result<()> {
+        if let Some(alert) = self.alert.as_ref() {
+            alert.phase_finished(self.phase, self.phase_secs());
+        }
+        (self.phase, self.cycle) = match self.phase {
+            PomodoroPhase::Work if self.cycle >= self.pomodoro.cycles => {
+                (PomodoroPhase::LongBreak, self.cycle)
+            }
+            PomodoroPhase::Work => (PomodoroPhase::Break, self.cycle),
+            PomodoroPhase::Break => (PomodoroPhase::Work, self.cycle + 1),
+            PomodoroPhase::LongBreak => (PomodoroPhase::Work, 1),
+        };
+        info!(phase = ?self.phase, cycle = self.cycle, "pomodoro phase started");
+        self.remaining = self.phase_secs();
+        self.update_deadline();
         self.push_display()
     }
 }
@@ -149,8 +563,11 @@ where
             return Ok(());
         }
 
-        if matches!(self.state, TimerDisplayState::Running) {
-            return Ok(()); // ignore adjustments while running
+        if matches!(
+            self.state,
+            TimerDisplayState::Running | TimerDisplayState::Paused
+        ) {
+            return Ok(()); // ignore adjustments while a countdown is in progress
         }
 
         let delta_steps = (delta as i64) * self.step as i64;
@@ -159,18 +576,34 @@ where
         self.remaining = self.configured;
         self.state = TimerDisplayState::Setting;
         self.finished_blink = false;
+        self.deadline = None;
         self.push_display()
     }
 
     fn on_press(&mut self) -> Result<()> {
-        match self.state {
-            TimerDisplayState::Setting => self.start(),
-            TimerDisplayState::Running | TimerDisplayState::Finished => self.reset_to_setting(),
+        Ok(()) // tap vs hold is decided on release
+    }
+
+    fn on_release(&mut self, held: Duration) -> Result<()> {
+        if held >= HOLD_RESET_MIN {
+            if self.state == TimerDisplayState::Setting {
+                return self.toggle_mode();
+            }
+            return self.reset_to_setting();
+        }
+        if held >= TAP_MAX {
+            return Ok(());
         }
+        self.on_touch_tap()
     }
 
-    fn on_release(&mut self) -> Result<()> {
-        Ok(())
+    fn on_touch_tap(&mut self) -> Result<()> {
+        match self.state {
+            TimerDisplayState::Setting => self.start(),
+            TimerDisplayState::Running => self.pause(),
+            TimerDisplayState::Paused => self.resume(),
+            TimerDisplayState::Finished => self.reset_to_setting(),
+        }
     }
 }
 
@@ -180,16 +613,17 @@ where
 {
     fn on_tick(&mut self) -> Result<()> {
         match self.state {
-            TimerDisplayState::Setting => Ok(()),
+            TimerDisplayState::Setting | TimerDisplayState::Paused => Ok(()),
             TimerDisplayState::Running => {
                 if self.remaining == 0 {
-                    return self.finish();
+                    return self.complete_phase();
                 }
 
                 self.remaining = self.remaining.saturating_sub(1);
                 if self.remaining == 0 {
-                    self.finish()
+                    self.complete_phase()
                 } else {
+                    self.persist();
                     self.push_display()
                 }
             }
@@ -211,6 +645,7 @@ mod tests {
     #[derive(Clone, Default)]
     struct TestDisplay {
         pub updates: Arc<Mutex<Vec<EncoderDisplay>>>,
+        pub banners: Arc<Mutex<Vec<String>>>,
     }
 
     impl DisplayPipeline for TestDisplay {
@@ -218,8 +653,59 @@ mod tests {
             self.updates.lock().unwrap().push(display);
             Ok(())
         }
+
+        fn show_banner(&self, text: &str, _duration: Duration, _color: [u8; 3]) -> Result<()> {
+            self.banners.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStore {
+        snapshot: Arc<Mutex<Option<TimerSnapshot>>>,
+    }
+
+    impl TimerStore for MemoryStore {
+        fn load(&self) -> Result<Option<TimerSnapshot>> {
+            Ok(*self.snapshot.lock().unwrap())
+        }
+
+        fn save(&self, snapshot: &TimerSnapshot) -> Result<()> {
+            *self.snapshot.lock().unwrap() = Some(*snapshot);
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<()> {
+            *self.snapshot.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CountingAlert {
+        fired: Arc<Mutex<Vec<u64>>>,
     }
 
+    impl TimerAlert for CountingAlert {
+        fn timer_finished(&self, configured_secs: u64) {
+            self.fired.lock().unwrap().push(configured_secs);
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FixedClock {
+        secs: Arc<Mutex<u64>>,
+    }
+
+    impl WallClock for FixedClock {
+        fn secs_since_midnight(&self) -> u64 {
+            *self.secs.lock().unwrap()
+        }
+    }
+
+    const TAP: Duration = Duration::from_millis(120);
+    const HOLD: Duration = Duration::from_secs(2);
+
     #[test]
     fn rotation_adjusts_configuration() {
         let display = TestDisplay::default();
@@ -239,12 +725,16 @@ mod tests {
         let mut controller =
             TimerController::new(display.clone(), EncoderId::Three, 10, 10, 600, 100).unwrap();
 
-        controller.on_press().unwrap(); // start
+        controller.on_release(TAP).unwrap(); // start
         controller.on_tick().unwrap(); // first second
         controller.on_tick().unwrap(); // second second
 
         let updates = display.updates.lock().unwrap();
-        assert!(updates.iter().any(|d| d.status.as_deref() == Some("run")));
+        assert!(
+            updates
+                .iter()
+                .any(|d| d.status.as_deref().is_some_and(|s| s.starts_with("run")))
+        );
 
         let before = controller.remaining;
         controller.on_turn(1).unwrap(); // ignored while running
@@ -252,16 +742,28 @@ mod tests {
     }
 
     #[test]
-    fn pressing_while_running_resets_without_restarting() {
+    fn touch_tap_starts_and_pauses_like_a_press() {
+        let display = TestDisplay::default();
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120).unwrap();
+
+        controller.on_touch_tap().unwrap();
+        assert_eq!(controller.state, TimerDisplayState::Running);
+        controller.on_touch_tap().unwrap();
+        assert_eq!(controller.state, TimerDisplayState::Paused);
+    }
+
+    #[test]
+    fn holding_while_running_resets_without_restarting() {
         let display = TestDisplay::default();
         let mut controller =
             TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120).unwrap();
 
-        controller.on_press().unwrap();
+        controller.on_release(TAP).unwrap();
         controller.on_tick().unwrap();
         assert!(controller.remaining < controller.configured);
 
-        controller.on_press().unwrap(); // restart
+        controller.on_release(HOLD).unwrap(); // reset
         assert_eq!(controller.remaining, controller.configured);
         assert!(matches!(controller.state, TimerDisplayState::Setting));
 
@@ -277,40 +779,43 @@ mod tests {
         let mut controller =
             TimerController::new(display.clone(), EncoderId::Three, 5, 5, 600, 100).unwrap();
 
-        controller.on_press().unwrap();
+        controller.on_release(TAP).unwrap();
         for _ in 0..90 {
             controller.on_tick().unwrap();
         }
 
         let updates = display.updates.lock().unwrap();
-        let blue = updates
-            .iter()
-            .rev()
-            .find(|d| d.progress_color.is_some())
-            .expect("blue update present");
+        let blue = updates.last().unwrap();
 
-        assert_eq!(blue.status.as_deref(), Some("run"));
+        assert!(blue.status.as_deref().is_some_and(|s| s.starts_with("run")));
         assert!(
             blue.progress
                 .map(|p| (p - 0.1).abs() < f32::EPSILON)
                 .unwrap_or(false)
         );
-        assert_eq!(blue.progress_color, Some(PROGRESS_ALERT_COLOR));
+        assert_eq!(
+            blue.progress_style,
+            Some(ProgressStyle::Thresholds(vec![(0.1, PROGRESS_ALERT_COLOR)]))
+        );
     }
 
     #[test]
-    fn pressing_after_finish_resets_without_restarting() {
+    fn tapping_after_finish_resets_without_restarting() {
         let display = TestDisplay::default();
         let mut controller =
             TimerController::new(display.clone(), EncoderId::Three, 1, 1, 600, 3).unwrap();
 
-        controller.on_press().unwrap();
+        controller.on_release(TAP).unwrap();
         for _ in 0..3 {
             controller.on_tick().unwrap();
         }
         assert!(matches!(controller.state, TimerDisplayState::Finished));
+        assert_eq!(
+            *display.banners.lock().unwrap(),
+            ["00:03 countdown finished"]
+        );
 
-        controller.on_press().unwrap();
+        controller.on_release(TAP).unwrap();
         assert!(matches!(controller.state, TimerDisplayState::Setting));
         assert_eq!(controller.remaining, controller.configured);
 
@@ -319,13 +824,28 @@ mod tests {
         assert_eq!(last.status.as_deref(), Some("set"));
     }
 
+    #[test]
+    fn value_turns_amber_in_the_final_minute() {
+        let display = TestDisplay::default();
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 1, 1, 600, 61).unwrap();
+        let last_color = || display.updates.lock().unwrap().last().unwrap().value_color;
+
+        controller.on_release(TAP).unwrap();
+        controller.on_tick().unwrap(); // remaining -> 60
+        assert_eq!(last_color(), None);
+
+        controller.on_tick().unwrap(); // remaining -> 59
+        assert_eq!(last_color(), Some(FINAL_MINUTE_COLOR));
+    }
+
     #[test]
     fn finished_timer_value_blinks_blue() {
         let display = TestDisplay::default();
         let mut controller =
             TimerController::new(display.clone(), EncoderId::Three, 1, 1, 600, 2).unwrap();
 
-        controller.on_press().unwrap();
+        controller.on_release(TAP).unwrap();
         controller.on_tick().unwrap(); // remaining -> 1
         controller.on_tick().unwrap(); // finish
         assert!(matches!(controller.state, TimerDisplayState::Finished));
@@ -350,4 +870,333 @@ mod tests {
             assert_eq!(last.value_color, Some(PROGRESS_ALERT_COLOR));
         }
     }
+
+    #[test]
+    fn tapping_while_running_pauses_and_resumes() {
+        let display = TestDisplay::default();
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120).unwrap();
+
+        controller.on_release(TAP).unwrap();
+        controller.on_tick().unwrap();
+        controller.on_release(TAP).unwrap(); // pause
+        assert!(matches!(controller.state, TimerDisplayState::Paused));
+
+        let paused_at = controller.remaining;
+        controller.on_tick().unwrap();
+        controller.on_turn(1).unwrap();
+        assert_eq!(controller.remaining, paused_at);
+        assert_eq!(
+            display
+                .updates
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .status
+                .as_deref(),
+            Some("paused")
+        );
+
+        controller.on_release(TAP).unwrap(); // resume
+        controller.on_tick().unwrap();
+        assert!(matches!(controller.state, TimerDisplayState::Running));
+        assert_eq!(controller.remaining, paused_at - 1);
+    }
+
+    #[test]
+    fn medium_press_is_ignored() {
+        let display = TestDisplay::default();
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120).unwrap();
+
+        controller.on_release(TAP).unwrap();
+        controller.on_release(Duration::from_millis(800)).unwrap();
+        assert!(matches!(controller.state, TimerDisplayState::Running));
+    }
+
+    #[test]
+    fn running_countdown_survives_restart() {
+        let store = MemoryStore::default();
+        let mut controller =
+            TimerController::new(TestDisplay::default(), EncoderId::Three, 60, 60, 3600, 600)
+                .unwrap()
+                .with_store(store.clone())
+                .unwrap();
+
+        controller.on_release(TAP).unwrap();
+        controller.on_tick().unwrap();
+        let saved = store.snapshot.lock().unwrap().expect("snapshot saved");
+        assert_eq!(saved.configured, 600);
+        assert_eq!(saved.remaining, 599);
+        drop(controller);
+
+        let display = TestDisplay::default();
+        let restored = TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120)
+            .unwrap()
+            .with_store(store.clone())
+            .unwrap();
+        assert!(matches!(restored.state, TimerDisplayState::Running));
+        assert_eq!(restored.configured, 600);
+        assert!((598..=599).contains(&restored.remaining));
+        let updates = display.updates.lock().unwrap();
+        let status = updates.last().unwrap().status.clone().unwrap();
+        assert!(status.starts_with("run"), "{status}");
+    }
+
+    #[test]
+    fn expired_deadline_restores_finished_once() {
+        let store = MemoryStore::default();
+        *store.snapshot.lock().unwrap() = Some(TimerSnapshot {
+            configured: 300,
+            remaining: 10,
+            deadline: Some(unix_now() - 5),
+        });
+
+        let display = TestDisplay::default();
+        let controller = TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120)
+            .unwrap()
+            .with_store(store.clone())
+            .unwrap();
+        assert!(matches!(controller.state, TimerDisplayState::Finished));
+        assert_eq!(
+            display
+                .updates
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .status
+                .as_deref(),
+            Some("done")
+        );
+        assert!(store.snapshot.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn reset_clears_saved_state() {
+        let store = MemoryStore::default();
+        let mut controller =
+            TimerController::new(TestDisplay::default(), EncoderId::Three, 60, 60, 3600, 120)
+                .unwrap()
+                .with_store(store.clone())
+                .unwrap();
+
+        controller.on_release(TAP).unwrap();
+        controller.on_release(TAP).unwrap(); // pause
+        let paused = store.snapshot.lock().unwrap().expect("paused snapshot");
+        assert_eq!(paused.deadline, None);
+
+        controller.on_release(HOLD).unwrap();
+        assert!(store.snapshot.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn alert_fires_once_per_countdown() {
+        let alert = CountingAlert::default();
+        let mut controller =
+            TimerController::new(TestDisplay::default(), EncoderId::Three, 1, 1, 600, 2)
+                .unwrap()
+                .with_alert(alert.clone());
+
+        controller.on_release(TAP).unwrap();
+        for _ in 0..6 {
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(*alert.fired.lock().unwrap(), vec![2]);
+
+        controller.on_release(TAP).unwrap(); // back to setting
+        controller.on_release(TAP).unwrap(); // start again
+        for _ in 0..3 {
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(*alert.fired.lock().unwrap(), vec![2, 2]);
+    }
+
+    const SHORT_POMODORO: PomodoroConfig = PomodoroConfig {
+        work_secs: 3,
+        break_secs: 1,
+        long_break_secs: 2,
+        cycles: 2,
+    };
+
+    fn pomodoro(display: TestDisplay) -> TimerController<TestDisplay> {
+        TimerController::new(display, EncoderId::Three, 1, 1, 600, 10)
+            .unwrap()
+            .with_mode(TimerMode::Pomodoro, SHORT_POMODORO)
+            .unwrap()
+    }
+
+    fn shown(display: &TestDisplay) -> (String, String, Option<String>, Option<[u8; 3]>) {
+        let updates = display.updates.lock().unwrap();
+        let last = updates.last().unwrap();
+        (
+            last.title.clone(),
+            last.value.clone(),
+            last.status.clone(),
+            match last.progress_style {
+                Some(ProgressStyle::Solid(color)) => Some(color),
+                _ => None,
+            },
+        )
+    }
+
+    #[test]
+    fn pomodoro_rolls_through_work_breaks_and_long_break() {
+        let display = TestDisplay::default();
+        let alert = CountingAlert::default();
+        let mut controller = pomodoro(display.clone()).with_alert(alert.clone());
+        assert_eq!(
+            shown(&display),
+            ("work".into(), "00:03".into(), Some("pomodoro".into()), None)
+        );
+
+        controller.on_release(TAP).unwrap();
+        assert_eq!(
+            shown(&display),
+            (
+                "work".into(),
+                "00:03".into(),
+                Some("work 1/2".into()),
+                Some(WORK_COLOR)
+            )
+        );
+
+        let mut phases = Vec::new();
+        for _ in 0..9 {
+            controller.on_tick().unwrap();
+            let (title, _, status, color) = shown(&display);
+            let step = (title, status.unwrap(), color.unwrap());
+            if phases.last() != Some(&step) {
+                phases.push(step);
+            }
+        }
+        let expected = [
+            ("work", "work 1/2", WORK_COLOR),
+            ("break", "break 1/2", BREAK_COLOR),
+            ("work", "work 2/2", WORK_COLOR),
+            ("break", "long break 2/2", LONG_BREAK_COLOR),
+            ("work", "work 1/2", WORK_COLOR),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(title, status, color)| (title.to_string(), status.to_string(), *color))
+            .collect();
+        assert_eq!(phases, expected);
+        assert_eq!(controller.state, TimerDisplayState::Running);
+        assert_eq!(*alert.fired.lock().unwrap(), vec![3, 1, 3, 2]);
+    }
+
+    #[test]
+    fn pomodoro_reset_mid_phase_returns_to_first_work_cycle() {
+        let display = TestDisplay::default();
+        let mut controller = pomodoro(display.clone());
+
+        controller.on_release(TAP).unwrap();
+        for _ in 0..3 {
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(
+            (controller.phase, controller.cycle),
+            (PomodoroPhase::Break, 1)
+        );
+
+        controller.on_release(TAP).unwrap(); // pause mid-break
+        assert_eq!(shown(&display).2.as_deref(), Some("paused 1/2"));
+        controller.on_release(HOLD).unwrap();
+        assert_eq!(controller.state, TimerDisplayState::Setting);
+        assert_eq!(
+            (controller.phase, controller.cycle),
+            (PomodoroPhase::Work, 1)
+        );
+        assert_eq!(
+            shown(&display),
+            ("work".into(), "00:03".into(), Some("pomodoro".into()), None)
+        );
+
+        controller.on_release(TAP).unwrap();
+        for _ in 0..3 {
+            controller.on_tick().unwrap();
+        }
+        controller.on_release(HOLD).unwrap(); // reset straight after a phase change
+        controller.on_release(TAP).unwrap();
+        assert_eq!(shown(&display).2.as_deref(), Some("work 1/2"));
+        assert_eq!(controller.remaining, 3);
+    }
+
+    #[test]
+    fn turning_an_idle_pomodoro_only_changes_work_length() {
+        let display = TestDisplay::default();
+        let mut controller = pomodoro(display.clone());
+
+        controller.on_turn(2).unwrap();
+        assert_eq!(shown(&display).1, "00:05");
+        controller.on_release(TAP).unwrap();
+        controller.on_turn(5).unwrap(); // ignored while running
+        for _ in 0..5 {
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(controller.phase, PomodoroPhase::Break);
+        assert_eq!(controller.remaining, SHORT_POMODORO.break_secs);
+        assert_eq!(controller.configured, 5);
+    }
+
+    #[test]
+    fn holding_an_idle_timer_switches_mode() {
+        let display = TestDisplay::default();
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 120)
+                .unwrap()
+                .with_mode(TimerMode::Simple, PomodoroConfig::default())
+                .unwrap();
+        assert_eq!(shown(&display).0, "timer");
+
+        controller.on_release(HOLD).unwrap();
+        assert_eq!(controller.mode, TimerMode::Pomodoro);
+        assert_eq!(
+            shown(&display),
+            ("work".into(), "25:00".into(), Some("pomodoro".into()), None)
+        );
+
+        controller.on_release(HOLD).unwrap();
+        assert_eq!(controller.mode, TimerMode::Simple);
+        assert_eq!(shown(&display).2.as_deref(), Some("set"));
+    }
+
+    #[test]
+    fn running_status_shows_deadline_clock_time() {
+        let display = TestDisplay::default();
+        let clock = FixedClock::default();
+        *clock.secs.lock().unwrap() = 14 * 3600 + 10 * 60 + 30;
+        let mut controller =
+            TimerController::new(display.clone(), EncoderId::Three, 60, 60, 3600, 1500)
+                .unwrap()
+                .with_clock(clock.clone());
+        let status = || {
+            display
+                .updates
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .status
+                .clone()
+        };
+
+        controller.on_turn(-1).unwrap();
+        assert_eq!(status().as_deref(), Some("set"));
+        controller.on_release(TAP).unwrap();
+        assert_eq!(status().as_deref(), Some("run · 14:34"));
+        controller.on_tick().unwrap();
+        assert_eq!(status().as_deref(), Some("run · 14:34"));
+
+        controller.on_release(TAP).unwrap(); // pause
+        assert_eq!(status().as_deref(), Some("paused"));
+        *clock.secs.lock().unwrap() = 23 * 3600 + 50 * 60;
+        controller.on_release(TAP).unwrap(); // resume
+        assert_eq!(status().as_deref(), Some("run · 00:13"));
+
+        controller.on_release(HOLD).unwrap();
+        assert_eq!(status().as_deref(), Some("set"));
+    }
 }
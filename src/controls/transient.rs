@@ -0,0 +1,151 @@
+use anyhow::Result;
+
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+
+pub struct TransientStatus<D>
+where
+    D: DisplayPipeline,
+{
+    display: D,
+    encoder: EncoderId,
+    remaining_ticks: Option<u32>,
+}
+
+impl<D> TransientStatus<D>
+where
+    D: DisplayPipeline,
+{
+    pub fn new(display: D, encoder: EncoderId) -> Self {
+        Self {
+            display,
+            encoder,
+            remaining_ticks: None,
+        }
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining_ticks.is_some()
+    }
+
+    // Pushes the base display unless an override is still showing; the base is
+    // re-pushed from the provider once the override expires.
+    pub fn push_base(&self, display: EncoderDisplay) -> Result<()> {
+        if self.is_active() {
+            return Ok(());
+        }
+        self.display.update_encoder(self.encoder, display)
+    }
+
+    pub fn show(&mut self, display: EncoderDisplay, ttl_ticks: u32) -> Result<()> {
+        self.remaining_ticks = Some(ttl_ticks.max(1));
+        self.display.update_encoder(self.encoder, display)
+    }
+
+    pub fn cancel(&mut self) {
+        self.remaining_ticks = None;
+    }
+
+    pub fn on_tick<F>(&mut self, base: F) -> Result<bool>
+    where
+        F: FnOnce() -> EncoderDisplay,
+    {
+        let Some(remaining) = self.remaining_ticks else {
+            return Ok(false);
+        };
+        if remaining > 1 {
+            self.remaining_ticks = Some(remaining - 1);
+            return Ok(false);
+        }
+
+        self.remaining_ticks = None;
+        self.display.update_encoder(self.encoder, base())?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct TestDisplay {
+        inner: Arc<Mutex<Vec<EncoderDisplay>>>,
+    }
+
+    impl DisplayPipeline for TestDisplay {
+        fn update_encoder(&self, _encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.inner.lock().unwrap().push(display);
+            Ok(())
+        }
+    }
+
+    fn base() -> EncoderDisplay {
+        EncoderDisplay::new("volume", "50%")
+    }
+
+    fn values(display: &TestDisplay) -> Vec<String> {
+        display
+            .inner
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    #[test]
+    fn override_expires_exactly_once() {
+        let display = TestDisplay::default();
+        let mut status = TransientStatus::new(display.clone(), EncoderId::One);
+
+        status
+            .show(EncoderDisplay::new("volume", "ERR"), 2)
+            .expect("show");
+        assert!(!status.on_tick(base).expect("tick"));
+        assert!(status.on_tick(base).expect("tick"));
+        assert!(!status.on_tick(base).expect("tick"));
+        assert!(!status.on_tick(base).expect("tick"));
+
+        assert_eq!(values(&display), vec!["ERR", "50%"]);
+        assert!(!status.is_active());
+    }
+
+    #[test]
+    fn later_override_resets_ttl() {
+        let display = TestDisplay::default();
+        let mut status = TransientStatus::new(display.clone(), EncoderId::One);
+
+        status
+            .show(EncoderDisplay::new("volume", "A"), 2)
+            .expect("show");
+        status.on_tick(base).expect("tick");
+        status
+            .show(EncoderDisplay::new("volume", "B"), 2)
+            .expect("show");
+        assert!(!status.on_tick(base).expect("tick"));
+        assert!(status.on_tick(base).expect("tick"));
+
+        assert_eq!(values(&display), vec!["A", "B", "50%"]);
+    }
+
+    #[test]
+    fn base_updates_are_held_while_override_is_showing() {
+        let display = TestDisplay::default();
+        let mut status = TransientStatus::new(display.clone(), EncoderId::One);
+
+        status
+            .show(EncoderDisplay::new("volume", "ERR"), 1)
+            .expect("show");
+        status.push_base(base()).expect("base");
+        assert_eq!(values(&display), vec!["ERR"]);
+
+        status.on_tick(base).expect("tick");
+        status.push_base(base()).expect("base");
+        assert_eq!(values(&display), vec!["ERR", "50%", "50%"]);
+    }
+}
@@ -1,22 +1,92 @@
 use anyhow::Result;
+use tracing::{info, warn};
 
-use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId, ProgressStyle};
 use crate::system::audio::AudioBackend;
+use crate::system::worker::BackendWorker;
 
-use super::EncoderController;
+use super::acceleration::{Clock, SystemClock, TurnAcceleration};
+use super::commands::CommandLine;
+use super::launcher::{ExecSpec, launch_exec};
+use super::transient::TransientStatus;
+use super::{EncoderController, Tickable};
 
-pub struct VolumeController<A, D>
+const MUTE_FEEDBACK_TICKS: u32 = 2;
+const MUTE_FAILED_TICKS: u32 = 4;
+const RESET_FEEDBACK_TICKS: u32 = 2;
+const MAX_VOLUME_PERCENT: f32 = 150.0;
+const MAX_SINK_LABEL_CHARS: usize = 10;
+const OVERDRIVE_COLOR: [u8; 3] = [240, 170, 0];
+const OVERDRIVE_HIGH_COLOR: [u8; 3] = [230, 60, 60];
+const MUTED_COLOR: [u8; 3] = [230, 60, 60];
+
+pub struct VolumeController<A, D, C = SystemClock>
 where
     A: AudioBackend,
     D: DisplayPipeline,
+    C: Clock,
 {
-    audio: A,
-    display: D,
-    encoder: EncoderId,
+    audio: BackendWorker<A, AudioReport>,
+    status: TransientStatus<D>,
     step: i32,
+    acceleration: TurnAcceleration<C>,
     muted: bool,
     volume: f32,
     available: bool,
+    sink_label: Option<String>,
+    default_volume: Option<u8>,
+    hold_command: Option<ExecSpec>,
+    gradient: Option<ProgressStyle>,
+}
+
+enum AudioReport {
+    Read(Result<AudioReading>),
+    MuteToggled(Result<AudioReading>),
+    Reset(Result<AudioReading>),
+    SinkLabel(Result<Option<String>>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AudioReading {
+    available: bool,
+    volume: f32,
+    muted: bool,
+}
+
+fn read_audio<A: AudioBackend>(audio: &A) -> Result<AudioReading> {
+    if !audio.is_available() {
+        return Ok(AudioReading {
+            available: false,
+            volume: 0.0,
+            muted: false,
+        });
+    }
+    let volume = audio.get_volume()?;
+    let muted = audio.is_muted()?;
+    Ok(AudioReading {
+        available: audio.is_available(),
+        volume,
+        muted,
+    })
+}
+
+fn adjust_audio<A: AudioBackend>(audio: &A, unmute: bool, change: i32) -> Result<AudioReading> {
+    if unmute {
+        audio.toggle_mute()?;
+    }
+    if change != 0 {
+        audio.adjust_volume(change)?;
+    }
+    read_audio(audio)
+}
+
+// A reset is meant to be heard, so it unmutes as well.
+fn reset_audio<A: AudioBackend>(audio: &A, percent: u8) -> Result<AudioReading> {
+    if audio.is_muted()? {
+        audio.toggle_mute()?;
+    }
+    audio.set_volume(percent)?;
+    read_audio(audio)
 }
 
 impl<A, D> VolumeController<A, D>
@@ -25,111 +95,347 @@ where
     D: DisplayPipeline,
 {
     pub fn new(audio: A, display: D, encoder: EncoderId, step: i32) -> Result<Self> {
+        Self::with_clock(audio, display, encoder, step, SystemClock)
+    }
+}
+
+impl<A, D, C> VolumeController<A, D, C>
+where
+    A: AudioBackend,
+    D: DisplayPipeline,
+    C: Clock,
+{
+    pub fn with_clock(
+        audio: A,
+        display: D,
+        encoder: EncoderId,
+        step: i32,
+        clock: C,
+    ) -> Result<Self> {
         let available = audio.is_available();
         let mut controller = Self {
-            audio,
-            display,
-            encoder,
+            audio: BackendWorker::inline(audio),
+            status: TransientStatus::new(display, encoder),
             step: step.max(1),
+            acceleration: TurnAcceleration::new(clock),
             muted: false,
             volume: 0.0,
             available,
+            sink_label: None,
+            default_volume: None,
+            hold_command: None,
+            gradient: None,
         };
         if controller.available {
-            controller.refresh_state()?;
+            controller.sync()?;
         } else {
             controller.push_unavailable_display()?;
         }
         Ok(controller)
     }
 
-    fn refresh_state(&mut self) -> Result<()> {
-        self.available = self.audio.is_available();
-        if !self.available {
-            return self.push_unavailable_display();
+    /// Runs pactl on a worker thread from now on; turns and presses then
+    /// show their result on a later tick.
+    pub fn in_background(mut self) -> Result<Self> {
+        self.audio = self.audio.into_threaded("volume-pactl")?;
+        Ok(self)
+    }
+
+    /// The level a long press snaps to; without one, a long press mutes
+    /// like a short one.
+    pub fn with_default_volume(mut self, percent: Option<u8>) -> Self {
+        self.default_volume = percent;
+        self
+    }
+
+    /// Colours the bar up to 100% with a `ProgressStyle::Gradient`.
+    pub fn with_progress_gradient(mut self, gradient: Option<ProgressStyle>) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    /// A command, such as a mixer, that a long press starts instead of
+    /// snapping to the default level.
+    pub fn with_hold_command(mut self, command: Option<&CommandLine>) -> Self {
+        self.hold_command = command.and_then(|command| match command.to_exec() {
+            Ok(exec) => Some(exec),
+            Err(err) => {
+                warn!(error = %err, "ignoring invalid `app.volume_hold_command`");
+                None
+            }
+        });
+        self
+    }
+
+    fn request_reading(&mut self) -> Result<()> {
+        self.audio
+            .submit(|audio: &mut A| AudioReport::Read(read_audio(audio)))
+    }
+
+    fn poll_reports(&mut self) -> Result<()> {
+        for report in self.audio.finished() {
+            match report {
+                AudioReport::Read(Ok(reading)) => self.apply_reading(reading)?,
+                AudioReport::Read(Err(err)) => warn!(error = %err, "failed to read volume"),
+                AudioReport::MuteToggled(Err(err)) => {
+                    warn!(error = %err, "failed to toggle mute");
+                    let mut display =
+                        Self::volume_display(self.volume, self.muted, None, self.gradient.as_ref());
+                    display.status = Some("failed".into());
+                    self.status.show(display, MUTE_FAILED_TICKS)?;
+                }
+                AudioReport::MuteToggled(Ok(reading)) => {
+                    self.apply_reading(reading)?;
+                    if self.available {
+                        self.show_mute_feedback()?;
+                    }
+                }
+                AudioReport::Reset(Err(err)) => warn!(error = %err, "failed to reset volume"),
+                AudioReport::Reset(Ok(reading)) => {
+                    self.apply_reading(reading)?;
+                    if self.available {
+                        let mut display = Self::volume_display(
+                            self.volume,
+                            self.muted,
+                            None,
+                            self.gradient.as_ref(),
+                        );
+                        display.status = Some("reset".into());
+                        self.status.show(display, RESET_FEEDBACK_TICKS)?;
+                    }
+                }
+                AudioReport::SinkLabel(label) => {
+                    self.sink_label = match label {
+                        Ok(label) => label.map(|label| short_sink_label(&label)),
+                        Err(err) => {
+                            warn!(error = %err, "failed to look up the active audio sink");
+                            None
+                        }
+                    };
+                }
+            }
         }
+        Ok(())
+    }
 
-        self.volume = self.audio.get_volume()?;
-        self.muted = self.audio.is_muted()?;
-        self.available = self.audio.is_available();
+    fn apply_reading(&mut self, reading: AudioReading) -> Result<()> {
+        self.available = reading.available;
         if !self.available {
             return self.push_unavailable_display();
         }
+        self.volume = reading.volume;
+        self.muted = reading.muted;
         self.push_display()
     }
 
-    fn push_display(&self) -> Result<()> {
-        let mut display = EncoderDisplay::new("volume", format!("{:>3.0}%", self.volume));
+    fn volume_display(
+        volume: f32,
+        muted: bool,
+        sink_label: Option<&str>,
+        gradient: Option<&ProgressStyle>,
+    ) -> EncoderDisplay {
+        let mut display = EncoderDisplay::new("volume", format!("{:>3.0}%", volume));
 
-        let progress = (self.volume / 100.0).clamp(0.0, 1.25);
-        display.progress = Some(progress.min(1.0));
+        // Past 100% the bar stays full and turns amber, then red.
+        let progress = (volume / 100.0).clamp(0.0, 1.25);
+        display.progress = Some(progress);
+        display.progress_style = if progress > 1.0 {
+            Some(ProgressStyle::Thresholds(vec![
+                (1.2, OVERDRIVE_COLOR),
+                (f32::INFINITY, OVERDRIVE_HIGH_COLOR),
+            ]))
+        } else {
+            gradient.cloned()
+        };
 
-        if self.muted {
-            display.status = Some("muted".into());
+        if muted {
+            display.value_color = Some(MUTED_COLOR);
         }
+        display.status = match (muted, sink_label) {
+            (true, Some(label)) => Some(format!("muted · {label}")),
+            (true, None) => Some("muted".into()),
+            (false, label) => label.map(str::to_string),
+        };
 
-        self.display.update_encoder(self.encoder, display)
+        display
     }
 
-    fn push_unavailable_display(&self) -> Result<()> {
+    fn unavailable_display() -> EncoderDisplay {
         let mut display = EncoderDisplay::new("volume", "N/A");
         display.status = Some("audio disabled".into());
         display.progress = Some(0.0);
-        self.display.update_encoder(self.encoder, display)
+        display
+    }
+
+    fn base_display(
+        available: bool,
+        volume: f32,
+        muted: bool,
+        sink_label: Option<&str>,
+        gradient: Option<&ProgressStyle>,
+    ) -> EncoderDisplay {
+        if available {
+            Self::volume_display(volume, muted, sink_label, gradient)
+        } else {
+            Self::unavailable_display()
+        }
+    }
+
+    fn push_display(&self) -> Result<()> {
+        self.status.push_base(Self::volume_display(
+            self.volume,
+            self.muted,
+            self.sink_label.as_deref(),
+            self.gradient.as_ref(),
+        ))
+    }
+
+    fn push_unavailable_display(&self) -> Result<()> {
+        self.status.push_base(Self::unavailable_display())
+    }
+
+    fn show_mute_feedback(&mut self) -> Result<()> {
+        let mut display =
+            Self::volume_display(self.volume, self.muted, None, self.gradient.as_ref());
+        display.status = Some(if self.muted { "muted" } else { "unmuted" }.into());
+        self.status.show(display, MUTE_FEEDBACK_TICKS)
     }
 
+    // Queued turns are left to run: the reading submitted after them sees
+    // every change, so nothing here needs cancelling.
     pub fn sync(&mut self) -> Result<()> {
-        self.refresh_state()
+        self.audio
+            .submit(|audio: &mut A| AudioReport::SinkLabel(audio.current_sink_label()))?;
+        self.request_reading()?;
+        self.poll_reports()
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
+
+    /// The shortened sink name shown under the volume.
+    pub fn sink_label(&self) -> Option<&str> {
+        self.sink_label.as_deref()
     }
 }
 
-impl<A, D> EncoderController for VolumeController<A, D>
+impl<A, D, C> EncoderController for VolumeController<A, D, C>
 where
     A: AudioBackend,
     D: DisplayPipeline,
+    C: Clock,
 {
+    // The level is shown straight away from the last reading plus the
+    // change; the worker's reading afterwards corrects it.
     fn on_turn(&mut self, delta: i32) -> Result<()> {
-        self.available = self.audio.is_available();
+        self.poll_reports()?;
         if !self.available {
-            return self.push_unavailable_display();
+            // Lets the backend retry once its backoff has passed.
+            if !self.audio.is_busy() {
+                self.request_reading()?;
+            }
+            return self.poll_reports();
         }
 
         if delta == 0 {
             return Ok(());
         }
 
-        // Unmute on interaction if currently muted
-        if self.muted {
-            self.muted = self.audio.toggle_mute()?;
-        }
+        self.status.cancel();
 
-        let change = delta * self.step;
-        self.audio.adjust_volume(change)?;
-        self.refresh_state()
+        // Clamp here so an accelerated step stops at the limits rather than
+        // relying on the backend to.
+        let change = delta * self.step * self.acceleration.factor(delta);
+        let upper = (MAX_VOLUME_PERCENT - self.volume).floor().max(0.0) as i32;
+        let lower = (-self.volume).ceil().min(0.0) as i32;
+        let change = change.clamp(lower, upper);
+
+        // Unmute on interaction if currently muted
+        let unmute = self.muted;
+        self.audio
+            .submit(move |audio: &mut A| AudioReport::Read(adjust_audio(audio, unmute, change)))?;
+        self.muted = false;
+        self.volume = (self.volume + change as f32).clamp(0.0, MAX_VOLUME_PERCENT);
+        self.push_display()?;
+        self.poll_reports()
     }
 
     fn on_press(&mut self) -> Result<()> {
-        self.available = self.audio.is_available();
-        if !self.available {
-            return self.push_unavailable_display();
-        }
+        self.poll_reports()?;
+        self.audio.submit(|audio: &mut A| {
+            if !audio.is_available() {
+                return AudioReport::Read(read_audio(audio));
+            }
+            AudioReport::MuteToggled(audio.toggle_mute().and_then(|_| read_audio(audio)))
+        })?;
+        self.poll_reports()
+    }
 
-        self.audio.toggle_mute()?;
-        self.refresh_state()
+    fn has_long_press(&self) -> bool {
+        self.default_volume.is_some() || self.hold_command.is_some()
     }
 
-    fn on_release(&mut self) -> Result<()> {
+    fn on_long_press(&mut self) -> Result<()> {
+        if let Some(exec) = &self.hold_command {
+            match launch_exec(exec, None, false) {
+                Ok(()) => info!(command = %exec.program, "ran volume hold command"),
+                Err(err) => warn!(error = %err, "failed to run volume hold command"),
+            }
+            return Ok(());
+        }
+        let Some(percent) = self.default_volume else {
+            return self.on_press();
+        };
+        self.poll_reports()?;
+        self.status.cancel();
+        self.audio.submit(move |audio: &mut A| {
+            if !audio.is_available() {
+                return AudioReport::Read(read_audio(audio));
+            }
+            AudioReport::Reset(reset_audio(audio, percent))
+        })?;
+        self.poll_reports()
+    }
+}
+
+impl<A, D, C> Tickable for VolumeController<A, D, C>
+where
+    A: AudioBackend,
+    D: DisplayPipeline,
+    C: Clock,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        self.poll_reports()?;
+        let (available, volume, muted) = (self.available, self.volume, self.muted);
+        let (sink_label, gradient) = (self.sink_label.as_deref(), self.gradient.as_ref());
+        self.status
+            .on_tick(|| Self::base_display(available, volume, muted, sink_label, gradient))?;
         Ok(())
     }
 }
 
+// PulseAudio descriptions read "<port> - <device>"; the device part is what
+// distinguishes outputs on a narrow LCD segment.
+pub(super) fn short_sink_label(description: &str) -> String {
+    let label = description
+        .rsplit(" - ")
+        .next()
+        .unwrap_or(description)
+        .trim();
+    label.chars().take(MAX_SINK_LABEL_CHARS).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::controls::Tickable;
+    use crate::controls::acceleration::tests::FakeClock;
     use crate::hardware::DisplayPipeline;
     use crate::system::audio::tests::{MockAudioBackend, MockAudioState};
     use anyhow::Result;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     #[derive(Clone, Default)]
     struct TestDisplay {
@@ -161,6 +467,108 @@ mod tests {
         assert!(last.value.contains('%'));
     }
 
+    #[test]
+    fn long_press_resets_to_the_default_volume_and_unmutes() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 90.0,
+                muted: true,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 2)
+                .expect("init")
+                .with_default_volume(Some(40));
+        assert!(controller.has_long_press());
+
+        controller.on_long_press().expect("long press");
+
+        let state = audio_backend.inner.lock().unwrap();
+        assert_eq!(state.history, vec!["toggle_mute", "set:40"]);
+        assert!(!state.muted);
+        let events = display.inner.lock().unwrap();
+        let (_, last) = events.last().unwrap();
+        assert_eq!(last.value.trim(), "40%");
+        assert_eq!(last.status.as_deref(), Some("reset"));
+        assert_eq!(last.value_color, None);
+    }
+
+    #[test]
+    fn hold_command_replaces_the_reset_and_leaves_mute_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("mixer-opened");
+        let audio_backend = MockAudioBackend::default();
+        let command = CommandLine::Argv(vec!["touch".into(), marker.display().to_string()]);
+        let mut controller = VolumeController::new(
+            audio_backend.clone(),
+            TestDisplay::default(),
+            EncoderId::One,
+            2,
+        )
+        .expect("init")
+        .with_default_volume(Some(40))
+        .with_hold_command(Some(&command));
+        assert!(controller.has_long_press());
+
+        controller.on_long_press().expect("long press");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !marker.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(marker.exists());
+        let state = audio_backend.inner.lock().unwrap();
+        assert!(state.history.is_empty(), "{:?}", state.history);
+        assert!(!state.muted);
+    }
+
+    #[test]
+    fn gradient_colours_the_bar_until_overdrive() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 90.0,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let gradient = ProgressStyle::Gradient {
+            from: [30, 90, 160],
+            to: [80, 200, 255],
+        };
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 2)
+                .expect("init")
+                .with_progress_gradient(Some(gradient.clone()));
+        let last_style = || {
+            let events = display.inner.lock().unwrap();
+            events.last().unwrap().1.progress_style.clone()
+        };
+
+        controller.sync().expect("sync");
+        assert_eq!(last_style(), Some(gradient));
+
+        audio_backend.inner.lock().unwrap().volume = 130.0;
+        controller.sync().expect("sync");
+        assert!(matches!(last_style(), Some(ProgressStyle::Thresholds(_))));
+    }
+
+    #[test]
+    fn long_press_without_a_default_mutes() {
+        let audio_backend = MockAudioBackend::default();
+        let mut controller = VolumeController::new(
+            audio_backend.clone(),
+            TestDisplay::default(),
+            EncoderId::One,
+            2,
+        )
+        .expect("init");
+        assert!(!controller.has_long_press());
+
+        controller.on_long_press().expect("long press");
+        assert!(audio_backend.inner.lock().unwrap().muted);
+    }
+
     #[test]
     fn pressing_toggles_mute_status() {
         let audio_backend = MockAudioBackend {
@@ -178,6 +586,63 @@ mod tests {
         let events = display.inner.lock().unwrap();
         let (_, last) = events.last().unwrap();
         assert!(matches!(last.status.as_deref(), Some("muted")));
+        assert_eq!(last.value_color, Some(MUTED_COLOR));
+    }
+
+    #[test]
+    fn unmute_feedback_reverts_after_ttl() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                muted: true,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 2)
+                .expect("init");
+
+        controller.on_press().expect("press");
+        {
+            let events = display.inner.lock().unwrap();
+            let (_, last) = events.last().unwrap();
+            assert_eq!(last.status.as_deref(), Some("unmuted"));
+        }
+
+        for _ in 0..MUTE_FEEDBACK_TICKS {
+            controller.on_tick().expect("tick");
+        }
+        let events = display.inner.lock().unwrap();
+        let (_, last) = events.last().unwrap();
+        assert!(last.status.is_none());
+    }
+
+    #[test]
+    fn failed_mute_toggle_shows_failed_until_ttl() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                fail_mute: true,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 2)
+                .expect("init");
+
+        controller.on_press().expect("press");
+        for _ in 0..MUTE_FAILED_TICKS {
+            controller.on_tick().expect("tick");
+        }
+        let statuses: Vec<Option<String>> = display
+            .inner
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, display)| display.status.clone())
+            .collect();
+        assert_eq!(statuses, vec![None, Some("failed".into()), None]);
+        assert!(!audio_backend.inner.lock().unwrap().muted);
     }
 
     #[test]
@@ -203,4 +668,165 @@ mod tests {
         let (_, last) = events.last().unwrap();
         assert_eq!(last.value.trim(), "12%");
     }
+
+    #[test]
+    fn status_line_names_the_active_sink() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                sink_label: Some("Digital Output - A50".into()),
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 5)
+                .expect("init");
+        assert_eq!(
+            display
+                .inner
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .1
+                .status
+                .as_deref(),
+            Some("A50")
+        );
+
+        {
+            let mut state = audio_backend.inner.lock().unwrap();
+            state.muted = true;
+            state.sink_label = Some("Built-in Audio Analog Stereo".into());
+        }
+        controller.sync().expect("sync");
+        assert_eq!(
+            display
+                .inner
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .1
+                .status
+                .as_deref(),
+            Some("muted · Built-in A")
+        );
+    }
+
+    #[test]
+    fn background_turns_do_not_wait_for_a_stuck_backend() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 40.0,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 5)
+                .expect("init")
+                .in_background()
+                .expect("worker");
+        let last_value = || {
+            display
+                .inner
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .1
+                .value
+                .clone()
+        };
+
+        // Holding the mock's state blocks every backend call on the worker.
+        let stuck = audio_backend.inner.lock().unwrap();
+        controller.on_turn(1).expect("turn");
+        controller.on_tick().expect("tick");
+        assert_eq!(last_value().trim(), "45%");
+        assert!(controller.audio.is_busy());
+        drop(stuck);
+
+        for _ in 0..200 {
+            controller.on_tick().expect("tick");
+            if !controller.audio.is_busy() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(audio_backend.inner.lock().unwrap().history, ["adjust:5"]);
+        assert_eq!(last_value().trim(), "45%");
+    }
+
+    #[test]
+    fn sync_keeps_turns_still_queued_on_the_worker() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 40.0,
+                ..Default::default()
+            })),
+        };
+        let display = TestDisplay::default();
+        let mut controller =
+            VolumeController::new(audio_backend.clone(), display.clone(), EncoderId::One, 5)
+                .expect("init")
+                .in_background()
+                .expect("worker");
+
+        let stuck = audio_backend.inner.lock().unwrap();
+        for _ in 0..3 {
+            controller.on_turn(1).expect("turn");
+        }
+        controller.sync().expect("sync");
+        drop(stuck);
+
+        for _ in 0..200 {
+            controller.on_tick().expect("tick");
+            if !controller.audio.is_busy() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let state = audio_backend.inner.lock().unwrap();
+        assert_eq!(state.history, ["adjust:5", "adjust:5", "adjust:5"]);
+        assert_eq!(state.volume, 55.0);
+        let events = display.inner.lock().unwrap();
+        assert_eq!(events.last().unwrap().1.value.trim(), "55%");
+    }
+
+    #[test]
+    fn fast_turns_accelerate_without_passing_the_limit() {
+        let audio_backend = MockAudioBackend {
+            inner: Arc::new(Mutex::new(MockAudioState {
+                volume: 120.0,
+                ..Default::default()
+            })),
+        };
+        let clock = FakeClock::default();
+        let mut controller = VolumeController::with_clock(
+            audio_backend.clone(),
+            TestDisplay::default(),
+            EncoderId::One,
+            3,
+            clock.clone(),
+        )
+        .expect("init");
+
+        for _ in 0..6 {
+            clock.advance(Duration::from_millis(20));
+            controller.on_turn(1).expect("turn");
+        }
+        clock.advance(Duration::from_millis(20));
+        controller.on_turn(1).expect("turn");
+
+        let state = audio_backend.inner.lock().unwrap();
+        assert_eq!(state.volume, 150.0);
+        assert_eq!(
+            state.history,
+            [
+                "adjust:3", "adjust:3", "adjust:3", "adjust:3", "adjust:3", "adjust:9", "adjust:6"
+            ]
+        );
+    }
 }
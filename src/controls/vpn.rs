@@ -0,0 +1,431 @@
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+use anyhow::{Result, anyhow};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::hardware::{ButtonImage, DisplayPipeline};
+use crate::system::vpn::{SystemVpnBackend, VpnBackend};
+
+use super::audio_toggle::{
+    ACTIVE_TINT, AVAILABLE_TINT, DEGRADED_TINT, IconPaths, MaterialIcon, UNAVAILABLE_TINT,
+    load_material_icon,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VpnConfig {
+    #[serde(alias = "index", alias = "button")]
+    pub button_index: u8,
+    /// NetworkManager connection to toggle with `nmcli`.
+    #[serde(default)]
+    pub connection: Option<String>,
+    /// Interface to watch when `nmcli` is unavailable; defaults to the
+    /// connection name.
+    #[serde(default)]
+    pub interface: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VpnState {
+    Connected,
+    Disconnected,
+    Connecting,
+    Failed,
+    Unavailable,
+}
+
+pub struct VpnController<H>
+where
+    H: DisplayPipeline,
+{
+    worker: VpnWorker,
+    hardware: H,
+    button_index: u8,
+    name: String,
+    available: bool,
+    icons: VpnIcons,
+    state: Option<VpnState>,
+    connected: Option<bool>,
+    toggling: bool,
+    refreshing: bool,
+    failed: bool,
+}
+
+struct VpnIcons {
+    connected: ButtonImage,
+    disconnected: ButtonImage,
+    connecting: ButtonImage,
+    failed: ButtonImage,
+    unavailable: ButtonImage,
+}
+
+impl<H> VpnController<H>
+where
+    H: DisplayPipeline,
+{
+    fn new<B>(
+        config: &VpnConfig,
+        name: String,
+        backend: B,
+        hardware: H,
+        icon_paths: &IconPaths,
+    ) -> Result<Self>
+    where
+        B: VpnBackend + 'static,
+    {
+        let button_index = config.button_index;
+        let key = load_material_icon(MaterialIcon::VpnKey, icon_paths)?;
+        let mut connecting = tinted(&key, button_index, "connecting", AVAILABLE_TINT);
+        connecting.label = Some("connecting".to_string());
+        let icons = VpnIcons {
+            connected: tinted(&key, button_index, "connected", ACTIVE_TINT),
+            disconnected: tinted(&key, button_index, "disconnected", AVAILABLE_TINT),
+            connecting,
+            failed: tinted(&key, button_index, "error", DEGRADED_TINT),
+            unavailable: tinted(&key, button_index, "unavailable", UNAVAILABLE_TINT),
+        };
+
+        let available = backend.is_available();
+        let mut controller = Self {
+            worker: VpnWorker::spawn(backend),
+            hardware,
+            button_index,
+            name,
+            available,
+            icons,
+            state: None,
+            connected: None,
+            toggling: false,
+            refreshing: false,
+            failed: false,
+        };
+        controller.request_refresh()?;
+        controller.update_display()?;
+        Ok(controller)
+    }
+
+    pub fn button_indices(&self) -> impl Iterator<Item = u8> + '_ {
+        std::iter::once(self.button_index)
+    }
+
+    pub fn on_button_pressed(&mut self, button_index: u8) -> Result<bool> {
+        if button_index != self.button_index {
+            return Ok(false);
+        }
+        self.poll_reports();
+        if !self.available || self.toggling {
+            return Ok(true);
+        }
+
+        let target = !self.connected.unwrap_or(false);
+        self.send(WorkerRequest::Toggle(target))?;
+        self.toggling = true;
+        self.failed = false;
+        self.update_display()?;
+        Ok(true)
+    }
+
+    pub fn on_button_released(&mut self, button_index: u8) -> Result<bool> {
+        Ok(button_index == self.button_index)
+    }
+
+    pub fn on_tick(&mut self) -> Result<()> {
+        self.poll_reports();
+        if !self.toggling {
+            self.request_refresh()?;
+        }
+        self.update_display()
+    }
+
+    fn request_refresh(&mut self) -> Result<()> {
+        if !self.available || self.refreshing {
+            return Ok(());
+        }
+        self.send(WorkerRequest::Refresh)?;
+        self.refreshing = true;
+        Ok(())
+    }
+
+    fn send(&self, request: WorkerRequest) -> Result<()> {
+        self.worker
+            .requests
+            .send(request)
+            .map_err(|_| anyhow!("vpn worker disconnected"))
+    }
+
+    // A failed toggle stays amber until the connection is seen to change or
+    // the key is pressed again; a refresh alone would hide it within a tick.
+    fn poll_reports(&mut self) {
+        for report in self.worker.reports.try_iter() {
+            match report {
+                WorkerReport::Toggled(target, Ok(())) => {
+                    info!(connection = %self.name, connected = target, "toggled vpn");
+                    self.toggling = false;
+                    self.connected = Some(target);
+                }
+                WorkerReport::Toggled(target, Err(err)) => {
+                    warn!(error = %err, connection = %self.name, "failed to toggle vpn");
+                    self.toggling = false;
+                    self.failed = true;
+                    self.connected = Some(!target);
+                    notify_toggle_failure(&self.name, target, &err);
+                }
+                WorkerReport::Observed(Ok(connected)) => {
+                    self.refreshing = false;
+                    if !self.failed || self.connected != Some(connected) {
+                        self.failed = false;
+                        self.connected = Some(connected);
+                    }
+                }
+                WorkerReport::Observed(Err(err)) => {
+                    warn!(error = %err, connection = %self.name, "failed to query vpn state");
+                    self.refreshing = false;
+                    self.failed = true;
+                    self.connected = None;
+                }
+            }
+        }
+    }
+
+    fn update_display(&mut self) -> Result<()> {
+        let state = if !self.available {
+            VpnState::Unavailable
+        } else if self.toggling {
+            VpnState::Connecting
+        } else if self.failed {
+            VpnState::Failed
+        } else if self.connected == Some(true) {
+            VpnState::Connected
+        } else {
+            VpnState::Disconnected
+        };
+        if self.state == Some(state) {
+            return Ok(());
+        }
+        self.state = Some(state);
+
+        let icon = match state {
+            VpnState::Connected => &self.icons.connected,
+            VpnState::Disconnected => &self.icons.disconnected,
+            VpnState::Connecting => &self.icons.connecting,
+            VpnState::Failed => &self.icons.failed,
+            VpnState::Unavailable => &self.icons.unavailable,
+        };
+        self.hardware
+            .update_button_icon(self.button_index, Some(icon.clone()))
+    }
+
+    pub fn with_default_backend(
+        config: VpnConfig,
+        config_path: Option<&Path>,
+        hardware: H,
+    ) -> Result<Self> {
+        let backend =
+            SystemVpnBackend::detect(config.connection.as_deref(), config.interface.as_deref())
+                .ok_or_else(|| anyhow!("`vpn` needs a `connection` or an `interface`"))?;
+        let name = match &backend {
+            SystemVpnBackend::NetworkManager { connection } => connection.clone(),
+            SystemVpnBackend::Interface { name, .. } => name.clone(),
+        };
+        let icon_paths = IconPaths::new(config_path);
+        VpnController::new(&config, name, backend, hardware, &icon_paths)
+    }
+}
+
+enum WorkerRequest {
+    Refresh,
+    Toggle(bool),
+}
+
+enum WorkerReport {
+    Toggled(bool, Result<()>),
+    Observed(Result<bool>),
+}
+
+struct VpnWorker {
+    requests: Sender<WorkerRequest>,
+    reports: Receiver<WorkerReport>,
+}
+
+impl VpnWorker {
+    // nmcli can take seconds to bring a connection up, so both toggles and
+    // status queries run here. Every toggle is followed by a fresh status read.
+    fn spawn<B>(backend: B) -> Self
+    where
+        B: VpnBackend + 'static,
+    {
+        let (requests, request_rx) = unbounded::<WorkerRequest>();
+        let (report_tx, reports) = unbounded();
+        thread::spawn(move || {
+            while let Ok(first) = request_rx.recv() {
+                let mut toggle = None;
+                for request in std::iter::once(first).chain(request_rx.try_iter()) {
+                    if let WorkerRequest::Toggle(target) = request {
+                        toggle = Some(target);
+                    }
+                }
+
+                if let Some(target) = toggle {
+                    let result = backend.set_connected(target);
+                    if report_tx
+                        .send(WorkerReport::Toggled(target, result))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                if report_tx
+                    .send(WorkerReport::Observed(backend.is_connected()))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+        Self { requests, reports }
+    }
+}
+
+fn tinted(base: &ButtonImage, button_index: u8, suffix: &str, tint: [u8; 3]) -> ButtonImage {
+    ButtonImage {
+        id: format!("vpn-{button_index}-{suffix}"),
+        image: base.image.clone(),
+        tint: Some(tint),
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
+    }
+}
+
+fn notify_toggle_failure(name: &str, target: bool, error: &anyhow::Error) {
+    let title = "Stream Deck VPN";
+    let verb = if target { "connect" } else { "disconnect" };
+    let body = format!("Failed to {verb} {name}:\n{error}");
+    match Command::new("notify-send").arg(title).arg(body).status() {
+        Ok(status) => {
+            if !status.success() {
+                warn!(code = ?status.code(), "notify-send exited with failure status");
+            }
+        }
+        Err(err) => {
+            warn!(error = %err, "failed to send vpn failure notification");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use crate::system::vpn::tests::MockVpnBackend;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct RecordingHardware {
+        icons: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl DisplayPipeline for RecordingHardware {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_button_icon(&self, _index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.icons.lock().unwrap().push(icon.map(|icon| icon.id));
+            Ok(())
+        }
+    }
+
+    fn controller(
+        backend: MockVpnBackend,
+        hardware: RecordingHardware,
+    ) -> VpnController<RecordingHardware> {
+        let config = VpnConfig {
+            button_index: 4,
+            connection: Some("wg0".to_string()),
+            interface: None,
+        };
+        VpnController::new(
+            &config,
+            "wg0".to_string(),
+            backend,
+            hardware,
+            &IconPaths::new(None),
+        )
+        .expect("controller")
+    }
+
+    fn last_icon(hardware: &RecordingHardware) -> Option<String> {
+        hardware.icons.lock().unwrap().last().unwrap().clone()
+    }
+
+    fn tick_until(
+        controller: &mut VpnController<RecordingHardware>,
+        hardware: &RecordingHardware,
+        icon: &str,
+    ) {
+        for _ in 0..200 {
+            controller.on_tick().unwrap();
+            if last_icon(hardware).as_deref() == Some(icon) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("icon never became {icon}: {:?}", last_icon(hardware));
+    }
+
+    #[test]
+    fn press_connects_in_the_background_and_tick_follows_external_changes() {
+        let backend = MockVpnBackend {
+            available: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("vpn-4-disconnected"));
+
+        assert!(!controller.on_button_pressed(1).unwrap());
+        assert!(controller.on_button_pressed(4).unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("vpn-4-connecting"));
+        tick_until(&mut controller, &hardware, "vpn-4-connected");
+        assert!(*backend.connected.lock().unwrap());
+
+        *backend.connected.lock().unwrap() = false;
+        tick_until(&mut controller, &hardware, "vpn-4-disconnected");
+    }
+
+    #[test]
+    fn failed_toggle_stays_amber_until_the_state_changes() {
+        let backend = MockVpnBackend {
+            available: true,
+            fail_toggle: true,
+            ..Default::default()
+        };
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(backend.clone(), hardware.clone());
+
+        assert!(controller.on_button_pressed(4).unwrap());
+        tick_until(&mut controller, &hardware, "vpn-4-error");
+        for _ in 0..5 {
+            thread::sleep(Duration::from_millis(5));
+            controller.on_tick().unwrap();
+        }
+        assert_eq!(last_icon(&hardware).as_deref(), Some("vpn-4-error"));
+
+        *backend.connected.lock().unwrap() = true;
+        tick_until(&mut controller, &hardware, "vpn-4-connected");
+    }
+
+    #[test]
+    fn unavailable_backend_shows_disabled_icon() {
+        let hardware = RecordingHardware::default();
+        let mut controller = controller(MockVpnBackend::default(), hardware.clone());
+        assert!(controller.on_button_pressed(4).unwrap());
+        assert_eq!(last_icon(&hardware).as_deref(), Some("vpn-4-unavailable"));
+    }
+}
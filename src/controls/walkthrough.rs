@@ -0,0 +1,450 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+use tracing::{info, warn};
+
+use crate::config;
+use crate::hardware::{ButtonImage, DisplayPipeline, EncoderDisplay, EncoderId, HardwareEvent};
+use crate::init_config;
+
+use super::Tickable;
+
+const WRITE_BUTTON: u8 = 0;
+const WRITE_HOLD_TICKS: u32 = 2;
+const TURN_PROGRESS_STEP: f32 = 0.1;
+const LIT_COLOR: [u8; 4] = [0, 200, 150, 255];
+const WRITE_COLOR: [u8; 4] = [230, 170, 90, 255];
+const DONE_COLOR: [u8; 3] = [0, 200, 150];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkthroughStep {
+    Encoders,
+    Buttons,
+    Finish,
+    Done,
+}
+
+pub struct WalkthroughController<D>
+where
+    D: DisplayPipeline,
+{
+    display: D,
+    step: WalkthroughStep,
+    config_path: PathBuf,
+    button_count: u8,
+    turns: [f32; 4],
+    turned: [bool; 4],
+    pressed: BTreeSet<u8>,
+    held_ticks: Option<u32>,
+    starter_written: bool,
+}
+
+impl<D> WalkthroughController<D>
+where
+    D: DisplayPipeline,
+{
+    /// `button_count` is the number of keys to press in the second step.
+    pub fn new(display: D, config_path: PathBuf, button_count: u8) -> Result<Self> {
+        info!(
+            path = %config_path.display(),
+            "no configuration found; starting on-device walkthrough"
+        );
+        let controller = Self {
+            display,
+            step: WalkthroughStep::Encoders,
+            config_path,
+            button_count,
+            turns: [0.0; 4],
+            turned: [false; 4],
+            pressed: BTreeSet::new(),
+            held_ticks: None,
+            starter_written: false,
+        };
+        controller.push_step()?;
+        Ok(controller)
+    }
+
+    #[cfg(test)]
+    fn step(&self) -> WalkthroughStep {
+        self.step
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.step, WalkthroughStep::Done)
+    }
+
+    pub fn handle_event(&mut self, event: &HardwareEvent) -> Result<()> {
+        match (self.step, event) {
+            (WalkthroughStep::Encoders, HardwareEvent::EncoderTurned { encoder, delta }) => {
+                self.on_encoder_turned(*encoder, *delta)
+            }
+            (WalkthroughStep::Encoders, HardwareEvent::EncoderPressed { .. }) => {
+                self.advance(WalkthroughStep::Buttons)
+            }
+            (WalkthroughStep::Buttons, HardwareEvent::ButtonPressed(index)) => {
+                self.on_button_pressed(*index)
+            }
+            (WalkthroughStep::Buttons, HardwareEvent::EncoderPressed { .. }) => {
+                self.advance(WalkthroughStep::Finish)
+            }
+            (WalkthroughStep::Finish, HardwareEvent::ButtonPressed(index))
+                if *index == WRITE_BUTTON =>
+            {
+                self.held_ticks = Some(0);
+                Ok(())
+            }
+            (WalkthroughStep::Finish, HardwareEvent::ButtonReleased(index))
+                if *index == WRITE_BUTTON =>
+            {
+                self.held_ticks = None;
+                Ok(())
+            }
+            (WalkthroughStep::Finish, HardwareEvent::EncoderPressed { .. }) => {
+                self.advance(WalkthroughStep::Done)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn on_encoder_turned(&mut self, encoder: EncoderId, delta: i32) -> Result<()> {
+        let index = encoder.index();
+        self.turned[index] = true;
+        self.turns[index] = (self.turns[index] + delta as f32 * TURN_PROGRESS_STEP).clamp(0.0, 1.0);
+
+        if self.turned.iter().all(|turned| *turned) {
+            return self.advance(WalkthroughStep::Buttons);
+        }
+        self.push_encoder_segment(encoder)
+    }
+
+    fn on_button_pressed(&mut self, index: u8) -> Result<()> {
+        if !self.pressed.insert(index) {
+            return Ok(());
+        }
+        self.display
+            .update_button_icon(index, Some(solid_button(index, "lit", LIT_COLOR)))?;
+
+        if self.pressed.len() >= usize::from(self.button_count) {
+            return self.advance(WalkthroughStep::Finish);
+        }
+        self.push_step()
+    }
+
+    fn advance(&mut self, step: WalkthroughStep) -> Result<()> {
+        self.step = step;
+        self.held_ticks = None;
+        match step {
+            WalkthroughStep::Finish => {
+                self.clear_buttons()?;
+                self.display.update_button_icon(
+                    WRITE_BUTTON,
+                    Some(solid_button(WRITE_BUTTON, "write", WRITE_COLOR)),
+                )?;
+                info!(
+                    path = %self.config_path.display(),
+                    "create this file to configure streamdeck_ctrl, or hold button {} to write a starter config",
+                    WRITE_BUTTON + 1
+                );
+            }
+            WalkthroughStep::Done => {
+                self.clear_buttons()?;
+                self.display.update_button_icon(WRITE_BUTTON, None)?;
+                info!("walkthrough finished; handing control back");
+                return Ok(());
+            }
+            WalkthroughStep::Encoders | WalkthroughStep::Buttons => {}
+        }
+        self.push_step()
+    }
+
+    fn clear_buttons(&mut self) -> Result<()> {
+        for index in std::mem::take(&mut self.pressed) {
+            self.display.update_button_icon(index, None)?;
+        }
+        Ok(())
+    }
+
+    fn write_starter(&mut self) -> Result<()> {
+        let sinks = init_config::local_sinks().unwrap_or_else(|err| {
+            warn!(error = %err, "leaving the audio toggle out of the starter configuration");
+            Vec::new()
+        });
+        let entries = init_config::desktop_entries();
+        match config::write_starter_config(&self.config_path, &sinks, &entries, false) {
+            Ok(()) => {
+                info!(path = %self.config_path.display(), "wrote starter configuration");
+                self.starter_written = true;
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to write starter configuration");
+            }
+        }
+        self.push_step()
+    }
+
+    fn push_step(&self) -> Result<()> {
+        match self.step {
+            WalkthroughStep::Encoders => {
+                for encoder in EncoderId::all() {
+                    self.push_encoder_segment(encoder)?;
+                }
+                Ok(())
+            }
+            WalkthroughStep::Buttons => {
+                let pressed = format!("{} OF {}", self.pressed.len(), self.button_count);
+                self.push_all([
+                    EncoderDisplay::new("step 2", "PRESS").with_status("all buttons"),
+                    EncoderDisplay::new("pressed", pressed)
+                        .with_progress(self.pressed.len() as f32 / f32::from(self.button_count)),
+                    EncoderDisplay::new("or skip", "PUSH").with_status("an encoder"),
+                    EncoderDisplay::new("setup", ""),
+                ])
+            }
+            WalkthroughStep::Finish => {
+                let file_name = self
+                    .config_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "config".to_string());
+                let mut write = if self.starter_written {
+                    EncoderDisplay::new("starter", "SAVED").with_status("restart to use")
+                } else {
+                    EncoderDisplay::new("hold button 1", "WRITE").with_status("starter file")
+                };
+                if self.starter_written {
+                    write.value_color = Some(DONE_COLOR);
+                }
+                self.push_all([
+                    EncoderDisplay::new("setup", "DONE").with_status("no config found"),
+                    EncoderDisplay::new("create", "CONFIG").with_status(file_name),
+                    write,
+                    EncoderDisplay::new("exit", "PUSH").with_status("an encoder"),
+                ])
+            }
+            WalkthroughStep::Done => Ok(()),
+        }
+    }
+
+    fn push_encoder_segment(&self, encoder: EncoderId) -> Result<()> {
+        let index = encoder.index();
+        let display = if self.turned[index] {
+            let mut display = EncoderDisplay::new("step 1", "NICE")
+                .with_status("turned")
+                .with_progress(self.turns[index]);
+            display.value_color = Some(DONE_COLOR);
+            display
+        } else {
+            EncoderDisplay::new("step 1", "TURN ME")
+                .with_status(format!("encoder {}", index + 1))
+                .with_progress(0.0)
+        };
+        self.display.update_encoder(encoder, display)
+    }
+
+    fn push_all(&self, displays: [EncoderDisplay; 4]) -> Result<()> {
+        for (encoder, display) in EncoderId::all().into_iter().zip(displays) {
+            self.display.update_encoder(encoder, display)?;
+        }
+        Ok(())
+    }
+}
+
+impl<D> Tickable for WalkthroughController<D>
+where
+    D: DisplayPipeline,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        if self.step != WalkthroughStep::Finish || self.starter_written {
+            return Ok(());
+        }
+
+        let Some(ticks) = self.held_ticks.as_mut() else {
+            return Ok(());
+        };
+        *ticks += 1;
+        if *ticks >= WRITE_HOLD_TICKS {
+            self.held_ticks = None;
+            return self.write_starter();
+        }
+        Ok(())
+    }
+}
+
+fn solid_button(index: u8, state: &str, color: [u8; 4]) -> ButtonImage {
+    ButtonImage {
+        id: format!("walkthrough-{index}-{state}"),
+        image: Arc::new(RgbaImage::from_pixel(72, 72, Rgba(color))),
+        tint: None,
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const PLUS_KEYS: u8 = 8;
+
+    #[derive(Default)]
+    struct RecordingDisplay {
+        encoders: Mutex<Vec<(EncoderId, EncoderDisplay)>>,
+        buttons: Mutex<Vec<(u8, Option<String>)>>,
+    }
+
+    impl DisplayPipeline for Arc<RecordingDisplay> {
+        fn update_encoder(&self, encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.encoders.lock().unwrap().push((encoder, display));
+            Ok(())
+        }
+
+        fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+            self.buttons
+                .lock()
+                .unwrap()
+                .push((index, icon.map(|icon| icon.id)));
+            Ok(())
+        }
+    }
+
+    fn turn(encoder: EncoderId) -> HardwareEvent {
+        HardwareEvent::EncoderTurned { encoder, delta: 1 }
+    }
+
+    fn press_encoder() -> HardwareEvent {
+        HardwareEvent::EncoderPressed {
+            encoder: EncoderId::One,
+        }
+    }
+
+    #[test]
+    fn turning_every_encoder_advances_to_buttons() {
+        let display = Arc::new(RecordingDisplay::default());
+        let dir = tempfile::tempdir().unwrap();
+        let mut controller = WalkthroughController::new(
+            Arc::clone(&display),
+            dir.path().join("stream-deck.json"),
+            PLUS_KEYS,
+        )
+        .unwrap();
+
+        {
+            let encoders = display.encoders.lock().unwrap();
+            assert_eq!(encoders.len(), 4);
+            assert!(encoders.iter().all(|(_, d)| d.value == "TURN ME"));
+        }
+
+        controller.handle_event(&turn(EncoderId::Two)).unwrap();
+        {
+            let encoders = display.encoders.lock().unwrap();
+            let (encoder, last) = encoders.last().unwrap();
+            assert_eq!(*encoder, EncoderId::Two);
+            assert_eq!(last.value, "NICE");
+        }
+        assert_eq!(controller.step(), WalkthroughStep::Encoders);
+
+        for encoder in [EncoderId::One, EncoderId::Three, EncoderId::Four] {
+            controller.handle_event(&turn(encoder)).unwrap();
+        }
+        assert_eq!(controller.step(), WalkthroughStep::Buttons);
+    }
+
+    #[test]
+    fn pressed_buttons_light_up_once() {
+        let display = Arc::new(RecordingDisplay::default());
+        let dir = tempfile::tempdir().unwrap();
+        let mut controller = WalkthroughController::new(
+            Arc::clone(&display),
+            dir.path().join("stream-deck.json"),
+            PLUS_KEYS,
+        )
+        .unwrap();
+        controller.handle_event(&press_encoder()).unwrap();
+        assert_eq!(controller.step(), WalkthroughStep::Buttons);
+
+        controller
+            .handle_event(&HardwareEvent::ButtonPressed(3))
+            .unwrap();
+        controller
+            .handle_event(&HardwareEvent::ButtonPressed(3))
+            .unwrap();
+
+        let buttons = display.buttons.lock().unwrap();
+        assert_eq!(
+            buttons.as_slice(),
+            &[(3, Some("walkthrough-3-lit".to_string()))]
+        );
+    }
+
+    #[test]
+    fn pressing_every_button_advances_to_finish() {
+        let display = Arc::new(RecordingDisplay::default());
+        let dir = tempfile::tempdir().unwrap();
+        let keys = 15;
+        let mut controller = WalkthroughController::new(
+            Arc::clone(&display),
+            dir.path().join("stream-deck.json"),
+            keys,
+        )
+        .unwrap();
+        controller.handle_event(&press_encoder()).unwrap();
+
+        for index in 0..keys {
+            assert_eq!(controller.step(), WalkthroughStep::Buttons);
+            controller
+                .handle_event(&HardwareEvent::ButtonPressed(index))
+                .unwrap();
+        }
+        assert_eq!(controller.step(), WalkthroughStep::Finish);
+
+        let encoders = display.encoders.lock().unwrap();
+        let (_, last) = encoders.last().unwrap();
+        assert_eq!(last.title, "exit");
+    }
+
+    #[test]
+    fn holding_write_button_creates_starter_config() {
+        let display = Arc::new(RecordingDisplay::default());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("stream-deck.json");
+        let mut controller =
+            WalkthroughController::new(Arc::clone(&display), path.clone(), PLUS_KEYS).unwrap();
+        controller.handle_event(&press_encoder()).unwrap();
+        controller.handle_event(&press_encoder()).unwrap();
+        assert_eq!(controller.step(), WalkthroughStep::Finish);
+
+        controller
+            .handle_event(&HardwareEvent::ButtonPressed(WRITE_BUTTON))
+            .unwrap();
+        controller.on_tick().unwrap();
+        controller
+            .handle_event(&HardwareEvent::ButtonReleased(WRITE_BUTTON))
+            .unwrap();
+        controller.on_tick().unwrap();
+        controller.on_tick().unwrap();
+        assert!(!path.exists(), "short hold must not write the file");
+
+        controller
+            .handle_event(&HardwareEvent::ButtonPressed(WRITE_BUTTON))
+            .unwrap();
+        for _ in 0..WRITE_HOLD_TICKS {
+            controller.on_tick().unwrap();
+        }
+        assert!(path.exists());
+        {
+            let encoders = display.encoders.lock().unwrap();
+            assert!(encoders.iter().any(|(_, d)| d.value == "SAVED"));
+        }
+
+        controller.handle_event(&press_encoder()).unwrap();
+        assert!(controller.is_finished());
+        let buttons = display.buttons.lock().unwrap();
+        assert_eq!(buttons.last().unwrap(), &(WRITE_BUTTON, None));
+    }
+}
@@ -0,0 +1,347 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::hardware::{DisplayPipeline, EncoderDisplay, EncoderId};
+use crate::system::weather::{OpenMeteoBackend, WeatherBackend, WeatherReport};
+
+use super::acceleration::{Clock, SystemClock};
+use super::{EncoderController, Tickable};
+
+// No request is made sooner than this after the previous one, whether it was
+// scheduled, a retry after a failure or a press.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeatherConfig {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default = "default_refresh_mins")]
+    pub refresh_mins: u64,
+}
+
+fn default_refresh_mins() -> u64 {
+    15
+}
+
+/// Shows the temperature as the value and a condition word as the status.
+/// A failed refresh keeps the last reading, marked "cached".
+pub struct WeatherController<D, C = SystemClock>
+where
+    D: DisplayPipeline,
+    C: Clock,
+{
+    worker: WeatherWorker,
+    display: D,
+    encoder: EncoderId,
+    clock: C,
+    refresh: Duration,
+    report: Option<WeatherReport>,
+    stale: bool,
+    in_flight: bool,
+    last_request: Option<Instant>,
+    next_due: Instant,
+}
+
+impl<D> WeatherController<D>
+where
+    D: DisplayPipeline,
+{
+    pub fn with_default_backend(
+        config: &WeatherConfig,
+        display: D,
+        encoder: EncoderId,
+    ) -> Result<Self> {
+        let backend = OpenMeteoBackend::new(config.latitude, config.longitude);
+        Self::new(backend, config, display, encoder, SystemClock)
+    }
+}
+
+impl<D, C> WeatherController<D, C>
+where
+    D: DisplayPipeline,
+    C: Clock,
+{
+    pub fn new<B>(
+        backend: B,
+        config: &WeatherConfig,
+        display: D,
+        encoder: EncoderId,
+        clock: C,
+    ) -> Result<Self>
+    where
+        B: WeatherBackend + 'static,
+    {
+        let now = clock.now();
+        let mut controller = Self {
+            worker: WeatherWorker::spawn(backend),
+            display,
+            encoder,
+            clock,
+            refresh: Duration::from_secs(config.refresh_mins.max(1) * 60),
+            report: None,
+            stale: false,
+            in_flight: false,
+            last_request: None,
+            next_due: now,
+        };
+        controller.push_display()?;
+        controller.request_if_due(now)?;
+        Ok(controller)
+    }
+
+    fn request_if_due(&mut self, now: Instant) -> Result<()> {
+        let throttled = self
+            .last_request
+            .is_some_and(|last| now.saturating_duration_since(last) < MIN_REFRESH_INTERVAL);
+        if self.in_flight || throttled || now < self.next_due {
+            return Ok(());
+        }
+        self.worker
+            .requests
+            .send(())
+            .map_err(|_| anyhow!("weather worker disconnected"))?;
+        self.in_flight = true;
+        self.last_request = Some(now);
+        Ok(())
+    }
+
+    // Failures retry after `MIN_REFRESH_INTERVAL` rather than waiting out the
+    // full refresh period.
+    fn poll_reports(&mut self) -> bool {
+        let mut changed = false;
+        for report in self.worker.reports.try_iter() {
+            self.in_flight = false;
+            let requested = self.last_request.unwrap_or_else(|| self.clock.now());
+            match report {
+                Ok(report) => {
+                    debug!(
+                        temperature = report.temperature_c,
+                        condition = report.condition,
+                        "weather refreshed"
+                    );
+                    self.report = Some(report);
+                    self.stale = false;
+                    self.next_due = requested + self.refresh;
+                }
+                Err(err) => {
+                    warn!(error = %err, "failed to refresh weather");
+                    self.stale = true;
+                    self.next_due = requested + MIN_REFRESH_INTERVAL;
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    fn push_display(&self) -> Result<()> {
+        let display = match &self.report {
+            Some(report) => {
+                let value = format!("{:.0}°", report.temperature_c);
+                let status = if self.stale {
+                    "cached"
+                } else {
+                    report.condition
+                };
+                EncoderDisplay::new("weather", value).with_status(status)
+            }
+            None if self.stale => EncoderDisplay::new("weather", "--").with_status("offline"),
+            None => EncoderDisplay::new("weather", "--").with_status("loading"),
+        };
+        self.display.update_encoder(self.encoder, display)
+    }
+}
+
+impl<D, C> EncoderController for WeatherController<D, C>
+where
+    D: DisplayPipeline,
+    C: Clock,
+{
+    fn on_turn(&mut self, _delta: i32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Refreshes now, unless the last request was under a minute ago.
+    fn on_press(&mut self) -> Result<()> {
+        let now = self.clock.now();
+        self.next_due = now;
+        self.request_if_due(now)
+    }
+}
+
+impl<D, C> Tickable for WeatherController<D, C>
+where
+    D: DisplayPipeline,
+    C: Clock,
+{
+    fn on_tick(&mut self) -> Result<()> {
+        if self.poll_reports() {
+            self.push_display()?;
+        }
+        self.request_if_due(self.clock.now())
+    }
+}
+
+struct WeatherWorker {
+    requests: Sender<()>,
+    reports: Receiver<Result<WeatherReport>>,
+}
+
+impl WeatherWorker {
+    // HTTP requests can take seconds; ticks only ever look at finished ones.
+    fn spawn<B>(backend: B) -> Self
+    where
+        B: WeatherBackend + 'static,
+    {
+        let (requests, request_rx) = unbounded::<()>();
+        let (report_tx, reports) = unbounded();
+        thread::spawn(move || {
+            while request_rx.recv().is_ok() {
+                request_rx.try_iter().for_each(drop);
+                if report_tx.send(backend.current()).is_err() {
+                    return;
+                }
+            }
+        });
+        Self { requests, reports }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::acceleration::tests::FakeClock;
+    use crate::system::weather::tests::MockWeatherBackend;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingDisplay {
+        updates: Arc<Mutex<Vec<EncoderDisplay>>>,
+    }
+
+    impl DisplayPipeline for RecordingDisplay {
+        fn update_encoder(&self, _encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+            self.updates.lock().unwrap().push(display);
+            Ok(())
+        }
+    }
+
+    impl RecordingDisplay {
+        fn last(&self) -> (String, Option<String>) {
+            let updates = self.updates.lock().unwrap();
+            let last = updates.last().unwrap();
+            (last.value.clone(), last.status.clone())
+        }
+    }
+
+    fn report(temperature_c: f32, condition: &'static str) -> Result<WeatherReport, String> {
+        Ok(WeatherReport {
+            temperature_c,
+            condition,
+        })
+    }
+
+    fn config() -> WeatherConfig {
+        WeatherConfig {
+            latitude: 52.52,
+            longitude: 13.41,
+            refresh_mins: 15,
+        }
+    }
+
+    // Ticks until the worker has answered every request made so far.
+    fn settle(controller: &mut WeatherController<RecordingDisplay, FakeClock>) {
+        for _ in 0..200 {
+            controller.on_tick().unwrap();
+            if !controller.in_flight {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("weather worker did not answer");
+    }
+
+    #[test]
+    fn failures_keep_the_last_reading_marked_cached() {
+        let backend = MockWeatherBackend::with([
+            report(17.4, "rain"),
+            Err("dns lookup failed".to_string()),
+            report(-2.0, "snow"),
+        ]);
+        let display = RecordingDisplay::default();
+        let clock = FakeClock::default();
+        let mut controller = WeatherController::new(
+            backend.clone(),
+            &config(),
+            display.clone(),
+            EncoderId::Four,
+            clock.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            display.last(),
+            ("--".to_string(), Some("loading".to_string()))
+        );
+
+        settle(&mut controller);
+        assert_eq!(
+            display.last(),
+            ("17°".to_string(), Some("rain".to_string()))
+        );
+
+        clock.advance(Duration::from_secs(15 * 60));
+        controller.on_tick().unwrap();
+        settle(&mut controller);
+        assert_eq!(
+            display.last(),
+            ("17°".to_string(), Some("cached".to_string()))
+        );
+
+        // The retry comes a minute after the failure, not a full period.
+        clock.advance(MIN_REFRESH_INTERVAL);
+        controller.on_tick().unwrap();
+        settle(&mut controller);
+        assert_eq!(
+            display.last(),
+            ("-2°".to_string(), Some("snow".to_string()))
+        );
+        assert_eq!(backend.calls(), 3);
+    }
+
+    #[test]
+    fn presses_and_a_flapping_network_never_refresh_within_a_minute() {
+        let backend = MockWeatherBackend::default();
+        let display = RecordingDisplay::default();
+        let clock = FakeClock::default();
+        let mut controller = WeatherController::new(
+            backend.clone(),
+            &config(),
+            display.clone(),
+            EncoderId::Four,
+            clock.clone(),
+        )
+        .unwrap();
+        settle(&mut controller);
+        assert_eq!(
+            display.last(),
+            ("--".to_string(), Some("offline".to_string()))
+        );
+
+        for _ in 0..10 {
+            clock.advance(Duration::from_secs(5));
+            controller.on_press().unwrap();
+            settle(&mut controller);
+        }
+        assert_eq!(backend.calls(), 1);
+
+        clock.advance(Duration::from_secs(10));
+        controller.on_press().unwrap();
+        settle(&mut controller);
+        assert_eq!(backend.calls(), 2);
+    }
+}
@@ -0,0 +1,267 @@
+//! `streamdeck_ctrl discover`: the decks, sinks, monitors and players a
+//! configuration can refer to on this machine.
+
+use std::fmt;
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::hardware;
+use crate::system::audio::PulseAudioBackend;
+use crate::system::audio_switch::{AudioSwitchBackend, PulseAudioSwitch, SinkInfo};
+use crate::system::brightness::DdcutilBackend;
+use crate::system::now_playing::PlayerctlBackend;
+
+/// Printed as is by `--json`, so renaming a field breaks scripts.
+#[derive(Debug, Serialize)]
+pub struct Discovery {
+    pub decks: Probe<Deck>,
+    pub sinks: Probe<Sink>,
+    pub displays: Probe<Monitor>,
+    pub players: Probe<String>,
+}
+
+/// What one tool reported; `note` says why a probe that could not run has
+/// nothing to list.
+#[derive(Debug, Serialize)]
+pub struct Probe<T> {
+    pub available: bool,
+    pub items: Vec<T>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Deck {
+    pub kind: String,
+    pub serial: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Sink {
+    pub id: Option<u32>,
+    pub name: String,
+    pub description: Option<String>,
+    pub form_factor: Option<String>,
+}
+
+/// A DDC display, numbered as `monitor_display` expects.
+#[derive(Debug, Serialize)]
+pub struct Monitor {
+    pub display: u8,
+    pub model: String,
+}
+
+impl<T> Probe<T> {
+    fn from_result(result: Result<Vec<T>>) -> Self {
+        match result {
+            Ok(items) => Self {
+                available: true,
+                items,
+                note: None,
+            },
+            Err(err) => Self {
+                available: false,
+                items: Vec::new(),
+                note: Some(format!("{err:#}")),
+            },
+        }
+    }
+}
+
+pub fn discover() -> Discovery {
+    let decks = hardware::connected_decks().map(|decks| {
+        decks
+            .into_iter()
+            .map(|deck| Deck {
+                kind: deck.kind,
+                serial: deck.serial,
+            })
+            .collect()
+    });
+    let displays = DdcutilBackend::enumerate().map(|monitors| {
+        monitors
+            .into_iter()
+            .map(|monitor| Monitor {
+                display: monitor.display,
+                model: monitor.model,
+            })
+            .collect()
+    });
+    Discovery {
+        decks: Probe::from_result(decks),
+        sinks: Probe::from_result(list_sinks()),
+        displays: Probe::from_result(displays),
+        players: Probe::from_result(PlayerctlBackend::list_players()),
+    }
+}
+
+fn list_sinks() -> Result<Vec<Sink>> {
+    if !PulseAudioBackend::default().is_available() {
+        bail!("pactl not found");
+    }
+    Ok(PulseAudioSwitch::new()
+        .list_sinks()?
+        .into_iter()
+        .map(|sink| Sink {
+            id: sink.id,
+            name: sink.name,
+            description: sink.description,
+            form_factor: sink.form_factor,
+        })
+        .collect())
+}
+
+/// `--list-sinks`: one row per sink, the default marked with `*`.
+pub fn sink_table() -> Result<String> {
+    if !PulseAudioBackend::default().is_available() {
+        bail!("pactl not found");
+    }
+    let audio = PulseAudioSwitch::new();
+    let sinks = audio.list_sinks()?;
+    let default = audio.current_default_sink()?;
+    Ok(format_sinks(
+        &sinks,
+        default.as_ref().map(|sink| sink.name.as_str()),
+    ))
+}
+
+/// `--list-devices`: the kind and serial of every Stream Deck attached.
+pub fn deck_table() -> Result<String> {
+    let decks = hardware::connected_decks()?;
+    if decks.is_empty() {
+        return Ok("no Stream Decks found\n".to_string());
+    }
+    Ok(decks
+        .iter()
+        .map(|deck| format!("{:<10} {}\n", deck.kind, deck.serial))
+        .collect())
+}
+
+fn format_sinks(sinks: &[SinkInfo], default: Option<&str>) -> String {
+    if sinks.is_empty() {
+        return "no sinks found\n".to_string();
+    }
+    let width = sinks
+        .iter()
+        .map(|sink| sink.name.len())
+        .chain(["NAME".len()])
+        .max()
+        .unwrap_or_default();
+    let mut table = format!("  {:<5} {:<width$}  DESCRIPTION\n", "ID", "NAME");
+    for sink in sinks {
+        let marker = if Some(sink.name.as_str()) == default {
+            '*'
+        } else {
+            ' '
+        };
+        let id = sink.id.map(|id| id.to_string()).unwrap_or_default();
+        let description = sink.description.as_deref().unwrap_or("");
+        let row = format!("{marker} {id:<5} {:<width$}  {description}", sink.name);
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn section<T>(
+    f: &mut fmt::Formatter<'_>,
+    title: &str,
+    probe: &Probe<T>,
+    line: impl Fn(&T) -> String,
+) -> fmt::Result {
+    writeln!(f, "{title}:")?;
+    if let Some(note) = &probe.note {
+        return writeln!(f, "  ({note})");
+    }
+    if probe.items.is_empty() {
+        return writeln!(f, "  none found");
+    }
+    for item in &probe.items {
+        writeln!(f, "  {}", line(item))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Discovery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        section(f, "Stream Decks", &self.decks, |deck| {
+            format!("{:<10} {}", deck.kind, deck.serial)
+        })?;
+        section(f, "Audio sinks", &self.sinks, |sink| {
+            let id = sink.id.map(|id| format!("#{id}")).unwrap_or_default();
+            match &sink.description {
+                Some(description) => format!("{id:<5} {}  ({description})", sink.name),
+                None => format!("{id:<5} {}", sink.name),
+            }
+        })?;
+        section(f, "DDC displays", &self.displays, |monitor| {
+            format!("{:<5} {}", monitor.display, monitor.model)
+        })?;
+        section(f, "MPRIS players", &self.players, String::clone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn missing_tools_leave_a_note_in_both_formats() {
+        let discovery = Discovery {
+            decks: Probe::from_result(Ok(vec![Deck {
+                kind: "Plus".into(),
+                serial: "A00PLUS".into(),
+            }])),
+            sinks: Probe::from_result(Ok(vec![Sink {
+                id: Some(1),
+                name: "alsa_output.hdmi".into(),
+                description: Some("HDMI".into()),
+                form_factor: None,
+            }])),
+            displays: Probe::from_result(Err(anyhow!("ddcutil not available"))),
+            players: Probe::from_result(Ok(Vec::new())),
+        };
+
+        assert_eq!(
+            discovery.to_string(),
+            "Stream Decks:\n  Plus       A00PLUS\n\
+             Audio sinks:\n  #1    alsa_output.hdmi  (HDMI)\n\
+             DDC displays:\n  (ddcutil not available)\n\
+             MPRIS players:\n  none found\n"
+        );
+
+        let json = serde_json::to_value(&discovery).unwrap();
+        assert_eq!(json["decks"]["items"][0]["serial"], "A00PLUS");
+        assert_eq!(json["sinks"]["items"][0]["id"], 1);
+        assert_eq!(json["displays"]["available"], false);
+        assert_eq!(json["displays"]["note"], "ddcutil not available");
+        assert_eq!(json["players"]["items"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn sink_table_marks_the_default() {
+        let sink = |id, name: &str, description: Option<&str>| SinkInfo {
+            id: Some(id),
+            name: name.into(),
+            description: description.map(Into::into),
+            icon_name: None,
+            form_factor: None,
+        };
+        let sinks = [
+            sink(1, "alsa_output.hdmi", Some("HDMI")),
+            sink(12, "bluez_output.buds", None),
+        ];
+
+        let table = format_sinks(&sinks, Some("bluez_output.buds"));
+        assert_eq!(
+            table.lines().collect::<Vec<_>>(),
+            [
+                "  ID    NAME               DESCRIPTION",
+                "  1     alsa_output.hdmi   HDMI",
+                "* 12    bluez_output.buds",
+            ]
+        );
+        assert_eq!(format_sinks(&[], None), "no sinks found\n");
+    }
+}
@@ -1,32 +1,157 @@
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow};
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use elgato_streamdeck::info::Kind;
 use elgato_streamdeck::{
     StreamDeck, StreamDeckError, StreamDeckInput, list_devices, new_hidapi, refresh_device_list,
 };
-use tracing::{debug, error, info, warn};
+use tracing::{Level, debug, error, info, warn};
 
 use image::RgbaImage;
 
 use crate::hardware::render;
+use crate::hardware::simulator::{self, SIMULATED_CAPABILITIES, SimulatorPipeline};
+use crate::metrics::Metrics;
+use crate::util::icons::AnimatedIcon;
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+// Room for every key of the largest deck while it is not known which one will
+// be plugged in.
+const LATE_DEVICE_CAPABILITIES: DeviceCapabilities = DeviceCapabilities {
+    key_count: 32,
+    encoder_count: 4,
+    lcd_strip: true,
+};
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(25);
+const FLUSH_RETRY_BASE: Duration = Duration::from_millis(100);
+const FLUSH_RETRY_MAX: Duration = Duration::from_secs(4);
+const PRESS_FEEDBACK_DURATION: Duration = Duration::from_millis(150);
+const STATS_WINDOW: Duration = Duration::from_secs(60);
+const STATS_QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+const VALUE_ANIMATION_FRAME: Duration = Duration::from_millis(30);
+// Width of each encoder's part of the Stream Deck Plus touch strip.
+const STRIP_SEGMENT_WIDTH: u16 = 200;
+// Taps this close to the line between two segments are dropped.
+const STRIP_BORDER_DEADZONE: u16 = 4;
+// Role of the single device picked by `HardwareConfig::serial`.
+const DEFAULT_ROLE: &str = "default";
 
 #[derive(Clone, Debug)]
 pub struct HardwareConfig {
     pub serial: Option<String>,
+    /// Decks driven at once; the first is the default device. When empty, a
+    /// single deck is picked by `serial`.
+    pub devices: Vec<DeviceConfig>,
     pub device_brightness: u8,
+    pub allow_non_plus: bool,
+    /// Consecutive failed display writes after which the device is treated as
+    /// unplugged and reopened.
+    pub max_flush_failures: u32,
+    pub idle_dim: Option<IdleDim>,
+    pub simulator: Option<SimulatorConfig>,
+    /// Per encoder, applied to every display sent through a handle.
+    pub strip_theme: [SegmentTheme; 4],
+    /// `None` draws every progress change in a single repaint.
+    pub value_animation: Option<ValueAnimation>,
+    /// Connection and write failure counts for the metrics endpoint.
+    pub metrics: Arc<Metrics>,
+}
+
+/// One deck to drive, picked by `serial` or else the first unclaimed deck.
+/// Controllers address it by `role`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceConfig {
+    pub serial: Option<String>,
+    pub role: String,
+}
+
+/// Dims the deck's backlight to `brightness` after `after` without input;
+/// the next press, turn or touch restores it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdleDim {
+    pub after: Duration,
+    pub brightness: u8,
+}
+
+/// Eases a segment's progress bar, and a percentage value, to a new reading
+/// over `duration` when it moved by more than `min_delta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueAnimation {
+    pub min_delta: f32,
+    pub duration: Duration,
+}
+
+impl Default for ValueAnimation {
+    fn default() -> Self {
+        Self {
+            min_delta: 0.1,
+            duration: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Renders to PNG files under `output_dir` and reads input commands from
+/// `fifo`, or stdin when unset, instead of opening a device.
+#[derive(Clone, Debug)]
+pub struct SimulatorConfig {
+    pub output_dir: PathBuf,
+    pub fifo: Option<PathBuf>,
 }
 
 impl Default for HardwareConfig {
     fn default() -> Self {
         Self {
             serial: None,
+            devices: Vec::new(),
             device_brightness: 40,
+            allow_non_plus: false,
+            max_flush_failures: 20,
+            idle_dim: None,
+            simulator: None,
+            strip_theme: [SegmentTheme::default(); 4],
+            value_animation: Some(ValueAnimation::default()),
+            metrics: Arc::default(),
+        }
+    }
+}
+
+impl HardwareConfig {
+    fn device_configs(&self) -> Vec<DeviceConfig> {
+        if self.devices.is_empty() {
+            return vec![DeviceConfig {
+                serial: self.serial.clone(),
+                role: DEFAULT_ROLE.to_string(),
+            }];
+        }
+        self.devices.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    pub key_count: u8,
+    pub encoder_count: u8,
+    pub lcd_strip: bool,
+}
+
+impl DeviceCapabilities {
+    fn from_kind(kind: Kind) -> Self {
+        Self {
+            key_count: kind.key_count(),
+            encoder_count: kind.encoder_count(),
+            lcd_strip: kind.lcd_strip_size().is_some(),
         }
     }
+
+    pub fn has_encoders(&self) -> bool {
+        self.encoder_count > 0
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -60,16 +185,83 @@ impl EncoderId {
     pub fn all() -> [Self; 4] {
         [Self::One, Self::Two, Self::Three, Self::Four]
     }
+
+    /// The encoder whose LCD segment lies under `x` on the touch strip.
+    pub fn from_strip_x(x: u16) -> Self {
+        let index = (x / STRIP_SEGMENT_WIDTH).min(3);
+        Self::from_index(index as usize).unwrap_or(Self::Four)
+    }
+
+    /// Like `from_strip_x`, but `None` for a tap too close to the line
+    /// between two segments to say which one was meant.
+    pub fn from_strip_tap(x: u16) -> Option<Self> {
+        let offset = x % STRIP_SEGMENT_WIDTH;
+        let boundary = x >= STRIP_SEGMENT_WIDTH && offset < STRIP_BORDER_DEADZONE
+            || x < STRIP_SEGMENT_WIDTH * 3 && offset >= STRIP_SEGMENT_WIDTH - STRIP_BORDER_DEADZONE;
+        (!boundary).then(|| Self::from_strip_x(x))
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Colours of one LCD strip segment. A display's own `value_color` and
+/// `progress_color` still win over `value` and `progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentTheme {
+    pub background: [u8; 3],
+    pub title: [u8; 3],
+    pub value: [u8; 3],
+    pub status: [u8; 3],
+    pub progress_background: [u8; 3],
+    pub progress: [u8; 3],
+    pub border: bool,
+    /// Sets text in the bundled TTF font rather than the bitmap one.
+    pub use_ttf: bool,
+}
+
+impl Default for SegmentTheme {
+    fn default() -> Self {
+        Self {
+            background: [8, 10, 18],
+            title: [180, 190, 210],
+            value: [235, 240, 255],
+            status: [120, 210, 255],
+            progress_background: [30, 35, 45],
+            progress: [0, 180, 120],
+            border: true,
+            use_ttf: false,
+        }
+    }
+}
+
+/// How the filled part of a progress bar is coloured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressStyle {
+    Solid([u8; 3]),
+    /// `(up_to, colour)` bands in ascending order: the first band whose
+    /// bound the progress does not exceed colours the whole bar. Progress
+    /// past the last bound keeps the theme colour.
+    Thresholds(Vec<(f32, [u8; 3])>),
+    /// Blends from the left end of the bar to the right, so a partly filled
+    /// bar only reaches part of the way to `to`.
+    Gradient {
+        from: [u8; 3],
+        to: [u8; 3],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct EncoderDisplay {
     pub title: String,
     pub value: String,
     pub status: Option<String>,
+    /// Thresholds see values past 1.0; the bar itself stops at full.
     pub progress: Option<f32>,
+    pub progress_style: Option<ProgressStyle>,
+    /// Deprecated alias for `ProgressStyle::Solid`, used when
+    /// `progress_style` is unset.
     pub progress_color: Option<[u8; 3]>,
     pub value_color: Option<[u8; 3]>,
+    pub icon: Option<Arc<RgbaImage>>,
+    pub theme: SegmentTheme,
 }
 
 impl EncoderDisplay {
@@ -79,233 +271,1864 @@ impl EncoderDisplay {
             value: value.into(),
             status: None,
             progress: None,
+            progress_style: None,
             progress_color: None,
             value_color: None,
+            icon: None,
+            theme: SegmentTheme::default(),
+        }
+    }
+
+    pub fn with_status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    pub fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = Some(progress.clamp(0.0, 1.0));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ButtonImage {
+    pub id: String,
+    pub image: Arc<RgbaImage>,
+    pub tint: Option<[u8; 3]>,
+    /// Drawn along the bottom of the key over a dark band.
+    pub label: Option<String>,
+    /// Played back on the device in place of `image`, which stays the still
+    /// shown by the simulator.
+    pub animation: Option<Arc<AnimatedIcon>>,
+    /// Fills the key behind transparent icon pixels instead of black.
+    pub background: Option<[u8; 3]>,
+    /// A few characters, such as "2/3", drawn in the top-right corner.
+    pub badge: Option<String>,
+}
+
+/// What controllers last sent to a deck, whether or not it is connected.
+#[derive(Debug, Clone, Default)]
+pub struct ShownDisplays {
+    pub encoders: [Option<EncoderDisplay>; 4],
+    /// Icon id per key.
+    pub buttons: BTreeMap<u8, String>,
+}
+
+/// Index of a deck in the configured device list; the default device is 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceId(pub usize);
+
+/// Input from one deck.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeviceEvent {
+    pub device: DeviceId,
+    pub event: HardwareEvent,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HardwareEvent {
+    EncoderTurned {
+        encoder: EncoderId,
+        delta: i32,
+    },
+    EncoderPressed {
+        encoder: EncoderId,
+    },
+    EncoderReleased {
+        encoder: EncoderId,
+    },
+    ButtonPressed(u8),
+    ButtonReleased(u8),
+    TouchTap {
+        x: u16,
+        y: u16,
+        segment: EncoderId,
+    },
+    TouchSwipe {
+        from_x: u16,
+        to_x: u16,
+    },
+    /// The deck went away; releases for anything held were sent first.
+    DeviceDisconnected,
+    /// A deck came back, or one missing at startup was plugged in, and shows
+    /// the displays sent while it was gone.
+    DeviceConnected,
+}
+
+pub trait DisplayPipeline: Send + Sync {
+    fn update_encoder(&self, encoder: EncoderId, display: EncoderDisplay) -> Result<()>;
+    fn update_button_icon(&self, _index: u8, _icon: Option<ButtonImage>) -> Result<()> {
+        Ok(())
+    }
+    fn clear_encoder_display(&self, _encoder: EncoderId) -> Result<()> {
+        Ok(())
+    }
+    fn clear_button(&self, index: u8) -> Result<()> {
+        self.update_button_icon(index, None)
+    }
+    fn clear_all_displays(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Clears every display before the process exits, waiting up to `grace`
+    /// for the device to show it.
+    fn blank_for_exit(&self, _grace: Duration) -> Result<()> {
+        self.clear_all_displays()
+    }
+    /// Covers the whole LCD strip with `text` for `duration`, then shows the
+    /// encoder displays again, including any updated in the meantime.
+    fn show_banner(&self, _text: &str, _duration: Duration, _color: [u8; 3]) -> Result<()> {
+        Ok(())
+    }
+    /// Takes a banner down before its time is up.
+    fn dismiss_banner(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Changes the backlight of the deck itself, in percent.
+    fn set_device_brightness(&self, _level: u8) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends display updates to one deck; `for_role` and `devices` reach the
+/// others.
+#[derive(Clone)]
+pub struct HardwareHandle {
+    devices: Arc<[DeviceLink]>,
+    device: DeviceId,
+}
+
+struct DeviceLink {
+    role: String,
+    command_tx: Sender<HardwareCommand>,
+    capabilities: Option<DeviceCapabilities>,
+    strip_theme: [SegmentTheme; 4],
+    shown: Mutex<ShownDisplays>,
+}
+
+enum HardwareCommand {
+    UpdateEncoderDisplay {
+        encoder: EncoderId,
+        display: EncoderDisplay,
+    },
+    UpdateButtonIcon {
+        index: u8,
+        icon: Option<ButtonImage>,
+    },
+    ClearEncoderDisplay(EncoderId),
+    ClearButton(u8),
+    ClearAll,
+    SetDeviceBrightness(u8),
+    QueryStats(Sender<BackendStats>),
+    /// Replaces any banner already up and restarts the clock.
+    ShowBanner {
+        text: String,
+        duration_ms: u64,
+        color: [u8; 3],
+    },
+    DismissBanner,
+    /// Writes whatever is pending straight away at the configured brightness,
+    /// then answers; sent after `ClearAll` when the process is going down.
+    FlushForExit(Sender<()>),
+}
+
+/// Timing of one kind of backend work over the last minute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    pub count: usize,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendStats {
+    pub strip_flush: TimingStats,
+    pub button_flush: TimingStats,
+    pub command_drain: TimingStats,
+}
+
+impl DisplayPipeline for HardwareHandle {
+    fn update_encoder(&self, encoder: EncoderId, mut display: EncoderDisplay) -> Result<()> {
+        display.theme = self.link().strip_theme[encoder.index()];
+        self.with_shown(|shown| shown.encoders[encoder.index()] = Some(display.clone()));
+        self.send(HardwareCommand::UpdateEncoderDisplay { encoder, display })
+    }
+
+    fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+        self.with_shown(|shown| match &icon {
+            Some(icon) => {
+                shown.buttons.insert(index, icon.id.clone());
+            }
+            None => {
+                shown.buttons.remove(&index);
+            }
+        });
+        self.send(HardwareCommand::UpdateButtonIcon { index, icon })
+    }
+
+    fn clear_encoder_display(&self, encoder: EncoderId) -> Result<()> {
+        self.with_shown(|shown| shown.encoders[encoder.index()] = None);
+        self.send(HardwareCommand::ClearEncoderDisplay(encoder))
+    }
+
+    fn clear_button(&self, index: u8) -> Result<()> {
+        self.with_shown(|shown| {
+            shown.buttons.remove(&index);
+        });
+        self.send(HardwareCommand::ClearButton(index))
+    }
+
+    fn clear_all_displays(&self) -> Result<()> {
+        self.with_shown(|shown| *shown = ShownDisplays::default());
+        self.send(HardwareCommand::ClearAll)
+    }
+
+    fn blank_for_exit(&self, grace: Duration) -> Result<()> {
+        let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+        self.send(HardwareCommand::ClearAll)?;
+        self.send(HardwareCommand::FlushForExit(done_tx))?;
+        done_rx
+            .recv_timeout(grace)
+            .context("hardware backend did not blank the deck in time")
+    }
+
+    fn show_banner(&self, text: &str, duration: Duration, color: [u8; 3]) -> Result<()> {
+        self.send(HardwareCommand::ShowBanner {
+            text: text.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            color,
+        })
+    }
+
+    fn dismiss_banner(&self) -> Result<()> {
+        self.send(HardwareCommand::DismissBanner)
+    }
+
+    fn set_device_brightness(&self, level: u8) -> Result<()> {
+        self.send(HardwareCommand::SetDeviceBrightness(level))
+    }
+}
+
+impl HardwareHandle {
+    fn new(links: Vec<DeviceLink>) -> Self {
+        Self {
+            devices: links.into(),
+            device: DeviceId::default(),
+        }
+    }
+
+    /// One headless deck, without looking for hardware; what it was told to
+    /// show is still read back through `shown`.
+    #[cfg(test)]
+    pub fn headless() -> Result<(Self, Receiver<DeviceEvent>)> {
+        let (event_tx, events) = crossbeam_channel::unbounded();
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        spawn_headless(EventSink::new(DeviceId::default(), event_tx), command_rx)?;
+        let link = DeviceLink {
+            role: DEFAULT_ROLE.to_string(),
+            command_tx,
+            capabilities: None,
+            strip_theme: [SegmentTheme::default(); 4],
+            shown: Mutex::default(),
+        };
+        Ok((Self::new(vec![link]), events))
+    }
+
+    // `None` while running headless; controllers behave as on a Stream Deck Plus.
+    pub fn capabilities(&self) -> Option<DeviceCapabilities> {
+        self.link().capabilities
+    }
+
+    pub fn device(&self) -> DeviceId {
+        self.device
+    }
+
+    /// The device a controller's `device` setting names; `None` means the
+    /// default device.
+    pub fn device_id(&self, role: Option<&str>) -> Option<DeviceId> {
+        let Some(role) = role else {
+            return Some(DeviceId::default());
+        };
+        self.devices
+            .iter()
+            .position(|link| link.role == role)
+            .map(DeviceId)
+    }
+
+    pub fn for_device(&self, device: DeviceId) -> Option<Self> {
+        (device.0 < self.devices.len()).then(|| Self {
+            devices: Arc::clone(&self.devices),
+            device,
+        })
+    }
+
+    /// A handle for every configured device, the default first.
+    pub fn devices(&self) -> Vec<Self> {
+        (0..self.devices.len())
+            .filter_map(|index| self.for_device(DeviceId(index)))
+            .collect()
+    }
+
+    /// Flush and command-drain timings from the backend thread.
+    pub fn stats(&self) -> Result<BackendStats> {
+        let (reply_tx, reply_rx) = crossbeam_channel::bounded(1);
+        self.send(HardwareCommand::QueryStats(reply_tx))?;
+        reply_rx
+            .recv_timeout(STATS_QUERY_TIMEOUT)
+            .context("hardware backend did not report stats")
+    }
+
+    /// Commands not yet picked up by the backend, across every deck.
+    pub fn queue_depth(&self) -> usize {
+        self.devices.iter().map(|link| link.command_tx.len()).sum()
+    }
+
+    /// A copy of what this deck was last told to show.
+    pub fn shown(&self) -> ShownDisplays {
+        self.link()
+            .shown
+            .lock()
+            .map(|shown| shown.clone())
+            .unwrap_or_default()
+    }
+
+    fn link(&self) -> &DeviceLink {
+        &self.devices[self.device.0]
+    }
+
+    /// Shows `display` on a segment no controller has written to yet. The
+    /// check and the send happen under one lock, so it can never land on top
+    /// of a controller's first update.
+    pub fn show_placeholder(&self, encoder: EncoderId, mut display: EncoderDisplay) -> Result<()> {
+        let Ok(mut shown) = self.link().shown.lock() else {
+            return Ok(());
+        };
+        if shown.encoders[encoder.index()].is_some() {
+            return Ok(());
+        }
+        display.theme = self.link().strip_theme[encoder.index()];
+        shown.encoders[encoder.index()] = Some(display.clone());
+        self.send(HardwareCommand::UpdateEncoderDisplay { encoder, display })
+    }
+
+    fn with_shown(&self, update: impl FnOnce(&mut ShownDisplays)) {
+        if let Ok(mut shown) = self.link().shown.lock() {
+            update(&mut shown);
+        }
+    }
+
+    fn send(&self, command: HardwareCommand) -> Result<()> {
+        self.link()
+            .command_tx
+            .send(command)
+            .map_err(|err| anyhow!("hardware command channel closed: {err}"))
+    }
+}
+
+/// A deck hidapi can see, supported or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedDeck {
+    pub kind: String,
+    pub serial: String,
+}
+
+pub fn connected_decks() -> Result<Vec<ConnectedDeck>> {
+    let hid = new_hidapi().context("failed to initialise hidapi")?;
+    Ok(list_devices(&hid)
+        .into_iter()
+        .map(|(kind, serial)| ConnectedDeck {
+            kind: format!("{kind:?}"),
+            serial,
+        })
+        .collect())
+}
+
+pub fn start(config: HardwareConfig) -> Result<(HardwareHandle, Receiver<DeviceEvent>)> {
+    let (event_tx, event_rx) = crossbeam_channel::unbounded();
+    let devices = config.device_configs();
+    let strip_theme = config.strip_theme;
+    let (command_txs, mut command_rxs): (Vec<_>, Vec<_>) = devices
+        .iter()
+        .map(|_| crossbeam_channel::unbounded())
+        .unzip();
+
+    let capabilities = if let Some(simulator) = config.simulator.clone() {
+        // Only the default device is simulated; any others stay headless.
+        let simulated = command_rxs.remove(0);
+        for (index, command_rx) in command_rxs.into_iter().enumerate() {
+            spawn_headless(
+                EventSink::new(DeviceId(index + 1), event_tx.clone()),
+                command_rx,
+            )?;
+        }
+        start_simulator(
+            simulator,
+            EventSink::new(DeviceId::default(), event_tx),
+            simulated,
+        )?;
+        let mut capabilities = vec![None; devices.len()];
+        capabilities[0] = Some(SIMULATED_CAPABILITIES);
+        capabilities
+    } else {
+        let (capabilities_tx, capabilities_rx) = crossbeam_channel::bounded(1);
+        let count = devices.len();
+        let assigned = devices.clone();
+        thread::Builder::new()
+            .name("streamdeck-backend".into())
+            .spawn(move || {
+                if let Err(err) =
+                    run_backend(config, assigned, event_tx, command_rxs, capabilities_tx)
+                {
+                    error!(error = %err, "hardware backend terminated");
+                }
+            })
+            .context("failed to spawn hardware backend")?;
+        capabilities_rx
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap_or_else(|_| vec![None; count])
+    };
+
+    let links = devices
+        .into_iter()
+        .zip(command_txs)
+        .zip(capabilities)
+        .map(|((device, command_tx), capabilities)| DeviceLink {
+            role: device.role,
+            command_tx,
+            capabilities,
+            strip_theme,
+            shown: Mutex::default(),
+        })
+        .collect();
+    Ok((HardwareHandle::new(links), event_rx))
+}
+
+/// Tags input with the deck it came from.
+#[derive(Clone)]
+pub(super) struct EventSink {
+    device: DeviceId,
+    tx: Sender<DeviceEvent>,
+}
+
+impl EventSink {
+    fn new(device: DeviceId, tx: Sender<DeviceEvent>) -> Self {
+        Self { device, tx }
+    }
+
+    /// Returns false once the app has stopped listening.
+    pub(super) fn send(&self, event: HardwareEvent) -> bool {
+        self.tx
+            .send(DeviceEvent {
+                device: self.device,
+                event,
+            })
+            .is_ok()
+    }
+}
+
+fn start_simulator(
+    config: SimulatorConfig,
+    events: EventSink,
+    command_rx: Receiver<HardwareCommand>,
+) -> Result<()> {
+    let pipeline = SimulatorPipeline::new(&config.output_dir)?;
+    simulator::spawn_input_reader(config.fifo, events.clone())?;
+    info!(output = %config.output_dir.display(), "running against the display simulator");
+
+    thread::Builder::new()
+        .name("streamdeck-simulator".into())
+        .spawn(move || run_simulator(&pipeline, events, command_rx))
+        .context("failed to spawn display simulator")?;
+    Ok(())
+}
+
+// Holds `events` so the app keeps running after simulator input closes.
+fn run_simulator(
+    pipeline: &SimulatorPipeline,
+    events: EventSink,
+    command_rx: Receiver<HardwareCommand>,
+) {
+    let mut banner_until: Option<Instant> = None;
+    loop {
+        let received = match banner_until {
+            Some(until) => command_rx.recv_deadline(until),
+            None => command_rx
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let command = match received {
+            Ok(command) => command,
+            Err(RecvTimeoutError::Timeout) => {
+                banner_until = None;
+                if let Err(err) = pipeline.hide_banner() {
+                    warn!(error = %err, "simulator failed to render update");
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let result = match command {
+            HardwareCommand::UpdateEncoderDisplay { encoder, display } => {
+                pipeline.update_encoder(encoder, display)
+            }
+            HardwareCommand::UpdateButtonIcon { index, icon } => {
+                pipeline.update_button_icon(index, icon)
+            }
+            HardwareCommand::ClearEncoderDisplay(encoder) => {
+                pipeline.clear_encoder_display(encoder)
+            }
+            HardwareCommand::ClearButton(index) => pipeline.clear_button(index),
+            HardwareCommand::ClearAll => pipeline.clear_all_displays(),
+            HardwareCommand::SetDeviceBrightness(level) => {
+                debug!(level, "simulator ignores device brightness");
+                Ok(())
+            }
+            HardwareCommand::QueryStats(reply) => {
+                reply.send(BackendStats::default()).ok();
+                Ok(())
+            }
+            HardwareCommand::ShowBanner {
+                text,
+                duration_ms,
+                color,
+            } => {
+                banner_until = Some(Instant::now() + Duration::from_millis(duration_ms));
+                pipeline.show_banner(&text, color)
+            }
+            HardwareCommand::DismissBanner => match banner_until.take() {
+                Some(_) => pipeline.hide_banner(),
+                None => Ok(()),
+            },
+            HardwareCommand::FlushForExit(done) => {
+                done.send(()).ok();
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            warn!(error = %err, "simulator failed to render update");
+        }
+    }
+    drop(events);
+}
+
+// Picks a deck for every configured device, then drives each on its own
+// thread so one being unplugged never stalls the others.
+fn run_backend(
+    config: HardwareConfig,
+    devices: Vec<DeviceConfig>,
+    event_tx: Sender<DeviceEvent>,
+    command_rxs: Vec<Receiver<HardwareCommand>>,
+    capabilities_tx: Sender<Vec<Option<DeviceCapabilities>>>,
+) -> Result<()> {
+    let connected = match new_hidapi().context("failed to initialise hidapi") {
+        Ok(mut hid) => {
+            refresh_device_list(&mut hid).ok();
+            list_devices(&hid)
+        }
+        Err(err) => {
+            warn!(error = %err, "no Stream Deck can be opened yet; retrying in the background");
+            Vec::new()
+        }
+    };
+    debug!(device_count = connected.len(), "found stream deck devices");
+
+    let assigned = assign_devices(&connected, &devices, config.allow_non_plus);
+    capabilities_tx
+        .send(
+            assigned
+                .iter()
+                .map(|selected| {
+                    selected
+                        .as_ref()
+                        .ok()
+                        .map(|selected| DeviceCapabilities::from_kind(selected.kind))
+                })
+                .collect(),
+        )
+        .ok();
+
+    let claimed: Arc<Mutex<BTreeSet<String>>> = Arc::new(Mutex::new(
+        assigned
+            .iter()
+            .flatten()
+            .map(|selected| selected.serial.clone())
+            .collect(),
+    ));
+    for (index, (selected, command_rx)) in assigned.into_iter().zip(command_rxs).enumerate() {
+        let events = EventSink::new(DeviceId(index), event_tx.clone());
+        let device = devices[index].clone();
+        let selected = match selected {
+            Ok(selected) => Some(selected),
+            Err(err) => {
+                warn!(
+                    error = %err,
+                    role = %device.role,
+                    "no Stream Deck detected; keeping the display state until one is plugged in"
+                );
+                None
+            }
+        };
+        let config = config.clone();
+        let claimed = Arc::clone(&claimed);
+        thread::Builder::new()
+            .name(format!("streamdeck-device-{index}"))
+            .spawn(move || drive_device(&config, &device, selected, &claimed, &events, &command_rx))
+            .context("failed to spawn Stream Deck loop")?;
+    }
+    Ok(())
+}
+
+// Runs one configured device until the app shuts down. A deck that cannot be
+// opened is handed back and waited for again, keeping what the app sends
+// meanwhile.
+fn drive_device(
+    config: &HardwareConfig,
+    device: &DeviceConfig,
+    mut selected: Option<SelectedDevice>,
+    claimed: &Mutex<BTreeSet<String>>,
+    events: &EventSink,
+    command_rx: &Receiver<HardwareCommand>,
+) {
+    let mut retained = None;
+    loop {
+        let found = match selected.take() {
+            Some(found) => found,
+            None => {
+                let state = retained
+                    .get_or_insert_with(|| DeckState::new(config, LATE_DEVICE_CAPABILITIES));
+                match wait_for_late_device(config, device, claimed, state, command_rx) {
+                    Some(found) => found,
+                    None => return,
+                }
+            }
+        };
+        match run_device(config, &found, &mut retained, events, command_rx) {
+            Ok(()) => return,
+            Err(err) => {
+                warn!(error = %err, role = %device.role, "Stream Deck loop ended");
+                if let Ok(mut claimed) = claimed.lock() {
+                    claimed.remove(&found.serial);
+                }
+            }
+        }
+    }
+}
+
+// Waits for a deck matching `device` that no other device has claimed,
+// recording what the app sends meanwhile into `state`. Returns `None` once the
+// app has shut down.
+fn wait_for_late_device(
+    config: &HardwareConfig,
+    device: &DeviceConfig,
+    claimed: &Mutex<BTreeSet<String>>,
+    state: &mut DeckState,
+    command_rx: &Receiver<HardwareCommand>,
+) -> Option<SelectedDevice> {
+    let mut hid = None;
+    let mut found = None;
+    let device_appeared = || {
+        let hid = match hid.as_mut() {
+            Some(hid) => hid,
+            None => match new_hidapi() {
+                Ok(opened) => hid.insert(opened),
+                Err(err) => {
+                    debug!(error = %err, "failed to initialise hidapi");
+                    return false;
+                }
+            },
+        };
+        if let Err(err) = refresh_device_list(hid) {
+            debug!(error = %err, "failed to refresh HID device list");
+            return false;
+        }
+        let Ok(mut claimed) = claimed.lock() else {
+            return false;
+        };
+        let unclaimed: Vec<(Kind, String)> = list_devices(hid)
+            .into_iter()
+            .filter(|(_, serial)| !claimed.contains(serial))
+            .collect();
+        match select_device(&unclaimed, &device.serial, config.allow_non_plus) {
+            Ok(selected) => {
+                claimed.insert(selected.serial.clone());
+                found = Some(selected);
+                true
+            }
+            Err(_) => false,
+        }
+    };
+    if !wait_for_device(device_appeared, LATE_DEVICE_CAPABILITIES, state, command_rx) {
+        return None;
+    }
+    let selected = found?;
+    info!(serial = %selected.serial, role = %device.role, "Stream Deck plugged in");
+    Some(selected)
+}
+
+fn spawn_headless(events: EventSink, command_rx: Receiver<HardwareCommand>) -> Result<()> {
+    thread::Builder::new()
+        .name("streamdeck-headless".into())
+        .spawn(move || run_headless(events, command_rx))
+        .context("failed to spawn headless hardware backend")?;
+    Ok(())
+}
+
+// Returns once the app has shut down, or with an error when the deck could not
+// be opened; the caller goes headless either way.
+fn run_device(
+    config: &HardwareConfig,
+    selected: &SelectedDevice,
+    retained: &mut Option<DeckState>,
+    events: &EventSink,
+    command_rx: &Receiver<HardwareCommand>,
+) -> Result<()> {
+    let mut hid = new_hidapi().context("failed to initialise hidapi")?;
+    let capabilities = DeviceCapabilities::from_kind(selected.kind);
+    info!(kind = ?selected.kind, serial = %selected.serial, "connecting to Stream Deck");
+
+    let deck = connect_deck(selected, || {
+        StreamDeck::connect(&hid, selected.kind, &selected.serial)
+    })
+    .with_context(|| {
+        format!(
+            "failed to connect to Stream Deck {}; waiting for it to be plugged in again",
+            selected.serial
+        )
+    })?;
+    info!(serial = %selected.serial, "Stream Deck connection established");
+
+    let late = retained.is_some();
+    let mut state = match retained.take() {
+        Some(mut state) => {
+            state.fit(capabilities);
+            state.backlight.note_input(Instant::now());
+            state
+        }
+        None => DeckState::new(config, capabilities),
+    };
+    let initialized = (|| -> Result<()> {
+        if capabilities.lcd_strip {
+            render::flush_strip(&deck, &state.displays)?;
+        }
+        render::initialize_button_placeholders(&deck, &mut state.button_icons)
+    })();
+    if let Err(err) = initialized {
+        warn!(error = %err, "failed to initialise Stream Deck displays");
+    }
+    if late {
+        events.send(HardwareEvent::DeviceConnected);
+    }
+
+    let mut deck = Some(deck);
+    loop {
+        if let Some(connected) = deck.take() {
+            let err = run_session(
+                &connected,
+                config,
+                capabilities,
+                &mut state,
+                events,
+                command_rx,
+            );
+            info!(
+                error = %err,
+                serial = %selected.serial,
+                "Stream Deck disconnected; waiting for it to reappear"
+            );
+            events.send(HardwareEvent::DeviceDisconnected);
+        }
+
+        let device_present = || {
+            if let Err(err) = refresh_device_list(&mut hid) {
+                debug!(error = %err, "failed to refresh HID device list");
+                return false;
+            }
+            list_devices(&hid)
+                .iter()
+                .any(|(kind, serial)| *kind == selected.kind && *serial == selected.serial)
+        };
+        if !wait_for_device(device_present, capabilities, &mut state, command_rx) {
+            return Ok(());
+        }
+
+        let reconnected = connect_deck(selected, || {
+            StreamDeck::connect(&hid, selected.kind, &selected.serial)
+        });
+        match reconnected.and_then(|connected| {
+            restore_displays(
+                &connected,
+                capabilities,
+                &state.displays,
+                &state.button_icons,
+                &mut state.backlight,
+            )?;
+            Ok(connected)
+        }) {
+            Ok(connected) => {
+                info!(serial = %selected.serial, "Stream Deck reconnected");
+                events.send(HardwareEvent::DeviceConnected);
+                deck = Some(connected);
+            }
+            Err(err) => warn!(error = %err, "failed to reconnect to Stream Deck; retrying"),
+        }
+    }
+}
+
+fn connect_deck(
+    selected: &SelectedDevice,
+    connect: impl Fn() -> Result<StreamDeck, StreamDeckError>,
+) -> Result<StreamDeck> {
+    let mut permission_warned = false;
+    loop {
+        match connect() {
+            Ok(deck) => return Ok(deck),
+            Err(err) if is_permission_denied(&err) => {
+                if !permission_warned {
+                    warn!(
+                        error = %err,
+                        serial = %selected.serial,
+                        "permission denied opening Stream Deck; check udev rules or group membership. Retrying in 2s"
+                    );
+                    permission_warned = true;
+                }
+                thread::sleep(RECONNECT_INTERVAL);
+            }
+            Err(err) => return Err(anyhow!(err).context("failed to open Stream Deck")),
+        }
+    }
+}
+
+// Runs until the device stops answering and returns the error that ended it.
+// Keys and encoders still held at that point are reported as released so the
+// app never waits on a release that cannot arrive.
+fn run_session(
+    deck: &StreamDeck,
+    config: &HardwareConfig,
+    capabilities: DeviceCapabilities,
+    state: &mut DeckState,
+    events: &EventSink,
+    command_rx: &Receiver<HardwareCommand>,
+) -> anyhow::Error {
+    let DeckState {
+        displays,
+        button_icons,
+        backlight,
+    } = state;
+    let mut encoder_press_state = [false; 4];
+    let mut button_press_state = vec![false; capabilities.key_count as usize];
+    let mut animations = ButtonAnimations::new(button_icons, Instant::now());
+    let mut feedback = PressFeedback::default();
+    let mut pending = PendingFlush::default();
+    let mut tweens = ValueTweens::new(config.value_animation.filter(|_| capabilities.lcd_strip));
+    let mut banner = StripBanner::default();
+    let mut metrics = BackendMetrics::new(Instant::now());
+    config
+        .metrics
+        .devices_connected
+        .fetch_add(1, Ordering::Relaxed);
+
+    let result = (|| -> Result<()> {
+        loop {
+            // Drain command queue first to keep UI responsive
+            let before = tweens.shown(displays, Instant::now());
+            let drained = process_commands(
+                capabilities,
+                displays,
+                button_icons,
+                &mut animations,
+                &mut pending,
+                &mut metrics,
+                command_rx,
+            );
+            if let Some(level) = drained.device_brightness {
+                backlight.set_level(level);
+            }
+            if drained.cleared {
+                banner.dismiss();
+            }
+            if drained.dismissed {
+                banner.end(&mut pending);
+            }
+            if let Some(shown) = drained.banner {
+                banner.show(shown, &mut pending);
+            }
+            pending.mark_segments(tweens.start(
+                &before,
+                displays,
+                drained.segments,
+                Instant::now(),
+            ));
+            if let Some(done) = drained.exit {
+                // Skips any write backoff and undoes idle dimming or a
+                // brightness the app set, leaving the deck as configured.
+                *backlight = Backlight::new(config, Instant::now());
+                let written = backlight.apply(deck, Instant::now()).and_then(|()| {
+                    pending.write(
+                        deck,
+                        capabilities,
+                        displays,
+                        button_icons,
+                        &[],
+                        &mut metrics,
+                    )
+                });
+                if let Err(err) = written {
+                    warn!(error = %err, "failed to blank Stream Deck before exit");
+                }
+                done.send(()).ok();
+            }
+
+            let now = Instant::now();
+            backlight.apply(deck, now)?;
+            metrics.log_if_due(now);
+            pending.mark_buttons(animations.advance(button_icons, now));
+            pending.mark_buttons(feedback.redraws(button_icons, now));
+            pending.mark_segments(tweens.advance(now));
+            banner.advance(&mut pending, now);
+            if pending.is_due(now) {
+                let frame = tweens.frame(displays, now);
+                let outcome = pending.write(
+                    deck,
+                    capabilities,
+                    frame.as_ref().unwrap_or(displays),
+                    button_icons,
+                    &feedback.highlighted(),
+                    &mut metrics,
+                );
+                if outcome.is_err() {
+                    config
+                        .metrics
+                        .flush_failures
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+                pending.settle(outcome, config.max_flush_failures, now)?;
+            }
+            let poll = [
+                animations.next_due(),
+                feedback.next_due(),
+                tweens.next_due(),
+                banner.next_due(),
+                pending.retry_at,
+            ]
+            .into_iter()
+            .flatten()
+            .min()
+            .map_or(INPUT_POLL_INTERVAL, |due| {
+                due.saturating_duration_since(now)
+                    .clamp(Duration::from_millis(1), INPUT_POLL_INTERVAL)
+            });
+
+            match deck.read_input(Some(poll)) {
+                Ok(StreamDeckInput::NoData) => {}
+                Ok(input) => {
+                    backlight.note_input(Instant::now());
+                    handle_input(
+                        input,
+                        capabilities,
+                        &mut encoder_press_state,
+                        &mut button_press_state,
+                        &mut feedback,
+                        events,
+                    )?
+                }
+                Err(err) => handle_input_error(err)?,
+            }
+        }
+    })();
+    config
+        .metrics
+        .devices_connected
+        .fetch_sub(1, Ordering::Relaxed);
+
+    for (index, pressed) in encoder_press_state.iter().enumerate() {
+        if let Some(encoder) = EncoderId::from_index(index).filter(|_| *pressed) {
+            events.send(HardwareEvent::EncoderReleased { encoder });
+        }
+    }
+    for (index, pressed) in button_press_state.iter().enumerate() {
+        if *pressed {
+            events.send(HardwareEvent::ButtonReleased(index as u8));
+        }
+    }
+
+    match result {
+        Ok(()) => anyhow!("stream deck session ended"),
+        Err(err) => err,
+    }
+}
+
+// Polls for the device while keeping the display state current. Returns false
+// once the app has shut down and no reconnect is wanted.
+fn wait_for_device(
+    mut device_present: impl FnMut() -> bool,
+    capabilities: DeviceCapabilities,
+    state: &mut DeckState,
+    command_rx: &Receiver<HardwareCommand>,
+) -> bool {
+    loop {
+        let deadline = Instant::now() + RECONNECT_INTERVAL;
+        loop {
+            match command_rx.recv_deadline(deadline) {
+                Ok(command) => state.record(command, capabilities),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+
+        if device_present() {
+            return true;
+        }
+    }
+}
+
+fn restore_displays(
+    deck: &StreamDeck,
+    capabilities: DeviceCapabilities,
+    displays: &[Option<EncoderDisplay>; 4],
+    button_icons: &[Option<ButtonImage>],
+    backlight: &mut Backlight,
+) -> Result<()> {
+    backlight.invalidate();
+    backlight.apply(deck, Instant::now())?;
+    if capabilities.lcd_strip {
+        render::flush_strip(deck, displays)?;
+    }
+    let all: Vec<u8> = (0..button_icons.len()).map(|index| index as u8).collect();
+    render::flush_buttons(deck, button_icons, &all, &[])
+}
+
+fn process_commands(
+    capabilities: DeviceCapabilities,
+    displays: &mut [Option<EncoderDisplay>; 4],
+    button_icons: &mut [Option<ButtonImage>],
+    animations: &mut ButtonAnimations,
+    pending: &mut PendingFlush,
+    metrics: &mut BackendMetrics,
+    command_rx: &Receiver<HardwareCommand>,
+) -> Drained {
+    let started = Instant::now();
+    let mut drained = Drained::default();
+    let mut segments_changed = [false; 4];
+    let mut buttons_changed: Vec<u8> = Vec::new();
+    while let Ok(command) = command_rx.try_recv() {
+        drained.any = true;
+        let (index, icon) = match command {
+            HardwareCommand::UpdateEncoderDisplay { encoder, display } => {
+                displays[encoder.index()] = Some(display);
+                segments_changed[encoder.index()] = true;
+                continue;
+            }
+            HardwareCommand::ClearEncoderDisplay(encoder) => {
+                displays[encoder.index()] = None;
+                segments_changed[encoder.index()] = true;
+                continue;
+            }
+            HardwareCommand::UpdateButtonIcon { index, icon } => (index, icon),
+            HardwareCommand::ClearButton(index) => (index, None),
+            HardwareCommand::ClearAll => {
+                for display in displays.iter_mut() {
+                    *display = None;
+                }
+                for icon in button_icons.iter_mut() {
+                    *icon = None;
+                }
+                let key_count = button_icons.len();
+                animations.restart(button_icons, 0..key_count, Instant::now());
+                pending.reset();
+                segments_changed = [false; 4];
+                buttons_changed.clear();
+                drained.cleared = true;
+                drained.banner = None;
+                continue;
+            }
+            HardwareCommand::SetDeviceBrightness(level) => {
+                drained.device_brightness = Some(level);
+                continue;
+            }
+            HardwareCommand::QueryStats(reply) => {
+                reply.send(metrics.snapshot(Instant::now())).ok();
+                continue;
+            }
+            HardwareCommand::ShowBanner {
+                text,
+                duration_ms,
+                color,
+            } => {
+                if capabilities.lcd_strip {
+                    drained.banner = Some(Banner {
+                        text,
+                        color,
+                        until: Instant::now() + Duration::from_millis(duration_ms),
+                    });
+                }
+                continue;
+            }
+            HardwareCommand::DismissBanner => {
+                drained.banner = None;
+                drained.dismissed = true;
+                continue;
+            }
+            // Stops draining, so updates still flooding in from other
+            // threads cannot hold up the exit.
+            HardwareCommand::FlushForExit(done) => {
+                drained.exit = Some(done);
+                break;
+            }
+        };
+        if let Some(slot) = button_icons.get_mut(index as usize) {
+            *slot = icon;
+            buttons_changed.push(index);
+        } else {
+            warn!(index, "ignoring button icon update for out-of-range index");
+        }
+    }
+
+    if capabilities.lcd_strip {
+        pending.mark_segments(segments_changed);
+        drained.segments = segments_changed;
+    }
+
+    if !buttons_changed.is_empty() {
+        animations.restart(
+            button_icons,
+            buttons_changed.iter().map(|index| *index as usize),
+            Instant::now(),
+        );
+        pending.mark_buttons(buttons_changed);
+    }
+    if drained.any {
+        let now = Instant::now();
+        metrics.command_drain.record(now, now - started);
+    }
+    drained
+}
+
+#[derive(Debug, Default)]
+struct Drained {
+    any: bool,
+    /// Strip segments given a new display, or cleared.
+    segments: [bool; 4],
+    /// Set by `ClearAll`, which also takes down a banner.
+    cleared: bool,
+    /// A banner shown before this drain is to come down now.
+    dismissed: bool,
+    banner: Option<Banner>,
+    device_brightness: Option<u8>,
+    exit: Option<Sender<()>>,
+}
+
+/// Durations recorded within the last `STATS_WINDOW`.
+#[derive(Debug, Default)]
+struct RollingTimings {
+    samples: VecDeque<(Instant, Duration)>,
+}
+
+impl RollingTimings {
+    fn record(&mut self, now: Instant, elapsed: Duration) {
+        self.prune(now);
+        self.samples.push_back((now, elapsed));
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > STATS_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn summary(&self, now: Instant) -> TimingStats {
+        let recent = self
+            .samples
+            .iter()
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= STATS_WINDOW)
+            .map(|(_, elapsed)| *elapsed);
+        let (count, total, max) = recent.fold(
+            (0usize, Duration::ZERO, Duration::ZERO),
+            |(count, total, max), elapsed| (count + 1, total + elapsed, max.max(elapsed)),
+        );
+        TimingStats {
+            count,
+            mean: if count == 0 {
+                Duration::ZERO
+            } else {
+                total / count as u32
+            },
+            max,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BackendMetrics {
+    strip_flush: RollingTimings,
+    button_flush: RollingTimings,
+    command_drain: RollingTimings,
+    last_logged: Instant,
+}
+
+impl BackendMetrics {
+    fn new(now: Instant) -> Self {
+        Self {
+            strip_flush: RollingTimings::default(),
+            button_flush: RollingTimings::default(),
+            command_drain: RollingTimings::default(),
+            last_logged: now,
+        }
+    }
+
+    fn snapshot(&self, now: Instant) -> BackendStats {
+        BackendStats {
+            strip_flush: self.strip_flush.summary(now),
+            button_flush: self.button_flush.summary(now),
+            command_drain: self.command_drain.summary(now),
+        }
+    }
+
+    fn log_if_due(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_logged) < STATS_WINDOW {
+            return;
+        }
+        self.last_logged = now;
+        if !tracing::enabled!(Level::DEBUG) {
+            return;
+        }
+        let stats = self.snapshot(now);
+        debug!(
+            strip_flushes = stats.strip_flush.count,
+            strip_mean_us = stats.strip_flush.mean.as_micros() as u64,
+            strip_max_us = stats.strip_flush.max.as_micros() as u64,
+            button_flushes = stats.button_flush.count,
+            button_mean_us = stats.button_flush.mean.as_micros() as u64,
+            button_max_us = stats.button_flush.max.as_micros() as u64,
+            drains = stats.command_drain.count,
+            drain_mean_us = stats.command_drain.mean.as_micros() as u64,
+            drain_max_us = stats.command_drain.max.as_micros() as u64,
+            "hardware backend timings over the last minute"
+        );
+    }
+}
+
+/// Display writes that have not reached the device yet. A failed write stays
+/// pending and is retried with exponential backoff, so a transient USB error
+/// costs a late frame rather than the session.
+#[derive(Debug, Default)]
+struct PendingFlush {
+    clear: bool,
+    banner: Option<Banner>,
+    /// Strip segments whose display changed since the last write.
+    strip: [bool; 4],
+    buttons: Vec<u8>,
+    failures: u32,
+    retry_at: Option<Instant>,
+    written: render::StripCache,
+}
+
+impl PendingFlush {
+    fn reset(&mut self) {
+        self.clear = true;
+        self.written.invalidate();
+        self.banner = None;
+        self.strip = [false; 4];
+        self.buttons.clear();
+    }
+
+    fn mark_segments(&mut self, segments: [bool; 4]) {
+        for (pending, changed) in self.strip.iter_mut().zip(segments) {
+            *pending |= changed;
+        }
+    }
+
+    fn mark_buttons(&mut self, indices: impl IntoIterator<Item = u8>) {
+        for index in indices {
+            if !self.buttons.contains(&index) {
+                self.buttons.push(index);
+            }
+        }
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        let has_work = self.clear
+            || self.banner.is_some()
+            || self.strip.contains(&true)
+            || !self.buttons.is_empty();
+        has_work && self.retry_at.is_none_or(|retry_at| now >= retry_at)
+    }
+
+    // Each part is dropped from the queue as soon as it succeeds, so a retry
+    // resumes where the failed attempt stopped.
+    fn write(
+        &mut self,
+        deck: &StreamDeck,
+        capabilities: DeviceCapabilities,
+        displays: &[Option<EncoderDisplay>; 4],
+        button_icons: &[Option<ButtonImage>],
+        pressed: &[bool],
+        metrics: &mut BackendMetrics,
+    ) -> Result<()> {
+        if self.clear {
+            render::clear_buttons(deck)?;
+            if capabilities.lcd_strip {
+                render::clear_strip(deck)?;
+            }
+            self.clear = false;
+        }
+        if let Some(banner) = &self.banner {
+            render::flush_banner(deck, &banner.text, banner.color)?;
+            self.written.invalidate();
+            self.banner = None;
+        }
+        if self.strip.contains(&true) {
+            let started = Instant::now();
+            let segments =
+                render::flush_strip_segments(deck, displays, &self.strip, &mut self.written)?;
+            let elapsed = started.elapsed();
+            debug!(
+                segments,
+                elapsed_us = elapsed.as_micros() as u64,
+                "encoded and wrote LCD strip"
+            );
+            metrics.strip_flush.record(Instant::now(), elapsed);
+            self.strip = [false; 4];
+        }
+        if !self.buttons.is_empty() {
+            let started = Instant::now();
+            render::flush_buttons(deck, button_icons, &self.buttons, pressed)?;
+            metrics
+                .button_flush
+                .record(Instant::now(), started.elapsed());
+            self.buttons.clear();
+        }
+        Ok(())
+    }
+
+    /// Returns the error once `max_failures` writes in a row have failed.
+    fn settle(&mut self, outcome: Result<()>, max_failures: u32, now: Instant) -> Result<()> {
+        let err = match outcome {
+            Ok(()) => {
+                if self.failures > 0 {
+                    info!(
+                        failures = self.failures,
+                        "Stream Deck display writes recovered"
+                    );
+                }
+                self.failures = 0;
+                self.retry_at = None;
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+
+        self.failures += 1;
+        if self.failures >= max_failures {
+            return Err(err.context(format!(
+                "display writes failed {} times in a row",
+                self.failures
+            )));
+        }
+        let backoff = flush_backoff(self.failures);
+        warn!(
+            error = %err,
+            failures = self.failures,
+            retry_in_ms = backoff.as_millis() as u64,
+            "failed to update Stream Deck displays; retrying"
+        );
+        self.retry_at = Some(now + backoff);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Banner {
+    text: String,
+    color: [u8; 3],
+    until: Instant,
+}
+
+/// A full-width message over the strip. Segment updates meanwhile only land
+/// in `displays`, and the whole strip is written back from there once the
+/// banner ends.
+#[derive(Debug, Default)]
+struct StripBanner {
+    shown: Option<Banner>,
+}
+
+impl StripBanner {
+    fn show(&mut self, banner: Banner, pending: &mut PendingFlush) {
+        pending.banner = Some(banner.clone());
+        self.shown = Some(banner);
+    }
+
+    fn dismiss(&mut self) {
+        self.shown = None;
+    }
+
+    fn end(&mut self, pending: &mut PendingFlush) {
+        if self.shown.take().is_some() {
+            pending.banner = None;
+            pending.mark_segments([true; 4]);
+        }
+    }
+
+    // Keeps segment writes from painting over the banner until it expires.
+    fn advance(&mut self, pending: &mut PendingFlush, now: Instant) {
+        match &self.shown {
+            Some(banner) if now >= banner.until => {
+                self.shown = None;
+                pending.banner = None;
+                pending.mark_segments([true; 4]);
+            }
+            Some(_) => pending.strip = [false; 4],
+            None => {}
+        }
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.shown.as_ref().map(|banner| banner.until)
+    }
+}
+
+fn flush_backoff(failures: u32) -> Duration {
+    let doublings = failures.saturating_sub(1).min(16);
+    (FLUSH_RETRY_BASE * 2u32.pow(doublings)).min(FLUSH_RETRY_MAX)
+}
+
+/// Progress bars easing towards a new reading. `displays` always holds the
+/// reading itself, so a reconnect or a newer update picks up from there; the
+/// frames in between only exist in what is written to the strip.
+#[derive(Debug)]
+struct ValueTweens {
+    animation: Option<ValueAnimation>,
+    segments: [Option<Tween>; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tween {
+    from: ShownValue,
+    started: Instant,
+    due: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ShownValue {
+    progress: f32,
+    percent: Option<f32>,
+}
+
+impl ShownValue {
+    fn of(display: &EncoderDisplay) -> Option<Self> {
+        Some(Self {
+            progress: display.progress?,
+            percent: parse_percent(&display.value),
+        })
+    }
+}
+
+impl ValueTweens {
+    fn new(animation: Option<ValueAnimation>) -> Self {
+        Self {
+            animation,
+            segments: [None; 4],
+        }
+    }
+
+    /// What each segment shows at `now`, mid-animation or not.
+    fn shown(
+        &self,
+        displays: &[Option<EncoderDisplay>; 4],
+        now: Instant,
+    ) -> [Option<ShownValue>; 4] {
+        std::array::from_fn(|index| {
+            let target = ShownValue::of(displays[index].as_ref()?)?;
+            Some(match self.segments[index] {
+                Some(tween) => self.interpolate(tween, target, now),
+                None => target,
+            })
+        })
+    }
+
+    /// Starts animating a lone segment whose progress jumped, continuing
+    /// from whatever it showed. A batch touching several segments is drawn
+    /// as is and stops animations in flight, so a full-strip flush is never
+    /// followed by more frames. Returns the segments to redraw.
+    fn start(
+        &mut self,
+        before: &[Option<ShownValue>; 4],
+        displays: &[Option<EncoderDisplay>; 4],
+        changed: [bool; 4],
+        now: Instant,
+    ) -> [bool; 4] {
+        let Some(animation) = self.animation else {
+            return [false; 4];
+        };
+        match changed.iter().filter(|changed| **changed).count() {
+            0 => return [false; 4],
+            1 => {}
+            _ => {
+                let stopped = self.segments.map(|tween| tween.is_some());
+                self.segments = [None; 4];
+                return stopped;
+            }
+        }
+        let Some(index) = changed.iter().position(|changed| *changed) else {
+            return [false; 4];
+        };
+        let target = displays[index].as_ref().and_then(ShownValue::of);
+        self.segments[index] = match (before[index], target) {
+            (Some(from), Some(to)) if (to.progress - from.progress).abs() > animation.min_delta => {
+                Some(Tween {
+                    from,
+                    started: now,
+                    due: now + VALUE_ANIMATION_FRAME,
+                })
+            }
+            _ => None,
+        };
+        [false; 4]
+    }
+
+    /// Steps animations whose next frame is due and returns the segments to
+    /// redraw; an animation past its duration ends with the reading itself.
+    fn advance(&mut self, now: Instant) -> [bool; 4] {
+        let Some(animation) = self.animation else {
+            return [false; 4];
+        };
+        let mut redraw = [false; 4];
+        for (index, segment) in self.segments.iter_mut().enumerate() {
+            let Some(tween) = segment.as_mut().filter(|tween| tween.due <= now) else {
+                continue;
+            };
+            if now.saturating_duration_since(tween.started) >= animation.duration {
+                *segment = None;
+            } else {
+                tween.due = now + VALUE_ANIMATION_FRAME;
+            }
+            redraw[index] = true;
         }
+        redraw
     }
 
-    pub fn with_status(mut self, status: impl Into<String>) -> Self {
-        self.status = Some(status.into());
-        self
+    /// The displays to draw at `now`, or `None` when nothing is animating.
+    fn frame(
+        &self,
+        displays: &[Option<EncoderDisplay>; 4],
+        now: Instant,
+    ) -> Option<[Option<EncoderDisplay>; 4]> {
+        if self.segments.iter().all(Option::is_none) {
+            return None;
+        }
+        let mut frame = displays.clone();
+        for (display, tween) in frame.iter_mut().zip(self.segments) {
+            let (Some(display), Some(tween)) = (display.as_mut(), tween) else {
+                continue;
+            };
+            let Some(target) = ShownValue::of(display) else {
+                continue;
+            };
+            let shown = self.interpolate(tween, target, now);
+            display.progress = Some(shown.progress);
+            if let Some(percent) = shown.percent {
+                let width = display.value.trim_end_matches('%').len();
+                display.value = format!("{percent:>width$.0}%");
+            }
+        }
+        Some(frame)
     }
 
-    pub fn with_progress(mut self, progress: f32) -> Self {
-        self.progress = Some(progress.clamp(0.0, 1.0));
-        self
+    fn next_due(&self) -> Option<Instant> {
+        self.segments.iter().flatten().map(|tween| tween.due).min()
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct ButtonImage {
-    pub id: String,
-    pub image: Arc<RgbaImage>,
-    pub tint: Option<[u8; 3]>,
+    // Eases out, so most of the distance is covered in the first frames.
+    fn interpolate(&self, tween: Tween, target: ShownValue, now: Instant) -> ShownValue {
+        let duration = self
+            .animation
+            .map_or(Duration::ZERO, |animation| animation.duration);
+        let elapsed = now.saturating_duration_since(tween.started);
+        if elapsed >= duration {
+            return target;
+        }
+        let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let lerp = |from: f32, to: f32| from + (to - from) * eased;
+        ShownValue {
+            progress: lerp(tween.from.progress, target.progress),
+            percent: tween
+                .from
+                .percent
+                .zip(target.percent)
+                .map(|(from, to)| lerp(from, to)),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum HardwareEvent {
-    EncoderTurned { encoder: EncoderId, delta: i32 },
-    EncoderPressed { encoder: EncoderId },
-    EncoderReleased { encoder: EncoderId },
-    ButtonPressed(u8),
-    ButtonReleased(u8),
-    Touch,
+// Values drawn as a whole percentage, like " 40%".
+fn parse_percent(value: &str) -> Option<f32> {
+    value.trim().strip_suffix('%')?.trim().parse().ok()
 }
 
-pub trait DisplayPipeline: Send + Sync {
-    fn update_encoder(&self, encoder: EncoderId, display: EncoderDisplay) -> Result<()>;
-    fn update_button_icon(&self, _index: u8, _icon: Option<ButtonImage>) -> Result<()> {
-        Ok(())
-    }
+/// Frame position of every animated key. Only a connected session drives it,
+/// so animations stand still while headless or waiting for a replug.
+struct ButtonAnimations {
+    keys: Vec<Option<KeyAnimation>>,
 }
 
-#[derive(Clone)]
-pub struct HardwareHandle {
-    command_tx: Sender<HardwareCommand>,
+struct KeyAnimation {
+    frame: usize,
+    due: Instant,
 }
 
-enum HardwareCommand {
-    UpdateEncoderDisplay {
-        encoder: EncoderId,
-        display: EncoderDisplay,
-    },
-    UpdateButtonIcon {
-        index: u8,
-        icon: Option<ButtonImage>,
-    },
-    ResetDisplays,
-}
+impl ButtonAnimations {
+    fn new(button_icons: &mut [Option<ButtonImage>], now: Instant) -> Self {
+        let mut animations = Self { keys: Vec::new() };
+        let key_count = button_icons.len();
+        animations.restart(button_icons, 0..key_count, now);
+        animations
+    }
 
-impl DisplayPipeline for HardwareHandle {
-    fn update_encoder(&self, encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
-        self.command_tx
-            .send(HardwareCommand::UpdateEncoderDisplay { encoder, display })
-            .map_err(|err| anyhow!("hardware command channel closed: {err}"))
+    // Called for keys whose icon was replaced; each starts from its first frame.
+    fn restart(
+        &mut self,
+        button_icons: &mut [Option<ButtonImage>],
+        indices: impl IntoIterator<Item = usize>,
+        now: Instant,
+    ) {
+        self.keys.resize_with(button_icons.len(), || None);
+        for index in indices {
+            let Some(icon) = button_icons.get_mut(index) else {
+                continue;
+            };
+            self.keys[index] = icon
+                .as_mut()
+                .and_then(|icon| {
+                    let animation = icon.animation.as_ref()?;
+                    icon.image = Arc::clone(animation.frames.first()?);
+                    animation.delays.first().copied()
+                })
+                .map(|delay| KeyAnimation {
+                    frame: 0,
+                    due: now + delay,
+                });
+        }
     }
 
-    fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
-        self.command_tx
-            .send(HardwareCommand::UpdateButtonIcon { index, icon })
-            .map_err(|err| anyhow!("hardware command channel closed: {err}"))
+    /// Moves every key whose frame has expired on by one frame and returns
+    /// the keys that need to be redrawn.
+    fn advance(&mut self, button_icons: &mut [Option<ButtonImage>], now: Instant) -> Vec<u8> {
+        let mut advanced = Vec::new();
+        for (index, key) in self.keys.iter_mut().enumerate() {
+            let Some(state) = key.as_mut().filter(|state| state.due <= now) else {
+                continue;
+            };
+            let Some(icon) = button_icons.get_mut(index).and_then(Option::as_mut) else {
+                *key = None;
+                continue;
+            };
+            let Some(animation) = icon.animation.as_ref() else {
+                *key = None;
+                continue;
+            };
+            state.frame = (state.frame + 1) % animation.frames.len();
+            let delay = animation.delays[state.frame];
+            // Keep the cadence steady, but do not replay missed frames after a stall.
+            let next = state.due + delay;
+            state.due = if next < now { now + delay } else { next };
+            icon.image = Arc::clone(&animation.frames[state.frame]);
+            advanced.push(index as u8);
+        }
+        advanced
     }
-}
 
-impl HardwareHandle {
-    pub fn clear_all_displays(&self) -> Result<()> {
-        self.command_tx
-            .send(HardwareCommand::ResetDisplays)
-            .map_err(|err| anyhow!("hardware command channel closed: {err}"))
+    fn next_due(&self) -> Option<Instant> {
+        self.keys.iter().flatten().map(|state| state.due).min()
     }
 }
 
-pub fn start(config: HardwareConfig) -> Result<(HardwareHandle, Receiver<HardwareEvent>)> {
-    let (event_tx, event_rx) = crossbeam_channel::unbounded();
-    let (command_tx, command_rx) = crossbeam_channel::unbounded();
-
-    thread::Builder::new()
-        .name("streamdeck-backend".into())
-        .spawn(move || {
-            if let Err(err) = run_backend(config, event_tx, command_rx) {
-                error!(error = %err, "hardware backend terminated");
-            }
-        })
-        .context("failed to spawn hardware backend")?;
-
-    Ok((HardwareHandle { command_tx }, event_rx))
+/// Keys drawn highlighted after a press until they are released or
+/// `PRESS_FEEDBACK_DURATION` passes. The highlight is applied while
+/// rendering, so an icon pushed during the flash is what remains after it.
+#[derive(Debug, Default)]
+struct PressFeedback {
+    until: Vec<Option<Instant>>,
+    changed: Vec<u8>,
 }
 
-fn run_backend(
-    config: HardwareConfig,
-    event_tx: Sender<HardwareEvent>,
-    command_rx: Receiver<HardwareCommand>,
-) -> Result<()> {
-    let mut hid = new_hidapi().context("failed to initialise hidapi")?;
-    refresh_device_list(&mut hid).ok();
-
-    let devices = list_devices(&hid);
-    debug!(device_count = devices.len(), "found stream deck devices");
+impl PressFeedback {
+    fn press(&mut self, index: u8, now: Instant) {
+        let index = index as usize;
+        if index >= self.until.len() {
+            self.until.resize(index + 1, None);
+        }
+        self.until[index] = Some(now + PRESS_FEEDBACK_DURATION);
+        self.changed.push(index as u8);
+    }
 
-    let selected = match select_device(&devices, &config.serial) {
-        Ok(device) => device,
-        Err(err) => {
-            warn!(
-                error = %err,
-                "no Stream Deck detected; running hardware backend in headless mode"
-            );
-            return run_headless(event_tx, command_rx);
+    fn release(&mut self, index: u8) {
+        if let Some(Some(_)) = self.until.get_mut(index as usize).map(Option::take) {
+            self.changed.push(index);
         }
-    };
-    info!(kind = ?selected.kind, serial = %selected.serial, "connecting to Stream Deck Plus");
+    }
 
-    let mut permission_warned = false;
-    let deck = loop {
-        match StreamDeck::connect(&hid, selected.kind, &selected.serial) {
-            Ok(deck) => break deck,
-            Err(err) if is_permission_denied(&err) => {
-                if !permission_warned {
-                    warn!(
-                        error = %err,
-                        serial = %selected.serial,
-                        "permission denied opening Stream Deck; check udev rules or group membership. Retrying in 2s"
-                    );
-                    permission_warned = true;
-                }
-                thread::sleep(Duration::from_secs(2));
-                continue;
-            }
-            Err(err) => {
-                warn!(
-                    error = %err,
-                    serial = %selected.serial,
-                    "failed to connect to Stream Deck; running in headless mode"
-                );
-                return run_headless(event_tx, command_rx);
+    /// Ends expired highlights and returns the keys to redraw. Keys without
+    /// an icon are never highlighted.
+    fn redraws(&mut self, button_icons: &[Option<ButtonImage>], now: Instant) -> Vec<u8> {
+        for (index, until) in self.until.iter_mut().enumerate() {
+            let has_icon = button_icons.get(index).is_some_and(Option::is_some);
+            if !has_icon {
+                *until = None;
+            } else if until.is_some_and(|until| until <= now) {
+                *until = None;
+                self.changed.push(index as u8);
             }
         }
-    };
-    info!(serial = %selected.serial, "Stream Deck connection established");
-
-    deck.set_brightness(config.device_brightness)
-        .context("failed to set device brightness")?;
+        let mut changed = std::mem::take(&mut self.changed);
+        changed.retain(|index| {
+            button_icons
+                .get(*index as usize)
+                .is_some_and(Option::is_some)
+        });
+        changed
+    }
 
-    let mut displays: [Option<EncoderDisplay>; 4] = [None, None, None, None];
-    let mut button_icons = vec![None; selected.kind.key_count() as usize];
-    render::flush_strip(&deck, &displays)?;
-    render::initialize_button_placeholders(&deck, &mut button_icons)?;
+    fn highlighted(&self) -> Vec<bool> {
+        self.until.iter().map(Option::is_some).collect()
+    }
 
-    let mut encoder_press_state = [false; 4];
-    let mut button_press_state = vec![false; selected.kind.key_count() as usize];
+    fn next_due(&self) -> Option<Instant> {
+        self.until.iter().flatten().min().copied()
+    }
+}
 
-    loop {
-        // Drain command queue first to keep UI responsive
-        process_commands(&deck, &mut displays, &mut button_icons, &command_rx)?;
+/// Last-known device state, kept across reconnects so the LCD, keys and
+/// backlight can be restored without the controllers noticing the device
+/// went away.
+struct DeckState {
+    displays: [Option<EncoderDisplay>; 4],
+    button_icons: Vec<Option<ButtonImage>>,
+    backlight: Backlight,
+}
 
-        match deck.read_input(Some(Duration::from_millis(25))) {
-            Ok(input) => handle_input(
-                input,
-                &mut encoder_press_state,
-                &mut button_press_state,
-                &event_tx,
-            )?,
-            Err(err) => handle_input_error(err)?,
+impl DeckState {
+    fn new(config: &HardwareConfig, capabilities: DeviceCapabilities) -> Self {
+        Self {
+            displays: [None, None, None, None],
+            button_icons: vec![None; capabilities.key_count as usize],
+            backlight: Backlight::new(config, Instant::now()),
         }
     }
-}
 
-fn process_commands(
-    deck: &StreamDeck,
-    displays: &mut [Option<EncoderDisplay>; 4],
-    button_icons: &mut [Option<ButtonImage>],
-    command_rx: &Receiver<HardwareCommand>,
-) -> Result<()> {
-    let mut displays_changed = false;
-    let mut buttons_changed: Vec<u8> = Vec::new();
-    while let Ok(command) = command_rx.try_recv() {
+    // Nothing is written while the device is away; the next connection
+    // shows whatever is recorded here.
+    fn record(&mut self, command: HardwareCommand, capabilities: DeviceCapabilities) {
         match command {
             HardwareCommand::UpdateEncoderDisplay { encoder, display } => {
-                displays[encoder.index()] = Some(display);
-                displays_changed = true;
+                if capabilities.lcd_strip {
+                    self.displays[encoder.index()] = Some(display);
+                }
             }
             HardwareCommand::UpdateButtonIcon { index, icon } => {
-                if let Some(slot) = button_icons.get_mut(index as usize) {
+                if let Some(slot) = self.button_icons.get_mut(index as usize) {
                     *slot = icon;
-                    buttons_changed.push(index);
-                } else {
-                    warn!(index, "ignoring button icon update for out-of-range index");
                 }
             }
-            HardwareCommand::ResetDisplays => {
-                for display in displays.iter_mut() {
-                    *display = None;
-                }
-                for icon in button_icons.iter_mut() {
-                    *icon = None;
+            HardwareCommand::ClearEncoderDisplay(encoder) => self.displays[encoder.index()] = None,
+            HardwareCommand::ClearButton(index) => {
+                if let Some(slot) = self.button_icons.get_mut(index as usize) {
+                    *slot = None;
                 }
-                render::clear_buttons(deck)?;
-                render::clear_strip(deck)?;
-                displays_changed = false;
-                buttons_changed.clear();
-                continue;
+            }
+            HardwareCommand::ClearAll => {
+                self.displays.iter_mut().for_each(|display| *display = None);
+                self.button_icons.iter_mut().for_each(|icon| *icon = None);
+            }
+            HardwareCommand::SetDeviceBrightness(level) => self.backlight.set_level(level),
+            HardwareCommand::QueryStats(reply) => {
+                reply.send(BackendStats::default()).ok();
+            }
+            // Long gone by the time the deck is back.
+            HardwareCommand::ShowBanner { .. } | HardwareCommand::DismissBanner => {}
+            HardwareCommand::FlushForExit(done) => {
+                done.send(()).ok();
             }
         }
     }
 
-    if displays_changed {
-        render::flush_strip(deck, displays)?;
+    /// Trims state recorded before it was known which deck would turn up.
+    fn fit(&mut self, capabilities: DeviceCapabilities) {
+        self.button_icons
+            .resize(capabilities.key_count as usize, None);
+        if !capabilities.lcd_strip {
+            self.displays = [None, None, None, None];
+        }
     }
+}
 
-    if !buttons_changed.is_empty() {
-        render::flush_buttons(deck, button_icons, &buttons_changed)?;
+/// The deck's own backlight. Idle dimming never goes above the level set at
+/// runtime.
+#[derive(Debug)]
+struct Backlight {
+    level: u8,
+    idle_dim: Option<IdleDim>,
+    last_input: Instant,
+    applied: Option<u8>,
+}
+
+impl Backlight {
+    fn new(config: &HardwareConfig, now: Instant) -> Self {
+        Self {
+            level: config.device_brightness,
+            idle_dim: config.idle_dim,
+            last_input: now,
+            applied: None,
+        }
     }
 
-    Ok(())
+    fn set_level(&mut self, level: u8) {
+        self.level = level.min(100);
+    }
+
+    fn note_input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    fn invalidate(&mut self) {
+        self.applied = None;
+    }
+
+    fn target(&self, now: Instant) -> u8 {
+        match self.idle_dim {
+            Some(dim) if now.saturating_duration_since(self.last_input) >= dim.after => {
+                dim.brightness.min(self.level)
+            }
+            _ => self.level,
+        }
+    }
+
+    fn apply(&mut self, deck: &StreamDeck, now: Instant) -> Result<()> {
+        let target = self.target(now);
+        if self.applied == Some(target) {
+            return Ok(());
+        }
+        deck.set_brightness(target)
+            .context("failed to set device brightness")?;
+        debug!(
+            brightness = target,
+            dimmed = target != self.level,
+            "set device brightness"
+        );
+        self.applied = Some(target);
+        Ok(())
+    }
 }
 
 fn handle_input(
     input: StreamDeckInput,
+    capabilities: DeviceCapabilities,
     encoder_state: &mut [bool; 4],
     button_state: &mut Vec<bool>,
-    event_tx: &Sender<HardwareEvent>,
+    feedback: &mut PressFeedback,
+    events: &EventSink,
 ) -> Result<()> {
     match input {
         StreamDeckInput::NoData => {}
@@ -326,15 +2149,17 @@ fn handle_input(
                 if *previous != *state {
                     *previous = *state;
                     let event = if *state {
+                        feedback.press(index as u8, Instant::now());
                         HardwareEvent::ButtonPressed(index as u8)
                     } else {
+                        feedback.release(index as u8);
                         HardwareEvent::ButtonReleased(index as u8)
                     };
-                    event_tx.send(event).ok();
+                    events.send(event);
                 }
             }
         }
-        StreamDeckInput::EncoderStateChange(states) => {
+        StreamDeckInput::EncoderStateChange(states) if capabilities.has_encoders() => {
             for (index, state) in states.iter().enumerate().take(encoder_state.len()) {
                 let previous = &mut encoder_state[index];
                 if *previous != *state {
@@ -348,25 +2173,37 @@ fn handle_input(
                     } else {
                         HardwareEvent::EncoderReleased { encoder }
                     };
-                    event_tx.send(event).ok();
+                    events.send(event);
                 }
             }
         }
-        StreamDeckInput::EncoderTwist(deltas) => {
+        StreamDeckInput::EncoderTwist(deltas) if capabilities.has_encoders() => {
             for (index, delta) in deltas.iter().enumerate().take(encoder_state.len()) {
                 if *delta == 0 {
                     continue;
                 }
                 if let Some(encoder) = EncoderId::from_index(index) {
-                    event_tx
-                        .send(HardwareEvent::EncoderTurned {
-                            encoder,
-                            delta: i32::from(*delta),
-                        })
-                        .ok();
+                    events.send(HardwareEvent::EncoderTurned {
+                        encoder,
+                        delta: i32::from(*delta),
+                    });
+                }
+            }
+        }
+        // A long press is still a tap as far as the controllers are concerned.
+        StreamDeckInput::TouchScreenPress(x, y) | StreamDeckInput::TouchScreenLongPress(x, y)
+            if capabilities.lcd_strip =>
+        {
+            match EncoderId::from_strip_tap(x) {
+                Some(segment) => {
+                    events.send(HardwareEvent::TouchTap { x, y, segment });
                 }
+                None => debug!(x, "ignoring touch strip tap on a segment border"),
             }
         }
+        StreamDeckInput::TouchScreenSwipe((from_x, _), (to_x, _)) if capabilities.lcd_strip => {
+            events.send(HardwareEvent::TouchSwipe { from_x, to_x });
+        }
         other => {
             debug!("unhandled hardware input: {:?}", other);
         }
@@ -387,10 +2224,7 @@ fn handle_input_error(err: StreamDeckError) -> Result<()> {
     }
 }
 
-fn run_headless(
-    event_tx: Sender<HardwareEvent>,
-    command_rx: Receiver<HardwareCommand>,
-) -> Result<()> {
+fn run_headless(events: EventSink, command_rx: Receiver<HardwareCommand>) -> Result<()> {
     info!("hardware backend running without a connected Stream Deck");
 
     for command in command_rx.iter() {
@@ -401,13 +2235,24 @@ fn run_headless(
             HardwareCommand::UpdateButtonIcon { .. } => {
                 // Ignore button icon updates while headless
             }
-            HardwareCommand::ResetDisplays => {
+            HardwareCommand::ClearEncoderDisplay(_)
+            | HardwareCommand::ClearButton(_)
+            | HardwareCommand::ClearAll
+            | HardwareCommand::SetDeviceBrightness(_)
+            | HardwareCommand::ShowBanner { .. }
+            | HardwareCommand::DismissBanner => {
                 // Nothing to do in headless mode
             }
+            HardwareCommand::QueryStats(reply) => {
+                reply.send(BackendStats::default()).ok();
+            }
+            HardwareCommand::FlushForExit(done) => {
+                done.send(()).ok();
+            }
         }
     }
 
-    drop(event_tx);
+    drop(events);
     Ok(())
 }
 
@@ -427,26 +2272,40 @@ struct SelectedDevice {
     serial: String,
 }
 
-fn select_device(devices: &[(Kind, String)], serial: &Option<String>) -> Result<SelectedDevice> {
-    let missing_device_msg = "no Stream Deck Plus detected. Ensure the device is connected and you have permissions to access it.";
-    if !devices.iter().any(|(kind, _)| matches!(kind, Kind::Plus)) {
+fn select_device(
+    devices: &[(Kind, String)],
+    serial: &Option<String>,
+    allow_non_plus: bool,
+) -> Result<SelectedDevice> {
+    let accepted = |kind: &Kind| allow_non_plus || matches!(kind, Kind::Plus);
+    let missing_device_msg = if allow_non_plus {
+        "no Stream Deck detected. Ensure the device is connected and you have permissions to access it."
+    } else {
+        "no Stream Deck Plus detected. Ensure the device is connected and you have permissions to access it."
+    };
+    if !devices.iter().any(|(kind, _)| accepted(kind)) {
         return Err(anyhow!(missing_device_msg));
     }
 
     if let Some(serial_filter) = serial {
         let (kind, serial) = devices
             .iter()
-            .find(|(kind, s)| matches!(kind, Kind::Plus) && s == serial_filter)
-            .ok_or_else(|| anyhow!("no Stream Deck Plus with serial {serial_filter} was found"))?;
+            .find(|(kind, s)| accepted(kind) && s == serial_filter)
+            .ok_or_else(|| {
+                anyhow!("no supported Stream Deck with serial {serial_filter} was found")
+            })?;
         return Ok(SelectedDevice {
             kind: *kind,
             serial: serial.clone(),
         });
     }
 
+    // Prefer a Plus when several decks are attached; it is the only kind with
+    // encoders and an LCD strip.
     let (kind, serial) = devices
         .iter()
         .find(|(kind, _)| matches!(kind, Kind::Plus))
+        .or_else(|| devices.iter().find(|(kind, _)| accepted(kind)))
         .ok_or_else(|| anyhow!(missing_device_msg))?;
     Ok(SelectedDevice {
         kind: *kind,
@@ -454,8 +2313,738 @@ fn select_device(devices: &[(Kind, String)], serial: &Option<String>) -> Result<
     })
 }
 
+// Decks named by serial are claimed first; the rest take unclaimed decks in
+// configuration order, so the default device still prefers a Plus.
+fn assign_devices(
+    connected: &[(Kind, String)],
+    devices: &[DeviceConfig],
+    allow_non_plus: bool,
+) -> Vec<Result<SelectedDevice>> {
+    let mut unclaimed = connected.to_vec();
+    let mut assigned: Vec<Option<Result<SelectedDevice>>> = devices.iter().map(|_| None).collect();
+    let by_serial = (0..devices.len()).filter(|index| devices[*index].serial.is_some());
+    let by_order = (0..devices.len()).filter(|index| devices[*index].serial.is_none());
+    for index in by_serial.chain(by_order) {
+        let selected = select_device(&unclaimed, &devices[index].serial, allow_non_plus);
+        if let Ok(selected) = &selected {
+            unclaimed.retain(|(_, serial)| *serial != selected.serial);
+        }
+        assigned[index] = Some(selected);
+    }
+    assigned.into_iter().flatten().collect()
+}
+
 impl std::fmt::Debug for HardwareHandle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("HardwareHandle").finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> Vec<(Kind, String)> {
+        vec![
+            (Kind::Mk2, "MK2-SERIAL".to_string()),
+            (Kind::Plus, "PLUS-SERIAL".to_string()),
+        ]
+    }
+
+    #[test]
+    fn select_device_requires_plus_by_default() {
+        let mk2_only = vec![(Kind::Mk2, "MK2-SERIAL".to_string())];
+        assert!(select_device(&mk2_only, &None, false).is_err());
+
+        let selected = select_device(&mk2_only, &None, true).expect("mk2 accepted");
+        assert!(matches!(selected.kind, Kind::Mk2));
+    }
+
+    #[test]
+    fn select_device_prefers_plus_and_honours_serial() {
+        let selected = select_device(&devices(), &None, true).expect("device");
+        assert!(matches!(selected.kind, Kind::Plus));
+
+        let selected = select_device(&devices(), &Some("MK2-SERIAL".into()), true).expect("device");
+        assert_eq!(selected.serial, "MK2-SERIAL");
+        assert!(select_device(&devices(), &Some("MK2-SERIAL".into()), false).is_err());
+    }
+
+    fn link(role: &str, command_tx: Sender<HardwareCommand>) -> DeviceLink {
+        DeviceLink {
+            role: role.to_string(),
+            command_tx,
+            capabilities: None,
+            strip_theme: [SegmentTheme::default(); 4],
+            shown: Mutex::default(),
+        }
+    }
+
+    fn single_handle(command_tx: Sender<HardwareCommand>) -> HardwareHandle {
+        HardwareHandle::new(vec![link(DEFAULT_ROLE, command_tx)])
+    }
+
+    fn device(serial: Option<&str>, role: &str) -> DeviceConfig {
+        DeviceConfig {
+            serial: serial.map(str::to_string),
+            role: role.to_string(),
+        }
+    }
+
+    #[test]
+    fn devices_named_by_serial_are_claimed_before_the_rest() {
+        let connected = vec![
+            (Kind::Mk2, "MK2-SERIAL".to_string()),
+            (Kind::Plus, "PLUS-SERIAL".to_string()),
+            (Kind::Mini, "MINI-SERIAL".to_string()),
+        ];
+        let configured = [
+            device(None, "main"),
+            device(None, "extra"),
+            device(Some("PLUS-SERIAL"), "pinned"),
+            device(None, "missing"),
+        ];
+        let serials: Vec<_> = assign_devices(&connected, &configured, true)
+            .into_iter()
+            .map(|selected| selected.ok().map(|selected| selected.serial))
+            .collect();
+        assert_eq!(
+            serials,
+            [
+                Some("MK2-SERIAL".to_string()),
+                Some("MINI-SERIAL".to_string()),
+                Some("PLUS-SERIAL".to_string()),
+                None
+            ]
+        );
+
+        let legacy = HardwareConfig::default().device_configs();
+        let selected = assign_devices(&connected, &legacy, true);
+        assert_eq!(selected[0].as_ref().unwrap().serial, "PLUS-SERIAL");
+    }
+
+    #[test]
+    fn handles_address_their_own_device() {
+        let (main_tx, main_rx) = crossbeam_channel::unbounded();
+        let (extra_tx, extra_rx) = crossbeam_channel::unbounded();
+        let handle = HardwareHandle::new(vec![link("main", main_tx), link("extra", extra_tx)]);
+        assert_eq!(handle.device_id(None), Some(DeviceId(0)));
+        assert_eq!(handle.device_id(Some("extra")), Some(DeviceId(1)));
+        assert_eq!(handle.device_id(Some("other")), None);
+
+        let extra = handle.for_device(DeviceId(1)).unwrap();
+        extra.clear_button(3).unwrap();
+        handle.clear_button(5).unwrap();
+        assert!(matches!(
+            extra_rx.try_recv(),
+            Ok(HardwareCommand::ClearButton(3))
+        ));
+        assert!(matches!(
+            main_rx.try_recv(),
+            Ok(HardwareCommand::ClearButton(5))
+        ));
+        assert!(extra_rx.try_recv().is_err());
+        assert_eq!(handle.devices().len(), 2);
+        assert!(handle.for_device(DeviceId(2)).is_none());
+    }
+
+    #[test]
+    fn waiting_for_device_keeps_latest_display_state() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx);
+        let mut state = DeckState::new(&HardwareConfig::default(), SIMULATED_CAPABILITIES);
+
+        handle
+            .update_encoder(EncoderId::Two, EncoderDisplay::new("bright", "40%"))
+            .unwrap();
+        handle
+            .update_button_icon(
+                3,
+                Some(ButtonImage {
+                    id: "mic".into(),
+                    image: Arc::new(RgbaImage::new(1, 1)),
+                    tint: None,
+                    label: None,
+                    animation: None,
+                    background: None,
+                    badge: None,
+                }),
+            )
+            .unwrap();
+        handle.set_device_brightness(70).unwrap();
+        let shown = handle.shown();
+        assert_eq!(shown.encoders[1].as_ref().unwrap().value, "40%");
+        assert_eq!(shown.buttons.get(&3).map(String::as_str), Some("mic"));
+        let mut polls = 0;
+        assert!(wait_for_device(
+            || {
+                polls += 1;
+                true
+            },
+            SIMULATED_CAPABILITIES,
+            &mut state,
+            &command_rx,
+        ));
+
+        assert_eq!(polls, 1);
+        assert_eq!(state.displays[1].as_ref().unwrap().value, "40%");
+        assert_eq!(state.button_icons[3].as_ref().unwrap().id, "mic");
+        assert_eq!(state.backlight.level, 70);
+
+        drop(handle);
+        assert!(!wait_for_device(
+            || false,
+            SIMULATED_CAPABILITIES,
+            &mut state,
+            &command_rx,
+        ));
+    }
+
+    #[test]
+    fn clear_commands_blank_single_segments_and_keys() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx);
+        let mut displays: [Option<EncoderDisplay>; 4] = [
+            Some(EncoderDisplay::new("volume", "40%")),
+            Some(EncoderDisplay::new("bright", "80%")),
+            None,
+            None,
+        ];
+        let icon = ButtonImage {
+            id: "mic".into(),
+            image: Arc::new(RgbaImage::new(1, 1)),
+            tint: None,
+            label: None,
+            animation: None,
+            background: None,
+            badge: None,
+        };
+        let mut button_icons = vec![Some(icon.clone()), Some(icon), None];
+        let mut animations = ButtonAnimations::new(&mut button_icons, Instant::now());
+        let mut pending = PendingFlush::default();
+        let mut metrics = BackendMetrics::new(Instant::now());
+
+        handle.clear_encoder_display(EncoderId::Two).unwrap();
+        handle.clear_button(1).unwrap();
+        handle.set_device_brightness(20).unwrap();
+        handle.set_device_brightness(25).unwrap();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        assert_eq!(drained.device_brightness, Some(25));
+        assert!(displays[0].is_some() && displays[1].is_none());
+        assert!(button_icons[0].is_some() && button_icons[1].is_none());
+        assert_eq!(pending.strip, [false, true, false, false]);
+        assert!(!pending.clear);
+        assert_eq!(pending.buttons, vec![1]);
+
+        handle.clear_all_displays().unwrap();
+        process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        assert!(displays.iter().all(Option::is_none));
+        assert!(button_icons.iter().all(Option::is_none));
+        assert!(pending.clear && !pending.strip.contains(&true) && pending.buttons.is_empty());
+    }
+
+    #[test]
+    fn idle_dimming_caps_the_runtime_level_until_input() {
+        let start = Instant::now();
+        let config = HardwareConfig {
+            idle_dim: Some(IdleDim {
+                after: Duration::from_secs(60),
+                brightness: 10,
+            }),
+            ..Default::default()
+        };
+        let mut backlight = Backlight::new(&config, start);
+        assert_eq!(backlight.target(start + Duration::from_secs(59)), 40);
+        assert_eq!(backlight.target(start + Duration::from_secs(60)), 10);
+
+        backlight.note_input(start + Duration::from_secs(90));
+        assert_eq!(backlight.target(start + Duration::from_secs(91)), 40);
+
+        backlight.set_level(5);
+        assert_eq!(backlight.target(start + Duration::from_secs(200)), 5);
+        backlight.set_level(180);
+        assert_eq!(backlight.target(start + Duration::from_secs(91)), 100);
+
+        let always_on = Backlight::new(&HardwareConfig::default(), start);
+        assert_eq!(always_on.target(start + Duration::from_secs(3600)), 40);
+    }
+
+    #[test]
+    fn stats_cover_the_last_minute_and_answer_queries() {
+        let start = Instant::now();
+        let mut metrics = BackendMetrics::new(start);
+        metrics.strip_flush.record(start, Duration::from_millis(30));
+        metrics
+            .strip_flush
+            .record(start + Duration::from_secs(30), Duration::from_millis(10));
+        metrics
+            .button_flush
+            .record(start + Duration::from_secs(30), Duration::from_millis(4));
+
+        let stats = metrics.snapshot(start + Duration::from_secs(45));
+        assert_eq!(
+            stats.strip_flush,
+            TimingStats {
+                count: 2,
+                mean: Duration::from_millis(20),
+                max: Duration::from_millis(30),
+            }
+        );
+        assert_eq!(stats.button_flush.count, 1);
+
+        let later = metrics.snapshot(start + Duration::from_secs(75));
+        assert_eq!(later.strip_flush.count, 1);
+        assert_eq!(later.strip_flush.max, Duration::from_millis(10));
+
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let (reply_tx, reply_rx) = crossbeam_channel::bounded(1);
+        command_tx
+            .send(HardwareCommand::UpdateEncoderDisplay {
+                encoder: EncoderId::One,
+                display: EncoderDisplay::new("volume", "40%"),
+            })
+            .unwrap();
+        command_tx
+            .send(HardwareCommand::QueryStats(reply_tx))
+            .unwrap();
+        let mut displays: [Option<EncoderDisplay>; 4] = [None, None, None, None];
+        let mut button_icons = vec![None; 3];
+        let mut animations = ButtonAnimations::new(&mut button_icons, Instant::now());
+        let mut metrics = BackendMetrics::new(Instant::now());
+        process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut PendingFlush::default(),
+            &mut metrics,
+            &command_rx,
+        );
+        assert_eq!(reply_rx.try_recv().unwrap().command_drain.count, 0);
+        assert_eq!(metrics.snapshot(Instant::now()).command_drain.count, 1);
+    }
+
+    #[test]
+    fn exit_flush_stops_draining_behind_the_clear() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx.clone());
+        handle
+            .update_encoder(EncoderId::One, EncoderDisplay::new("volume", "40%"))
+            .unwrap();
+        assert!(handle.blank_for_exit(Duration::from_millis(10)).is_err());
+        handle
+            .update_encoder(EncoderId::Two, EncoderDisplay::new("timer", "05:00"))
+            .unwrap();
+
+        let mut displays: [Option<EncoderDisplay>; 4] = [None, None, None, None];
+        let mut button_icons = vec![None; 3];
+        let mut animations = ButtonAnimations::new(&mut button_icons, Instant::now());
+        let mut pending = PendingFlush::default();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut BackendMetrics::new(Instant::now()),
+            &command_rx,
+        );
+        assert!(drained.exit.is_some());
+        assert!(pending.clear);
+        assert!(displays.iter().all(Option::is_none));
+        assert_eq!(command_rx.len(), 1);
+    }
+
+    #[test]
+    fn banners_hold_back_segment_writes_until_they_expire() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx);
+        let mut displays: [Option<EncoderDisplay>; 4] = [None, None, None, None];
+        let mut button_icons = vec![None; 3];
+        let mut animations = ButtonAnimations::new(&mut button_icons, Instant::now());
+        let mut pending = PendingFlush::default();
+        let mut metrics = BackendMetrics::new(Instant::now());
+        let mut banner = StripBanner::default();
+
+        handle
+            .show_banner("timer done", Duration::from_secs(5), [255, 0, 0])
+            .unwrap();
+        handle
+            .show_banner("config reloaded", Duration::from_millis(100), [255; 3])
+            .unwrap();
+        handle
+            .update_encoder(EncoderId::One, EncoderDisplay::new("volume", "40%"))
+            .unwrap();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        let shown = drained.banner.unwrap();
+        assert_eq!(shown.text, "config reloaded");
+        let until = shown.until;
+        banner.show(shown, &mut pending);
+        banner.advance(&mut pending, until - Duration::from_millis(1));
+        assert!(pending.banner.is_some());
+        assert_eq!(pending.strip, [false; 4]);
+        assert_eq!(displays[0].as_ref().unwrap().value, "40%");
+        assert_eq!(banner.next_due(), Some(until));
+
+        banner.advance(&mut pending, until);
+        assert!(pending.banner.is_none());
+        assert_eq!(pending.strip, [true; 4]);
+        assert_eq!(banner.next_due(), None);
+
+        handle
+            .show_banner("timer done", Duration::from_secs(5), [255, 0, 0])
+            .unwrap();
+        handle.clear_all_displays().unwrap();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        assert!(drained.cleared && drained.banner.is_none());
+        assert!(pending.banner.is_none());
+
+        handle
+            .show_banner("starting", Duration::from_secs(30), [255; 3])
+            .unwrap();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        banner.show(drained.banner.unwrap(), &mut pending);
+        pending.strip = [false; 4];
+        handle.dismiss_banner().unwrap();
+        let drained = process_commands(
+            SIMULATED_CAPABILITIES,
+            &mut displays,
+            &mut button_icons,
+            &mut animations,
+            &mut pending,
+            &mut metrics,
+            &command_rx,
+        );
+        assert!(drained.dismissed && drained.banner.is_none());
+        banner.end(&mut pending);
+        assert!(pending.banner.is_none());
+        assert_eq!(pending.strip, [true; 4]);
+        assert_eq!(banner.next_due(), None);
+    }
+
+    #[test]
+    fn state_sent_before_a_deck_appears_is_kept_for_it() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx);
+        let mut state = DeckState::new(&HardwareConfig::default(), LATE_DEVICE_CAPABILITIES);
+        let icon = |id: &str| ButtonImage {
+            id: id.into(),
+            image: Arc::new(RgbaImage::new(1, 1)),
+            tint: None,
+            label: None,
+            animation: None,
+            background: None,
+            badge: None,
+        };
+
+        handle
+            .update_encoder(EncoderId::One, EncoderDisplay::new("volume", "40%"))
+            .unwrap();
+        handle
+            .update_encoder(EncoderId::One, EncoderDisplay::new("volume", "45%"))
+            .unwrap();
+        handle.update_button_icon(2, Some(icon("mic"))).unwrap();
+        handle
+            .update_button_icon(20, Some(icon("xl-only")))
+            .unwrap();
+        handle.set_device_brightness(30).unwrap();
+        handle
+            .show_banner("starting", Duration::from_secs(5), [255; 3])
+            .unwrap();
+        for command in command_rx.try_iter() {
+            state.record(command, LATE_DEVICE_CAPABILITIES);
+        }
+        assert_eq!(state.displays[0].as_ref().unwrap().value, "45%");
+        assert_eq!(state.button_icons[2].as_ref().unwrap().id, "mic");
+        assert_eq!(state.button_icons[20].as_ref().unwrap().id, "xl-only");
+        assert_eq!(state.backlight.level, 30);
+
+        state.fit(DeviceCapabilities {
+            key_count: 8,
+            encoder_count: 4,
+            lcd_strip: true,
+        });
+        assert_eq!(state.button_icons.len(), 8);
+        assert_eq!(state.displays[0].as_ref().unwrap().value, "45%");
+        state.fit(DeviceCapabilities {
+            key_count: 6,
+            encoder_count: 0,
+            lcd_strip: false,
+        });
+        assert_eq!(state.button_icons.len(), 6);
+        assert!(state.displays.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn placeholders_never_replace_a_controller_display() {
+        let (command_tx, command_rx) = crossbeam_channel::unbounded();
+        let handle = single_handle(command_tx);
+        handle
+            .update_encoder(EncoderId::One, EncoderDisplay::new("volume", "40%"))
+            .unwrap();
+        for encoder in [EncoderId::One, EncoderId::Two] {
+            handle
+                .show_placeholder(encoder, EncoderDisplay::new("", "…").with_status("init…"))
+                .unwrap();
+        }
+        let shown = handle.shown();
+        assert_eq!(shown.encoders[0].as_ref().unwrap().value, "40%");
+        assert_eq!(shown.encoders[1].as_ref().unwrap().value, "…");
+        assert_eq!(command_rx.len(), 2);
+    }
+
+    #[test]
+    fn strip_x_maps_to_the_segment_underneath() {
+        let segments: Vec<EncoderId> = [0, 199, 200, 399, 400, 599, 600, 799, 1000]
+            .into_iter()
+            .map(EncoderId::from_strip_x)
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                EncoderId::One,
+                EncoderId::One,
+                EncoderId::Two,
+                EncoderId::Two,
+                EncoderId::Three,
+                EncoderId::Three,
+                EncoderId::Four,
+                EncoderId::Four,
+                EncoderId::Four,
+            ]
+        );
+    }
+
+    #[test]
+    fn taps_on_segment_borders_are_dropped() {
+        let taps: Vec<Option<EncoderId>> = [0, 195, 196, 200, 203, 204, 599, 600, 799]
+            .into_iter()
+            .map(EncoderId::from_strip_tap)
+            .collect();
+        assert_eq!(
+            taps,
+            [
+                Some(EncoderId::One),
+                Some(EncoderId::One),
+                None,
+                None,
+                None,
+                Some(EncoderId::Two),
+                None,
+                None,
+                Some(EncoderId::Four),
+            ]
+        );
+    }
+
+    #[test]
+    fn press_feedback_ends_on_release_or_timeout() {
+        let icon = ButtonImage {
+            id: "launcher".into(),
+            image: Arc::new(RgbaImage::new(1, 1)),
+            tint: None,
+            label: None,
+            animation: None,
+            background: None,
+            badge: None,
+        };
+        let mut button_icons = vec![Some(icon.clone()), Some(icon), None];
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut feedback = PressFeedback::default();
+
+        feedback.press(0, start);
+        feedback.press(1, start);
+        feedback.press(2, start);
+        assert_eq!(feedback.redraws(&button_icons, start), vec![0, 1]);
+        assert_eq!(feedback.highlighted(), vec![true, true, false]);
+
+        feedback.release(0);
+        assert_eq!(feedback.redraws(&button_icons, at(50)), vec![0]);
+
+        // A new icon during the flash keeps the highlight until it expires.
+        button_icons[1].as_mut().unwrap().id = "launcher-new".into();
+        assert_eq!(feedback.next_due(), Some(at(150)));
+        assert!(feedback.redraws(&button_icons, at(149)).is_empty());
+        assert_eq!(feedback.redraws(&button_icons, at(150)), vec![1]);
+        assert_eq!(feedback.highlighted(), vec![false, false, false]);
+
+        feedback.release(1);
+        assert!(feedback.redraws(&button_icons, at(200)).is_empty());
+    }
+
+    #[test]
+    fn value_jumps_ease_in_and_yield_to_newer_updates() {
+        let reading = |percent: f32| {
+            Some(
+                EncoderDisplay::new("volume", format!("{percent:>3.0}%"))
+                    .with_progress(percent / 100.0),
+            )
+        };
+        let mut displays = [reading(20.0), reading(50.0), None, None];
+        let mut tweens = ValueTweens::new(Some(ValueAnimation::default()));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let before = tweens.shown(&displays, start);
+        displays[0] = reading(60.0);
+        assert_eq!(
+            tweens.start(&before, &displays, [true, false, false, false], start),
+            [false; 4]
+        );
+        assert_eq!(tweens.next_due(), Some(at(30)));
+        assert_eq!(tweens.advance(at(29)), [false; 4]);
+        assert_eq!(tweens.advance(at(30)), [true, false, false, false]);
+        let frame = tweens.frame(&displays, at(30)).unwrap();
+        let midway = frame[0].as_ref().unwrap();
+        assert!(midway.progress.unwrap() > 0.2 && midway.progress.unwrap() < 0.6);
+        assert_eq!(midway.value.len(), 4);
+        assert_ne!(midway.value, " 60%");
+        assert_eq!(frame[1].as_ref().unwrap().value, " 50%");
+
+        // A newer reading continues from the bar as drawn.
+        let before = tweens.shown(&displays, at(60));
+        displays[0] = reading(10.0);
+        tweens.start(&before, &displays, [true, false, false, false], at(60));
+        let restarted = tweens.frame(&displays, at(60)).unwrap();
+        assert_eq!(
+            restarted[0].as_ref().unwrap().progress,
+            before[0].map(|shown| shown.progress)
+        );
+        assert_eq!(tweens.advance(at(210)), [true, false, false, false]);
+        assert!(tweens.frame(&displays, at(210)).is_none());
+
+        // Small steps and multi-segment batches are drawn at once.
+        let before = tweens.shown(&displays, at(300));
+        displays[0] = reading(15.0);
+        tweens.start(&before, &displays, [true, false, false, false], at(300));
+        assert_eq!(tweens.next_due(), None);
+        displays[0] = reading(90.0);
+        tweens.start(&before, &displays, [true, false, false, false], at(300));
+        let before = tweens.shown(&displays, at(310));
+        displays[1] = reading(0.0);
+        assert_eq!(
+            tweens.start(&before, &displays, [false, true, false, false], at(310)),
+            [false; 4]
+        );
+        assert_eq!(tweens.segments.iter().flatten().count(), 2);
+        assert_eq!(
+            tweens.start(&before, &displays, [true, true, false, false], at(320)),
+            [true, true, false, false]
+        );
+        assert_eq!(tweens.next_due(), None);
+
+        let mut disabled = ValueTweens::new(None);
+        displays[0] = reading(0.0);
+        disabled.start(&before, &displays, [true, false, false, false], at(400));
+        assert_eq!(disabled.next_due(), None);
+    }
+
+    #[test]
+    fn animations_advance_each_key_on_its_own_delays() {
+        let frames: Vec<_> = (0..3u8)
+            .map(|shade| Arc::new(RgbaImage::from_pixel(1, 1, image::Rgba([shade, 0, 0, 255]))))
+            .collect();
+        let animation = Arc::new(AnimatedIcon {
+            frames: frames.clone(),
+            delays: [100, 50, 100].map(Duration::from_millis).to_vec(),
+        });
+        let icon = |animation: Option<Arc<AnimatedIcon>>| ButtonImage {
+            id: "spinner".into(),
+            image: Arc::new(RgbaImage::new(1, 1)),
+            tint: None,
+            label: None,
+            animation,
+            background: None,
+            badge: None,
+        };
+        let mut button_icons = vec![Some(icon(Some(animation))), Some(icon(None)), None];
+
+        let start = Instant::now();
+        let mut animations = ButtonAnimations::new(&mut button_icons, start);
+        let shown = |icons: &[Option<ButtonImage>]| icons[0].as_ref().unwrap().image.clone();
+        assert!(Arc::ptr_eq(&shown(&button_icons), &frames[0]));
+        assert_eq!(
+            animations.next_due(),
+            Some(start + Duration::from_millis(100))
+        );
+
+        let at = |ms| start + Duration::from_millis(ms);
+        assert!(animations.advance(&mut button_icons, at(99)).is_empty());
+        assert_eq!(animations.advance(&mut button_icons, at(100)), vec![0]);
+        assert!(Arc::ptr_eq(&shown(&button_icons), &frames[1]));
+        assert_eq!(animations.advance(&mut button_icons, at(150)), vec![0]);
+        assert!(Arc::ptr_eq(&shown(&button_icons), &frames[2]));
+        assert_eq!(animations.advance(&mut button_icons, at(250)), vec![0]);
+        assert!(Arc::ptr_eq(&shown(&button_icons), &frames[0]));
+
+        button_icons[0] = Some(icon(None));
+        animations.restart(&mut button_icons, [0], at(260));
+        assert_eq!(animations.next_due(), None);
+    }
+
+    #[test]
+    fn failed_flushes_back_off_and_give_up_after_the_limit() {
+        let start = Instant::now();
+        let mut pending = PendingFlush {
+            strip: [true, false, false, false],
+            ..Default::default()
+        };
+        pending.mark_buttons([2, 2, 5]);
+        assert_eq!(pending.buttons, vec![2, 5]);
+        assert!(pending.is_due(start));
+
+        pending.settle(Err(anyhow!("usb busy")), 3, start).unwrap();
+        assert!(!pending.is_due(start + Duration::from_millis(99)));
+        assert!(pending.is_due(start + FLUSH_RETRY_BASE));
+        pending.settle(Err(anyhow!("usb busy")), 3, start).unwrap();
+        assert_eq!(pending.retry_at, Some(start + FLUSH_RETRY_BASE * 2));
+        assert!(pending.strip[0] && pending.buttons == vec![2, 5]);
+        assert!(pending.settle(Err(anyhow!("usb busy")), 3, start).is_err());
+
+        pending.failures = 1;
+        pending.settle(Ok(()), 3, start).unwrap();
+        assert_eq!((pending.failures, pending.retry_at), (0, None));
+        assert_eq!(flush_backoff(30), FLUSH_RETRY_MAX);
+    }
+}
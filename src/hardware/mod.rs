@@ -2,8 +2,14 @@
 mod backend;
 #[cfg(feature = "hardware")]
 mod render;
+#[cfg(feature = "hardware")]
+mod simulator;
 
 pub use backend::{
-    ButtonImage, DisplayPipeline, EncoderDisplay, EncoderId, HardwareConfig, HardwareEvent,
-    HardwareHandle, start,
+    BackendStats, ButtonImage, DeviceConfig, DeviceEvent, DeviceId, DisplayPipeline,
+    EncoderDisplay, EncoderId, HardwareConfig, HardwareEvent, HardwareHandle, IdleDim,
+    ProgressStyle, SegmentTheme, ShownDisplays, TimingStats, connected_decks, start,
 };
+// Only `main` builds one, and only with the `hardware` feature.
+#[cfg(feature = "hardware")]
+pub use backend::SimulatorConfig;
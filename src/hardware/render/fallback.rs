@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use ab_glyph::{Font, FontArc, FontRef, FontVec, Glyph, GlyphId, PxScale, ScaleFont, point};
+use fontdb::{Database, ID, Style, Weight};
+use once_cell::sync::Lazy;
+use tracing::debug;
+
+// Installed fonts, searched in order for the first face that covers a
+// character; regular sans faces come first.
+static FONTS: Lazy<FallbackFonts> = Lazy::new(FallbackFonts::load);
+
+// Sets TTF text the same on every machine; installed fonts only fill in the
+// characters it lacks.
+static BUNDLED: Lazy<FontArc> = Lazy::new(|| {
+    FontArc::try_from_slice(include_bytes!("../../../assets/fonts/DejaVuSans.ttf"))
+        .expect("bundled font parses")
+});
+
+/// Coverage of one glyph rasterised into a `width` x `height` cell.
+#[derive(Debug)]
+pub(super) struct GlyphMask {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    coverage: Vec<f32>,
+}
+
+impl GlyphMask {
+    pub(super) fn coverage(&self, x: u32, y: u32) -> f32 {
+        self.coverage[(y * self.width + x) as usize]
+    }
+}
+
+// Rendered masks by character and cell width and height; `None` when no font has the
+// character.
+type MaskCache = HashMap<(char, u32, u32), Option<Arc<GlyphMask>>>;
+
+struct FallbackFonts {
+    db: Database,
+    order: Vec<ID>,
+    faces: Mutex<HashMap<char, Option<ID>>>,
+    loaded: Mutex<HashMap<ID, FontArc>>,
+    masks: Mutex<MaskCache>,
+}
+
+impl FallbackFonts {
+    fn load() -> Self {
+        let mut db = Database::new();
+        db.load_system_fonts();
+        let mut order: Vec<_> = db
+            .faces()
+            .map(|face| {
+                let sans = face
+                    .families
+                    .iter()
+                    .any(|(family, _)| family.contains("Sans") && !family.contains("Mono"));
+                let regular = face.style == Style::Normal && face.weight == Weight::NORMAL;
+                ((!regular, !sans), face.id)
+            })
+            .collect();
+        order.sort_by_key(|(rank, _)| *rank);
+        debug!(faces = order.len(), "loaded fallback fonts");
+        Self {
+            db,
+            order: order.into_iter().map(|(_, id)| id).collect(),
+            faces: Mutex::new(HashMap::new()),
+            loaded: Mutex::new(HashMap::new()),
+            masks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn face_for(&self, ch: char) -> Option<ID> {
+        if let Some(found) = self.faces.lock().unwrap().get(&ch) {
+            return *found;
+        }
+        let found = self.order.iter().copied().find(|id| {
+            self.db
+                .with_face_data(*id, |data, index| {
+                    FontRef::try_from_slice_and_index(data, index)
+                        .is_ok_and(|font| font.glyph_id(ch).0 != 0)
+                })
+                .unwrap_or(false)
+        });
+        self.faces.lock().unwrap().insert(ch, found);
+        found
+    }
+
+    fn font(&self, id: ID) -> Option<FontArc> {
+        if let Some(font) = self.loaded.lock().unwrap().get(&id) {
+            return Some(font.clone());
+        }
+        let font = self
+            .db
+            .with_face_data(id, |data, index| {
+                FontVec::try_from_vec_and_index(data.to_vec(), index).ok()
+            })
+            .flatten()
+            .map(FontArc::new)?;
+        self.loaded.lock().unwrap().insert(id, font.clone());
+        Some(font)
+    }
+
+    fn mask(&self, ch: char, width: u32, height: u32) -> Option<Arc<GlyphMask>> {
+        let key = (ch, width, height);
+        if let Some(mask) = self.masks.lock().unwrap().get(&key) {
+            return mask.clone();
+        }
+        let mask = self
+            .face_for(ch)
+            .and_then(|id| self.font(id))
+            .and_then(|font| rasterize(&font, ch, width, height))
+            .map(Arc::new);
+        self.masks.lock().unwrap().insert(key, mask.clone());
+        mask
+    }
+}
+
+/// One line of text set proportionally, its origin at the top left.
+pub(super) struct TextLayout {
+    glyphs: Vec<(FontArc, Glyph)>,
+    pub(super) width: u32,
+    pub(super) height: u32,
+}
+
+impl TextLayout {
+    /// Calls `plot` with the coverage of every pixel the glyphs touch.
+    pub(super) fn draw(&self, mut plot: impl FnMut(u32, u32, f32)) {
+        for (font, glyph) in &self.glyphs {
+            let Some(outlined) = font.outline_glyph(glyph.clone()) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, value| {
+                let x = bounds.min.x as i32 + gx as i32;
+                let y = bounds.min.y as i32 + gy as i32;
+                if x >= 0 && y >= 0 && value > 0.0 {
+                    plot(x as u32, y as u32, value);
+                }
+            });
+        }
+    }
+}
+
+/// Renders `ch` from the first installed font that has it, or `None` when no
+/// font covers it.
+pub(super) fn glyph_mask(ch: char, width: u32, height: u32) -> Option<Arc<GlyphMask>> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    FONTS.mask(ch, width, height)
+}
+
+/// Sets `text` `px_size` pixels tall in the bundled font, taking characters
+/// it lacks from installed fonts. `None` when `px_size` is not positive.
+pub(super) fn layout_text(text: &str, px_size: f32) -> Option<TextLayout> {
+    if px_size <= 0.0 {
+        return None;
+    }
+    let primary = &*BUNDLED;
+    let scale = PxScale::from(px_size);
+    let ascent = primary.as_scaled(scale).ascent();
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0f32;
+    let mut previous: Option<GlyphId> = None;
+    for ch in text.chars() {
+        let installed = if ch.is_whitespace() || primary.glyph_id(ch).0 != 0 {
+            None
+        } else {
+            FONTS.face_for(ch).and_then(|id| FONTS.font(id))
+        };
+        let from_primary = installed.is_none();
+        let font = installed.unwrap_or_else(|| primary.clone());
+        let scaled = font.as_scaled(scale);
+        let id = font.glyph_id(ch);
+        if let Some(previous) = previous.filter(|_| from_primary) {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, ascent));
+        caret += scaled.h_advance(id);
+        previous = from_primary.then_some(id);
+        glyphs.push((font, glyph));
+    }
+    let height = if glyphs.is_empty() {
+        0
+    } else {
+        primary.as_scaled(scale).height().ceil() as u32
+    };
+    Some(TextLayout {
+        glyphs,
+        width: caret.ceil().max(0.0) as u32,
+        height,
+    })
+}
+
+// The font's ascent-to-descent span fills the cell height; wide glyphs such as
+// CJK are squeezed horizontally so every character keeps the bitmap advance.
+fn rasterize(font: &impl Font, ch: char, width: u32, height: u32) -> Option<GlyphMask> {
+    let id = font.glyph_id(ch);
+    let mut scale = PxScale::from(height as f32);
+    let advance = font.as_scaled(scale).h_advance(id);
+    if advance > width as f32 {
+        scale.x *= width as f32 / advance;
+    }
+    let scaled = font.as_scaled(scale);
+    let x = ((width as f32 - scaled.h_advance(id)) / 2.0).max(0.0);
+    let glyph = id.with_scale_and_position(scale, point(x, scaled.ascent()));
+    let outlined = font.outline_glyph(glyph)?;
+
+    let bounds = outlined.px_bounds();
+    let mut coverage = vec![0.0; (width * height) as usize];
+    outlined.draw(|gx, gy, value| {
+        let x = bounds.min.x as i32 + gx as i32;
+        let y = bounds.min.y as i32 + gy as i32;
+        if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+            coverage[(y as u32 * width + x as u32) as usize] = value;
+        }
+    });
+    coverage
+        .iter()
+        .any(|value| *value > 0.0)
+        .then_some(GlyphMask {
+            width,
+            height,
+            coverage,
+        })
+}
@@ -1,8 +1,13 @@
 use image::{Rgb, RgbImage};
 
+use super::fallback::{self, GlyphMask};
+
 const GLYPH_WIDTH: u32 = 5;
 const GLYPH_HEIGHT: u32 = 7;
 
+/// Every character other than a space takes one glyph cell, whether it comes
+/// from the bitmap font, an installed font or the substitution box, so widths
+/// depend only on the character count.
 pub fn measure_text(text: &str, scale: u32) -> (u32, u32) {
     if scale == 0 {
         return (0, 0);
@@ -11,19 +16,18 @@ pub fn measure_text(text: &str, scale: u32) -> (u32, u32) {
     let mut width = 0;
     let mut drawn = false;
 
+    // Mirrors the advances in `draw_text`.
     for ch in text.chars() {
         if ch == ' ' {
             width += scale * 3;
             continue;
         }
 
-        if glyph_for(ch).is_some() {
-            if drawn {
-                width += scale; // inter-character spacing
-            }
-            width += GLYPH_WIDTH * scale;
-            drawn = true;
+        if drawn {
+            width += scale; // inter-character spacing
         }
+        width += GLYPH_WIDTH * scale;
+        drawn = true;
     }
 
     let height = if drawn { GLYPH_HEIGHT * scale } else { 0 };
@@ -42,19 +46,63 @@ pub fn draw_text(image: &mut RgbImage, text: &str, mut x: u32, y: u32, scale: u3
             continue;
         }
 
-        let glyph = match glyph_for(ch) {
-            Some(g) => g,
-            None => {
-                x = x.saturating_add(scale * 2);
-                continue;
-            }
-        };
-
-        draw_glyph(image, glyph, x, y, scale, color);
+        match glyph_for(ch) {
+            Some(glyph) => draw_glyph(image, glyph, x, y, scale, color),
+            None => match fallback::glyph_mask(raw_ch, GLYPH_WIDTH * scale, GLYPH_HEIGHT * scale) {
+                Some(mask) => draw_mask(image, &mask, x, y, color),
+                None => draw_glyph(image, &GLYPH_BOX, x, y, scale, color),
+            },
+        }
         x = x.saturating_add(GLYPH_WIDTH * scale).saturating_add(scale);
     }
 }
 
+/// Size of `text` set proportionally in the bundled font `px_size` pixels
+/// tall.
+pub fn measure_text_ttf(text: &str, px_size: f32) -> (u32, u32) {
+    fallback::layout_text(text, px_size).map_or((0, 0), |layout| (layout.width, layout.height))
+}
+
+/// Draws `text` as measured by `measure_text_ttf`, keeping its case and
+/// diacritics.
+pub fn draw_text_ttf(
+    image: &mut RgbImage,
+    text: &str,
+    x: u32,
+    y: u32,
+    px_size: f32,
+    color: [u8; 3],
+) {
+    if let Some(layout) = fallback::layout_text(text, px_size) {
+        layout.draw(|gx, gy, coverage| blend(image, x + gx, y + gy, coverage, color));
+    }
+}
+
+fn draw_mask(image: &mut RgbImage, mask: &GlyphMask, origin_x: u32, origin_y: u32, color: [u8; 3]) {
+    for y in 0..mask.height {
+        for x in 0..mask.width {
+            blend(
+                image,
+                origin_x + x,
+                origin_y + y,
+                mask.coverage(x, y),
+                color,
+            );
+        }
+    }
+}
+
+fn blend(image: &mut RgbImage, x: u32, y: u32, coverage: f32, color: [u8; 3]) {
+    if coverage <= 0.0 || x >= image.width() || y >= image.height() {
+        return;
+    }
+    let coverage = coverage.min(1.0);
+    let pixel = image.get_pixel_mut(x, y);
+    for (channel, target) in pixel.0.iter_mut().zip(color) {
+        *channel = (target as f32 * coverage + *channel as f32 * (1.0 - coverage)).round() as u8;
+    }
+}
+
 fn draw_glyph(
     image: &mut RgbImage,
     glyph: &[&str; GLYPH_HEIGHT as usize],
@@ -105,19 +153,40 @@ fn glyph_for(ch: char) -> Option<&'static [&'static str; GLYPH_HEIGHT as usize]>
         'G' => Some(&LETTER_G),
         'H' => Some(&LETTER_H),
         'I' => Some(&LETTER_I),
+        'J' => Some(&LETTER_J),
+        'K' => Some(&LETTER_K),
         'L' => Some(&LETTER_L),
         'M' => Some(&LETTER_M),
         'N' => Some(&LETTER_N),
         'O' => Some(&LETTER_O),
         'P' => Some(&LETTER_P),
+        'Q' => Some(&LETTER_Q),
         'R' => Some(&LETTER_R),
         'S' => Some(&LETTER_S),
         'T' => Some(&LETTER_T),
         'U' => Some(&LETTER_U),
         'V' => Some(&LETTER_V),
+        'W' => Some(&LETTER_W),
+        'X' => Some(&LETTER_X),
+        'Y' => Some(&LETTER_Y),
+        'Z' => Some(&LETTER_Z),
         ':' => Some(&GLYPH_COLON),
         '%' => Some(&GLYPH_PERCENT),
         '-' => Some(&GLYPH_DASH),
+        '.' => Some(&GLYPH_PERIOD),
+        '/' => Some(&GLYPH_SLASH),
+        '+' => Some(&GLYPH_PLUS),
+        ',' => Some(&GLYPH_COMMA),
+        '\'' | '’' => Some(&GLYPH_APOSTROPHE),
+        '!' => Some(&GLYPH_EXCLAMATION),
+        '?' => Some(&GLYPH_QUESTION),
+        '(' => Some(&GLYPH_PAREN_OPEN),
+        ')' => Some(&GLYPH_PAREN_CLOSE),
+        '&' => Some(&GLYPH_AMPERSAND),
+        '_' => Some(&GLYPH_UNDERSCORE),
+        '#' => Some(&GLYPH_HASH),
+        '…' => Some(&GLYPH_ELLIPSIS),
+        '°' => Some(&GLYPH_DEGREE),
         _ => None,
     }
 }
@@ -204,6 +273,14 @@ const LETTER_I: [&str; 7] = glyph![
     "#####", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "#####",
 ];
 
+const LETTER_J: [&str; 7] = glyph![
+    "  ###", "    #", "    #", "    #", "    #", "#   #", " ### ",
+];
+
+const LETTER_K: [&str; 7] = glyph![
+    "#   #", "#  # ", "# #  ", "##   ", "# #  ", "#  # ", "#   #",
+];
+
 const LETTER_L: [&str; 7] = glyph![
     "#    ", "#    ", "#    ", "#    ", "#    ", "#    ", "#####",
 ];
@@ -224,6 +301,10 @@ const LETTER_P: [&str; 7] = glyph![
     "#### ", "#   #", "#   #", "#### ", "#    ", "#    ", "#    ",
 ];
 
+const LETTER_Q: [&str; 7] = glyph![
+    " ### ", "#   #", "#   #", "#   #", "# # #", "#  # ", " ## #",
+];
+
 const LETTER_R: [&str; 7] = glyph![
     "#### ", "#   #", "#   #", "#### ", "# #  ", "#  # ", "#   #",
 ];
@@ -244,6 +325,22 @@ const LETTER_V: [&str; 7] = glyph![
     "#   #", "#   #", "#   #", "#   #", "#   #", " # # ", "  #  ",
 ];
 
+const LETTER_W: [&str; 7] = glyph![
+    "#   #", "#   #", "#   #", "#   #", "# # #", "## ##", "#   #",
+];
+
+const LETTER_X: [&str; 7] = glyph![
+    "#   #", "#   #", " # # ", "  #  ", " # # ", "#   #", "#   #",
+];
+
+const LETTER_Y: [&str; 7] = glyph![
+    "#   #", "#   #", " # # ", "  #  ", "  #  ", "  #  ", "  #  ",
+];
+
+const LETTER_Z: [&str; 7] = glyph![
+    "#####", "    #", "   # ", "  #  ", " #   ", "#    ", "#####",
+];
+
 const GLYPH_COLON: [&str; 7] = glyph![
     "     ", "  #  ", "  #  ", "     ", "  #  ", "  #  ", "     ",
 ];
@@ -255,3 +352,149 @@ const GLYPH_PERCENT: [&str; 7] = glyph![
 const GLYPH_DASH: [&str; 7] = glyph![
     "     ", "     ", "     ", " ### ", "     ", "     ", "     ",
 ];
+
+const GLYPH_PERIOD: [&str; 7] = glyph![
+    "     ", "     ", "     ", "     ", "     ", "     ", "  #  ",
+];
+
+const GLYPH_DEGREE: [&str; 7] = glyph![
+    " ##  ", "#  # ", " ##  ", "     ", "     ", "     ", "     ",
+];
+
+const GLYPH_SLASH: [&str; 7] = glyph![
+    "     ", "    #", "   # ", "  #  ", " #   ", "#    ", "     ",
+];
+
+const GLYPH_ELLIPSIS: [&str; 7] = glyph![
+    "     ", "     ", "     ", "     ", "     ", "     ", "# # #",
+];
+
+// Numeric zone abbreviations such as "+09" on the clock.
+const GLYPH_PLUS: [&str; 7] = glyph![
+    "     ", "  #  ", "  #  ", "#####", "  #  ", "  #  ", "     ",
+];
+
+const GLYPH_COMMA: [&str; 7] = glyph![
+    "     ", "     ", "     ", "     ", "     ", "  #  ", " #   ",
+];
+
+const GLYPH_APOSTROPHE: [&str; 7] = glyph![
+    "  #  ", "  #  ", " #   ", "     ", "     ", "     ", "     ",
+];
+
+const GLYPH_EXCLAMATION: [&str; 7] = glyph![
+    "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "     ", "  #  ",
+];
+
+const GLYPH_QUESTION: [&str; 7] = glyph![
+    " ### ", "#   #", "    #", "   # ", "  #  ", "     ", "  #  ",
+];
+
+const GLYPH_PAREN_OPEN: [&str; 7] = glyph![
+    "   # ", "  #  ", " #   ", " #   ", " #   ", "  #  ", "   # ",
+];
+
+const GLYPH_PAREN_CLOSE: [&str; 7] = glyph![
+    " #   ", "  #  ", "   # ", "   # ", "   # ", "  #  ", " #   ",
+];
+
+const GLYPH_AMPERSAND: [&str; 7] = glyph![
+    " ##  ", "#  # ", "# #  ", " #   ", "# # #", "#  # ", " ## #",
+];
+
+const GLYPH_UNDERSCORE: [&str; 7] = glyph![
+    "     ", "     ", "     ", "     ", "     ", "     ", "#####",
+];
+
+const GLYPH_HASH: [&str; 7] = glyph![
+    " # # ", " # # ", "#####", " # # ", "#####", " # # ", " # # ",
+];
+
+// Drawn for characters that no installed font covers.
+const GLYPH_BOX: [&str; 7] = glyph![
+    "#####", "#   #", "#   #", "#   #", "#   #", "#   #", "#####",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit_pixels(text: &str) -> usize {
+        let (width, height) = measure_text(text, 2);
+        let mut image = RgbImage::new(width, height);
+        draw_text(&mut image, text, 0, 0, 2, [255, 255, 255]);
+        image.pixels().filter(|pixel| pixel.0 != [0, 0, 0]).count()
+    }
+
+    #[test]
+    fn accented_and_cjk_characters_are_drawn_in_whole_cells() {
+        assert_eq!(measure_text("Björk", 2), measure_text("BJORK", 2));
+        assert_eq!(measure_text("東京", 2), measure_text("AB", 2));
+        for text in [
+            "ö",
+            "é",
+            "東",
+            "京",
+            "\u{10FFFD}",
+            "K",
+            "Q",
+            "W",
+            "X",
+            "Y",
+            "Z",
+            "J",
+        ] {
+            assert!(lit_pixels(text) > 0, "{text:?} drew nothing");
+        }
+        assert!(lit_pixels("Björk 東京") > lit_pixels("Bjrk"));
+    }
+
+    #[test]
+    fn ttf_text_keeps_diacritics_and_proportional_widths() {
+        let lit = |text: &str| {
+            let (width, height) = measure_text_ttf(text, 28.0);
+            let mut image = RgbImage::new(width, height);
+            draw_text_ttf(&mut image, text, 0, 0, 28.0, [255, 255, 255]);
+            image.pixels().filter(|pixel| pixel.0 != [0, 0, 0]).count()
+        };
+        assert!(lit("Für Elise") > lit("Fur Elise"));
+        assert!(lit("Beyoncé") > lit("Beyonce"));
+        assert!(measure_text_ttf("il", 28.0).0 < measure_text_ttf("WM", 28.0).0);
+        assert_eq!(measure_text_ttf("", 28.0), (0, 0));
+    }
+
+    #[test]
+    fn pangram_is_drawn_within_its_measured_width() {
+        let text = "The quick brown fox jumps over the lazy dog. (Don't!) A/B & C+D, #1_2?";
+        for ch in text.chars().filter(|ch| *ch != ' ') {
+            assert!(
+                glyph_for(ch.to_ascii_uppercase()).is_some(),
+                "{ch:?} has no bitmap glyph"
+            );
+        }
+
+        let (width, height) = measure_text(text, 2);
+        assert_eq!(height, GLYPH_HEIGHT * 2);
+        let mut image = RgbImage::new(width + 20, height);
+        draw_text(&mut image, text, 0, 0, 2, [255, 255, 255]);
+        let rightmost = image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel.0 != [0, 0, 0])
+            .map(|(x, _, _)| x)
+            .max()
+            .unwrap();
+        assert!(rightmost < width);
+        assert!(rightmost >= width - GLYPH_WIDTH * 2);
+    }
+
+    #[test]
+    fn clock_statuses_use_bitmap_glyphs_only() {
+        for ch in
+            "MON TUE WED THU FRI SAT SUN JAN FEB MAR APR MAY JUN JUL AUG SEP OCT NOV DEC AM PM +09"
+                .chars()
+                .filter(|ch| *ch != ' ')
+        {
+            assert!(glyph_for(ch).is_some(), "{ch:?} has no bitmap glyph");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+mod fallback;
 mod font;
 
 use std::borrow::Cow;
@@ -8,23 +9,69 @@ use elgato_streamdeck::StreamDeck;
 use elgato_streamdeck::images::ImageRect;
 use image::{DynamicImage, Rgb, RgbImage, RgbaImage, imageops::FilterType};
 
-use crate::hardware::backend::{ButtonImage, EncoderDisplay};
+use crate::hardware::backend::{ButtonImage, EncoderDisplay, ProgressStyle, SegmentTheme};
 
 const SEGMENT_WIDTH: u32 = 200;
 const SEGMENT_HEIGHT: u32 = 100;
 const SEGMENT_MARGIN: u32 = 12;
 const PROGRESS_HEIGHT: u32 = 12;
 const PROGRESS_MARGIN: u32 = 10;
-const BACKGROUND: [u8; 3] = [8, 10, 18];
-const TITLE_COLOR: [u8; 3] = [180, 190, 210];
-const VALUE_COLOR: [u8; 3] = [235, 240, 255];
-const STATUS_COLOR: [u8; 3] = [120, 210, 255];
+const ART_SIZE: u32 = 64;
+const BANNER_MAX_SCALE: u32 = 6;
 const PLACEHOLDER_COLOR: [u8; 3] = [80, 80, 92];
-const PROGRESS_BG: [u8; 3] = [30, 35, 45];
-const PROGRESS_FG: [u8; 3] = [0, 180, 120];
 const BORDER_COLOR: [u8; 3] = [50, 55, 65];
 const KEY_BACKGROUND: [u8; 3] = [12, 14, 24];
 const KEY_PLACEHOLDER_COLOR: [u8; 3] = [120, 130, 150];
+const KEY_LABEL_COLOR: [u8; 3] = [235, 240, 255];
+// Fraction of the underlying pixel kept beneath the label band.
+const KEY_LABEL_BAND_SHADE: f32 = 0.25;
+// How far a pressed key is blended towards white, plus its border colour.
+const KEY_PRESSED_LIGHTEN: f32 = 0.35;
+const KEY_PRESSED_BORDER: [u8; 3] = [255, 255, 255];
+// Bitmap cells are 7 pixels tall per scale step; the bundled font gets a
+// little more since its lowercase sits below the cap height.
+const TTF_PX_PER_SCALE: f32 = 8.0;
+
+/// How segment text is set: the capitals-only bitmap font, or with the
+/// theme's `use_ttf` the bundled font that keeps case and diacritics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextFace {
+    Bitmap,
+    Ttf,
+}
+
+impl TextFace {
+    fn for_theme(theme: &SegmentTheme) -> Self {
+        if theme.use_ttf {
+            Self::Ttf
+        } else {
+            Self::Bitmap
+        }
+    }
+
+    fn measure(self, text: &str, scale: u32) -> (u32, u32) {
+        match self {
+            Self::Bitmap => font::measure_text(text, scale),
+            Self::Ttf => font::measure_text_ttf(text, scale as f32 * TTF_PX_PER_SCALE),
+        }
+    }
+
+    fn draw(self, image: &mut RgbImage, text: &str, x: u32, y: u32, scale: u32, color: [u8; 3]) {
+        match self {
+            Self::Bitmap => font::draw_text(image, text, x, y, scale, color),
+            Self::Ttf => {
+                font::draw_text_ttf(image, text, x, y, scale as f32 * TTF_PX_PER_SCALE, color)
+            }
+        }
+    }
+
+    fn label(self, text: &str) -> String {
+        match self {
+            Self::Bitmap => text.to_ascii_uppercase(),
+            Self::Ttf => text.to_uppercase(),
+        }
+    }
+}
 
 pub fn flush_strip(deck: &StreamDeck, displays: &[Option<EncoderDisplay>; 4]) -> Result<()> {
     let image = compose_strip(displays)?;
@@ -32,10 +79,93 @@ pub fn flush_strip(deck: &StreamDeck, displays: &[Option<EncoderDisplay>; 4]) ->
         .context("failed to push LCD strip image")
 }
 
+pub fn flush_banner(deck: &StreamDeck, text: &str, color: [u8; 3]) -> Result<()> {
+    let image = ImageRect::from_image(DynamicImage::ImageRgb8(compose_banner_image(text, color)))
+        .context("failed to encode LCD banner into JPEG")?;
+    deck.write_lcd(0, 0, &image)
+        .context("failed to push LCD banner image")
+}
+
+/// What each strip segment last showed, theme included, so one that has not
+/// changed is neither rendered, encoded nor sent again.
+#[derive(Debug, Default)]
+pub struct StripCache {
+    /// `None` until the segment is written.
+    segments: [Option<Option<EncoderDisplay>>; 4],
+}
+
+impl StripCache {
+    /// Forgets what was written, for when something else drew over the strip.
+    pub fn invalidate(&mut self) {
+        self.segments = Default::default();
+    }
+
+    /// Indices of the `dirty` segments whose display differs from the one
+    /// last written.
+    fn changed(&self, displays: &[Option<EncoderDisplay>; 4], dirty: &[bool; 4]) -> Vec<usize> {
+        (0..displays.len())
+            .filter(|index| dirty[*index])
+            .filter(|index| self.segments[*index].as_ref() != Some(&displays[*index]))
+            .collect()
+    }
+
+    fn store(&mut self, index: usize, display: &Option<EncoderDisplay>) {
+        self.segments[index] = Some(display.clone());
+    }
+}
+
+/// Renders and writes the segments marked in `dirty` that differ from
+/// `cache`, as one strip image when all four do. Returns how many were written.
+pub fn flush_strip_segments(
+    deck: &StreamDeck,
+    displays: &[Option<EncoderDisplay>; 4],
+    dirty: &[bool; 4],
+    cache: &mut StripCache,
+) -> Result<usize> {
+    let changed = cache.changed(displays, dirty);
+    let segments = render_segments(displays, &changed, render_segment);
+    let written = segments.len();
+    if written == displays.len() {
+        let mut canvas = RgbImage::new(SEGMENT_WIDTH * displays.len() as u32, SEGMENT_HEIGHT);
+        for (index, segment) in &segments {
+            overlay_segment(&mut canvas, segment, *index as u32 * SEGMENT_WIDTH);
+        }
+        let image = ImageRect::from_image(DynamicImage::ImageRgb8(canvas))
+            .context("failed to encode LCD segment into JPEG")?;
+        deck.write_lcd(0, 0, &image)
+            .context("failed to push LCD strip image")?;
+    } else {
+        for (index, segment) in segments {
+            let offset_x = (index as u32 * SEGMENT_WIDTH) as u16;
+            let image = ImageRect::from_image(DynamicImage::ImageRgb8(segment))
+                .context("failed to encode LCD segment into JPEG")?;
+            deck.write_lcd(offset_x, 0, &image)
+                .with_context(|| format!("failed to push LCD segment at x={offset_x}"))?;
+        }
+    }
+    for index in changed {
+        cache.store(index, &displays[index]);
+    }
+    Ok(written)
+}
+
+fn render_segments(
+    displays: &[Option<EncoderDisplay>; 4],
+    indices: &[usize],
+    mut render: impl FnMut(&Option<EncoderDisplay>) -> RgbImage,
+) -> Vec<(usize, RgbImage)> {
+    indices
+        .iter()
+        .map(|index| (*index, render(&displays[*index])))
+        .collect()
+}
+
+/// Writes the `changed` keys; keys marked in `pressed` are drawn highlighted.
 pub fn flush_buttons(
     deck: &StreamDeck,
     button_icons: &[Option<ButtonImage>],
     changed: &[u8],
+    pressed: &[bool],
 ) -> Result<()> {
     if changed.is_empty() {
         return Ok(());
@@ -55,7 +185,8 @@ pub fn flush_buttons(
 
         match button_icons[idx].as_ref() {
             Some(icon) => {
-                let image = render_button_icon(kind, icon)?;
+                let highlighted = pressed.get(idx).copied().unwrap_or(false);
+                let image = render_button_icon(kind, icon, highlighted)?;
                 deck.set_button_image(*index, image)
                     .with_context(|| format!("failed to set button image for index {index}"))?;
             }
@@ -89,48 +220,91 @@ pub fn initialize_button_placeholders(
         id: "placeholder".into(),
         image: placeholder,
         tint: None,
+        label: None,
+        animation: None,
+        background: None,
+        badge: None,
     };
 
-    for slot in button_icons.iter_mut() {
+    for slot in button_icons.iter_mut().filter(|slot| slot.is_none()) {
         *slot = Some(template.clone());
     }
 
     let changed: Vec<u8> = (0..button_icons.len()).map(|index| index as u8).collect();
-    flush_buttons(deck, button_icons, &changed)
+    flush_buttons(deck, button_icons, &changed, &[])
 }
 
 fn compose_strip(displays: &[Option<EncoderDisplay>; 4]) -> Result<ImageRect> {
+    let dynamic = DynamicImage::ImageRgb8(compose_strip_image(displays));
+    ImageRect::from_image(dynamic).context("failed to encode LCD segment into JPEG")
+}
+
+pub fn compose_strip_image(displays: &[Option<EncoderDisplay>; 4]) -> RgbImage {
     let width = SEGMENT_WIDTH * displays.len() as u32;
-    let mut canvas = RgbImage::from_pixel(width, SEGMENT_HEIGHT, Rgb(BACKGROUND));
+    let mut canvas = RgbImage::new(width, SEGMENT_HEIGHT);
 
     for (index, display) in displays.iter().enumerate() {
         let segment = render_segment(display);
         overlay_segment(&mut canvas, &segment, index as u32 * SEGMENT_WIDTH);
     }
 
-    let dynamic = DynamicImage::ImageRgb8(canvas);
-    ImageRect::from_image(dynamic).context("failed to encode LCD segment into JPEG")
+    canvas
+}
+
+/// `text` centred across the full strip at the largest scale that fits.
+pub fn compose_banner_image(text: &str, color: [u8; 3]) -> RgbImage {
+    let width = SEGMENT_WIDTH * 4;
+    let mut canvas = RgbImage::from_pixel(width, SEGMENT_HEIGHT, Rgb(KEY_BACKGROUND));
+    let max_width = width - SEGMENT_MARGIN * 2;
+    let text = text.to_ascii_uppercase();
+    let scale = (2..=BANNER_MAX_SCALE)
+        .rev()
+        .find(|scale| font::measure_text(&text, *scale).0 <= max_width)
+        .unwrap_or(2);
+    let text = ellipsize_to_width(&text, max_width, scale, TextFace::Bitmap);
+    let (text_width, text_height) = font::measure_text(&text, scale);
+    font::draw_text(
+        &mut canvas,
+        &text,
+        width.saturating_sub(text_width) / 2,
+        SEGMENT_HEIGHT.saturating_sub(text_height) / 2,
+        scale,
+        color,
+    );
+    canvas
 }
 
 fn render_segment(display: &Option<EncoderDisplay>) -> RgbImage {
-    let mut segment = RgbImage::from_pixel(SEGMENT_WIDTH, SEGMENT_HEIGHT, Rgb(BACKGROUND));
-    draw_border(&mut segment);
+    let theme = display.as_ref().map(|data| data.theme).unwrap_or_default();
+    let mut segment = RgbImage::from_pixel(SEGMENT_WIDTH, SEGMENT_HEIGHT, Rgb(theme.background));
+    if theme.border {
+        draw_border(&mut segment);
+    }
 
     if let Some(data) = display {
-        draw_title(&mut segment, &data.title);
+        let face = TextFace::for_theme(&theme);
+        let text_left = match data.icon.as_deref() {
+            Some(icon) => draw_art(&mut segment, icon),
+            None => 0,
+        };
+        draw_title(&mut segment, &data.title, text_left, theme.title, face);
         draw_value(
             &mut segment,
             &data.value,
             data.status.is_some(),
-            data.value_color,
+            data.value_color.unwrap_or(theme.value),
+            text_left,
+            face,
         );
 
         if let Some(status) = &data.status {
-            draw_status(&mut segment, status);
+            draw_status(&mut segment, status, text_left, theme.status, face);
         }
 
         if let Some(progress) = data.progress {
-            draw_progress(&mut segment, progress, data.progress_color);
+            let legacy = data.progress_color.map(ProgressStyle::Solid);
+            let style = data.progress_style.as_ref().or(legacy.as_ref());
+            draw_progress(&mut segment, progress, &theme, style);
         }
     } else {
         font::draw_text(
@@ -169,68 +343,137 @@ fn draw_border(segment: &mut RgbImage) {
     }
 }
 
-fn draw_title(segment: &mut RgbImage, title: &str) {
-    let text = title.to_ascii_uppercase();
-    font::draw_text(
+// Draws cover art on the left of the segment and returns where text may start.
+fn draw_art(segment: &mut RgbImage, art: &RgbaImage) -> u32 {
+    let (width, height) = scale_to_fit(art.width(), art.height(), ART_SIZE, ART_SIZE);
+    let resized: Cow<'_, RgbaImage> = if art.width() == width && art.height() == height {
+        Cow::Borrowed(art)
+    } else {
+        Cow::Owned(image::imageops::resize(
+            art,
+            width,
+            height,
+            FilterType::Triangle,
+        ))
+    };
+    let offset_y = SEGMENT_HEIGHT.saturating_sub(height) / 2;
+    overlay_rgba(segment, resized.as_ref(), SEGMENT_MARGIN, offset_y);
+    SEGMENT_MARGIN + ART_SIZE
+}
+
+fn centered_x(text_left: u32, text_width: u32) -> u32 {
+    let available = SEGMENT_WIDTH.saturating_sub(text_left);
+    let offset = match available.checked_sub(text_width) {
+        Some(delta) => (delta / 2).max(SEGMENT_MARGIN),
+        None => SEGMENT_MARGIN,
+    };
+    text_left + offset
+}
+
+fn draw_title(segment: &mut RgbImage, title: &str, text_left: u32, color: [u8; 3], face: TextFace) {
+    let text = face.label(title);
+    face.draw(
         segment,
         &text,
-        SEGMENT_MARGIN,
+        text_left + SEGMENT_MARGIN,
         SEGMENT_MARGIN,
         2,
-        TITLE_COLOR,
+        color,
     );
 }
 
-fn draw_value(segment: &mut RgbImage, value: &str, has_status: bool, color: Option<[u8; 3]>) {
+fn draw_value(
+    segment: &mut RgbImage,
+    value: &str,
+    has_status: bool,
+    color: [u8; 3],
+    text_left: u32,
+    face: TextFace,
+) {
     let scale = 4;
-    let (text_width, text_height) = font::measure_text(value, scale);
+    let (text_width, text_height) = face.measure(value, scale);
     let mut y_center = (SEGMENT_HEIGHT / 2).saturating_sub(text_height / 2);
     if has_status {
         y_center = y_center.saturating_sub(6);
     }
-    let x = match SEGMENT_WIDTH.checked_sub(text_width) {
-        Some(delta) => (delta / 2).max(SEGMENT_MARGIN),
-        None => SEGMENT_MARGIN,
-    };
-    font::draw_text(
+    let x = centered_x(text_left, text_width);
+    face.draw(
         segment,
         value,
         x,
         y_center.max(SEGMENT_MARGIN),
         scale,
-        color.unwrap_or(VALUE_COLOR),
+        color,
     );
 }
 
-fn draw_status(segment: &mut RgbImage, status: &str) {
-    let text = status.to_ascii_uppercase();
+fn draw_status(
+    segment: &mut RgbImage,
+    status: &str,
+    text_left: u32,
+    color: [u8; 3],
+    face: TextFace,
+) {
     let scale = 2;
-    let (text_width, text_height) = font::measure_text(&text, scale);
-    let x = match SEGMENT_WIDTH.checked_sub(text_width) {
-        Some(delta) => (delta / 2).max(SEGMENT_MARGIN),
-        None => SEGMENT_MARGIN,
+    let max_width = SEGMENT_WIDTH.saturating_sub(text_left + SEGMENT_MARGIN * 2);
+    // Installed fonts keep the case, which is most of what they are for.
+    let text = match face {
+        TextFace::Bitmap => status.to_ascii_uppercase(),
+        TextFace::Ttf => status.to_string(),
     };
+    let text = ellipsize_to_width(&text, max_width, scale, face);
+    let (text_width, text_height) = face.measure(&text, scale);
+    let x = centered_x(text_left, text_width);
     let y = SEGMENT_HEIGHT.saturating_sub(PROGRESS_MARGIN + PROGRESS_HEIGHT + text_height + 4);
-    font::draw_text(segment, &text, x, y, scale, STATUS_COLOR);
+    face.draw(segment, &text, x, y, scale, color);
 }
 
-fn draw_progress(segment: &mut RgbImage, mut progress: f32, color: Option<[u8; 3]>) {
-    progress = progress.clamp(0.0, 1.0);
+fn draw_progress(
+    segment: &mut RgbImage,
+    progress: f32,
+    theme: &SegmentTheme,
+    style: Option<&ProgressStyle>,
+) {
     let width = SEGMENT_WIDTH.saturating_sub(PROGRESS_MARGIN * 2);
     let x0 = PROGRESS_MARGIN;
     let y0 = SEGMENT_HEIGHT.saturating_sub(PROGRESS_MARGIN + PROGRESS_HEIGHT);
-    let fg = color.unwrap_or(PROGRESS_FG);
 
     for y in 0..PROGRESS_HEIGHT {
         for x in 0..width {
-            segment.put_pixel(x0 + x, y0 + y, Rgb(PROGRESS_BG));
+            segment.put_pixel(x0 + x, y0 + y, Rgb(theme.progress_background));
         }
     }
 
-    let filled = (progress * width as f32).round() as u32;
-    for y in 0..PROGRESS_HEIGHT {
-        for x in 0..filled {
-            segment.put_pixel(x0 + x, y0 + y, Rgb(fg));
+    let filled = (progress.clamp(0.0, 1.0) * width as f32).round() as u32;
+    for x in 0..filled {
+        let position = x as f32 / width.saturating_sub(1).max(1) as f32;
+        let fill = fill_color(style, progress, position, theme.progress);
+        for y in 0..PROGRESS_HEIGHT {
+            segment.put_pixel(x0 + x, y0 + y, Rgb(fill));
+        }
+    }
+}
+
+// `position` runs from 0 at the left end of the bar to 1 at the right end.
+fn fill_color(
+    style: Option<&ProgressStyle>,
+    progress: f32,
+    position: f32,
+    base: [u8; 3],
+) -> [u8; 3] {
+    match style {
+        None => base,
+        Some(ProgressStyle::Solid(color)) => *color,
+        Some(ProgressStyle::Thresholds(bands)) => bands
+            .iter()
+            .find(|(up_to, _)| progress <= *up_to)
+            .map_or(base, |(_, color)| *color),
+        Some(ProgressStyle::Gradient { from, to }) => {
+            let channel = |index: usize| {
+                let (from, to) = (from[index] as f32, to[index] as f32);
+                (from + (to - from) * position).round() as u8
+            };
+            [channel(0), channel(1), channel(2)]
         }
     }
 }
@@ -238,13 +481,38 @@ fn draw_progress(segment: &mut RgbImage, mut progress: f32, color: Option<[u8; 3
 fn render_button_icon(
     kind: elgato_streamdeck::info::Kind,
     icon: &ButtonImage,
+    highlighted: bool,
 ) -> Result<DynamicImage> {
     use elgato_streamdeck::info::ImageFormat;
 
     let ImageFormat { size, .. } = kind.key_image_format();
-    let width = size.0 as u32;
-    let height = size.1 as u32;
-    let mut canvas = RgbImage::from_pixel(width, height, Rgb(KEY_BACKGROUND));
+    let mut image = render_key_image(size.0 as u32, size.1 as u32, icon);
+    if highlighted {
+        highlight_pressed_key(&mut image);
+    }
+    Ok(DynamicImage::ImageRgb8(image))
+}
+
+// Lightens the whole key and frames it, so a press shows even on dark icons.
+fn highlight_pressed_key(canvas: &mut RgbImage) {
+    let (width, height) = canvas.dimensions();
+    let border = (width / 24).max(2);
+    for (x, y, pixel) in canvas.enumerate_pixels_mut() {
+        let on_border = x < border || y < border || x >= width - border || y >= height - border;
+        if on_border {
+            *pixel = Rgb(KEY_PRESSED_BORDER);
+            continue;
+        }
+        for channel in pixel.0.iter_mut() {
+            let lifted = *channel as f32 + (255.0 - *channel as f32) * KEY_PRESSED_LIGHTEN;
+            *channel = lifted.round() as u8;
+        }
+    }
+}
+
+pub fn render_key_image(width: u32, height: u32, icon: &ButtonImage) -> RgbImage {
+    let background = icon.background.unwrap_or(KEY_BACKGROUND);
+    let mut canvas = RgbImage::from_pixel(width, height, Rgb(background));
 
     let max_width = (width as f32 * 0.78).max(1.0) as u32;
     let max_height = (height as f32 * 0.78).max(1.0) as u32;
@@ -275,8 +543,96 @@ fn render_button_icon(
     } else {
         overlay_rgba(&mut canvas, resized.as_ref(), offset_x, offset_y);
     }
+    if let Some(badge) = icon
+        .badge
+        .as_deref()
+        .filter(|badge| !badge.trim().is_empty())
+    {
+        draw_key_badge(&mut canvas, badge.trim());
+    }
+
+    if let Some(label) = icon
+        .label
+        .as_deref()
+        .filter(|label| !label.trim().is_empty())
+    {
+        draw_key_label(&mut canvas, label.trim());
+    }
+
+    canvas
+}
+
+// 72 px keys get single-pixel glyphs; the 96 px and larger keys get double.
+fn draw_key_label(canvas: &mut RgbImage, label: &str) {
+    let (width, height) = canvas.dimensions();
+    let scale = if width >= 96 { 2 } else { 1 };
+    let padding = 2 * scale;
+    let text = ellipsize_to_width(
+        label,
+        width.saturating_sub(padding * 2),
+        scale,
+        TextFace::Bitmap,
+    );
+    let (text_width, text_height) = font::measure_text(&text, scale);
+    if text_height == 0 {
+        return;
+    }
+
+    let band_top = height.saturating_sub(text_height + padding * 2);
+    shade_area(canvas, 0, band_top, width, height);
+
+    let x = width.saturating_sub(text_width) / 2;
+    font::draw_text(canvas, &text, x, band_top + padding, scale, KEY_LABEL_COLOR);
+}
+
+// Shaded like the label band, so it reads over light icons too.
+fn draw_key_badge(canvas: &mut RgbImage, badge: &str) {
+    let (width, height) = canvas.dimensions();
+    let scale = if width >= 96 { 2 } else { 1 };
+    let padding = 2 * scale;
+    let (text_width, text_height) = font::measure_text(badge, scale);
+    if text_height == 0 {
+        return;
+    }
+
+    let left = width.saturating_sub(text_width + padding * 2);
+    let bottom = (text_height + padding * 2).min(height);
+    shade_area(canvas, left, 0, width, bottom);
+    font::draw_text(
+        canvas,
+        badge,
+        left + padding,
+        padding,
+        scale,
+        KEY_LABEL_COLOR,
+    );
+}
+
+fn shade_area(canvas: &mut RgbImage, left: u32, top: u32, right: u32, bottom: u32) {
+    for y in top..bottom {
+        for x in left..right {
+            let pixel = canvas.get_pixel_mut(x, y);
+            for channel in pixel.0.iter_mut() {
+                *channel = (*channel as f32 * KEY_LABEL_BAND_SHADE).round() as u8;
+            }
+        }
+    }
+}
+
+fn ellipsize_to_width(text: &str, max_width: u32, scale: u32, face: TextFace) -> String {
+    if face.measure(text, scale).0 <= max_width {
+        return text.to_string();
+    }
 
-    Ok(DynamicImage::ImageRgb8(canvas))
+    let mut truncated = String::new();
+    for ch in text.chars() {
+        let candidate = format!("{truncated}{ch}…");
+        if face.measure(&candidate, scale).0 > max_width {
+            break;
+        }
+        truncated.push(ch);
+    }
+    format!("{}…", truncated.trim_end())
 }
 
 pub fn clear_strip(deck: &StreamDeck) -> Result<()> {
@@ -388,3 +744,321 @@ fn overlay_tinted(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const GOLDEN_THEMED_SEGMENT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/hardware/golden/segment_themed.png"
+    );
+
+    fn white_icon(label: Option<&str>) -> ButtonImage {
+        ButtonImage {
+            id: "white".into(),
+            image: Arc::new(RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]))),
+            tint: None,
+            label: label.map(str::to_string),
+            animation: None,
+            background: None,
+            badge: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_segments_are_not_rendered_again() {
+        let mut displays = [
+            Some(EncoderDisplay::new("vol", "40%")),
+            Some(EncoderDisplay::new("bright", "70%")),
+            None,
+            None,
+        ];
+        let mut cache = StripCache::default();
+        let mut rendered = 0;
+        let mut render = |displays: &[Option<EncoderDisplay>; 4], dirty: &[bool; 4]| {
+            let changed = cache.changed(displays, dirty);
+            let segments = render_segments(displays, &changed, |display| {
+                rendered += 1;
+                render_segment(display)
+            });
+            for index in changed {
+                cache.store(index, &displays[index]);
+            }
+            segments
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(render(&displays, &[true; 4]), [0, 1, 2, 3]);
+
+        displays[1] = Some(EncoderDisplay::new("bright", "75%"));
+        assert_eq!(render(&displays, &[true, true, false, false]), [1]);
+        assert!(render(&displays, &[true; 4]).is_empty());
+
+        // The theme is part of what a segment shows.
+        displays[0].as_mut().unwrap().theme.background = [40, 0, 0];
+        assert_eq!(render(&displays, &[true; 4]), [0]);
+        assert_eq!(rendered, 6);
+
+        cache.invalidate();
+        assert_eq!(cache.changed(&displays, &[true, false, false, false]), [0]);
+    }
+
+    #[test]
+    fn only_dirty_segments_are_rendered() {
+        let displays = [
+            Some(EncoderDisplay::new("vol", "40%")),
+            None,
+            Some(EncoderDisplay::new("timer", "04:59")),
+            None,
+        ];
+        let mut rendered = 0;
+        let segments = render_segments(&displays, &[2], |display| {
+            rendered += 1;
+            render_segment(display)
+        });
+        assert_eq!(rendered, 1);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].0, 2);
+
+        let full = compose_strip_image(&displays);
+        let segment = &segments[0].1;
+        assert_eq!(segment.dimensions(), (SEGMENT_WIDTH, SEGMENT_HEIGHT));
+        assert!(
+            segment
+                .enumerate_pixels()
+                .all(|(x, y, pixel)| full.get_pixel(400 + x, y) == pixel)
+        );
+    }
+
+    #[test]
+    fn long_labels_are_ellipsized_to_fit() {
+        let text = ellipsize_to_width("TERMINAL PROFILE", 68, 1, TextFace::Bitmap);
+        assert!(text.ends_with('…'));
+        assert!(font::measure_text(&text, 1).0 <= 68);
+        assert_eq!(ellipsize_to_width("TERM", 68, 1, TextFace::Bitmap), "TERM");
+    }
+
+    #[test]
+    fn pressed_keys_are_framed_and_lightened() {
+        let mut canvas = RgbImage::from_pixel(72, 72, Rgb([0, 0, 0]));
+        highlight_pressed_key(&mut canvas);
+        assert_eq!(canvas.get_pixel(0, 40), &Rgb(KEY_PRESSED_BORDER));
+        assert_eq!(canvas.get_pixel(71, 71), &Rgb(KEY_PRESSED_BORDER));
+        assert_eq!(canvas.get_pixel(36, 36), &Rgb([89, 89, 89]));
+    }
+
+    #[test]
+    fn long_status_stays_inside_the_segment_margin() {
+        let mut display = EncoderDisplay::new("spotify", "Song");
+        display.status = Some("Artist One, Artist Two, Artist Three".into());
+        let segment = render_segment(&Some(display));
+
+        let status_rows = SEGMENT_HEIGHT - PROGRESS_MARGIN - PROGRESS_HEIGHT - 20
+            ..SEGMENT_HEIGHT - PROGRESS_MARGIN - PROGRESS_HEIGHT;
+        for y in status_rows {
+            for x in SEGMENT_WIDTH - SEGMENT_MARGIN..SEGMENT_WIDTH - 1 {
+                let background = SegmentTheme::default().background;
+                assert_eq!(segment.get_pixel(x, y), &Rgb(background), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn banners_span_the_strip_inside_its_margins() {
+        let color = [255, 200, 0];
+        let lit_columns = |image: &RgbImage| {
+            let columns: Vec<u32> = (0..image.width())
+                .filter(|x| (0..image.height()).any(|y| image.get_pixel(*x, y) == &Rgb(color)))
+                .collect();
+            (columns[0], *columns.last().unwrap())
+        };
+
+        let short = compose_banner_image("saved", color);
+        assert_eq!(short.dimensions(), (SEGMENT_WIDTH * 4, SEGMENT_HEIGHT));
+        let (left, right) = lit_columns(&short);
+        assert!((left + right).abs_diff(short.width()) <= 2);
+
+        let long = compose_banner_image(&"configuration reloaded ".repeat(8), color);
+        let (left, right) = lit_columns(&long);
+        assert!(left >= SEGMENT_MARGIN && right < long.width() - SEGMENT_MARGIN);
+    }
+
+    // Set UPDATE_GOLDEN=1 to regenerate the reference image after an
+    // intentional renderer change.
+    #[test]
+    fn themed_segment_matches_golden_image() {
+        let display = EncoderDisplay::new("volume", "40%")
+            .with_status("speakers")
+            .with_progress(0.4);
+        let mut themed = display.clone();
+        themed.theme = SegmentTheme {
+            background: [250, 244, 230],
+            title: [90, 70, 40],
+            value: [20, 20, 20],
+            status: [180, 60, 30],
+            progress_background: [220, 210, 190],
+            progress: [230, 120, 0],
+            border: false,
+            use_ttf: false,
+        };
+
+        let plain = render_segment(&Some(display));
+        let rendered = render_segment(&Some(themed));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            rendered.save(GOLDEN_THEMED_SEGMENT).unwrap();
+        }
+        let golden = image::open(GOLDEN_THEMED_SEGMENT)
+            .expect("golden image missing; run with UPDATE_GOLDEN=1")
+            .to_rgb8();
+        assert!(
+            rendered.as_raw() == golden.as_raw(),
+            "themed segment differs from golden image"
+        );
+
+        assert_eq!(plain.get_pixel(0, 0), &Rgb(BORDER_COLOR));
+        assert_eq!(rendered.get_pixel(0, 0), &Rgb([250, 244, 230]));
+        let fill = (PROGRESS_MARGIN + 1, SEGMENT_HEIGHT - PROGRESS_MARGIN - 1);
+        assert_eq!(plain.get_pixel(fill.0, fill.1), &Rgb([0, 180, 120]));
+        assert_eq!(rendered.get_pixel(fill.0, fill.1), &Rgb([230, 120, 0]));
+    }
+
+    fn bar_pixels(progress: f32, style: Option<ProgressStyle>, columns: &[u32]) -> Vec<[u8; 3]> {
+        let mut segment = RgbImage::new(SEGMENT_WIDTH, SEGMENT_HEIGHT);
+        draw_progress(
+            &mut segment,
+            progress,
+            &SegmentTheme::default(),
+            style.as_ref(),
+        );
+        let y = SEGMENT_HEIGHT - PROGRESS_MARGIN - 1;
+        columns
+            .iter()
+            .map(|column| segment.get_pixel(PROGRESS_MARGIN + column, y).0)
+            .collect()
+    }
+
+    #[test]
+    fn progress_styles_colour_the_expected_columns() {
+        let theme = SegmentTheme::default();
+        let red = [230, 60, 60];
+        let amber = [240, 170, 0];
+
+        assert_eq!(
+            bar_pixels(0.5, None, &[0, 89, 90, 179]),
+            [
+                theme.progress,
+                theme.progress,
+                theme.progress_background,
+                theme.progress_background
+            ]
+        );
+        assert_eq!(
+            bar_pixels(1.0, Some(ProgressStyle::Solid(red)), &[0, 179]),
+            [red, red]
+        );
+
+        let bands = ProgressStyle::Thresholds(vec![(0.1, red), (0.5, amber)]);
+        assert_eq!(
+            bar_pixels(0.05, Some(bands.clone()), &[8, 9]),
+            [red, theme.progress_background]
+        );
+        assert_eq!(
+            bar_pixels(0.3, Some(bands.clone()), &[0, 50]),
+            [amber, amber]
+        );
+        assert_eq!(
+            bar_pixels(0.8, Some(bands.clone()), &[0, 140]),
+            [theme.progress, theme.progress]
+        );
+        // Values past full still pick a band although the bar stops at full.
+        let over = ProgressStyle::Thresholds(vec![(1.0, theme.progress), (1.25, red)]);
+        assert_eq!(bar_pixels(1.2, Some(over), &[179]), [red]);
+
+        let gradient = ProgressStyle::Gradient {
+            from: [0, 0, 0],
+            to: [255, 0, 179],
+        };
+        assert_eq!(
+            bar_pixels(1.0, Some(gradient.clone()), &[0, 89, 179]),
+            [[0, 0, 0], [127, 0, 89], [255, 0, 179]]
+        );
+        assert_eq!(
+            bar_pixels(0.5, Some(gradient), &[89, 90]),
+            [[127, 0, 89], theme.progress_background]
+        );
+    }
+
+    #[test]
+    fn label_band_darkens_bottom_of_key() {
+        let plain = render_key_image(72, 72, &white_icon(None));
+        let labelled = render_key_image(72, 72, &white_icon(Some("Dev")));
+
+        // The icon covers this pixel; the band shades it.
+        let (x, y) = (14, 62);
+        assert_eq!(plain.get_pixel(x, y), &Rgb([255, 255, 255]));
+        assert!(labelled.get_pixel(x, y)[0] < 100);
+        assert_eq!(plain.get_pixel(36, 20), labelled.get_pixel(36, 20));
+    }
+
+    #[test]
+    fn tint_recolours_by_alpha_over_the_background() {
+        let background = [20, 40, 60];
+        let mut canvas = RgbImage::from_pixel(3, 1, Rgb(background));
+        let icon = RgbaImage::from_fn(3, 1, |x, _| Rgba([200, 10, 10, [255, 128, 0][x as usize]]));
+        overlay_tinted(&mut canvas, &icon, 0, 0, [0, 200, 100]);
+
+        assert_eq!(canvas.get_pixel(0, 0), &Rgb([0, 200, 100]));
+        assert_eq!(canvas.get_pixel(1, 0), &Rgb([10, 120, 80]));
+        assert_eq!(canvas.get_pixel(2, 0), &Rgb(background));
+    }
+
+    #[test]
+    fn non_square_icons_are_letterboxed_in_the_centre() {
+        let white = Rgb([255, 255, 255]);
+        let background = Rgb(KEY_BACKGROUND);
+        let mut icon = white_icon(None);
+
+        icon.image = Arc::new(RgbaImage::from_pixel(40, 20, Rgba([255, 255, 255, 255])));
+        let wide = render_key_image(72, 72, &icon);
+        assert_eq!(wide.get_pixel(8, 22), &white);
+        assert_eq!(wide.get_pixel(63, 49), &white);
+        assert_eq!(wide.get_pixel(36, 21), &background);
+        assert_eq!(wide.get_pixel(36, 50), &background);
+        assert_eq!(wide.get_pixel(7, 36), &background);
+
+        icon.image = Arc::new(RgbaImage::from_pixel(20, 40, Rgba([255, 255, 255, 255])));
+        let tall = render_key_image(72, 72, &icon);
+        assert_eq!(tall.get_pixel(22, 8), &white);
+        assert_eq!(tall.get_pixel(49, 63), &white);
+        assert_eq!(tall.get_pixel(21, 36), &background);
+        assert_eq!(tall.get_pixel(50, 36), &background);
+        assert_eq!(tall.get_pixel(36, 7), &background);
+    }
+
+    #[test]
+    fn badge_sits_in_the_top_right_corner() {
+        let mut icon = white_icon(None);
+        icon.image = Arc::new(RgbaImage::from_pixel(72, 72, Rgba([255, 255, 255, 255])));
+        let plain = render_key_image(72, 72, &icon);
+        icon.badge = Some("2/3".into());
+        let badged = render_key_image(72, 72, &icon);
+
+        let (text_width, _) = font::measure_text("2/3", 1);
+        let left = 72 - text_width - 4;
+        assert_eq!(plain.get_pixel(left, 10), &Rgb([255, 255, 255]));
+        assert!(badged.get_pixel(left, 10)[0] < 100);
+        assert_eq!(
+            badged.get_pixel(left - 1, 10),
+            plain.get_pixel(left - 1, 10)
+        );
+        assert_eq!(badged.get_pixel(70, 60), plain.get_pixel(70, 60));
+        let lit = (left..72)
+            .flat_map(|x| (0..14).map(move |y| (x, y)))
+            .filter(|(x, y)| badged.get_pixel(*x, *y) == &Rgb(KEY_LABEL_COLOR))
+            .count();
+        assert!(lit > 0);
+    }
+}
@@ -0,0 +1,416 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use image::RgbImage;
+use tracing::{info, warn};
+
+use crate::hardware::backend::{
+    ButtonImage, DeviceCapabilities, DisplayPipeline, EncoderDisplay, EncoderId, EventSink,
+    HardwareEvent,
+};
+use crate::hardware::render;
+
+// Matches a Stream Deck Plus so every controller is exercised.
+pub const SIMULATED_CAPABILITIES: DeviceCapabilities = DeviceCapabilities {
+    key_count: 8,
+    encoder_count: 4,
+    lcd_strip: true,
+};
+const KEY_SIZE: u32 = 120;
+
+/// Renders the LCD strip and button icons to PNG files instead of a device.
+pub struct SimulatorPipeline {
+    output_dir: PathBuf,
+    displays: Mutex<[Option<EncoderDisplay>; 4]>,
+    banner: Mutex<Option<(String, [u8; 3])>>,
+}
+
+impl SimulatorPipeline {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Result<Self> {
+        let output_dir = output_dir.into();
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("failed to create {}", output_dir.display()))?;
+        let pipeline = Self {
+            output_dir,
+            displays: Mutex::new([None, None, None, None]),
+            banner: Mutex::new(None),
+        };
+        pipeline.write_strip()?;
+        Ok(pipeline)
+    }
+
+    pub fn strip_path(&self) -> PathBuf {
+        self.output_dir.join("strip.png")
+    }
+
+    pub fn button_path(&self, index: u8) -> PathBuf {
+        self.output_dir.join(format!("button_{index}.png"))
+    }
+
+    /// Keeps the banner in `strip.png` until `hide_banner`; encoder updates
+    /// meanwhile are only remembered.
+    pub fn show_banner(&self, text: &str, color: [u8; 3]) -> Result<()> {
+        *self.banner.lock().expect("simulator banner mutex poisoned") =
+            Some((text.to_string(), color));
+        self.write_strip()
+    }
+
+    pub fn hide_banner(&self) -> Result<()> {
+        *self.banner.lock().expect("simulator banner mutex poisoned") = None;
+        self.write_strip()
+    }
+
+    fn write_strip(&self) -> Result<()> {
+        if let Some((text, color)) = self
+            .banner
+            .lock()
+            .expect("simulator banner mutex poisoned")
+            .as_ref()
+        {
+            return save_png(
+                &render::compose_banner_image(text, *color),
+                &self.strip_path(),
+            );
+        }
+        let image = {
+            let displays = self
+                .displays
+                .lock()
+                .expect("simulator display mutex poisoned");
+            render::compose_strip_image(&displays)
+        };
+        save_png(&image, &self.strip_path())
+    }
+}
+
+impl DisplayPipeline for SimulatorPipeline {
+    fn update_encoder(&self, encoder: EncoderId, display: EncoderDisplay) -> Result<()> {
+        self.displays
+            .lock()
+            .expect("simulator display mutex poisoned")[encoder.index()] = Some(display);
+        self.write_strip()
+    }
+
+    fn update_button_icon(&self, index: u8, icon: Option<ButtonImage>) -> Result<()> {
+        if index >= SIMULATED_CAPABILITIES.key_count {
+            bail!("button index {index} is out of range for the simulator");
+        }
+        let path = self.button_path(index);
+        match icon {
+            Some(icon) => save_png(&render::render_key_image(KEY_SIZE, KEY_SIZE, &icon), &path),
+            None => remove_if_present(&path),
+        }
+    }
+
+    fn clear_encoder_display(&self, encoder: EncoderId) -> Result<()> {
+        self.displays
+            .lock()
+            .expect("simulator display mutex poisoned")[encoder.index()] = None;
+        self.write_strip()
+    }
+
+    fn clear_all_displays(&self) -> Result<()> {
+        *self
+            .displays
+            .lock()
+            .expect("simulator display mutex poisoned") = [None, None, None, None];
+        self.hide_banner()?;
+        for index in 0..SIMULATED_CAPABILITIES.key_count {
+            remove_if_present(&self.button_path(index))?;
+        }
+        Ok(())
+    }
+}
+
+fn save_png(image: &RgbImage, path: &Path) -> Result<()> {
+    image
+        .save(path)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("failed to remove {}", path.display())),
+    }
+}
+
+#[derive(Debug)]
+enum SimulatedInput {
+    Event(HardwareEvent),
+    Wait(Duration),
+}
+
+/// Feeds events from `fifo` (reopened whenever its writer closes) or, when
+/// `None`, from stdin until EOF.
+pub fn spawn_input_reader(fifo: Option<PathBuf>, events: EventSink) -> Result<()> {
+    thread::Builder::new()
+        .name("streamdeck-simulator-input".into())
+        .spawn(move || match fifo {
+            Some(path) => loop {
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        warn!(error = %err, path = %path.display(), "failed to open simulator FIFO");
+                        return;
+                    }
+                };
+                if !forward_lines(BufReader::new(file), &events) {
+                    return;
+                }
+            },
+            None => {
+                forward_lines(io::stdin().lock(), &events);
+                info!("simulator stdin closed; no further input will be injected");
+            }
+        })
+        .context("failed to spawn simulator input reader")?;
+    Ok(())
+}
+
+// Returns false once the event receiver has gone away.
+fn forward_lines(reader: impl BufRead, events: &EventSink) -> bool {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(error = %err, "failed to read simulator input");
+                return true;
+            }
+        };
+        let inputs = match parse_command(&line) {
+            Ok(inputs) => inputs,
+            Err(err) => {
+                warn!(error = %err, %line, "ignoring simulator command");
+                continue;
+            }
+        };
+        for input in inputs {
+            match input {
+                SimulatedInput::Event(event) => {
+                    if !events.send(event) {
+                        return false;
+                    }
+                }
+                SimulatedInput::Wait(duration) => thread::sleep(duration),
+            }
+        }
+    }
+    true
+}
+
+// Commands: `turn <encoder> <delta>`, `push <encoder> [held_ms]`,
+// `press <button> [held_ms]`, `tap <x> [y]` and `swipe <from_x> <to_x>`.
+// Indices are zero-based; strip coordinates are pixels.
+fn parse_command(line: &str) -> Result<Vec<SimulatedInput>> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(Vec::new());
+    };
+    if command.starts_with('#') {
+        return Ok(Vec::new());
+    }
+
+    let mut arg = |name: &str| {
+        words
+            .next()
+            .ok_or_else(|| anyhow!("`{command}` is missing its {name}"))
+    };
+
+    let inputs = match command {
+        "turn" => {
+            let encoder = parse_encoder(arg("encoder")?)?;
+            let delta: i32 = arg("delta")?
+                .trim_start_matches('+')
+                .parse()
+                .context("delta must be an integer")?;
+            vec![SimulatedInput::Event(HardwareEvent::EncoderTurned {
+                encoder,
+                delta,
+            })]
+        }
+        "push" => {
+            let encoder = parse_encoder(arg("encoder")?)?;
+            let held = parse_held(words.next())?;
+            vec![
+                SimulatedInput::Event(HardwareEvent::EncoderPressed { encoder }),
+                SimulatedInput::Wait(held),
+                SimulatedInput::Event(HardwareEvent::EncoderReleased { encoder }),
+            ]
+        }
+        "press" => {
+            let index: u8 = arg("button")?.parse().context("button must be a number")?;
+            if index >= SIMULATED_CAPABILITIES.key_count {
+                bail!("button {index} is out of range");
+            }
+            let held = parse_held(words.next())?;
+            vec![
+                SimulatedInput::Event(HardwareEvent::ButtonPressed(index)),
+                SimulatedInput::Wait(held),
+                SimulatedInput::Event(HardwareEvent::ButtonReleased(index)),
+            ]
+        }
+        "tap" => {
+            let x = parse_strip_coordinate(arg("x")?)?;
+            let y = match words.next() {
+                Some(y) => parse_strip_coordinate(y)?,
+                None => 0,
+            };
+            // Dropped on a segment border, as the hardware backend does.
+            EncoderId::from_strip_tap(x)
+                .map(|segment| SimulatedInput::Event(HardwareEvent::TouchTap { x, y, segment }))
+                .into_iter()
+                .collect()
+        }
+        "swipe" => {
+            let from_x = parse_strip_coordinate(arg("from_x")?)?;
+            let to_x = parse_strip_coordinate(arg("to_x")?)?;
+            vec![SimulatedInput::Event(HardwareEvent::TouchSwipe {
+                from_x,
+                to_x,
+            })]
+        }
+        other => bail!("unknown command `{other}`"),
+    };
+    Ok(inputs)
+}
+
+fn parse_encoder(value: &str) -> Result<EncoderId> {
+    value
+        .parse::<usize>()
+        .ok()
+        .and_then(EncoderId::from_index)
+        .ok_or_else(|| anyhow!("encoder must be 0-3, got `{value}`"))
+}
+
+fn parse_strip_coordinate(value: &str) -> Result<u16> {
+    value
+        .parse()
+        .with_context(|| format!("strip coordinate must be a number, got `{value}`"))
+}
+
+fn parse_held(value: Option<&str>) -> Result<Duration> {
+    match value {
+        Some(ms) => Ok(Duration::from_millis(
+            ms.parse().context("hold duration must be milliseconds")?,
+        )),
+        None => Ok(Duration::ZERO),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const GOLDEN_STRIP: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/hardware/golden/strip_volume_timer.png"
+    );
+
+    #[test]
+    fn parses_line_protocol() {
+        let inputs = parse_command("turn 0 +2").unwrap();
+        assert!(matches!(
+            inputs.as_slice(),
+            [SimulatedInput::Event(HardwareEvent::EncoderTurned {
+                encoder: EncoderId::One,
+                delta: 2
+            })]
+        ));
+
+        let inputs = parse_command("press 3 700").unwrap();
+        assert!(matches!(
+            inputs.as_slice(),
+            [
+                SimulatedInput::Event(HardwareEvent::ButtonPressed(3)),
+                SimulatedInput::Wait(held),
+                SimulatedInput::Event(HardwareEvent::ButtonReleased(3)),
+            ] if *held == Duration::from_millis(700)
+        ));
+
+        let inputs = parse_command("tap 450").unwrap();
+        assert!(matches!(
+            inputs.as_slice(),
+            [SimulatedInput::Event(HardwareEvent::TouchTap {
+                x: 450,
+                segment: EncoderId::Three,
+                ..
+            })]
+        ));
+
+        assert!(parse_command("  ").unwrap().is_empty());
+        assert!(parse_command("swipe 100").is_err());
+        assert!(parse_command("turn 4 1").is_err());
+        assert!(parse_command("press 12").is_err());
+        assert!(parse_command("spin 0").is_err());
+    }
+
+    #[test]
+    fn writes_buttons_and_removes_cleared_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline = SimulatorPipeline::new(dir.path()).unwrap();
+        let icon = ButtonImage {
+            id: "square".into(),
+            image: std::sync::Arc::new(image::RgbaImage::from_pixel(
+                10,
+                10,
+                image::Rgba([255, 255, 255, 255]),
+            )),
+            tint: Some([0, 200, 0]),
+            label: None,
+            animation: None,
+            background: None,
+            badge: None,
+        };
+
+        pipeline.update_button_icon(3, Some(icon)).unwrap();
+        let written = image::open(pipeline.button_path(3)).unwrap().to_rgb8();
+        assert_eq!(written.dimensions(), (KEY_SIZE, KEY_SIZE));
+        assert_eq!(
+            written.get_pixel(KEY_SIZE / 2, KEY_SIZE / 2),
+            &Rgb([0, 200, 0])
+        );
+
+        pipeline.update_button_icon(3, None).unwrap();
+        assert!(!pipeline.button_path(3).exists());
+    }
+
+    // Set UPDATE_GOLDEN=1 to regenerate the reference image after an
+    // intentional renderer change.
+    #[test]
+    fn strip_matches_golden_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline = SimulatorPipeline::new(dir.path()).unwrap();
+        let mut volume = EncoderDisplay::new("volume", " 40%");
+        volume.progress = Some(0.4);
+        pipeline.update_encoder(EncoderId::One, volume).unwrap();
+        pipeline
+            .update_encoder(
+                EncoderId::Three,
+                EncoderDisplay::new("timer", "25:00")
+                    .with_status("set")
+                    .with_progress(1.0),
+            )
+            .unwrap();
+
+        let rendered = image::open(pipeline.strip_path()).unwrap().to_rgb8();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            rendered.save(GOLDEN_STRIP).unwrap();
+        }
+        let golden = image::open(GOLDEN_STRIP)
+            .expect("golden image missing; run with UPDATE_GOLDEN=1")
+            .to_rgb8();
+        assert_eq!(rendered.dimensions(), golden.dimensions());
+        assert!(
+            rendered.as_raw() == golden.as_raw(),
+            "strip differs from golden image"
+        );
+    }
+}
@@ -1,26 +1,99 @@
 #![allow(dead_code)]
 
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Result, anyhow};
 use crossbeam_channel::Receiver;
 use image::RgbaImage;
 
+use crate::metrics::Metrics;
+use crate::util::icons::AnimatedIcon;
+
+const STRIP_SEGMENT_WIDTH: u16 = 200;
+const STRIP_BORDER_DEADZONE: u16 = 4;
+
 #[derive(Clone, Debug)]
 pub struct HardwareConfig {
     pub serial: Option<String>,
+    pub devices: Vec<DeviceConfig>,
     pub device_brightness: u8,
+    pub allow_non_plus: bool,
+    pub max_flush_failures: u32,
+    pub idle_dim: Option<IdleDim>,
+    pub simulator: Option<SimulatorConfig>,
+    pub strip_theme: [SegmentTheme; 4],
+    pub value_animation: Option<ValueAnimation>,
+    pub metrics: Arc<Metrics>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceConfig {
+    pub serial: Option<String>,
+    pub role: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdleDim {
+    pub after: Duration,
+    pub brightness: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueAnimation {
+    pub min_delta: f32,
+    pub duration: Duration,
+}
+
+impl Default for ValueAnimation {
+    fn default() -> Self {
+        Self {
+            min_delta: 0.1,
+            duration: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Renders to PNG files under `output_dir` and reads input commands from
+/// `fifo`, or stdin when unset, instead of opening a device.
+#[derive(Clone, Debug)]
+pub struct SimulatorConfig {
+    pub output_dir: PathBuf,
+    pub fifo: Option<PathBuf>,
 }
 
 impl Default for HardwareConfig {
     fn default() -> Self {
         Self {
             serial: None,
+            devices: Vec::new(),
             device_brightness: 40,
+            allow_non_plus: false,
+            max_flush_failures: 20,
+            idle_dim: None,
+            simulator: None,
+            strip_theme: [SegmentTheme::default(); 4],
+            value_animation: Some(ValueAnimation::default()),
+            metrics: Arc::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    pub key_count: u8,
+    pub encoder_count: u8,
+    pub lcd_strip: bool,
+}
+
+impl DeviceCapabilities {
+    pub fn has_encoders(&self) -> bool {
+        self.encoder_count > 0
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EncoderId {
     One,
@@ -29,14 +102,106 @@ pub enum EncoderId {
     Four,
 }
 
-#[derive(Debug, Clone)]
+impl EncoderId {
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self::One),
+            1 => Some(Self::Two),
+            2 => Some(Self::Three),
+            3 => Some(Self::Four),
+            _ => None,
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            Self::One => 0,
+            Self::Two => 1,
+            Self::Three => 2,
+            Self::Four => 3,
+        }
+    }
+
+    pub fn all() -> [Self; 4] {
+        [Self::One, Self::Two, Self::Three, Self::Four]
+    }
+
+    /// The encoder whose LCD segment lies under `x` on the touch strip.
+    pub fn from_strip_x(x: u16) -> Self {
+        let index = (x / STRIP_SEGMENT_WIDTH).min(3);
+        Self::from_index(index as usize).unwrap_or(Self::Four)
+    }
+
+    /// Like `from_strip_x`, but `None` for a tap too close to the line
+    /// between two segments to say which one was meant.
+    pub fn from_strip_tap(x: u16) -> Option<Self> {
+        let offset = x % STRIP_SEGMENT_WIDTH;
+        let boundary = x >= STRIP_SEGMENT_WIDTH && offset < STRIP_BORDER_DEADZONE
+            || x < STRIP_SEGMENT_WIDTH * 3 && offset >= STRIP_SEGMENT_WIDTH - STRIP_BORDER_DEADZONE;
+        (!boundary).then(|| Self::from_strip_x(x))
+    }
+}
+
+/// Colours of one LCD strip segment. A display's own `value_color` and
+/// `progress_color` still win over `value` and `progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentTheme {
+    pub background: [u8; 3],
+    pub title: [u8; 3],
+    pub value: [u8; 3],
+    pub status: [u8; 3],
+    pub progress_background: [u8; 3],
+    pub progress: [u8; 3],
+    pub border: bool,
+    /// Sets text in the bundled TTF font rather than the bitmap one.
+    pub use_ttf: bool,
+}
+
+impl Default for SegmentTheme {
+    fn default() -> Self {
+        Self {
+            background: [8, 10, 18],
+            title: [180, 190, 210],
+            value: [235, 240, 255],
+            status: [120, 210, 255],
+            progress_background: [30, 35, 45],
+            progress: [0, 180, 120],
+            border: true,
+            use_ttf: false,
+        }
+    }
+}
+
+/// How the filled part of a progress bar is coloured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressStyle {
+    Solid([u8; 3]),
+    /// `(up_to, colour)` bands in ascending order: the first band whose
+    /// bound the progress does not exceed colours the whole bar. Progress
+    /// past the last bound keeps the theme colour.
+    Thresholds(Vec<(f32, [u8; 3])>),
+    /// Blends from the left end of the bar to the right, so a partly filled
+    /// bar only reaches part of the way to `to`.
+    Gradient {
+        from: [u8; 3],
+        to: [u8; 3],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct EncoderDisplay {
     pub title: String,
     pub value: String,
     pub status: Option<String>,
+    /// Thresholds see values past 1.0; the bar itself stops at full.
     pub progress: Option<f32>,
+    pub progress_style: Option<ProgressStyle>,
+    /// Deprecated alias for `ProgressStyle::Solid`, used when
+    /// `progress_style` is unset.
     pub progress_color: Option<[u8; 3]>,
     pub value_color: Option<[u8; 3]>,
+    pub icon: Option<Arc<RgbaImage>>,
+    pub theme: SegmentTheme,
 }
 
 impl EncoderDisplay {
@@ -46,10 +211,23 @@ impl EncoderDisplay {
             value: value.into(),
             status: None,
             progress: None,
+            progress_style: None,
             progress_color: None,
             value_color: None,
+            icon: None,
+            theme: SegmentTheme::default(),
         }
     }
+
+    pub fn with_status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    pub fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = Some(progress.clamp(0.0, 1.0));
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -57,16 +235,60 @@ pub struct ButtonImage {
     pub id: String,
     pub image: Arc<RgbaImage>,
     pub tint: Option<[u8; 3]>,
+    /// Drawn along the bottom of the key over a dark band.
+    pub label: Option<String>,
+    /// Played back on the device in place of `image`, which stays the still
+    /// shown by the simulator.
+    pub animation: Option<Arc<AnimatedIcon>>,
+    /// Fills the key behind transparent icon pixels instead of black.
+    pub background: Option<[u8; 3]>,
+    /// A few characters, such as "2/3", drawn in the top-right corner.
+    pub badge: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
+pub struct ShownDisplays {
+    pub encoders: [Option<EncoderDisplay>; 4],
+    pub buttons: BTreeMap<u8, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceId(pub usize);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeviceEvent {
+    pub device: DeviceId,
+    pub event: HardwareEvent,
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum HardwareEvent {
-    EncoderTurned { encoder: EncoderId, delta: i32 },
-    EncoderPressed { encoder: EncoderId },
-    EncoderReleased { encoder: EncoderId },
+    EncoderTurned {
+        encoder: EncoderId,
+        delta: i32,
+    },
+    EncoderPressed {
+        encoder: EncoderId,
+    },
+    EncoderReleased {
+        encoder: EncoderId,
+    },
     ButtonPressed(u8),
     ButtonReleased(u8),
-    Touch,
+    TouchTap {
+        x: u16,
+        y: u16,
+        segment: EncoderId,
+    },
+    TouchSwipe {
+        from_x: u16,
+        to_x: u16,
+    },
+    /// The deck went away; releases for anything held were sent first.
+    DeviceDisconnected,
+    /// A deck came back, or one missing at startup was plugged in, and shows
+    /// the displays sent while it was gone.
+    DeviceConnected,
 }
 
 pub trait DisplayPipeline: Send + Sync {
@@ -77,6 +299,48 @@ pub trait DisplayPipeline: Send + Sync {
     fn update_button_icon(&self, _index: u8, _icon: Option<ButtonImage>) -> Result<()> {
         Ok(())
     }
+
+    fn clear_encoder_display(&self, _encoder: EncoderId) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear_button(&self, index: u8) -> Result<()> {
+        self.update_button_icon(index, None)
+    }
+
+    fn clear_all_displays(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn blank_for_exit(&self, _grace: Duration) -> Result<()> {
+        self.clear_all_displays()
+    }
+
+    fn show_banner(&self, _text: &str, _duration: Duration, _color: [u8; 3]) -> Result<()> {
+        Ok(())
+    }
+
+    fn dismiss_banner(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_device_brightness(&self, _level: u8) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    pub count: usize,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendStats {
+    pub strip_flush: TimingStats,
+    pub button_flush: TimingStats,
+    pub command_drain: TimingStats,
 }
 
 #[derive(Clone, Default)]
@@ -85,12 +349,56 @@ pub struct HardwareHandle;
 impl DisplayPipeline for HardwareHandle {}
 
 impl HardwareHandle {
-    pub fn clear_all_displays(&self) -> Result<()> {
+    pub fn capabilities(&self) -> Option<DeviceCapabilities> {
+        None
+    }
+
+    pub fn device(&self) -> DeviceId {
+        DeviceId::default()
+    }
+
+    pub fn device_id(&self, role: Option<&str>) -> Option<DeviceId> {
+        role.is_none().then_some(DeviceId::default())
+    }
+
+    pub fn for_device(&self, device: DeviceId) -> Option<Self> {
+        (device == DeviceId::default()).then_some(Self)
+    }
+
+    pub fn devices(&self) -> Vec<Self> {
+        vec![Self]
+    }
+
+    pub fn stats(&self) -> Result<BackendStats> {
+        Ok(BackendStats::default())
+    }
+
+    pub fn show_placeholder(&self, _encoder: EncoderId, _display: EncoderDisplay) -> Result<()> {
         Ok(())
     }
+
+    pub fn shown(&self) -> ShownDisplays {
+        ShownDisplays::default()
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedDeck {
+    pub kind: String,
+    pub serial: String,
+}
+
+pub fn connected_decks() -> Result<Vec<ConnectedDeck>> {
+    Err(anyhow!(
+        "built without the `hardware` feature; Stream Decks cannot be listed"
+    ))
 }
 
-pub fn start(_: HardwareConfig) -> Result<(HardwareHandle, Receiver<HardwareEvent>)> {
+pub fn start(_: HardwareConfig) -> Result<(HardwareHandle, Receiver<DeviceEvent>)> {
     Err(anyhow!(
         "hardware support disabled. Enable the `hardware` feature to connect to the Stream Deck."
     ))
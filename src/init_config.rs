@@ -0,0 +1,146 @@
+//! `streamdeck_ctrl --init-config`: a first `stream-deck.json` built from the
+//! sinks and applications found on this machine.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::config;
+use crate::system::audio::PulseAudioBackend;
+use crate::system::audio_switch::{AudioSwitchBackend, PulseAudioSwitch, SinkInfo};
+use crate::system::desktop::DesktopEntry;
+
+// Tried first so the example launchers are applications people recognise.
+const FAMILIAR_ENTRIES: &[&str] = &[
+    "firefox.desktop",
+    "org.mozilla.firefox.desktop",
+    "chromium.desktop",
+    "google-chrome.desktop",
+    "org.gnome.Terminal.desktop",
+    "org.kde.konsole.desktop",
+    "code.desktop",
+    "org.gnome.Nautilus.desktop",
+    "org.kde.dolphin.desktop",
+    "thunderbird.desktop",
+    "spotify.desktop",
+];
+
+/// Writes a starter configuration to `path`, which must not exist yet
+/// unless `force` is set.
+pub fn init_config(path: &Path, force: bool) -> Result<()> {
+    let sinks = local_sinks().unwrap_or_else(|err| {
+        eprintln!("{err:#}; leaving the audio toggle out");
+        Vec::new()
+    });
+    config::write_starter_config(path, &sinks, &desktop_entries(), force)
+}
+
+/// The sinks a starter audio toggle switches between.
+pub fn local_sinks() -> Result<Vec<SinkInfo>> {
+    if !PulseAudioBackend::default().is_available() {
+        bail!("pactl not found");
+    }
+    PulseAudioSwitch::new()
+        .list_sinks()
+        .context("could not list audio sinks")
+}
+
+/// Applications for the starter launchers, familiar ones first.
+pub fn desktop_entries() -> Vec<DesktopEntry> {
+    find_desktop_entries(&application_dirs())
+}
+
+fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data) => dirs.push(PathBuf::from(data).join("applications")),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share/applications"));
+            }
+        }
+    }
+    dirs.push(PathBuf::from("/usr/share/applications"));
+    dirs
+}
+
+// Applications with a command to run, familiar ones first; an entry in an
+// earlier directory hides one with the same id in a later one.
+fn find_desktop_entries(dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut found: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+            .filter(|path| {
+                !paths
+                    .iter()
+                    .any(|seen| seen.file_name() == path.file_name())
+            })
+            .collect();
+        found.sort();
+        paths.extend(found);
+    }
+    let familiar = |path: &PathBuf| {
+        let name = path.file_name().and_then(|name| name.to_str());
+        FAMILIAR_ENTRIES
+            .iter()
+            .position(|familiar| Some(*familiar) == name)
+            .unwrap_or(FAMILIAR_ENTRIES.len())
+    };
+    paths.sort_by_key(familiar);
+
+    paths
+        .iter()
+        .filter_map(|path| DesktopEntry::from_path(path).ok())
+        .filter(|entry| {
+            entry.exec.is_some()
+                && entry
+                    .entry_type
+                    .as_deref()
+                    .is_none_or(|kind| kind.eq_ignore_ascii_case("application"))
+        })
+        .take(config::STARTER_ENTRIES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_entry(dir: &Path, id: &str, contents: &str) {
+        fs::write(dir.join(id), contents).unwrap();
+    }
+
+    #[test]
+    fn familiar_applications_come_first_and_user_entries_win() {
+        let dir = tempfile::tempdir().unwrap();
+        let (user, system) = (dir.path().join("user"), dir.path().join("system"));
+        fs::create_dir_all(&user).unwrap();
+        fs::create_dir_all(&system).unwrap();
+        let app = "[Desktop Entry]\nName=App\nExec=/usr/bin/true\nType=Application\n";
+        write_entry(&system, "aaa-tool.desktop", app);
+        write_entry(&system, "firefox.desktop", app);
+        write_entry(&user, "firefox.desktop", app);
+        write_entry(
+            &system,
+            "link.desktop",
+            "[Desktop Entry]\nName=Docs\nType=Link\n",
+        );
+
+        let entries = find_desktop_entries(&[user.clone(), system.clone()]);
+        let paths: Vec<&Path> = entries.iter().map(|e| e.source_path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                user.join("firefox.desktop").as_path(),
+                system.join("aaa-tool.desktop").as_path()
+            ]
+        );
+    }
+}
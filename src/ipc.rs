@@ -0,0 +1,346 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{debug, info, warn};
+
+use crate::hardware::{BackendStats, EncoderDisplay, EncoderId, HardwareEvent, TimingStats};
+
+const SOCKET_NAME: &str = "streamdeck_ctrl.sock";
+const MAX_HOLD: Duration = Duration::from_secs(10);
+const STATS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Work handed to the app loop by external scripts.
+#[derive(Debug)]
+pub enum IpcCommand {
+    Event(HardwareEvent),
+    SetEncoderDisplay {
+        encoder: EncoderId,
+        display: EncoderDisplay,
+    },
+    QueryStats(Sender<BackendStats>),
+}
+
+// One JSON object per line, e.g. `{"command": "turn_encoder", "encoder": 1, "delta": -2}`.
+// Encoder and button indices are zero-based, as on the hardware.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    TurnEncoder {
+        encoder: usize,
+        delta: i32,
+    },
+    PressEncoder {
+        encoder: usize,
+        #[serde(default)]
+        held_ms: u64,
+    },
+    PressButton {
+        #[serde(alias = "index")]
+        button: u8,
+        #[serde(default)]
+        held_ms: u64,
+    },
+    SetEncoderDisplay {
+        encoder: usize,
+        #[serde(default)]
+        title: String,
+        #[serde(default)]
+        value: String,
+        #[serde(default)]
+        status: Option<String>,
+        #[serde(default)]
+        progress: Option<f32>,
+    },
+    Stats,
+}
+
+pub fn default_socket_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join(SOCKET_NAME))
+}
+
+/// Owns the listening socket file and removes it when dropped.
+#[derive(Debug)]
+pub struct IpcServer {
+    path: PathBuf,
+}
+
+impl IpcServer {
+    pub fn start(path: impl Into<PathBuf>) -> Result<(Self, Receiver<IpcCommand>)> {
+        let path = path.into();
+        let listener = bind(&path)?;
+        let (command_tx, command_rx) = unbounded();
+        thread::Builder::new()
+            .name("streamdeck-ipc".into())
+            .spawn(move || accept_clients(listener, command_tx))
+            .context("failed to spawn IPC listener thread")?;
+        info!(path = %path.display(), "listening for IPC commands");
+        Ok((Self { path }, command_rx))
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                warn!(error = %err, path = %self.path.display(), "failed to remove IPC socket");
+            }
+            _ => {}
+        }
+    }
+}
+
+// A socket file left by a crashed instance is replaced; one that still
+// accepts connections belongs to a running daemon and is left alone.
+fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!(
+                "another instance is already listening on {}",
+                path.display()
+            );
+        }
+        fs::remove_file(path)
+            .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+    UnixListener::bind(path).with_context(|| format!("failed to bind {}", path.display()))
+}
+
+fn accept_clients(listener: UnixListener, command_tx: Sender<IpcCommand>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(error = %err, "failed to accept IPC connection");
+                continue;
+            }
+        };
+        let command_tx = command_tx.clone();
+        let spawned = thread::Builder::new()
+            .name("streamdeck-ipc-client".into())
+            .spawn(move || serve_client(stream, command_tx));
+        if let Err(err) = spawned {
+            warn!(error = %err, "failed to spawn IPC client thread");
+        }
+    }
+}
+
+fn serve_client(stream: UnixStream, command_tx: Sender<IpcCommand>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            warn!(error = %err, "failed to clone IPC stream");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match handle_line(&line, &command_tx) {
+            Ok(reply) => reply,
+            Err(err) => {
+                debug!(error = %err, %line, "rejected IPC command");
+                json!({ "ok": false, "error": format!("{err:#}") })
+            }
+        };
+        if writeln!(writer, "{reply}").is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_line(line: &str, command_tx: &Sender<IpcCommand>) -> Result<Value> {
+    let request: Request = serde_json::from_str(line).context("invalid command")?;
+    let send = |command| {
+        command_tx
+            .send(command)
+            .map_err(|_| anyhow!("daemon is shutting down"))
+    };
+
+    match request {
+        Request::Stats => {
+            let (reply_tx, reply_rx) = crossbeam_channel::bounded(1);
+            send(IpcCommand::QueryStats(reply_tx))?;
+            let stats = reply_rx
+                .recv_timeout(STATS_TIMEOUT)
+                .context("hardware backend did not report stats")?;
+            return Ok(json!({ "ok": true, "stats": stats_json(&stats) }));
+        }
+        Request::TurnEncoder { encoder, delta } => {
+            let encoder = encoder_id(encoder)?;
+            send(IpcCommand::Event(HardwareEvent::EncoderTurned {
+                encoder,
+                delta,
+            }))
+        }
+        Request::PressEncoder { encoder, held_ms } => {
+            let encoder = encoder_id(encoder)?;
+            send(IpcCommand::Event(HardwareEvent::EncoderPressed { encoder }))?;
+            hold(held_ms);
+            send(IpcCommand::Event(HardwareEvent::EncoderReleased {
+                encoder,
+            }))
+        }
+        Request::PressButton { button, held_ms } => {
+            send(IpcCommand::Event(HardwareEvent::ButtonPressed(button)))?;
+            hold(held_ms);
+            send(IpcCommand::Event(HardwareEvent::ButtonReleased(button)))
+        }
+        Request::SetEncoderDisplay {
+            encoder,
+            title,
+            value,
+            status,
+            progress,
+        } => {
+            let encoder = encoder_id(encoder)?;
+            let mut display = EncoderDisplay::new(title, value);
+            display.status = status;
+            display.progress = progress.map(|progress| progress.clamp(0.0, 1.0));
+            send(IpcCommand::SetEncoderDisplay { encoder, display })
+        }
+    }?;
+    Ok(json!({ "ok": true }))
+}
+
+fn stats_json(stats: &BackendStats) -> Value {
+    let timing = |timing: &TimingStats| {
+        json!({
+            "count": timing.count,
+            "mean_ms": timing.mean.as_secs_f64() * 1000.0,
+            "max_ms": timing.max.as_secs_f64() * 1000.0,
+        })
+    };
+    json!({
+        "strip_flush": timing(&stats.strip_flush),
+        "button_flush": timing(&stats.button_flush),
+        "command_drain": timing(&stats.command_drain),
+    })
+}
+
+fn encoder_id(index: usize) -> Result<EncoderId> {
+    EncoderId::from_index(index).ok_or_else(|| anyhow!("encoder must be 0-3, got {index}"))
+}
+
+// Holds block only this client's thread, so long presses stay possible
+// without stalling other clients or the app loop.
+fn hold(held_ms: u64) {
+    if held_ms > 0 {
+        thread::sleep(Duration::from_millis(held_ms).min(MAX_HOLD));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(stream: &mut UnixStream, reader: &mut impl BufRead, line: &str) -> String {
+        writeln!(stream, "{line}").unwrap();
+        let mut reply = String::new();
+        reader.read_line(&mut reply).unwrap();
+        reply
+    }
+
+    #[test]
+    fn clients_inject_events_and_survive_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_NAME);
+        let (server, commands) = IpcServer::start(&path).unwrap();
+
+        let mut first = UnixStream::connect(&path).unwrap();
+        let mut first_reader = BufReader::new(first.try_clone().unwrap());
+        let mut second = UnixStream::connect(&path).unwrap();
+        let mut second_reader = BufReader::new(second.try_clone().unwrap());
+
+        let reply = round_trip(&mut first, &mut first_reader, "{not json");
+        assert!(reply.contains("\"ok\":false"), "{reply}");
+        let reply = round_trip(
+            &mut second,
+            &mut second_reader,
+            r#"{"command": "turn_encoder", "encoder": 4, "delta": 1}"#,
+        );
+        assert!(reply.contains("encoder must be 0-3"), "{reply}");
+
+        let reply = round_trip(
+            &mut first,
+            &mut first_reader,
+            r#"{"command": "turn_encoder", "encoder": 1, "delta": -2}"#,
+        );
+        assert_eq!(reply.trim(), r#"{"ok":true}"#);
+        let reply = round_trip(
+            &mut second,
+            &mut second_reader,
+            r#"{"command": "set_encoder_display", "encoder": 3, "title": "ci", "value": "OK"}"#,
+        );
+        assert_eq!(reply.trim(), r#"{"ok":true}"#);
+
+        assert!(matches!(
+            commands.recv_timeout(Duration::from_secs(1)).unwrap(),
+            IpcCommand::Event(HardwareEvent::EncoderTurned {
+                encoder: EncoderId::Two,
+                delta: -2
+            })
+        ));
+        match commands.recv_timeout(Duration::from_secs(1)).unwrap() {
+            IpcCommand::SetEncoderDisplay { encoder, display } => {
+                assert_eq!(encoder, EncoderId::Four);
+                assert_eq!(display.value, "OK");
+            }
+            other => panic!("unexpected command {other:?}"),
+        }
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn button_press_sends_press_then_release() {
+        let (command_tx, command_rx) = unbounded();
+        handle_line(r#"{"command": "press_button", "index": 5}"#, &command_tx).unwrap();
+        let events: Vec<_> = command_rx.try_iter().collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                IpcCommand::Event(HardwareEvent::ButtonPressed(5)),
+                IpcCommand::Event(HardwareEvent::ButtonReleased(5)),
+            ]
+        ));
+    }
+
+    #[test]
+    fn stats_request_replies_with_backend_timings() {
+        let (command_tx, command_rx) = unbounded();
+        let daemon = thread::spawn(move || match command_rx.recv().unwrap() {
+            IpcCommand::QueryStats(reply) => {
+                let stats = BackendStats {
+                    strip_flush: TimingStats {
+                        count: 3,
+                        mean: Duration::from_micros(12_500),
+                        max: Duration::from_millis(20),
+                    },
+                    ..Default::default()
+                };
+                reply.send(stats).unwrap();
+            }
+            other => panic!("unexpected command {other:?}"),
+        });
+
+        let reply = handle_line(r#"{"command": "stats"}"#, &command_tx).unwrap();
+        daemon.join().unwrap();
+        assert_eq!(reply["stats"]["strip_flush"]["count"], 3);
+        assert_eq!(reply["stats"]["strip_flush"]["mean_ms"], 12.5);
+        assert_eq!(reply["stats"]["command_drain"]["count"], 0);
+    }
+}
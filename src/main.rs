@@ -1,7 +1,16 @@
 mod app;
 mod config;
+#[cfg(feature = "watch")]
+mod config_watch;
 mod controls;
+mod discover;
 mod hardware;
+mod init_config;
+mod ipc;
+mod metrics;
+#[cfg(feature = "hardware")]
+mod panic_hook;
+mod state_file;
 mod system;
 mod util;
 
@@ -10,32 +19,76 @@ use anyhow::Result;
 #[cfg(feature = "hardware")]
 use crossbeam_channel;
 #[cfg(feature = "hardware")]
-use signal_hook::consts::TERM_SIGNALS;
+use hardware::DisplayPipeline;
+#[cfg(feature = "hardware")]
+use signal_hook::consts::{SIGHUP, TERM_SIGNALS};
 #[cfg(feature = "hardware")]
 use signal_hook::iterator::Signals;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+#[cfg(feature = "hardware")]
+use std::sync::Arc;
 #[cfg(feature = "hardware")]
 use std::thread;
 #[cfg(feature = "hardware")]
-use tracing::warn;
+use tracing::{info, warn};
 
 #[cfg(feature = "hardware")]
 fn main() -> Result<()> {
+    if let Some(json) = discover_arg() {
+        discover(json);
+    }
+    if let Some(path) = check_config_arg() {
+        check_config(path);
+    }
+    if let Some(force) = init_config_arg() {
+        init_config(force);
+    }
+    if let Some(table) = list_arg() {
+        list(table);
+    }
     init_tracing();
 
-    let config = app::AppConfig::default();
-    let mut app = app::App::new(config)?;
+    let mut hardware_config = hardware::HardwareConfig {
+        allow_non_plus: env::var_os("STREAMDECK_CTRL_ALLOW_NON_PLUS")
+            .is_some_and(|value| value == "1"),
+        simulator: simulator_config(),
+        ..Default::default()
+    };
+    if let Some(limit) = env::var("STREAMDECK_CTRL_MAX_FLUSH_FAILURES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+    {
+        hardware_config.max_flush_failures = limit;
+    }
+    let config = app::AppConfig {
+        hardware: hardware_config,
+        ..Default::default()
+    };
+    let mut app = app::App::new(config, Arc::default())?;
     let hardware = app.hardware_handle();
+    panic_hook::install(hardware.devices());
 
     let (shutdown_tx, shutdown_rx) = crossbeam_channel::bounded(1);
-    let signals = Signals::new(TERM_SIGNALS)?;
+    let reload_tx = app.reload_sender();
+    let signals = Signals::new(TERM_SIGNALS.iter().chain([&SIGHUP]))?;
     let signal_handle = signals.handle();
     let signal_thread = thread::spawn({
         let mut signals = signals;
         let hardware = hardware.clone();
         move || {
             for signal in signals.forever() {
+                if signal == SIGHUP {
+                    info!("SIGHUP received; reloading configuration");
+                    // A reload already queued covers this one too.
+                    let _ = reload_tx.try_send(());
+                    continue;
+                }
                 warn!(signal = signal, "termination signal received");
-                let _ = hardware.clear_all_displays();
+                for device in hardware.devices() {
+                    let _ = device.clear_all_displays();
+                }
                 let _ = shutdown_tx.send(());
                 break;
             }
@@ -49,14 +102,164 @@ fn main() -> Result<()> {
     result
 }
 
+// `--simulate[=DIR]` or STREAMDECK_CTRL_SIMULATE=DIR renders to PNG files
+// instead of a device; STREAMDECK_CTRL_SIM_FIFO reads input from a FIFO
+// rather than stdin.
+#[cfg(feature = "hardware")]
+fn simulator_config() -> Option<hardware::SimulatorConfig> {
+    let from_args = env::args().skip(1).find_map(|arg| {
+        if arg == "--simulate" {
+            Some(None)
+        } else {
+            arg.strip_prefix("--simulate=")
+                .map(|dir| Some(PathBuf::from(dir)))
+        }
+    });
+    let output_dir = match from_args {
+        Some(dir) => dir,
+        None => Some(PathBuf::from(env::var_os("STREAMDECK_CTRL_SIMULATE")?)),
+    };
+    Some(hardware::SimulatorConfig {
+        output_dir: output_dir.unwrap_or_else(|| PathBuf::from("/tmp/streamdeck_sim")),
+        fifo: env::var_os("STREAMDECK_CTRL_SIM_FIFO").map(PathBuf::from),
+    })
+}
+
 #[cfg(not(feature = "hardware"))]
 fn main() {
+    if let Some(json) = discover_arg() {
+        discover(json);
+    }
+    if let Some(path) = check_config_arg() {
+        check_config(path);
+    }
+    if let Some(force) = init_config_arg() {
+        init_config(force);
+    }
+    if let Some(table) = list_arg() {
+        list(table);
+    }
     init_tracing();
     eprintln!(
         "streamdeck_ctrl was built without the `hardware` feature. Enable it to control a Stream Deck Plus."
     );
 }
 
+// `--check-config [PATH]` validates the configuration, defaulting to the
+// file the daemon would load, and exits without opening a device.
+fn check_config_arg() -> Option<Option<PathBuf>> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--check-config" {
+            return Some(
+                args.next()
+                    .filter(|next| !next.starts_with("--"))
+                    .map(PathBuf::from),
+            );
+        }
+        if let Some(path) = arg.strip_prefix("--check-config=") {
+            return Some(Some(PathBuf::from(path)));
+        }
+    }
+    None
+}
+
+fn check_config(path: Option<PathBuf>) -> ! {
+    let path = path.or_else(|| {
+        config::default_config_paths()
+            .into_iter()
+            .find(|candidate| candidate.exists())
+    });
+    let Some(path) = path else {
+        eprintln!("no configuration file found; pass a path to --check-config");
+        process::exit(1);
+    };
+    let audio = system::audio_switch::PulseAudioSwitch::new();
+    let report = controls::check_config(&path, &audio);
+    println!("{report}");
+    process::exit(if report.has_errors() { 1 } else { 0 });
+}
+
+// `--init-config [--force]` writes a starter configuration built from the
+// sinks and desktop entries on this machine.
+fn init_config_arg() -> Option<bool> {
+    let mut args = env::args().skip(1);
+    if !args.any(|arg| arg == "--init-config") {
+        return None;
+    }
+    Some(env::args().skip(1).any(|arg| arg == "--force"))
+}
+
+fn init_config(force: bool) -> ! {
+    let Some(path) = config::user_config_path() else {
+        eprintln!("neither XDG_CONFIG_HOME nor HOME is set; cannot choose where to write");
+        process::exit(1);
+    };
+    match init_config::init_config(&path, force) {
+        Ok(()) => {
+            println!("wrote {}", path.display());
+            process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            if !force && path.exists() {
+                eprintln!("pass --force to replace it");
+            }
+            process::exit(1);
+        }
+    }
+}
+
+// `--list-sinks` prints the sinks an audio output can name, `--list-devices`
+// the attached decks.
+fn list_arg() -> Option<fn() -> anyhow::Result<String>> {
+    env::args().skip(1).find_map(|arg| match arg.as_str() {
+        "--list-sinks" => Some(discover::sink_table as fn() -> anyhow::Result<String>),
+        "--list-devices" => Some(discover::deck_table as fn() -> anyhow::Result<String>),
+        _ => None,
+    })
+}
+
+fn list(table: fn() -> anyhow::Result<String>) -> ! {
+    match table() {
+        Ok(table) => {
+            print!("{table}");
+            process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            process::exit(1);
+        }
+    }
+}
+
+// `discover [--json]` lists what the configuration can name and exits.
+fn discover_arg() -> Option<bool> {
+    let mut args = env::args().skip(1);
+    if args.next()? != "discover" {
+        return None;
+    }
+    Some(args.any(|arg| arg == "--json"))
+}
+
+fn discover(json: bool) -> ! {
+    let discovery = discover::discover();
+    if !json {
+        print!("{discovery}");
+        process::exit(0);
+    }
+    match serde_json::to_string_pretty(&discovery) {
+        Ok(json) => {
+            println!("{json}");
+            process::exit(0);
+        }
+        Err(err) => {
+            eprintln!("failed to encode discovery results: {err}");
+            process::exit(1);
+        }
+    }
+}
+
 fn init_tracing() {
     use tracing_subscriber::EnvFilter;
 
@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicBool, AtomicU64};
+
+#[cfg(feature = "metrics")]
+pub use server::MetricsServer;
+
+/// Health counters shared by the hardware backend, the controllers and the
+/// metrics endpoint. Plain atomics, so updating one never takes a lock.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Decks with an open session.
+    pub devices_connected: AtomicU64,
+    /// Display commands queued for the backend, sampled every tick.
+    pub command_queue_depth: AtomicU64,
+    pub flush_failures: AtomicU64,
+    pub sink_switch_failures: AtomicU64,
+    pub pactl_available: AtomicBool,
+    pub ddcutil_available: AtomicBool,
+    pub playerctl_available: AtomicBool,
+}
+
+impl Metrics {
+    /// Prometheus text exposition format.
+    #[cfg(feature = "metrics")]
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+        use std::sync::atomic::Ordering;
+
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+        let flag = |value: &AtomicBool| u8::from(value.load(Ordering::Relaxed));
+        let mut out = String::new();
+        for (name, kind, help, value) in [
+            (
+                "devices_connected",
+                "gauge",
+                "Stream Decks with an open connection.",
+                load(&self.devices_connected),
+            ),
+            (
+                "command_queue_depth",
+                "gauge",
+                "Display commands waiting for the hardware backend.",
+                load(&self.command_queue_depth),
+            ),
+            (
+                "flush_failures_total",
+                "counter",
+                "Failed writes of the LCD strip or keys.",
+                load(&self.flush_failures),
+            ),
+            (
+                "sink_switch_failures_total",
+                "counter",
+                "Failed audio output switches.",
+                load(&self.sink_switch_failures),
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP streamdeck_ctrl_{name} {help}");
+            let _ = writeln!(out, "# TYPE streamdeck_ctrl_{name} {kind}");
+            let _ = writeln!(out, "streamdeck_ctrl_{name} {value}");
+        }
+        let _ = writeln!(
+            out,
+            "# HELP streamdeck_ctrl_backend_available Whether a system tool answered when last used."
+        );
+        let _ = writeln!(out, "# TYPE streamdeck_ctrl_backend_available gauge");
+        for (backend, available) in [
+            ("pactl", &self.pactl_available),
+            ("ddcutil", &self.ddcutil_available),
+            ("playerctl", &self.playerctl_available),
+        ] {
+            let _ = writeln!(
+                out,
+                "streamdeck_ctrl_backend_available{{backend=\"{backend}\"}} {}",
+                flag(available)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod server {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    use anyhow::{Context, Result};
+    use tracing::{debug, info};
+
+    use super::Metrics;
+
+    const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Serves `/metrics` and `/healthz` over plain HTTP, one request per
+    /// connection.
+    #[derive(Debug)]
+    pub struct MetricsServer {
+        address: String,
+    }
+
+    impl MetricsServer {
+        pub fn start(address: &str, metrics: Arc<Metrics>) -> Result<Self> {
+            let listener = TcpListener::bind(address)
+                .with_context(|| format!("failed to bind metrics endpoint to {address}"))?;
+            let address = listener
+                .local_addr()
+                .map_or_else(|_| address.to_string(), |bound| bound.to_string());
+            info!(%address, "serving metrics");
+            thread::Builder::new()
+                .name("metrics-http".into())
+                .spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if let Err(err) = respond(stream, &metrics) {
+                            debug!(error = %err, "metrics request failed");
+                        }
+                    }
+                })
+                .context("failed to spawn metrics endpoint")?;
+            Ok(Self { address })
+        }
+
+        pub fn address(&self) -> &str {
+            &self.address
+        }
+    }
+
+    fn respond(stream: TcpStream, metrics: &Metrics) -> Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Headers are read and ignored so the client sees a clean close.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, path) = (parts.next(), parts.next());
+        let (status, content_type, body) = match (method, path) {
+            (Some("GET"), Some("/metrics")) => {
+                ("200 OK", "text/plain; version=0.0.4", metrics.render())
+            }
+            (Some("GET"), Some("/healthz"))
+                if metrics.devices_connected.load(Ordering::Relaxed) > 0 =>
+            {
+                ("200 OK", "text/plain", "ok\n".to_string())
+            }
+            (Some("GET"), Some("/healthz")) => (
+                "503 Service Unavailable",
+                "text/plain",
+                "no Stream Deck connected\n".to_string(),
+            ),
+            (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+            _ => (
+                "405 Method Not Allowed",
+                "text/plain",
+                "method not allowed\n".to_string(),
+            ),
+        };
+        write!(
+            &stream,
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+
+    fn get(address: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_metrics_and_health() {
+        let metrics = Arc::new(Metrics::default());
+        let server = MetricsServer::start("127.0.0.1:0", Arc::clone(&metrics)).unwrap();
+        metrics.flush_failures.fetch_add(2, Ordering::Relaxed);
+        metrics.pactl_available.store(true, Ordering::Relaxed);
+
+        let response = get(server.address(), "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("\nstreamdeck_ctrl_flush_failures_total 2\n"));
+        assert!(response.contains("\nstreamdeck_ctrl_backend_available{backend=\"pactl\"} 1\n"));
+        assert!(response.contains("\nstreamdeck_ctrl_backend_available{backend=\"ddcutil\"} 0\n"));
+        assert!(response.contains("# TYPE streamdeck_ctrl_devices_connected gauge"));
+
+        assert!(get(server.address(), "/healthz").starts_with("HTTP/1.1 503"));
+        metrics.devices_connected.fetch_add(1, Ordering::Relaxed);
+        assert!(get(server.address(), "/healthz").starts_with("HTTP/1.1 200 OK"));
+        assert!(get(server.address(), "/").starts_with("HTTP/1.1 404"));
+
+        assert!(MetricsServer::start(server.address(), metrics).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use std::panic;
+use std::thread;
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::hardware::DisplayPipeline;
+
+// Enough for the backend to clear the strip and keys; a wedged device must not
+// keep a crashed process around.
+const BLANK_GRACE: Duration = Duration::from_secs(1);
+
+/// Blanks every deck when the calling thread panics, so a crash does not leave
+/// stale segments and keys behind. The panic is reported by the previous hook
+/// first and then unwinds as usual. Panics on other threads only end that
+/// thread, so they leave the deck alone.
+pub fn install<P>(devices: Vec<P>)
+where
+    P: DisplayPipeline + 'static,
+{
+    let owner = thread::current().id();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        if thread::current().id() == owner {
+            blank(&devices);
+        }
+    }));
+}
+
+fn blank<P: DisplayPipeline>(devices: &[P]) {
+    for device in devices {
+        if let Err(err) = device.blank_for_exit(BLANK_GRACE) {
+            warn!(error = %err, "failed to blank stream deck after panic");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{EncoderDisplay, EncoderId};
+    use anyhow::Result;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct RecordingPipeline {
+        blanked: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl DisplayPipeline for RecordingPipeline {
+        fn update_encoder(&self, _: EncoderId, _: EncoderDisplay) -> Result<()> {
+            Ok(())
+        }
+
+        fn blank_for_exit(&self, grace: Duration) -> Result<()> {
+            self.blanked.lock().unwrap().push(grace);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn only_a_panic_on_the_installing_thread_blanks_the_deck() {
+        let pipeline = RecordingPipeline::default();
+        let devices = vec![pipeline.clone(), pipeline.clone()];
+        let app = thread::spawn(move || {
+            install(devices);
+            let worker = thread::spawn(|| panic!("worker failed"));
+            assert!(worker.join().is_err());
+            panic!("app failed");
+        });
+        assert!(app.join().is_err());
+        assert_eq!(
+            *pipeline.blanked.lock().unwrap(),
+            [BLANK_GRACE, BLANK_GRACE]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::controls::TimerDisplayState;
+use crate::hardware::{EncoderDisplay, ShownDisplays};
+
+const STATE_DIR: &str = "streamdeck_ctrl";
+const STATE_NAME: &str = "state.json";
+const MIN_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn default_state_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join(STATE_DIR).join(STATE_NAME))
+}
+
+/// What the deck shows, for status bars and other external readers.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppState {
+    /// One entry per encoder, `null` while its segment is blank.
+    pub encoders: Vec<Option<EncoderState>>,
+    pub active_sink: Option<String>,
+    pub timer: TimerState,
+    /// Icon id per key of the default deck.
+    pub buttons: Vec<ButtonState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EncoderState {
+    pub title: String,
+    pub value: String,
+    pub status: Option<String>,
+    pub progress: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimerState {
+    pub state: TimerDisplayState,
+    pub remaining_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ButtonState {
+    pub index: u8,
+    pub icon: String,
+}
+
+impl AppState {
+    // Only text and icon ids are copied; encoder icons stay behind.
+    pub fn capture(shown: &ShownDisplays, active_sink: Option<&str>, timer: TimerState) -> Self {
+        Self {
+            encoders: shown
+                .encoders
+                .iter()
+                .map(|display| display.as_ref().map(EncoderState::from))
+                .collect(),
+            active_sink: active_sink.map(str::to_string),
+            timer,
+            buttons: shown
+                .buttons
+                .iter()
+                .map(|(index, icon)| ButtonState {
+                    index: *index,
+                    icon: icon.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&EncoderDisplay> for EncoderState {
+    fn from(display: &EncoderDisplay) -> Self {
+        Self {
+            title: display.title.clone(),
+            value: display.value.clone(),
+            status: display.status.clone(),
+            progress: display.progress,
+        }
+    }
+}
+
+/// Writes `AppState` to `path` when it changes, at most once per
+/// `MIN_WRITE_INTERVAL`; a change made sooner is written by a later call.
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    written: Option<AppState>,
+    written_at: Option<Instant>,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            written: None,
+            written_at: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether the file was written.
+    pub fn update(&mut self, state: AppState, now: Instant) -> Result<bool> {
+        if self.written.as_ref() == Some(&state) {
+            return Ok(false);
+        }
+        let throttled = self
+            .written_at
+            .is_some_and(|at| now.saturating_duration_since(at) < MIN_WRITE_INTERVAL);
+        if throttled {
+            return Ok(false);
+        }
+        // Recorded even if the write fails, so a broken path is retried at
+        // the same pace rather than on every event.
+        self.written_at = Some(now);
+        self.write(&state)?;
+        self.written = Some(state);
+        Ok(true)
+    }
+
+    fn write(&self, state: &AppState) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string(state).context("failed to encode deck state")?;
+        let staging = self.path.with_extension("json.tmp");
+        fs::write(&staging, contents)
+            .with_context(|| format!("failed to write {}", staging.display()))?;
+        fs::rename(&staging, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+}
+
+// A file left behind would show a stopped daemon's deck as current.
+impl Drop for StateFile {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                warn!(error = %err, path = %self.path.display(), "failed to remove state file");
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn read(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn state(shown: &ShownDisplays, remaining_secs: u64) -> AppState {
+        AppState::capture(
+            shown,
+            Some("Speakers"),
+            TimerState {
+                state: TimerDisplayState::Running,
+                remaining_secs,
+            },
+        )
+    }
+
+    #[test]
+    fn writes_changes_at_most_once_a_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = StateFile::new(dir.path().join("nested").join(STATE_NAME));
+        let mut shown = ShownDisplays::default();
+        shown.encoders[0] = Some(
+            EncoderDisplay::new("volume", " 40%")
+                .with_status("Speakers")
+                .with_progress(0.5),
+        );
+        shown.buttons.insert(3, "audio-0-active".to_string());
+        let start = Instant::now();
+
+        assert!(file.update(state(&shown, 300), start).unwrap());
+        assert_eq!(
+            read(file.path()),
+            json!({
+                "encoders": [
+                    {"title": "volume", "value": " 40%", "status": "Speakers", "progress": 0.5},
+                    null,
+                    null,
+                    null
+                ],
+                "active_sink": "Speakers",
+                "timer": {"state": "running", "remaining_secs": 300},
+                "buttons": [{"index": 3, "icon": "audio-0-active"}]
+            })
+        );
+        assert!(!dir.path().join("nested").join("state.json.tmp").exists());
+
+        let soon = start + Duration::from_millis(400);
+        assert!(!file.update(state(&shown, 299), soon).unwrap());
+        assert_eq!(read(file.path())["timer"]["remaining_secs"], 300);
+
+        let later = start + MIN_WRITE_INTERVAL;
+        assert!(file.update(state(&shown, 299), later).unwrap());
+        assert_eq!(read(file.path())["timer"]["remaining_secs"], 299);
+        let idle = later + MIN_WRITE_INTERVAL * 5;
+        assert!(!file.update(state(&shown, 299), idle).unwrap());
+
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+    }
+}
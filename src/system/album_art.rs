@@ -0,0 +1,118 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use image::RgbaImage;
+use tracing::debug;
+
+const CACHE_CAPACITY: usize = 8;
+// Covers are decoded once and shrunk to roughly LCD size so the cache stays small.
+const MAX_ART_SIZE: u32 = 96;
+
+/// Decodes cover art by URL, remembering both successes and failures so a
+/// broken URL is not retried on every refresh.
+#[derive(Debug, Default)]
+pub struct AlbumArtCache {
+    entries: HashMap<String, Option<Arc<RgbaImage>>>,
+    order: VecDeque<String>,
+}
+
+impl AlbumArtCache {
+    pub fn get(&mut self, url: &str) -> Option<Arc<RgbaImage>> {
+        if let Some(entry) = self.entries.get(url) {
+            return entry.clone();
+        }
+
+        let art = match load_art(url) {
+            Ok(art) => Some(Arc::new(art)),
+            Err(err) => {
+                debug!(%url, error = %err, "album art unavailable");
+                None
+            }
+        };
+
+        while self.order.len() >= CACHE_CAPACITY {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.order.push_back(url.to_string());
+        self.entries.insert(url.to_string(), art.clone());
+        art
+    }
+}
+
+fn load_art(url: &str) -> Result<RgbaImage> {
+    let path = file_url_path(url)?;
+    let image =
+        image::open(&path).with_context(|| format!("failed to decode {}", path.display()))?;
+    Ok(image.thumbnail(MAX_ART_SIZE, MAX_ART_SIZE).to_rgba8())
+}
+
+fn file_url_path(url: &str) -> Result<PathBuf> {
+    if let Some(rest) = url.strip_prefix("file://") {
+        // Only local files are supported; a host component other than
+        // localhost cannot be resolved here.
+        let path = rest.strip_prefix("localhost").unwrap_or(rest);
+        return Ok(PathBuf::from(percent_decode(path)));
+    }
+    if url.starts_with('/') {
+        return Ok(PathBuf::from(url));
+    }
+    bail!("unsupported album art URL scheme");
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+            if let Some(value) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(value);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_percent_escaped_file_urls() {
+        assert_eq!(
+            file_url_path("file:///home/me/My%20Music/cover.png").unwrap(),
+            PathBuf::from("/home/me/My Music/cover.png")
+        );
+        assert_eq!(percent_decode("100%"), "100%");
+        assert!(file_url_path("https://example.com/cover.jpg").is_err());
+    }
+
+    #[test]
+    fn cache_loads_local_art_and_remembers_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cover.png");
+        RgbaImage::from_pixel(300, 300, image::Rgba([200, 10, 10, 255]))
+            .save(&path)
+            .unwrap();
+
+        let mut cache = AlbumArtCache::default();
+        let url = format!("file://{}", path.display());
+        let art = cache.get(&url).expect("art decoded");
+        assert_eq!(art.dimensions(), (MAX_ART_SIZE, MAX_ART_SIZE));
+        assert!(Arc::ptr_eq(&art, &cache.get(&url).unwrap()));
+
+        let missing = format!("file://{}", dir.path().join("missing.png").display());
+        assert!(cache.get(&missing).is_none());
+        assert!(cache.entries.contains_key(&missing));
+    }
+}
@@ -7,9 +7,12 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use tracing::{info, warn};
 
+use super::audio_switch::{parse_default_sink, parse_sinks};
 use super::availability::RetryableAvailability;
+use super::worker::output_with_timeout;
 
 const DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+const DEFAULT_SOURCE: &str = "@DEFAULT_SOURCE@";
 const RETRY_BACKOFF_SECS: u64 = 5;
 static PACTL_AVAILABLE: Lazy<bool> = Lazy::new(|| {
     Command::new("pactl")
@@ -20,11 +23,62 @@ static PACTL_AVAILABLE: Lazy<bool> = Lazy::new(|| {
 });
 static WARNED_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
 
-pub trait AudioBackend: Send {
+pub trait AudioBackend: Send + 'static {
     fn get_volume(&self) -> Result<f32>;
     fn adjust_volume(&self, delta_percent: i32) -> Result<f32>;
+    fn set_volume(&self, percent: u8) -> Result<f32>;
     fn is_muted(&self) -> Result<bool>;
     fn toggle_mute(&self) -> Result<bool>;
+    fn current_sink_label(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+pub trait AudioSourceBackend: Send {
+    fn is_source_muted(&self) -> Result<bool>;
+    fn toggle_source_mute(&self) -> Result<bool>;
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// One application's playback stream, from `pactl list sink-inputs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkInput {
+    pub index: u32,
+    pub application_name: Option<String>,
+    pub binary: Option<String>,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl SinkInput {
+    /// Whether the stream belongs to `application`, going by its name or
+    /// executable, ignoring case.
+    pub fn belongs_to(&self, application: &str) -> bool {
+        [&self.application_name, &self.binary]
+            .into_iter()
+            .flatten()
+            .any(|name| name.eq_ignore_ascii_case(application))
+    }
+}
+
+/// Per-application streams. Indices are handed out afresh whenever a stream
+/// is created, so they are only good until the next listing.
+pub trait SinkInputBackend: Send + 'static {
+    fn list_sink_inputs(&self) -> Result<Vec<SinkInput>>;
+    fn get_sink_input_volume(&self, index: u32) -> Result<f32> {
+        self.list_sink_inputs()?
+            .into_iter()
+            .find(|input| input.index == index)
+            .map(|input| input.volume)
+            .ok_or_else(|| anyhow!("sink input #{index} no longer exists"))
+    }
+    fn set_sink_input_volume(&self, index: u32, percent: f32) -> Result<()>;
+    fn set_sink_input_mute(&self, index: u32, muted: bool) -> Result<()>;
     fn is_available(&self) -> bool {
         true
     }
@@ -81,10 +135,15 @@ impl PulseAudioBackend {
             bail!("pactl not available");
         }
 
-        let output = Command::new("pactl")
-            .args(args)
-            .output()
-            .with_context(|| format!("failed to execute pactl with args {args:?}"))?;
+        let output = match output_with_timeout(Command::new("pactl").args(args))
+            .with_context(|| format!("failed to execute pactl with args {args:?}"))
+        {
+            Ok(output) => output,
+            Err(err) => {
+                self.mark_unavailable(format!("{err:#}"));
+                return Err(err);
+            }
+        };
 
         if !output.status.success() {
             let message = format!(
@@ -182,13 +241,31 @@ impl AudioBackend for PulseAudioBackend {
         self.get_volume()
     }
 
+    fn set_volume(&self, percent: u8) -> Result<f32> {
+        if !self.is_available() {
+            warn_backend_disabled();
+            return Ok(0.0);
+        }
+
+        if let Err(err) = self.run_pactl(&[
+            String::from("set-sink-volume"),
+            self.sink_arg(),
+            format!("{percent}%"),
+        ]) {
+            warn!(error = %err, "pactl set-sink-volume failed; disabling PulseAudio backend");
+            self.mark_unavailable(err.to_string());
+            return Ok(0.0);
+        }
+
+        self.get_volume()
+    }
+
     fn is_muted(&self) -> Result<bool> {
         if !self.is_available() {
             warn_backend_disabled();
             return Ok(false);
         }
 
-        static MUTE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"Mute:\s+(yes|no)").unwrap());
         let output = match self.run_pactl(&[String::from("get-sink-mute"), self.sink_arg()]) {
             Ok(output) => output,
             Err(err) => {
@@ -197,8 +274,8 @@ impl AudioBackend for PulseAudioBackend {
                 return Ok(false);
             }
         };
-        let muted = match MUTE_RE.captures(&output).and_then(|capture| capture.get(1)) {
-            Some(mat) => mat.as_str().eq_ignore_ascii_case("yes"),
+        let muted = match parse_mute(&output) {
+            Some(muted) => muted,
             None => {
                 warn!("could not parse pactl mute output: {output}");
                 self.mark_unavailable("unexpected pactl mute output");
@@ -226,11 +303,168 @@ impl AudioBackend for PulseAudioBackend {
         self.is_muted()
     }
 
+    fn current_sink_label(&self) -> Result<Option<String>> {
+        if !self.is_available() {
+            return Ok(None);
+        }
+
+        let name = if self.sink == DEFAULT_SINK {
+            let info = self
+                .run_pactl(&[String::from("info")])
+                .context("pactl info failed")?;
+            match parse_default_sink(&info) {
+                Some(name) => name,
+                None => return Ok(None),
+            }
+        } else {
+            self.sink.clone()
+        };
+
+        let listing = self
+            .run_pactl(&[String::from("list"), String::from("sinks")])
+            .context("pactl list sinks failed")?;
+        let label = parse_sinks(&listing)
+            .into_iter()
+            .find(|sink| sink.name == name)
+            .and_then(|sink| sink.description)
+            .unwrap_or(name);
+        Ok(Some(label))
+    }
+
     fn is_available(&self) -> bool {
         PulseAudioBackend::is_available(self)
     }
 }
 
+impl AudioSourceBackend for PulseAudioBackend {
+    fn is_source_muted(&self) -> Result<bool> {
+        if !self.is_available() {
+            return Ok(false);
+        }
+
+        let output = self
+            .run_pactl(&[String::from("get-source-mute"), DEFAULT_SOURCE.to_string()])
+            .context("pactl get-source-mute failed")?;
+        parse_mute(&output)
+            .ok_or_else(|| anyhow!("could not parse pactl source mute output: {output}"))
+    }
+
+    fn toggle_source_mute(&self) -> Result<bool> {
+        if !self.is_available() {
+            bail!("pactl not available");
+        }
+
+        self.run_pactl(&[
+            String::from("set-source-mute"),
+            DEFAULT_SOURCE.to_string(),
+            String::from("toggle"),
+        ])
+        .context("pactl set-source-mute failed")?;
+        self.is_source_muted()
+    }
+
+    fn is_available(&self) -> bool {
+        PulseAudioBackend::is_available(self)
+    }
+}
+
+impl SinkInputBackend for PulseAudioBackend {
+    fn list_sink_inputs(&self) -> Result<Vec<SinkInput>> {
+        let listing = self
+            .run_pactl(&[String::from("list"), String::from("sink-inputs")])
+            .context("pactl list sink-inputs failed")?;
+        Ok(parse_sink_inputs(&listing))
+    }
+
+    fn set_sink_input_volume(&self, index: u32, percent: f32) -> Result<()> {
+        self.run_pactl(&[
+            String::from("set-sink-input-volume"),
+            index.to_string(),
+            format!("{}%", percent.round().max(0.0)),
+        ])
+        .context("pactl set-sink-input-volume failed")?;
+        Ok(())
+    }
+
+    fn set_sink_input_mute(&self, index: u32, muted: bool) -> Result<()> {
+        self.run_pactl(&[
+            String::from("set-sink-input-mute"),
+            index.to_string(),
+            String::from(if muted { "1" } else { "0" }),
+        ])
+        .context("pactl set-sink-input-mute failed")?;
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        PulseAudioBackend::is_available(self)
+    }
+}
+
+// Streams without a parsable volume line are skipped rather than shown at 0%.
+fn parse_sink_inputs(output: &str) -> Vec<SinkInput> {
+    static PERCENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)%").unwrap());
+    let mut inputs = Vec::new();
+    let mut current: Option<(u32, Option<f32>, SinkInput)> = None;
+    let mut finish = |current: Option<(u32, Option<f32>, SinkInput)>| {
+        if let Some((index, Some(volume), input)) = current {
+            inputs.push(SinkInput {
+                index,
+                volume,
+                ..input
+            });
+        }
+    };
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(value) = trimmed.strip_prefix("Sink Input #") {
+            finish(current.take());
+            current = value.trim().parse().ok().map(|index| {
+                let input = SinkInput {
+                    index,
+                    application_name: None,
+                    binary: None,
+                    volume: 0.0,
+                    muted: false,
+                };
+                (index, None, input)
+            });
+            continue;
+        }
+        let Some((_, volume, input)) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(value) = trimmed.strip_prefix("Mute:") {
+            input.muted = value.trim().eq_ignore_ascii_case("yes");
+        } else if let Some(value) = trimmed.strip_prefix("Volume:") {
+            *volume = PERCENT_RE
+                .captures(value)
+                .and_then(|capture| capture[1].parse().ok());
+        } else if let Some(value) = trimmed.strip_prefix("application.name =") {
+            input.application_name = property_value(value);
+        } else if let Some(value) = trimmed.strip_prefix("application.process.binary =") {
+            input.binary = property_value(value);
+        }
+    }
+    finish(current);
+    inputs
+}
+
+fn property_value(raw: &str) -> Option<String> {
+    let value = raw.trim().trim_matches('"');
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn parse_mute(output: &str) -> Option<bool> {
+    static MUTE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"Mute:\s+(yes|no)").unwrap());
+    MUTE_RE
+        .captures(output)
+        .and_then(|capture| capture.get(1))
+        .map(|mat| mat.as_str().eq_ignore_ascii_case("yes"))
+}
+
 fn warn_backend_disabled() {
     warn_backend_disabled_with_reason("PulseAudio CLI (`pactl`) not found or returned an error");
 }
@@ -267,6 +501,13 @@ pub mod tests {
             Ok(new_volume)
         }
 
+        fn set_volume(&self, percent: u8) -> Result<f32> {
+            let mut state = self.inner.lock().unwrap();
+            state.history.push(format!("set:{percent}"));
+            state.volume = f32::from(percent);
+            Ok(state.volume)
+        }
+
         fn is_muted(&self) -> Result<bool> {
             Ok(self.inner.lock().unwrap().muted)
         }
@@ -274,16 +515,173 @@ pub mod tests {
         fn toggle_mute(&self) -> Result<bool> {
             let mut state = self.inner.lock().unwrap();
             state.history.push("toggle_mute".into());
+            if state.fail_mute {
+                return Err(anyhow!("mock mute toggle failed"));
+            }
             state.muted = !state.muted;
             Ok(state.muted)
         }
+
+        fn current_sink_label(&self) -> Result<Option<String>> {
+            Ok(self.inner.lock().unwrap().sink_label.clone())
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct MockAudioSourceBackend {
+        pub muted: Arc<Mutex<bool>>,
+        pub available: bool,
+    }
+
+    impl AudioSourceBackend for MockAudioSourceBackend {
+        fn is_source_muted(&self) -> Result<bool> {
+            Ok(*self.muted.lock().unwrap())
+        }
+
+        fn toggle_source_mute(&self) -> Result<bool> {
+            let mut muted = self.muted.lock().unwrap();
+            *muted = !*muted;
+            Ok(*muted)
+        }
+
+        fn is_available(&self) -> bool {
+            self.available
+        }
+    }
+
+    /// Streams that come and go as the test edits `inputs`.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockSinkInputBackend {
+        pub inputs: Arc<Mutex<Vec<SinkInput>>>,
+        pub unavailable: bool,
+    }
+
+    impl MockSinkInputBackend {
+        pub fn stream(index: u32, application: &str, volume: f32) -> SinkInput {
+            SinkInput {
+                index,
+                application_name: Some(application.to_string()),
+                binary: None,
+                volume,
+                muted: false,
+            }
+        }
+    }
+
+    impl SinkInputBackend for MockSinkInputBackend {
+        fn list_sink_inputs(&self) -> Result<Vec<SinkInput>> {
+            Ok(self.inputs.lock().unwrap().clone())
+        }
+
+        fn set_sink_input_volume(&self, index: u32, percent: f32) -> Result<()> {
+            let mut inputs = self.inputs.lock().unwrap();
+            let input = inputs
+                .iter_mut()
+                .find(|input| input.index == index)
+                .ok_or_else(|| anyhow!("no sink input #{index}"))?;
+            input.volume = percent;
+            Ok(())
+        }
+
+        fn set_sink_input_mute(&self, index: u32, muted: bool) -> Result<()> {
+            let mut inputs = self.inputs.lock().unwrap();
+            let input = inputs
+                .iter_mut()
+                .find(|input| input.index == index)
+                .ok_or_else(|| anyhow!("no sink input #{index}"))?;
+            input.muted = muted;
+            Ok(())
+        }
+
+        fn is_available(&self) -> bool {
+            !self.unavailable
+        }
+    }
+
+    // Trimmed from PipeWire's pactl; PulseAudio prints the same fields.
+    const SINK_INPUTS: &str = r#"Sink Input #87
+	Driver: PipeWire
+	Owner Module: n/a
+	Client: 85
+	Sink: 52
+	Sample Specification: float32le 2ch 44100Hz
+	Channel Map: front-left,front-right
+	Format: pcm, format.sample_format = "\"float32le\""  format.rate = "44100"  format.channels = "2"  format.channel_map = "\"front-left,front-right\""
+	Corked: no
+	Mute: no
+	Volume: front-left: 42598 /  65% / -11.23 dB,   front-right: 42598 /  65% / -11.23 dB
+	        balance 0.00
+	Buffer Latency: 0 usec
+	Sink Latency: 0 usec
+	Resample method: PipeWire
+	Properties:
+		media.name = "Spotify"
+		application.name = "spotify"
+		application.process.id = "40211"
+		application.process.binary = "spotify"
+		media.class = "Stream/Output/Audio"
+
+Sink Input #112
+	Driver: PipeWire
+	Owner Module: n/a
+	Client: 109
+	Sink: 52
+	Mute: yes
+	Volume: mono: 65536 / 100% / 0.00 dB
+	        balance 0.00
+	Properties:
+		media.name = "Playback"
+		application.name = "Firefox"
+		application.process.binary = "firefox"
+
+Sink Input #113
+	Driver: PipeWire
+	Mute: no
+	Properties:
+		application.name = "Firefox"
+"#;
+
+    #[test]
+    fn parses_pactl_sink_inputs() {
+        let inputs = parse_sink_inputs(SINK_INPUTS);
+        assert_eq!(
+            inputs,
+            [
+                SinkInput {
+                    index: 87,
+                    application_name: Some("spotify".into()),
+                    binary: Some("spotify".into()),
+                    volume: 65.0,
+                    muted: false,
+                },
+                SinkInput {
+                    index: 112,
+                    application_name: Some("Firefox".into()),
+                    binary: Some("firefox".into()),
+                    volume: 100.0,
+                    muted: true,
+                },
+            ]
+        );
+        assert!(inputs[0].belongs_to("Spotify"));
+        assert!(!inputs[1].belongs_to("spotify"));
+        assert!(parse_sink_inputs("").is_empty());
+    }
+
+    #[test]
+    fn parses_pactl_mute_output() {
+        assert_eq!(parse_mute("Mute: yes\n"), Some(true));
+        assert_eq!(parse_mute("Mute: no"), Some(false));
+        assert_eq!(parse_mute("garbage"), None);
     }
 
     #[derive(Debug)]
     pub struct MockAudioState {
         pub volume: f32,
         pub muted: bool,
+        pub sink_label: Option<String>,
         pub history: Vec<String>,
+        pub fail_mute: bool,
     }
 
     impl Default for MockAudioState {
@@ -291,7 +689,9 @@ pub mod tests {
             Self {
                 volume: 50.0,
                 muted: false,
+                sink_label: None,
                 history: Vec::new(),
+                fail_mute: false,
             }
         }
     }
@@ -2,13 +2,22 @@ use std::process::Command;
 
 use anyhow::{Context, Result, anyhow, bail};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use super::worker::output_with_timeout;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct SinkInfo {
     pub id: Option<u32>,
     pub name: String,
     pub description: Option<String>,
+    /// `device.icon_name`, e.g. "audio-headset-usb".
+    pub icon_name: Option<String>,
+    /// `device.form_factor`, e.g. "headset" or "speaker".
+    pub form_factor: Option<String>,
 }
 
+/// Sources are listed and selected exactly like sinks.
+pub type SourceInfo = SinkInfo;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SinkSelector {
     Id(u32),
@@ -68,10 +77,15 @@ impl SinkSelector {
     }
 }
 
-pub trait AudioSwitchBackend: Send + Sync {
+pub trait AudioSwitchBackend: Send + Sync + 'static {
     fn set_default_sink(&self, selector: &SinkSelector) -> Result<SinkInfo>;
     fn current_default_sink(&self) -> Result<Option<SinkInfo>>;
     fn list_sinks(&self) -> Result<Vec<SinkInfo>>;
+    fn sink_volume(&self, sink_name: &str) -> Result<Option<u8>>;
+    fn set_sink_volume(&self, sink_name: &str, percent: u8) -> Result<()>;
+    fn set_default_source(&self, selector: &SinkSelector) -> Result<SourceInfo>;
+    fn current_default_source(&self) -> Result<Option<SourceInfo>>;
+    fn list_sources(&self) -> Result<Vec<SourceInfo>>;
 }
 
 #[derive(Debug, Default, Clone)]
@@ -83,9 +97,7 @@ impl PulseAudioSwitch {
     }
 
     fn run_pactl(args: &[&str]) -> Result<String> {
-        let output = Command::new("pactl")
-            .args(args)
-            .output()
+        let output = output_with_timeout(Command::new("pactl").args(args))
             .with_context(|| format!("failed to execute pactl with args {args:?}"))?;
 
         if !output.status.success() {
@@ -107,6 +119,15 @@ impl PulseAudioSwitch {
         Ok(sinks)
     }
 
+    fn list_sources_internal(&self) -> Result<Vec<SourceInfo>> {
+        let output = Self::run_pactl(&["list", "sources"])?;
+        let sources = parse_sources(&output);
+        if sources.is_empty() {
+            bail!("no sources reported by pactl");
+        }
+        Ok(sources)
+    }
+
     fn move_inputs(target_sink: &str) -> Result<()> {
         let output = Self::run_pactl(&["list", "short", "sink-inputs"])?;
         for input in parse_sink_inputs(&output) {
@@ -121,6 +142,23 @@ impl PulseAudioSwitch {
         }
         Ok(())
     }
+
+    fn move_outputs(target_source: &str) -> Result<()> {
+        let output = Self::run_pactl(&["list", "short", "source-outputs"])?;
+        for source_output in parse_sink_inputs(&output) {
+            if let Err(err) =
+                Self::run_pactl(&["move-source-output", &source_output, target_source])
+            {
+                tracing::warn!(
+                    error = %err,
+                    source_output = %source_output,
+                    target = target_source,
+                    "failed to move source output"
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 impl AudioSwitchBackend for PulseAudioSwitch {
@@ -143,34 +181,84 @@ impl AudioSwitchBackend for PulseAudioSwitch {
         let Some(default) = parse_default_sink(&output) else {
             return Ok(None);
         };
+        Ok(Some(find_default(default, &self.list_sinks_internal()?)))
+    }
 
-        let sinks = self.list_sinks_internal()?;
-        if let Some(found) = sinks.iter().find(|sink| sink.name == default) {
-            return Ok(Some(found.clone()));
-        }
+    fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
+        self.list_sinks_internal()
+    }
+
+    fn sink_volume(&self, sink_name: &str) -> Result<Option<u8>> {
+        let output = Self::run_pactl(&["get-sink-volume", sink_name])?;
+        Ok(parse_volume_percent(&output))
+    }
 
-        let default_lower = default.to_ascii_lowercase();
-        if let Some(found) = sinks.iter().find(|sink| {
-            sink.description
-                .as_ref()
-                .map(|desc| desc.to_ascii_lowercase() == default_lower)
-                .unwrap_or(false)
-        }) {
-            return Ok(Some(found.clone()));
+    fn set_sink_volume(&self, sink_name: &str, percent: u8) -> Result<()> {
+        Self::run_pactl(&["set-sink-volume", sink_name, &format!("{percent}%")])
+            .with_context(|| format!("failed to set volume of {sink_name} to {percent}%"))?;
+        Ok(())
+    }
+
+    fn set_default_source(&self, selector: &SinkSelector) -> Result<SourceInfo> {
+        let sources = self.list_sources_internal()?;
+        let source = select_sink(&sources, selector)?;
+
+        Self::run_pactl(&["set-default-source", &source.name])
+            .with_context(|| format!("failed to set default source to {}", source.name))?;
+
+        if let Err(err) = Self::move_outputs(&source.name) {
+            tracing::warn!(error = %err, "failed to move source outputs to {}", source.name);
         }
 
-        Ok(Some(SinkInfo {
-            id: None,
-            name: default,
-            description: None,
-        }))
+        Ok(source.clone())
     }
 
-    fn list_sinks(&self) -> Result<Vec<SinkInfo>> {
-        self.list_sinks_internal()
+    fn current_default_source(&self) -> Result<Option<SourceInfo>> {
+        let output = Self::run_pactl(&["info"])?;
+        let Some(default) = parse_default_source(&output) else {
+            return Ok(None);
+        };
+        Ok(Some(find_default(default, &self.list_sources_internal()?)))
+    }
+
+    fn list_sources(&self) -> Result<Vec<SourceInfo>> {
+        self.list_sources_internal()
     }
 }
 
+// `pactl info` names the default by its PulseAudio name, which older servers
+// sometimes report as the description instead.
+fn find_default(default: String, devices: &[SinkInfo]) -> SinkInfo {
+    if let Some(found) = devices.iter().find(|device| device.name == default) {
+        return found.clone();
+    }
+
+    let default_lower = default.to_ascii_lowercase();
+    if let Some(found) = devices.iter().find(|device| {
+        device
+            .description
+            .as_ref()
+            .map(|desc| desc.to_ascii_lowercase() == default_lower)
+            .unwrap_or(false)
+    }) {
+        return found.clone();
+    }
+
+    SinkInfo {
+        id: None,
+        name: default,
+        ..SinkInfo::default()
+    }
+}
+
+fn parse_volume_percent(output: &str) -> Option<u8> {
+    output.split_whitespace().find_map(|word| {
+        word.strip_suffix('%')
+            .and_then(|value| value.parse::<u16>().ok())
+            .map(|value| value.min(u8::MAX as u16) as u8)
+    })
+}
+
 pub(crate) fn select_sink<'a>(
     sinks: &'a [SinkInfo],
     selector: &SinkSelector,
@@ -204,24 +292,42 @@ pub(crate) fn select_sink<'a>(
 }
 
 pub(crate) fn parse_sinks(output: &str) -> Vec<SinkInfo> {
+    parse_devices(output, "Sink #")
+}
+
+// Monitor sources mirror a sink's output and are never a microphone.
+fn parse_sources(output: &str) -> Vec<SourceInfo> {
+    parse_devices(output, "Source #")
+        .into_iter()
+        .filter(|source| !source.name.ends_with(".monitor"))
+        .collect()
+}
+
+fn parse_devices(output: &str, header: &str) -> Vec<SinkInfo> {
     let mut sinks = Vec::new();
     let mut current_id: Option<u32> = None;
     let mut current_name: Option<String> = None;
     let mut description: Option<String> = None;
+    let mut icon_name: Option<String> = None;
+    let mut form_factor: Option<String> = None;
 
     for line in output.lines() {
         let trimmed = line.trim();
-        if let Some(value) = trimmed.strip_prefix("Sink #") {
+        if let Some(value) = trimmed.strip_prefix(header) {
             if let Some(name) = current_name.take() {
                 sinks.push(SinkInfo {
                     id: current_id,
                     name,
                     description: description.take(),
+                    icon_name: icon_name.take(),
+                    form_factor: form_factor.take(),
                 });
             }
             // reset for the next sink
             current_name = None;
             description = None;
+            icon_name = None;
+            form_factor = None;
             current_id = value
                 .split_whitespace()
                 .next()
@@ -244,6 +350,16 @@ pub(crate) fn parse_sinks(output: &str) -> Vec<SinkInfo> {
             if !value.is_empty() {
                 description = Some(value.to_string());
             }
+            continue;
+        }
+
+        if let Some(value) = property_value(trimmed, "device.icon_name") {
+            icon_name = Some(value);
+            continue;
+        }
+
+        if let Some(value) = property_value(trimmed, "device.form_factor") {
+            form_factor = Some(value);
         }
     }
 
@@ -252,17 +368,33 @@ pub(crate) fn parse_sinks(output: &str) -> Vec<SinkInfo> {
             id: current_id,
             name,
             description,
+            icon_name,
+            form_factor,
         });
     }
 
     sinks
 }
 
+fn property_value(line: &str, key: &str) -> Option<String> {
+    let value = line.strip_prefix(key)?.trim_start().strip_prefix('=')?;
+    let value = value.trim().trim_matches('"');
+    (!value.is_empty()).then(|| value.to_string())
+}
+
 pub(crate) fn parse_default_sink(output: &str) -> Option<String> {
+    parse_info_field(output, "Default Sink:")
+}
+
+fn parse_default_source(output: &str) -> Option<String> {
+    parse_info_field(output, "Default Source:")
+}
+
+fn parse_info_field(output: &str, field: &str) -> Option<String> {
     output.lines().find_map(|line| {
         let trimmed = line.trim();
         trimmed
-            .strip_prefix("Default Sink:")
+            .strip_prefix(field)
             .map(|value| value.trim().to_string())
     })
 }
@@ -296,12 +428,15 @@ Sink #1
     Description: HDMI/DisplayPort 3 (HDA NVidia Digital Stereo (HDMI))
     Properties:
         device.description = "HDMI/DisplayPort - HDA NVidia"
+        device.icon_name = "audio-card-pci"
 
 Sink #2
     State: IDLE
     Name: alsa_output.usb-SteelSeries_Arctis_Pro-00.analog-stereo
     Properties:
         device.description = "Digital Output (SteelSeries Arctis Pro)"
+        device.form_factor = "headset"
+        device.icon_name = "audio-headset-usb"
 "#;
 
         let sinks = parse_sinks(output);
@@ -320,6 +455,17 @@ Sink #2
             sinks[1].description.as_deref(),
             Some("Digital Output (SteelSeries Arctis Pro)")
         );
+        assert_eq!(sinks[0].icon_name.as_deref(), Some("audio-card-pci"));
+        assert_eq!(sinks[0].form_factor, None);
+        assert_eq!(sinks[1].form_factor.as_deref(), Some("headset"));
+        assert_eq!(sinks[1].icon_name.as_deref(), Some("audio-headset-usb"));
+    }
+
+    #[test]
+    fn parses_sink_volume_percent() {
+        let output = "Volume: front-left: 22938 /  35% / -27.36 dB,   front-right: 22938 /  35% / -27.36 dB\n        balance 0.00";
+        assert_eq!(parse_volume_percent(output), Some(35));
+        assert_eq!(parse_volume_percent("garbage"), None);
     }
 
     #[test]
@@ -334,6 +480,29 @@ Default Source: alsa_input.usb-SteelSeries_Arctis_Pro-00.mono-fallback
             default,
             Some("alsa_output.usb-SteelSeries_Arctis_Pro-00.analog-stereo".to_string())
         );
+        assert_eq!(
+            parse_default_source(output).as_deref(),
+            Some("alsa_input.usb-SteelSeries_Arctis_Pro-00.mono-fallback")
+        );
+    }
+
+    #[test]
+    fn parses_sources_without_monitors() {
+        let output = r#"
+Source #0
+    State: SUSPENDED
+    Name: alsa_output.pci-0000_09_00.3.hdmi-stereo.monitor
+    Description: Monitor of HDMI/DisplayPort
+
+Source #3
+    State: RUNNING
+    Name: alsa_input.usb-Blue_Yeti-00.analog-stereo
+    Description: Yeti Stereo Microphone Analog Stereo
+"#;
+        let sources = parse_sources(output);
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].id, Some(3));
+        assert_eq!(sources[0].name, "alsa_input.usb-Blue_Yeti-00.analog-stereo");
     }
 
     #[test]
@@ -343,11 +512,13 @@ Default Source: alsa_input.usb-SteelSeries_Arctis_Pro-00.mono-fallback
                 id: Some(1),
                 name: "sink_a".into(),
                 description: Some("First Sink".into()),
+                ..Default::default()
             },
             SinkInfo {
                 id: Some(2),
                 name: "sink_b".into(),
                 description: Some("Second Device".into()),
+                ..Default::default()
             },
         ];
 
@@ -373,11 +544,13 @@ Default Source: alsa_input.usb-SteelSeries_Arctis_Pro-00.mono-fallback
                 id: Some(11),
                 name: "sink_a".into(),
                 description: Some("First Sink".into()),
+                ..Default::default()
             },
             SinkInfo {
                 id: Some(12),
                 name: "sink_b".into(),
                 description: Some("Second Device".into()),
+                ..Default::default()
             },
         ];
 
@@ -0,0 +1,163 @@
+use std::env;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use once_cell::sync::Lazy;
+
+use super::worker;
+
+// bluetoothctl gives up on its own after this many seconds, well before a
+// connect attempt could be called stuck.
+const CONNECT_TIMEOUT_SECS: &str = "20";
+
+static BLUETOOTHCTL_AVAILABLE: Lazy<bool> = Lazy::new(|| on_path("bluetoothctl"));
+
+pub trait BluetoothBackend: Send + 'static {
+    fn is_connected(&self, mac: &str) -> Result<bool>;
+    fn set_connected(&self, mac: &str, connected: bool) -> Result<()>;
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// BlueZ through `bluetoothctl`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BluetoothctlBackend;
+
+impl BluetoothBackend for BluetoothctlBackend {
+    fn is_connected(&self, mac: &str) -> Result<bool> {
+        let output = worker::output_with_timeout(Command::new("bluetoothctl").args(["info", mac]))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match parse_connected(&stdout) {
+            Some(connected) => Ok(connected),
+            // `info` exits non-zero for devices that were never paired.
+            None => bail!("bluetoothctl does not know device {mac}"),
+        }
+    }
+
+    // A connect can take several seconds, so this is not held to the command
+    // timeout; it only ever runs on the controller's worker thread.
+    fn set_connected(&self, mac: &str, connected: bool) -> Result<()> {
+        let verb = if connected { "connect" } else { "disconnect" };
+        let output = Command::new("bluetoothctl")
+            .args(["--timeout", CONNECT_TIMEOUT_SECS, verb, mac])
+            .output()
+            .context("failed to execute bluetoothctl")?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Older releases exit 0 even when the attempt failed.
+        if let Some(failure) = find_failure(&stdout) {
+            bail!("{failure}");
+        }
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!(
+                "bluetoothctl {verb} exited with status {}: {}",
+                output.status.code().unwrap_or(-1),
+                stderr.trim()
+            );
+        }
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        *BLUETOOTHCTL_AVAILABLE
+    }
+}
+
+/// `AA:BB:CC:DD:EE:FF`, six pairs of hex digits.
+pub fn is_valid_mac(mac: &str) -> bool {
+    let pairs: Vec<&str> = mac.split(':').collect();
+    pairs.len() == 6
+        && pairs
+            .iter()
+            .all(|pair| pair.len() == 2 && pair.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn parse_connected(info: &str) -> Option<bool> {
+    info.lines()
+        .find_map(|line| line.trim().strip_prefix("Connected:"))
+        .map(|value| value.trim() == "yes")
+}
+
+fn find_failure(output: &str) -> Option<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("Failed to") || line.contains("not available"))
+}
+
+fn on_path(binary: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_bluetoothctl_output() {
+        let info = "Device AA:BB:CC:DD:EE:FF (public)\n\tName: WH-1000XM4\n\tPaired: yes\n\tConnected: yes\n";
+        assert_eq!(parse_connected(info), Some(true));
+        assert_eq!(
+            parse_connected(&info.replace("Connected: yes", "Connected: no")),
+            Some(false)
+        );
+        assert_eq!(
+            parse_connected("Device AA:BB:CC:DD:EE:FF not available\n"),
+            None
+        );
+
+        let connect = "Attempting to connect to AA:BB:CC:DD:EE:FF\nFailed to connect: org.bluez.Error.Failed br-connection-page-timeout\n";
+        assert_eq!(
+            find_failure(connect),
+            Some("Failed to connect: org.bluez.Error.Failed br-connection-page-timeout")
+        );
+        assert_eq!(
+            find_failure("Attempting to connect to AA:BB:CC:DD:EE:FF\nConnection successful\n"),
+            None
+        );
+
+        assert!(is_valid_mac("AA:bb:01:23:45:67"));
+        assert!(!is_valid_mac("AA:BB:CC:DD:EE"));
+        assert!(!is_valid_mac("AA:BB:CC:DD:EE:FG"));
+    }
+
+    /// Answers connect and disconnect attempts from `script` in order,
+    /// succeeding once it runs out.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockBluetoothBackend {
+        pub connected: Arc<Mutex<HashMap<String, bool>>>,
+        pub script: Arc<Mutex<VecDeque<Result<(), String>>>>,
+        pub unavailable: bool,
+    }
+
+    impl BluetoothBackend for MockBluetoothBackend {
+        fn is_connected(&self, mac: &str) -> Result<bool> {
+            Ok(self
+                .connected
+                .lock()
+                .unwrap()
+                .get(mac)
+                .copied()
+                .unwrap_or(false))
+        }
+
+        fn set_connected(&self, mac: &str, connected: bool) -> Result<()> {
+            if let Some(Err(err)) = self.script.lock().unwrap().pop_front() {
+                bail!("{err}");
+            }
+            self.connected
+                .lock()
+                .unwrap()
+                .insert(mac.to_string(), connected);
+            Ok(())
+        }
+
+        fn is_available(&self) -> bool {
+            !self.unavailable
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -26,6 +28,14 @@ static DDCUTIL_AVAILABLE: Lazy<bool> = Lazy::new(|| {
 });
 static WARNED_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
 const RETRY_BACKOFF_SECS: u64 = 5;
+pub const SYSFS_BACKLIGHT_ROOT: &str = "/sys/class/backlight";
+
+/// A display reported by `ddcutil detect`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedMonitor {
+    pub display: u8,
+    pub model: String,
+}
 
 pub struct DdcutilBackend {
     pub display: Option<String>,
@@ -71,6 +81,24 @@ impl DdcutilBackend {
         }
     }
 
+    /// Lists the DDC-capable displays, in ddcutil's display-number order.
+    pub fn enumerate() -> Result<Vec<DetectedMonitor>> {
+        if !*DDCUTIL_AVAILABLE {
+            bail!("ddcutil not available");
+        }
+        let output = Command::new("ddcutil")
+            .arg("detect")
+            .output()
+            .context("failed to execute ddcutil detect")?;
+        if !output.status.success() {
+            bail!(
+                "ddcutil detect exited with {}",
+                output.status.code().unwrap_or(-1)
+            );
+        }
+        Ok(parse_detect(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     pub fn is_available(&self) -> bool {
         let (available, became_available) = self.availability.try_acquire();
         if became_available {
@@ -178,6 +206,238 @@ impl BrightnessBackend for DdcutilBackend {
     }
 }
 
+/// Drives a laptop panel through `/sys/class/backlight/<device>`, scaling the
+/// raw `brightness` value against `max_brightness`.
+#[derive(Clone)]
+pub struct SysfsBacklightBackend {
+    pub device: PathBuf,
+    max_brightness: u32,
+    availability: Arc<RetryableAvailability>,
+}
+
+impl std::fmt::Debug for SysfsBacklightBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysfsBacklightBackend")
+            .field("device", &self.device)
+            .field("max_brightness", &self.max_brightness)
+            .field("available", &self.availability.current())
+            .finish()
+    }
+}
+
+impl SysfsBacklightBackend {
+    /// Opens `name` under `root`, or the first device by name when `name` is
+    /// `None`. Returns `Ok(None)` when there is no usable device.
+    pub fn detect(root: &Path, name: Option<&str>) -> Result<Option<Self>> {
+        let device = match name {
+            Some(name) => Some(root.join(name)),
+            None => {
+                let entries = match fs::read_dir(root) {
+                    Ok(entries) => entries,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                    Err(err) => {
+                        return Err(err)
+                            .with_context(|| format!("failed to list {}", root.display()));
+                    }
+                };
+                let mut devices: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.join("max_brightness").exists())
+                    .collect();
+                devices.sort();
+                devices.into_iter().next()
+            }
+        };
+        device.map(Self::open).transpose()
+    }
+
+    pub fn open(device: PathBuf) -> Result<Self> {
+        let max_path = device.join("max_brightness");
+        let max_brightness = fs::read_to_string(&max_path)
+            .with_context(|| format!("failed to read {}", max_path.display()))?
+            .trim()
+            .parse::<u32>()
+            .with_context(|| format!("failed to parse {}", max_path.display()))?;
+        if max_brightness == 0 {
+            bail!("{} reports a maximum of 0", device.display());
+        }
+
+        let brightness_path = device.join("brightness");
+        let writable = match OpenOptions::new().write(true).open(&brightness_path) {
+            Ok(_) => true,
+            Err(err) => {
+                warn!(
+                    error = %err,
+                    path = %brightness_path.display(),
+                    "cannot write backlight brightness (is the user in the `video` group?); brightness encoder operates in placeholder mode"
+                );
+                false
+            }
+        };
+        Ok(Self {
+            device,
+            max_brightness,
+            availability: Arc::new(RetryableAvailability::new(writable, RETRY_BACKOFF_SECS)),
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.device
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn brightness_path(&self) -> PathBuf {
+        self.device.join("brightness")
+    }
+
+    fn to_percent(&self, raw: u32) -> u8 {
+        let max = u64::from(self.max_brightness);
+        ((u64::from(raw.min(self.max_brightness)) * 100 + max / 2) / max) as u8
+    }
+
+    fn to_raw(&self, percent: u8) -> u32 {
+        let max = u64::from(self.max_brightness);
+        ((u64::from(percent.min(100)) * max + 50) / 100) as u32
+    }
+
+    fn mark_unavailable(&self, err: &anyhow::Error) {
+        if self.availability.mark_unavailable() {
+            warn!(
+                error = %err,
+                device = %self.device.display(),
+                "backlight backend disabled; brightness encoder operates in placeholder mode"
+            );
+        }
+    }
+
+    fn read_raw(&self) -> Result<u32> {
+        let path = self.brightness_path();
+        fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+            .trim()
+            .parse()
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    fn write_raw(&self, raw: u32) -> Result<()> {
+        let path = self.brightness_path();
+        fs::write(&path, raw.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl BrightnessBackend for SysfsBacklightBackend {
+    fn get_brightness(&self) -> Result<u8> {
+        if !self.is_available() {
+            return Ok(100);
+        }
+        match self.read_raw() {
+            Ok(raw) => Ok(self.to_percent(raw)),
+            Err(err) => {
+                self.mark_unavailable(&err);
+                Ok(100)
+            }
+        }
+    }
+
+    fn set_brightness(&self, value: u8) -> Result<u8> {
+        if !self.is_available() {
+            return Ok(value.min(100));
+        }
+        if let Err(err) = self.write_raw(self.to_raw(value)) {
+            self.mark_unavailable(&err);
+            return Ok(value.min(100));
+        }
+        self.get_brightness()
+    }
+
+    fn is_available(&self) -> bool {
+        let (available, became_available) = self.availability.try_acquire();
+        if became_available {
+            info!(device = %self.device.display(), "backlight backend is available again");
+        }
+        available
+    }
+}
+
+/// The brightness backend picked at startup from the `backend` setting.
+#[derive(Debug, Clone)]
+pub enum DisplayBrightnessBackend {
+    Ddcutil(DdcutilBackend),
+    Backlight(SysfsBacklightBackend),
+}
+
+impl BrightnessBackend for DisplayBrightnessBackend {
+    fn get_brightness(&self) -> Result<u8> {
+        match self {
+            Self::Ddcutil(backend) => backend.get_brightness(),
+            Self::Backlight(backend) => backend.get_brightness(),
+        }
+    }
+
+    fn set_brightness(&self, value: u8) -> Result<u8> {
+        match self {
+            Self::Ddcutil(backend) => backend.set_brightness(value),
+            Self::Backlight(backend) => backend.set_brightness(value),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        match self {
+            Self::Ddcutil(backend) => DdcutilBackend::is_available(backend),
+            Self::Backlight(backend) => BrightnessBackend::is_available(backend),
+        }
+    }
+}
+
+// Entries look like `Display 2` followed by indented details, including a
+// `Model:` line (ddcutil 1.x only has `Monitor: MFG:MODEL:SERIAL`). Invalid
+// displays have no number and are skipped.
+fn parse_detect(output: &str) -> Vec<DetectedMonitor> {
+    let mut monitors = Vec::new();
+    let mut current: Option<(u8, Option<String>)> = None;
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            monitors.extend(current.take().map(detected_monitor));
+            current = line
+                .strip_prefix("Display ")
+                .and_then(|number| number.trim().parse().ok())
+                .map(|display| (display, None));
+            continue;
+        }
+        let Some((_, model)) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "Model" if !value.is_empty() => *model = Some(value.to_string()),
+            "Monitor" if model.is_none() => {
+                *model = value
+                    .split(':')
+                    .nth(1)
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string);
+            }
+            _ => {}
+        }
+    }
+    monitors.extend(current.map(detected_monitor));
+    monitors
+}
+
+fn detected_monitor((display, model): (u8, Option<String>)) -> DetectedMonitor {
+    DetectedMonitor {
+        display,
+        model: model.unwrap_or_else(|| format!("Display {display}")),
+    }
+}
+
 fn warn_backend_disabled(reason: &str) {
     if !WARNED_UNAVAILABLE.swap(true, Ordering::Relaxed) {
         warn!(
@@ -190,6 +450,99 @@ fn warn_backend_disabled(reason: &str) {
 pub mod tests {
     use super::*;
     use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_detect_output() {
+        let output = "Display 1
+   I2C bus:  /dev/i2c-4
+   DRM connector:           card1-DP-1
+   EDID synopsis:
+      Mfg id:               DEL - Dell Inc.
+      Model:                DELL U2720Q
+      Serial number:        ABC123
+   VCP version:         2.1
+
+Invalid display
+   I2C bus:  /dev/i2c-6
+   EDID synopsis:
+      Model:                Built-in
+
+Display 2
+   I2C bus:  /dev/i2c-7
+   Monitor:             GSM:LG HDR 4K:
+";
+        assert_eq!(
+            parse_detect(output),
+            vec![
+                DetectedMonitor {
+                    display: 1,
+                    model: "DELL U2720Q".into(),
+                },
+                DetectedMonitor {
+                    display: 2,
+                    model: "LG HDR 4K".into(),
+                },
+            ]
+        );
+    }
+
+    fn backlight_tree(max: &str, level: &str) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        let device = root.path().join("intel_backlight");
+        fs::create_dir(&device).unwrap();
+        fs::write(device.join("max_brightness"), max).unwrap();
+        fs::write(device.join("brightness"), level).unwrap();
+        root
+    }
+
+    #[test]
+    fn sysfs_backlight_scales_to_percent() {
+        let root = backlight_tree("19393\n", "9696\n");
+        fs::create_dir(root.path().join("acpi_video0")).unwrap();
+        let backend = SysfsBacklightBackend::detect(root.path(), None)
+            .unwrap()
+            .expect("device");
+        assert_eq!(backend.name(), "intel_backlight");
+        assert_eq!(backend.get_brightness().unwrap(), 50);
+
+        assert_eq!(backend.set_brightness(25).unwrap(), 25);
+        let raw = fs::read_to_string(backend.device.join("brightness")).unwrap();
+        assert_eq!(raw, "4848");
+        assert_eq!(backend.set_brightness(100).unwrap(), 100);
+        let raw = fs::read_to_string(backend.device.join("brightness")).unwrap();
+        assert_eq!(raw, "19393");
+    }
+
+    #[test]
+    fn sysfs_backlight_detection_and_write_failures() {
+        let empty = tempfile::tempdir().unwrap();
+        assert!(
+            SysfsBacklightBackend::detect(empty.path(), None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            SysfsBacklightBackend::detect(&empty.path().join("missing"), None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(SysfsBacklightBackend::detect(empty.path(), Some("nope")).is_err());
+
+        let root = backlight_tree("255", "255");
+        let backend = SysfsBacklightBackend::detect(root.path(), Some("intel_backlight"))
+            .unwrap()
+            .expect("device");
+        assert!(BrightnessBackend::is_available(&backend));
+
+        let brightness = backend.device.join("brightness");
+        fs::remove_file(&brightness).unwrap();
+        fs::create_dir(&brightness).unwrap();
+        assert_eq!(backend.set_brightness(40).unwrap(), 40);
+        assert!(!BrightnessBackend::is_available(&backend));
+        assert_eq!(backend.get_brightness().unwrap(), 100);
+    }
 
     #[derive(Clone, Default)]
     pub struct MockBrightnessBackend {
@@ -202,7 +555,20 @@ pub mod tests {
         }
 
         fn set_brightness(&self, value: u8) -> Result<u8> {
+            let delay = {
+                let mut state = self.inner.lock().unwrap();
+                state.active_writers += 1;
+                state.max_active_writers = state.max_active_writers.max(state.active_writers);
+                state.set_delay
+            };
+            if !delay.is_zero() {
+                thread::sleep(delay);
+            }
             let mut state = self.inner.lock().unwrap();
+            state.active_writers -= 1;
+            if state.fail_sets {
+                return Err(anyhow!("mock brightness write failed"));
+            }
             state.history.push(value);
             state.level = value;
             Ok(value)
@@ -213,6 +579,10 @@ pub mod tests {
     pub struct MockBrightnessState {
         pub level: u8,
         pub history: Vec<u8>,
+        pub set_delay: Duration,
+        pub active_writers: usize,
+        pub max_active_writers: usize,
+        pub fail_sets: bool,
     }
 
     impl Default for MockBrightnessState {
@@ -220,6 +590,10 @@ pub mod tests {
             Self {
                 level: 50,
                 history: Vec::new(),
+                set_delay: Duration::ZERO,
+                active_writers: 0,
+                max_active_writers: 0,
+                fail_sets: false,
             }
         }
     }
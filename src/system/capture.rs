@@ -0,0 +1,99 @@
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+// Long enough for wf-recorder to flush and finalise the file.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+const STOP_POLL: Duration = Duration::from_millis(20);
+
+/// A screen recorder process. Recorders such as `wf-recorder` finish their
+/// file on SIGINT, so that is how it is stopped.
+#[derive(Debug)]
+pub struct Recorder {
+    program: String,
+    child: Child,
+}
+
+impl Recorder {
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self> {
+        let child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to start {program}"))?;
+        Ok(Self {
+            program: program.to_string(),
+            child,
+        })
+    }
+
+    /// The exit status once the recorder has exited on its own.
+    pub fn try_exited(&mut self) -> Result<Option<ExitStatus>> {
+        self.child
+            .try_wait()
+            .with_context(|| format!("failed to poll {}", self.program))
+    }
+
+    /// Sends SIGINT and waits for the recorder to exit, killing it if it is
+    /// still running after `STOP_TIMEOUT`.
+    pub fn stop(mut self) -> Result<ExitStatus> {
+        if let Some(status) = self.try_exited()? {
+            return Ok(status);
+        }
+        interrupt(self.child.id());
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = self.try_exited()? {
+                return Ok(status);
+            }
+            thread::sleep(STOP_POLL);
+        }
+        warn!(program = %self.program, "recorder ignored SIGINT; killing it");
+        self.child
+            .kill()
+            .with_context(|| format!("failed to kill {}", self.program))?;
+        self.child
+            .wait()
+            .with_context(|| format!("failed to wait for {}", self.program))
+    }
+}
+
+fn interrupt(pid: u32) {
+    let sent = Command::new("kill")
+        .args(["-INT", &pid.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match sent {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!(pid, code = ?status.code(), "kill -INT exited with failure status"),
+        Err(err) => warn!(error = %err, pid, "failed to run kill"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_interrupts_a_running_recorder() {
+        let mut recorder = Recorder::spawn("sleep", &["30".to_string()]).unwrap();
+        assert!(recorder.try_exited().unwrap().is_none());
+        let status = recorder.stop().unwrap();
+        assert!(!status.success());
+
+        let mut finished = Recorder::spawn("true", &[]).unwrap();
+        let status = (0..200).find_map(|_| {
+            thread::sleep(Duration::from_millis(10));
+            finished.try_exited().unwrap()
+        });
+        assert!(status.is_some_and(|status| status.success()));
+        assert!(Recorder::spawn("/nonexistent/wf-recorder", &[]).is_err());
+    }
+}
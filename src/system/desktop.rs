@@ -16,6 +16,13 @@ pub struct DesktopEntry {
     pub terminal: bool,
     pub startup_wm_class: Option<String>,
     pub entry_type: Option<String>,
+    pub actions: HashMap<String, DesktopAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DesktopAction {
+    pub name: Option<String>,
+    pub exec: Option<String>,
 }
 
 impl DesktopEntry {
@@ -23,7 +30,11 @@ impl DesktopEntry {
         let path = path.as_ref();
         let contents = fs::read_to_string(path)
             .with_context(|| format!("failed to read desktop entry at {}", path.display()))?;
-        let fields = parse_desktop_entry(&contents)?;
+        let mut sections = parse_desktop_sections(&contents)?;
+        let fields = sections
+            .remove("Desktop Entry")
+            .filter(|fields| !fields.is_empty())
+            .ok_or_else(|| anyhow!("desktop entry missing required [Desktop Entry] section"))?;
 
         let desktop_id = path
             .file_name()
@@ -51,6 +62,7 @@ impl DesktopEntry {
             terminal,
             startup_wm_class: fields.get("StartupWMClass").cloned(),
             entry_type: fields.get("Type").cloned(),
+            actions: parse_actions(sections),
         })
     }
 }
@@ -67,9 +79,27 @@ fn resolve_relative_path(value: &str, source: &Path) -> PathBuf {
     }
 }
 
-fn parse_desktop_entry(contents: &str) -> Result<HashMap<String, String>> {
-    let mut section = None;
-    let mut fields = HashMap::new();
+fn parse_actions(
+    sections: HashMap<String, HashMap<String, String>>,
+) -> HashMap<String, DesktopAction> {
+    sections
+        .into_iter()
+        .filter_map(|(section, mut fields)| {
+            let id = section.strip_prefix("Desktop Action ")?.trim().to_string();
+            Some((
+                id,
+                DesktopAction {
+                    name: fields.remove("Name"),
+                    exec: fields.remove("Exec"),
+                },
+            ))
+        })
+        .collect()
+}
+
+fn parse_desktop_sections(contents: &str) -> Result<HashMap<String, HashMap<String, String>>> {
+    let mut section: Option<String> = None;
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
 
     for raw_line in contents.lines() {
         let line = raw_line.trim();
@@ -78,14 +108,15 @@ fn parse_desktop_entry(contents: &str) -> Result<HashMap<String, String>> {
         }
 
         if line.starts_with('[') && line.ends_with(']') {
-            let section_name = &line[1..line.len() - 1];
-            section = Some(section_name.trim().to_string());
+            let section_name = line[1..line.len() - 1].trim().to_string();
+            sections.entry(section_name.clone()).or_default();
+            section = Some(section_name);
             continue;
         }
 
-        if !matches!(section.as_deref(), Some("Desktop Entry")) {
+        let Some(current) = section.as_ref() else {
             continue;
-        }
+        };
 
         let mut parts = line.splitn(2, '=');
         let key = parts
@@ -94,16 +125,12 @@ fn parse_desktop_entry(contents: &str) -> Result<HashMap<String, String>> {
             .filter(|key| !key.is_empty())
             .ok_or_else(|| anyhow!("invalid desktop entry line: {line}"))?;
         let value = parts.next().map(str::trim).unwrap_or_default().to_string();
-        fields.insert(key.to_string(), value);
+        if let Some(fields) = sections.get_mut(current) {
+            fields.insert(key.to_string(), value);
+        }
     }
 
-    if fields.is_empty() {
-        Err(anyhow!(
-            "desktop entry missing required [Desktop Entry] section"
-        ))
-    } else {
-        Ok(fields)
-    }
+    Ok(sections)
 }
 
 #[cfg(test)]
@@ -139,6 +166,31 @@ Type=Application
         assert_eq!(entry.entry_type.as_deref(), Some("Application"));
     }
 
+    #[test]
+    fn parses_desktop_actions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("browser.desktop");
+        fs::write(
+            &path,
+            "[Desktop Entry]
+Name=Browser
+Exec=browser %u
+Actions=new-private-window;
+
+[Desktop Action new-private-window]
+Name=New Private Window
+Exec=browser --private-window
+",
+        )
+        .unwrap();
+
+        let entry = DesktopEntry::from_path(&path).unwrap();
+        assert_eq!(entry.exec.as_deref(), Some("browser %u"));
+        let action = entry.actions.get("new-private-window").expect("action");
+        assert_eq!(action.name.as_deref(), Some("New Private Window"));
+        assert_eq!(action.exec.as_deref(), Some("browser --private-window"));
+    }
+
     #[test]
     fn resolves_relative_working_directory() {
         let dir = tempdir().unwrap();
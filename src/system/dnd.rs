@@ -0,0 +1,166 @@
+use std::env;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use once_cell::sync::Lazy;
+use tracing::{info, warn};
+
+const MAKO_DND_MODE: &str = "do-not-disturb";
+const GNOME_NOTIFICATIONS_SCHEMA: &str = "org.gnome.desktop.notifications";
+
+// makoctl is checked first: gsettings ships with GLib and is often installed
+// on desktops that do not run GNOME's notification daemon.
+static DETECTED_BACKEND: Lazy<DesktopDndBackend> = Lazy::new(|| {
+    let backend = if on_path("makoctl") {
+        DesktopDndBackend::Mako
+    } else if on_path("gsettings") {
+        DesktopDndBackend::Gnome
+    } else {
+        warn!("neither makoctl nor gsettings found on PATH; do-not-disturb toggle disabled");
+        return DesktopDndBackend::Unavailable;
+    };
+    info!(?backend, "using do-not-disturb backend");
+    backend
+});
+
+pub trait DndBackend: Send {
+    fn is_enabled(&self) -> Result<bool>;
+    /// Flips do-not-disturb and returns the new state.
+    fn toggle(&self) -> Result<bool>;
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Do-not-disturb through whichever notification daemon was found on `PATH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesktopDndBackend {
+    /// `makoctl mode` with a `do-not-disturb` mode, as used under sway.
+    Mako,
+    /// GNOME's `show-banners` setting, which is what its own toggle flips.
+    Gnome,
+    Unavailable,
+}
+
+impl DesktopDndBackend {
+    /// Returns the backend picked on first use; detection runs only once.
+    pub fn detect() -> Self {
+        *DETECTED_BACKEND
+    }
+}
+
+impl DndBackend for DesktopDndBackend {
+    fn is_enabled(&self) -> Result<bool> {
+        match self {
+            Self::Mako => Ok(parse_mako_modes(&run("makoctl", &["mode"])?)),
+            Self::Gnome => {
+                let output = run(
+                    "gsettings",
+                    &["get", GNOME_NOTIFICATIONS_SCHEMA, "show-banners"],
+                )?;
+                match parse_gsettings_bool(&output) {
+                    Some(show_banners) => Ok(!show_banners),
+                    None => bail!("unexpected gsettings output `{}`", output.trim()),
+                }
+            }
+            Self::Unavailable => bail!("no do-not-disturb backend available"),
+        }
+    }
+
+    fn toggle(&self) -> Result<bool> {
+        match self {
+            Self::Mako => {
+                run("makoctl", &["mode", "-t", MAKO_DND_MODE])?;
+                self.is_enabled()
+            }
+            Self::Gnome => {
+                let enabled = !self.is_enabled()?;
+                let show_banners = if enabled { "false" } else { "true" };
+                run(
+                    "gsettings",
+                    &[
+                        "set",
+                        GNOME_NOTIFICATIONS_SCHEMA,
+                        "show-banners",
+                        show_banners,
+                    ],
+                )?;
+                Ok(enabled)
+            }
+            Self::Unavailable => bail!("no do-not-disturb backend available"),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        *self != Self::Unavailable
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to execute {program} with args {args:?}"))?;
+    if !output.status.success() {
+        bail!(
+            "{program} exited with status {}",
+            output.status.code().unwrap_or(-1)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn on_path(binary: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+}
+
+// `makoctl mode` lists the active modes, one per line.
+fn parse_mako_modes(output: &str) -> bool {
+    output.lines().any(|line| line.trim() == MAKO_DND_MODE)
+}
+
+fn parse_gsettings_bool(output: &str) -> Option<bool> {
+    match output.trim() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_daemon_output() {
+        assert!(parse_mako_modes("default\ndo-not-disturb\n"));
+        assert!(!parse_mako_modes("default\n"));
+        assert_eq!(parse_gsettings_bool("false\n"), Some(false));
+        assert_eq!(parse_gsettings_bool("true"), Some(true));
+        assert_eq!(parse_gsettings_bool(""), None);
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct MockDndBackend {
+        pub enabled: Arc<Mutex<bool>>,
+        pub available: bool,
+    }
+
+    impl DndBackend for MockDndBackend {
+        fn is_enabled(&self) -> Result<bool> {
+            Ok(*self.enabled.lock().unwrap())
+        }
+
+        fn toggle(&self) -> Result<bool> {
+            let mut enabled = self.enabled.lock().unwrap();
+            *enabled = !*enabled;
+            Ok(*enabled)
+        }
+
+        fn is_available(&self) -> bool {
+            self.available
+        }
+    }
+}
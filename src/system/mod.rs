@@ -1,6 +1,19 @@
+pub mod album_art;
 pub mod audio;
 pub mod audio_switch;
 pub mod availability;
+pub mod bluetooth;
 pub mod brightness;
+pub mod capture;
 pub mod desktop;
+pub mod dnd;
 pub mod now_playing;
+pub mod pulse_events;
+pub mod session;
+pub mod snippet;
+pub mod system_stats;
+pub mod timer_state;
+pub mod vpn;
+pub mod weather;
+pub mod window_focus;
+pub mod worker;
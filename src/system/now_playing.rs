@@ -5,6 +5,7 @@ use anyhow::{Context, Result, bail};
 use tracing::{debug, info, warn};
 
 use crate::system::availability::RetryableAvailability;
+use crate::system::worker::output_with_timeout;
 
 const FIELD_SEPARATOR: &str = "\u{1F}";
 const PLAYERCTL_BACKOFF_SECS: u64 = 10;
@@ -33,6 +34,10 @@ pub struct PlaybackState {
     pub status: PlaybackStatus,
     pub title: Option<String>,
     pub artist: Option<String>,
+    pub art_url: Option<String>,
+    pub position_secs: Option<u64>,
+    /// `None` for live streams and players that do not report a length.
+    pub length_secs: Option<u64>,
 }
 
 impl PlaybackState {
@@ -41,6 +46,9 @@ impl PlaybackState {
             status: PlaybackStatus::Unavailable,
             title: None,
             artist: None,
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
         }
     }
 
@@ -49,14 +57,29 @@ impl PlaybackState {
             status: PlaybackStatus::Stopped,
             title: None,
             artist: None,
+            art_url: None,
+            position_secs: None,
+            length_secs: None,
         }
     }
+
+    /// Equal apart from the position, which moves on every poll.
+    pub fn same_track_as(&self, other: &Self) -> bool {
+        self.status == other.status
+            && self.title == other.title
+            && self.artist == other.artist
+            && self.art_url == other.art_url
+            && self.length_secs == other.length_secs
+    }
 }
 
-pub trait NowPlayingBackend: Send {
+pub trait NowPlayingBackend: Send + 'static {
     fn now_playing(&self) -> Result<PlaybackState>;
     fn next(&self) -> Result<()>;
     fn previous(&self) -> Result<()>;
+    fn play_pause(&self) -> Result<()>;
+    /// Moves the playback position by `offset_secs`, backwards when negative.
+    fn seek(&self, offset_secs: i64) -> Result<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +96,19 @@ impl PlayerctlBackend {
         }
     }
 
+    /// Names of the running MPRIS players, as `--player` takes them.
+    pub fn list_players() -> Result<Vec<String>> {
+        // Exits non-zero with "No players found" when nothing is running.
+        let output = output_with_timeout(Command::new("playerctl").arg("--list-all"))
+            .context("failed to execute playerctl --list-all")?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
     fn mark_unavailable(&self, reason: &str) {
         if self.availability.mark_unavailable() {
             warn!(
@@ -102,17 +138,18 @@ impl PlayerctlBackend {
             bail!("playerctl backend currently unavailable");
         }
 
-        let command = Command::new("playerctl")
-            .arg("--player")
-            .arg(&self.player)
-            .arg("metadata")
-            .arg("--format")
-            .arg(format!(
-                "{{{{status}}}}{sep}{{{{xesam:title}}}}{sep}{{{{xesam:artist}}}}",
-                sep = FIELD_SEPARATOR
-            ))
-            .output()
-            .with_context(|| {
+        let command = output_with_timeout(
+            Command::new("playerctl")
+                .arg("--player")
+                .arg(&self.player)
+                .arg("metadata")
+                .arg("--format")
+                .arg(format!(
+                    "{{{{status}}}}{sep}{{{{xesam:title}}}}{sep}{{{{xesam:artist}}}}{sep}{{{{mpris:artUrl}}}}{sep}{{{{position}}}}{sep}{{{{mpris:length}}}}",
+                    sep = FIELD_SEPARATOR
+                )),
+        )
+        .with_context(|| {
                 format!(
                     "failed to execute playerctl metadata for player {}",
                     self.player
@@ -123,10 +160,13 @@ impl PlayerctlBackend {
     }
 
     fn parse_metadata(&self, output: &str) -> Option<PlaybackState> {
-        let mut parts = output.splitn(3, FIELD_SEPARATOR);
+        let mut parts = output.splitn(6, FIELD_SEPARATOR);
         let status_str = parts.next()?.trim();
         let title_raw = parts.next().unwrap_or_default().trim();
         let artist_raw = parts.next().unwrap_or_default().trim();
+        let art_raw = parts.next().unwrap_or_default().trim();
+        let position_secs = parts.next().and_then(parse_micros);
+        let length_secs = parts.next().and_then(parse_micros).filter(|secs| *secs > 0);
 
         let status = PlaybackStatus::from_status_string(status_str)?;
         let title = if title_raw.is_empty() {
@@ -140,10 +180,19 @@ impl PlayerctlBackend {
             Some(artist_raw.replace(';', ", "))
         };
 
+        let art_url = if art_raw.is_empty() {
+            None
+        } else {
+            Some(art_raw.to_string())
+        };
+
         Some(PlaybackState {
             status,
             title,
             artist,
+            art_url,
+            position_secs,
+            length_secs,
         })
     }
 }
@@ -192,24 +241,40 @@ impl NowPlayingBackend for PlayerctlBackend {
     }
 
     fn next(&self) -> Result<()> {
-        Self::run_playerctl_command(&self.player, "next")
+        Self::run_playerctl_command(&self.player, "next", &[])
     }
 
     fn previous(&self) -> Result<()> {
-        Self::run_playerctl_command(&self.player, "previous")
+        Self::run_playerctl_command(&self.player, "previous", &[])
+    }
+
+    fn play_pause(&self) -> Result<()> {
+        Self::run_playerctl_command(&self.player, "play-pause", &[])
+    }
+
+    fn seek(&self, offset_secs: i64) -> Result<()> {
+        PlayerctlBackend::seek(self, offset_secs)
     }
 }
 
 impl PlayerctlBackend {
-    fn run_playerctl_command(player: &str, command: &str) -> Result<()> {
-        let status = Command::new("playerctl")
-            .arg("--player")
-            .arg(player)
-            .arg(command)
-            .status()
-            .with_context(|| {
-                format!("failed to execute playerctl {command} for player {player}")
-            })?;
+    pub fn seek(&self, offset_secs: i64) -> Result<()> {
+        if offset_secs == 0 {
+            return Ok(());
+        }
+        Self::run_playerctl_command(&self.player, "position", &[&seek_argument(offset_secs)])
+    }
+
+    fn run_playerctl_command(player: &str, command: &str, args: &[&str]) -> Result<()> {
+        let status = output_with_timeout(
+            Command::new("playerctl")
+                .arg("--player")
+                .arg(player)
+                .arg(command)
+                .args(args),
+        )
+        .with_context(|| format!("failed to execute playerctl {command} for player {player}"))?
+        .status;
 
         if status.success() {
             Ok(())
@@ -221,10 +286,42 @@ impl PlayerctlBackend {
     }
 }
 
+// Both fields are microseconds. Most players send integers, but some send
+// `mpris:length` as a double, which playerctl prints in exponent form.
+fn parse_micros(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    let micros = match raw.parse::<u64>() {
+        Ok(micros) => micros,
+        Err(_) => {
+            let micros = raw.parse::<f64>().ok()?;
+            if !micros.is_finite() || micros < 0.0 {
+                return None;
+            }
+            micros as u64
+        }
+    };
+    Some(micros / 1_000_000)
+}
+
+// playerctl takes relative positions as `SECONDS+` or `SECONDS-`.
+fn seek_argument(offset_secs: i64) -> String {
+    let sign = if offset_secs < 0 { '-' } else { '+' };
+    format!("{}{sign}", offset_secs.unsigned_abs())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn seek_argument_uses_relative_syntax() {
+        assert_eq!(seek_argument(5), "5+");
+        assert_eq!(seek_argument(-10), "10-");
+    }
+
     #[test]
     fn playback_status_parses_known_states() {
         assert_eq!(
@@ -252,6 +349,40 @@ mod tests {
         assert_eq!(state.status, PlaybackStatus::Playing);
         assert_eq!(state.title.as_deref(), Some("Song Name"));
         assert_eq!(state.artist.as_deref(), Some("Artist Name"));
+        assert!(state.art_url.is_none());
+        assert!(state.position_secs.is_none() && state.length_secs.is_none());
+    }
+
+    #[test]
+    fn parse_metadata_reads_position_and_length_in_microseconds() {
+        let backend = PlayerctlBackend::new("spotify");
+        let state = backend
+            .parse_metadata("Playing\u{1F}Song\u{1F}Artist\u{1F}\u{1F}83421000\u{1F}215000000")
+            .expect("metadata parsed");
+        assert_eq!(state.art_url, None);
+        assert_eq!(state.position_secs, Some(83));
+        assert_eq!(state.length_secs, Some(215));
+
+        let stream = backend
+            .parse_metadata("Playing\u{1F}Radio\u{1F}\u{1F}\u{1F}1250000\u{1F}")
+            .expect("metadata parsed");
+        assert_eq!(stream.position_secs, Some(1));
+        assert_eq!(stream.length_secs, None);
+
+        assert_eq!(parse_micros("2.4e+08"), Some(240));
+        assert_eq!(parse_micros("0"), Some(0));
+        assert_eq!(parse_micros("-5"), None);
+        assert_eq!(parse_micros("n/a"), None);
+    }
+
+    #[test]
+    fn parse_metadata_reads_art_url() {
+        let backend = PlayerctlBackend::new("spotify");
+        let state = backend
+            .parse_metadata("Playing\u{1F}Song\u{1F}Artist\u{1F}file:///tmp/cover.png")
+            .expect("metadata parsed");
+
+        assert_eq!(state.art_url.as_deref(), Some("file:///tmp/cover.png"));
     }
 
     #[test]
@@ -264,5 +395,6 @@ mod tests {
         assert_eq!(state.status, PlaybackStatus::Paused);
         assert!(state.title.is_none());
         assert!(state.artist.is_none());
+        assert!(state.art_url.is_none());
     }
 }
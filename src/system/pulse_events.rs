@@ -0,0 +1,202 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use tracing::{debug, info, warn};
+
+use super::availability::RetryableAvailability;
+
+const SUBSCRIBE_BACKOFF_SECS: u64 = 5;
+const RETRY_POLL: Duration = Duration::from_secs(1);
+
+/// What a `pactl subscribe` line says changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PulseEvent {
+    /// A sink's volume or mute changed, or a sink came or went.
+    Sink,
+    Source,
+    /// Server settings such as the default sink or source changed. Also sent
+    /// after the subscription restarts, since changes may have been missed.
+    Server,
+}
+
+/// Runs `pactl subscribe` on a background thread, restarting it with a
+/// backoff when it exits.
+pub struct PulseEventWatcher {
+    events: Receiver<PulseEvent>,
+    availability: Arc<RetryableAvailability>,
+}
+
+impl PulseEventWatcher {
+    pub fn spawn() -> Self {
+        Self::spawn_command(
+            vec!["pactl".to_string(), "subscribe".to_string()],
+            SUBSCRIBE_BACKOFF_SECS,
+        )
+    }
+
+    fn spawn_command(command: Vec<String>, backoff_secs: u64) -> Self {
+        let (tx, events) = unbounded();
+        let availability = Arc::new(RetryableAvailability::new(true, backoff_secs));
+        let shared = Arc::clone(&availability);
+        thread::spawn(move || {
+            let mut attempts = 0u32;
+            loop {
+                if !shared.try_acquire().0 {
+                    thread::sleep(RETRY_POLL);
+                    continue;
+                }
+                let mut started = false;
+                match subscribe(&command, &tx, &shared, attempts > 0, &mut started) {
+                    Ok(()) => return,
+                    // Only the first failure after a working subscription is
+                    // worth a warning; retries while pactl stays away are not.
+                    Err(err) if started || attempts == 0 => {
+                        warn!(error = %err, "audio event subscription lost; polling instead")
+                    }
+                    Err(err) => debug!(error = %err, "audio event subscription still unavailable"),
+                }
+                shared.mark_unavailable();
+                attempts = attempts.saturating_add(1);
+            }
+        });
+        Self {
+            events,
+            availability,
+        }
+    }
+
+    /// Never subscribes, so no events arrive and it is never live.
+    #[cfg(test)]
+    pub fn idle() -> Self {
+        Self {
+            events: crossbeam_channel::never(),
+            availability: Arc::new(RetryableAvailability::new(false, SUBSCRIBE_BACKOFF_SECS)),
+        }
+    }
+
+    pub fn events(&self) -> &Receiver<PulseEvent> {
+        &self.events
+    }
+
+    /// False while the subscription is down; callers poll instead.
+    pub fn is_live(&self) -> bool {
+        self.availability.current()
+    }
+}
+
+// Returns `Ok` once nobody is listening any more, and an error when the
+// subscription could not start or ended.
+fn subscribe(
+    command: &[String],
+    tx: &Sender<PulseEvent>,
+    availability: &RetryableAvailability,
+    restarted: bool,
+    started: &mut bool,
+) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("empty subscribe command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to start {program}"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("{program} has no stdout"))?;
+    *started = true;
+
+    if availability.mark_available() || restarted {
+        info!("audio event subscription started");
+        if tx.send(PulseEvent::Server).is_err() {
+            let _ = child.kill();
+            return Ok(());
+        }
+    }
+
+    for line in BufReader::new(stdout).lines() {
+        let Some(event) = line.ok().as_deref().and_then(parse_event) else {
+            continue;
+        };
+        if tx.send(event).is_err() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(());
+        }
+    }
+    let status = child.wait().context("failed to wait for subscription")?;
+    Err(anyhow!("{program} exited with {status}"))
+}
+
+// Lines look like `Event 'change' on sink #47`; client, card and stream
+// events are ignored.
+fn parse_event(line: &str) -> Option<PulseEvent> {
+    let (_, facility) = line.trim().split_once("' on ")?;
+    match facility.split(" #").next()? {
+        "sink" => Some(PulseEvent::Sink),
+        "source" => Some(PulseEvent::Source),
+        "server" => Some(PulseEvent::Server),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sink_source_and_server_events() {
+        assert_eq!(
+            parse_event("Event 'change' on sink #47"),
+            Some(PulseEvent::Sink)
+        );
+        assert_eq!(
+            parse_event("Event 'new' on source #3\n"),
+            Some(PulseEvent::Source)
+        );
+        assert_eq!(
+            parse_event("Event 'change' on server #-1"),
+            Some(PulseEvent::Server)
+        );
+        assert_eq!(parse_event("Event 'change' on sink-input #12"), None);
+        assert_eq!(parse_event("Event 'remove' on client #88"), None);
+        assert_eq!(parse_event("garbage"), None);
+    }
+
+    #[test]
+    fn restarts_a_subscription_that_exits() {
+        let script = "printf \"Event 'change' on sink-input #1\\nEvent 'change' on sink #2\\n\"";
+        let watcher = PulseEventWatcher::spawn_command(
+            vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            0,
+        );
+        let received: Vec<_> = (0..3)
+            .map(|_| {
+                watcher
+                    .events()
+                    .recv_timeout(Duration::from_secs(5))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            received,
+            [PulseEvent::Sink, PulseEvent::Server, PulseEvent::Sink]
+        );
+
+        let missing = PulseEventWatcher::spawn_command(
+            vec!["/nonexistent/pactl".to_string(), "subscribe".to_string()],
+            60,
+        );
+        thread::sleep(Duration::from_millis(50));
+        assert!(!missing.is_live());
+        assert!(missing.events().try_recv().is_err());
+    }
+}
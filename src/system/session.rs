@@ -0,0 +1,209 @@
+use std::env;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use tracing::{debug, warn};
+
+// Ticks in between reuse the last answer, so loginctl runs twice a minute.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub trait SessionMonitor: Send {
+    /// Whether the session is locked or idle.
+    fn is_locked(&self) -> Result<bool>;
+}
+
+/// Reads logind's `LockedHint` and `IdleHint` for the session in
+/// `XDG_SESSION_ID`, or else the user's display session, which is what a
+/// systemd user unit gets.
+#[derive(Debug, Clone)]
+pub struct LoginctlSession {
+    session: String,
+}
+
+impl LoginctlSession {
+    pub fn new() -> Self {
+        let session = env::var("XDG_SESSION_ID")
+            .ok()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| "auto".to_string());
+        Self { session }
+    }
+}
+
+impl Default for LoginctlSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionMonitor for LoginctlSession {
+    fn is_locked(&self) -> Result<bool> {
+        let output = Command::new("loginctl")
+            .args([
+                "show-session",
+                &self.session,
+                "-p",
+                "LockedHint",
+                "-p",
+                "IdleHint",
+            ])
+            .output()
+            .context("failed to execute loginctl")?;
+        if !output.status.success() {
+            bail!(
+                "loginctl exited with status {}",
+                output.status.code().unwrap_or(-1)
+            );
+        }
+        parse_hints(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+// `loginctl show-session -p` prints one `Name=value` line per property.
+fn parse_hints(output: &str) -> Result<bool> {
+    let mut seen = false;
+    let mut locked = false;
+    for (name, value) in output
+        .lines()
+        .filter_map(|line| line.trim().split_once('='))
+    {
+        if matches!(name, "LockedHint" | "IdleHint") {
+            seen = true;
+            locked |= value == "yes";
+        }
+    }
+    if !seen {
+        bail!("loginctl reported neither LockedHint nor IdleHint");
+    }
+    Ok(locked)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionChange {
+    Locked,
+    Unlocked,
+}
+
+/// Asks a `SessionMonitor` at most every `SESSION_POLL_INTERVAL` and reports
+/// lock and unlock transitions. A monitor that fails counts as unlocked, so
+/// nothing stays paused just because logind cannot be reached.
+pub struct SessionWatch<M>
+where
+    M: SessionMonitor,
+{
+    monitor: M,
+    locked: bool,
+    next_poll: Option<Instant>,
+    warned: bool,
+}
+
+impl<M> SessionWatch<M>
+where
+    M: SessionMonitor,
+{
+    pub fn new(monitor: M) -> Self {
+        Self {
+            monitor,
+            locked: false,
+            next_poll: None,
+            warned: false,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Option<SessionChange> {
+        if self.next_poll.is_some_and(|due| now < due) {
+            return None;
+        }
+        self.next_poll = Some(now + SESSION_POLL_INTERVAL);
+        let locked = match self.monitor.is_locked() {
+            Ok(locked) => locked,
+            Err(err) if self.warned => {
+                debug!(error = %err, "failed to read session lock state");
+                false
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to read session lock state; polling continues while locked");
+                self.warned = true;
+                false
+            }
+        };
+        if locked == self.locked {
+            return None;
+        }
+        self.locked = locked;
+        Some(if locked {
+            SessionChange::Locked
+        } else {
+            SessionChange::Unlocked
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_loginctl_hints() {
+        assert!(!parse_hints("LockedHint=no\nIdleHint=no\n").unwrap());
+        assert!(parse_hints("LockedHint=yes\nIdleHint=no\n").unwrap());
+        assert!(parse_hints("IdleHint=yes\n").unwrap());
+        assert!(parse_hints("").is_err());
+    }
+
+    /// Reports whatever `locked` holds; `None` makes the query fail.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockSessionMonitor {
+        pub locked: Arc<Mutex<Option<bool>>>,
+        pub calls: Arc<Mutex<usize>>,
+    }
+
+    impl MockSessionMonitor {
+        pub fn set(&self, locked: Option<bool>) {
+            *self.locked.lock().unwrap() = locked;
+        }
+    }
+
+    impl SessionMonitor for MockSessionMonitor {
+        fn is_locked(&self) -> Result<bool> {
+            *self.calls.lock().unwrap() += 1;
+            self.locked
+                .lock()
+                .unwrap()
+                .ok_or_else(|| anyhow!("logind is not running"))
+        }
+    }
+
+    #[test]
+    fn reports_transitions_at_the_poll_interval() {
+        let monitor = MockSessionMonitor::default();
+        monitor.set(Some(false));
+        let mut watch = SessionWatch::new(monitor.clone());
+        let start = Instant::now();
+        assert_eq!(watch.poll(start), None);
+
+        monitor.set(Some(true));
+        assert_eq!(watch.poll(start + Duration::from_secs(1)), None);
+        assert!(!watch.is_locked());
+        assert_eq!(
+            watch.poll(start + SESSION_POLL_INTERVAL),
+            Some(SessionChange::Locked)
+        );
+        assert!(watch.is_locked());
+        assert_eq!(*monitor.calls.lock().unwrap(), 2);
+
+        monitor.set(None);
+        assert_eq!(
+            watch.poll(start + SESSION_POLL_INTERVAL * 2),
+            Some(SessionChange::Unlocked)
+        );
+        assert!(!watch.is_locked());
+    }
+}
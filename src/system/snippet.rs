@@ -0,0 +1,249 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::worker;
+
+static DETECTED_TOOLS: Lazy<PasteTools> = Lazy::new(|| {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let clipboard = if wayland && on_path("wl-copy") {
+        Some(ClipboardTool::WlCopy)
+    } else if on_path("xclip") {
+        Some(ClipboardTool::Xclip)
+    } else {
+        None
+    };
+    let tools = PasteTools {
+        wayland,
+        wtype: on_path("wtype"),
+        xdotool: on_path("xdotool"),
+        clipboard,
+    };
+    if !tools.wtype && !tools.xdotool && tools.clipboard.is_none() {
+        warn!("none of wtype, xdotool, wl-copy or xclip found on PATH; snippet keys disabled");
+    } else {
+        info!(?tools, "detected snippet paste tools");
+    }
+    tools
+});
+
+/// How a snippet reaches the focused window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteMethod {
+    /// Typed with `wtype` under Wayland.
+    Wtype,
+    /// Typed with `xdotool type` under X11.
+    Xdotool,
+    /// Copied with `wl-copy` or `xclip`, for the user to paste.
+    Clipboard,
+}
+
+pub trait SnippetBackend: Send {
+    /// The method a press will use given the configured one, or `None` when
+    /// neither it nor the clipboard fallback is installed.
+    fn resolve(&self, preferred: Option<PasteMethod>) -> Option<PasteMethod>;
+    fn paste(&self, method: PasteMethod, text: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClipboardTool {
+    WlCopy,
+    Xclip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PasteTools {
+    wayland: bool,
+    wtype: bool,
+    xdotool: bool,
+    clipboard: Option<ClipboardTool>,
+}
+
+impl PasteTools {
+    fn resolve(&self, preferred: Option<PasteMethod>) -> Option<PasteMethod> {
+        let typed = match preferred {
+            Some(PasteMethod::Wtype) => self.wtype.then_some(PasteMethod::Wtype),
+            Some(PasteMethod::Xdotool) => self.xdotool.then_some(PasteMethod::Xdotool),
+            Some(PasteMethod::Clipboard) => None,
+            None if self.wayland => self.wtype.then_some(PasteMethod::Wtype),
+            None => self.xdotool.then_some(PasteMethod::Xdotool),
+        };
+        typed.or(self.clipboard.map(|_| PasteMethod::Clipboard))
+    }
+}
+
+/// Pastes with whichever tools were found on `PATH`; detection runs once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DesktopSnippetBackend;
+
+impl SnippetBackend for DesktopSnippetBackend {
+    fn resolve(&self, preferred: Option<PasteMethod>) -> Option<PasteMethod> {
+        DETECTED_TOOLS.resolve(preferred)
+    }
+
+    fn paste(&self, method: PasteMethod, text: &str) -> Result<()> {
+        match method {
+            PasteMethod::Wtype => type_text("wtype", &[], text),
+            PasteMethod::Xdotool => type_text("xdotool", &["type", "--clearmodifiers"], text),
+            PasteMethod::Clipboard => match DETECTED_TOOLS.clipboard {
+                Some(ClipboardTool::WlCopy) => pipe_text("wl-copy", &[], text),
+                Some(ClipboardTool::Xclip) => {
+                    pipe_text("xclip", &["-selection", "clipboard"], text)
+                }
+                None => bail!("neither wl-copy nor xclip is installed"),
+            },
+        }
+    }
+}
+
+// The text is its own argument after `--`, so quotes, newlines and a leading
+// dash reach the tool untouched. Typing a long snippet takes a while, so the
+// child is reaped on its own thread instead of held to the command timeout.
+fn type_text(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .arg("--")
+        .arg(text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to execute {program}"))?;
+    let program = program.to_string();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!(%program, code = ?status.code(), "snippet typing failed"),
+        Err(err) => warn!(error = %err, %program, "failed to wait for snippet typing"),
+    });
+    Ok(())
+}
+
+// Both tools read the selection from stdin and fork a process that keeps
+// serving it, so the direct child exits once the pipe is closed.
+fn pipe_text(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to execute {program}"))?;
+    let written = child
+        .stdin
+        .take()
+        .context("clipboard tool has no stdin")
+        .and_then(|mut stdin| {
+            stdin
+                .write_all(text.as_bytes())
+                .with_context(|| format!("failed to write to {program}"))
+        });
+    let status = worker::wait_with_timeout(&mut child, program)?;
+    written?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+fn on_path(binary: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    pub struct MockSnippetBackend {
+        pub methods: Vec<PasteMethod>,
+        pub pasted: Arc<Mutex<Vec<(PasteMethod, String)>>>,
+    }
+
+    impl SnippetBackend for MockSnippetBackend {
+        fn resolve(&self, preferred: Option<PasteMethod>) -> Option<PasteMethod> {
+            match preferred {
+                Some(method) if self.methods.contains(&method) => Some(method),
+                Some(_) => self
+                    .methods
+                    .contains(&PasteMethod::Clipboard)
+                    .then_some(PasteMethod::Clipboard),
+                None => self.methods.first().copied(),
+            }
+        }
+
+        fn paste(&self, method: PasteMethod, text: &str) -> Result<()> {
+            self.pasted.lock().unwrap().push((method, text.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn text_reaches_the_tools_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = "-n first line\n  \"quoted\" $HOME `date`\nnaïve – 日本語 🎉";
+
+        let piped = dir.path().join("piped");
+        let target = piped.display().to_string();
+        pipe_text("sh", &["-c", r#"cat > "$0""#, &target], text).unwrap();
+        assert_eq!(std::fs::read_to_string(&piped).unwrap(), text);
+
+        // `sh -c` sees the path as $0, then `--` and the text.
+        let typed = dir.path().join("typed");
+        let target = typed.display().to_string();
+        type_text(
+            "sh",
+            &[
+                "-c",
+                r#"printf %s "$2" > "$0.tmp" && mv "$0.tmp" "$0""#,
+                &target,
+            ],
+            text,
+        )
+        .unwrap();
+        let contents = (0..200).find_map(|_| {
+            thread::sleep(std::time::Duration::from_millis(10));
+            std::fs::read_to_string(&typed).ok()
+        });
+        assert_eq!(contents.as_deref(), Some(text));
+
+        assert!(pipe_text("sh", &["-c", "exit 3"], text).is_err());
+        assert!(type_text("/nonexistent/wtype", &[], text).is_err());
+    }
+
+    #[test]
+    fn missing_typing_tools_fall_back_to_the_clipboard() {
+        let mut tools = PasteTools {
+            wayland: true,
+            wtype: true,
+            xdotool: false,
+            clipboard: Some(ClipboardTool::WlCopy),
+        };
+        assert_eq!(tools.resolve(None), Some(PasteMethod::Wtype));
+        assert_eq!(
+            tools.resolve(Some(PasteMethod::Clipboard)),
+            Some(PasteMethod::Clipboard)
+        );
+        assert_eq!(
+            tools.resolve(Some(PasteMethod::Xdotool)),
+            Some(PasteMethod::Clipboard)
+        );
+
+        tools.wayland = false;
+        assert_eq!(tools.resolve(None), Some(PasteMethod::Clipboard));
+        tools.clipboard = None;
+        assert_eq!(tools.resolve(None), None);
+        assert_eq!(
+            tools.resolve(Some(PasteMethod::Wtype)),
+            Some(PasteMethod::Wtype)
+        );
+    }
+}
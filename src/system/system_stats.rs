@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+
+/// Cumulative CPU time from the aggregate `cpu` line of `/proc/stat`, in
+/// clock ticks. Usage is the busy share of the difference between two samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    pub fn usage_since(&self, earlier: &CpuTimes) -> Option<f32> {
+        let total = self.total.checked_sub(earlier.total)?;
+        let busy = self.busy.checked_sub(earlier.busy)?;
+        if total == 0 {
+            return None;
+        }
+        Some((busy as f32 * 100.0 / total as f32).clamp(0.0, 100.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub used_kib: u64,
+    pub total_kib: u64,
+}
+
+impl MemoryUsage {
+    pub fn percent(&self) -> f32 {
+        if self.total_kib == 0 {
+            return 0.0;
+        }
+        (self.used_kib as f32 * 100.0 / self.total_kib as f32).clamp(0.0, 100.0)
+    }
+}
+
+pub trait SystemStatsBackend: Send {
+    fn cpu_times(&self) -> Result<CpuTimes>;
+    fn memory(&self) -> Result<MemoryUsage>;
+    /// One-minute load average.
+    fn load_average(&self) -> Result<f32>;
+}
+
+/// Reads `/proc/stat`, `/proc/meminfo` and `/proc/loadavg`.
+#[derive(Debug, Clone)]
+pub struct ProcStatsBackend {
+    root: PathBuf,
+}
+
+impl Default for ProcStatsBackend {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/proc"),
+        }
+    }
+}
+
+impl ProcStatsBackend {
+    fn read(&self, name: &str) -> Result<String> {
+        let path = self.root.join(name);
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))
+    }
+}
+
+impl SystemStatsBackend for ProcStatsBackend {
+    fn cpu_times(&self) -> Result<CpuTimes> {
+        parse_cpu_times(&self.read("stat")?).ok_or_else(|| anyhow!("no cpu line in /proc/stat"))
+    }
+
+    fn memory(&self) -> Result<MemoryUsage> {
+        parse_meminfo(&self.read("meminfo")?)
+            .ok_or_else(|| anyhow!("MemTotal missing from /proc/meminfo"))
+    }
+
+    fn load_average(&self) -> Result<f32> {
+        parse_loadavg(&self.read("loadavg")?).ok_or_else(|| anyhow!("malformed /proc/loadavg"))
+    }
+}
+
+// Fields after `cpu`: user nice system idle iowait irq softirq steal guest
+// guest_nice. Guest time is already counted in user and nice.
+fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some("cpu"))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    if fields.len() < 4 {
+        return None;
+    }
+    let total: u64 = fields.iter().sum();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    Some(CpuTimes {
+        busy: total - idle,
+        total,
+    })
+}
+
+// Used memory is what the kernel cannot reclaim, i.e. MemTotal minus
+// MemAvailable; older kernels without MemAvailable fall back to MemFree.
+fn parse_meminfo(meminfo: &str) -> Option<MemoryUsage> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+    let total_kib = field("MemTotal")?;
+    let available = field("MemAvailable").or_else(|| field("MemFree"))?;
+    Some(MemoryUsage {
+        used_kib: total_kib.saturating_sub(available),
+        total_kib,
+    })
+}
+
+fn parse_loadavg(loadavg: &str) -> Option<f32> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_proc_files() {
+        let stat = "cpu  100 5 50 800 40 3 2 0 0 0\ncpu0 50 2 25 400 20 1 1 0 0 0\n";
+        assert_eq!(
+            parse_cpu_times(stat),
+            Some(CpuTimes {
+                busy: 160,
+                total: 1000
+            })
+        );
+        let later = CpuTimes {
+            busy: 220,
+            total: 1100,
+        };
+        assert_eq!(
+            later.usage_since(&parse_cpu_times(stat).unwrap()),
+            Some(60.0)
+        );
+
+        let meminfo = "MemTotal:       32768000 kB\nMemFree:         1000000 kB\nMemAvailable:   20000000 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some(MemoryUsage {
+                used_kib: 12_768_000,
+                total_kib: 32_768_000
+            })
+        );
+        assert_eq!(parse_loadavg("1.25 0.80 0.40 2/812 12345\n"), Some(1.25));
+        assert_eq!(parse_cpu_times("intr 1 2 3\n"), None);
+    }
+
+    #[derive(Clone)]
+    pub struct MockSystemStatsBackend {
+        pub state: Arc<Mutex<MockSystemStats>>,
+    }
+
+    pub struct MockSystemStats {
+        pub cpu: CpuTimes,
+        pub memory: MemoryUsage,
+        pub load: f32,
+    }
+
+    impl MockSystemStatsBackend {
+        pub fn new(memory: MemoryUsage, load: f32) -> Self {
+            Self {
+                state: Arc::new(Mutex::new(MockSystemStats {
+                    cpu: CpuTimes { busy: 0, total: 0 },
+                    memory,
+                    load,
+                })),
+            }
+        }
+
+        /// Advances the CPU counters by 100 ticks, `busy` of them busy.
+        pub fn run_cpu(&self, busy: u64) {
+            let mut state = self.state.lock().unwrap();
+            state.cpu.busy += busy;
+            state.cpu.total += 100;
+        }
+    }
+
+    impl SystemStatsBackend for MockSystemStatsBackend {
+        fn cpu_times(&self) -> Result<CpuTimes> {
+            Ok(self.state.lock().unwrap().cpu)
+        }
+
+        fn memory(&self) -> Result<MemoryUsage> {
+            Ok(self.state.lock().unwrap().memory)
+        }
+
+        fn load_average(&self) -> Result<f32> {
+            Ok(self.state.lock().unwrap().load)
+        }
+    }
+}
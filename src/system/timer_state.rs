@@ -0,0 +1,111 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub configured: u64,
+    pub remaining: u64,
+    /// Unix timestamp (seconds) at which a running countdown reaches zero;
+    /// `None` while the countdown is paused.
+    pub deadline: Option<u64>,
+}
+
+pub trait TimerStore: Send {
+    fn load(&self) -> Result<Option<TimerSnapshot>>;
+    fn save(&self, snapshot: &TimerSnapshot) -> Result<()>;
+    fn clear(&self) -> Result<()>;
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone)]
+pub struct FileTimerStore {
+    path: PathBuf,
+}
+
+impl FileTimerStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn default_location() -> Option<Self> {
+        let base = env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+        Some(Self::new(base.join("streamdeck_ctrl").join("timer.json")))
+    }
+}
+
+impl TimerStore for FileTimerStore {
+    fn load(&self) -> Result<Option<TimerSnapshot>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", self.path.display()));
+            }
+        };
+        let snapshot = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", self.path.display()))?;
+        Ok(Some(snapshot))
+    }
+
+    fn save(&self, snapshot: &TimerSnapshot) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string(snapshot).context("failed to encode timer state")?;
+        // Write-then-rename so a crash mid-write never leaves a truncated file.
+        let staging = self.path.with_extension("json.tmp");
+        fs::write(&staging, contents)
+            .with_context(|| format!("failed to write {}", staging.display()))?;
+        fs::rename(&staging, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+
+    fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => {
+                Err(err).with_context(|| format!("failed to remove {}", self.path.display()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_round_trips_and_clears() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = FileTimerStore::new(dir.path().join("nested").join("timer.json"));
+        assert_eq!(store.load().unwrap(), None);
+
+        let snapshot = TimerSnapshot {
+            configured: 1500,
+            remaining: 1200,
+            deadline: Some(1_700_000_000),
+        };
+        store.save(&snapshot).unwrap();
+        assert_eq!(store.load().unwrap(), Some(snapshot));
+
+        store.clear().unwrap();
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+    }
+}
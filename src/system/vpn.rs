@@ -0,0 +1,148 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+pub trait VpnBackend: Send {
+    fn is_connected(&self) -> Result<bool>;
+    fn set_connected(&self, connected: bool) -> Result<()>;
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemVpnBackend {
+    /// A NetworkManager connection, brought up and down with `nmcli`.
+    NetworkManager { connection: String },
+    /// Only reports whether an interface such as `wg0` exists; it cannot be
+    /// toggled.
+    Interface { name: String, root: PathBuf },
+}
+
+impl SystemVpnBackend {
+    /// Prefers NetworkManager when a connection is named and `nmcli` is on
+    /// `PATH`, otherwise watches the interface, which defaults to the
+    /// connection name.
+    pub fn detect(connection: Option<&str>, interface: Option<&str>) -> Option<Self> {
+        if let Some(connection) = connection.filter(|_| on_path("nmcli")) {
+            return Some(Self::NetworkManager {
+                connection: connection.to_string(),
+            });
+        }
+        let name = interface.or(connection)?;
+        Some(Self::Interface {
+            name: name.to_string(),
+            root: PathBuf::from(SYS_CLASS_NET),
+        })
+    }
+}
+
+impl VpnBackend for SystemVpnBackend {
+    fn is_connected(&self) -> Result<bool> {
+        match self {
+            Self::NetworkManager { connection } => {
+                let output = run(
+                    "nmcli",
+                    &["-t", "-f", "NAME", "connection", "show", "--active"],
+                )?;
+                Ok(parse_active_connections(&output, connection))
+            }
+            Self::Interface { name, root } => Ok(root.join(name).exists()),
+        }
+    }
+
+    fn set_connected(&self, connected: bool) -> Result<()> {
+        match self {
+            Self::NetworkManager { connection } => {
+                let verb = if connected { "up" } else { "down" };
+                run("nmcli", &["connection", verb, connection])?;
+                Ok(())
+            }
+            Self::Interface { name, .. } => {
+                bail!("interface `{name}` is only monitored; name a NetworkManager connection")
+            }
+        }
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to execute {program} with args {args:?}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "{program} exited with status {}: {}",
+            output.status.code().unwrap_or(-1),
+            stderr.trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn on_path(binary: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(binary).is_file()))
+}
+
+// Terse mode prints one name per line, escaping `:` and `\` with a backslash.
+fn parse_active_connections(output: &str, connection: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.replace("\\:", ":").replace("\\\\", "\\") == connection)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_active_connections_and_interfaces() {
+        let output = "Wired connection 1\nwg0\nOffice\\: VPN\nlo\n";
+        assert!(parse_active_connections(output, "wg0"));
+        assert!(parse_active_connections(output, "Office: VPN"));
+        assert!(!parse_active_connections(output, "wg1"));
+
+        let root = tempfile::tempdir().unwrap();
+        let backend = SystemVpnBackend::Interface {
+            name: "wg0".to_string(),
+            root: root.path().to_path_buf(),
+        };
+        assert!(!backend.is_connected().unwrap());
+        fs::create_dir(root.path().join("wg0")).unwrap();
+        assert!(backend.is_connected().unwrap());
+        assert!(backend.set_connected(false).is_err());
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct MockVpnBackend {
+        pub connected: Arc<Mutex<bool>>,
+        pub fail_toggle: bool,
+        pub available: bool,
+    }
+
+    impl VpnBackend for MockVpnBackend {
+        fn is_connected(&self) -> Result<bool> {
+            Ok(*self.connected.lock().unwrap())
+        }
+
+        fn set_connected(&self, connected: bool) -> Result<()> {
+            if self.fail_toggle {
+                bail!("connection activation failed");
+            }
+            *self.connected.lock().unwrap() = connected;
+            Ok(())
+        }
+
+        fn is_available(&self) -> bool {
+            self.available
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+
+const OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Current conditions at the configured location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherReport {
+    pub temperature_c: f32,
+    /// One short word such as "clear" or "rain".
+    pub condition: &'static str,
+}
+
+pub trait WeatherBackend: Send {
+    /// Blocks on the network; only call it off the event loop.
+    fn current(&self) -> Result<WeatherReport>;
+}
+
+/// Fetches the `current` block of the Open-Meteo forecast API, which needs no
+/// key.
+#[derive(Debug, Clone)]
+pub struct OpenMeteoBackend {
+    latitude: f64,
+    longitude: f64,
+    agent: ureq::Agent,
+}
+
+impl OpenMeteoBackend {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+}
+
+impl WeatherBackend for OpenMeteoBackend {
+    fn current(&self) -> Result<WeatherReport> {
+        let body = self
+            .agent
+            .get(OPEN_METEO_URL)
+            .query("latitude", &self.latitude.to_string())
+            .query("longitude", &self.longitude.to_string())
+            .query("current", "temperature_2m,weather_code")
+            .call()
+            .context("weather request failed")?
+            .into_string()
+            .context("failed to read weather response")?;
+        parse_current(&body)
+    }
+}
+
+#[derive(Deserialize)]
+struct ForecastResponse {
+    current: Option<CurrentWeather>,
+}
+
+#[derive(Deserialize)]
+struct CurrentWeather {
+    temperature_2m: f32,
+    weather_code: u8,
+}
+
+fn parse_current(body: &str) -> Result<WeatherReport> {
+    let response: ForecastResponse =
+        serde_json::from_str(body).context("weather response is not valid JSON")?;
+    let current = response
+        .current
+        .ok_or_else(|| anyhow!("weather response has no `current` block"))?;
+    Ok(WeatherReport {
+        temperature_c: current.temperature_2m,
+        condition: condition_word(current.weather_code),
+    })
+}
+
+// WMO weather interpretation codes as used by Open-Meteo.
+fn condition_word(code: u8) -> &'static str {
+    match code {
+        0 => "clear",
+        1 | 2 => "partly",
+        3 => "cloudy",
+        45 | 48 => "fog",
+        51..=57 => "drizzle",
+        61..=67 | 80..=82 => "rain",
+        71..=77 | 85 | 86 => "snow",
+        95..=99 => "storm",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn parses_open_meteo_current_block() {
+        let body = r#"{
+            "latitude": 52.52,
+            "current_units": {"temperature_2m": "°C"},
+            "current": {"time": "2024-05-01T12:00", "temperature_2m": 17.4, "weather_code": 61}
+        }"#;
+        assert_eq!(
+            parse_current(body).unwrap(),
+            WeatherReport {
+                temperature_c: 17.4,
+                condition: "rain",
+            }
+        );
+        assert!(parse_current(r#"{"latitude": 52.52}"#).is_err());
+        assert!(parse_current("<html>rate limited</html>").is_err());
+        assert_eq!(condition_word(2), "partly");
+        assert_eq!(condition_word(200), "unknown");
+    }
+
+    /// Answers with `responses` in order, then keeps failing; counts calls.
+    #[derive(Clone, Default)]
+    pub struct MockWeatherBackend {
+        pub responses: Arc<Mutex<VecDeque<Result<WeatherReport, String>>>>,
+        pub calls: Arc<Mutex<usize>>,
+    }
+
+    impl MockWeatherBackend {
+        pub fn with(responses: impl IntoIterator<Item = Result<WeatherReport, String>>) -> Self {
+            Self {
+                responses: Arc::new(Mutex::new(responses.into_iter().collect())),
+                calls: Arc::default(),
+            }
+        }
+
+        pub fn calls(&self) -> usize {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    impl WeatherBackend for MockWeatherBackend {
+        fn current(&self) -> Result<WeatherReport> {
+            *self.calls.lock().unwrap() += 1;
+            match self.responses.lock().unwrap().pop_front() {
+                Some(Ok(report)) => Ok(report),
+                Some(Err(err)) => Err(anyhow!(err)),
+                None => Err(anyhow!("network unreachable")),
+            }
+        }
+    }
+}
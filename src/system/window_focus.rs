@@ -0,0 +1,130 @@
+use std::env;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+pub trait WindowActivator: Send {
+    /// Focuses a window whose app id or WM class matches `class`, ignoring
+    /// case. Returns `Ok(false)` when no window matched.
+    fn activate(&self, class: &str) -> Result<bool>;
+}
+
+/// Uses `swaymsg` under sway and `wmctrl` on X11.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DesktopWindowActivator;
+
+impl WindowActivator for DesktopWindowActivator {
+    fn activate(&self, class: &str) -> Result<bool> {
+        if env::var_os("SWAYSOCK").is_some() {
+            activate_sway(class)
+        } else if env::var_os("DISPLAY").is_some() {
+            activate_wmctrl(class)
+        } else {
+            bail!("no supported window manager found (needs sway or X11)");
+        }
+    }
+}
+
+fn activate_sway(class: &str) -> Result<bool> {
+    let output = Command::new("swaymsg")
+        .args(["-t", "get_tree"])
+        .output()
+        .context("failed to execute swaymsg")?;
+    if !output.status.success() {
+        bail!(
+            "swaymsg -t get_tree exited with status {}",
+            output.status.code().unwrap_or(-1)
+        );
+    }
+    let tree: Value =
+        serde_json::from_slice(&output.stdout).context("swaymsg returned invalid JSON")?;
+    let Some(id) = find_sway_window(&tree, class) else {
+        return Ok(false);
+    };
+
+    let status = Command::new("swaymsg")
+        .arg(format!("[con_id={id}] focus"))
+        .output()
+        .context("failed to execute swaymsg")?
+        .status;
+    if !status.success() {
+        bail!(
+            "swaymsg focus exited with status {}",
+            status.code().unwrap_or(-1)
+        );
+    }
+    Ok(true)
+}
+
+// wmctrl exits with 1 both when nothing matched and on errors it reports on
+// stderr, so only a silent failure counts as "no window".
+fn activate_wmctrl(class: &str) -> Result<bool> {
+    let output = Command::new("wmctrl")
+        .args(["-x", "-a", class])
+        .output()
+        .context("failed to execute wmctrl")?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    if output.stderr.is_empty() {
+        return Ok(false);
+    }
+    bail!(
+        "wmctrl failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+}
+
+// Windows are `con` or `floating_con` nodes; Wayland clients carry an
+// `app_id`, XWayland ones a `window_properties.class`.
+fn find_sway_window(node: &Value, class: &str) -> Option<i64> {
+    let matches = |value: Option<&Value>| {
+        value
+            .and_then(Value::as_str)
+            .is_some_and(|name| name.eq_ignore_ascii_case(class))
+    };
+    let is_window = matches!(
+        node.get("type").and_then(Value::as_str),
+        Some("con" | "floating_con")
+    );
+    if is_window
+        && (matches(node.get("app_id")) || matches(node.pointer("/window_properties/class")))
+    {
+        return node.get("id").and_then(Value::as_i64);
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(*key).and_then(Value::as_array))
+        .flatten()
+        .find_map(|child| find_sway_window(child, class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_windows_in_sway_tree() {
+        let tree: Value = serde_json::from_str(
+            r#"{
+                "id": 1, "type": "root", "nodes": [{
+                    "id": 2, "type": "output", "nodes": [{
+                        "id": 3, "type": "workspace",
+                        "nodes": [{"id": 10, "type": "con", "app_id": "org.gnome.Nautilus"}],
+                        "floating_nodes": [{
+                            "id": 11, "type": "floating_con", "app_id": null,
+                            "window_properties": {"class": "Slack"}
+                        }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(find_sway_window(&tree, "org.gnome.nautilus"), Some(10));
+        assert_eq!(find_sway_window(&tree, "slack"), Some(11));
+        assert_eq!(find_sway_window(&tree, "firefox"), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use crossbeam_channel::{Receiver, Sender, TryRecvError, unbounded};
+
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+const EXIT_POLL: Duration = Duration::from_millis(5);
+
+static COMMAND_TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_COMMAND_TIMEOUT.as_millis() as u64);
+
+/// Sets the deadline used by `output_with_timeout`, from the `app` section.
+pub fn set_command_timeout(timeout: Duration) {
+    COMMAND_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn command_timeout() -> Duration {
+    Duration::from_millis(COMMAND_TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Like `Command::output`, but a child still running after `command_timeout`
+/// is killed and reported as an error, so a hung pactl or playerctl cannot
+/// hold up its caller.
+pub fn output_with_timeout(command: &mut Command) -> Result<Output> {
+    output_within(command, command_timeout())
+}
+
+fn output_within(command: &mut Command, timeout: Duration) -> Result<Output> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to execute {program}"))?;
+    // Read on their own threads so a child with a lot to say cannot stall on
+    // a full pipe before it exits.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let status = wait_within(&mut child, &program, timeout)?;
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Waits for an already spawned child, killing it after `command_timeout`.
+pub fn wait_with_timeout(child: &mut Child, program: &str) -> Result<ExitStatus> {
+    wait_within(child, program, command_timeout())
+}
+
+fn wait_within(child: &mut Child, program: &str, timeout: Duration) -> Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("failed to wait for {program}"))?
+        {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("{program} timed out after {} ms", timeout.as_millis());
+        }
+        thread::sleep(EXIT_POLL);
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+type Job<B, R> = Box<dyn FnOnce(&mut B) -> R + Send>;
+
+/// Runs jobs against a blocking backend and hands their results back to be
+/// collected on the next tick. A threaded worker keeps the calls off the
+/// event loop; an inline one runs each job as it is submitted, which is what
+/// controllers start with so tests with mock backends stay synchronous.
+pub struct BackendWorker<B, R> {
+    mode: Mode<B, R>,
+    results: Receiver<(u64, R)>,
+    generation: Arc<AtomicU64>,
+    pending: usize,
+}
+
+enum Mode<B, R> {
+    Inline(B, Sender<(u64, R)>),
+    Threaded(Sender<(u64, Job<B, R>)>),
+}
+
+impl<B, R> BackendWorker<B, R> {
+    pub fn inline(backend: B) -> Self {
+        let (result_tx, results) = unbounded();
+        Self {
+            mode: Mode::Inline(backend, result_tx),
+            results,
+            generation: Arc::default(),
+            pending: 0,
+        }
+    }
+
+    pub fn submit(&mut self, job: impl FnOnce(&mut B) -> R + Send + 'static) -> Result<()> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        match &mut self.mode {
+            Mode::Inline(backend, result_tx) => {
+                let _ = result_tx.send((generation, job(backend)));
+            }
+            Mode::Threaded(jobs) => jobs
+                .send((generation, Box::new(job)))
+                .map_err(|_| anyhow!("backend worker stopped"))?,
+        }
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Whether a submitted job has not been collected yet.
+    pub fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Drops jobs that have not started and the results of any that have, so
+    /// a reading taken before a change cannot overwrite it.
+    pub fn cancel_pending(&mut self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.pending = 0;
+    }
+
+    pub fn finished(&mut self) -> Vec<R> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let mut finished = Vec::new();
+        loop {
+            match self.results.try_recv() {
+                Ok((issued, result)) if issued == generation => {
+                    self.pending = self.pending.saturating_sub(1);
+                    finished.push(result);
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+                // The thread is gone; nothing further will arrive.
+                Err(TryRecvError::Disconnected) => {
+                    self.pending = 0;
+                    break;
+                }
+            }
+        }
+        finished
+    }
+}
+
+impl<B, R> BackendWorker<B, R>
+where
+    B: Send + 'static,
+    R: Send + 'static,
+{
+    pub fn threaded(name: &str, backend: B) -> Result<Self> {
+        let (job_tx, jobs) = unbounded::<(u64, Job<B, R>)>();
+        let (result_tx, results) = unbounded();
+        let generation = Arc::new(AtomicU64::new(0));
+        let current = Arc::clone(&generation);
+        thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut backend = backend;
+                while let Ok((issued, job)) = jobs.recv() {
+                    if issued != current.load(Ordering::SeqCst) {
+                        continue;
+                    }
+                    if result_tx.send((issued, job(&mut backend))).is_err() {
+                        return;
+                    }
+                }
+            })
+            .with_context(|| format!("failed to spawn {name}"))?;
+        Ok(Self {
+            mode: Mode::Threaded(job_tx),
+            results,
+            generation,
+            pending: 0,
+        })
+    }
+
+    /// Moves an inline worker's backend onto its own thread; results not
+    /// collected yet are dropped.
+    pub fn into_threaded(self, name: &str) -> Result<Self> {
+        let Mode::Inline(backend, _) = self.mode else {
+            return Ok(self);
+        };
+        Self::threaded(name, backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hung_commands_are_killed_at_the_deadline() {
+        let started = Instant::now();
+        let err =
+            output_within(Command::new("sleep").arg("30"), Duration::from_millis(100)).unwrap_err();
+        assert!(
+            err.to_string().contains("sleep timed out after 100 ms"),
+            "{err}"
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let output = output_within(
+            Command::new("sh").args(["-c", "head -c 200000 /dev/zero; echo done >&2"]),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), 200_000);
+        assert_eq!(output.stderr, b"done\n");
+    }
+
+    #[test]
+    fn threaded_workers_return_results_and_drop_cancelled_jobs() {
+        let mut inline = BackendWorker::inline(1);
+        inline.submit(|value: &mut i32| *value * 10).unwrap();
+        assert_eq!(inline.finished(), [10]);
+        assert!(!inline.is_busy());
+
+        let (gate_tx, gate) = unbounded::<()>();
+        let mut worker = BackendWorker::threaded("test-worker", gate).unwrap();
+        worker
+            .submit(|gate: &mut Receiver<()>| gate.recv().is_ok())
+            .unwrap();
+        worker.submit(|_: &mut Receiver<()>| false).unwrap();
+        assert!(worker.is_busy());
+        worker.cancel_pending();
+        worker.submit(|_: &mut Receiver<()>| true).unwrap();
+        gate_tx.send(()).unwrap();
+
+        let mut collected = Vec::new();
+        for _ in 0..200 {
+            collected.extend(worker.finished());
+            if !worker.is_busy() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(collected, [true]);
+    }
+}
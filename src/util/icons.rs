@@ -1,17 +1,41 @@
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufReader, Cursor};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use anyhow::{Context, Result, anyhow};
-use image::{ImageReader, RgbaImage};
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, Frame, ImageReader, RgbaImage};
 use once_cell::sync::Lazy;
 use resvg::render as render_svg_tree;
 use tiny_skia::{Pixmap, Transform};
 use usvg::{Options as UsvgOptions, Tree as UsvgTree};
 
+// Browsers treat near-zero GIF delays as 100 ms; without this some files
+// would spin as fast as the device accepts frames.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
 static ICON_CACHE: Lazy<Mutex<HashMap<PathBuf, Arc<RgbaImage>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
+// Keyed by a hash of the decoded bytes, so repeated inline icons share pixels.
+static INLINE_ICON_CACHE: Lazy<Mutex<HashMap<u64, Arc<RgbaImage>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static ANIMATION_CACHE: Lazy<Mutex<HashMap<PathBuf, Option<Arc<AnimatedIcon>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Decoded frames of a GIF or APNG, each shown for the matching delay.
+#[derive(Debug)]
+pub struct AnimatedIcon {
+    pub frames: Vec<Arc<RgbaImage>>,
+    pub delays: Vec<Duration>,
+}
 
 pub fn load_icon(path: &Path) -> Result<Arc<RgbaImage>> {
     let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
@@ -35,6 +59,139 @@ pub fn load_icon(path: &Path) -> Result<Arc<RgbaImage>> {
     Ok(image)
 }
 
+/// Decodes an icon embedded in the config, either bare base64 or a
+/// `data:<mime>;base64,<payload>` URI. Returns the content hash along with the
+/// image so callers can derive a stable id.
+pub fn load_inline_icon(data: &str) -> Result<(u64, Arc<RgbaImage>)> {
+    let bytes = decode_inline_data(data)?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some(image) = INLINE_ICON_CACHE
+        .lock()
+        .expect("inline icon cache mutex poisoned")
+        .get(&key)
+        .map(Arc::clone)
+    {
+        return Ok((key, image));
+    }
+
+    let decoded = if looks_like_svg(&bytes) {
+        render_svg_data(&bytes, None, "inline icon")?
+    } else {
+        decode_raster_icon(&bytes, "inline icon")?
+    };
+    let image = Arc::new(decoded);
+    INLINE_ICON_CACHE
+        .lock()
+        .expect("inline icon cache mutex poisoned")
+        .insert(key, Arc::clone(&image));
+    Ok((key, image))
+}
+
+pub fn is_data_uri(value: &str) -> bool {
+    value.trim_start().starts_with("data:")
+}
+
+fn decode_inline_data(data: &str) -> Result<Vec<u8>> {
+    let data = data.trim();
+    let payload = match data.strip_prefix("data:") {
+        Some(uri) => {
+            let (header, payload) = uri
+                .split_once(',')
+                .ok_or_else(|| anyhow!("data URI has no `,` before its payload"))?;
+            if !header.ends_with(";base64") {
+                bail!("only base64 data URIs are supported");
+            }
+            payload
+        }
+        None => data,
+    };
+    let compact: String = payload.split_whitespace().collect();
+    BASE64
+        .decode(compact)
+        .context("inline icon is not valid base64")
+}
+
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]);
+    let text = text.trim_start();
+    text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg"))
+}
+
+/// Returns the frame set of an animated GIF or APNG, or `None` for any other
+/// icon, including single-frame files. Buttons sharing a path share frames.
+pub fn load_animated_icon(path: &Path) -> Result<Option<Arc<AnimatedIcon>>> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    if let Some(animation) = ANIMATION_CACHE
+        .lock()
+        .expect("animation cache mutex poisoned")
+        .get(&canonical)
+    {
+        return Ok(animation.clone());
+    }
+
+    let animation = decode_animation(&canonical)?.map(Arc::new);
+    ANIMATION_CACHE
+        .lock()
+        .expect("animation cache mutex poisoned")
+        .insert(canonical, animation.clone());
+
+    Ok(animation)
+}
+
+fn decode_animation(path: &Path) -> Result<Option<AnimatedIcon>> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    let open = || {
+        File::open(path)
+            .map(BufReader::new)
+            .with_context(|| format!("failed to open icon at {}", path.display()))
+    };
+    let decode_error = || format!("failed to decode animation frames of {}", path.display());
+
+    let frames = match ext.as_str() {
+        "gif" => GifDecoder::new(open()?)
+            .and_then(|decoder| decoder.into_frames().collect_frames())
+            .with_context(decode_error)?,
+        "png" | "apng" => {
+            let decoder = PngDecoder::new(open()?).with_context(decode_error)?;
+            if !decoder.is_apng().with_context(decode_error)? {
+                return Ok(None);
+            }
+            decoder
+                .apng()
+                .and_then(|decoder| decoder.into_frames().collect_frames())
+                .with_context(decode_error)?
+        }
+        _ => return Ok(None),
+    };
+    if frames.len() < 2 {
+        return Ok(None);
+    }
+
+    let delays = frames.iter().map(frame_delay).collect();
+    let frames = frames
+        .into_iter()
+        .map(|frame| Arc::new(frame.into_buffer()))
+        .collect();
+    Ok(Some(AnimatedIcon { frames, delays }))
+}
+
+fn frame_delay(frame: &Frame) -> Duration {
+    let delay = Duration::from(frame.delay());
+    if delay < MIN_FRAME_DELAY {
+        DEFAULT_FRAME_DELAY
+    } else {
+        delay
+    }
+}
+
 fn decode_icon(path: &Path) -> Result<RgbaImage> {
     let ext = path
         .extension()
@@ -50,31 +207,42 @@ fn decode_icon(path: &Path) -> Result<RgbaImage> {
 }
 
 fn load_raster_icon(path: &Path) -> Result<RgbaImage> {
-    let reader = ImageReader::open(path)
-        .with_context(|| format!("failed to open icon at {}", path.display()))?;
-    let image = reader
+    let data =
+        fs::read(path).with_context(|| format!("failed to open icon at {}", path.display()))?;
+    decode_raster_icon(&data, &format!("icon at {}", path.display()))
+}
+
+fn decode_raster_icon(data: &[u8], source: &str) -> Result<RgbaImage> {
+    let image = ImageReader::new(Cursor::new(data))
         .with_guessed_format()
         .context("failed to guess icon image format")?
         .decode()
-        .with_context(|| format!("failed to decode icon at {}", path.display()))?;
+        .with_context(|| format!("failed to decode {source}"))?;
     Ok(image.to_rgba8())
 }
 
 fn render_svg_icon(path: &Path) -> Result<RgbaImage> {
     let data =
         fs::read(path).with_context(|| format!("failed to read svg icon at {}", path.display()))?;
+    render_svg_data(
+        &data,
+        path.parent().map(|dir| dir.to_path_buf()),
+        &format!("svg icon at {}", path.display()),
+    )
+}
 
+fn render_svg_data(data: &[u8], resources_dir: Option<PathBuf>, source: &str) -> Result<RgbaImage> {
     let mut options = UsvgOptions::default();
-    options.resources_dir = path.parent().map(|dir| dir.to_path_buf());
-    let tree = UsvgTree::from_data(&data, &options)
-        .with_context(|| format!("failed to parse svg icon at {}", path.display()))?;
+    options.resources_dir = resources_dir;
+    let tree =
+        UsvgTree::from_data(data, &options).with_context(|| format!("failed to parse {source}"))?;
 
     let size = tree.size().to_int_size();
     let width = size.width().max(1);
     let height = size.height().max(1);
 
     let mut pixmap = Pixmap::new(width, height)
-        .ok_or_else(|| anyhow!("failed to allocate pixmap for icon {}", path.display()))?;
+        .ok_or_else(|| anyhow!("failed to allocate pixmap for {source}"))?;
 
     {
         let mut pixmap_mut = pixmap.as_mut();
@@ -97,7 +265,7 @@ fn render_svg_icon(path: &Path) -> Result<RgbaImage> {
     }
 
     RgbaImage::from_vec(width, height, buffer)
-        .ok_or_else(|| anyhow!("failed to build rgba image for icon {}", path.display()))
+        .ok_or_else(|| anyhow!("failed to build rgba image for {source}"))
 }
 
 fn unpremultiply_component(component: u8, alpha: u8) -> u8 {
@@ -108,3 +276,63 @@ fn unpremultiply_component(component: u8, alpha: u8) -> u8 {
         value.min(255) as u8
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Rgba};
+
+    #[test]
+    fn gif_frames_are_decoded_once_and_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spinner.gif");
+        let frames = [[255, 0, 0, 255], [0, 0, 255, 255]].map(|color| {
+            Frame::from_parts(
+                RgbaImage::from_pixel(4, 4, Rgba(color)),
+                0,
+                0,
+                Delay::from_numer_denom_ms(70, 1),
+            )
+        });
+        GifEncoder::new(File::create(&path).unwrap())
+            .encode_frames(frames)
+            .unwrap();
+
+        let animation = load_animated_icon(&path).unwrap().expect("animated");
+        assert_eq!(animation.frames.len(), 2);
+        assert_eq!(animation.delays, vec![Duration::from_millis(70); 2]);
+        assert_eq!(animation.frames[1].get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+        let again = load_animated_icon(&path).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&animation, &again));
+
+        let still = dir.path().join("still.png");
+        RgbaImage::new(4, 4).save(&still).unwrap();
+        assert!(load_animated_icon(&still).unwrap().is_none());
+    }
+
+    #[test]
+    fn inline_icons_decode_once_per_content() {
+        let mut png = Vec::new();
+        RgbaImage::from_pixel(3, 2, Rgba([10, 20, 30, 255]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let encoded = BASE64.encode(&png);
+
+        let (key, image) = load_inline_icon(&encoded).unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([10, 20, 30, 255]));
+        let (uri_key, from_uri) =
+            load_inline_icon(&format!("data:image/png;base64,{encoded}")).unwrap();
+        assert_eq!(key, uri_key);
+        assert!(Arc::ptr_eq(&image, &from_uri));
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="6" height="4"/>"#;
+        let (_, rendered) = load_inline_icon(&BASE64.encode(svg)).unwrap();
+        assert_eq!(rendered.dimensions(), (6, 4));
+
+        assert!(load_inline_icon("not base64!").is_err());
+        assert!(load_inline_icon(&BASE64.encode("plain text")).is_err());
+        assert!(load_inline_icon("data:image/png,raw").is_err());
+    }
+}
@@ -5,3 +5,10 @@ pub fn format_duration(total_secs: u64) -> String {
     let seconds = total_secs % 60;
     format!("{minutes:02}:{seconds:02}")
 }
+
+/// Formats a time of day given in seconds since midnight as `HH:MM`, wrapping
+/// past midnight.
+pub fn format_clock_time(secs_since_midnight: u64) -> String {
+    let minutes = secs_since_midnight / 60 % (24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}